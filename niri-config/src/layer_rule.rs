@@ -1,4 +1,4 @@
-use crate::{BlockOutFrom, CornerRadius, RegexEq, ShadowRule};
+use crate::{BlockOutFrom, BorderRule, CornerRadius, RegexEq, ShadowRule};
 
 #[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
 pub struct LayerRule {
@@ -13,11 +13,15 @@ pub struct LayerRule {
     pub block_out_from: Option<BlockOutFrom>,
     #[knuffel(child, default)]
     pub shadow: ShadowRule,
+    #[knuffel(child, default)]
+    pub focus_ring: BorderRule,
     #[knuffel(child)]
     pub geometry_corner_radius: Option<CornerRadius>,
     #[knuffel(child, unwrap(argument))]
     pub place_within_backdrop: Option<bool>,
     #[knuffel(child, unwrap(argument))]
+    pub backdrop_workspace: Option<String>,
+    #[knuffel(child, unwrap(argument))]
     pub baba_is_float: Option<bool>,
 }
 
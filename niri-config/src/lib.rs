@@ -12,9 +12,10 @@ use bitflags::bitflags;
 use knuffel::errors::DecodeError;
 use knuffel::Decode as _;
 use layer_rule::LayerRule;
-use miette::{miette, Context, IntoDiagnostic};
+use miette::{miette, Context, Diagnostic, IntoDiagnostic};
 use niri_ipc::{
-    ColumnDisplay, ConfiguredMode, LayoutSwitchTarget, PositionChange, SizeChange, Transform,
+    ColumnDisplay, ColumnPinEdge, ConfiguredMode, LayoutSwitchTarget, PositionChange,
+    ScreenTransitionStyle, SizeChange, Transform, WallpaperMode, WindowWarpTarget,
     WorkspaceReferenceArg,
 };
 use smithay::backend::renderer::Color32F;
@@ -25,8 +26,10 @@ use smithay::reexports::input;
 
 pub const DEFAULT_BACKGROUND_COLOR: Color = Color::from_array_unpremul([0.25, 0.25, 0.25, 1.]);
 pub const DEFAULT_BACKDROP_COLOR: Color = Color::from_array_unpremul([0.15, 0.15, 0.15, 1.]);
+pub const DEFAULT_SEAT_FOCUS_RING_COLOR: Color = Color::from_array_unpremul([0.9, 0.4, 0.6, 1.]);
 
 pub mod layer_rule;
+pub mod overrides;
 
 mod utils;
 pub use utils::RegexEq;
@@ -50,6 +53,12 @@ pub struct Config {
     #[knuffel(child, default)]
     pub hotkey_overlay: HotkeyOverlay,
     #[knuffel(child, default)]
+    pub tutorial: Tutorial,
+    #[knuffel(child, default)]
+    pub adjust_mode: AdjustMode,
+    #[knuffel(child, default)]
+    pub launch_placeholder: LaunchPlaceholder,
+    #[knuffel(child, default)]
     pub animations: Animations,
     #[knuffel(child, default)]
     pub gestures: Gestures,
@@ -66,9 +75,19 @@ pub struct Config {
     #[knuffel(child, default)]
     pub switch_events: SwitchBinds,
     #[knuffel(child, default)]
+    pub hardware_keys: HardwareKeys,
+    #[knuffel(child, default)]
+    pub logind: Logind,
+    #[knuffel(child, default)]
+    pub idle_fade: IdleFade,
+    #[knuffel(child, default)]
+    pub xwayland_satellite: XwaylandSatellite,
+    #[knuffel(child, default)]
     pub debug: DebugConfig,
     #[knuffel(children(name = "workspace"))]
     pub workspaces: Vec<Workspace>,
+    #[knuffel(children(name = "seat"))]
+    pub seats: Vec<Seat>,
 }
 
 #[derive(knuffel::Decode, Debug, Default, PartialEq)]
@@ -99,6 +118,28 @@ pub struct Input {
     pub mod_key: Option<ModKey>,
     #[knuffel(child, unwrap(argument, str))]
     pub mod_key_nested: Option<ModKey>,
+    #[knuffel(child, unwrap(argument), default)]
+    pub virtual_input: VirtualInputPolicy,
+    #[knuffel(child, default)]
+    pub drag_threshold: DragThreshold,
+    #[knuffel(child)]
+    pub dwell_click: Option<DwellClick>,
+    #[knuffel(children(name = "tablet-pad"))]
+    pub tablet_pads: Vec<TabletPad>,
+}
+
+/// Which clients may create virtual pointers and keyboards.
+///
+/// Sandboxed clients are never allowed regardless of this setting.
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VirtualInputPolicy {
+    /// All clients may inject input.
+    Allow,
+    /// The user is asked once for every executable.
+    #[default]
+    Ask,
+    /// Virtual input is disabled.
+    Deny,
 }
 
 #[derive(knuffel::Decode, Debug, PartialEq, Eq)]
@@ -112,6 +153,8 @@ pub struct Keyboard {
     pub repeat_rate: u8,
     #[knuffel(child, unwrap(argument), default)]
     pub track_layout: TrackLayout,
+    #[knuffel(child, unwrap(argument), default)]
+    pub track_state: TrackState,
     #[knuffel(child)]
     pub numlock: bool,
 }
@@ -123,6 +166,7 @@ impl Default for Keyboard {
             repeat_delay: 600,
             repeat_rate: 25,
             track_layout: Default::default(),
+            track_state: Default::default(),
             numlock: Default::default(),
         }
     }
@@ -168,6 +212,23 @@ pub enum CenterFocusedColumn {
     OnOverflow,
 }
 
+/// Which window to focus after the focused window closes.
+#[derive(knuffel::DecodeScalar, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum FocusAfterClose {
+    /// Focus the window below in the column, or the next column. If the closed window was opened
+    /// from the column to the left, focus that column instead.
+    #[default]
+    Auto,
+    /// Focus the column to the left when the closed window was alone in its column.
+    Left,
+    /// Focus the column to the right when the closed window was alone in its column.
+    Right,
+    /// Focus the most recently focused window on the same workspace.
+    Previous,
+    /// Focus the parent window, for example the window that opened a dialog.
+    Parent,
+}
+
 #[derive(knuffel::DecodeScalar, Debug, Default, PartialEq, Eq)]
 pub enum TrackLayout {
     /// The layout change is global.
@@ -177,6 +238,15 @@ pub enum TrackLayout {
     Window,
 }
 
+#[derive(knuffel::DecodeScalar, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum TrackState {
+    /// Lock keys and the active layout are shared between all keyboards.
+    #[default]
+    Global,
+    /// Every keyboard keeps its own lock keys and active layout.
+    PerDevice,
+}
+
 #[derive(knuffel::Decode, Debug, Default, PartialEq)]
 pub struct Touchpad {
     #[knuffel(child)]
@@ -235,6 +305,9 @@ pub struct Mouse {
     pub middle_emulation: bool,
     #[knuffel(child, unwrap(argument))]
     pub scroll_factor: Option<FloatOrInt<0, 100>>,
+    /// Send the relative motion of a locked pointer once per frame rather than for every event.
+    #[knuffel(child)]
+    pub batch_relative_motion: bool,
 }
 
 #[derive(knuffel::Decode, Debug, Default, PartialEq)]
@@ -349,6 +422,9 @@ pub struct Tablet {
     pub calibration_matrix: Option<Vec<f32>>,
     #[knuffel(child, unwrap(argument))]
     pub map_to_output: Option<String>,
+    /// Map the tablet area to the focused window instead of a whole output.
+    #[knuffel(child)]
+    pub map_to_focused_window: bool,
     #[knuffel(child)]
     pub left_handed: bool,
 }
@@ -367,6 +443,100 @@ pub struct FocusFollowsMouse {
     pub max_scroll_amount: Option<Percent>,
 }
 
+/// How far and how long the pointer must move with a button held before it counts as a drag.
+///
+/// Applies to interactive moves and view gestures that niri starts itself.
+#[derive(knuffel::Decode, Debug, PartialEq, Clone, Copy)]
+pub struct DragThreshold {
+    #[knuffel(child, unwrap(argument), default = Self::default().distance)]
+    pub distance: FloatOrInt<0, 1000>,
+    #[knuffel(child, unwrap(argument), default = Self::default().delay_ms)]
+    pub delay_ms: u16,
+}
+
+/// Clicking by holding the pointer still, for people who can't easily press buttons.
+#[derive(knuffel::Decode, Debug, PartialEq, Clone, Copy)]
+pub struct DwellClick {
+    /// How long the pointer must stay still before the click.
+    #[knuffel(child, unwrap(argument), default = Self::default().delay_ms)]
+    pub delay_ms: u16,
+    /// How far the pointer may move without restarting the countdown.
+    #[knuffel(child, unwrap(argument), default = Self::default().distance)]
+    pub distance: FloatOrInt<0, 1000>,
+}
+
+/// Mappings for the buttons, rings and strips of a drawing tablet pad.
+///
+/// Profiles with an `app-id` apply while a matching window is focused, and take precedence over
+/// the ones without.
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
+pub struct TabletPad {
+    #[knuffel(property, str)]
+    pub app_id: Option<RegexEq>,
+    #[knuffel(children(name = "button"))]
+    pub buttons: Vec<PadButtonBind>,
+    #[knuffel(children(name = "ring"))]
+    pub rings: Vec<PadAxisBind>,
+    #[knuffel(children(name = "strip"))]
+    pub strips: Vec<PadAxisBind>,
+}
+
+/// What a pad button does: either emulates a key press or runs actions.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct PadButtonBind {
+    /// Button number as reported by libinput, starting from 0.
+    #[knuffel(argument)]
+    pub button: u32,
+    #[knuffel(property, str)]
+    pub key: Option<Key>,
+    #[knuffel(children)]
+    pub actions: Vec<Action>,
+}
+
+/// What turning a pad ring or sliding along a pad strip does, for one direction.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct PadAxisBind {
+    /// Ring or strip number as reported by libinput, starting from 0.
+    #[knuffel(argument)]
+    pub number: u32,
+    #[knuffel(argument)]
+    pub direction: PadDirection,
+    #[knuffel(property, str)]
+    pub key: Option<Key>,
+    #[knuffel(children)]
+    pub actions: Vec<Action>,
+}
+
+/// Direction of a pad ring or strip step.
+///
+/// Rings turn clockwise or counter-clockwise, and strips go up or down.
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PadDirection {
+    Clockwise,
+    CounterClockwise,
+    Up,
+    Down,
+}
+
+impl Default for DwellClick {
+    fn default() -> Self {
+        Self {
+            delay_ms: 1000,
+            distance: FloatOrInt(4.),
+        }
+    }
+}
+
+impl Default for DragThreshold {
+    fn default() -> Self {
+        Self {
+            // Same as GTK 4.
+            distance: FloatOrInt(8.),
+            delay_ms: 0,
+        }
+    }
+}
+
 #[derive(knuffel::Decode, Debug, PartialEq, Eq, Clone, Copy)]
 pub struct WarpMouseToFocus {
     #[knuffel(property, str)]
@@ -439,11 +609,20 @@ pub struct Output {
     #[knuffel(child)]
     pub variable_refresh_rate: Option<Vrr>,
     #[knuffel(child)]
+    pub dynamic_resolution: Option<DynamicResolution>,
+    #[knuffel(child)]
     pub focus_at_startup: bool,
     #[knuffel(child)]
     pub background_color: Option<Color>,
     #[knuffel(child)]
     pub backdrop_color: Option<Color>,
+    #[knuffel(child)]
+    pub wallpaper: Option<Wallpaper>,
+    #[knuffel(child, unwrap(argument))]
+    pub color_depth: Option<ColorDepth>,
+    /// Hot corners and edges of this output, replacing the ones in `gestures`.
+    #[knuffel(child)]
+    pub hot_corners: Option<HotCorners>,
 }
 
 impl Output {
@@ -458,6 +637,20 @@ impl Output {
     pub fn is_vrr_always_off(&self) -> bool {
         self.variable_refresh_rate.is_none()
     }
+
+    pub fn is_10_bit(&self) -> bool {
+        self.color_depth == Some(ColorDepth::Ten)
+    }
+}
+
+/// Bits per color channel for scanout.
+///
+/// Only the scanout format changes. Rendering stays in 8-bit sRGB, so there is no HDR.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    #[default]
+    Eight,
+    Ten,
 }
 
 impl Default for Output {
@@ -471,12 +664,26 @@ impl Default for Output {
             position: None,
             mode: None,
             variable_refresh_rate: None,
+            dynamic_resolution: None,
             background_color: None,
             backdrop_color: None,
+            wallpaper: None,
+            color_depth: None,
+            hot_corners: None,
         }
     }
 }
 
+/// Image drawn above the background color, below the background layer-shell surfaces.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct Wallpaper {
+    /// Path to the image, in any format that gdk-pixbuf can load.
+    #[knuffel(argument)]
+    pub path: String,
+    #[knuffel(property, str, default)]
+    pub mode: WallpaperMode,
+}
+
 #[derive(Debug, Clone)]
 pub struct OutputName {
     pub connector: String,
@@ -499,10 +706,44 @@ pub struct Vrr {
     pub on_demand: bool,
 }
 
+/// Rendering below the native resolution while frames miss their deadline.
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct DynamicResolution {
+    /// Smallest fraction of the native resolution to render at.
+    #[knuffel(property, default = Self::default().min_scale)]
+    pub min_scale: FloatOrInt<0, 1>,
+    /// Strength of the sharpening applied when upscaling, from 0 to 1.
+    #[knuffel(property, default = Self::default().sharpness)]
+    pub sharpness: FloatOrInt<0, 1>,
+}
+
+impl Default for DynamicResolution {
+    fn default() -> Self {
+        Self {
+            min_scale: FloatOrInt(0.5),
+            sharpness: FloatOrInt(0.5),
+        }
+    }
+}
+
 // MIN and MAX generics are only used during parsing to check the value.
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct FloatOrInt<const MIN: i32, const MAX: i32>(pub f64);
 
+/// A length in logical pixels, or in units that depend on the output.
+///
+/// Plain numbers are logical pixels. Strings can have a `px`, `mm` or `%` suffix.
+// MIN and MAX generics are only used during parsing to check the value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length<const MIN: i32, const MAX: i32> {
+    /// Logical pixels.
+    Logical(f64),
+    /// Millimeters on the physical output.
+    Millimeters(f64),
+    /// Percent of a reference size that depends on the setting.
+    Percent(f64),
+}
+
 #[derive(knuffel::Decode, Debug, Clone, PartialEq)]
 pub struct Layout {
     #[knuffel(child, default)]
@@ -530,11 +771,13 @@ pub struct Layout {
     #[knuffel(child, unwrap(argument, str), default = Self::default().default_column_display)]
     pub default_column_display: ColumnDisplay,
     #[knuffel(child, unwrap(argument), default = Self::default().gaps)]
-    pub gaps: FloatOrInt<0, 65535>,
+    pub gaps: Length<0, 65535>,
     #[knuffel(child, default)]
     pub struts: Struts,
     #[knuffel(child, default = DEFAULT_BACKGROUND_COLOR)]
     pub background_color: Color,
+    #[knuffel(child, unwrap(argument), default)]
+    pub focus_after_close: FocusAfterClose,
 }
 
 impl Default for Layout {
@@ -551,10 +794,11 @@ impl Default for Layout {
             always_center_single_column: false,
             empty_workspace_above_first: false,
             default_column_display: ColumnDisplay::Normal,
-            gaps: FloatOrInt(16.),
+            gaps: Length::Logical(16.),
             struts: Default::default(),
             preset_window_heights: Default::default(),
             background_color: DEFAULT_BACKGROUND_COLOR,
+            focus_after_close: FocusAfterClose::Auto,
         }
     }
 }
@@ -563,6 +807,10 @@ impl Default for Layout {
 pub struct SpawnAtStartup {
     #[knuffel(arguments)]
     pub command: Vec<String>,
+    #[knuffel(property, default)]
+    pub restart_on_failure: bool,
+    #[knuffel(property, default = 5)]
+    pub max_restarts: u32,
 }
 
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
@@ -583,6 +831,8 @@ pub struct FocusRing {
     pub inactive_gradient: Option<Gradient>,
     #[knuffel(child)]
     pub urgent_gradient: Option<Gradient>,
+    #[knuffel(child, default)]
+    pub glow: FocusRingGlow,
 }
 
 impl Default for FocusRing {
@@ -596,6 +846,55 @@ impl Default for FocusRing {
             active_gradient: None,
             inactive_gradient: None,
             urgent_gradient: None,
+            glow: FocusRingGlow::default(),
+        }
+    }
+}
+
+/// Soft glow drawn around the focus ring, in the focus ring color.
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct FocusRingGlow {
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument), default = Self::default().radius)]
+    pub radius: FloatOrInt<0, 1024>,
+    #[knuffel(child, unwrap(argument), default = Self::default().intensity)]
+    pub intensity: FloatOrInt<0, 1>,
+}
+
+impl Default for FocusRingGlow {
+    fn default() -> Self {
+        Self {
+            on: false,
+            radius: FloatOrInt(20.),
+            intensity: FloatOrInt(0.5),
+        }
+    }
+}
+
+/// Pulsing glow drawn around a window when it becomes urgent.
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct UrgencyFlash {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child, unwrap(argument), default = Self::default().pulses)]
+    pub pulses: u8,
+    #[knuffel(child, unwrap(argument), default = Self::default().pulse_duration_ms)]
+    pub pulse_duration_ms: u32,
+    #[knuffel(child, unwrap(argument), default = Self::default().width)]
+    pub width: FloatOrInt<0, 1024>,
+    #[knuffel(child, default = Self::default().color)]
+    pub color: Color,
+}
+
+impl Default for UrgencyFlash {
+    fn default() -> Self {
+        Self {
+            off: false,
+            pulses: 3,
+            pulse_duration_ms: 600,
+            width: FloatOrInt(16.),
+            color: Color::from_rgba8_unpremul(155, 0, 0, 255),
         }
     }
 }
@@ -703,6 +1002,7 @@ impl From<Border> for FocusRing {
             active_gradient: value.active_gradient,
             inactive_gradient: value.inactive_gradient,
             urgent_gradient: value.urgent_gradient,
+            glow: FocusRingGlow::default(),
         }
     }
 }
@@ -1026,9 +1326,98 @@ pub struct HotkeyOverlay {
 }
 
 #[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Tutorial {
+    #[knuffel(child)]
+    pub skip_at_startup: bool,
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct AdjustMode {
+    #[knuffel(child, unwrap(argument), default = Self::default().move_step)]
+    pub move_step: FloatOrInt<1, 65535>,
+    #[knuffel(child, unwrap(argument), default = Self::default().resize_step)]
+    pub resize_step: u16,
+}
+
+impl Default for AdjustMode {
+    fn default() -> Self {
+        Self {
+            move_step: FloatOrInt(10.),
+            resize_step: 10,
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaunchPlaceholder {
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument), default = Self::default().timeout_ms)]
+    pub timeout_ms: u32,
+}
+
+impl Default for LaunchPlaceholder {
+    fn default() -> Self {
+        Self {
+            on: false,
+            timeout_ms: 10000,
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq, Eq)]
 pub struct Clipboard {
     #[knuffel(child)]
     pub disable_primary: bool,
+    /// Mirroring between the primary selection and the clipboard.
+    #[knuffel(child)]
+    pub sync_primary: Option<SyncPrimary>,
+}
+
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct SyncPrimary {
+    #[knuffel(child, unwrap(argument), default)]
+    pub direction: SelectionSyncDirection,
+    /// Minimum time between two mirrorings; faster selection changes are coalesced.
+    #[knuffel(child, unwrap(argument), default = Self::default().min_interval_ms)]
+    pub min_interval_ms: u16,
+    /// Mime types to mirror. If empty, text mime types are mirrored.
+    #[knuffel(children(name = "mime-type"), unwrap(argument))]
+    pub mime_types: Vec<String>,
+}
+
+impl Default for SyncPrimary {
+    fn default() -> Self {
+        Self {
+            direction: SelectionSyncDirection::default(),
+            min_interval_ms: 200,
+            mime_types: Vec::new(),
+        }
+    }
+}
+
+impl SyncPrimary {
+    /// Returns whether a mime type should be mirrored.
+    pub fn allows_mime_type(&self, mime_type: &str) -> bool {
+        if self.mime_types.is_empty() {
+            let x11_text = ["UTF8_STRING", "STRING", "TEXT", "COMPOUND_TEXT"];
+            return mime_type.starts_with("text/") || x11_text.contains(&mime_type);
+        }
+
+        self.mime_types.iter().any(|m| m == mime_type)
+    }
+}
+
+/// Which way selections are mirrored.
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionSyncDirection {
+    /// Primary selection into the clipboard.
+    ToClipboard,
+    /// Clipboard into the primary selection.
+    ToPrimary,
+    /// Both ways.
+    #[default]
+    Both,
 }
 
 #[derive(knuffel::Decode, Debug, Clone, PartialEq)]
@@ -1053,6 +1442,10 @@ pub struct Animations {
     pub config_notification_open_close: ConfigNotificationOpenCloseAnim,
     #[knuffel(child, default)]
     pub overview_open_close: OverviewOpenCloseAnim,
+    #[knuffel(child, default)]
+    pub floating_window_movement: FloatingWindowMovementAnim,
+    #[knuffel(child, default)]
+    pub saturation_change: SaturationChangeAnim,
 }
 
 impl Default for Animations {
@@ -1068,6 +1461,8 @@ impl Default for Animations {
             window_resize: Default::default(),
             config_notification_open_close: Default::default(),
             overview_open_close: Default::default(),
+            floating_window_movement: Default::default(),
+            saturation_change: Default::default(),
         }
     }
 }
@@ -1216,6 +1611,32 @@ impl Default for OverviewOpenCloseAnim {
     }
 }
 
+/// Animation for moving floating windows to a new position, for example over IPC.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatingWindowMovementAnim(pub Animation);
+
+impl Default for FloatingWindowMovementAnim {
+    fn default() -> Self {
+        Self(WindowMovementAnim::default().0)
+    }
+}
+
+/// Animation for fading the saturation of windows and outputs, for example to grayscale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SaturationChangeAnim(pub Animation);
+
+impl Default for SaturationChangeAnim {
+    fn default() -> Self {
+        Self(Animation {
+            off: false,
+            kind: AnimationKind::Easing(EasingParams {
+                duration_ms: 300,
+                curve: AnimationCurve::EaseOutCubic,
+            }),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Animation {
     pub off: bool,
@@ -1249,7 +1670,7 @@ pub struct SpringParams {
     pub epsilon: f64,
 }
 
-#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
 pub struct Gestures {
     #[knuffel(child, default)]
     pub dnd_edge_view_scroll: DndEdgeViewScroll,
@@ -1257,6 +1678,8 @@ pub struct Gestures {
     pub dnd_edge_workspace_switch: DndEdgeWorkspaceSwitch,
     #[knuffel(child, default)]
     pub hot_corners: HotCorners,
+    #[knuffel(child, default)]
+    pub touchscreen: TouchscreenGestures,
 }
 
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
@@ -1287,6 +1710,9 @@ pub struct DndEdgeWorkspaceSwitch {
     pub delay_ms: u16,
     #[knuffel(child, unwrap(argument), default = Self::default().max_speed)]
     pub max_speed: FloatOrInt<0, 1_000_000>,
+    /// Outside the overview, how long the drag has to stay at the edge to switch one workspace.
+    #[knuffel(child, unwrap(argument), default = Self::default().dwell_ms)]
+    pub dwell_ms: u16,
 }
 
 impl Default for DndEdgeWorkspaceSwitch {
@@ -1295,14 +1721,104 @@ impl Default for DndEdgeWorkspaceSwitch {
             trigger_height: FloatOrInt(50.),
             delay_ms: 100,
             max_speed: FloatOrInt(1500.),
+            dwell_ms: 500,
         }
     }
 }
 
-#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+/// Hot corners and edges that run actions when the pointer enters them.
+///
+/// Without any corner or edge set up, the top-left corner toggles the overview.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
 pub struct HotCorners {
     #[knuffel(child)]
     pub off: bool,
+    /// Side of the corner squares and thickness of the edges in logical pixels.
+    #[knuffel(child, unwrap(argument), default = Self::default().size)]
+    pub size: FloatOrInt<1, 65535>,
+    /// How long the pointer must stay in a hot area before it triggers.
+    #[knuffel(child, unwrap(argument), default)]
+    pub delay_ms: u16,
+    /// Color of the hint drawn over a hot area while the pointer is inside.
+    #[knuffel(child)]
+    pub hint_color: Option<Color>,
+    #[knuffel(child)]
+    pub top_left: Option<HotArea>,
+    #[knuffel(child)]
+    pub top_right: Option<HotArea>,
+    #[knuffel(child)]
+    pub bottom_left: Option<HotArea>,
+    #[knuffel(child)]
+    pub bottom_right: Option<HotArea>,
+    #[knuffel(child)]
+    pub top: Option<HotArea>,
+    #[knuffel(child)]
+    pub bottom: Option<HotArea>,
+    #[knuffel(child)]
+    pub left: Option<HotArea>,
+    #[knuffel(child)]
+    pub right: Option<HotArea>,
+}
+
+/// What a hot corner or edge does. Without actions, it toggles the overview.
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
+pub struct HotArea {
+    #[knuffel(children)]
+    pub actions: Vec<Action>,
+}
+
+impl Default for HotCorners {
+    fn default() -> Self {
+        Self {
+            off: false,
+            size: FloatOrInt(1.),
+            delay_ms: 0,
+            hint_color: None,
+            top_left: None,
+            top_right: None,
+            bottom_left: None,
+            bottom_right: None,
+            top: None,
+            bottom: None,
+            left: None,
+            right: None,
+        }
+    }
+}
+
+impl HotCorners {
+    /// Returns whether any corner or edge is set up explicitly.
+    pub fn has_explicit_areas(&self) -> bool {
+        [
+            &self.top_left,
+            &self.top_right,
+            &self.bottom_left,
+            &self.bottom_right,
+            &self.top,
+            &self.bottom,
+            &self.left,
+            &self.right,
+        ]
+        .iter()
+        .any(|area| area.is_some())
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct TouchscreenGestures {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child, unwrap(argument), default = Self::default().edge_swipe_width)]
+    pub edge_swipe_width: FloatOrInt<0, 65535>,
+}
+
+impl Default for TouchscreenGestures {
+    fn default() -> Self {
+        Self {
+            off: false,
+            edge_swipe_width: FloatOrInt(20.),
+        }
+    }
 }
 
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
@@ -1313,6 +1829,19 @@ pub struct Overview {
     pub backdrop_color: Color,
     #[knuffel(child, default)]
     pub workspace_shadow: WorkspaceShadow,
+    #[knuffel(child, unwrap(argument), default)]
+    pub thumbnail_quality: ThumbnailQuality,
+}
+
+/// How window thumbnails are scaled down.
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ThumbnailQuality {
+    /// The window is rendered directly at the thumbnail size.
+    #[default]
+    Fast,
+    /// The window is rendered at full size and averaged down on the CPU, which keeps text and
+    /// thin lines from aliasing at small sizes.
+    Smooth,
 }
 
 impl Default for Overview {
@@ -1321,6 +1850,7 @@ impl Default for Overview {
             zoom: FloatOrInt(0.5),
             backdrop_color: DEFAULT_BACKDROP_COLOR,
             workspace_shadow: WorkspaceShadow::default(),
+            thumbnail_quality: ThumbnailQuality::default(),
         }
     }
 }
@@ -1336,25 +1866,72 @@ pub struct EnvironmentVariable {
     pub value: Option<String>,
 }
 
+/// An extra seat driven by its own input devices, for example for a second user.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct Seat {
+    #[knuffel(argument)]
+    pub name: String,
+    #[knuffel(children(name = "device"))]
+    pub devices: Vec<SeatDevice>,
+    #[knuffel(child, default = DEFAULT_SEAT_FOCUS_RING_COLOR)]
+    pub focus_ring_color: Color,
+}
+
+/// An input device that belongs to a seat, matched by its name.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct SeatDevice {
+    #[knuffel(argument)]
+    pub name: String,
+}
+
 #[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
 pub struct Workspace {
     #[knuffel(argument)]
     pub name: WorkspaceName,
     #[knuffel(child, unwrap(argument))]
     pub open_on_output: Option<String>,
+    #[knuffel(child)]
+    pub wallpaper: Option<Wallpaper>,
+    #[knuffel(children(name = "reserved-slot"))]
+    pub reserved_slots: Vec<ReservedSlot>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WorkspaceName(pub String);
 
-#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
-pub struct WindowRule {
-    #[knuffel(children(name = "match"))]
-    pub matches: Vec<Match>,
-    #[knuffel(children(name = "exclude"))]
-    pub excludes: Vec<Match>,
-
-    // Rules applied at initial configure.
+/// Column on a named workspace kept for windows of one app.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct ReservedSlot {
+    #[knuffel(argument)]
+    pub app_id: String,
+    #[knuffel(child, unwrap(argument), default)]
+    pub position: ReservedSlotPosition,
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ReservedSlotPosition {
+    /// The leftmost column.
+    First,
+    /// The rightmost column.
+    #[default]
+    Last,
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
+pub struct WindowRule {
+    /// Name for enabling and disabling the rule at runtime.
+    #[knuffel(child, unwrap(argument))]
+    pub name: Option<String>,
+    /// Whether the rule is disabled.
+    #[knuffel(child)]
+    pub off: bool,
+
+    #[knuffel(children(name = "match"))]
+    pub matches: Vec<Match>,
+    #[knuffel(children(name = "exclude"))]
+    pub excludes: Vec<Match>,
+
+    // Rules applied at initial configure.
     #[knuffel(child)]
     pub default_column_width: Option<DefaultPresetSize>,
     #[knuffel(child)]
@@ -1372,6 +1949,10 @@ pub struct WindowRule {
     #[knuffel(child, unwrap(argument))]
     pub open_focused: Option<bool>,
 
+    // Rules applied at map.
+    #[knuffel(child, unwrap(argument))]
+    pub skip_open_animation: Option<bool>,
+
     // Rules applied dynamically.
     #[knuffel(child, unwrap(argument))]
     pub min_width: Option<u16>,
@@ -1381,6 +1962,8 @@ pub struct WindowRule {
     pub max_width: Option<u16>,
     #[knuffel(child, unwrap(argument))]
     pub max_height: Option<u16>,
+    #[knuffel(child, unwrap(argument))]
+    pub keep_aspect_ratio: Option<bool>,
 
     #[knuffel(child, default)]
     pub focus_ring: BorderRule,
@@ -1399,6 +1982,8 @@ pub struct WindowRule {
     #[knuffel(child, unwrap(argument))]
     pub clip_to_geometry: Option<bool>,
     #[knuffel(child, unwrap(argument))]
+    pub resize_content: Option<ResizeContent>,
+    #[knuffel(child, unwrap(argument))]
     pub baba_is_float: Option<bool>,
     #[knuffel(child, unwrap(argument))]
     pub block_out_from: Option<BlockOutFrom>,
@@ -1412,6 +1997,18 @@ pub struct WindowRule {
     pub scroll_factor: Option<FloatOrInt<0, 100>>,
     #[knuffel(child, unwrap(argument))]
     pub tiled_state: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub skip_animations: Option<bool>,
+    #[knuffel(child)]
+    pub urgency_flash: Option<UrgencyFlash>,
+    #[knuffel(child, unwrap(argument))]
+    pub max_render_fps: Option<FloatOrInt<1, 1000>>,
+    #[knuffel(child, unwrap(argument))]
+    pub focus_after_close: Option<FocusAfterClose>,
+    #[knuffel(child, unwrap(argument))]
+    pub swallow_children: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub saturation: Option<f32>,
 }
 
 #[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
@@ -1434,6 +2031,32 @@ pub struct Match {
     pub is_urgent: Option<bool>,
     #[knuffel(property)]
     pub at_startup: Option<bool>,
+    #[knuffel(property)]
+    pub size: Option<WindowSize>,
+    /// Percentage of the working area that the window covers at least.
+    #[knuffel(property)]
+    pub min_coverage: Option<FloatOrInt<0, 100>>,
+    /// Sandbox engine of the client, such as `org.flatpak`.
+    #[knuffel(property, str)]
+    pub sandbox_engine: Option<RegexEq>,
+    /// App id of the client within its sandbox engine, such as the Flatpak app id.
+    #[knuffel(property, str)]
+    pub sandbox_app_id: Option<RegexEq>,
+    /// Path to the executable of the client process.
+    #[knuffel(property, str)]
+    pub executable: Option<RegexEq>,
+    /// Whether this is an X11 window shown through xwayland-satellite.
+    #[knuffel(property)]
+    pub is_x11: Option<bool>,
+}
+
+/// Size category of a window, relative to the working area of its output when it maps.
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowSize {
+    /// Fits within half the working area both horizontally and vertically.
+    Small,
+    /// Anything bigger than small.
+    Large,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
@@ -1466,6 +2089,19 @@ impl From<f32> for CornerRadius {
     }
 }
 
+/// How the window contents follow the size during a resize animation.
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeContent {
+    /// Stretch the old and the new contents to the animated size.
+    #[default]
+    Stretch,
+    /// Keep the old and the new contents at their own size and crossfade them, cropped to the
+    /// animated size.
+    Crop,
+    /// Show the new contents at their own size right away, cropped to the animated size.
+    Reveal,
+}
+
 #[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BlockOutFrom {
     Screencast,
@@ -1532,10 +2168,12 @@ pub struct TabIndicatorRule {
 
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
 pub struct FloatingPosition {
+    /// Horizontal offset, percentages are of the working area width.
     #[knuffel(property)]
-    pub x: FloatOrInt<-65535, 65535>,
+    pub x: Length<-65535, 65535>,
+    /// Vertical offset, percentages are of the working area height.
     #[knuffel(property)]
-    pub y: FloatOrInt<-65535, 65535>,
+    pub y: Length<-65535, 65535>,
     #[knuffel(property, default)]
     pub relative_to: RelativeTo,
 }
@@ -1560,13 +2198,30 @@ pub struct Binds(pub Vec<Bind>);
 pub struct Bind {
     pub key: Key,
     pub action: Action,
+    pub activation: BindActivation,
     pub repeat: bool,
+    /// Key repeat delay overriding the keyboard one, in milliseconds.
+    pub repeat_delay: Option<u16>,
+    /// Key repeat rate overriding the keyboard one, in characters per second.
+    pub repeat_rate: Option<u8>,
     pub cooldown: Option<Duration>,
     pub allow_when_locked: bool,
     pub allow_inhibiting: bool,
     pub hotkey_overlay_title: Option<Option<String>>,
 }
 
+/// When a bind triggers.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum BindActivation {
+    /// When the key is pressed.
+    #[default]
+    Press,
+    /// When the key is released, if it was pressed together with the bind modifiers.
+    Release,
+    /// When a lone modifier key is tapped twice in quick succession.
+    DoubleTap,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct Key {
     pub trigger: Trigger,
@@ -1589,6 +2244,9 @@ pub enum Trigger {
     TouchpadScrollUp,
     TouchpadScrollLeft,
     TouchpadScrollRight,
+    PenButton1,
+    PenButton2,
+    PenButton3,
 }
 
 bitflags! {
@@ -1622,6 +2280,109 @@ pub struct SwitchAction {
     pub spawn: Vec<String>,
 }
 
+/// Built-in handling of the volume and brightness keys that aren't bound to anything.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct HardwareKeys {
+    #[knuffel(child)]
+    pub off: bool,
+    /// Volume change in percent for one key press.
+    #[knuffel(child, unwrap(argument), default = Self::default().volume_step)]
+    pub volume_step: u8,
+    /// Brightness change in percent of the maximum for one key press.
+    #[knuffel(child, unwrap(argument), default = Self::default().brightness_step)]
+    pub brightness_step: u8,
+    /// Name of the device in `/sys/class/backlight` to control.
+    ///
+    /// When unset, the first device is used.
+    #[knuffel(child, unwrap(argument))]
+    pub backlight: Option<String>,
+}
+
+impl Default for HardwareKeys {
+    fn default() -> Self {
+        Self {
+            off: false,
+            volume_step: 5,
+            brightness_step: 5,
+            backlight: None,
+        }
+    }
+}
+
+/// Integration with systemd-logind.
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
+pub struct Logind {
+    /// What to do when the laptop lid closes.
+    ///
+    /// When set, niri takes lid switch handling over from logind.
+    #[knuffel(child, unwrap(argument))]
+    pub lid_switch: Option<LidSwitchPolicy>,
+    /// Command to spawn when logind asks to lock the session, e.g. with `loginctl lock-session`.
+    ///
+    /// When logind asks to unlock the session, niri terminates the command with SIGTERM, so it
+    /// should stay in the foreground.
+    #[knuffel(child, unwrap(arguments), default)]
+    pub lock_command: Vec<String>,
+}
+
+/// Fading the outputs to black after some idle time.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct IdleFade {
+    /// Idle time after which the outputs start fading; unset disables the fade.
+    #[knuffel(child, unwrap(argument))]
+    pub timeout_ms: Option<u32>,
+    /// How long the fade to black takes.
+    #[knuffel(child, unwrap(argument), default = Self::default().fade_duration_ms)]
+    pub fade_duration_ms: u32,
+    /// Whether to run `logind.lock-command` once the outputs are black.
+    #[knuffel(child)]
+    pub lock: bool,
+}
+
+impl Default for IdleFade {
+    fn default() -> Self {
+        Self {
+            timeout_ms: None,
+            fade_duration_ms: 5000,
+            lock: false,
+        }
+    }
+}
+
+/// X11 app support through xwayland-satellite.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct XwaylandSatellite {
+    /// Don't start xwayland-satellite even if it's installed.
+    #[knuffel(child)]
+    pub off: bool,
+    /// Name or path of the xwayland-satellite binary.
+    #[knuffel(child, unwrap(argument), default = Self::default().path)]
+    pub path: String,
+    /// How many times in a row to restart xwayland-satellite if it crashes.
+    #[knuffel(child, unwrap(argument), default = Self::default().max_restarts)]
+    pub max_restarts: u32,
+}
+
+impl Default for XwaylandSatellite {
+    fn default() -> Self {
+        Self {
+            off: false,
+            path: String::from("xwayland-satellite"),
+            max_restarts: 5,
+        }
+    }
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LidSwitchPolicy {
+    /// Keep everything running, including the internal output.
+    Ignore,
+    /// Suspend the system.
+    Suspend,
+    /// Turn off the internal output, even if it's the only one.
+    SwitchOffInternalOutput,
+}
+
 // Remember to add new actions to the CLI enum too.
 #[derive(knuffel::Decode, Debug, Clone, PartialEq)]
 pub enum Action {
@@ -1631,14 +2392,31 @@ pub enum Action {
     Suspend,
     PowerOffMonitors,
     PowerOnMonitors,
+    VolumeUp,
+    VolumeDown,
+    ToggleMute,
+    BrightnessUp,
+    BrightnessDown,
     ToggleDebugTint,
     DebugToggleOpaqueRegions,
     DebugToggleDamage,
+    DebugToggleDamageHeatmap,
     Spawn(#[knuffel(arguments)] Vec<String>),
+    FocusOrSpawn(
+        #[knuffel(argument)] String,
+        #[knuffel(arguments)] Vec<String>,
+        #[knuffel(property(name = "all-outputs"), default)] bool,
+    ),
     ToggleKeyboardShortcutsInhibit,
     CloseWindow,
     #[knuffel(skip)]
     CloseWindowById(u64),
+    CloseWindowOrKill(
+        #[knuffel(property(name = "timeout-ms"))] Option<u32>,
+        #[knuffel(property(name = "auto-kill"), default)] bool,
+    ),
+    #[knuffel(skip)]
+    CloseWindowOrKillById(u64, Option<u32>, bool),
     FullscreenWindow,
     #[knuffel(skip)]
     FullscreenWindowById(u64),
@@ -1649,6 +2427,8 @@ pub enum Action {
     FocusWindow(u64),
     FocusWindowInColumn(#[knuffel(argument)] u8),
     FocusWindowPrevious,
+    CycleFocusHistoryBackward,
+    CycleFocusHistoryForward,
     FocusColumnLeft,
     #[knuffel(skip)]
     FocusColumnLeftUnderMouse,
@@ -1699,6 +2479,7 @@ pub enum Action {
     SwapWindowRight,
     ToggleColumnTabbedDisplay,
     SetColumnDisplay(#[knuffel(argument, str)] ColumnDisplay),
+    ToggleColumnPin(#[knuffel(argument, str)] ColumnPinEdge),
     CenterColumn,
     CenterWindow,
     #[knuffel(skip)]
@@ -1712,6 +2493,9 @@ pub enum Action {
     FocusWorkspaceUpUnderMouse,
     FocusWorkspace(#[knuffel(argument)] WorkspaceReference),
     FocusWorkspacePrevious,
+    PeekWorkspaceDown,
+    PeekWorkspaceUp,
+    EndWorkspacePeek,
     MoveWindowToWorkspaceDown,
     MoveWindowToWorkspaceUp,
     MoveWindowToWorkspace(
@@ -1804,6 +2588,7 @@ pub enum Action {
     MaximizeColumn,
     SetColumnWidth(#[knuffel(argument, str)] SizeChange),
     ExpandColumnToAvailableWidth,
+    FitColumnToContent,
     SwitchLayout(#[knuffel(argument, str)] LayoutSwitchTarget),
     MoveWorkspaceToMonitorLeft,
     MoveWorkspaceToMonitorRight,
@@ -1828,10 +2613,14 @@ pub enum Action {
         id: Option<u64>,
         x: PositionChange,
         y: PositionChange,
+        animate: bool,
     },
     ToggleWindowRuleOpacity,
     #[knuffel(skip)]
     ToggleWindowRuleOpacityById(u64),
+    ToggleWindowKeepAspectRatio,
+    #[knuffel(skip)]
+    ToggleWindowKeepAspectRatioById(u64),
     ToggleOverview,
     OpenOverview,
     CloseOverview,
@@ -1841,6 +2630,32 @@ pub enum Action {
     SetWindowUrgent(u64),
     #[knuffel(skip)]
     UnsetWindowUrgent(u64),
+    ShowBindConflicts,
+    ShowTutorial,
+    DoScreenTransition(
+        #[knuffel(property(name = "delay-ms"))] Option<u16>,
+        #[knuffel(property(name = "duration-ms"))] Option<u16>,
+        #[knuffel(property(name = "style"), str, default)] ScreenTransitionStyle,
+    ),
+    SetWallpaper(
+        #[knuffel(argument)] String,
+        #[knuffel(property(name = "output"))] Option<String>,
+        #[knuffel(property(name = "mode"), str, default)] WallpaperMode,
+        #[knuffel(property(name = "crossfade-ms"))] Option<u16>,
+    ),
+    EnterAdjustMode,
+    OpenConfigError,
+    ToggleWindowRule(#[knuffel(argument)] String),
+    EnableWindowRule(#[knuffel(argument)] String),
+    DisableWindowRule(#[knuffel(argument)] String),
+    LoadConfigFile(#[knuffel(argument)] String),
+    WarpPointerToWindow(#[knuffel(argument, str)] WindowWarpTarget),
+    WarpPointerToRegion(#[knuffel(argument)] u8),
+    SaveGroup(
+        #[knuffel(argument)] String,
+        #[knuffel(property(name = "columns"))] Option<u8>,
+    ),
+    RecallGroup(#[knuffel(argument)] String),
 }
 
 impl From<niri_ipc::Action> for Action {
@@ -1849,9 +2664,29 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::Quit { skip_confirmation } => Self::Quit(skip_confirmation),
             niri_ipc::Action::PowerOffMonitors {} => Self::PowerOffMonitors,
             niri_ipc::Action::PowerOnMonitors {} => Self::PowerOnMonitors,
+            niri_ipc::Action::VolumeUp {} => Self::VolumeUp,
+            niri_ipc::Action::VolumeDown {} => Self::VolumeDown,
+            niri_ipc::Action::ToggleMute {} => Self::ToggleMute,
+            niri_ipc::Action::BrightnessUp {} => Self::BrightnessUp,
+            niri_ipc::Action::BrightnessDown {} => Self::BrightnessDown,
             niri_ipc::Action::Spawn { command } => Self::Spawn(command),
+            niri_ipc::Action::FocusOrSpawn {
+                app_id,
+                all_outputs,
+                command,
+            } => Self::FocusOrSpawn(app_id, command, all_outputs),
             niri_ipc::Action::CloseWindow { id: None } => Self::CloseWindow,
             niri_ipc::Action::CloseWindow { id: Some(id) } => Self::CloseWindowById(id),
+            niri_ipc::Action::CloseWindowOrKill {
+                id: None,
+                timeout_ms,
+                auto_kill,
+            } => Self::CloseWindowOrKill(timeout_ms, auto_kill),
+            niri_ipc::Action::CloseWindowOrKill {
+                id: Some(id),
+                timeout_ms,
+                auto_kill,
+            } => Self::CloseWindowOrKillById(id, timeout_ms, auto_kill),
             niri_ipc::Action::FullscreenWindow { id: None } => Self::FullscreenWindow,
             niri_ipc::Action::FullscreenWindow { id: Some(id) } => Self::FullscreenWindowById(id),
             niri_ipc::Action::ToggleWindowedFullscreen { id: None } => {
@@ -1863,6 +2698,8 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::FocusWindow { id } => Self::FocusWindow(id),
             niri_ipc::Action::FocusWindowInColumn { index } => Self::FocusWindowInColumn(index),
             niri_ipc::Action::FocusWindowPrevious {} => Self::FocusWindowPrevious,
+            niri_ipc::Action::CycleFocusHistoryBackward {} => Self::CycleFocusHistoryBackward,
+            niri_ipc::Action::CycleFocusHistoryForward {} => Self::CycleFocusHistoryForward,
             niri_ipc::Action::FocusColumnLeft {} => Self::FocusColumnLeft,
             niri_ipc::Action::FocusColumnRight {} => Self::FocusColumnRight,
             niri_ipc::Action::FocusColumnFirst {} => Self::FocusColumnFirst,
@@ -1921,6 +2758,7 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::SwapWindowLeft {} => Self::SwapWindowLeft,
             niri_ipc::Action::ToggleColumnTabbedDisplay {} => Self::ToggleColumnTabbedDisplay,
             niri_ipc::Action::SetColumnDisplay { display } => Self::SetColumnDisplay(display),
+            niri_ipc::Action::ToggleColumnPin { edge } => Self::ToggleColumnPin(edge),
             niri_ipc::Action::CenterColumn {} => Self::CenterColumn,
             niri_ipc::Action::CenterWindow { id: None } => Self::CenterWindow,
             niri_ipc::Action::CenterWindow { id: Some(id) } => Self::CenterWindowById(id),
@@ -1931,6 +2769,9 @@ impl From<niri_ipc::Action> for Action {
                 Self::FocusWorkspace(WorkspaceReference::from(reference))
             }
             niri_ipc::Action::FocusWorkspacePrevious {} => Self::FocusWorkspacePrevious,
+            niri_ipc::Action::PeekWorkspaceDown {} => Self::PeekWorkspaceDown,
+            niri_ipc::Action::PeekWorkspaceUp {} => Self::PeekWorkspaceUp,
+            niri_ipc::Action::EndWorkspacePeek {} => Self::EndWorkspacePeek,
             niri_ipc::Action::MoveWindowToWorkspaceDown {} => Self::MoveWindowToWorkspaceDown,
             niri_ipc::Action::MoveWindowToWorkspaceUp {} => Self::MoveWindowToWorkspaceUp,
             niri_ipc::Action::MoveWindowToWorkspace {
@@ -2026,6 +2867,7 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::MaximizeColumn {} => Self::MaximizeColumn,
             niri_ipc::Action::SetColumnWidth { change } => Self::SetColumnWidth(change),
             niri_ipc::Action::ExpandColumnToAvailableWidth {} => Self::ExpandColumnToAvailableWidth,
+            niri_ipc::Action::FitColumnToContent {} => Self::FitColumnToContent,
             niri_ipc::Action::SwitchLayout { layout } => Self::SwitchLayout(layout),
             niri_ipc::Action::MoveWorkspaceToMonitorLeft {} => Self::MoveWorkspaceToMonitorLeft,
             niri_ipc::Action::MoveWorkspaceToMonitorRight {} => Self::MoveWorkspaceToMonitorRight,
@@ -2060,6 +2902,7 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::ToggleDebugTint {} => Self::ToggleDebugTint,
             niri_ipc::Action::DebugToggleOpaqueRegions {} => Self::DebugToggleOpaqueRegions,
             niri_ipc::Action::DebugToggleDamage {} => Self::DebugToggleDamage,
+            niri_ipc::Action::DebugToggleDamageHeatmap {} => Self::DebugToggleDamageHeatmap,
             niri_ipc::Action::ToggleWindowFloating { id: None } => Self::ToggleWindowFloating,
             niri_ipc::Action::ToggleWindowFloating { id: Some(id) } => {
                 Self::ToggleWindowFloatingById(id)
@@ -2077,19 +2920,48 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::SwitchFocusBetweenFloatingAndTiling {} => {
                 Self::SwitchFocusBetweenFloatingAndTiling
             }
-            niri_ipc::Action::MoveFloatingWindow { id, x, y } => {
-                Self::MoveFloatingWindowById { id, x, y }
+            niri_ipc::Action::MoveFloatingWindow { id, x, y, animate } => {
+                Self::MoveFloatingWindowById { id, x, y, animate }
             }
             niri_ipc::Action::ToggleWindowRuleOpacity { id: None } => Self::ToggleWindowRuleOpacity,
             niri_ipc::Action::ToggleWindowRuleOpacity { id: Some(id) } => {
                 Self::ToggleWindowRuleOpacityById(id)
             }
+            niri_ipc::Action::ToggleWindowKeepAspectRatio { id: None } => {
+                Self::ToggleWindowKeepAspectRatio
+            }
+            niri_ipc::Action::ToggleWindowKeepAspectRatio { id: Some(id) } => {
+                Self::ToggleWindowKeepAspectRatioById(id)
+            }
             niri_ipc::Action::ToggleOverview {} => Self::ToggleOverview,
             niri_ipc::Action::OpenOverview {} => Self::OpenOverview,
             niri_ipc::Action::CloseOverview {} => Self::CloseOverview,
             niri_ipc::Action::ToggleWindowUrgent { id } => Self::ToggleWindowUrgent(id),
             niri_ipc::Action::SetWindowUrgent { id } => Self::SetWindowUrgent(id),
             niri_ipc::Action::UnsetWindowUrgent { id } => Self::UnsetWindowUrgent(id),
+            niri_ipc::Action::ShowBindConflicts {} => Self::ShowBindConflicts,
+            niri_ipc::Action::ShowTutorial {} => Self::ShowTutorial,
+            niri_ipc::Action::DoScreenTransition {
+                delay_ms,
+                duration_ms,
+                style,
+            } => Self::DoScreenTransition(delay_ms, duration_ms, style.unwrap_or_default()),
+            niri_ipc::Action::SetWallpaper {
+                path,
+                output,
+                mode,
+                crossfade_ms,
+            } => Self::SetWallpaper(path, output, mode.unwrap_or_default(), crossfade_ms),
+            niri_ipc::Action::EnterAdjustMode {} => Self::EnterAdjustMode,
+            niri_ipc::Action::OpenConfigError {} => Self::OpenConfigError,
+            niri_ipc::Action::ToggleWindowRule { name } => Self::ToggleWindowRule(name),
+            niri_ipc::Action::EnableWindowRule { name } => Self::EnableWindowRule(name),
+            niri_ipc::Action::DisableWindowRule { name } => Self::DisableWindowRule(name),
+            niri_ipc::Action::LoadConfigFile { path } => Self::LoadConfigFile(path),
+            niri_ipc::Action::WarpPointerToWindow { target } => Self::WarpPointerToWindow(target),
+            niri_ipc::Action::WarpPointerToRegion { region } => Self::WarpPointerToRegion(region),
+            niri_ipc::Action::SaveGroup { name, columns } => Self::SaveGroup(name, columns),
+            niri_ipc::Action::RecallGroup { name } => Self::RecallGroup(name),
         }
     }
 }
@@ -2215,6 +3087,85 @@ impl<S: knuffel::traits::ErrorSpan, const MIN: i32, const MAX: i32> knuffel::Dec
     }
 }
 
+impl<const MIN: i32, const MAX: i32> Length<MIN, MAX> {
+    /// Converts the length to logical pixels.
+    ///
+    /// `px_per_mm` is the number of logical pixels in one millimeter on the output, and
+    /// `reference` is the logical size that percentages are relative to.
+    pub fn to_logical(self, px_per_mm: f64, reference: f64) -> f64 {
+        match self {
+            Length::Logical(px) => px,
+            Length::Millimeters(mm) => mm * px_per_mm,
+            Length::Percent(percent) => reference * percent / 100.,
+        }
+    }
+}
+
+impl<const MIN: i32, const MAX: i32> FromStr for Length<MIN, MAX> {
+    type Err = miette::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (value, length): (_, fn(f64) -> Self) = if let Some(value) = s.strip_suffix("mm") {
+            (value, Length::Millimeters)
+        } else if let Some(value) = s.strip_suffix('%') {
+            (value, Length::Percent)
+        } else if let Some(value) = s.strip_suffix("px") {
+            (value, Length::Logical)
+        } else {
+            (s, Length::Logical)
+        };
+
+        let value = value
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| miette!("invalid length {s:?}, expected a number with px, mm or %"))?;
+
+        if !(f64::from(MIN)..=f64::from(MAX)).contains(&value) {
+            return Err(miette!("value must be between {MIN} and {MAX}"));
+        }
+
+        Ok(length(value))
+    }
+}
+
+impl<const MIN: i32, const MAX: i32> std::fmt::Display for Length<MIN, MAX> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Length::Logical(px) => write!(f, "{px}"),
+            Length::Millimeters(mm) => write!(f, "{mm}mm"),
+            Length::Percent(percent) => write!(f, "{percent}%"),
+        }
+    }
+}
+
+impl<S: knuffel::traits::ErrorSpan, const MIN: i32, const MAX: i32> knuffel::DecodeScalar<S>
+    for Length<MIN, MAX>
+{
+    fn type_check(
+        type_name: &Option<knuffel::span::Spanned<knuffel::ast::TypeName, S>>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) {
+        FloatOrInt::<MIN, MAX>::type_check(type_name, ctx);
+    }
+
+    fn raw_decode(
+        val: &knuffel::span::Spanned<knuffel::ast::Literal, S>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) -> Result<Self, DecodeError<S>> {
+        match &**val {
+            knuffel::ast::Literal::String(ref s) => match s.parse() {
+                Ok(length) => Ok(length),
+                Err(err) => {
+                    ctx.emit_error(DecodeError::conversion(val, err));
+                    Ok(Length::Logical(0.))
+                }
+            },
+            _ => FloatOrInt::<MIN, MAX>::raw_decode(val, ctx).map(|v| Length::Logical(v.0)),
+        }
+    }
+}
+
 #[derive(knuffel::Decode, Debug, Default, PartialEq)]
 pub struct DebugConfig {
     #[knuffel(child, unwrap(argument))]
@@ -2251,6 +3202,8 @@ pub struct DebugConfig {
     pub strict_new_window_focus_policy: bool,
     #[knuffel(child)]
     pub honor_xdg_activation_with_invalid_serial: bool,
+    #[knuffel(child)]
+    pub ipc_input_injection: bool,
 }
 
 #[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
@@ -2287,6 +3240,74 @@ impl Config {
     }
 }
 
+/// The first error of a config that failed to load, for showing to the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigErrorDetail {
+    pub path: PathBuf,
+    pub message: String,
+    /// 1-based line and column where the error is, if known.
+    pub position: Option<(usize, usize)>,
+    /// The offending line of the config.
+    pub excerpt: Option<String>,
+}
+
+impl ConfigErrorDetail {
+    /// Extracts the first error out of a [`Config::load()`] error.
+    pub fn from_report(path: &Path, report: &miette::Report) -> Self {
+        if let Some(err) = report.downcast_ref::<knuffel::Error>() {
+            // The error doesn't expose the parsed text, so read it again.
+            if let Ok(text) = std::fs::read_to_string(path) {
+                return Self::from_parse_error(path, &text, err);
+            }
+        }
+
+        let message = report
+            .chain()
+            .last()
+            .map_or_else(|| report.to_string(), |err| err.to_string());
+
+        Self {
+            path: path.to_owned(),
+            message,
+            position: None,
+            excerpt: None,
+        }
+    }
+
+    pub fn from_parse_error(path: &Path, text: &str, err: &knuffel::Error) -> Self {
+        // knuffel::Error itself only says "error parsing KDL", the actual errors are related
+        // diagnostics.
+        let first = err.related().and_then(|mut errors| errors.next());
+        let diagnostic: &dyn Diagnostic = first.unwrap_or(err);
+
+        let message = diagnostic.to_string();
+        let offset = diagnostic
+            .labels()
+            .and_then(|mut labels| labels.next())
+            .map(|label| label.offset());
+
+        let mut position = None;
+        let mut excerpt = None;
+        if let Some(offset) = offset.filter(|offset| *offset <= text.len()) {
+            let before = text.get(..offset).unwrap_or_default();
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            let line = before.matches('\n').count() + 1;
+            let column = before[line_start..].chars().count() + 1;
+            position = Some((line, column));
+
+            let line_text = text[line_start..].lines().next().unwrap_or_default();
+            excerpt = Some(line_text.trim().to_owned());
+        }
+
+        Self {
+            path: path.to_owned(),
+            message,
+            position,
+            excerpt,
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config::parse(
@@ -2934,6 +3955,44 @@ where
     }
 }
 
+impl<S: knuffel::traits::ErrorSpan> knuffel::DecodeScalar<S> for ColorDepth {
+    fn type_check(
+        type_name: &Option<knuffel::span::Spanned<knuffel::ast::TypeName, S>>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) {
+        if let Some(type_name) = &type_name {
+            ctx.emit_error(DecodeError::unexpected(
+                type_name,
+                "type name",
+                "no type name expected for this node",
+            ));
+        }
+    }
+
+    fn raw_decode(
+        val: &knuffel::span::Spanned<knuffel::ast::Literal, S>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) -> Result<ColorDepth, DecodeError<S>> {
+        match &**val {
+            knuffel::ast::Literal::Int(ref value) => match u8::try_from(value) {
+                Ok(8) => Ok(Self::Eight),
+                Ok(10) => Ok(Self::Ten),
+                _ => {
+                    ctx.emit_error(DecodeError::conversion(val, "color depth must be 8 or 10"));
+                    Ok(Self::default())
+                }
+            },
+            _ => {
+                ctx.emit_error(DecodeError::unsupported(
+                    val,
+                    "color depth must be an integer",
+                ));
+                Ok(Self::default())
+            }
+        }
+    }
+}
+
 impl<S: knuffel::traits::ErrorSpan> knuffel::DecodeScalar<S> for WorkspaceName {
     fn type_check(
         type_name: &Option<knuffel::span::Spanned<knuffel::ast::TypeName, S>>,
@@ -3094,6 +4153,36 @@ where
     }
 }
 
+impl<S> knuffel::Decode<S> for FloatingWindowMovementAnim
+where
+    S: knuffel::traits::ErrorSpan,
+{
+    fn decode_node(
+        node: &knuffel::ast::SpannedNode<S>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) -> Result<Self, DecodeError<S>> {
+        let default = Self::default().0;
+        Ok(Self(Animation::decode_node(node, ctx, default, |_, _| {
+            Ok(false)
+        })?))
+    }
+}
+
+impl<S> knuffel::Decode<S> for SaturationChangeAnim
+where
+    S: knuffel::traits::ErrorSpan,
+{
+    fn decode_node(
+        node: &knuffel::ast::SpannedNode<S>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) -> Result<Self, DecodeError<S>> {
+        let default = Self::default().0;
+        Ok(Self(Animation::decode_node(node, ctx, default, |_, _| {
+            Ok(false)
+        })?))
+    }
+}
+
 impl Animation {
     pub fn new_off() -> Self {
         Self {
@@ -3458,7 +4547,9 @@ where
                     ctx.emit_error(e);
                 }
                 Ok(bind) => {
-                    if seen_keys.insert(bind.key) {
+                    // Double-tap binds live separately from press and release binds.
+                    let is_double_tap = bind.activation == BindActivation::DoubleTap;
+                    if seen_keys.insert((bind.key, is_double_tap)) {
                         binds.push(bind);
                     } else {
                         // ideally, this error should point to the previous instance of this keybind
@@ -3530,7 +4621,12 @@ where
             .map_err(|e| DecodeError::conversion(&node.node_name, e.wrap_err("invalid keybind")))?;
 
         let mut repeat = true;
+        let mut repeat_delay = None;
+        let mut repeat_rate = None;
+        let mut repeat_override_node = None;
         let mut cooldown = None;
+        let mut on_release_node = None;
+        let mut double_tap_node = None;
         let mut allow_when_locked = false;
         let mut allow_when_locked_node = None;
         let mut allow_inhibiting = true;
@@ -3540,11 +4636,27 @@ where
                 "repeat" => {
                     repeat = knuffel::traits::DecodeScalar::decode(val, ctx)?;
                 }
+                "repeat-delay" => {
+                    repeat_delay = Some(knuffel::traits::DecodeScalar::decode(val, ctx)?);
+                    repeat_override_node = Some(name);
+                }
+                "repeat-rate" => {
+                    repeat_rate = Some(knuffel::traits::DecodeScalar::decode(val, ctx)?);
+                    repeat_override_node = Some(name);
+                }
                 "cooldown-ms" => {
                     cooldown = Some(Duration::from_millis(
                         knuffel::traits::DecodeScalar::decode(val, ctx)?,
                     ));
                 }
+                "on-release" => {
+                    let value: bool = knuffel::traits::DecodeScalar::decode(val, ctx)?;
+                    on_release_node = value.then_some(name);
+                }
+                "double-tap" => {
+                    let value: bool = knuffel::traits::DecodeScalar::decode(val, ctx)?;
+                    double_tap_node = value.then_some(name);
+                }
                 "allow-when-locked" => {
                     allow_when_locked = knuffel::traits::DecodeScalar::decode(val, ctx)?;
                     allow_when_locked_node = Some(name);
@@ -3565,6 +4677,62 @@ where
             }
         }
 
+        let activation = match (on_release_node, double_tap_node) {
+            (Some(_), Some(node)) => {
+                ctx.emit_error(DecodeError::unexpected(
+                    node,
+                    "property",
+                    "on-release and double-tap cannot be used together",
+                ));
+                BindActivation::Press
+            }
+            (Some(node), None) => {
+                if !matches!(key.trigger, Trigger::Keysym(_)) {
+                    ctx.emit_error(DecodeError::unexpected(
+                        node,
+                        "property",
+                        "on-release can only be set on keyboard binds",
+                    ));
+                }
+                BindActivation::Release
+            }
+            (None, Some(node)) => {
+                // Double-tap binds only work on modifiers because taps are passed through to
+                // clients: we can't take back a regular key press once it turns out to be the
+                // first of a double tap.
+                let is_lone_modifier = match key.trigger {
+                    Trigger::Keysym(keysym) => keysym.is_modifier_key() && key.modifiers.is_empty(),
+                    _ => false,
+                };
+                if !is_lone_modifier {
+                    ctx.emit_error(DecodeError::unexpected(
+                        node,
+                        "property",
+                        "double-tap can only be set on binds of a lone modifier key, \
+                         like Super_L",
+                    ));
+                }
+                BindActivation::DoubleTap
+            }
+            (None, None) => BindActivation::Press,
+        };
+
+        if let Some(node) = repeat_override_node {
+            if !repeat {
+                ctx.emit_error(DecodeError::unexpected(
+                    node,
+                    "property",
+                    "repeat-delay and repeat-rate cannot be used with repeat=false",
+                ));
+            } else if activation != BindActivation::Press {
+                ctx.emit_error(DecodeError::unexpected(
+                    node,
+                    "property",
+                    "repeat-delay and repeat-rate can only be set on binds triggered on press",
+                ));
+            }
+        }
+
         let mut children = node.children();
 
         // If the action is invalid but the key is fine, we still want to return something.
@@ -3573,7 +4741,10 @@ where
         let dummy = Self {
             key,
             action: Action::Spawn(vec![]),
+            activation,
             repeat: true,
+            repeat_delay: None,
+            repeat_rate: None,
             cooldown: None,
             allow_when_locked: false,
             allow_inhibiting: true,
@@ -3609,7 +4780,10 @@ where
                     Ok(Self {
                         key,
                         action,
+                        activation,
                         repeat,
+                        repeat_delay,
+                        repeat_rate,
                         cooldown,
                         allow_when_locked,
                         allow_inhibiting,
@@ -3707,6 +4881,12 @@ impl FromStr for Key {
             Trigger::TouchpadScrollLeft
         } else if key.eq_ignore_ascii_case("TouchpadScrollRight") {
             Trigger::TouchpadScrollRight
+        } else if key.eq_ignore_ascii_case("PenButton1") {
+            Trigger::PenButton1
+        } else if key.eq_ignore_ascii_case("PenButton2") {
+            Trigger::PenButton2
+        } else if key.eq_ignore_ascii_case("PenButton3") {
+            Trigger::PenButton3
         } else {
             let keysym = keysym_from_name(key, KEYSYM_CASE_INSENSITIVE);
             if keysym.raw() == KEY_NoSymbol {
@@ -3809,6 +4989,17 @@ mod tests {
             .unwrap()
     }
 
+    #[test]
+    fn config_error_detail() {
+        let text = "layout {\n    gaps 16\n}\n\nbinds {\n    Mod+T { no-such-action; }\n}\n";
+        let err = Config::parse("config.kdl", text).unwrap_err();
+        let detail = ConfigErrorDetail::from_parse_error(Path::new("config.kdl"), text, &err);
+
+        assert_eq!(detail.position.map(|(line, _)| line), Some(6));
+        assert_eq!(detail.excerpt.as_deref(), Some("Mod+T { no-such-action; }"));
+        assert!(!detail.message.is_empty());
+    }
+
     #[test]
     fn parse() {
         let parsed = do_parse(
@@ -3818,6 +5009,7 @@ mod tests {
                     repeat-delay 600
                     repeat-rate 25
                     track-layout "window"
+                    track-state "per-device"
                     xkb {
                         layout "us,ru"
                         options "grp:win_space_toggle"
@@ -3847,6 +5039,7 @@ mod tests {
                     scroll-button 273
                     middle-emulation
                     scroll-factor 0.2
+                    batch-relative-motion
                 }
 
                 trackpoint {
@@ -3871,6 +5064,7 @@ mod tests {
 
                 tablet {
                     map-to-output "eDP-1"
+                    map-to-focused-window
                     calibration-matrix 1.0 2.0 3.0 \
                                        4.0 5.0 6.0
                 }
@@ -3887,16 +5081,39 @@ mod tests {
 
                 mod-key "Mod5"
                 mod-key-nested "Super"
-            }
 
-            output "eDP-1" {
-                focus-at-startup
+                drag-threshold {
+                    distance 12
+                    delay-ms 50
+                }
+
+                dwell-click {
+                    delay-ms 800
+                }
+
+                tablet-pad {
+                    button 0 key="Ctrl+Z"
+                    ring 0 "clockwise" {
+                        focus-column-right
+                    }
+                }
+
+                tablet-pad app-id="^krita$" {
+                    strip 0 "up" key="plus"
+                }
+            }
+
+            output "eDP-1" {
+                focus-at-startup
                 scale 2
                 transform "flipped-90"
                 position x=10 y=20
                 mode "1920x1080@144"
                 variable-refresh-rate on-demand=true
+                dynamic-resolution min-scale=0.75
                 background-color "rgba(25, 25, 102, 1.0)"
+                wallpaper "~/Pictures/wallpaper.png" mode="fit"
+                color-depth 10
             }
 
             layout {
@@ -3905,6 +5122,12 @@ mod tests {
                     active-color 0 100 200 255
                     inactive-color 255 200 100 0
                     active-gradient from="rgba(10, 20, 30, 1.0)" to="#0080ffff" relative-to="workspace-view"
+
+                    glow {
+                        on
+                        radius 12
+                        intensity 0.8
+                    }
                 }
 
                 border {
@@ -3946,6 +5169,7 @@ mod tests {
                 }
 
                 center-focused-column "on-overflow"
+                focus-after-close "previous"
 
                 default-column-display "tabbed"
 
@@ -3956,6 +5180,7 @@ mod tests {
             }
 
             spawn-at-startup "alacritty" "-e" "fish"
+            spawn-at-startup "waybar" restart-on-failure=true max-restarts=3
 
             prefer-no-csd
 
@@ -3968,12 +5193,30 @@ mod tests {
 
             clipboard {
                 disable-primary
+
+                sync-primary {
+                    direction "to-clipboard"
+                    mime-type "text/plain;charset=utf-8"
+                }
             }
 
             hotkey-overlay {
                 skip-at-startup
             }
 
+            tutorial {
+                skip-at-startup
+            }
+
+            launch-placeholder {
+                on
+                timeout-ms 5000
+            }
+
+            overview {
+                thumbnail-quality "smooth"
+            }
+
             animations {
                 slowdown 2.0
 
@@ -3987,6 +5230,11 @@ mod tests {
                 }
 
                 window-open { off; }
+
+                floating-window-movement {
+                    duration-ms 200
+                    curve "ease-out-cubic"
+                }
             }
 
             gestures {
@@ -3994,6 +5242,18 @@ mod tests {
                     trigger-width 10
                     max-speed 50
                 }
+
+                touchscreen {
+                    edge-swipe-width 30
+                }
+
+                hot-corners {
+                    delay-ms 150
+                    top-right
+                    bottom {
+                        toggle-overview
+                    }
+                }
             }
 
             environment {
@@ -4002,6 +5262,7 @@ mod tests {
             }
 
             window-rule {
+                name "terminals"
                 match app-id=".*alacritty"
                 exclude title="~"
                 exclude is-active=true is-focused=false
@@ -4014,12 +5275,23 @@ mod tests {
                 default-window-height { fixed 500; }
                 default-column-display "tabbed"
                 default-floating-position x=100 y=-200 relative-to="bottom-left"
+                skip-animations true
 
                 focus-ring {
                     off
                     width 3
                 }
 
+                urgency-flash {
+                    pulses 2
+                    color "#f00"
+                }
+
+                max-render-fps 30
+                focus-after-close "parent"
+                swallow-children true
+                saturation 0.5
+
                 border {
                     on
                     width 8.5
@@ -4028,17 +5300,31 @@ mod tests {
                 tab-indicator {
                     active-color "#f00"
                 }
+
+                resize-content "crop"
+                keep-aspect-ratio true
             }
 
             layer-rule {
                 match namespace="^notifications$"
                 block-out-from "screencast"
+                focus-ring {
+                    on
+                    width 2
+                }
+            }
+
+            layer-rule {
+                match namespace="^wallpaper$"
+                place-within-backdrop true
+                backdrop-workspace "workspace-1"
             }
 
             binds {
                 Mod+Escape hotkey-overlay-title="Inhibit" { toggle-keyboard-shortcuts-inhibit; }
                 Mod+Shift+Escape allow-inhibiting=true { toggle-keyboard-shortcuts-inhibit; }
                 Mod+T allow-when-locked=true { spawn "alacritty"; }
+                Mod+B { focus-or-spawn "firefox" "firefox" "--new-window" all-outputs=true; }
                 Mod+Q hotkey-overlay-title=null { close-window; }
                 Mod+Shift+H { focus-monitor-left; }
                 Mod+Shift+O { focus-monitor "eDP-1"; }
@@ -4050,6 +5336,7 @@ mod tests {
                 Mod+Shift+1 { focus-workspace "workspace-1"; }
                 Mod+Shift+E allow-inhibiting=false { quit skip-confirmation=true; }
                 Mod+WheelScrollDown cooldown-ms=150 { focus-workspace-down; }
+                Mod+PenButton2 { toggle-overview; }
             }
 
             switch-events {
@@ -4057,15 +5344,41 @@ mod tests {
                 tablet-mode-off { spawn "bash" "-c" "gsettings set org.gnome.desktop.a11y.applications screen-keyboard-enabled false"; }
             }
 
+            hardware-keys {
+                volume-step 10
+                backlight "intel_backlight"
+            }
+
+            logind {
+                lid-switch "switch-off-internal-output"
+                lock-command "swaylock" "-f"
+            }
+
+            idle-fade {
+                timeout-ms 300000
+                lock
+            }
+
+            xwayland-satellite {
+                path "~/bin/xwayland-satellite"
+            }
+
             debug {
                 render-drm-device "/dev/dri/renderD129"
             }
 
             workspace "workspace-1" {
                 open-on-output "eDP-1"
+                wallpaper "/usr/share/backgrounds/default.png"
             }
             workspace "workspace-2"
             workspace "workspace-3"
+
+            seat "guest" {
+                device "USB Keyboard"
+                device "USB Mouse"
+                focus-ring-color "#f00"
+            }
             "##,
         );
 
@@ -4086,6 +5399,7 @@ mod tests {
                     repeat_delay: 600,
                     repeat_rate: 25,
                     track_layout: Window,
+                    track_state: PerDevice,
                     numlock: false,
                 },
                 touchpad: Touchpad {
@@ -4143,6 +5457,7 @@ mod tests {
                             0.2,
                         ),
                     ),
+                    batch_relative_motion: true,
                 },
                 trackpoint: Trackpoint {
                     off: true,
@@ -4191,6 +5506,7 @@ mod tests {
                     map_to_output: Some(
                         "eDP-1",
                     ),
+                    map_to_focused_window: true,
                     left_handed: false,
                 },
                 touch: Touch {
@@ -4217,6 +5533,81 @@ mod tests {
                 mod_key_nested: Some(
                     Super,
                 ),
+                virtual_input: Ask,
+                drag_threshold: DragThreshold {
+                    distance: FloatOrInt(
+                        12.0,
+                    ),
+                    delay_ms: 50,
+                },
+                dwell_click: Some(
+                    DwellClick {
+                        delay_ms: 800,
+                        distance: FloatOrInt(
+                            4.0,
+                        ),
+                    },
+                ),
+                tablet_pads: [
+                    TabletPad {
+                        app_id: None,
+                        buttons: [
+                            PadButtonBind {
+                                button: 0,
+                                key: Some(
+                                    Key {
+                                        trigger: Keysym(
+                                            XK_z,
+                                        ),
+                                        modifiers: Modifiers(
+                                            CTRL,
+                                        ),
+                                    },
+                                ),
+                                actions: [],
+                            },
+                        ],
+                        rings: [
+                            PadAxisBind {
+                                number: 0,
+                                direction: Clockwise,
+                                key: None,
+                                actions: [
+                                    FocusColumnRight,
+                                ],
+                            },
+                        ],
+                        strips: [],
+                    },
+                    TabletPad {
+                        app_id: Some(
+                            RegexEq(
+                                Regex(
+                                    "^krita$",
+                                ),
+                            ),
+                        ),
+                        buttons: [],
+                        rings: [],
+                        strips: [
+                            PadAxisBind {
+                                number: 0,
+                                direction: Up,
+                                key: Some(
+                                    Key {
+                                        trigger: Keysym(
+                                            XK_plus,
+                                        ),
+                                        modifiers: Modifiers(
+                                            0x0,
+                                        ),
+                                    },
+                                ),
+                                actions: [],
+                            },
+                        ],
+                    },
+                ],
             },
             outputs: Outputs(
                 [
@@ -4249,6 +5640,16 @@ mod tests {
                                 on_demand: true,
                             },
                         ),
+                        dynamic_resolution: Some(
+                            DynamicResolution {
+                                min_scale: FloatOrInt(
+                                    0.75,
+                                ),
+                                sharpness: FloatOrInt(
+                                    0.5,
+                                ),
+                            },
+                        ),
                         focus_at_startup: true,
                         background_color: Some(
                             Color {
@@ -4259,6 +5660,16 @@ mod tests {
                             },
                         ),
                         backdrop_color: None,
+                        wallpaper: Some(
+                            Wallpaper {
+                                path: "~/Pictures/wallpaper.png",
+                                mode: Fit,
+                            },
+                        ),
+                        color_depth: Some(
+                            Ten,
+                        ),
+                        hot_corners: None,
                     },
                 ],
             ),
@@ -4269,6 +5680,15 @@ mod tests {
                         "-e",
                         "fish",
                     ],
+                    restart_on_failure: false,
+                    max_restarts: 5,
+                },
+                SpawnAtStartup {
+                    command: [
+                        "waybar",
+                    ],
+                    restart_on_failure: true,
+                    max_restarts: 3,
                 },
             ],
             layout: Layout {
@@ -4319,6 +5739,15 @@ mod tests {
                     ),
                     inactive_gradient: None,
                     urgent_gradient: None,
+                    glow: FocusRingGlow {
+                        on: true,
+                        radius: FloatOrInt(
+                            12.0,
+                        ),
+                        intensity: FloatOrInt(
+                            0.8,
+                        ),
+                    },
                 },
                 border: Border {
                     off: false,
@@ -4473,7 +5902,7 @@ mod tests {
                 always_center_single_column: false,
                 empty_workspace_above_first: false,
                 default_column_display: Tabbed,
-                gaps: FloatOrInt(
+                gaps: Logical(
                     8.0,
                 ),
                 struts: Struts {
@@ -4496,6 +5925,7 @@ mod tests {
                     b: 0.25,
                     a: 1.0,
                 },
+                focus_after_close: Previous,
             },
             prefer_no_csd: true,
             cursor: Cursor {
@@ -4508,10 +5938,32 @@ mod tests {
             },
             clipboard: Clipboard {
                 disable_primary: true,
+                sync_primary: Some(
+                    SyncPrimary {
+                        direction: ToClipboard,
+                        min_interval_ms: 200,
+                        mime_types: [
+                            "text/plain;charset=utf-8",
+                        ],
+                    },
+                ),
             },
             hotkey_overlay: HotkeyOverlay {
                 skip_at_startup: true,
             },
+            tutorial: Tutorial {
+                skip_at_startup: true,
+            },
+            adjust_mode: AdjustMode {
+                move_step: FloatOrInt(
+                    10.0,
+                ),
+                resize_step: 10,
+            },
+            launch_placeholder: LaunchPlaceholder {
+                on: true,
+                timeout_ms: 5000,
+            },
             animations: Animations {
                 off: false,
                 slowdown: 2.0,
@@ -4611,6 +6063,28 @@ mod tests {
                         ),
                     },
                 ),
+                floating_window_movement: FloatingWindowMovementAnim(
+                    Animation {
+                        off: false,
+                        kind: Easing(
+                            EasingParams {
+                                duration_ms: 200,
+                                curve: EaseOutCubic,
+                            },
+                        ),
+                    },
+                ),
+                saturation_change: SaturationChangeAnim(
+                    Animation {
+                        off: false,
+                        kind: Easing(
+                            EasingParams {
+                                duration_ms: 300,
+                                curve: EaseOutCubic,
+                            },
+                        ),
+                    },
+                ),
             },
             gestures: Gestures {
                 dnd_edge_view_scroll: DndEdgeViewScroll {
@@ -4630,9 +6104,39 @@ mod tests {
                     max_speed: FloatOrInt(
                         1500.0,
                     ),
+                    dwell_ms: 500,
                 },
                 hot_corners: HotCorners {
                     off: false,
+                    size: FloatOrInt(
+                        1.0,
+                    ),
+                    delay_ms: 150,
+                    hint_color: None,
+                    top_left: None,
+                    top_right: Some(
+                        HotArea {
+                            actions: [],
+                        },
+                    ),
+                    bottom_left: None,
+                    bottom_right: None,
+                    top: None,
+                    bottom: Some(
+                        HotArea {
+                            actions: [
+                                ToggleOverview,
+                            ],
+                        },
+                    ),
+                    left: None,
+                    right: None,
+                },
+                touchscreen: TouchscreenGestures {
+                    off: false,
+                    edge_swipe_width: FloatOrInt(
+                        30.0,
+                    ),
                 },
             },
             overview: Overview {
@@ -4668,6 +6172,7 @@ mod tests {
                         a: 0.3137255,
                     },
                 },
+                thumbnail_quality: Smooth,
             },
             environment: Environment(
                 [
@@ -4685,6 +6190,10 @@ mod tests {
             ),
             window_rules: [
                 WindowRule {
+                    name: Some(
+                        "terminals",
+                    ),
+                    off: false,
                     matches: [
                         Match {
                             app_id: Some(
@@ -4702,6 +6211,12 @@ mod tests {
                             is_window_cast_target: None,
                             is_urgent: None,
                             at_startup: None,
+                            size: None,
+                            min_coverage: None,
+                            sandbox_engine: None,
+                            sandbox_app_id: None,
+                            executable: None,
+                            is_x11: None,
                         },
                     ],
                     excludes: [
@@ -4721,6 +6236,12 @@ mod tests {
                             is_window_cast_target: None,
                             is_urgent: None,
                             at_startup: None,
+                            size: None,
+                            min_coverage: None,
+                            sandbox_engine: None,
+                            sandbox_app_id: None,
+                            executable: None,
+                            is_x11: None,
                         },
                         Match {
                             app_id: None,
@@ -4736,6 +6257,12 @@ mod tests {
                             is_window_cast_target: None,
                             is_urgent: None,
                             at_startup: None,
+                            size: None,
+                            min_coverage: None,
+                            sandbox_engine: None,
+                            sandbox_app_id: None,
+                            executable: None,
+                            is_x11: None,
                         },
                     ],
                     default_column_width: None,
@@ -4764,10 +6291,14 @@ mod tests {
                     open_focused: Some(
                         true,
                     ),
+                    skip_open_animation: None,
                     min_width: None,
                     min_height: None,
                     max_width: None,
                     max_height: None,
+                    keep_aspect_ratio: Some(
+                        true,
+                    ),
                     focus_ring: BorderRule {
                         off: true,
                         on: false,
@@ -4827,6 +6358,9 @@ mod tests {
                     opacity: None,
                     geometry_corner_radius: None,
                     clip_to_geometry: None,
+                    resize_content: Some(
+                        Crop,
+                    ),
                     baba_is_float: None,
                     block_out_from: None,
                     variable_refresh_rate: None,
@@ -4835,10 +6369,10 @@ mod tests {
                     ),
                     default_floating_position: Some(
                         FloatingPosition {
-                            x: FloatOrInt(
+                            x: Logical(
                                 100.0,
                             ),
-                            y: FloatOrInt(
+                            y: Logical(
                                 -200.0,
                             ),
                             relative_to: BottomLeft,
@@ -4846,6 +6380,39 @@ mod tests {
                     ),
                     scroll_factor: None,
                     tiled_state: None,
+                    skip_animations: Some(
+                        true,
+                    ),
+                    urgency_flash: Some(
+                        UrgencyFlash {
+                            off: false,
+                            pulses: 2,
+                            pulse_duration_ms: 600,
+                            width: FloatOrInt(
+                                16.0,
+                            ),
+                            color: Color {
+                                r: 1.0,
+                                g: 0.0,
+                                b: 0.0,
+                                a: 1.0,
+                            },
+                        },
+                    ),
+                    max_render_fps: Some(
+                        FloatOrInt(
+                            30.0,
+                        ),
+                    ),
+                    focus_after_close: Some(
+                        Parent,
+                    ),
+                    swallow_children: Some(
+                        true,
+                    ),
+                    saturation: Some(
+                        0.5,
+                    ),
                 },
             ],
             layer_rules: [
@@ -4877,8 +6444,70 @@ mod tests {
                         color: None,
                         inactive_color: None,
                     },
+                    focus_ring: BorderRule {
+                        off: false,
+                        on: true,
+                        width: Some(
+                            FloatOrInt(
+                                2.0,
+                            ),
+                        ),
+                        active_color: None,
+                        inactive_color: None,
+                        urgent_color: None,
+                        active_gradient: None,
+                        inactive_gradient: None,
+                        urgent_gradient: None,
+                    },
                     geometry_corner_radius: None,
                     place_within_backdrop: None,
+                    backdrop_workspace: None,
+                    baba_is_float: None,
+                },
+                LayerRule {
+                    matches: [
+                        Match {
+                            namespace: Some(
+                                RegexEq(
+                                    Regex(
+                                        "^wallpaper$",
+                                    ),
+                                ),
+                            ),
+                            at_startup: None,
+                        },
+                    ],
+                    excludes: [],
+                    opacity: None,
+                    block_out_from: None,
+                    shadow: ShadowRule {
+                        off: false,
+                        on: false,
+                        offset: None,
+                        softness: None,
+                        spread: None,
+                        draw_behind_window: None,
+                        color: None,
+                        inactive_color: None,
+                    },
+                    focus_ring: BorderRule {
+                        off: false,
+                        on: false,
+                        width: None,
+                        active_color: None,
+                        inactive_color: None,
+                        urgent_color: None,
+                        active_gradient: None,
+                        inactive_gradient: None,
+                        urgent_gradient: None,
+                    },
+                    geometry_corner_radius: None,
+                    place_within_backdrop: Some(
+                        true,
+                    ),
+                    backdrop_workspace: Some(
+                        "workspace-1",
+                    ),
                     baba_is_float: None,
                 },
             ],
@@ -4894,7 +6523,10 @@ mod tests {
                             ),
                         },
                         action: ToggleKeyboardShortcutsInhibit,
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: None,
                         allow_when_locked: false,
                         allow_inhibiting: false,
@@ -4914,7 +6546,10 @@ mod tests {
                             ),
                         },
                         action: ToggleKeyboardShortcutsInhibit,
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: None,
                         allow_when_locked: false,
                         allow_inhibiting: false,
@@ -4934,12 +6569,41 @@ mod tests {
                                 "alacritty",
                             ],
                         ),
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: None,
                         allow_when_locked: true,
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
                     },
+                    Bind {
+                        key: Key {
+                            trigger: Keysym(
+                                XK_b,
+                            ),
+                            modifiers: Modifiers(
+                                COMPOSITOR,
+                            ),
+                        },
+                        action: FocusOrSpawn(
+                            "firefox",
+                            [
+                                "firefox",
+                                "--new-window",
+                            ],
+                            true,
+                        ),
+                        activation: Press,
+                        repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
+                        cooldown: None,
+                        allow_when_locked: false,
+                        allow_inhibiting: true,
+                        hotkey_overlay_title: None,
+                    },
                     Bind {
                         key: Key {
                             trigger: Keysym(
@@ -4950,7 +6614,10 @@ mod tests {
                             ),
                         },
                         action: CloseWindow,
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: None,
                         allow_when_locked: false,
                         allow_inhibiting: true,
@@ -4968,7 +6635,10 @@ mod tests {
                             ),
                         },
                         action: FocusMonitorLeft,
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: None,
                         allow_when_locked: false,
                         allow_inhibiting: true,
@@ -4986,7 +6656,10 @@ mod tests {
                         action: FocusMonitor(
                             "eDP-1",
                         ),
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: None,
                         allow_when_locked: false,
                         allow_inhibiting: true,
@@ -5002,7 +6675,10 @@ mod tests {
                             ),
                         },
                         action: MoveWindowToMonitorRight,
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: None,
                         allow_when_locked: false,
                         allow_inhibiting: true,
@@ -5020,7 +6696,10 @@ mod tests {
                         action: MoveWindowToMonitor(
                             "eDP-1",
                         ),
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: None,
                         allow_when_locked: false,
                         allow_inhibiting: true,
@@ -5038,7 +6717,10 @@ mod tests {
                         action: MoveColumnToMonitor(
                             "DP-1",
                         ),
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: None,
                         allow_when_locked: false,
                         allow_inhibiting: true,
@@ -5054,7 +6736,10 @@ mod tests {
                             ),
                         },
                         action: ConsumeWindowIntoColumn,
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: None,
                         allow_when_locked: false,
                         allow_inhibiting: true,
@@ -5074,7 +6759,10 @@ mod tests {
                                 1,
                             ),
                         ),
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: None,
                         allow_when_locked: false,
                         allow_inhibiting: true,
@@ -5094,7 +6782,10 @@ mod tests {
                                 "workspace-1",
                             ),
                         ),
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: None,
                         allow_when_locked: false,
                         allow_inhibiting: true,
@@ -5112,7 +6803,10 @@ mod tests {
                         action: Quit(
                             true,
                         ),
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: None,
                         allow_when_locked: false,
                         allow_inhibiting: false,
@@ -5126,7 +6820,10 @@ mod tests {
                             ),
                         },
                         action: FocusWorkspaceDown,
+                        activation: Press,
                         repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
                         cooldown: Some(
                             150ms,
                         ),
@@ -5134,6 +6831,23 @@ mod tests {
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
                     },
+                    Bind {
+                        key: Key {
+                            trigger: PenButton2,
+                            modifiers: Modifiers(
+                                COMPOSITOR,
+                            ),
+                        },
+                        action: ToggleOverview,
+                        activation: Press,
+                        repeat: true,
+                        repeat_delay: None,
+                        repeat_rate: None,
+                        cooldown: None,
+                        allow_when_locked: false,
+                        allow_inhibiting: true,
+                        hotkey_overlay_title: None,
+                    },
                 ],
             ),
             switch_events: SwitchBinds {
@@ -5158,6 +6872,35 @@ mod tests {
                     },
                 ),
             },
+            hardware_keys: HardwareKeys {
+                off: false,
+                volume_step: 10,
+                brightness_step: 5,
+                backlight: Some(
+                    "intel_backlight",
+                ),
+            },
+            logind: Logind {
+                lid_switch: Some(
+                    SwitchOffInternalOutput,
+                ),
+                lock_command: [
+                    "swaylock",
+                    "-f",
+                ],
+            },
+            idle_fade: IdleFade {
+                timeout_ms: Some(
+                    300000,
+                ),
+                fade_duration_ms: 5000,
+                lock: true,
+            },
+            xwayland_satellite: XwaylandSatellite {
+                off: false,
+                path: "~/bin/xwayland-satellite",
+                max_restarts: 5,
+            },
             debug: DebugConfig {
                 preview_render: None,
                 dbus_interfaces_in_non_session_instances: false,
@@ -5178,6 +6921,7 @@ mod tests {
                 disable_monitor_names: false,
                 strict_new_window_focus_policy: false,
                 honor_xdg_activation_with_invalid_serial: false,
+                ipc_input_injection: false,
             },
             workspaces: [
                 Workspace {
@@ -5187,18 +6931,48 @@ mod tests {
                     open_on_output: Some(
                         "eDP-1",
                     ),
+                    wallpaper: Some(
+                        Wallpaper {
+                            path: "/usr/share/backgrounds/default.png",
+                            mode: Fill,
+                        },
+                    ),
+                    reserved_slots: [],
                 },
                 Workspace {
                     name: WorkspaceName(
                         "workspace-2",
                     ),
                     open_on_output: None,
+                    wallpaper: None,
+                    reserved_slots: [],
                 },
                 Workspace {
                     name: WorkspaceName(
                         "workspace-3",
                     ),
                     open_on_output: None,
+                    wallpaper: None,
+                    reserved_slots: [],
+                },
+            ],
+            seats: [
+                Seat {
+                    name: "guest",
+                    devices: [
+                        SeatDevice {
+                            name: "USB Keyboard",
+                        },
+                        SeatDevice {
+                            name: "USB Mouse",
+                        },
+                    ],
+                    focus_ring_color: Color {
+                        r: 1.0,
+                        g: 0.0,
+                        b: 0.0,
+                        a: 1.0,
+                    },
                 },
             ],
         }
@@ -5289,6 +7063,26 @@ mod tests {
         assert!("10% ".parse::<PositionChange>().is_err());
     }
 
+    #[test]
+    fn parse_length() {
+        type L = Length<-100, 100>;
+
+        assert_eq!("16".parse::<L>().unwrap(), Length::Logical(16.));
+        assert_eq!("16.5px".parse::<L>().unwrap(), Length::Logical(16.5));
+        assert_eq!("2mm".parse::<L>().unwrap(), Length::Millimeters(2.));
+        assert_eq!("-50%".parse::<L>().unwrap(), Length::Percent(-50.));
+
+        assert!("".parse::<L>().is_err());
+        assert!("mm".parse::<L>().is_err());
+        assert!("2 mm".parse::<L>().is_err());
+        assert!("2in".parse::<L>().is_err());
+        assert!("200%".parse::<L>().is_err());
+
+        assert_eq!(Length::<0, 100>::Millimeters(2.).to_logical(4., 1000.), 8.);
+        assert_eq!(Length::<0, 100>::Percent(10.).to_logical(4., 1000.), 100.);
+        assert_eq!("2mm".parse::<L>().unwrap().to_string(), "2mm");
+    }
+
     #[test]
     fn parse_gradient_interpolation() {
         assert_eq!(
@@ -5404,6 +7198,232 @@ mod tests {
         assert_eq!(config.input.keyboard.repeat_rate, 25);
     }
 
+    #[test]
+    fn parse_bind_activation() {
+        let config = do_parse(
+            r#"
+            binds {
+                Mod+T { spawn "alacritty"; }
+                Mod+Q on-release=true { close-window; }
+                Super_L double-tap=true { toggle-overview; }
+                Super_L { spawn "fuzzel"; }
+            }
+            "#,
+        );
+        let activations: Vec<_> = config.binds.0.iter().map(|b| b.activation).collect();
+        assert_eq!(
+            activations,
+            [
+                BindActivation::Press,
+                BindActivation::Release,
+                BindActivation::DoubleTap,
+                BindActivation::Press,
+            ]
+        );
+
+        for invalid in [
+            "Mod+Q on-release=true double-tap=true { close-window; }",
+            "Mod+MouseLeft on-release=true { close-window; }",
+            "T double-tap=true { close-window; }",
+            "Ctrl+Super_L double-tap=true { close-window; }",
+        ] {
+            let text = format!("binds {{ {invalid} }}");
+            assert!(Config::parse("test.kdl", &text).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parse_bind_repeat_override() {
+        let config = do_parse(
+            r#"
+            binds {
+                Mod+Comma repeat-delay=200 repeat-rate=50 { consume-window-into-column; }
+                Mod+Period repeat-rate=10 { expel-window-from-column; }
+                Mod+T { spawn "alacritty"; }
+            }
+            "#,
+        );
+        let repeat: Vec<_> = config
+            .binds
+            .0
+            .iter()
+            .map(|b| (b.repeat_delay, b.repeat_rate))
+            .collect();
+        assert_eq!(
+            repeat,
+            [(Some(200), Some(50)), (None, Some(10)), (None, None)]
+        );
+
+        for invalid in [
+            "Mod+Q repeat=false repeat-delay=200 { close-window; }",
+            "Mod+Q on-release=true repeat-rate=50 { close-window; }",
+            "Super_L double-tap=true repeat-delay=200 { toggle-overview; }",
+        ] {
+            let text = format!("binds {{ {invalid} }}");
+            assert!(Config::parse("test.kdl", &text).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parse_floating_position_units() {
+        let config = do_parse(
+            r#"
+            window-rule {
+                default-floating-position x="25%" y="-20mm" relative-to="bottom-left"
+            }
+            "#,
+        );
+        let pos = config.window_rules[0].default_floating_position.unwrap();
+        assert_eq!(pos.x, Length::Percent(25.));
+        assert_eq!(pos.y, Length::Millimeters(-20.));
+    }
+
+    #[test]
+    fn parse_size_matches() {
+        let config = do_parse(
+            r#"
+            window-rule {
+                match size="large"
+                exclude size="small" min-coverage=90
+            }
+            "#,
+        );
+        let rule = &config.window_rules[0];
+        assert_eq!(rule.matches[0].size, Some(WindowSize::Large));
+        assert_eq!(rule.matches[0].min_coverage, None);
+        assert_eq!(rule.excludes[0].size, Some(WindowSize::Small));
+        assert_eq!(rule.excludes[0].min_coverage, Some(FloatOrInt(90.)));
+    }
+
+    #[test]
+    fn parse_client_matches() {
+        let config = do_parse(
+            r#"
+            window-rule {
+                match sandbox-engine="^org\\.flatpak$" sandbox-app-id="^org\\.gimp\\.GIMP$"
+                exclude executable="/firefox$"
+            }
+            "#,
+        );
+        let rule = &config.window_rules[0];
+        let regex = |m: &Option<RegexEq>| m.as_ref().map(|m| m.0.as_str().to_owned());
+        assert_eq!(
+            regex(&rule.matches[0].sandbox_engine).as_deref(),
+            Some("^org\\.flatpak$")
+        );
+        assert_eq!(
+            regex(&rule.matches[0].sandbox_app_id).as_deref(),
+            Some("^org\\.gimp\\.GIMP$")
+        );
+        assert_eq!(regex(&rule.matches[0].executable), None);
+        assert_eq!(
+            regex(&rule.excludes[0].executable).as_deref(),
+            Some("/firefox$")
+        );
+    }
+
+    #[test]
+    fn parse_reserved_slots() {
+        let config = do_parse(
+            r#"
+            workspace "workspace-1" {
+                reserved-slot "org.keepassxc.KeePassXC"
+                reserved-slot "firefox" {
+                    position "first"
+                }
+            }
+            workspace "workspace-2"
+            "#,
+        );
+        assert_eq!(
+            config.workspaces[0].reserved_slots,
+            [
+                ReservedSlot {
+                    app_id: String::from("org.keepassxc.KeePassXC"),
+                    position: ReservedSlotPosition::Last,
+                },
+                ReservedSlot {
+                    app_id: String::from("firefox"),
+                    position: ReservedSlotPosition::First,
+                },
+            ]
+        );
+        assert_eq!(config.workspaces[1].reserved_slots, []);
+    }
+
+    #[test]
+    fn parse_is_x11_match() {
+        let config = do_parse(
+            r#"
+            window-rule {
+                match app-id="steam" is-x11=true
+            }
+            "#,
+        );
+        let m = &config.window_rules[0].matches[0];
+        assert_eq!(m.is_x11, Some(true));
+        assert_eq!(m.app_id.as_ref().unwrap().0.as_str(), "steam");
+    }
+
+    #[test]
+    fn sync_primary_mime_types() {
+        let config = do_parse("clipboard { sync-primary; }");
+        let sync = config.clipboard.sync_primary.unwrap();
+        assert_eq!(sync.direction, SelectionSyncDirection::Both);
+        assert_eq!(sync.min_interval_ms, 200);
+
+        // Without a list, only text is mirrored.
+        assert!(sync.allows_mime_type("text/plain;charset=utf-8"));
+        assert!(sync.allows_mime_type("UTF8_STRING"));
+        assert!(!sync.allows_mime_type("image/png"));
+
+        let config = do_parse(
+            r#"
+            clipboard {
+                sync-primary {
+                    mime-type "image/png"
+                }
+            }
+            "#,
+        );
+        let sync = config.clipboard.sync_primary.unwrap();
+        assert!(sync.allows_mime_type("image/png"));
+        assert!(!sync.allows_mime_type("text/plain"));
+    }
+
+    #[test]
+    fn parse_mode_rejects_invalid_values() {
+        for invalid in [
+            "0x1080",
+            "1920x0",
+            "1920x1080@0",
+            "1920x1080@-60",
+            "1920x1080@inf",
+        ] {
+            assert!(invalid.parse::<ConfiguredMode>().is_err(), "{invalid}");
+
+            let text = format!("output \"DP-1\" {{ mode \"{invalid}\"; }}");
+            assert!(Config::parse("test.kdl", &text).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parse_color_depth() {
+        let config = do_parse(
+            r#"
+            output "DP-1" {
+                color-depth 8
+            }
+            "#,
+        );
+        assert_eq!(config.outputs.0[0].color_depth, Some(ColorDepth::Eight));
+
+        for invalid in ["6", "12", "10.0", "\"10\""] {
+            let text = format!("output \"DP-1\" {{ color-depth {invalid}; }}");
+            assert!(Config::parse("test.kdl", &text).is_err(), "{invalid}");
+        }
+    }
+
     fn make_output_name(
         connector: &str,
         make: Option<&str>,
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use crate::{Config, FocusFollowsMouse, Length};
+
+/// Config paths that can be changed at runtime.
+pub const OVERRIDABLE_PATHS: &[&str] = &[
+    "layout.gaps",
+    "animations.off",
+    "animations.slowdown",
+    "input.focus-follows-mouse",
+    "cursor.xcursor-size",
+];
+
+/// Config values changed at runtime, merged over the values from the config file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ConfigOverrides {
+    gaps: Option<Length<0, 65535>>,
+    animations_off: Option<bool>,
+    animations_slowdown: Option<f64>,
+    focus_follows_mouse: Option<bool>,
+    xcursor_size: Option<u8>,
+    /// Whether window rules are enabled, by rule name.
+    window_rules: HashMap<String, bool>,
+}
+
+impl ConfigOverrides {
+    /// Parses and stores the value for a config path.
+    pub fn set(&mut self, path: &str, value: &str) -> Result<(), String> {
+        match path {
+            "layout.gaps" => {
+                let gaps = value
+                    .parse()
+                    .map_err(|err| format!("invalid gaps: {err}"))?;
+                self.gaps = Some(gaps);
+            }
+            "animations.off" => self.animations_off = Some(parse_bool(value)?),
+            "animations.slowdown" => {
+                let slowdown = parse_f64(value)?;
+                if slowdown <= 0. {
+                    return Err(String::from("slowdown must be greater than 0"));
+                }
+                self.animations_slowdown = Some(slowdown);
+            }
+            "input.focus-follows-mouse" => self.focus_follows_mouse = Some(parse_bool(value)?),
+            "cursor.xcursor-size" => {
+                let size = value
+                    .parse()
+                    .map_err(|_| format!("invalid cursor size: {value:?}"))?;
+                self.xcursor_size = Some(size);
+            }
+            _ => return Err(unsupported_path(path)),
+        }
+
+        Ok(())
+    }
+
+    /// Enables or disables the window rules with this name.
+    pub fn set_window_rule_enabled(&mut self, name: &str, enabled: bool) {
+        self.window_rules.insert(String::from(name), enabled);
+    }
+
+    /// Applies the stored values over the config.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(gaps) = self.gaps {
+            config.layout.gaps = gaps;
+        }
+        if let Some(off) = self.animations_off {
+            config.animations.off = off;
+        }
+        if let Some(slowdown) = self.animations_slowdown {
+            config.animations.slowdown = slowdown;
+        }
+        if let Some(on) = self.focus_follows_mouse {
+            let ffm = &mut config.input.focus_follows_mouse;
+            if !on {
+                *ffm = None;
+            } else if ffm.is_none() {
+                *ffm = Some(FocusFollowsMouse {
+                    max_scroll_amount: None,
+                });
+            }
+        }
+        if let Some(size) = self.xcursor_size {
+            config.cursor.xcursor_size = size;
+        }
+        for rule in &mut config.window_rules {
+            let Some(name) = &rule.name else { continue };
+            if let Some(enabled) = self.window_rules.get(name) {
+                rule.off = !enabled;
+            }
+        }
+    }
+}
+
+/// Returns the current value of a config path that can be changed at runtime.
+pub fn get_value(config: &Config, path: &str) -> Result<String, String> {
+    let value = match path {
+        "layout.gaps" => config.layout.gaps.to_string(),
+        "animations.off" => config.animations.off.to_string(),
+        "animations.slowdown" => config.animations.slowdown.to_string(),
+        "input.focus-follows-mouse" => config.input.focus_follows_mouse.is_some().to_string(),
+        "cursor.xcursor-size" => config.cursor.xcursor_size.to_string(),
+        _ => return Err(unsupported_path(path)),
+    };
+    Ok(value)
+}
+
+fn unsupported_path(path: &str) -> String {
+    format!(
+        "config path {path:?} can't be changed at runtime, supported paths: {}",
+        OVERRIDABLE_PATHS.join(", ")
+    )
+}
+
+fn parse_f64(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| format!("invalid number: {value:?}"))
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "on" => Ok(true),
+        "false" | "off" => Ok(false),
+        _ => Err(format!(
+            "invalid boolean: {value:?}, expected true or false"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WindowRule;
+
+    #[test]
+    fn overrides_apply_over_config() {
+        let mut overrides = ConfigOverrides::default();
+        overrides.set("layout.gaps", "8").unwrap();
+        overrides.set("input.focus-follows-mouse", "true").unwrap();
+        overrides.set("cursor.xcursor-size", "48").unwrap();
+
+        let mut config = Config::default();
+        overrides.apply(&mut config);
+
+        assert_eq!(get_value(&config, "layout.gaps").unwrap(), "8");
+        assert_eq!(
+            get_value(&config, "input.focus-follows-mouse").unwrap(),
+            "true"
+        );
+        assert_eq!(get_value(&config, "cursor.xcursor-size").unwrap(), "48");
+        assert_eq!(get_value(&config, "animations.off").unwrap(), "false");
+    }
+
+    #[test]
+    fn overrides_toggle_named_window_rules() {
+        let mut config = Config::default();
+        config.window_rules = vec![
+            WindowRule {
+                name: Some(String::from("presentation")),
+                off: true,
+                ..Default::default()
+            },
+            WindowRule::default(),
+        ];
+
+        let mut overrides = ConfigOverrides::default();
+        overrides.set_window_rule_enabled("presentation", true);
+        overrides.apply(&mut config);
+        assert!(!config.window_rules[0].off);
+        assert!(!config.window_rules[1].off);
+
+        overrides.set_window_rule_enabled("presentation", false);
+        overrides.apply(&mut config);
+        assert!(config.window_rules[0].off);
+    }
+
+    #[test]
+    fn overrides_reject_invalid_values() {
+        let mut overrides = ConfigOverrides::default();
+        assert!(overrides.set("layout.gaps", "-1").is_err());
+        assert!(overrides.set("animations.slowdown", "0").is_err());
+        assert!(overrides.set("animations.off", "maybe").is_err());
+        assert!(overrides.set("cursor.xcursor-size", "300").is_err());
+        assert!(overrides.set("layout.focus-ring.width", "4").is_err());
+        assert_eq!(overrides, ConfigOverrides::default());
+    }
+}
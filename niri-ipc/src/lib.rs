@@ -112,6 +112,84 @@ pub enum Request {
     ReturnError,
     /// Request information about the overview.
     OverviewState,
+    /// Request an analysis of the configured binds for conflicts.
+    BindConflicts,
+    /// Request information about virtual input devices created by clients.
+    VirtualDevices,
+    /// Request compositor status, such as frame timing statistics.
+    Status,
+    /// Request which window rules match a window, for debugging rules.
+    MatchWindowRules {
+        /// Id of the window.
+        id: u64,
+    },
+    /// Request the status of supervised `spawn-at-startup` services.
+    Services,
+    /// Request the windows in the order they were most recently focused.
+    FocusHistory,
+    /// Request whether each output can show the cursor on its hardware cursor plane.
+    CursorPlanes,
+    /// Change a config value at runtime.
+    ///
+    /// Only a few config paths can be changed this way, such as `layout.gaps`. The value stays in
+    /// effect over the config file until niri exits.
+    SetConfigValue {
+        /// Path of the config value, for example `layout.gaps`.
+        path: String,
+        /// New value.
+        value: String,
+    },
+    /// Request the current value of a config path that can be changed at runtime.
+    GetConfigValue {
+        /// Path of the config value, for example `layout.gaps`.
+        path: String,
+    },
+    /// Save a thumbnail of a window as a PNG file.
+    ///
+    /// Thumbnails come from a cache shared with the rest of niri, so they may lag behind the
+    /// window contents by a fraction of a second. Windows with a `block-out-from` rule are
+    /// blocked out.
+    WindowThumbnail {
+        /// Id of the window.
+        id: u64,
+        /// Absolute path of the PNG file to write.
+        path: String,
+    },
+    /// Inject a synthetic input event, for automated testing of clients.
+    ///
+    /// Only available in the headless and winit backends, and only when the
+    /// `debug { ipc-input-injection; }` config flag is set.
+    InjectInput(InjectedInput),
+    /// Perform several actions at once.
+    ///
+    /// The actions run in order within a single event loop iteration, so the intermediate layout
+    /// states are neither shown on screen nor sent over the event stream. Window and workspace
+    /// ids are checked before running anything: if one of them doesn't exist, no action runs.
+    Actions(Vec<Action>),
+    /// Save the layout to a file.
+    ///
+    /// The snapshot records the workspaces with their columns and floating windows, and the app
+    /// ids and sizes of the windows in them.
+    SaveLayoutSnapshot {
+        /// Absolute path of the file to write.
+        ///
+        /// If `None`, saves to `$XDG_STATE_HOME/niri/layout-snapshot.json`, which niri restores
+        /// once, on the next startup. After restoring, niri renames it to
+        /// `layout-snapshot.restored.json`.
+        path: Option<String>,
+    },
+    /// Restore a layout snapshot.
+    ///
+    /// Windows that are already open stay where they are. For the next minute, every opening
+    /// window takes the place of a saved window with the same app id.
+    RestoreLayoutSnapshot {
+        /// Absolute path of the file to read.
+        ///
+        /// If `None`, reads `$XDG_STATE_HOME/niri/layout-snapshot.json`.
+        path: Option<String>,
+    },
+    /// Request a summary of the config in effect, for comparing it with another config.
+    ConfigSummary,
 }
 
 /// Reply from niri to client.
@@ -152,6 +230,26 @@ pub enum Response {
     OutputConfigChanged(OutputConfigChanged),
     /// Information about the overview.
     OverviewState(Overview),
+    /// Conflicts found in the configured binds.
+    BindConflicts(Vec<BindConflict>),
+    /// Information about virtual input devices.
+    VirtualDevices(Vec<VirtualDevice>),
+    /// Compositor status.
+    Status(Status),
+    /// Window rule matching results for a window.
+    WindowRuleMatches(WindowRuleMatches),
+    /// Status of supervised services.
+    Services(Vec<Service>),
+    /// Windows from the most to the least recently focused.
+    FocusHistory(Vec<Window>),
+    /// Hardware cursor plane usage per output.
+    ///
+    /// Map from output name to the cursor plane decision made for its last frame.
+    CursorPlanes(HashMap<String, CursorPlane>),
+    /// Current value of a config path.
+    ConfigValue(String),
+    /// Summary of the config in effect.
+    ConfigSummary(ConfigSummary),
 }
 
 /// Overview information.
@@ -162,6 +260,197 @@ pub struct Overview {
     pub is_open: bool,
 }
 
+/// A problem with a configured bind that can make it never trigger.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct BindConflict {
+    /// The affected bind, formatted like in the config (e.g. `Mod+Shift+T`).
+    pub key: String,
+    /// Kind of the conflict.
+    pub kind: BindConflictKind,
+}
+
+/// Kind of a bind conflict.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum BindConflictKind {
+    /// An earlier bind resolves to the same key combination and always takes precedence.
+    ///
+    /// This happens when the same combination is written both with `Mod` and with the modifier
+    /// that `Mod` currently maps to, e.g. `Mod+T` and `Super+T`.
+    ShadowedBy {
+        /// The earlier bind.
+        key: String,
+    },
+    /// A hardcoded compositor bind, such as VT switching, intercepts this key first.
+    ShadowedByHardcoded,
+    /// The bind replaces the built-in `Mod`+drag interactive move.
+    OverridesInteractiveMove,
+    /// The bind replaces the built-in `Mod`+drag interactive resize.
+    OverridesInteractiveResize,
+    /// The bind replaces the built-in `Mod`+drag view scrolling.
+    OverridesViewScroll,
+}
+
+/// A virtual input device created by a client.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct VirtualDevice {
+    /// Kind of the device.
+    pub kind: VirtualDeviceKind,
+    /// Process ID of the client that created this device, if known.
+    pub pid: Option<i32>,
+    /// Path to the executable of the client that created this device, if known.
+    pub executable: Option<String>,
+    /// Whether input from this device currently reaches the compositor.
+    ///
+    /// Input is dropped while the user hasn't answered the permission prompt, or has denied it.
+    pub allowed: bool,
+}
+
+/// Kind of a virtual input device.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum VirtualDeviceKind {
+    /// A `zwlr_virtual_pointer_v1` pointer.
+    Pointer,
+    /// A `zwp_virtual_keyboard_v1` keyboard.
+    Keyboard,
+}
+
+/// Compositor status.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Status {
+    /// Frame timing statistics per output.
+    ///
+    /// Map from output name to its statistics.
+    pub frame_stats: HashMap<String, FrameStats>,
+}
+
+/// How the cursor is shown on an output.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum CursorPlane {
+    /// The cursor may be put on the hardware cursor plane.
+    Hardware,
+    /// The cursor is composited into the frame.
+    Composited(CursorPlaneFallback),
+}
+
+/// Reason why the cursor is composited rather than put on the hardware cursor plane.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum CursorPlaneFallback {
+    /// The backend has no hardware cursor plane.
+    Unsupported,
+    /// The cursor plane is disabled in the debug config.
+    Disabled,
+    /// The output has a fractional scale, which the cursor plane cannot scale to.
+    FractionalScale,
+    /// The cursor is larger than the cursor plane.
+    Oversized,
+    /// The cursor surface has a buffer transform, which the cursor plane cannot apply.
+    SurfaceTransform,
+    /// The cursor surface buffer scale differs from the output scale.
+    SurfaceScale,
+}
+
+/// `spawn-at-startup` command that niri restarts when it fails.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Service {
+    /// Command line of the service.
+    pub command: Vec<String>,
+    /// Current state of the service.
+    pub state: ServiceState,
+    /// Process id of the running service process.
+    pub pid: Option<i32>,
+    /// How many times the service was restarted after a failure.
+    pub restarts: u32,
+}
+
+/// State of a supervised service.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum ServiceState {
+    /// The service process is running.
+    Running,
+    /// The service process failed and will be restarted after a delay.
+    Restarting,
+    /// The service process exited successfully and won't be restarted.
+    Exited,
+    /// The service failed too many times in a row and won't be restarted.
+    Failed,
+}
+
+/// Window rule matching results for a window.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct WindowRuleMatches {
+    /// Results for every `window-rule` in the config, in config order.
+    pub rules: Vec<WindowRuleMatch>,
+    /// Final resolved rules for the window, in a human-readable debug format.
+    pub resolved: String,
+}
+
+/// Summary of a config, for comparing two configs.
+///
+/// Values are in a human-readable debug format.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct ConfigSummary {
+    /// Actions of the binds.
+    ///
+    /// Map from the bind key, formatted like in the config (e.g. `Mod+Shift+T`), to its action.
+    pub binds: HashMap<String, String>,
+    /// Settings of the `output` sections.
+    ///
+    /// Map from the output name, as written in the config, to its settings.
+    pub outputs: HashMap<String, String>,
+    /// The `window-rule`s, in config order.
+    pub window_rules: Vec<String>,
+}
+
+/// Matching result of a single `window-rule`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct WindowRuleMatch {
+    /// Index of the rule among the `window-rule`s in the config, starting from 0.
+    pub index: usize,
+    /// Indices of the `match` directives of the rule that matched the window.
+    pub matched: Vec<usize>,
+    /// Indices of the `exclude` directives of the rule that matched the window.
+    pub excluded: Vec<usize>,
+    /// Whether the rule was applied to the window.
+    ///
+    /// A rule applies when it has no `match` directives or at least one of them matched, and none
+    /// of its `exclude` directives matched.
+    pub applied: bool,
+}
+
+/// Frame timing statistics of an output.
+///
+/// Prediction error is the difference between the actual presentation time reported by the
+/// display and the time niri predicted when scheduling the frame. Positive values mean the frame
+/// was presented later than predicted. Error statistics cover only the most recent frames.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct FrameStats {
+    /// Number of frames with a known presentation time.
+    pub presented_frames: u64,
+    /// Number of frames that missed the VBlank they were scheduled for.
+    pub missed_deadlines: u64,
+    /// Number of VBlanks skipped because of missed deadlines.
+    pub dropped_frames: u64,
+    /// Mean prediction error, in microseconds.
+    pub mean_prediction_error_us: f64,
+    /// Standard deviation of the prediction error, in microseconds.
+    pub prediction_jitter_us: f64,
+    /// Largest absolute prediction error, in microseconds.
+    pub max_prediction_error_us: f64,
+}
+
 /// Color picked from the screen.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
@@ -189,12 +478,34 @@ pub enum Action {
     PowerOffMonitors {},
     /// Power on all monitors via DPMS.
     PowerOnMonitors {},
+    /// Raise the volume of the default audio output.
+    VolumeUp {},
+    /// Lower the volume of the default audio output.
+    VolumeDown {},
+    /// Toggle mute of the default audio output.
+    ToggleMute {},
+    /// Raise the backlight brightness.
+    BrightnessUp {},
+    /// Lower the backlight brightness.
+    BrightnessDown {},
     /// Spawn a command.
     Spawn {
         /// Command to spawn.
         #[cfg_attr(feature = "clap", arg(last = true, required = true))]
         command: Vec<String>,
     },
+    /// Focus a window by app ID, or spawn a command if there is no such window.
+    FocusOrSpawn {
+        /// App ID of the window to focus.
+        #[cfg_attr(feature = "clap", arg())]
+        app_id: String,
+        /// Look for the window on all outputs rather than only on the focused one.
+        #[cfg_attr(feature = "clap", arg(long))]
+        all_outputs: bool,
+        /// Command to spawn if no window is found.
+        #[cfg_attr(feature = "clap", arg(last = true, required = true))]
+        command: Vec<String>,
+    },
 
 
     /// Close a window.
@@ -206,6 +517,26 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg(long))]
         id: Option<u64>,
     },
+    /// Close a window, and kill its client if the window doesn't close in time.
+    #[cfg_attr(
+        feature = "clap",
+        clap(about = "Close the focused window, and kill its client if it doesn't respond")
+    )]
+    CloseWindowOrKill {
+        /// Id of the window to close.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+        /// How long to wait for the window to close, in milliseconds.
+        ///
+        /// If `None`, waits 5 seconds.
+        #[cfg_attr(feature = "clap", arg(long))]
+        timeout_ms: Option<u32>,
+        /// Kill the client right away on timeout instead of asking for confirmation.
+        #[cfg_attr(feature = "clap", arg(long))]
+        auto_kill: bool,
+    },
     /// Toggle fullscreen on a window.
     #[cfg_attr(
         feature = "clap",
@@ -246,6 +577,13 @@ pub enum Action {
     },
     /// Focus the previously focused window.
     FocusWindowPrevious {},
+    /// Cycle the focus to an older window in the focus history.
+    ///
+    /// Repeated cycling walks further back in the history. The history is reordered once the
+    /// focus changes in some other way.
+    CycleFocusHistoryBackward {},
+    /// Cycle the focus to a newer window in the focus history.
+    CycleFocusHistoryForward {},
     /// Focus the column to the left.
     FocusColumnLeft {},
     /// Focus the column to the right.
@@ -366,6 +704,14 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg())]
         display: ColumnDisplay,
     },
+    /// Pin the focused column to an edge of the view, or unpin it if already pinned there.
+    ///
+    /// The pinned column stays visible while the other columns scroll.
+    ToggleColumnPin {
+        /// Edge of the view to pin the column to.
+        #[cfg_attr(feature = "clap", arg())]
+        edge: ColumnPinEdge,
+    },
     /// Center the focused column on the screen.
     CenterColumn {},
     /// Center a window on the screen.
@@ -394,6 +740,18 @@ pub enum Action {
     },
     /// Focus the previous workspace.
     FocusWorkspacePrevious {},
+    /// Slide the workspace below partially into view without focusing it.
+    ///
+    /// When triggered from a bind, the peek ends when the key is released. Otherwise, end it with
+    /// `end-workspace-peek`. Focusing a workspace during the peek commits the switch.
+    PeekWorkspaceDown {},
+    /// Slide the workspace above partially into view without focusing it.
+    ///
+    /// When triggered from a bind, the peek ends when the key is released. Otherwise, end it with
+    /// `end-workspace-peek`. Focusing a workspace during the peek commits the switch.
+    PeekWorkspaceUp {},
+    /// End a workspace peek and spring back to the focused workspace.
+    EndWorkspacePeek {},
     /// Move the focused window to the workspace below.
     MoveWindowToWorkspaceDown {},
     /// Move the focused window to the workspace above.
@@ -637,6 +995,8 @@ pub enum Action {
     },
     /// Expand the focused column to space not taken up by other fully visible columns.
     ExpandColumnToAvailableWidth {},
+    /// Resize the focused column to fit the width of its widest window.
+    FitColumnToContent {},
     /// Switch between keyboard layouts.
     SwitchLayout {
         /// Layout to switch to.
@@ -677,6 +1037,11 @@ pub enum Action {
     DebugToggleOpaqueRegions {},
     /// Toggle visualization of output damage.
     DebugToggleDamage {},
+    /// Toggle a heatmap of output damage accumulated over time.
+    ///
+    /// Regions repainted on every frame glow red, and the heat decays over a few seconds once
+    /// the repaints stop.
+    DebugToggleDamageHeatmap {},
     /// Move the focused window between the floating and the tiling layout.
     ToggleWindowFloating {
         /// Id of the window to move.
@@ -729,6 +1094,13 @@ pub enum Action {
             arg(short, long, default_value = "+0", allow_negative_numbers = true)
         )]
         y: PositionChange,
+
+        /// Whether to animate the move.
+        ///
+        /// If `true` (the default), the window slides to the new position using the
+        /// floating-window-movement animation. If `false`, it jumps there right away.
+        #[cfg_attr(feature = "clap", arg(long, action = clap::ArgAction::Set, default_value_t = true))]
+        animate: bool,
     },
     /// Toggle the opacity of a window.
     #[cfg_attr(
@@ -742,6 +1114,18 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg(long))]
         id: Option<u64>,
     },
+    /// Toggle whether resizing a floating window keeps its aspect ratio.
+    #[cfg_attr(
+        feature = "clap",
+        clap(about = "Toggle whether resizing the focused floating window keeps its aspect ratio")
+    )]
+    ToggleWindowKeepAspectRatio {
+        /// Id of the window.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+    },
     /// Clear the dynamic cast target, making it show nothing.
     /// Toggle (open/close) the Overview.
     ToggleOverview {},
@@ -767,6 +1151,124 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg(long))]
         id: u64,
     },
+    /// Show an overlay listing conflicting and shadowed binds.
+    ShowBindConflicts {},
+    /// Show the first-run tutorial again.
+    ShowTutorial {},
+    /// Do a screen transition.
+    ///
+    /// The current screen contents are frozen for the delay, then give way to the new contents.
+    /// Call this right before switching the theme or the wallpaper to hide the flurry of
+    /// repaints.
+    DoScreenTransition {
+        /// Delay in milliseconds for the screen to freeze before starting the transition.
+        #[cfg_attr(feature = "clap", arg(short, long))]
+        delay_ms: Option<u16>,
+        /// Duration of the transition in milliseconds.
+        #[cfg_attr(feature = "clap", arg(long))]
+        duration_ms: Option<u16>,
+        /// How the old contents give way to the new ones.
+        ///
+        /// If `None`, crossfades.
+        #[cfg_attr(feature = "clap", arg(short, long))]
+        style: Option<ScreenTransitionStyle>,
+    },
+    /// Set the wallpaper of an output, overriding the config until niri exits.
+    SetWallpaper {
+        /// Path to the image, in any format that gdk-pixbuf can load.
+        #[cfg_attr(feature = "clap", arg())]
+        path: String,
+        /// Name of the output.
+        ///
+        /// If `None`, uses the focused output.
+        #[cfg_attr(feature = "clap", arg(long))]
+        output: Option<String>,
+        /// How the image fills the output.
+        ///
+        /// If `None`, fills the output.
+        #[cfg_attr(feature = "clap", arg(long))]
+        mode: Option<WallpaperMode>,
+        /// Duration of the crossfade from the previous wallpaper in milliseconds.
+        ///
+        /// If `None`, switches instantly.
+        #[cfg_attr(feature = "clap", arg(long))]
+        crossfade_ms: Option<u16>,
+    },
+    /// Enter the mode for adjusting the focused window with the arrow keys.
+    ///
+    /// The arrow keys move a floating window, and resize it with Shift held. For a tiled window,
+    /// they change the column width and the window height. Escape or Enter leaves the mode.
+    EnterAdjustMode {},
+    /// Open the config file at the error location in an editor.
+    ///
+    /// Does nothing unless the config error notification is shown.
+    OpenConfigError {},
+    /// Toggle the window rules with this name on or off.
+    ///
+    /// The change stays over config reloads until niri exits.
+    ToggleWindowRule {
+        /// Name of the window rules.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+    },
+    /// Enable the window rules with this name.
+    EnableWindowRule {
+        /// Name of the window rules.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+    },
+    /// Disable the window rules with this name.
+    DisableWindowRule {
+        /// Name of the window rules.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+    },
+    /// Load a different config file and watch it for changes instead of the current one.
+    ///
+    /// If the file has errors, the current config stays and the error notification is shown.
+    /// Fixing the file loads it.
+    LoadConfigFile {
+        /// Path to the config file.
+        #[cfg_attr(feature = "clap", arg())]
+        path: String,
+    },
+    /// Warp the pointer to the center or an edge of the focused window.
+    WarpPointerToWindow {
+        /// Part of the window to warp the pointer to.
+        #[cfg_attr(feature = "clap", arg())]
+        target: WindowWarpTarget,
+    },
+    /// Warp the pointer to a region of the focused monitor.
+    ///
+    /// The monitor is split into a 3×3 grid, numbered from 1 at the top left to 9 at the bottom
+    /// right, row by row. The pointer warps to the center of the region.
+    WarpPointerToRegion {
+        /// Number of the region, from 1 to 9.
+        #[cfg_attr(feature = "clap", arg())]
+        region: u8,
+    },
+    /// Save columns of the focused workspace as a named column group.
+    ///
+    /// Groups are kept until niri exits. Saving a group with an existing name replaces it.
+    SaveGroup {
+        /// Name of the group.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+        /// Number of columns to save, starting from the focused column.
+        ///
+        /// If `None`, saves all columns of the workspace.
+        #[cfg_attr(feature = "clap", arg(long))]
+        columns: Option<u8>,
+    },
+    /// Re-create a saved column group on the focused workspace.
+    ///
+    /// Open windows of the group's apps are moved into its columns, and missing apps are started
+    /// from the desktop entries matching their app ids.
+    RecallGroup {
+        /// Name of the group.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+    },
 }
 
 /// Change in window or column size.
@@ -817,6 +1319,38 @@ pub enum LayoutSwitchTarget {
     Index(u8),
 }
 
+/// Style of a screen transition.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum ScreenTransitionStyle {
+    /// The old contents fade out.
+    #[default]
+    Crossfade,
+    /// The new contents wipe in from the right edge towards the left.
+    WipeLeft,
+    /// The new contents wipe in from the left edge towards the right.
+    WipeRight,
+    /// The new contents wipe in from the bottom edge upwards.
+    WipeUp,
+    /// The new contents wipe in from the top edge downwards.
+    WipeDown,
+}
+
+/// How a wallpaper image fills the output.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum WallpaperMode {
+    /// Scale the image to cover the whole output, cropping the overflowing edges.
+    #[default]
+    Fill,
+    /// Scale the image to fit inside the output, showing the background color around it.
+    Fit,
+    /// Show the image unscaled in the middle of the output.
+    Center,
+}
+
 /// How windows display in a column.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
@@ -825,6 +1359,38 @@ pub enum ColumnDisplay {
     Normal,
     /// Windows are in tabs.
     Tabbed,
+    /// Windows are placed side by side within the column width.
+    ///
+    /// This is the horizontal split; the vertical split is the [`ColumnDisplay::Normal`] mode.
+    /// Mixing the two within one column (e.g. one window next to a stack of two) is not
+    /// supported.
+    Split,
+}
+
+/// Edge of the view that a column can be pinned to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum ColumnPinEdge {
+    /// The column stays at the left edge of the view.
+    Left,
+    /// The column stays at the right edge of the view.
+    Right,
+}
+
+/// Part of a window that the pointer can be warped to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum WindowWarpTarget {
+    /// Center of the window.
+    Center,
+    /// Middle of the left edge of the window.
+    Left,
+    /// Middle of the right edge of the window.
+    Right,
+    /// Middle of the top edge of the window.
+    Top,
+    /// Middle of the bottom edge of the window.
+    Bottom,
 }
 
 /// Output actions that niri can perform.
@@ -872,6 +1438,29 @@ pub enum OutputAction {
         #[cfg_attr(feature = "clap", command(flatten))]
         vrr: VrrToSet,
     },
+    /// Power off the monitor, keeping the output enabled.
+    ///
+    /// Unlike `off`, this doesn't change the output layout: windows and workspaces stay on the
+    /// output. The monitor stays powered off until `power-on`, even through input.
+    PowerOff,
+    /// Power the monitor back on after `power-off`.
+    PowerOn,
+    /// Set the software gamma and brightness of the output.
+    ///
+    /// Only supported on the TTY backend. Running it with the default values resets the gamma.
+    Gamma {
+        /// Gamma exponent, higher values brighten the midtones.
+        #[cfg_attr(feature = "clap", arg(long, default_value_t = 1.))]
+        gamma: f64,
+        /// Brightness multiplier, from 0 to 1.
+        #[cfg_attr(feature = "clap", arg(long, default_value_t = 1.))]
+        brightness: f64,
+    },
+    /// Toggle the grayscale mode of the output.
+    ///
+    /// Fades the whole output to grayscale, for example as a distraction-free focus mode. Only
+    /// supported on the TTY backend.
+    ToggleGrayscale,
 }
 
 /// Output mode to set.
@@ -953,6 +1542,68 @@ pub struct VrrToSet {
     pub on_demand: bool,
 }
 
+/// Synthetic input event to inject.
+///
+/// The events go through the same handling as real input, so they trigger binds and move focus.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::Subcommand))]
+#[cfg_attr(feature = "clap", command(subcommand_value_name = "EVENT"))]
+#[cfg_attr(feature = "clap", command(subcommand_help_heading = "Events"))]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum InjectedInput {
+    /// Press a key.
+    KeyPress {
+        /// Evdev key code, for example 30 for the A key.
+        #[cfg_attr(feature = "clap", arg())]
+        keycode: u32,
+    },
+    /// Release a key.
+    KeyRelease {
+        /// Evdev key code, for example 30 for the A key.
+        #[cfg_attr(feature = "clap", arg())]
+        keycode: u32,
+    },
+    /// Move the pointer to a position.
+    PointerMoveTo {
+        /// Logical X position in the global coordinate space.
+        #[cfg_attr(feature = "clap", arg(allow_negative_numbers = true))]
+        x: f64,
+        /// Logical Y position in the global coordinate space.
+        #[cfg_attr(feature = "clap", arg(allow_negative_numbers = true))]
+        y: f64,
+    },
+    /// Move the pointer by an offset.
+    PointerMoveBy {
+        /// Logical X offset.
+        #[cfg_attr(feature = "clap", arg(allow_negative_numbers = true))]
+        dx: f64,
+        /// Logical Y offset.
+        #[cfg_attr(feature = "clap", arg(allow_negative_numbers = true))]
+        dy: f64,
+    },
+    /// Press a pointer button.
+    ButtonPress {
+        /// Evdev button code, for example 272 for the left button.
+        #[cfg_attr(feature = "clap", arg())]
+        button: u32,
+    },
+    /// Release a pointer button.
+    ButtonRelease {
+        /// Evdev button code, for example 272 for the left button.
+        #[cfg_attr(feature = "clap", arg())]
+        button: u32,
+    },
+    /// Scroll with the mouse wheel.
+    Scroll {
+        /// Horizontal amount in wheel clicks.
+        #[cfg_attr(feature = "clap", arg(allow_negative_numbers = true))]
+        horizontal: f64,
+        /// Vertical amount in wheel clicks, positive scrolls down.
+        #[cfg_attr(feature = "clap", arg(allow_negative_numbers = true))]
+        vertical: f64,
+    },
+}
+
 /// Connected output.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
@@ -981,6 +1632,72 @@ pub struct Output {
     ///
     /// `None` if the output is not mapped to any logical output (for example, if it is disabled).
     pub logical: Option<LogicalOutput>,
+    /// Capabilities of the output, from its EDID and its modes.
+    ///
+    /// `None` if the EDID is missing or could not be parsed, or if the backend does not have
+    /// access to it (for example, when running nested in a window).
+    pub edid: Option<OutputEdid>,
+    /// Pixel density of the current mode in dots per inch.
+    ///
+    /// `None` if the output is disabled or its physical size is unknown.
+    pub dpi: Option<f64>,
+    /// Scale that niri picks for the current mode when the config doesn't set one.
+    ///
+    /// It's based on the DPI and rounded to a multiple of 0.25. `None` if the output is
+    /// disabled.
+    pub suggested_scale: Option<f64>,
+}
+
+/// Output capabilities, from its EDID and its modes.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct OutputEdid {
+    /// Largest resolution among the output's non-interlaced modes, in physical pixels.
+    ///
+    /// The modes are the ones the kernel lists for the connector, which it builds mostly from
+    /// the EDID.
+    pub max_resolution: Option<(u16, u16)>,
+    /// Highest refresh rate among the output's non-interlaced modes, in millihertz.
+    pub max_refresh_rate: Option<u32>,
+    /// HDR static metadata from the EDID's CTA-861 extension, if it lists any HDR transfer
+    /// functions.
+    pub hdr: Option<HdrMetadata>,
+    /// Colorimetry signals from the EDID's CTA-861 extension, in addition to the default one.
+    pub colorimetry: Vec<Colorimetry>,
+}
+
+/// HDR static metadata of an output.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct HdrMetadata {
+    /// Whether the output supports the SMPTE ST 2084 (PQ) transfer function.
+    pub pq: bool,
+    /// Whether the output supports the hybrid log-gamma (HLG) transfer function.
+    pub hlg: bool,
+    /// Whether the output supports the traditional gamma HDR transfer function.
+    pub traditional_hdr: bool,
+    /// Desired content max luminance in cd/m², if known.
+    pub max_luminance: Option<f64>,
+    /// Desired content max frame-average luminance in cd/m², if known.
+    pub max_frame_avg_luminance: Option<f64>,
+    /// Desired content min luminance in cd/m², if known.
+    pub min_luminance: Option<f64>,
+}
+
+/// Colorimetry signal supported by an output.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum Colorimetry {
+    /// BT.2020 RGB.
+    Bt2020Rgb,
+    /// BT.2020 YCbCr.
+    Bt2020Ycc,
+    /// BT.2020 constant luminance YCbCr.
+    Bt2020Cycc,
+    /// SMPTE ST 2113 RGB (P3D65 and P3DCI).
+    St2113Rgb,
+    /// ICtCp.
+    Ictcp,
 }
 
 /// Output mode.
@@ -1050,7 +1767,9 @@ pub enum Transform {
 pub struct Window {
     /// Unique id of this window.
     ///
-    /// This id remains constant while this window is open.
+    /// This id remains constant while this window is open. Ids are not reused for the lifetime of
+    /// the compositor, so an id of a closed window will never refer to a different window. Actions
+    /// targeting a window id that no longer exists return an error.
     ///
     /// Do not assume that window ids will always increase without wrapping, or start at 1. That is
     /// an implementation detail subject to change. For example, ids may change to be randomly
@@ -1077,6 +1796,31 @@ pub struct Window {
     pub is_floating: bool,
     /// Whether this window requests your attention.
     pub is_urgent: bool,
+    /// Geometry of this window within its workspace view, if it is on a workspace.
+    ///
+    /// This is where the window shows up while its workspace is fully on screen, so it takes the
+    /// scrolling view offset into account. It doesn't include borders and other decorations drawn
+    /// by niri.
+    pub workspace_geometry: Option<WindowGeometry>,
+    /// Geometry of this window relative to its output, if it is on a visible workspace.
+    ///
+    /// This is where the window is currently drawn, including workspace switch animations and the
+    /// overview zoom.
+    pub output_geometry: Option<WindowGeometry>,
+}
+
+/// Position and size of a window, in logical pixels.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct WindowGeometry {
+    /// X coordinate of the top-left corner.
+    pub x: f64,
+    /// Y coordinate of the top-left corner.
+    pub y: f64,
+    /// Width.
+    pub width: f64,
+    /// Height.
+    pub height: f64,
 }
 
 /// Output configuration change result.
@@ -1095,7 +1839,10 @@ pub enum OutputConfigChanged {
 pub struct Workspace {
     /// Unique id of this workspace.
     ///
-    /// This id remains constant regardless of the workspace moving around and across monitors.
+    /// This id remains constant regardless of the workspace moving around and across monitors. Ids
+    /// are not reused for the lifetime of the compositor, so an id of a removed workspace will
+    /// never refer to a different workspace. Actions targeting a workspace id that no longer
+    /// exists return an error.
     ///
     /// Do not assume that workspace ids will always increase without wrapping, or start at 1. That
     /// is an implementation detail subject to change. For example, ids may change to be randomly
@@ -1140,6 +1887,15 @@ pub struct KeyboardLayouts {
     pub current_idx: u8,
 }
 
+impl KeyboardLayouts {
+    /// Returns the XKB name of the currently active layout.
+    pub fn current_name(&self) -> Option<&str> {
+        self.names
+            .get(usize::from(self.current_idx))
+            .map(String::as_str)
+    }
+}
+
 /// A layer-shell layer.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
@@ -1253,6 +2009,18 @@ pub enum Event {
         /// The new urgency state of the window.
         urgent: bool,
     },
+    /// The geometry of a window changed.
+    ///
+    /// These events are throttled, so not every intermediate geometry during an animation is sent.
+    /// The final geometry is always sent once the window settles.
+    WindowGeometryChanged {
+        /// Id of the window.
+        id: u64,
+        /// The new geometry within the workspace view.
+        workspace_geometry: Option<WindowGeometry>,
+        /// The new geometry relative to the output.
+        output_geometry: Option<WindowGeometry>,
+    },
     /// The configured keyboard layouts have changed.
     KeyboardLayoutsChanged {
         /// The new keyboard layout configuration.
@@ -1262,12 +2030,21 @@ pub enum Event {
     KeyboardLayoutSwitched {
         /// Index of the newly active layout.
         idx: u8,
+        /// XKB name of the newly active layout.
+        name: String,
     },
     /// The overview was opened or closed.
     OverviewOpenedOrClosed {
         /// The new state of the overview.
         is_open: bool,
     },
+    /// The session was paused or resumed, for example by switching to another VT and back.
+    ///
+    /// While the session is paused, niri doesn't draw anything and withholds frame callbacks.
+    SessionPausedOrResumed {
+        /// Whether the session is now paused.
+        is_paused: bool,
+    },
 }
 
 impl FromStr for WorkspaceReferenceArg {
@@ -1369,7 +2146,67 @@ impl FromStr for ColumnDisplay {
         match s {
             "normal" => Ok(Self::Normal),
             "tabbed" => Ok(Self::Tabbed),
-            _ => Err(r#"invalid column display, can be "normal" or "tabbed""#),
+            "split" => Ok(Self::Split),
+            _ => Err(r#"invalid column display, can be "normal", "tabbed" or "split""#),
+        }
+    }
+}
+
+impl FromStr for ColumnPinEdge {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            _ => Err(r#"invalid column pin edge, can be "left" or "right""#),
+        }
+    }
+}
+
+impl FromStr for WindowWarpTarget {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "center" => Ok(Self::Center),
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "top" => Ok(Self::Top),
+            "bottom" => Ok(Self::Bottom),
+            _ => Err(
+                r#"invalid window warp target, can be "center", "left", "right", "top" or "bottom""#,
+            ),
+        }
+    }
+}
+
+impl FromStr for ScreenTransitionStyle {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "crossfade" => Ok(Self::Crossfade),
+            "wipe-left" => Ok(Self::WipeLeft),
+            "wipe-right" => Ok(Self::WipeRight),
+            "wipe-up" => Ok(Self::WipeUp),
+            "wipe-down" => Ok(Self::WipeDown),
+            _ => Err(
+                r#"invalid screen transition style, can be "crossfade", "wipe-left", "wipe-right", "wipe-up" or "wipe-down""#,
+            ),
+        }
+    }
+}
+
+impl FromStr for WallpaperMode {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fill" => Ok(Self::Fill),
+            "fit" => Ok(Self::Fit),
+            "center" => Ok(Self::Center),
+            _ => Err(r#"invalid wallpaper mode, can be "fill", "fit" or "center""#),
         }
     }
 }
@@ -1423,11 +2260,18 @@ impl FromStr for ConfiguredMode {
 
         let width = width.parse().map_err(|_| "error parsing width")?;
         let height = height.parse().map_err(|_| "error parsing height")?;
-        let refresh = refresh
+        let refresh: Option<f64> = refresh
             .map(str::parse)
             .transpose()
             .map_err(|_| "error parsing refresh rate")?;
 
+        if width == 0 || height == 0 {
+            return Err("width and height must be greater than zero");
+        }
+        if refresh.is_some_and(|refresh| !refresh.is_finite() || refresh <= 0.) {
+            return Err("refresh rate must be a positive number");
+        }
+
         Ok(Self {
             width,
             height,
@@ -43,6 +43,9 @@ pub struct EventStreamState {
 
     /// State of the overview.
     pub overview: OverviewState,
+
+    /// State of the session.
+    pub session: SessionState,
 }
 
 /// The workspaces state communicated over the event stream.
@@ -73,6 +76,13 @@ pub struct OverviewState {
     pub is_open: bool,
 }
 
+/// The session state communicated over the event stream.
+#[derive(Debug, Default)]
+pub struct SessionState {
+    /// Whether the session is currently paused.
+    pub is_paused: bool,
+}
+
 impl EventStreamStatePart for EventStreamState {
     fn replicate(&self) -> Vec<Event> {
         let mut events = Vec::new();
@@ -80,6 +90,7 @@ impl EventStreamStatePart for EventStreamState {
         events.extend(self.windows.replicate());
         events.extend(self.keyboard_layouts.replicate());
         events.extend(self.overview.replicate());
+        events.extend(self.session.replicate());
         events
     }
 
@@ -88,6 +99,7 @@ impl EventStreamStatePart for EventStreamState {
         let event = self.windows.apply(event)?;
         let event = self.keyboard_layouts.apply(event)?;
         let event = self.overview.apply(event)?;
+        let event = self.session.apply(event)?;
         Some(event)
     }
 }
@@ -189,6 +201,16 @@ impl EventStreamStatePart for WindowsState {
                     }
                 }
             }
+            Event::WindowGeometryChanged {
+                id,
+                workspace_geometry,
+                output_geometry,
+            } => {
+                if let Some(win) = self.windows.get_mut(&id) {
+                    win.workspace_geometry = workspace_geometry;
+                    win.output_geometry = output_geometry;
+                }
+            }
             event => return Some(event),
         }
         None
@@ -209,7 +231,7 @@ impl EventStreamStatePart for KeyboardLayoutsState {
             Event::KeyboardLayoutsChanged { keyboard_layouts } => {
                 self.keyboard_layouts = Some(keyboard_layouts);
             }
-            Event::KeyboardLayoutSwitched { idx } => {
+            Event::KeyboardLayoutSwitched { idx, .. } => {
                 let kb = self.keyboard_layouts.as_mut();
                 let kb = kb.expect("keyboard layouts must be set before a layout can be switched");
                 kb.current_idx = idx;
@@ -237,3 +259,21 @@ impl EventStreamStatePart for OverviewState {
         None
     }
 }
+
+impl EventStreamStatePart for SessionState {
+    fn replicate(&self) -> Vec<Event> {
+        vec![Event::SessionPausedOrResumed {
+            is_paused: self.is_paused,
+        }]
+    }
+
+    fn apply(&mut self, event: Event) -> Option<Event> {
+        match event {
+            Event::SessionPausedOrResumed { is_paused } => {
+                self.is_paused = is_paused;
+            }
+            event => return Some(event),
+        }
+        None
+    }
+}
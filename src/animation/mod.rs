@@ -140,9 +140,11 @@ impl Animation {
     /// Restarts the animation using the previous config.
     /// 使用相同配置重启动画（可改变起始/目标值）
     pub fn restarted(&self, from: f64, to: f64, initial_velocity: f64) -> Self {
-        // 禁用时直接返回副本（无动画）
+        // 禁用时直接跳到新的目标值（无动画）
         if self.is_off {
-            return self.clone();  // Rust概念：克隆语义（深拷贝）
+            let mut rv = Self::ease(self.clock.clone(), from, to, 0., 0, Curve::EaseOutCubic);
+            rv.is_off = true;
+            return rv;
         }
 
         // 速度调整（同new方法）
@@ -163,7 +165,7 @@ impl Animation {
                 let spring = Spring {
                     from,
                     to,
-                    initial_velocity: self.initial_velocity,
+                    initial_velocity,
                     params: spring.params,
                 };
                 Self::spring(self.clock.clone(), spring)
@@ -356,6 +358,28 @@ impl Animation {
         self.value_at(self.clock.now())
     }
 
+    /// Returns the current velocity, in the same units as `initial_velocity`.
+    /// 返回当前速度（单位与 initial_velocity 相同）
+    ///
+    /// Pass it to `restarted()` to retarget an ongoing animation without a jump in speed.
+    /// 传给 `restarted()` 即可在动画进行中平滑地改变目标
+    pub fn velocity(&self) -> f64 {
+        if self.is_off || self.is_done() {
+            return 0.;
+        }
+
+        // 数值差分：适用于所有动画类型
+        let now = self.clock.now();
+        let step = Duration::from_micros(500);
+        let before = now.saturating_sub(step).max(self.start_time);
+        let after = now + step;
+        let velocity =
+            (self.value_at(after) - self.value_at(before)) / (after - before).as_secs_f64();
+
+        // 撤销构造时按时钟速率做的缩放
+        velocity * self.clock.rate().max(0.001)
+    }
+
     /// Returns a value that stops at the target value after first reaching it.
     /// 返回首次到达目标值后保持目标值的动画值
     ///
@@ -455,4 +479,35 @@ impl From<niri_config::AnimationCurve> for Curve {
    |    - 弹簧: 物理公式     |
    |    - 减速: 指数衰减     |
    +------------------------+
-*/
\ No newline at end of file
+*/
+
+#[cfg(test)]  // 条件编译：仅在测试时包含
+mod tests {
+    use super::*;  // 导入父模块所有内容
+
+    // 测试中途反向时位置和速度保持连续
+    #[test]
+    fn restarted_spring_keeps_velocity() {
+        let mut clock = Clock::with_time(Duration::ZERO);
+        let spring = Spring {
+            from: 0.,
+            to: 1.,
+            initial_velocity: 0.,
+            params: SpringParams::new(1., 800., 0.0001),
+        };
+        let anim = Animation::spring(clock.clone(), spring);
+
+        clock.set_unadjusted(Duration::from_millis(30));
+        let value = anim.value();
+        let velocity = anim.velocity();
+        assert!(velocity > 0.);
+
+        // 反向：从当前位置和速度回到 0
+        let reversed = anim.restarted(value, 0., velocity);
+        assert_eq!(reversed.value(), value);
+
+        // 刚开始时仍按原方向运动，而不是突然折返
+        clock.set_unadjusted(Duration::from_millis(31));
+        assert!(reversed.value() > value);
+    }
+}
@@ -109,6 +109,9 @@ impl Headless {
                 vrr_supported: false, // 不支持VRR
                 vrr_enabled: false,
                 logical: Some(logical_output(&output)), // 逻辑位置信息
+                edid: None, // 无EDID
+                dpi: None,  // 无物理尺寸
+                suggested_scale: Some(1.),
             },
         );
 
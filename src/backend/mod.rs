@@ -234,6 +234,32 @@ impl Backend {
         }
     }
 
+    // 函数：设置单个显示器电源状态
+    // 作用：关闭或打开一个输出的显示器，输出本身保持启用（仅TTY）
+    pub fn set_output_active(&mut self, output: &Output, active: bool) {
+        if let Backend::Tty(tty) = self {
+            tty.set_output_active(output, active);
+        }
+    }
+
+    // 函数：获取伽马曲线长度
+    // 作用：返回输出CRTC的伽马表项数（仅TTY支持）
+    pub fn get_gamma_size(&self, output: &Output) -> anyhow::Result<u32> {
+        match self {
+            Backend::Tty(tty) => tty.get_gamma_size(output),
+            _ => anyhow::bail!("setting gamma is only supported on the TTY backend"),
+        }
+    }
+
+    // 函数：设置伽马曲线
+    // 作用：设置输出的软件伽马，None表示恢复线性曲线（仅TTY支持）
+    pub fn set_gamma(&mut self, output: &Output, ramp: Option<Vec<u16>>) -> anyhow::Result<()> {
+        match self {
+            Backend::Tty(tty) => tty.set_gamma(output, ramp),
+            _ => anyhow::bail!("setting gamma is only supported on the TTY backend"),
+        }
+    }
+
     // 函数：动态设置VRR
     // 作用：按需启用/禁用可变刷新率（仅TTY）
     pub fn set_output_on_demand_vrr(&mut self, niri: &mut Niri, output: &Output, enable_vrr: bool) {
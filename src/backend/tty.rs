@@ -13,7 +13,8 @@ use std::{io, mem};
 use anyhow::{anyhow, bail, ensure, Context};
 use bytemuck::cast_slice_mut;
 use libc::dev_t;
-use niri_config::{Config, OutputName};
+use niri_config::{Config, LidSwitchPolicy, OutputName};
+use niri_ipc::{CursorPlane, CursorPlaneFallback};
 use smithay::backend::allocator::dmabuf::Dmabuf;
 use smithay::backend::allocator::format::FormatSet;
 use smithay::backend::allocator::gbm::{GbmAllocator, GbmBufferFlags, GbmDevice};
@@ -24,8 +25,9 @@ use smithay::backend::drm::{
     DrmDevice, DrmDeviceFd, DrmEvent, DrmEventMetadata, DrmEventTime, DrmNode, NodeType, VrrSupport,
 };
 use smithay::backend::egl::context::ContextPriority;
-use smithay::backend::egl::{EGLDevice, EGLDisplay};
+use smithay::backend::egl::{EGLContext, EGLDevice, EGLDisplay};
 use smithay::backend::libinput::{LibinputInputBackend, LibinputSessionInterface};
+use smithay::backend::renderer::element::RenderElementStates;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::backend::renderer::multigpu::gbm::GbmGlesBackend;
 use smithay::backend::renderer::multigpu::{GpuManager, MultiFrame, MultiRenderer};
@@ -46,7 +48,7 @@ use smithay::reexports::input::Libinput;
 use smithay::reexports::rustix::fs::OFlags;
 use smithay::reexports::wayland_protocols;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
-use smithay::utils::DeviceFd;
+use smithay::utils::{DeviceFd, Point, Scale, Size};
 use smithay::wayland::dmabuf::{DmabufFeedback, DmabufFeedbackBuilder, DmabufGlobal};
 use smithay::wayland::drm_lease::{
     DrmLease, DrmLeaseBuilder, DrmLeaseRequest, DrmLeaseState, LeaseRejected,
@@ -59,10 +61,15 @@ use wayland_protocols::wp::presentation_time::server::wp_presentation_feedback;
 use super::{IpcOutputMap, RenderResult};
 use crate::backend::OutputId;
 use crate::frame_clock::FrameClock;
-use crate::niri::{Niri, RedrawState, State};
-use crate::render_helpers::debug::draw_damage;
+use crate::niri::{Niri, OutputRenderElements, RedrawState, State};
+use crate::render_helpers::debug::{draw_damage, draw_damage_heatmap};
+use crate::render_helpers::desaturate::Desaturation;
 use crate::render_helpers::renderer::AsGlesRenderer;
-use crate::render_helpers::{resources, shaders, RenderTarget};
+use crate::render_helpers::upscale::Upscaler;
+use crate::render_helpers::{
+    create_robust_context, is_context_lost, resources, shaders, RenderTarget,
+};
+use crate::utils::scale::{guess_monitor_scale, monitor_dpi};
 use crate::utils::{get_monotonic_time, is_laptop_panel, logical_output};
 
 const SUPPORTED_COLOR_FORMATS: [Fourcc; 4] = [
@@ -72,6 +79,18 @@ const SUPPORTED_COLOR_FORMATS: [Fourcc; 4] = [
     Fourcc::Abgr8888,
 ];
 
+/// Formats for outputs configured with 10 bits per color, falling back to 8 bits.
+const SUPPORTED_COLOR_FORMATS_10_BIT: [Fourcc; 8] = [
+    Fourcc::Xrgb2101010,
+    Fourcc::Xbgr2101010,
+    Fourcc::Argb2101010,
+    Fourcc::Abgr2101010,
+    Fourcc::Xrgb8888,
+    Fourcc::Xbgr8888,
+    Fourcc::Argb8888,
+    Fourcc::Abgr8888,
+];
+
 pub struct Tty {
     config: Rc<RefCell<Config>>,
     session: LibSeatSession,
@@ -92,6 +111,8 @@ pub struct Tty {
     update_output_config_on_resume: bool,
     // Whether the debug tinting is enabled.
     debug_tint: bool,
+    // The GPU was reset and recreating the renderer is scheduled.
+    gpu_reset_recovery_queued: bool,
     ipc_outputs: Arc<Mutex<IpcOutputMap>>,
 }
 
@@ -141,6 +162,7 @@ pub struct OutputDevice {
 pub struct CrtcInfo {
     id: OutputId,
     name: OutputName,
+    edid: Option<niri_ipc::OutputEdid>,
 }
 
 impl OutputDevice {
@@ -236,6 +258,12 @@ struct Surface {
     /// Plot name for the presentation misprediction plot.
     presentation_misprediction_plot_name: tracy_client::PlotName,
     sequence_delta_plot_name: tracy_client::PlotName,
+    /// Buffer for rendering below the native resolution.
+    upscaler: Upscaler,
+    /// Buffer for rendering the output in grayscale mode.
+    desaturation: Desaturation,
+    /// Whether the output was set up for 10 bits per color.
+    ten_bit: bool,
 }
 
 pub struct SurfaceDmabufFeedback {
@@ -290,7 +318,17 @@ impl Tty {
             })
             .unwrap();
 
-        let api = GbmGlesBackend::with_context_priority(ContextPriority::High);
+        // Robust contexts report GPU resets, which lets us recreate the renderer afterwards.
+        let api = GbmGlesBackend::with_factory(|display: &EGLDisplay| {
+            let context = match create_robust_context(display) {
+                Ok(context) => context,
+                Err(err) => {
+                    warn!("GPU resets won't be detected: {err:?}");
+                    EGLContext::new_with_priority(display, ContextPriority::High)?
+                }
+            };
+            Ok(unsafe { GlesRenderer::new(context)? })
+        });
         let gpu_manager = GpuManager::new(api).context("error creating the GPU manager")?;
 
         let (primary_node, primary_render_node) = primary_node_from_config(&config.borrow())
@@ -334,6 +372,7 @@ impl Tty {
             dmabuf_global: None,
             update_output_config_on_resume: false,
             debug_tint: false,
+            gpu_reset_recovery_queued: false,
             ipc_outputs: Arc::new(Mutex::new(HashMap::new())),
         })
     }
@@ -386,6 +425,11 @@ impl Tty {
             SessionEvent::PauseSession => {
                 debug!("pausing session");
 
+                // Clients stop getting frame callbacks until the session resumes. The devices,
+                // renderers and DRM compositors stay around so that resuming doesn't need to
+                // recreate them.
+                niri.is_session_paused = true;
+
                 self.libinput.suspend();
 
                 for device in self.devices.values_mut() {
@@ -399,6 +443,8 @@ impl Tty {
             SessionEvent::ActivateSession => {
                 debug!("resuming session");
 
+                niri.is_session_paused = false;
+
                 if self.libinput.resume().is_err() {
                     warn!("error resuming libinput");
                 }
@@ -435,10 +481,20 @@ impl Tty {
                     device_list.remove(&node.dev_id());
 
                     // It hasn't been removed, update its state as usual.
+                    //
+                    // Don't disable the connectors: whoever had the VT before us usually left
+                    // them lit, and our first frame can take them over without a visible
+                    // modeset. Resetting the compositor state makes that frame a full commit
+                    // rather than assuming that our old state is still in place.
                     let device = self.devices.get_mut(&node).unwrap();
-                    if let Err(err) = device.drm.activate(true) {
+                    if let Err(err) = device.drm.activate(false) {
                         warn!("error activating DRM device: {err:?}");
                     }
+                    for surface in device.surfaces.values_mut() {
+                        if let Err(err) = surface.compositor.reset_state() {
+                            warn!("error resetting DRM compositor state: {err:?}");
+                        }
+                    }
                     if let Some(lease_state) = &mut device.drm_lease_state {
                         lease_state.resume::<State>();
                     }
@@ -524,35 +580,14 @@ impl Tty {
                 debug!("this is the primary render node");
             }
 
-            let mut renderer = self
-                .gpu_manager
-                .single_renderer(&render_node)
-                .context("error creating renderer")?;
-
-            if let Err(err) = renderer.bind_wl_display(&niri.display_handle) {
-                warn!("error binding wl-display in EGL: {err:?}");
-            }
-
-            let gles_renderer = renderer.as_gles_renderer();
-            resources::init(gles_renderer);
-            shaders::init(gles_renderer);
-
-            let config = self.config.borrow();
-            if let Some(src) = config.animations.window_resize.custom_shader.as_deref() {
-                shaders::set_custom_resize_program(gles_renderer, Some(src));
-            }
-            if let Some(src) = config.animations.window_close.custom_shader.as_deref() {
-                shaders::set_custom_close_program(gles_renderer, Some(src));
-            }
-            if let Some(src) = config.animations.window_open.custom_shader.as_deref() {
-                shaders::set_custom_open_program(gles_renderer, Some(src));
-            }
-            drop(config);
-
-            niri.update_shaders();
+            self.init_primary_renderer(niri, render_node)?;
 
             // Create the dmabuf global.
-            let primary_formats = renderer.dmabuf_formats();
+            let primary_formats = self
+                .gpu_manager
+                .single_renderer(&render_node)
+                .context("error creating renderer")?
+                .dmabuf_formats();
             let default_feedback =
                 DmabufFeedbackBuilder::new(render_node.dev_id(), primary_formats.clone())
                     .build()
@@ -622,6 +657,105 @@ impl Tty {
         Ok(())
     }
 
+    /// Sets up the renderer of the primary GPU, which has our shaders and resources.
+    fn init_primary_renderer(
+        &mut self,
+        niri: &mut Niri,
+        render_node: DrmNode,
+    ) -> anyhow::Result<()> {
+        let mut renderer = self
+            .gpu_manager
+            .single_renderer(&render_node)
+            .context("error creating renderer")?;
+
+        if let Err(err) = renderer.bind_wl_display(&niri.display_handle) {
+            warn!("error binding wl-display in EGL: {err:?}");
+        }
+
+        let gles_renderer = renderer.as_gles_renderer();
+        resources::init(gles_renderer);
+        shaders::init(gles_renderer);
+
+        let config = self.config.borrow();
+        if let Some(src) = config.animations.window_resize.custom_shader.as_deref() {
+            shaders::set_custom_resize_program(gles_renderer, Some(src));
+        }
+        if let Some(src) = config.animations.window_close.custom_shader.as_deref() {
+            shaders::set_custom_close_program(gles_renderer, Some(src));
+        }
+        if let Some(src) = config.animations.window_open.custom_shader.as_deref() {
+            shaders::set_custom_open_program(gles_renderer, Some(src));
+        }
+        drop(config);
+
+        niri.update_shaders();
+
+        Ok(())
+    }
+
+    /// Checks whether the GPU was reset after a failed render, and schedules recovery if so.
+    fn check_gpu_reset(&mut self, niri: &mut Niri) {
+        if self.gpu_reset_recovery_queued {
+            return;
+        }
+
+        let lost = match self.gpu_manager.single_renderer(&self.primary_render_node) {
+            Ok(mut renderer) => is_context_lost(renderer.as_gles_renderer()),
+            Err(err) => {
+                debug!("error creating renderer for primary GPU: {err:?}");
+                false
+            }
+        };
+        if !lost {
+            return;
+        }
+
+        warn!("the GPU was reset, recreating the renderer");
+
+        // We're in the middle of a redraw, so recover once it's done.
+        self.gpu_reset_recovery_queued = true;
+        niri.event_loop.insert_idle(|state| {
+            state.backend.tty().recover_from_gpu_reset(&mut state.niri);
+        });
+    }
+
+    /// Recreates the renderers after a GPU reset, along with everything that lived in them.
+    fn recover_from_gpu_reset(&mut self, niri: &mut Niri) {
+        let _span = tracy_client::span!("Tty::recover_from_gpu_reset");
+
+        self.gpu_reset_recovery_queued = false;
+
+        if let Ok(mut renderer) = self.gpu_manager.single_renderer(&self.primary_render_node) {
+            renderer.unbind_wl_display();
+        }
+
+        // Removing a node drops its renderer and EGL context, adding it back creates new ones on
+        // next use.
+        let mut nodes = HashMap::new();
+        for device in self.devices.values() {
+            nodes.insert(device.render_node, device.gbm.clone());
+        }
+        for (render_node, gbm) in nodes {
+            self.gpu_manager.as_mut().remove_node(&render_node);
+            if let Err(err) = self.gpu_manager.as_mut().add_node(render_node, gbm) {
+                warn!("error adding render node {render_node} back to GPU manager: {err:?}");
+            }
+        }
+
+        if let Err(err) = self.init_primary_renderer(niri, self.primary_render_node) {
+            warn!("error setting up the renderer after a GPU reset: {err:?}");
+        }
+
+        // The swapchain contents are gone too, so redraw everything from scratch.
+        for device in self.devices.values_mut() {
+            for surface in device.surfaces.values_mut() {
+                surface.compositor.reset_buffers();
+            }
+        }
+
+        niri.on_renderer_reset();
+    }
+
     fn device_changed(&mut self, device_id: dev_t, niri: &mut Niri) {
         debug!("device changed: {device_id}");
 
@@ -652,16 +786,25 @@ impl Tty {
                     crtc: Some(crtc),
                 } => {
                     let connector_name = format_connector_name(&connector);
-                    let name = make_output_name(&device.drm, connector.handle(), connector_name);
+                    let info = get_edid_info(&device.drm, connector.handle())
+                        .map_err(|err| {
+                            warn!("error getting EDID info for {connector_name}: {err:?}")
+                        })
+                        .ok();
+                    let name = make_output_name(info.as_ref(), connector_name);
+                    let edid = info.as_ref().map(|info| make_output_edid(info, &connector));
                     debug!(
                         "new connector: {} \"{}\"",
                         &name.connector,
                         name.format_make_model_serial(),
                     );
+                    if let Some(edid) = &edid {
+                        debug!("EDID capabilities: {edid:?}");
+                    }
 
                     // Assign an id to this crtc.
                     let id = OutputId::next();
-                    added.push((crtc, CrtcInfo { id, name }));
+                    added.push((crtc, CrtcInfo { id, name, edid }));
                 }
                 DrmScanEvent::Disconnected {
                     crtc: Some(crtc), ..
@@ -844,6 +987,13 @@ impl Tty {
             pick_mode(&connector, config.mode).ok_or_else(|| anyhow!("no mode"))?;
         if fallback {
             let target = config.mode.unwrap();
+            let edid = device
+                .known_crtcs
+                .get(&crtc)
+                .and_then(|info| info.edid.as_ref());
+            if let Some(reason) = edid.and_then(|edid| mode_exceeds_edid(edid, target)) {
+                warn!("configured mode is not supported by the monitor: {reason}");
+            }
             warn!(
                 "configured mode {}x{}{} could not be found, falling back to preferred",
                 target.width,
@@ -857,8 +1007,15 @@ impl Tty {
         }
         debug!("picking mode: {mode:?}");
 
-        // We only use 8888 RGB formats, so set max bpc to 8 to allow more types of links to run.
-        match set_max_bpc(&device.drm, connector.handle(), 8) {
+        // By default we only use 8888 RGB formats, so set max bpc to 8 to allow more types of links
+        // to run.
+        let ten_bit = config.is_10_bit();
+        let (max_bpc, color_formats): (u64, &[Fourcc]) = if ten_bit {
+            (10, &SUPPORTED_COLOR_FORMATS_10_BIT)
+        } else {
+            (8, &SUPPORTED_COLOR_FORMATS)
+        };
+        match set_max_bpc(&device.drm, connector.handle(), max_bpc) {
             Ok(bpc) => debug!("set max bpc to {bpc}"),
             Err(err) => debug!("error setting max bpc: {err:?}"),
         }
@@ -973,7 +1130,7 @@ impl Tty {
             None,
             allocator.clone(),
             GbmFramebufferExporter::new(device.gbm.clone()),
-            SUPPORTED_COLOR_FORMATS,
+            color_formats.iter().copied(),
             // This is only used to pick a good internal format, so it can use the surface's render
             // formats, even though we only ever render on the primary GPU.
             render_formats.clone(),
@@ -1003,7 +1160,7 @@ impl Tty {
                     None,
                     allocator,
                     GbmFramebufferExporter::new(device.gbm.clone()),
-                    SUPPORTED_COLOR_FORMATS,
+                    color_formats.iter().copied(),
                     render_formats,
                     device.drm.cursor_size(),
                     Some(device.gbm.clone()),
@@ -1068,6 +1225,9 @@ impl Tty {
             time_since_presentation_plot_name,
             presentation_misprediction_plot_name,
             sequence_delta_plot_name,
+            upscaler: Upscaler::default(),
+            desaturation: Desaturation::default(),
+            ten_bit,
         };
 
         let res = device.surfaces.insert(crtc, surface);
@@ -1273,6 +1433,10 @@ impl Tty {
                         misprediction_s * 1000.,
                     );
                 }
+
+                output_state
+                    .frame_clock
+                    .record_presentation(target_presentation_time, presentation_time);
             }
             Ok(None) => (),
             Err(err) => {
@@ -1390,9 +1554,52 @@ impl Tty {
             }
         };
 
+        // Go below the native resolution while frames keep missing their deadline.
+        let dynamic_resolution = self
+            .config
+            .borrow()
+            .outputs
+            .find(&surface.name)
+            .and_then(|config| config.dynamic_resolution);
+        let output_state = niri.output_state.get_mut(output).unwrap();
+        let frame_clock = &mut output_state.frame_clock;
+        frame_clock.set_min_render_scale(dynamic_resolution.map_or(1., |d| d.min_scale.0));
+        let render_scale = frame_clock.render_scale();
+        let saturation = niri.output_saturation(output) as f32;
+
         // Render the elements.
-        let mut elements =
-            niri.render::<TtyRenderer>(&mut renderer, output, true, RenderTarget::Output);
+        let mut offscreen_states = None;
+        let mut elements = if render_scale < 1. || saturation < 1. {
+            let sharpness = dynamic_resolution.map_or(0., |d| d.sharpness.0 as f32);
+            let res = render_offscreen(
+                niri,
+                &mut renderer,
+                output,
+                surface,
+                render_scale,
+                sharpness,
+                saturation,
+            );
+            match res {
+                Ok((elements, states)) => {
+                    offscreen_states = Some(states);
+                    elements
+                }
+                Err(err) => {
+                    warn!("error rendering the output offscreen: {err:?}");
+                    niri.render::<TtyRenderer>(&mut renderer, output, true, RenderTarget::Output)
+                }
+            }
+        } else {
+            niri.render::<TtyRenderer>(&mut renderer, output, true, RenderTarget::Output)
+        };
+
+        // Visualize the damage accumulated over time, if enabled.
+        if niri.debug_damage_heatmap {
+            let now = niri.clock.now_unadjusted();
+            let output_state = niri.output_state.get_mut(output).unwrap();
+            draw_damage_heatmap(&mut output_state.debug_damage_heatmap, now, &mut elements);
+        }
 
         // Visualize the damage, if enabled.
         if niri.debug_draw_damage {
@@ -1419,14 +1626,35 @@ impl Tty {
                 flags.remove(primary_scanout_flag);
                 flags.remove(FrameFlags::ALLOW_OVERLAY_PLANE_SCANOUT);
             }
-            if debug.disable_cursor_plane {
+
+            // Fall back to compositing the cursor on this output if the plane can't show it
+            // correctly.
+            let cursor_plane = if debug.disable_cursor_plane {
+                CursorPlane::Composited(CursorPlaneFallback::Disabled)
+            } else {
+                match niri.cursor_plane_fallback(output, device.drm.cursor_size()) {
+                    Some(reason) => CursorPlane::Composited(reason),
+                    None => CursorPlane::Hardware,
+                }
+            };
+            if cursor_plane != CursorPlane::Hardware {
                 flags.remove(FrameFlags::ALLOW_CURSOR_PLANE_SCANOUT);
             }
 
+            let output_state = niri.output_state.get_mut(output).unwrap();
+            if output_state.cursor_plane != cursor_plane {
+                debug!(
+                    "cursor plane for {} changed to {cursor_plane:?}",
+                    surface.name.connector
+                );
+                output_state.cursor_plane = cursor_plane;
+            }
+
             flags
         };
 
         // Hand them over to the DRM.
+        let mut check_gpu_reset = false;
         let drm_compositor = &mut surface.compositor;
         match drm_compositor.render_frame::<_, _>(&mut renderer, &elements, [0.; 4], flags) {
             Ok(res) => {
@@ -1445,14 +1673,17 @@ impl Tty {
                     }
                 }
 
-                niri.update_primary_scanout_output(output, &res.states);
+                // When rendering below the native resolution or in grayscale, the surfaces are in
+                // the offscreen.
+                let states = offscreen_states.as_ref().unwrap_or(&res.states);
+
+                niri.update_primary_scanout_output(output, states);
                 if let Some(dmabuf_feedback) = surface.dmabuf_feedback.as_ref() {
-                    niri.send_dmabuf_feedbacks(output, dmabuf_feedback, &res.states);
+                    niri.send_dmabuf_feedbacks(output, dmabuf_feedback, states);
                 }
 
                 if !res.is_empty {
-                    let presentation_feedbacks =
-                        niri.take_presentation_feedbacks(output, &res.states);
+                    let presentation_feedbacks = niri.take_presentation_feedbacks(output, states);
                     let data = (presentation_feedbacks, target_presentation_time);
 
                     match drm_compositor.queue_frame(data) {
@@ -1490,6 +1721,7 @@ impl Tty {
             Err(err) => {
                 // Can fail if we switched to a different TTY.
                 warn!("error rendering frame: {err}");
+                check_gpu_reset = true;
             }
         }
 
@@ -1499,6 +1731,12 @@ impl Tty {
         // Queue a timer to fire at the predicted vblank time.
         queue_estimated_vblank_timer(niri, output.clone(), target_presentation_time);
 
+        // Rendering also fails after a GPU reset, until we recreate the renderer.
+        drop(renderer);
+        if check_gpu_reset {
+            self.check_gpu_reset(niri);
+        }
+
         rv
     }
 
@@ -1671,12 +1909,27 @@ impl Tty {
                     })
                     .map(logical_output);
 
-                let id = device.known_crtcs.get(&crtc).map(|info| info.id);
+                let known = device.known_crtcs.get(&crtc);
+                let edid = known.and_then(|info| info.edid.clone());
+                let id = known.map(|info| info.id);
                 let id = id.unwrap_or_else(|| {
                     error!("crtc for connector {connector_name} missing from known");
                     OutputId::next()
                 });
 
+                let (dpi, suggested_scale) = match current_mode {
+                    Some(idx) => {
+                        let mode = &modes[idx];
+                        let (w, h) = physical_size.unwrap_or((0, 0));
+                        let size_mm = Size::from((w as i32, h as i32));
+                        let resolution =
+                            Size::from((i32::from(mode.width), i32::from(mode.height)));
+                        let scale = guess_monitor_scale(size_mm, resolution);
+                        (monitor_dpi(size_mm, resolution), Some(scale))
+                    }
+                    None => (None, None),
+                };
+
                 let ipc_output = niri_ipc::Output {
                     name: connector_name,
                     make: output_name.make.unwrap_or_else(|| "Unknown".into()),
@@ -1688,6 +1941,9 @@ impl Tty {
                     vrr_supported,
                     vrr_enabled,
                     logical,
+                    edid,
+                    dpi,
+                    suggested_scale,
                 };
 
                 ipc_outputs.insert(id, ipc_output);
@@ -1722,6 +1978,26 @@ impl Tty {
         }
     }
 
+    pub fn set_output_active(&mut self, output: &Output, active: bool) {
+        // Same as set_monitors_active(), but for a single output. The next rendered frame will
+        // turn the CRTC back on.
+        if active {
+            return;
+        }
+
+        let tty_state = output.user_data().get::<TtyOutputState>().unwrap();
+        let Some(device) = self.devices.get_mut(&tty_state.node) else {
+            return;
+        };
+        let Some(surface) = device.surfaces.get_mut(&tty_state.crtc) else {
+            return;
+        };
+
+        if let Err(err) = surface.compositor.clear() {
+            warn!("error clearing drm surface: {err:?}");
+        }
+    }
+
     pub fn set_output_on_demand_vrr(&mut self, niri: &mut Niri, output: &Output, enable_vrr: bool) {
         let _span = tracy_client::span!("Tty::set_output_on_demand_vrr");
 
@@ -1766,7 +2042,11 @@ impl Tty {
         let mut disable_laptop_panels = false;
         if niri.is_lid_closed {
             let config = self.config.borrow();
-            if !config.debug.keep_laptop_panel_on_when_lid_is_closed {
+            if config.logind.lid_switch == Some(LidSwitchPolicy::SwitchOffInternalOutput) {
+                disable_laptop_panels = true;
+            } else if config.logind.lid_switch != Some(LidSwitchPolicy::Ignore)
+                && !config.debug.keep_laptop_panel_on_when_lid_is_closed
+            {
                 // Check if any external monitor is connected.
                 'outer: for device in self.devices.values() {
                     for (connector, _crtc) in device.drm_scanner.crtcs() {
@@ -1804,6 +2084,13 @@ impl Tty {
                     continue;
                 };
 
+                // The color formats are picked when creating the DRM compositor, so reconnect.
+                if surface.ten_bit != config.is_10_bit() {
+                    to_disconnect.push((node, crtc));
+                    to_connect.push((node, connector.clone(), crtc, surface.name.clone()));
+                    continue;
+                }
+
                 let Some((mode, fallback)) = pick_mode(connector, config.mode) else {
                     warn!("couldn't pick mode for enabled connector");
                     continue;
@@ -1855,6 +2142,15 @@ impl Tty {
                 if change_mode {
                     if fallback {
                         let target = config.mode.unwrap();
+                        let edid = device.known_crtcs.get(&crtc).and_then(|i| i.edid.as_ref());
+                        if let Some(reason) = edid.and_then(|edid| mode_exceeds_edid(edid, target))
+                        {
+                            warn!(
+                                "output {:?}: configured mode is not supported \
+                                 by the monitor: {reason}",
+                                surface.name.connector,
+                            );
+                        }
                         warn!(
                             "output {:?}: configured mode {}x{}{} could not be found, \
                              falling back to preferred",
@@ -2295,6 +2591,46 @@ fn suspend() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Renders the output at `render_scale` of its resolution and `saturation` offscreen, and draws
+/// the result over the output.
+///
+/// Returns the elements along with the render states of the offscreen contents.
+fn render_offscreen<'render>(
+    niri: &Niri,
+    renderer: &mut TtyRenderer<'render>,
+    output: &Output,
+    surface: &Surface,
+    render_scale: f64,
+    sharpness: f32,
+    saturation: f32,
+) -> anyhow::Result<(
+    Vec<OutputRenderElements<TtyRenderer<'render>>>,
+    RenderElementStates,
+)> {
+    // Keep the pointer sharp and on the cursor plane by leaving it at the native resolution.
+    let mut elements = niri.pointer_element(renderer, output);
+    elements.extend(niri.extra_seat_pointer_elements(renderer, output));
+
+    let renderer = renderer.as_gles_renderer();
+    let contents = niri.render::<GlesRenderer>(renderer, output, false, RenderTarget::Output);
+
+    let scale = output.current_scale().fractional_scale();
+    let (elem, data) = if saturation < 1. {
+        // The desaturation shader takes the place of the sharpening one.
+        let scale = Scale::from(scale * render_scale);
+        let location = Point::from((0., 0.));
+        surface
+            .desaturation
+            .render(renderer, scale, location, saturation, 1., &contents)?
+    } else {
+        let upscaler = &surface.upscaler;
+        upscaler.render(renderer, scale, render_scale, sharpness, &contents)?
+    };
+    elements.push(OutputRenderElements::Offscreen(elem));
+
+    Ok((elements, data.states))
+}
+
 fn queue_estimated_vblank_timer(
     niri: &mut Niri,
     output: Output,
@@ -2518,13 +2854,9 @@ fn format_connector_name(connector: &connector::Info) -> String {
 }
 
 fn make_output_name(
-    device: &DrmDevice,
-    connector: connector::Handle,
+    info: Option<&libdisplay_info::info::Info>,
     connector_name: String,
 ) -> OutputName {
-    let info = get_edid_info(device, connector)
-        .map_err(|err| warn!("error getting EDID info for {connector_name}: {err:?}"))
-        .ok();
     OutputName {
         connector: connector_name,
         make: info.as_ref().and_then(|info| info.make()),
@@ -2532,3 +2864,81 @@ fn make_output_name(
         serial: info.as_ref().and_then(|info| info.serial()),
     }
 }
+
+fn make_output_edid(
+    info: &libdisplay_info::info::Info,
+    connector: &connector::Info,
+) -> niri_ipc::OutputEdid {
+    let modes = connector
+        .modes()
+        .iter()
+        .filter(|m| !m.flags().contains(ModeFlags::INTERLACE));
+    let max_resolution = modes
+        .clone()
+        .map(|m| m.size())
+        .max_by_key(|(w, h)| u32::from(*w) * u32::from(*h));
+    let max_refresh_rate = modes.map(|m| Mode::from(*m).refresh as u32).max();
+
+    // Luminance values of zero mean that the monitor didn't specify them.
+    let luminance = |value: f32| (value > 0.).then_some(f64::from(value));
+
+    let hdr = info.hdr_static_metadata();
+    let hdr = (hdr.pq || hdr.hlg || hdr.traditional_hdr).then(|| niri_ipc::HdrMetadata {
+        pq: hdr.pq,
+        hlg: hdr.hlg,
+        traditional_hdr: hdr.traditional_hdr,
+        max_luminance: luminance(hdr.desired_content_max_luminance),
+        max_frame_avg_luminance: luminance(hdr.desired_content_max_frame_avg_luminance),
+        min_luminance: luminance(hdr.desired_content_min_luminance),
+    });
+
+    let signal = info.supported_signal_colorimetry();
+    let colorimetry = [
+        (signal.bt2020_rgb, niri_ipc::Colorimetry::Bt2020Rgb),
+        (signal.bt2020_ycc, niri_ipc::Colorimetry::Bt2020Ycc),
+        (signal.bt2020_cycc, niri_ipc::Colorimetry::Bt2020Cycc),
+        (signal.st2113_rgb, niri_ipc::Colorimetry::St2113Rgb),
+        (signal.ictcp, niri_ipc::Colorimetry::Ictcp),
+    ]
+    .into_iter()
+    .filter_map(|(supported, colorimetry)| supported.then_some(colorimetry))
+    .collect();
+
+    niri_ipc::OutputEdid {
+        max_resolution,
+        max_refresh_rate,
+        hdr,
+        colorimetry,
+    }
+}
+
+/// Explains why a configured mode that isn't among the connector's modes can't work.
+///
+/// Compares the mode with the largest resolution and refresh rate among the connector's modes,
+/// and returns a human-readable reason if it's outside of them. This only adds detail to the
+/// fallback warning; the mode is rejected because it's missing from the list either way.
+fn mode_exceeds_edid(
+    edid: &niri_ipc::OutputEdid,
+    target: niri_ipc::ConfiguredMode,
+) -> Option<String> {
+    if let Some((max_w, max_h)) = edid.max_resolution {
+        if target.width > max_w || target.height > max_h {
+            return Some(format!(
+                "{}x{} exceeds the maximum resolution of {max_w}x{max_h}",
+                target.width, target.height,
+            ));
+        }
+    }
+
+    if let (Some(refresh), Some(max)) = (target.refresh, edid.max_refresh_rate) {
+        let max = max as f64 / 1000.;
+        // Allow for rounding in the user-specified value.
+        if refresh > max + 0.5 {
+            return Some(format!(
+                "{refresh} Hz exceeds the maximum refresh rate of {max:.3} Hz"
+            ));
+        }
+    }
+
+    None
+}
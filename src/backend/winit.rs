@@ -27,7 +27,7 @@ use smithay::wayland::presentation::Refresh; // 呈现刷新类型
 
 use super::{IpcOutputMap, OutputId, RenderResult}; // 父模块类型
 use crate::niri::{Niri, RedrawState, State}; // 主合成器状态
-use crate::render_helpers::debug::draw_damage; // 调试损伤可视化
+use crate::render_helpers::debug::{draw_damage, draw_damage_heatmap}; // 调试损伤可视化
 use crate::render_helpers::{resources, shaders, RenderTarget}; // 渲染辅助工具
 use crate::utils::{get_monotonic_time, logical_output}; // 实用函数
 
@@ -114,6 +114,9 @@ impl Winit {
                 vrr_supported: false, // 不支持VRR
                 vrr_enabled: false,
                 logical: Some(logical_output(&output)), // 逻辑位置信息
+                edid: None, // 嵌套窗口无法读取EDID
+                dpi: None,
+                suggested_scale: Some(1.),
             },
         )])));
 
@@ -250,6 +253,13 @@ impl Winit {
             RenderTarget::Output,
         );
 
+        // 调试：损伤热力图（累积一段时间内的损伤）
+        if niri.debug_damage_heatmap {
+            let now = niri.clock.now_unadjusted();
+            let output_state = niri.output_state.get_mut(output).unwrap();
+            draw_damage_heatmap(&mut output_state.debug_damage_heatmap, now, &mut elements);
+        }
+
         // 调试：可视化损伤区域
         if niri.debug_draw_damage {
             let output_state = niri.output_state.get_mut(output).unwrap();
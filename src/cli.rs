@@ -5,9 +5,9 @@
 use std::ffi::OsString;  // 操作系统字符串类型（兼容任意字符）
 use std::path::PathBuf;  // 路径对象
 
-use clap::{Parser, Subcommand};  // clap 宏库
+use clap::{Parser, Subcommand, ValueEnum};  // clap 宏库
 use clap_complete::Shell;  // Shell 补全支持
-use niri_ipc::{Action, OutputAction};  // IPC 动作类型
+use niri_ipc::{Action, InjectedInput, OutputAction};  // IPC 动作类型
 
 use crate::utils::version;  // 版本信息工具
 
@@ -42,11 +42,21 @@ pub struct Cli {
     #[arg(long)]
     pub session: bool,
 
+    /// 以安全模式启动：忽略配置文件，使用默认配置并关闭动画
+    ///
+    /// 从崩溃中恢复后可通过提示直接以此模式重启。
+    #[arg(long)]
+    pub safe_mode: bool,
+
     /// 合成器启动后执行的命令
     /// 特性：支持多个参数（如：--command firefox --new-window）
     #[arg(last = true)]  // 必须放在最后
     pub command: Vec<OsString>,
 
+    /// 渲染脚本化场景(RON)并在同目录写出 PNG，用于渲染回归测试
+    #[arg(long, hide = true)]
+    pub test_render_scenario: Option<PathBuf>,
+
     /// 子命令集合
     #[command(subcommand)]
     pub subcommand: Option<Sub>,
@@ -62,9 +72,15 @@ pub enum Sub {
         #[command(subcommand)]
         msg: Msg,
         
-        /// 以 JSON 格式输出结果
-        #[arg(short, long)]
+        /// 以 JSON 格式输出结果（等同于 `--format json`）
+        #[arg(short, long, conflicts_with = "format")]
         json: bool,
+
+        /// 输出格式
+        ///
+        /// table 和 csv 仅支持 windows、workspaces 和 outputs 查询。
+        #[arg(long)]
+        format: Option<MsgFormat>,
     },
     
     /// 验证配置文件语法
@@ -72,6 +88,9 @@ pub enum Sub {
         /// 配置文件路径（规则同主命令）
         #[arg(short, long)]
         config: Option<PathBuf>,
+        /// 与正在运行的 niri 实例的配置比较，打印将会变化的绑定、输出和窗口规则（不会应用）
+        #[arg(long)]
+        diff: bool,
     },
     
     /// 触发 panic（用于调试和测试）
@@ -81,6 +100,17 @@ pub enum Sub {
     Completions { shell: Shell },
 }
 
+/// `niri msg` 查询结果的输出格式
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MsgFormat {
+    /// 按列对齐的表格，便于阅读
+    Table,
+    /// CSV，便于脚本处理
+    Csv,
+    /// JSON，与 `--json` 相同
+    Json,
+}
+
 /// IPC 消息子命令枚举
 /// 作用：通过 niri msg 命令与合成器运行时交互
 #[derive(Subcommand)]
@@ -92,19 +122,39 @@ pub enum Msg {
     Workspaces,
     
     /// 列出所有打开的窗口
-    Windows,
+    Windows {
+        /// 保持连接，以每行一个 JSON 对象的形式持续输出窗口的打开、关闭、聚焦和标题变化
+        #[arg(long)]
+        watch: bool,
+    },
     
     /// 列出所有 layer-shell 表面（状态栏/通知等）
     Layers,
     
     /// 获取已配置的键盘布局
-    KeyboardLayouts,
+    KeyboardLayouts {
+        /// 只打印当前布局的名称（便于状态栏使用）
+        #[arg(long)]
+        current: bool,
+    },
     
     /// 打印当前聚焦的输出信息
     FocusedOutput,
     
     /// 打印当前聚焦的窗口信息
     FocusedWindow,
+
+    /// 按最近聚焦顺序列出窗口
+    FocusHistory,
+
+    /// 将窗口缩略图保存为 PNG 文件
+    WindowThumbnail {
+        /// 窗口 id（使用 `niri msg windows` 查看）
+        #[arg(long)]
+        id: u64,
+        /// PNG 文件的保存路径
+        path: PathBuf,
+    },
     
     /// 执行合成器动作（如切换工作区）
     Action {
@@ -112,6 +162,16 @@ pub enum Msg {
         #[command(subcommand)]
         action: Action,
     },
+
+    /// 一次性执行多个动作，中间状态不会显示或发送到事件流
+    ///
+    /// 动作之间用单独的 `;` 分隔（在 shell 中需要转义），例如：
+    /// `niri msg batch move-window-to-workspace 3 \; focus-monitor-right`
+    Batch {
+        /// 动作及其参数，语法与 `niri msg action` 相同
+        #[arg(required = true, allow_hyphen_values = true, trailing_var_arg = true)]
+        actions: Vec<String>,
+    },
     
     /// 临时更改输出配置（不修改配置文件）
     ///
@@ -137,6 +197,106 @@ pub enum Msg {
     
     /// 打印窗口概览状态
     OverviewState,
+
+    /// 列出互相冲突或被遮蔽的按键绑定
+    BindConflicts,
+
+    /// 列出客户端创建的虚拟输入设备
+    VirtualDevices,
+
+    /// 显示合成器状态
+    Status {
+        /// 显示每个输出的帧时序详细统计
+        #[arg(long)]
+        verbose: bool,
+    },
+
+    /// 列出受监管的自启动服务及其状态
+    Services,
+
+    /// 保存或恢复布局快照
+    Snapshot {
+        /// 具体快照命令
+        #[command(subcommand)]
+        snapshot: SnapshotMsg,
+    },
+
+    /// 在运行时读取或修改部分配置值（不修改配置文件）
+    Config {
+        /// 具体配置命令
+        #[command(subcommand)]
+        config: ConfigMsg,
+    },
+
+    /// 调试工具
+    Debug {
+        /// 具体调试命令
+        #[command(subcommand)]
+        debug: DebugMsg,
+    },
+}
+
+/// 调试子命令枚举
+#[derive(Subcommand)]
+pub enum DebugMsg {
+    /// 显示窗口匹配了哪些窗口规则以及最终解析出的规则
+    MatchRules {
+        /// 窗口 id（使用 `niri msg windows` 查看）
+        #[arg(long)]
+        id: u64,
+    },
+
+    /// 显示各输出的光标是否使用硬件光标平面，以及回退到合成光标的原因
+    CursorPlanes,
+
+    /// 注入合成的输入事件，用于客户端的自动化测试
+    ///
+    /// 仅在 headless 和 winit 后端可用，并且需要在配置中设置 debug { ipc-input-injection; }
+    Inject {
+        /// 要注入的事件
+        #[command(subcommand)]
+        input: InjectedInput,
+    },
+}
+
+/// 布局快照命令
+#[derive(Subcommand)]
+pub enum SnapshotMsg {
+    /// 将当前布局（工作区、列、窗口的 app id 和尺寸、浮动位置）保存到文件
+    ///
+    /// 保存到默认位置的快照会在 niri 下次启动时自动恢复一次，
+    /// 之后改名为 `layout-snapshot.restored.json`
+    Save {
+        /// 快照文件路径（默认：`$XDG_STATE_HOME/niri/layout-snapshot.json`）
+        path: Option<PathBuf>,
+    },
+    /// 恢复布局快照：之后一分钟内打开的窗口按 app id 放回保存的位置
+    ///
+    /// 已经打开的窗口不会移动
+    Restore {
+        /// 快照文件路径（默认：`$XDG_STATE_HOME/niri/layout-snapshot.json`）
+        path: Option<PathBuf>,
+    },
+}
+
+/// 运行时配置命令
+#[derive(Subcommand)]
+pub enum ConfigMsg {
+    /// 修改配置值，直到 niri 退出前一直覆盖配置文件中的值
+    ///
+    /// 支持的路径：layout.gaps、animations.off、animations.slowdown、
+    /// input.focus-follows-mouse、cursor.xcursor-size
+    Set {
+        /// 配置路径（如 `layout.gaps`）
+        path: String,
+        /// 新的值
+        value: String,
+    },
+    /// 打印配置值的当前值
+    Get {
+        /// 配置路径（如 `layout.gaps`）
+        path: String,
+    },
 }
 
 /* 命令行结构示意图：
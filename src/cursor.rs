@@ -231,7 +231,7 @@ impl CursorManager {
     }
 
     /// 创建后备光标（内置默认光标）
-    fn fallback_cursor() -> XCursor {
+    pub fn fallback_cursor() -> XCursor {
         // 创建单帧光标（32x32尺寸）
         let images = vec![Image {
             size: 32,
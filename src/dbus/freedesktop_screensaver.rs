@@ -0,0 +1,176 @@
+//! The freedesktop ScreenSaver interface.
+//!
+//! Video players and browsers use it to inhibit idling. Some apps also report activity through it
+//! or query how long the session has been idle, for example to show an "away" status.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use anyhow::Context;
+use calloop::channel::Sender;
+use zbus::blocking::fdo::DBusProxy;
+use zbus::blocking::Connection;
+use zbus::fdo;
+use zbus::message::Header;
+use zbus::names::{BusName, OwnedUniqueName};
+
+const NAME: &str = "org.freedesktop.ScreenSaver";
+/// Apps use either of these paths, so serve the interface on both.
+const PATHS: [&str; 2] = ["/org/freedesktop/ScreenSaver", "/ScreenSaver"];
+
+/// Requests from the ScreenSaver interface that need the compositor state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenSaverToNiri {
+    /// An app reported user activity, so reset the idle timer.
+    SimulateUserActivity,
+    /// The set of inhibitors changed.
+    InhibitedChanged,
+}
+
+pub struct ScreenSaver {
+    _conn: Connection,
+}
+
+#[derive(Default)]
+struct Inhibitors {
+    /// Inhibitor cookies and the bus names that hold them.
+    by_cookie: HashMap<u32, OwnedUniqueName>,
+    next_cookie: u32,
+}
+
+#[derive(Clone)]
+struct Interface {
+    inhibitors: Arc<Mutex<Inhibitors>>,
+    is_inhibited: Arc<AtomicBool>,
+    last_activity: Arc<Mutex<Instant>>,
+    to_niri: Sender<ScreenSaverToNiri>,
+}
+
+impl ScreenSaver {
+    /// Serves the interface on the session bus.
+    ///
+    /// `is_inhibited` is kept up to date with whether any app holds an inhibitor, and
+    /// `last_activity` is read to compute the session idle time.
+    pub fn start(
+        is_inhibited: Arc<AtomicBool>,
+        last_activity: Arc<Mutex<Instant>>,
+        to_niri: Sender<ScreenSaverToNiri>,
+    ) -> anyhow::Result<Self> {
+        let iface = Interface {
+            inhibitors: Arc::default(),
+            is_inhibited,
+            last_activity,
+            to_niri,
+        };
+
+        let mut builder = zbus::blocking::connection::Builder::session()?;
+        for path in PATHS {
+            builder = builder.serve_at(path, iface.clone())?;
+        }
+        let conn = builder
+            .name(NAME)?
+            .build()
+            .context("error serving the ScreenSaver interface")?;
+
+        // Drop the inhibitors of apps that exit without calling UnInhibit.
+        let dbus = DBusProxy::new(&conn).context("error creating the D-Bus proxy")?;
+        let name_owner_changed = dbus
+            .receive_name_owner_changed()
+            .context("error subscribing to NameOwnerChanged")?;
+        thread::Builder::new()
+            .name("ScreenSaver Inhibitors".to_owned())
+            .spawn(move || {
+                for signal in name_owner_changed {
+                    let Ok(args) = signal.args() else {
+                        continue;
+                    };
+                    if args.new_owner().is_some() {
+                        continue;
+                    }
+                    if let BusName::Unique(name) = args.name() {
+                        iface.remove_inhibitors_of(&OwnedUniqueName::from(name.to_owned()));
+                    }
+                }
+            })
+            .context("error spawning the ScreenSaver inhibitor thread")?;
+
+        Ok(Self { _conn: conn })
+    }
+}
+
+impl Interface {
+    fn remove_inhibitors_of(&self, name: &OwnedUniqueName) {
+        let mut inhibitors = self.inhibitors.lock().unwrap();
+        let len = inhibitors.by_cookie.len();
+        inhibitors.by_cookie.retain(|_, owner| owner != name);
+
+        if inhibitors.by_cookie.len() != len {
+            debug!("{name} disconnected, removing its inhibitors");
+            self.update_inhibited(&inhibitors);
+        }
+    }
+
+    fn update_inhibited(&self, inhibitors: &Inhibitors) {
+        let is_inhibited = !inhibitors.by_cookie.is_empty();
+        if self.is_inhibited.swap(is_inhibited, Ordering::SeqCst) != is_inhibited {
+            let _ = self.to_niri.send(ScreenSaverToNiri::InhibitedChanged);
+        }
+    }
+}
+
+#[zbus::interface(name = "org.freedesktop.ScreenSaver")]
+impl Interface {
+    fn inhibit(
+        &self,
+        #[zbus(header)] hdr: Header<'_>,
+        application_name: &str,
+        reason_for_inhibit: &str,
+    ) -> fdo::Result<u32> {
+        debug!("{application_name} inhibits idle: {reason_for_inhibit}");
+
+        let Some(name) = hdr.sender() else {
+            return Err(fdo::Error::Failed(String::from("no sender")));
+        };
+
+        let mut inhibitors = self.inhibitors.lock().unwrap();
+
+        // Cookie 0 is never handed out since some apps treat it as "no inhibitor".
+        let mut cookie = inhibitors.next_cookie;
+        while cookie == 0 || inhibitors.by_cookie.contains_key(&cookie) {
+            cookie = cookie.wrapping_add(1);
+        }
+        inhibitors.next_cookie = cookie.wrapping_add(1);
+
+        inhibitors.by_cookie.insert(cookie, name.to_owned().into());
+        self.update_inhibited(&inhibitors);
+
+        Ok(cookie)
+    }
+
+    fn un_inhibit(&self, cookie: u32) -> fdo::Result<()> {
+        debug!("removing inhibitor {cookie}");
+
+        let mut inhibitors = self.inhibitors.lock().unwrap();
+        if inhibitors.by_cookie.remove(&cookie).is_none() {
+            return Err(fdo::Error::InvalidArgs(format!("invalid cookie: {cookie}")));
+        }
+        self.update_inhibited(&inhibitors);
+
+        Ok(())
+    }
+
+    fn simulate_user_activity(&self) {
+        debug!("an app reported user activity");
+        let _ = self.to_niri.send(ScreenSaverToNiri::SimulateUserActivity);
+    }
+
+    /// Returns the number of seconds since the last user activity.
+    fn get_session_idle_time(&self) -> u32 {
+        let last_activity = *self.last_activity.lock().unwrap();
+        let idle_time = last_activity.elapsed().as_secs();
+        u32::try_from(idle_time).unwrap_or(u32::MAX)
+    }
+}
@@ -0,0 +1,245 @@
+//! Integration with systemd-logind.
+//!
+//! niri reports the session idle hint, forwards the session Lock/Unlock signals, and, when it
+//! handles the lid switch itself, holds a logind inhibitor lock so that logind doesn't act on the
+//! lid switch at the same time. It also sets the backlight brightness through logind, which
+//! doesn't need write access to sysfs.
+//!
+//! All calls are async and run on the event loop executor, so a slow or stuck system bus never
+//! blocks the compositor.
+
+use std::cell::RefCell;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use anyhow::Context;
+use calloop::channel::Sender;
+use calloop::futures::Scheduler;
+use futures_util::StreamExt as _;
+use zbus::zvariant::{OwnedFd, OwnedObjectPath};
+use zbus::{Connection, Proxy};
+
+const DESTINATION: &str = "org.freedesktop.login1";
+const MANAGER_PATH: &str = "/org/freedesktop/login1";
+const MANAGER_INTERFACE: &str = "org.freedesktop.login1.Manager";
+const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+/// Session signals that logind sends to the compositor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionSignal {
+    /// Something asked to lock the session, e.g. `loginctl lock-session`.
+    Lock,
+    /// Something asked to unlock the session, e.g. `loginctl unlock-session`.
+    Unlock,
+}
+
+pub enum LogindToNiri {
+    /// The connection to logind is ready.
+    Connected(Logind),
+    /// logind sent a session signal.
+    Signal(SessionSignal),
+}
+
+pub struct Logind {
+    scheduler: Scheduler<()>,
+    conn: Connection,
+    session: Proxy<'static>,
+    idle_hint: bool,
+    lid_switch: Rc<RefCell<LidSwitchInhibitor>>,
+}
+
+/// State of the lid switch inhibitor lock, shared with the calls that take it.
+#[derive(Default)]
+struct LidSwitchInhibitor {
+    /// Whether niri wants to hold the lock.
+    wanted: bool,
+    /// Held while niri handles the lid switch; dropping it releases the lock.
+    fd: Option<OwnedFd>,
+}
+
+/// Connects to logind in the background.
+///
+/// Once connected, sends [`LogindToNiri::Connected`] and then forwards the session signals.
+pub fn start(scheduler: &Scheduler<()>, to_niri: Sender<LogindToNiri>) {
+    let future = {
+        let scheduler = scheduler.clone();
+        async move {
+            let (logind, mut signals) = match Logind::connect(scheduler).await {
+                Ok(res) => res,
+                Err(err) => {
+                    warn!("error starting logind integration: {err:?}");
+                    return;
+                }
+            };
+
+            if to_niri.send(LogindToNiri::Connected(logind)).is_err() {
+                return;
+            }
+
+            while let Some(message) = signals.next().await {
+                let header = message.header();
+                let signal = match header.member().map(|member| member.as_str()) {
+                    Some("Lock") => SessionSignal::Lock,
+                    Some("Unlock") => SessionSignal::Unlock,
+                    _ => continue,
+                };
+
+                if to_niri.send(LogindToNiri::Signal(signal)).is_err() {
+                    break;
+                }
+            }
+        }
+    };
+
+    if let Err(err) = scheduler.schedule(future) {
+        warn!("error scheduling logind connection: {err:?}");
+    }
+}
+
+impl Logind {
+    async fn connect(
+        scheduler: Scheduler<()>,
+    ) -> anyhow::Result<(Self, zbus::proxy::SignalStream<'static>)> {
+        let conn = Connection::system()
+            .await
+            .context("error connecting to system bus")?;
+
+        // "auto" resolves to the session of the calling process, or to the user's display session
+        // if niri runs outside of it, e.g. as a systemd user service.
+        let reply = conn
+            .call_method(
+                Some(DESTINATION),
+                MANAGER_PATH,
+                Some(MANAGER_INTERFACE),
+                "GetSession",
+                &("auto"),
+            )
+            .await
+            .context("error getting the logind session")?;
+        let path: OwnedObjectPath = reply
+            .body()
+            .deserialize()
+            .context("error parsing the logind session path")?;
+        debug!("using logind session {}", path.as_str());
+
+        let session = Proxy::new(&conn, DESTINATION, path.into_inner(), SESSION_INTERFACE)
+            .await
+            .context("error creating the logind session proxy")?;
+
+        let signals = session
+            .receive_all_signals()
+            .await
+            .context("error subscribing to logind session signals")?;
+
+        let logind = Self {
+            scheduler,
+            conn,
+            session,
+            idle_hint: false,
+            lid_switch: Rc::default(),
+        };
+        Ok((logind, signals))
+    }
+
+    fn schedule(&self, future: impl Future<Output = ()> + 'static) {
+        if let Err(err) = self.scheduler.schedule(future) {
+            warn!("error scheduling logind call: {err:?}");
+        }
+    }
+
+    /// Tells logind whether the session is idle.
+    pub fn set_idle_hint(&mut self, idle: bool) {
+        if self.idle_hint == idle {
+            return;
+        }
+        self.idle_hint = idle;
+
+        let session = self.session.clone();
+        self.schedule(async move {
+            if let Err(err) = session.call_method("SetIdleHint", &(idle)).await {
+                warn!("error setting the session idle hint: {err:?}");
+            }
+        });
+    }
+
+    /// Sets the brightness of a `/sys/class/backlight` device.
+    ///
+    /// Falls back to writing `fallback_path` if logind fails.
+    pub fn set_backlight_brightness(&self, name: String, brightness: u32, fallback_path: PathBuf) {
+        let session = self.session.clone();
+        self.schedule(async move {
+            let res = session
+                .call_method("SetBrightness", &("backlight", name.as_str(), brightness))
+                .await;
+            let Err(err) = res else {
+                return;
+            };
+
+            debug!("error setting brightness via logind, using sysfs: {err:?}");
+            if let Err(err) = fs::write(&fallback_path, brightness.to_string()) {
+                warn!("error writing {fallback_path:?}: {err:?}");
+            }
+        });
+    }
+
+    /// Takes or releases the lid switch inhibitor lock.
+    pub fn set_handle_lid_switch(&mut self, handle: bool) {
+        let mut inhibitor = self.lid_switch.borrow_mut();
+        if inhibitor.wanted == handle {
+            return;
+        }
+        inhibitor.wanted = handle;
+
+        if !handle {
+            if inhibitor.fd.take().is_some() {
+                debug!("released the lid switch inhibitor");
+            }
+            return;
+        }
+        drop(inhibitor);
+
+        let conn = self.conn.clone();
+        let lid_switch = self.lid_switch.clone();
+        self.schedule(async move {
+            let res = inhibit_lid_switch(&conn).await;
+
+            // The config may have changed while waiting for the reply.
+            let mut inhibitor = lid_switch.borrow_mut();
+            match res {
+                Ok(fd) => {
+                    if inhibitor.wanted {
+                        debug!("took the lid switch inhibitor");
+                        inhibitor.fd = Some(fd);
+                    }
+                }
+                Err(err) => {
+                    warn!("error inhibiting logind lid switch handling: {err:?}");
+                    // Try again on the next config change.
+                    inhibitor.wanted = false;
+                }
+            }
+        });
+    }
+}
+
+async fn inhibit_lid_switch(conn: &Connection) -> anyhow::Result<OwnedFd> {
+    let reply = conn
+        .call_method(
+            Some(DESTINATION),
+            MANAGER_PATH,
+            Some(MANAGER_INTERFACE),
+            "Inhibit",
+            &(
+                "handle-lid-switch",
+                "niri",
+                "niri handles the lid switch according to its config",
+                "block",
+            ),
+        )
+        .await?;
+
+    let fd = reply.body().deserialize()?;
+    Ok(fd)
+}
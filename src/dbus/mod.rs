@@ -0,0 +1,3 @@
+pub mod freedesktop_screensaver;
+pub mod logind;
+pub mod mutter_idle_monitor;
@@ -0,0 +1,421 @@
+//! The Mutter IdleMonitor interface.
+//!
+//! GNOME apps and gnome-settings-daemon components use it for presence detection: they ask to be
+//! told when the user has been idle for some time, and when the user becomes active again.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use calloop::channel::Sender;
+use zbus::blocking::fdo::DBusProxy;
+use zbus::blocking::Connection;
+use zbus::fdo;
+use zbus::message::Header;
+use zbus::names::{BusName, OwnedUniqueName};
+
+const NAME: &str = "org.gnome.Mutter.IdleMonitor";
+/// Mutter exposes its monitors under this path through an ObjectManager, and some clients find
+/// the Core monitor that way rather than by its fixed path.
+const MANAGER_PATH: &str = "/org/gnome/Mutter/IdleMonitor";
+const PATH: &str = "/org/gnome/Mutter/IdleMonitor/Core";
+const INTERFACE: &str = "org.gnome.Mutter.IdleMonitor";
+
+/// Requests from the IdleMonitor interface that need the compositor state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleMonitorToNiri {
+    /// An app asked to reset the idle time, like user activity would.
+    ResetIdletime,
+}
+
+pub struct IdleMonitor {
+    _conn: Connection,
+    shared: Arc<Shared>,
+}
+
+/// State shared between the interface, the watch thread and the compositor.
+#[derive(Default)]
+struct Shared {
+    watches: Mutex<Watches>,
+    /// Wakes up the watch thread when the watches change or the user becomes active.
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct Watches {
+    by_id: HashMap<u32, Watch>,
+    next_id: u32,
+    /// Whether there was user activity that the watch thread hasn't handled yet.
+    activity: bool,
+}
+
+struct Watch {
+    /// Bus name of the app that added the watch; `WatchFired` is only sent to it.
+    owner: OwnedUniqueName,
+    kind: WatchKind,
+}
+
+enum WatchKind {
+    /// Fires once the user has been idle for `interval`, then again after the next activity.
+    Idle { interval: Duration, fired: bool },
+    /// Fires once on the next user activity, then goes away.
+    UserActive,
+}
+
+struct Interface {
+    shared: Arc<Shared>,
+    last_activity: Arc<Mutex<Instant>>,
+    to_niri: Sender<IdleMonitorToNiri>,
+}
+
+impl IdleMonitor {
+    /// Serves the interface on the session bus.
+    ///
+    /// `last_activity` is read to compute the idle time, and [`Self::notify_activity()`] must be
+    /// called on every user activity.
+    pub fn start(
+        last_activity: Arc<Mutex<Instant>>,
+        to_niri: Sender<IdleMonitorToNiri>,
+    ) -> anyhow::Result<Self> {
+        let shared = Arc::new(Shared::default());
+        let iface = Interface {
+            shared: shared.clone(),
+            last_activity: last_activity.clone(),
+            to_niri,
+        };
+
+        let conn = zbus::blocking::connection::Builder::session()?
+            .serve_at(MANAGER_PATH, fdo::ObjectManager)?
+            .serve_at(PATH, iface)?
+            .name(NAME)?
+            .build()
+            .context("error serving the IdleMonitor interface")?;
+
+        // Drop the watches of apps that exit without calling RemoveWatch.
+        let dbus = DBusProxy::new(&conn).context("error creating the D-Bus proxy")?;
+        let name_owner_changed = dbus
+            .receive_name_owner_changed()
+            .context("error subscribing to NameOwnerChanged")?;
+        let watches_shared = shared.clone();
+        thread::Builder::new()
+            .name("IdleMonitor Owners".to_owned())
+            .spawn(move || {
+                for signal in name_owner_changed {
+                    let Ok(args) = signal.args() else {
+                        continue;
+                    };
+                    if args.new_owner().is_some() {
+                        continue;
+                    }
+                    if let BusName::Unique(name) = args.name() {
+                        let name = OwnedUniqueName::from(name.to_owned());
+                        let mut watches = watches_shared.watches.lock().unwrap();
+                        watches.by_id.retain(|_, watch| watch.owner != name);
+                    }
+                }
+            })
+            .context("error spawning the IdleMonitor owner thread")?;
+
+        let watch_conn = conn.clone();
+        let watch_shared = shared.clone();
+        thread::Builder::new()
+            .name("IdleMonitor Watches".to_owned())
+            .spawn(move || run_watches(&watch_conn, &watch_shared, &last_activity))
+            .context("error spawning the IdleMonitor watch thread")?;
+
+        Ok(Self {
+            _conn: conn,
+            shared,
+        })
+    }
+
+    /// Fires the user-active watches and re-arms the fired idle watches.
+    pub fn notify_activity(&self) {
+        let mut watches = self.shared.watches.lock().unwrap();
+        if watches.activity || !watches.wants_activity() {
+            return;
+        }
+
+        watches.activity = true;
+        self.shared.changed.notify_one();
+    }
+}
+
+impl Watches {
+    /// Whether the next user activity needs to be handled by the watch thread.
+    fn wants_activity(&self) -> bool {
+        self.by_id.values().any(|watch| match watch.kind {
+            WatchKind::Idle { fired, .. } => fired,
+            WatchKind::UserActive => true,
+        })
+    }
+
+    fn add(&mut self, owner: OwnedUniqueName, kind: WatchKind) -> u32 {
+        // Id 0 is never handed out, same as in Mutter.
+        let mut id = self.next_id;
+        while id == 0 || self.by_id.contains_key(&id) {
+            id = id.wrapping_add(1);
+        }
+        self.next_id = id.wrapping_add(1);
+
+        self.by_id.insert(id, Watch { owner, kind });
+        id
+    }
+
+    /// Removes a watch on behalf of `sender`, who must be the app that added it.
+    fn remove(&mut self, id: u32, sender: &OwnedUniqueName) -> fdo::Result<()> {
+        match self.by_id.get(&id) {
+            Some(watch) if watch.owner == *sender => {
+                self.by_id.remove(&id);
+                Ok(())
+            }
+            // Same error for both cases, so that apps can't probe the watches of others.
+            _ => Err(fdo::Error::InvalidArgs(format!("invalid watch id: {id}"))),
+        }
+    }
+
+    /// Marks the watches that fire at this idle time and returns them.
+    ///
+    /// Also returns how long until the next idle watch is due, if any.
+    fn fire(&mut self, idle_time: Duration) -> (Vec<(u32, OwnedUniqueName)>, Option<Duration>) {
+        let mut fired = Vec::new();
+
+        if self.activity {
+            self.activity = false;
+            self.by_id.retain(|id, watch| match &mut watch.kind {
+                WatchKind::Idle {
+                    fired: is_fired, ..
+                } => {
+                    *is_fired = false;
+                    true
+                }
+                WatchKind::UserActive => {
+                    fired.push((*id, watch.owner.clone()));
+                    false
+                }
+            });
+        }
+
+        let mut next_due: Option<Duration> = None;
+        for (id, watch) in &mut self.by_id {
+            let WatchKind::Idle {
+                interval,
+                fired: is_fired,
+            } = &mut watch.kind
+            else {
+                continue;
+            };
+            if *is_fired {
+                continue;
+            }
+
+            if *interval <= idle_time {
+                *is_fired = true;
+                fired.push((*id, watch.owner.clone()));
+            } else {
+                let due = *interval - idle_time;
+                next_due = Some(next_due.map_or(due, |next| next.min(due)));
+            }
+        }
+
+        (fired, next_due)
+    }
+}
+
+/// Fires the watches as they become due, emitting `WatchFired` to their owners.
+fn run_watches(conn: &Connection, shared: &Shared, last_activity: &Mutex<Instant>) {
+    let mut watches = shared.watches.lock().unwrap();
+    loop {
+        let idle_time = last_activity.lock().unwrap().elapsed();
+        let (fired, next_due) = watches.fire(idle_time);
+
+        if !fired.is_empty() {
+            // Don't hold the lock while talking to the bus. Go around once more afterwards since
+            // the watches could've changed in the meantime.
+            drop(watches);
+            for (id, owner) in fired {
+                trace!("firing idle monitor watch {id}");
+                if let Err(err) =
+                    conn.emit_signal(Some(owner.as_str()), PATH, INTERFACE, "WatchFired", &(id,))
+                {
+                    warn!("error emitting WatchFired: {err:?}");
+                }
+            }
+            watches = shared.watches.lock().unwrap();
+            continue;
+        }
+
+        watches = match next_due {
+            Some(timeout) => shared.changed.wait_timeout(watches, timeout).unwrap().0,
+            None => shared.changed.wait(watches).unwrap(),
+        };
+    }
+}
+
+#[zbus::interface(name = "org.gnome.Mutter.IdleMonitor")]
+impl Interface {
+    /// Returns the number of milliseconds since the last user activity.
+    fn get_idletime(&self) -> u64 {
+        let last_activity = *self.last_activity.lock().unwrap();
+        u64::try_from(last_activity.elapsed().as_millis()).unwrap_or(u64::MAX)
+    }
+
+    fn add_idle_watch(&self, #[zbus(header)] hdr: Header<'_>, interval: u64) -> fdo::Result<u32> {
+        if interval == 0 {
+            return Err(fdo::Error::InvalidArgs(String::from(
+                "interval must be greater than zero",
+            )));
+        }
+
+        let Some(owner) = hdr.sender() else {
+            return Err(fdo::Error::Failed(String::from("no sender")));
+        };
+
+        let kind = WatchKind::Idle {
+            interval: Duration::from_millis(interval),
+            fired: false,
+        };
+        let id = self.add_watch(owner.to_owned().into(), kind);
+        debug!("{owner} added idle watch {id} for {interval} ms");
+        Ok(id)
+    }
+
+    fn add_user_active_watch(&self, #[zbus(header)] hdr: Header<'_>) -> fdo::Result<u32> {
+        let Some(owner) = hdr.sender() else {
+            return Err(fdo::Error::Failed(String::from("no sender")));
+        };
+
+        let id = self.add_watch(owner.to_owned().into(), WatchKind::UserActive);
+        debug!("{owner} added user active watch {id}");
+        Ok(id)
+    }
+
+    fn remove_watch(&self, #[zbus(header)] hdr: Header<'_>, id: u32) -> fdo::Result<()> {
+        let Some(sender) = hdr.sender() else {
+            return Err(fdo::Error::Failed(String::from("no sender")));
+        };
+
+        debug!("{sender} is removing idle monitor watch {id}");
+
+        let mut watches = self.shared.watches.lock().unwrap();
+        watches.remove(id, &sender.to_owned().into())
+    }
+
+    fn reset_idletime(&self) {
+        debug!("an app reset the idle time");
+        let _ = self.to_niri.send(IdleMonitorToNiri::ResetIdletime);
+    }
+}
+
+impl Interface {
+    fn add_watch(&self, owner: OwnedUniqueName, kind: WatchKind) -> u32 {
+        let mut watches = self.shared.watches.lock().unwrap();
+        let id = watches.add(owner, kind);
+        self.shared.changed.notify_one();
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zbus::names::UniqueName;
+
+    use super::*;
+
+    fn owner(name: &str) -> OwnedUniqueName {
+        UniqueName::try_from(name).unwrap().to_owned().into()
+    }
+
+    fn idle(ms: u64) -> WatchKind {
+        WatchKind::Idle {
+            interval: Duration::from_millis(ms),
+            fired: false,
+        }
+    }
+
+    fn ids(fired: Vec<(u32, OwnedUniqueName)>) -> Vec<u32> {
+        let mut ids: Vec<_> = fired.into_iter().map(|(id, _)| id).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn idle_watches_fire_once_until_activity() {
+        let mut watches = Watches::default();
+        let short = watches.add(owner(":1.1"), idle(100));
+        let long = watches.add(owner(":1.2"), idle(300));
+
+        let (fired, next_due) = watches.fire(Duration::from_millis(50));
+        assert!(fired.is_empty());
+        assert_eq!(next_due, Some(Duration::from_millis(50)));
+
+        let (fired, next_due) = watches.fire(Duration::from_millis(100));
+        assert_eq!(fired, [(short, owner(":1.1"))]);
+        assert_eq!(next_due, Some(Duration::from_millis(200)));
+
+        // A fired watch doesn't fire again while the user stays idle.
+        let (fired, next_due) = watches.fire(Duration::from_millis(300));
+        assert_eq!(ids(fired), [long]);
+        assert_eq!(next_due, None);
+        let (fired, _) = watches.fire(Duration::from_millis(1000));
+        assert!(fired.is_empty());
+
+        // Activity re-arms both watches.
+        assert!(watches.wants_activity());
+        watches.activity = true;
+        let (fired, next_due) = watches.fire(Duration::ZERO);
+        assert!(fired.is_empty());
+        assert_eq!(next_due, Some(Duration::from_millis(100)));
+        assert!(!watches.wants_activity());
+
+        let (fired, _) = watches.fire(Duration::from_millis(300));
+        assert_eq!(ids(fired), [short, long]);
+    }
+
+    #[test]
+    fn user_active_watches_fire_once_on_activity() {
+        let mut watches = Watches::default();
+        let id = watches.add(owner(":1.1"), WatchKind::UserActive);
+        assert!(watches.wants_activity());
+
+        let (fired, next_due) = watches.fire(Duration::from_secs(10));
+        assert!(fired.is_empty());
+        assert_eq!(next_due, None);
+
+        watches.activity = true;
+        let (fired, _) = watches.fire(Duration::ZERO);
+        assert_eq!(fired, [(id, owner(":1.1"))]);
+
+        // The watch is gone after firing.
+        assert!(watches.by_id.is_empty());
+        watches.activity = true;
+        let (fired, _) = watches.fire(Duration::ZERO);
+        assert!(fired.is_empty());
+    }
+
+    #[test]
+    fn ids_skip_zero_and_taken() {
+        let mut watches = Watches {
+            next_id: u32::MAX,
+            ..Watches::default()
+        };
+        assert_eq!(watches.add(owner(":1.1"), idle(100)), u32::MAX);
+        // Wraps around past 0.
+        assert_eq!(watches.add(owner(":1.1"), idle(100)), 1);
+    }
+
+    #[test]
+    fn only_owner_removes_watch() {
+        let mut watches = Watches::default();
+        let id = watches.add(owner(":1.1"), idle(100));
+
+        assert!(watches.remove(id, &owner(":1.2")).is_err());
+        assert!(watches.by_id.contains_key(&id));
+
+        assert!(watches.remove(id + 1, &owner(":1.1")).is_err());
+        assert!(watches.remove(id, &owner(":1.1")).is_ok());
+        assert!(watches.by_id.is_empty());
+    }
+}
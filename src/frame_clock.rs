@@ -5,6 +5,7 @@
 //   - VRR (可变刷新率): 允许显示器动态调整刷新率的技术，减少画面撕裂
 //   - 呈现时间: 帧实际显示在屏幕上的时间点
 
+use std::collections::VecDeque;  // Rust标准库: 双端队列，用作最近帧误差的环形缓冲
 use std::num::NonZeroU64;  // Rust特性: 非零整数类型，优化内存布局并保证安全
 use std::time::Duration;   // Rust标准库: 表示时间跨度
 
@@ -20,6 +21,49 @@ pub struct FrameClock {
     
     // 是否启用可变刷新率(VRR)
     vrr: bool,  // Wayland概念: VRR允许动态调整刷新率匹配渲染速度
+
+    // 呈现时间统计，用于诊断卡顿
+    stats: FrameStats,
+
+    // 动态分辨率: 根据是否错过截止时间调整的渲染比例
+    render_scale: DynamicRenderScale,
+}
+
+// 在这么多帧内错过截止时间达到 LATE_FRAMES_TO_DOWNSCALE 次时降低渲染比例
+const LATE_FRAME_WINDOW: u32 = 30;
+const LATE_FRAMES_TO_DOWNSCALE: u32 = 3;
+// 连续按时呈现这么多帧后提高渲染比例，远多于降低所需的帧数以免来回跳动
+const ON_TIME_FRAMES_TO_UPSCALE: u32 = 180;
+// 每次调整渲染比例的步长
+const RENDER_SCALE_STEP: f64 = 0.125;
+
+// 动态分辨率控制器: 帧持续错过截止时间时以较低的内部分辨率渲染，稳定后逐步恢复
+#[derive(Debug)]
+struct DynamicRenderScale {
+    // 允许的最低渲染比例，为 1 时禁用
+    min_scale: f64,
+    // 当前渲染比例
+    scale: f64,
+    // 最近窗口内错过截止时间的帧数
+    late_frames: u32,
+    // 连续按时呈现的帧数
+    on_time_frames: u32,
+}
+
+// 参与均值和抖动计算的最近帧数
+const RECENT_FRAMES: usize = 120;
+
+// 帧呈现统计: 预测误差、错过的截止时间和丢帧
+#[derive(Debug, Default)]
+pub struct FrameStats {
+    // 有实际呈现时间的帧数
+    pub presented_frames: u64,
+    // 晚于目标VBlank呈现的帧数(合成器错过了截止时间)
+    pub missed_deadlines: u64,
+    // 因错过截止时间而跳过的VBlank数
+    pub dropped_frames: u64,
+    // 最近帧的预测误差(实际 - 预测)，单位微秒
+    recent_errors_us: VecDeque<f64>,
 }
 
 impl FrameClock {
@@ -42,9 +86,21 @@ impl FrameClock {
             last_presentation_time: None,  // 初始无历史呈现时间
             refresh_interval_ns,
             vrr,
+            stats: FrameStats::default(),
+            render_scale: DynamicRenderScale::new(),
         }
     }
 
+    // 设置动态分辨率允许的最低渲染比例，为 1 时禁用动态分辨率
+    pub fn set_min_render_scale(&mut self, min_scale: f64) {
+        self.render_scale.set_min_scale(min_scale);
+    }
+
+    // 当前帧应使用的渲染比例(相对原生分辨率)
+    pub fn render_scale(&self) -> f64 {
+        self.render_scale.scale
+    }
+
     // 获取当前刷新间隔
     pub fn refresh_interval(&self) -> Option<Duration> {
         // 将纳秒值转回Duration类型
@@ -80,6 +136,77 @@ impl FrameClock {
         self.last_presentation_time = Some(presentation_time);
     }
 
+    // 记录一帧的预测呈现时间与实际呈现时间，更新统计
+    pub fn record_presentation(&mut self, target: Duration, presentation_time: Duration) {
+        if presentation_time.is_zero() {
+            return;
+        }
+
+        let error_us = (presentation_time.as_secs_f64() - target.as_secs_f64()) * 1_000_000.;
+
+        let stats = &mut self.stats;
+        stats.presented_frames += 1;
+        if stats.recent_errors_us.len() == RECENT_FRAMES {
+            stats.recent_errors_us.pop_front();
+        }
+        stats.recent_errors_us.push_back(error_us);
+
+        // VRR下没有固定的VBlank，无法判断是否错过
+        if self.vrr {
+            return;
+        }
+        let Some(refresh_interval) = self.refresh_interval() else {
+            return;
+        };
+
+        // 落在目标之后的第几个VBlank(四舍五入以容忍时间戳误差)
+        let refresh_us = refresh_interval.as_secs_f64() * 1_000_000.;
+        let late_vblanks = (error_us / refresh_us).round();
+        self.render_scale.record(late_vblanks >= 1.);
+        if late_vblanks >= 1. {
+            stats.missed_deadlines += 1;
+            stats.dropped_frames += late_vblanks as u64;
+
+            trace!(
+                late_vblanks,
+                error_us,
+                "frame presented later than the target VBlank"
+            );
+        }
+    }
+
+    // 获取呈现统计
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    // 目标呈现时间为 timestamp 的提交现在是否可以应用: 此刻应用的提交最早在下一帧呈现
+    pub fn is_commit_due(&self, timestamp: Duration) -> bool {
+        timestamp <= self.next_presentation_time()
+    }
+
+    // 目标呈现时间为 timestamp 的提交最早可以应用的时刻，即 is_commit_due 开始返回 true 的时刻
+    //
+    // 也就是目标帧之前那次 VBlank 的时间；没有帧时钟信息时就是 timestamp 本身
+    pub fn commit_release_time(&self, timestamp: Duration) -> Duration {
+        let (Some(refresh_interval_ns), Some(last_presentation_time)) =
+            (self.refresh_interval_ns, self.last_presentation_time)
+        else {
+            return timestamp;
+        };
+
+        let release_time =
+            release_time_on_grid(timestamp, last_presentation_time, refresh_interval_ns.get());
+
+        // VRR 下超过一帧之后的下一帧随时可以呈现（见 next_presentation_time），
+        // 所以这样的提交要等到 timestamp 本身，否则会比目标更早显示
+        if self.vrr && release_time > last_presentation_time {
+            timestamp
+        } else {
+            release_time
+        }
+    }
+
     // 计算并返回下一帧的理想呈现时间
     pub fn next_presentation_time(&self) -> Duration {
         // 获取当前单调时间(不受系统时钟调整影响)
@@ -144,4 +271,170 @@ impl FrameClock {
             last_presentation_time + Duration::from_nanos(to_next_ns)
         }
     }
-}
\ No newline at end of file
+}
+
+// 在以 last_presentation_time 为起点、refresh_interval_ns 为间隔的 VBlank 网格上，
+// 找到第一个不早于 timestamp 的帧，返回它之前一个 VBlank 的时间
+fn release_time_on_grid(
+    timestamp: Duration,
+    last_presentation_time: Duration,
+    refresh_interval_ns: u64,
+) -> Duration {
+    let Some(ahead) = timestamp.checked_sub(last_presentation_time) else {
+        return last_presentation_time;
+    };
+    let ahead_ns = u64::try_from(ahead.as_nanos()).unwrap_or(u64::MAX);
+
+    // 目标帧是上次呈现之后的第 frames 帧（向上取整）
+    let frames = ahead_ns.div_ceil(refresh_interval_ns);
+    let release_ns = frames.saturating_sub(1).saturating_mul(refresh_interval_ns);
+    last_presentation_time + Duration::from_nanos(release_ns)
+}
+
+impl DynamicRenderScale {
+    fn new() -> Self {
+        Self {
+            min_scale: 1.,
+            scale: 1.,
+            late_frames: 0,
+            on_time_frames: 0,
+        }
+    }
+
+    fn set_min_scale(&mut self, min_scale: f64) {
+        let min_scale = min_scale.clamp(0.25, 1.);
+        if self.min_scale == min_scale {
+            return;
+        }
+
+        self.min_scale = min_scale;
+        self.scale = self.scale.max(min_scale);
+        self.late_frames = 0;
+        self.on_time_frames = 0;
+    }
+
+    // 记录一帧是否错过了截止时间，必要时调整渲染比例
+    fn record(&mut self, late: bool) {
+        if self.min_scale >= 1. {
+            return;
+        }
+
+        if late {
+            self.on_time_frames = 0;
+            self.late_frames += 1;
+            if self.late_frames >= LATE_FRAMES_TO_DOWNSCALE {
+                self.late_frames = 0;
+                self.scale = (self.scale - RENDER_SCALE_STEP).max(self.min_scale);
+                debug!("lowering render scale to {}", self.scale);
+            }
+        } else {
+            self.on_time_frames += 1;
+            // 偶尔错过一帧不算持续掉帧
+            if self.on_time_frames % LATE_FRAME_WINDOW == 0 {
+                self.late_frames = 0;
+            }
+            if self.on_time_frames >= ON_TIME_FRAMES_TO_UPSCALE && self.scale < 1. {
+                self.on_time_frames = 0;
+                self.scale = (self.scale + RENDER_SCALE_STEP).min(1.);
+                debug!("raising render scale to {}", self.scale);
+            }
+        }
+    }
+}
+
+impl FrameStats {
+    // 转换为IPC格式
+    pub fn to_ipc(&self) -> niri_ipc::FrameStats {
+        let errors = &self.recent_errors_us;
+        let count = errors.len().max(1) as f64;
+
+        let mean = errors.iter().sum::<f64>() / count;
+        let variance = errors.iter().map(|e| (e - mean).powi(2)).sum::<f64>() / count;
+        let max = errors.iter().fold(0., |max: f64, e| max.max(e.abs()));
+
+        niri_ipc::FrameStats {
+            presented_frames: self.presented_frames,
+            missed_deadlines: self.missed_deadlines,
+            dropped_frames: self.dropped_frames,
+            mean_prediction_error_us: mean,
+            prediction_jitter_us: variance.sqrt(),
+            max_prediction_error_us: max,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commit_release_on_vblank_grid() {
+        let ms = Duration::from_millis;
+        let interval = 10_000_000;
+
+        // 目标时间已过，立即放行
+        assert_eq!(release_time_on_grid(ms(90), ms(100), interval), ms(100));
+        assert_eq!(release_time_on_grid(ms(100), ms(100), interval), ms(100));
+        // 目标在下一帧之内: 上次 VBlank 之后就可以放行
+        assert_eq!(release_time_on_grid(ms(105), ms(100), interval), ms(100));
+        assert_eq!(release_time_on_grid(ms(110), ms(100), interval), ms(100));
+        // 目标在之后的帧: 在目标帧之前的那次 VBlank 放行
+        assert_eq!(release_time_on_grid(ms(111), ms(100), interval), ms(110));
+        assert_eq!(release_time_on_grid(ms(130), ms(100), interval), ms(120));
+    }
+
+    #[test]
+    fn commit_release_without_clock_info() {
+        let ms = Duration::from_millis;
+
+        let clock = FrameClock::new(None, false);
+        assert_eq!(clock.commit_release_time(ms(130)), ms(130));
+
+        let mut clock = FrameClock::new(Some(ms(10)), false);
+        assert_eq!(clock.commit_release_time(ms(130)), ms(130));
+        clock.presented(ms(100));
+        assert_eq!(clock.commit_release_time(ms(130)), ms(120));
+
+        // VRR 下空闲的输出可以随时呈现，所以要等到目标时间
+        let mut clock = FrameClock::new(Some(ms(10)), true);
+        clock.presented(ms(100));
+        assert_eq!(clock.commit_release_time(ms(105)), ms(100));
+        assert_eq!(clock.commit_release_time(ms(130)), ms(130));
+    }
+
+    #[test]
+    fn render_scale_hysteresis() {
+        let mut scale = DynamicRenderScale::new();
+
+        // 默认禁用
+        for _ in 0..10 {
+            scale.record(true);
+        }
+        assert_eq!(scale.scale, 1.);
+
+        scale.set_min_scale(0.5);
+
+        // 偶尔错过一帧不会改变渲染比例
+        for _ in 0..10 {
+            scale.record(true);
+            for _ in 0..LATE_FRAME_WINDOW {
+                scale.record(false);
+            }
+        }
+        assert_eq!(scale.scale, 1.);
+
+        // 持续错过截止时间时降到最低比例
+        for _ in 0..100 {
+            scale.record(true);
+        }
+        assert_eq!(scale.scale, 0.5);
+
+        // 只有长时间按时呈现后才会回升
+        for _ in 0..ON_TIME_FRAMES_TO_UPSCALE - 1 {
+            scale.record(false);
+        }
+        assert_eq!(scale.scale, 0.5);
+        scale.record(false);
+        assert_eq!(scale.scale, 0.625);
+    }
+}
@@ -8,14 +8,15 @@ use niri_ipc::PositionChange;
 use smithay::backend::renderer::utils::on_commit_buffer_handler;
 use smithay::input::pointer::{CursorImageStatus, CursorImageSurfaceData};
 use smithay::reexports::calloop::Interest;
+use smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1;
 use smithay::reexports::wayland_server::protocol::wl_buffer;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::{Client, Resource};
 use smithay::wayland::buffer::BufferHandler;
 use smithay::wayland::compositor::{
     add_blocker, add_pre_commit_hook, get_parent, is_sync_subsurface, remove_pre_commit_hook,
-    with_states, BufferAssignment, CompositorClientState, CompositorHandler, CompositorState,
-    SurfaceAttributes,
+    with_states, with_surface_tree_downward, BufferAssignment, CompositorClientState,
+    CompositorHandler, CompositorState, SurfaceAttributes, TraversalAction,
 };
 use smithay::wayland::dmabuf::get_dmabuf;
 use smithay::wayland::shell::xdg::XdgToplevelSurfaceData;
@@ -27,8 +28,10 @@ use crate::handlers::XDG_ACTIVATION_TOKEN_TIMEOUT;
 use crate::layout::{ActivateWindow, AddWindowTarget};
 use crate::niri::{ClientState, State};
 use crate::utils::transaction::Transaction;
-use crate::utils::{is_mapped, send_scale_transform};
-use crate::window::{InitialConfigureState, Mapped, ResolvedWindowRules, Unmapped};
+use crate::utils::{is_mapped, is_surface_transparent, send_scale_transform, with_toplevel_role};
+use crate::window::{
+    InitialConfigureState, MapSize, Mapped, ResolvedWindowRules, Unmapped, WindowRef,
+};
 
 // 实现CompositorHandler trait
 // 作用: 处理compositor协议的核心回调
@@ -64,6 +67,8 @@ impl CompositorHandler for State {
         let _span = tracy_client::span!("CompositorHandler::commit");
         trace!(surface = ?surface.id(), "commit");
 
+        // Check before on_commit_buffer_handler() takes the damage.
+        let has_damage = has_new_damage(surface);
         on_commit_buffer_handler::<Self>(surface);
         self.backend.early_import(surface);
 
@@ -86,13 +91,40 @@ impl CompositorHandler for State {
             if let Entry::Occupied(entry) = self.niri.unmapped_windows.entry(surface.clone()) {
                 if is_mapped(surface) {
                     // The toplevel got mapped.
+                    let unmapped = entry.into_mut();
+                    unmapped.window.on_commit();
+
+                    // Window rules can match on the size that the window maps with, so resolve
+                    // them again now that it's known.
+                    if let InitialConfigureState::Configured { output, .. } = &unmapped.state {
+                        let output = output.as_ref().or_else(|| self.niri.layout.active_output());
+                        let working_area = output
+                            .and_then(|o| self.niri.layout.monitor_for_output(o))
+                            .map(|mon| mon.working_area().size);
+                        unmapped.map_size = working_area.map(|working_area| {
+                            let size = unmapped.window.geometry().size;
+                            MapSize::new(unmapped.toplevel(), size, working_area)
+                        });
+
+                        let config = self.niri.config.borrow();
+                        let new_rules = ResolvedWindowRules::compute(
+                            &config.window_rules,
+                            WindowRef::Unmapped(unmapped),
+                        );
+                        if let InitialConfigureState::Configured { rules, .. } = &mut unmapped.state
+                        {
+                            *rules = new_rules;
+                        }
+                    }
+
                     let Unmapped {
                         window,
                         state,
                         activation_token_data,
-                    } = entry.remove();
-
-                    window.on_commit();
+                        map_size,
+                        restore_slot,
+                        is_at_startup,
+                    } = self.niri.unmapped_windows.remove(surface).unwrap();
 
                     let toplevel = window.toplevel().expect("no X11 support");
 
@@ -127,7 +159,19 @@ impl CompositorHandler for State {
                     // The GTK about dialog sets min/max size after the initial configure but
                     // before mapping, so we need to compute open_floating at the last possible
                     // moment, that is here.
-                    let is_floating = rules.compute_open_floating(toplevel);
+                    let is_floating = match &restore_slot {
+                        Some(slot) => slot.is_floating(),
+                        None => rules.compute_open_floating(toplevel),
+                    };
+
+                    // Apps that ignore the activation token still replace their launch
+                    // placeholder when they open a window.
+                    let app_id = with_toplevel_role(toplevel, |role| role.app_id.clone());
+                    if let Some(app_id) = &app_id {
+                        if self.niri.launch_placeholders.remove_app_id(app_id) {
+                            self.niri.queue_redraw_all();
+                        }
+                    }
 
                     // Figure out if we should activate the window.
                     let activate = rules.open_focused.map(|focus| {
@@ -171,16 +215,60 @@ impl CompositorHandler for State {
                         })
                         .map(|(mapped, _)| mapped.window.clone());
 
+                    // A server-side decorated window with transparent contents is just a border, so
+                    // only check windows that draw their own decorations.
+                    let skip_open_animation = rules.skip_open_animation.unwrap_or_else(|| {
+                        let is_csd = toplevel.current_state().decoration_mode
+                            != Some(zxdg_toplevel_decoration_v1::Mode::ServerSide);
+                        is_csd && is_surface_transparent(toplevel.wl_surface())
+                    });
+
                     // The mapped pre-commit hook deals with dma-bufs on its own.
                     self.remove_default_dmabuf_pre_commit_hook(toplevel.wl_surface());
                     let hook = add_mapped_toplevel_pre_commit_hook(toplevel);
-                    let mapped = Mapped::new(window, rules, hook);
+                    let clock = self.niri.clock.clone();
+                    let mapped = Mapped::new(window, rules, map_size, is_at_startup, hook, clock);
                     let window = mapped.window.clone();
+                    let id = mapped.id();
+
+                    // Windows spawned from a terminal with swallow-children take its place.
+                    let swallower = mapped
+                        .credentials()
+                        .filter(|_| parent.is_none() && restore_slot.is_none() && !is_floating)
+                        .and_then(|creds| self.niri.find_swallower(creds.pid));
+
+                    // Windows restored from a layout snapshot open next to the windows restored
+                    // before them, so that they end up in the saved columns.
+                    let restore_anchor = restore_slot.as_ref().and_then(|slot| {
+                        let restore = self.niri.layout_restore.as_ref()?;
+                        let (anchor, consume) =
+                            restore.anchor(slot, |id| self.niri.layout.has_window(id))?;
+                        Some((*anchor, consume))
+                    });
+                    let restore_workspace_id = restore_slot
+                        .as_ref()
+                        .and_then(|slot| self.niri.layout.workspace_for_restore(slot))
+                        .map(|(_, ws)| ws.id());
+
+                    // Apps with a free reserved slot open into it.
+                    let reserved_slot = app_id
+                        .as_deref()
+                        .filter(|_| restore_slot.is_none() && !is_floating)
+                        .and_then(|app_id| {
+                            let ws = self.niri.layout.workspace_with_free_reserved_slot(app_id)?;
+                            Some((ws.id(), app_id))
+                        });
 
                     let target = if let Some(p) = &parent {
                         // Open dialogs next to their parent window.
                         AddWindowTarget::NextTo(p)
-                    } else if let Some(id) = workspace_id {
+                    } else if let Some((anchor, _)) = &restore_anchor {
+                        AddWindowTarget::NextTo(anchor)
+                    } else if let Some(swallower) = &swallower {
+                        AddWindowTarget::NextTo(swallower)
+                    } else if let Some((workspace, app_id)) = reserved_slot {
+                        AddWindowTarget::ReservedSlot { workspace, app_id }
+                    } else if let Some(id) = restore_workspace_id.or(workspace_id) {
                         AddWindowTarget::Workspace(id)
                     } else if let Some(output) = &output {
                         AddWindowTarget::Output(output)
@@ -198,7 +286,28 @@ impl CompositorHandler for State {
                     );
 
                     if let Some(output) = output.cloned() {
-                        self.niri.layout.start_open_animation_for_window(&window);
+                        if let Some(swallower) = swallower {
+                            self.niri.swallow_window(swallower, id);
+                        }
+
+                        if let Some(slot) = restore_slot {
+                            if restore_anchor.is_some_and(|(_, consume)| consume) {
+                                self.niri.layout.consume_or_expel_window_left(Some(&id));
+                            }
+                            if let Some(pos) = slot.floating_pos {
+                                self.niri.layout.move_floating_window(
+                                    Some(&id),
+                                    PositionChange::SetFixed(pos.x),
+                                    PositionChange::SetFixed(pos.y),
+                                    false,
+                                );
+                            }
+                            self.niri.layout_restore_window_placed(&slot, id);
+                        }
+
+                        if !skip_open_animation {
+                            self.niri.layout.start_open_animation_for_window(&window);
+                        }
 
                         let new_focus = self.niri.layout.focus().map(|m| &m.window);
                         if new_focus == Some(&window) {
@@ -221,9 +330,40 @@ impl CompositorHandler for State {
                 return;
             }
 
+            // This is a commit of a toplevel hidden by a window that swallowed it.
+            let swallowed = self
+                .niri
+                .swallowed_windows
+                .iter()
+                .position(|(_, mapped)| mapped.is_wl_surface(surface));
+            if let Some(idx) = swallowed {
+                let window = self.niri.swallowed_windows[idx].1.window.clone();
+                window.on_commit();
+
+                if !is_mapped(surface) {
+                    // The hidden toplevel got unmapped. It won't come back in place of the window
+                    // that swallowed it, and must perform the initial commit-configure sequence
+                    // afresh.
+                    let (_, mapped) = self.niri.swallowed_windows.remove(idx);
+                    let id = mapped.id().get();
+                    drop(mapped);
+
+                    self.niri.focus_history.remove(&window);
+                    self.niri.thumbnails.remove(id);
+                    self.niri.cancel_pending_close(id);
+                    self.add_default_dmabuf_pre_commit_hook(surface);
+
+                    let unmapped = Unmapped::new(window, self.niri.is_at_startup);
+                    self.niri.unmapped_windows.insert(surface.clone(), unmapped);
+                }
+                return;
+            }
+
             // This is a commit of a previously-mapped root or a non-toplevel root.
             if let Some((mapped, output)) = self.niri.layout.find_window_and_output(surface) {
                 let window = mapped.window.clone();
+                let mapped_id = mapped.id();
+                let id = mapped_id.get();
                 let output = output.cloned();
 
                 // This is a commit of a previously-mapped toplevel.
@@ -233,10 +373,16 @@ impl CompositorHandler for State {
                 let transaction = Transaction::new();
                 if !is_mapped {
                     let blocker = transaction.blocker();
+                    let restores = self.niri.will_restore_swallowed_window(mapped_id);
                     self.backend.with_primary_renderer(|renderer| {
-                        self.niri
-                            .layout
-                            .start_close_animation_for_window(renderer, &window, blocker);
+                        let layout = &mut self.niri.layout;
+                        if restores {
+                            layout.start_close_animation_for_replaced_window(
+                                renderer, &window, blocker,
+                            );
+                        } else {
+                            layout.start_close_animation_for_window(renderer, &window, blocker);
+                        }
                     });
                 }
 
@@ -249,7 +395,23 @@ impl CompositorHandler for State {
                     let active_window = self.niri.layout.focus().map(|m| &m.window);
                     let was_active = active_window == Some(&window);
 
-                    self.niri.layout.remove_window(&window, transaction.clone());
+                    // A window that swallowed another one gives its place back.
+                    let restored = self.niri.restore_swallowed_window(mapped_id);
+                    let focus_target = if was_active && !restored {
+                        self.niri.window_to_focus_after_close(&window)
+                    } else {
+                        None
+                    };
+
+                    if !restored {
+                        self.niri.layout.remove_window(&window, transaction.clone());
+                    }
+                    if let Some(target) = focus_target {
+                        self.niri.layout.activate_window(&target);
+                    }
+                    self.niri.focus_history.remove(&window);
+                    self.niri.thumbnails.remove(id);
+                    self.niri.cancel_pending_close(id);
                     self.add_default_dmabuf_pre_commit_hook(surface);
 
                     // If this is the only instance, then this transaction will complete
@@ -264,7 +426,7 @@ impl CompositorHandler for State {
 
                     // Newly-unmapped toplevels must perform the initial commit-configure sequence
                     // afresh.
-                    let unmapped = Unmapped::new(window);
+                    let unmapped = Unmapped::new(window, self.niri.is_at_startup);
                     self.niri.unmapped_windows.insert(surface.clone(), unmapped);
 
                     if let Some(output) = output {
@@ -295,6 +457,9 @@ impl CompositorHandler for State {
 
                 // The toplevel remains mapped.
                 self.niri.layout.update_window(&window, serial);
+                if has_damage {
+                    self.niri.thumbnails.mark_damaged(id);
+                }
 
                 // Move the toplevel according to the attach offset.
                 if let Some(delta) = buffer_delta {
@@ -325,9 +490,13 @@ impl CompositorHandler for State {
         let root_window_output = self.niri.layout.find_window_and_output(&root_surface);
         if let Some((mapped, output)) = root_window_output {
             let window = mapped.window.clone();
+            let id = mapped.id().get();
             let output = output.cloned();
             window.on_commit();
             self.niri.layout.update_window(&window, None);
+            if has_damage {
+                self.niri.thumbnails.mark_damaged(id);
+            }
             if let Some(output) = output {
                 self.niri.queue_redraw(&output);
             }
@@ -513,3 +682,21 @@ impl State {
         }
     }
 }
+
+// 函数: has_new_damage
+// 作用: 判断提交是否给表面或其子表面带来了新的损坏区域
+// 只确认 configure 或请求帧回调的提交不会改变内容，缓存的窗口缩略图无需为它们重新渲染
+fn has_new_damage(surface: &WlSurface) -> bool {
+    let mut damaged = false;
+    with_surface_tree_downward(
+        surface,
+        (),
+        |_, _, _| TraversalAction::DoChildren(()),
+        |_, states, _| {
+            let mut guard = states.cached_state.get::<SurfaceAttributes>();
+            damaged |= !guard.current().damage.is_empty();
+        },
+        |_, _, _| !damaged,
+    );
+    damaged
+}
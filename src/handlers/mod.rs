@@ -6,6 +6,7 @@ mod compositor;
 mod layer_shell;
 mod xdg_shell;
 
+use std::collections::hash_map::Entry;
 use std::fs::File;
 use std::io::Write;
 use std::os::fd::OwnedFd;
@@ -13,21 +14,32 @@ use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
+use niri_config::{SelectionSyncDirection, SyncPrimary};
 use smithay::backend::allocator::dmabuf::Dmabuf;
 use smithay::backend::drm::DrmNode;
-use smithay::backend::input::{TabletToolDescriptor};
+use smithay::backend::input::{Event as _, InputEvent, TabletToolDescriptor};
 use smithay::desktop::{PopupKind, PopupManager};
 use smithay::input::pointer::{
    CursorImageStatus, CursorImageSurfaceData, PointerHandle,
 };
 use smithay::input::{keyboard, Seat, SeatHandler, SeatState};
 use smithay::output::Output;
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
 use smithay::reexports::rustix::fs::{fcntl_setfl, OFlags};
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
+use smithay::reexports::wayland_protocols_misc::zwp_virtual_keyboard_v1::server::zwp_virtual_keyboard_manager_v1::{
+    self, ZwpVirtualKeyboardManagerV1,
+};
+use smithay::reexports::wayland_protocols_misc::zwp_virtual_keyboard_v1::server::zwp_virtual_keyboard_v1::{
+    self, ZwpVirtualKeyboardV1,
+};
+use smithay::reexports::wayland_server::backend::ClientId;
 use smithay::reexports::wayland_server::protocol::wl_data_source::WlDataSource;
 use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
-use smithay::reexports::wayland_server::Resource;
+use smithay::reexports::wayland_server::{
+    delegate_global_dispatch, Client, DataInit, Dispatch, DisplayHandle, Resource,
+};
 use smithay::utils::{Logical, Point, Rectangle, Size};
 use smithay::wayland::compositor::{get_parent, with_states};
 use smithay::wayland::dmabuf::{DmabufGlobal, DmabufHandler, DmabufState, ImportNotifier};
@@ -47,23 +59,28 @@ use smithay::wayland::security_context::{
     SecurityContext, SecurityContextHandler, SecurityContextListenerSource,
 };
 use smithay::wayland::selection::data_device::{
-    set_data_device_focus, ClientDndGrabHandler, DataDeviceHandler, DataDeviceState,
-    ServerDndGrabHandler,
+    clear_data_device_selection, current_data_device_selection_userdata,
+    request_data_device_client_selection, set_data_device_focus, set_data_device_selection,
+    ClientDndGrabHandler, DataDeviceHandler, DataDeviceState, ServerDndGrabHandler,
 };
 use smithay::wayland::selection::ext_data_control::{
     DataControlHandler as ExtDataControlHandler, DataControlState as ExtDataControlState,
 };
 use smithay::wayland::selection::primary_selection::{
-    set_primary_focus, PrimarySelectionHandler, PrimarySelectionState,
+    clear_primary_selection, current_primary_selection_userdata, request_primary_client_selection,
+    set_primary_focus, set_primary_selection, PrimarySelectionHandler, PrimarySelectionState,
 };
 use smithay::wayland::selection::wlr_data_control::{
     DataControlHandler as WlrDataControlHandler, DataControlState as WlrDataControlState,
 };
-use smithay::wayland::selection::{SelectionHandler, SelectionTarget};
+use smithay::wayland::selection::{SelectionHandler, SelectionSource, SelectionTarget};
 use smithay::wayland::session_lock::{
     LockSurface,
 };
 use smithay::wayland::tablet_manager::TabletSeatHandler;
+use smithay::wayland::virtual_keyboard::{
+    VirtualKeyboardManagerGlobalData, VirtualKeyboardManagerState, VirtualKeyboardUserData,
+};
 use smithay::wayland::xdg_activation::{
     XdgActivationHandler, XdgActivationState, XdgActivationToken, XdgActivationTokenData,
 };
@@ -75,19 +92,28 @@ use smithay::{
     delegate_pointer_gestures, delegate_presentation, delegate_primary_selection,
     delegate_relative_pointer, delegate_seat, delegate_security_context, 
     delegate_single_pixel_buffer, delegate_tablet_manager, delegate_text_input_manager,
-    delegate_viewporter, delegate_virtual_keyboard_manager, delegate_xdg_activation,
+    delegate_viewporter, delegate_xdg_activation,
 };
 
+use crate::input::virtual_input::VirtualInputAccess;
 use crate::layout::ActivateWindow;
-use crate::niri::{DndIcon, NewClient, State};
+use crate::niri::{ClientSandbox, ClientState, DndIcon, NewClient, State};
+use crate::protocols::alpha_modifier::AlphaModifierHandler;
+use crate::protocols::commit_timing::{CommitTimer, CommitTimingHandler};
+use crate::protocols::fifo::FifoHandler;
 use crate::protocols::foreign_toplevel::{
     self, ForeignToplevelHandler, ForeignToplevelManagerState,
 };
 use crate::protocols::output_management::{OutputManagementHandler, OutputManagementManagerState};
-use crate::utils::{output_size, send_scale_transform, with_toplevel_role};
+use crate::protocols::virtual_pointer::{
+    VirtualPointer, VirtualPointerAxisEvent, VirtualPointerButtonEvent, VirtualPointerHandler,
+    VirtualPointerManagerState, VirtualPointerMotionAbsoluteEvent, VirtualPointerMotionEvent,
+};
+use crate::ui::virtual_input_prompt::PromptRequest;
+use crate::utils::{get_monotonic_time, output_size, send_scale_transform, with_toplevel_role};
 use crate::{
-    delegate_foreign_toplevel,
-    delegate_output_management,
+    delegate_alpha_modifier, delegate_commit_timing, delegate_fifo, delegate_foreign_toplevel,
+    delegate_output_management, delegate_virtual_pointer,
 };
 
 pub const XDG_ACTIVATION_TOKEN_TIMEOUT: Duration = Duration::from_secs(10);
@@ -101,7 +127,12 @@ impl SeatHandler for State {
         &mut self.niri.seat_state
     }
 
-    fn cursor_image(&mut self, _seat: &Seat<Self>,image: CursorImageStatus) {
+    fn cursor_image(&mut self, seat: &Seat<Self>,image: CursorImageStatus) {
+        // Extra seats always show the default cursor.
+        if *seat != self.niri.seat {
+            return;
+        }
+
         // FIXME: this hack should be removable once the screenshot UI is tracked with a
         // PointerFocus properly.
         self.niri.cursor_manager.set_cursor_image(image);
@@ -116,17 +147,13 @@ impl SeatHandler for State {
         set_primary_focus(dh, seat, client);
     }
 
-    fn led_state_changed(&mut self, _seat: &Seat<Self>, led_state: keyboard::LedState) {
-        let keyboards = self
-            .niri
-            .devices
-            .iter()
-            .filter(|device| device.has_capability(input::DeviceCapability::Keyboard))
-            .cloned();
-
-        for mut keyboard in keyboards {
-            keyboard.led_update(led_state.into());
+    fn led_state_changed(&mut self, seat: &Seat<Self>, led_state: keyboard::LedState) {
+        // The lock keys of extra seats don't drive the LEDs.
+        if *seat != self.niri.seat {
+            return;
         }
+
+        self.update_keyboard_leds(led_state);
     }
 }
 delegate_seat!(State);
@@ -271,22 +298,57 @@ impl KeyboardShortcutsInhibitHandler for State {
 
 delegate_input_method_manager!(State);
 delegate_keyboard_shortcuts_inhibit!(State);
-delegate_virtual_keyboard_manager!(State);
+
+/// Data of a selection set by the compositor.
+#[derive(Debug, Clone)]
+pub enum SelectionData {
+    /// Fixed contents, sent for every mime type.
+    Bytes(Arc<[u8]>),
+    /// Mirror of a client selection; requests are forwarded to the selection of this target.
+    Mirror(SelectionTarget),
+}
 
 impl SelectionHandler for State {
-    type SelectionUserData = Arc<[u8]>;
+    type SelectionUserData = SelectionData;
+
+    fn new_selection(
+        &mut self,
+        ty: SelectionTarget,
+        source: Option<SelectionSource>,
+        seat: Seat<Self>,
+    ) {
+        let mime_types = source.map(|source| source.mime_types()).unwrap_or_default();
+        self.sync_selection(ty, mime_types, seat);
+    }
 
     fn send_selection(
         &mut self,
         _ty: SelectionTarget,
-        _mime_type: String,
+        mime_type: String,
         fd: OwnedFd,
-        _seat: Seat<Self>,
+        seat: Seat<Self>,
         user_data: &Self::SelectionUserData,
     ) {
         let _span = tracy_client::span!("send_selection");
 
-        let buf = user_data.clone();
+        let buf = match user_data {
+            SelectionData::Bytes(buf) => buf.clone(),
+            SelectionData::Mirror(source) => {
+                let res = match source {
+                    SelectionTarget::Clipboard => {
+                        request_data_device_client_selection(&seat, mime_type, fd)
+                    }
+                    SelectionTarget::Primary => {
+                        request_primary_client_selection(&seat, mime_type, fd)
+                    }
+                };
+                if let Err(err) = res {
+                    debug!("error forwarding mirrored selection: {err:?}");
+                }
+                return;
+            }
+        };
+
         thread::spawn(move || {
             // Clear O_NONBLOCK, otherwise File::write_all() will stop halfway.
             if let Err(err) = fcntl_setfl(&fd, OFlags::empty()) {
@@ -451,10 +513,15 @@ impl SecurityContextHandler for State {
             .event_loop
             .insert_source(source, move |client, _, state| {
                 trace!("inserting a new restricted client, context={context:?}");
+                let sandbox = ClientSandbox {
+                    engine: context.sandbox_engine.clone(),
+                    app_id: context.app_id.clone(),
+                };
                 state.niri.insert_client(NewClient {
                     client,
                     restricted: true,
                     credentials_unknown: false,
+                    sandbox: Some(sandbox),
                 });
             })
             .unwrap();
@@ -587,6 +654,19 @@ delegate_drm_lease!(State);
 
 delegate_viewporter!(State);
 
+impl AlphaModifierHandler for State {}
+delegate_alpha_modifier!(State);
+
+impl FifoHandler for State {}
+delegate_fifo!(State);
+
+impl CommitTimingHandler for State {
+    fn commit_timer_queued(&mut self, timer: CommitTimer) {
+        self.niri.queue_commit_timer(timer);
+    }
+}
+delegate_commit_timing!(State);
+
 struct UrgentOnlyMarker;
 
 impl XdgActivationHandler for State {
@@ -640,6 +720,11 @@ impl XdgActivationHandler for State {
         token_data: XdgActivationTokenData,
         surface: WlSurface,
     ) {
+        // The app has started, so its launch placeholder is no longer needed.
+        if self.niri.launch_placeholders.remove_token(token.as_str()) {
+            self.niri.queue_redraw_all();
+        }
+
         if token_data.timestamp.elapsed() < XDG_ACTIVATION_TOKEN_TIMEOUT {
             if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(&surface) {
                 let window = mapped.window.clone();
@@ -676,4 +761,250 @@ impl OutputManagementHandler for State {
 }
 delegate_output_management!(State);
 
+impl VirtualPointerHandler for State {
+    fn virtual_pointer_manager_state(&mut self) -> &mut VirtualPointerManagerState {
+        &mut self.niri.virtual_pointer_state
+    }
+
+    fn create_virtual_pointer(&mut self, pointer: VirtualPointer) {
+        let client = pointer.client();
+        self.niri.virtual_pointers.insert(pointer);
+
+        if let Some(client) = client {
+            self.request_virtual_input_permission(&client);
+        }
+    }
+
+    fn destroy_virtual_pointer(&mut self, pointer: VirtualPointer) {
+        self.niri.virtual_pointers.remove(&pointer);
+    }
+
+    fn on_virtual_pointer_motion(&mut self, event: VirtualPointerMotionEvent) {
+        if self.virtual_pointer_is_allowed(&event.device()) {
+            self.process_input_event(InputEvent::PointerMotion { event });
+        }
+    }
+
+    fn on_virtual_pointer_motion_absolute(&mut self, event: VirtualPointerMotionAbsoluteEvent) {
+        if self.virtual_pointer_is_allowed(&event.device()) {
+            self.process_input_event(InputEvent::PointerMotionAbsolute { event });
+        }
+    }
+
+    fn on_virtual_pointer_button(&mut self, event: VirtualPointerButtonEvent) {
+        if self.virtual_pointer_is_allowed(&event.device()) {
+            self.process_input_event(InputEvent::PointerButton { event });
+        }
+    }
+
+    fn on_virtual_pointer_axis(&mut self, event: VirtualPointerAxisEvent) {
+        if self.virtual_pointer_is_allowed(&event.device()) {
+            self.process_input_event(InputEvent::PointerAxis { event });
+        }
+    }
+}
+delegate_virtual_pointer!(State);
+
+// Virtual keyboard events go straight to the focused client, so there's no way to hold them back
+// until the user answers the prompt. Instead, creating a virtual keyboard is checked here before
+// Smithay handles it, and refused until the client is allowed.
+impl Dispatch<ZwpVirtualKeyboardManagerV1, (), State> for State {
+    fn request(
+        state: &mut State,
+        client: &Client,
+        resource: &ZwpVirtualKeyboardManagerV1,
+        request: zwp_virtual_keyboard_manager_v1::Request,
+        data: &(),
+        dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        if let zwp_virtual_keyboard_manager_v1::Request::CreateVirtualKeyboard { .. } = &request {
+            match state.niri.virtual_input.access(client) {
+                VirtualInputAccess::Allowed => {
+                    *state.niri.virtual_keyboards.entry(client.id()).or_default() += 1;
+                }
+                VirtualInputAccess::Undecided => {
+                    state.request_virtual_input_permission(client);
+                    resource.post_error(
+                        zwp_virtual_keyboard_manager_v1::Error::Unauthorized,
+                        "virtual input is waiting for the user's permission, try again later",
+                    );
+                    return;
+                }
+                VirtualInputAccess::Denied => {
+                    resource.post_error(
+                        zwp_virtual_keyboard_manager_v1::Error::Unauthorized,
+                        "virtual input is not allowed for this client",
+                    );
+                    return;
+                }
+            }
+        }
+
+        <VirtualKeyboardManagerState as Dispatch<ZwpVirtualKeyboardManagerV1, (), State>>::request(
+            state, client, resource, request, data, dhandle, data_init,
+        );
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardV1, VirtualKeyboardUserData<State>, State> for State {
+    fn request(
+        state: &mut State,
+        client: &Client,
+        resource: &ZwpVirtualKeyboardV1,
+        request: zwp_virtual_keyboard_v1::Request,
+        data: &VirtualKeyboardUserData<State>,
+        dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, State>,
+    ) {
+        <VirtualKeyboardManagerState as Dispatch<
+            ZwpVirtualKeyboardV1,
+            VirtualKeyboardUserData<State>,
+            State,
+        >>::request(state, client, resource, request, data, dhandle, data_init);
+    }
+
+    fn destroyed(
+        state: &mut State,
+        client: ClientId,
+        resource: &ZwpVirtualKeyboardV1,
+        data: &VirtualKeyboardUserData<State>,
+    ) {
+        if let Entry::Occupied(mut entry) = state.niri.virtual_keyboards.entry(client.clone()) {
+            *entry.get_mut() -= 1;
+            if *entry.get() == 0 {
+                entry.remove();
+            }
+        }
+
+        <VirtualKeyboardManagerState as Dispatch<
+            ZwpVirtualKeyboardV1,
+            VirtualKeyboardUserData<State>,
+            State,
+        >>::destroyed(state, client, resource, data);
+    }
+}
+
+delegate_global_dispatch!(State: [
+    ZwpVirtualKeyboardManagerV1: VirtualKeyboardManagerGlobalData
+] => VirtualKeyboardManagerState);
+
+impl State {
+    fn virtual_pointer_is_allowed(&self, pointer: &VirtualPointer) -> bool {
+        pointer.client().is_some_and(|client| {
+            self.niri.virtual_input.access(&client) == VirtualInputAccess::Allowed
+        })
+    }
+
+    /// Shows the virtual input permission prompt for this client, unless it's already decided.
+    fn request_virtual_input_permission(&mut self, client: &Client) {
+        if self.niri.virtual_input.access(client) != VirtualInputAccess::Undecided {
+            return;
+        }
+
+        let process = client
+            .get_data::<ClientState>()
+            .and_then(|data| data.process.get());
+        let Some(process) = process else {
+            return;
+        };
+        let Some(executable) = process.executable.clone() else {
+            return;
+        };
+
+        let request = PromptRequest {
+            pid: process.pid,
+            executable,
+        };
+        if self.niri.virtual_input_prompt.request(request) {
+            self.niri.queue_redraw_all();
+        }
+    }
+
+    /// Mirrors a new client selection into the other selection target, if enabled in the config.
+    fn sync_selection(&mut self, from: SelectionTarget, mime_types: Vec<String>, seat: Seat<Self>) {
+        let Some(config) = self.niri.config.borrow().clipboard.sync_primary.clone() else {
+            return;
+        };
+
+        let enabled = match config.direction {
+            SelectionSyncDirection::ToClipboard => from == SelectionTarget::Primary,
+            SelectionSyncDirection::ToPrimary => from == SelectionTarget::Clipboard,
+            SelectionSyncDirection::Both => true,
+        };
+        if !enabled {
+            return;
+        }
+
+        if let Some(timer) = self.niri.selection_sync_timer.take() {
+            self.niri.event_loop.remove(timer);
+        }
+
+        // Coalesce fast changes, like the primary selection following a text selection drag.
+        let interval = Duration::from_millis(u64::from(config.min_interval_ms));
+        if let Some(last) = self.niri.selection_sync_last {
+            let elapsed = get_monotonic_time().saturating_sub(last);
+            if elapsed < interval {
+                let timer = self
+                    .niri
+                    .event_loop
+                    .insert_source(
+                        Timer::from_duration(interval - elapsed),
+                        move |_, _, state| {
+                            state.niri.selection_sync_timer = None;
+                            state.mirror_selection(from, mime_types.clone(), &seat, &config);
+                            TimeoutAction::Drop
+                        },
+                    )
+                    .unwrap();
+                self.niri.selection_sync_timer = Some(timer);
+                return;
+            }
+        }
+
+        self.mirror_selection(from, mime_types, &seat, &config);
+    }
+
+    fn mirror_selection(
+        &mut self,
+        from: SelectionTarget,
+        mime_types: Vec<String>,
+        seat: &Seat<Self>,
+        config: &SyncPrimary,
+    ) {
+        self.niri.selection_sync_last = Some(get_monotonic_time());
+
+        let mime_types: Vec<_> = mime_types
+            .into_iter()
+            .filter(|mime_type| config.allows_mime_type(mime_type))
+            .collect();
+
+        let dh = &self.niri.display_handle;
+        let data = SelectionData::Mirror(from);
+        match from {
+            SelectionTarget::Primary => {
+                if !mime_types.is_empty() {
+                    set_data_device_selection(dh, seat, mime_types, data);
+                } else if matches!(
+                    current_data_device_selection_userdata(seat),
+                    Some(SelectionData::Mirror(_))
+                ) {
+                    // Don't leave a mirror of a selection that is gone.
+                    clear_data_device_selection(dh, seat);
+                }
+            }
+            SelectionTarget::Clipboard => {
+                if !mime_types.is_empty() {
+                    set_primary_selection(dh, seat, mime_types, data);
+                } else if matches!(
+                    current_primary_selection_userdata(seat),
+                    Some(SelectionData::Mirror(_))
+                ) {
+                    clear_primary_selection(dh, seat);
+                }
+            }
+        }
+    }
+}
+
 delegate_single_pixel_buffer!(State);
@@ -38,11 +38,12 @@ use crate::input::resize_grab::ResizeGrab;
 use crate::input::touch_move_grab::TouchMoveGrab;
 use crate::input::touch_resize_grab::TouchResizeGrab;
 use crate::input::{PointerOrTouchStartData, DOUBLE_CLICK_TIME};
-use crate::layout::ActivateWindow;
+use crate::layout::{ActivateWindow, LayoutElement as _};
 use crate::niri::{PopupGrabState, State};
 use crate::utils::transaction::Transaction;
 use crate::utils::{
-    get_monotonic_time, output_matches_name, send_scale_transform, update_tiled_state, ResizeEdge,
+    get_monotonic_time, output_matches_name, send_scale_transform, update_tiled_state,
+    with_toplevel_role, ResizeEdge,
 };
 use crate::window::{InitialConfigureState, ResolvedWindowRules, Unmapped, WindowRef};
 
@@ -53,7 +54,8 @@ impl XdgShellHandler for State {
 
     fn new_toplevel(&mut self, surface: ToplevelSurface) {
         let wl_surface = surface.wl_surface().clone();
-        let unmapped = Unmapped::new(Window::new_wayland_window(surface));
+        let window = Window::new_wayland_window(surface);
+        let unmapped = Unmapped::new(window, self.niri.is_at_startup);
         let existing = self.niri.unmapped_windows.insert(wl_surface, unmapped);
         assert!(existing.is_none());
     }
@@ -148,7 +150,7 @@ impl XdgShellHandler for State {
 
         match start_data {
             PointerOrTouchStartData::Pointer(start_data) => {
-                let grab = MoveGrab::new(start_data, window, false);
+                let grab = MoveGrab::new(start_data, window, None);
                 pointer.set_grab(self, grab, serial, Focus::Clear);
             }
             PointerOrTouchStartData::Touch(start_data) => {
@@ -634,6 +636,22 @@ impl XdgShellHandler for State {
             return;
         }
 
+        let swallowed = self
+            .niri
+            .swallowed_windows
+            .iter()
+            .position(|(_, mapped)| mapped.is_wl_surface(surface.wl_surface()));
+        if let Some(idx) = swallowed {
+            // A window swallowed by another one got destroyed while hidden.
+            let (_, mapped) = self.niri.swallowed_windows.remove(idx);
+            let id = mapped.id().get();
+            self.niri.focus_history.remove(&mapped.window);
+            self.niri.thumbnails.remove(id);
+            self.niri.cancel_pending_close(id);
+            self.add_default_dmabuf_pre_commit_hook(surface.wl_surface());
+            return;
+        }
+
         let win_out = self
             .niri
             .layout
@@ -646,6 +664,8 @@ impl XdgShellHandler for State {
             return;
         };
         let window = mapped.window.clone();
+        let mapped_id = mapped.id();
+        let id = mapped_id.get();
         let output = output.cloned();
 
         self.backend.with_primary_renderer(|renderer| {
@@ -654,16 +674,36 @@ impl XdgShellHandler for State {
 
         let transaction = Transaction::new();
         let blocker = transaction.blocker();
+        let restores = self.niri.will_restore_swallowed_window(mapped_id);
         self.backend.with_primary_renderer(|renderer| {
-            self.niri
-                .layout
-                .start_close_animation_for_window(renderer, &window, blocker);
+            let layout = &mut self.niri.layout;
+            if restores {
+                layout.start_close_animation_for_replaced_window(renderer, &window, blocker);
+            } else {
+                layout.start_close_animation_for_window(renderer, &window, blocker);
+            }
         });
 
         let active_window = self.niri.layout.focus().map(|m| &m.window);
         let was_active = active_window == Some(&window);
 
-        self.niri.layout.remove_window(&window, transaction.clone());
+        // A window that swallowed another one gives its place back.
+        let restored = self.niri.restore_swallowed_window(mapped_id);
+        let focus_target = if was_active && !restored {
+            self.niri.window_to_focus_after_close(&window)
+        } else {
+            None
+        };
+
+        if !restored {
+            self.niri.layout.remove_window(&window, transaction.clone());
+        }
+        if let Some(target) = focus_target {
+            self.niri.layout.activate_window(&target);
+        }
+        self.niri.focus_history.remove(&window);
+        self.niri.thumbnails.remove(id);
+        self.niri.cancel_pending_close(id);
         self.add_default_dmabuf_pre_commit_hook(surface.wl_surface());
 
         // If this is the only instance, then this transaction will complete immediately, so no
@@ -781,24 +821,62 @@ impl State {
         };
 
         let config = self.niri.config.borrow();
-        let rules = ResolvedWindowRules::compute(
-            &config.window_rules,
-            WindowRef::Unmapped(unmapped),
-            self.niri.is_at_startup,
-        );
+        let rules =
+            ResolvedWindowRules::compute(&config.window_rules, WindowRef::Unmapped(unmapped));
+
+        // While a layout snapshot is being restored, windows take their saved place. Dialogs
+        // still open next to their parent.
+        if let Some(restore) = &mut self.niri.layout_restore {
+            if toplevel.parent().is_none() {
+                let app_id = with_toplevel_role(toplevel, |role| role.app_id.clone());
+                unmapped.restore_slot = app_id.and_then(|app_id| restore.take_slot(&app_id));
+            }
+        }
 
-        let Unmapped { window, state, .. } = unmapped;
+        let Unmapped {
+            window,
+            state,
+            restore_slot,
+            ..
+        } = unmapped;
 
         let InitialConfigureState::NotConfigured { wants_fullscreen } = state else {
             error!("window must not be already configured in send_initial_configure()");
             return;
         };
 
-        // Pick the target monitor. First, check if we had a workspace set in the window rules.
-        let mon = rules
-            .open_on_workspace
-            .as_deref()
-            .and_then(|name| self.niri.layout.monitor_for_workspace(name));
+        let restore_slot = restore_slot.as_ref();
+        let restore_target =
+            restore_slot.and_then(|slot| self.niri.layout.workspace_for_restore(slot));
+
+        // Windows of an app with a free reserved slot open on the workspace with that slot.
+        let reserved_slot_ws = if restore_slot.is_none()
+            && toplevel.parent().is_none()
+            && !rules.compute_open_floating(toplevel)
+        {
+            with_toplevel_role(toplevel, |role| role.app_id.clone())
+                .and_then(|app_id| self.niri.layout.workspace_with_free_reserved_slot(&app_id))
+        } else {
+            None
+        };
+
+        // Pick the target monitor. First, check if the window is restored into a snapshot slot.
+        let mon = restore_target.map(|(mon, _)| mon);
+
+        // If not, check if the window has a reserved slot.
+        let mon = mon.or_else(|| {
+            reserved_slot_ws
+                .and_then(|ws| ws.name())
+                .and_then(|name| self.niri.layout.monitor_for_workspace(name))
+        });
+
+        // If not, check if we had a workspace set in the window rules.
+        let mon = mon.or_else(|| {
+            rules
+                .open_on_workspace
+                .as_deref()
+                .and_then(|name| self.niri.layout.monitor_for_workspace(name))
+        });
 
         // If not, check if we had an output set in the window rules.
         let mon = mon.or_else(|| {
@@ -852,17 +930,28 @@ impl State {
         let mut floating_width = None;
         let mut height = None;
         let mut floating_height = None;
-        let is_full_width = rules.open_maximized.unwrap_or(false);
-        let is_floating = rules.compute_open_floating(toplevel);
+        let is_full_width = restore_slot.map_or_else(
+            || rules.open_maximized.unwrap_or(false),
+            |slot| slot.is_full_width,
+        );
+        let is_floating = restore_slot.map_or_else(
+            || rules.compute_open_floating(toplevel),
+            |slot| slot.is_floating(),
+        );
 
         // Tell the surface the preferred size and bounds for its likely output.
-        let ws = rules
-            .open_on_workspace
-            .as_deref()
-            .and_then(|name| mon.map(|mon| mon.find_named_workspace(name)))
-            .unwrap_or_else(|| {
-                mon.map(|mon| mon.active_workspace_ref())
-                    .or_else(|| self.niri.layout.active_workspace())
+        let ws = restore_target
+            .map(|(_, ws)| ws)
+            .or(reserved_slot_ws)
+            .or_else(|| {
+                rules
+                    .open_on_workspace
+                    .as_deref()
+                    .and_then(|name| mon.map(|mon| mon.find_named_workspace(name)))
+                    .unwrap_or_else(|| {
+                        mon.map(|mon| mon.active_workspace_ref())
+                            .or_else(|| self.niri.layout.active_workspace())
+                    })
             });
 
         if let Some(ws) = ws {
@@ -880,6 +969,13 @@ impl State {
             height = ws.resolve_default_height(rules.default_height, false);
             floating_height = ws.resolve_default_height(rules.default_height, true);
 
+            // Restored windows get their saved size.
+            if let Some(slot) = restore_slot {
+                width = Some(slot.width);
+                floating_width = Some(slot.width);
+                floating_height = Some(slot.height);
+            }
+
             let configure_width = if is_floating {
                 floating_width
             } else if is_full_width {
@@ -1091,11 +1187,8 @@ impl State {
         let window_rules = &config.window_rules;
 
         if let Some(unmapped) = self.niri.unmapped_windows.get_mut(toplevel.wl_surface()) {
-            let new_rules = ResolvedWindowRules::compute(
-                window_rules,
-                WindowRef::Unmapped(unmapped),
-                self.niri.is_at_startup,
-            );
+            let new_rules =
+                ResolvedWindowRules::compute(window_rules, WindowRef::Unmapped(unmapped));
             if let InitialConfigureState::Configured { rules, .. } = &mut unmapped.state {
                 *rules = new_rules;
             }
@@ -1104,7 +1197,7 @@ impl State {
             .layout
             .find_window_and_output_mut(toplevel.wl_surface())
         {
-            if mapped.recompute_window_rules(window_rules, self.niri.is_at_startup) {
+            if mapped.recompute_window_rules(window_rules) {
                 drop(config);
                 let output = output.cloned();
                 let window = mapped.window.clone();
@@ -3,6 +3,7 @@ use smithay::backend::input;
 use smithay::backend::winit::WinitVirtualDevice;
 use smithay::output::Output;
 
+use crate::input::injected::InjectedDevice;
 use crate::niri::State;
 use crate::protocols::virtual_pointer::VirtualPointer;
 
@@ -44,6 +45,13 @@ impl NiriInputDevice for WinitVirtualDevice {
     }
 }
 
+impl NiriInputDevice for InjectedDevice {
+    fn output(&self, _: &State) -> Option<Output> {
+        // Injected positions are in global coordinates.
+        None
+    }
+}
+
 impl NiriInputDevice for VirtualPointer {
     fn output(&self, _: &State) -> Option<Output> {
         self.output().cloned()
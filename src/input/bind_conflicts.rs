@@ -0,0 +1,314 @@
+use niri_config::{BindActivation, Binds, Key, ModKey, Modifiers, Trigger};
+use smithay::input::keyboard::xkb::keysym_get_name;
+use smithay::input::keyboard::{keysyms, Keysym};
+
+/// A problem with a configured bind that can make it never trigger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindConflict {
+    /// The affected bind.
+    pub key: Key,
+    pub kind: BindConflictKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindConflictKind {
+    /// An earlier bind resolves to the same trigger and modifiers, and always takes precedence.
+    ///
+    /// This happens when the same combination is written both with `Mod` and with the modifier
+    /// that `Mod` currently maps to, e.g. `Mod+T` and `Super+T`.
+    ShadowedBy(Key),
+    /// A hardcoded compositor bind, such as VT switching, intercepts this key first.
+    ShadowedByHardcoded,
+    /// The bind replaces the built-in `Mod`+drag interactive move.
+    OverridesInteractiveMove,
+    /// The bind replaces the built-in `Mod`+drag interactive resize.
+    OverridesInteractiveResize,
+    /// The bind replaces the built-in `Mod`+drag view scrolling.
+    OverridesViewScroll,
+}
+
+/// Analyzes the bind table for binds that are unreachable or shadow built-in behavior.
+///
+/// The conflicts are returned in config order.
+pub fn find_bind_conflicts(
+    binds: &Binds,
+    mod_key: ModKey,
+    disable_power_key_handling: bool,
+) -> Vec<BindConflict> {
+    let mut rv = Vec::new();
+    let mut seen: Vec<(Trigger, Modifiers, bool, Key)> = Vec::new();
+
+    for bind in &binds.0 {
+        let key = bind.key;
+        let mods = resolve_modifiers(mod_key, key.modifiers);
+
+        // Double-tap binds don't compete with press and release binds on the same key.
+        let is_double_tap = bind.activation == BindActivation::DoubleTap;
+        if is_double_tap {
+            if let Some((_, _, _, earlier)) = seen
+                .iter()
+                .find(|(trigger, _, double_tap, _)| *trigger == key.trigger && *double_tap)
+            {
+                rv.push(BindConflict {
+                    key,
+                    kind: BindConflictKind::ShadowedBy(*earlier),
+                });
+            } else {
+                seen.push((key.trigger, mods, true, key));
+            }
+            continue;
+        }
+
+        if let Some((_, _, _, earlier)) = seen.iter().find(|(trigger, m, double_tap, _)| {
+            *trigger == key.trigger && *m == mods && !*double_tap
+        }) {
+            rv.push(BindConflict {
+                key,
+                kind: BindConflictKind::ShadowedBy(*earlier),
+            });
+            continue;
+        }
+        seen.push((key.trigger, mods, false, key));
+
+        if let Trigger::Keysym(keysym) = key.trigger {
+            if is_hardcoded_keysym(keysym, disable_power_key_handling) {
+                rv.push(BindConflict {
+                    key,
+                    kind: BindConflictKind::ShadowedByHardcoded,
+                });
+                continue;
+            }
+        }
+
+        // The built-in mouse gestures trigger with the mod key held. Binds on exactly the mod key
+        // are checked first and therefore replace them.
+        if mods == mod_key.to_modifiers() {
+            let kind = match key.trigger {
+                Trigger::MouseLeft => Some(BindConflictKind::OverridesInteractiveMove),
+                Trigger::MouseRight => Some(BindConflictKind::OverridesInteractiveResize),
+                Trigger::MouseMiddle => Some(BindConflictKind::OverridesViewScroll),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                rv.push(BindConflict { key, kind });
+            }
+        }
+    }
+
+    rv
+}
+
+/// Resolves `Mod` to the modifier it currently maps to.
+fn resolve_modifiers(mod_key: ModKey, mut mods: Modifiers) -> Modifiers {
+    if mods.contains(Modifiers::COMPOSITOR) {
+        mods.remove(Modifiers::COMPOSITOR);
+        mods.insert(mod_key.to_modifiers());
+    }
+    mods
+}
+
+/// Whether this keysym is handled by a hardcoded bind before looking at the configured ones.
+///
+/// Must be kept in sync with `find_bind()`.
+fn is_hardcoded_keysym(keysym: Keysym, disable_power_key_handling: bool) -> bool {
+    use keysyms::*;
+
+    match keysym.raw() {
+        KEY_XF86Switch_VT_1..=KEY_XF86Switch_VT_12 => true,
+        KEY_XF86PowerOff => !disable_power_key_handling,
+        _ => false,
+    }
+}
+
+/// Formats a key the way it would be written in the config.
+pub fn format_key(key: Key) -> String {
+    let mut name = String::new();
+
+    let mods = [
+        (Modifiers::COMPOSITOR, "Mod"),
+        (Modifiers::SUPER, "Super"),
+        (Modifiers::CTRL, "Ctrl"),
+        (Modifiers::ALT, "Alt"),
+        (Modifiers::SHIFT, "Shift"),
+        (Modifiers::ISO_LEVEL3_SHIFT, "ISO_Level3_Shift"),
+        (Modifiers::ISO_LEVEL5_SHIFT, "ISO_Level5_Shift"),
+    ];
+    for (modifier, modifier_name) in mods {
+        if key.modifiers.contains(modifier) {
+            name.push_str(modifier_name);
+            name.push('+');
+        }
+    }
+
+    let trigger = match key.trigger {
+        Trigger::Keysym(keysym) => keysym_get_name(keysym),
+        Trigger::MouseLeft => String::from("MouseLeft"),
+        Trigger::MouseRight => String::from("MouseRight"),
+        Trigger::MouseMiddle => String::from("MouseMiddle"),
+        Trigger::MouseBack => String::from("MouseBack"),
+        Trigger::MouseForward => String::from("MouseForward"),
+        Trigger::WheelScrollDown => String::from("WheelScrollDown"),
+        Trigger::WheelScrollUp => String::from("WheelScrollUp"),
+        Trigger::WheelScrollLeft => String::from("WheelScrollLeft"),
+        Trigger::WheelScrollRight => String::from("WheelScrollRight"),
+        Trigger::TouchpadScrollDown => String::from("TouchpadScrollDown"),
+        Trigger::TouchpadScrollUp => String::from("TouchpadScrollUp"),
+        Trigger::TouchpadScrollLeft => String::from("TouchpadScrollLeft"),
+        Trigger::TouchpadScrollRight => String::from("TouchpadScrollRight"),
+        Trigger::PenButton1 => String::from("PenButton1"),
+        Trigger::PenButton2 => String::from("PenButton2"),
+        Trigger::PenButton3 => String::from("PenButton3"),
+    };
+    name.push_str(&trigger);
+
+    name
+}
+
+impl BindConflict {
+    pub fn to_ipc(&self) -> niri_ipc::BindConflict {
+        let kind = match self.kind {
+            BindConflictKind::ShadowedBy(key) => niri_ipc::BindConflictKind::ShadowedBy {
+                key: format_key(key),
+            },
+            BindConflictKind::ShadowedByHardcoded => {
+                niri_ipc::BindConflictKind::ShadowedByHardcoded
+            }
+            BindConflictKind::OverridesInteractiveMove => {
+                niri_ipc::BindConflictKind::OverridesInteractiveMove
+            }
+            BindConflictKind::OverridesInteractiveResize => {
+                niri_ipc::BindConflictKind::OverridesInteractiveResize
+            }
+            BindConflictKind::OverridesViewScroll => {
+                niri_ipc::BindConflictKind::OverridesViewScroll
+            }
+        };
+
+        niri_ipc::BindConflict {
+            key: format_key(self.key),
+            kind,
+        }
+    }
+
+    /// Human-readable description of the conflict.
+    pub fn describe(&self) -> String {
+        let key = format_key(self.key);
+        match self.kind {
+            BindConflictKind::ShadowedBy(other) => {
+                format!(
+                    "{key} is unreachable: {} is the same key",
+                    format_key(other)
+                )
+            }
+            BindConflictKind::ShadowedByHardcoded => {
+                format!("{key} is unreachable: it is handled by a hardcoded bind")
+            }
+            BindConflictKind::OverridesInteractiveMove => {
+                format!("{key} replaces the built-in interactive window move")
+            }
+            BindConflictKind::OverridesInteractiveResize => {
+                format!("{key} replaces the built-in interactive window resize")
+            }
+            BindConflictKind::OverridesViewScroll => {
+                format!("{key} replaces the built-in view scrolling")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use niri_config::{Action, Bind};
+
+    use super::*;
+
+    fn bind(key: &str) -> Bind {
+        Bind {
+            key: key.parse().unwrap(),
+            action: Action::CloseWindow,
+            activation: BindActivation::Press,
+            repeat: true,
+            repeat_delay: None,
+            repeat_rate: None,
+            cooldown: None,
+            allow_when_locked: false,
+            allow_inhibiting: true,
+            hotkey_overlay_title: None,
+        }
+    }
+
+    #[test]
+    fn mod_and_explicit_modifier_conflict() {
+        let binds = Binds(vec![bind("Mod+T"), bind("Super+T"), bind("Alt+T")]);
+
+        let conflicts = find_bind_conflicts(&binds, ModKey::Super, false);
+        assert_eq!(
+            conflicts,
+            vec![BindConflict {
+                key: "Super+T".parse().unwrap(),
+                kind: BindConflictKind::ShadowedBy("Mod+T".parse().unwrap()),
+            }]
+        );
+
+        // With a different mod key, these are distinct binds.
+        let conflicts = find_bind_conflicts(&binds, ModKey::Ctrl, false);
+        assert_eq!(conflicts, vec![]);
+    }
+
+    #[test]
+    fn hardcoded_binds() {
+        let binds = Binds(vec![bind("XF86PowerOff"), bind("XF86Switch_VT_3")]);
+
+        let conflicts = find_bind_conflicts(&binds, ModKey::Super, false);
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts
+            .iter()
+            .all(|c| c.kind == BindConflictKind::ShadowedByHardcoded));
+
+        // Power key handling can be disabled, making the bind reachable.
+        let conflicts = find_bind_conflicts(&binds, ModKey::Super, true);
+        assert_eq!(conflicts.len(), 1);
+    }
+
+    #[test]
+    fn builtin_mouse_gestures() {
+        let binds = Binds(vec![
+            bind("Mod+MouseLeft"),
+            bind("Mod+Shift+MouseLeft"),
+            bind("Super+MouseRight"),
+        ]);
+
+        let conflicts = find_bind_conflicts(&binds, ModKey::Super, false);
+        let kinds: Vec<_> = conflicts.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                BindConflictKind::OverridesInteractiveMove,
+                BindConflictKind::OverridesInteractiveResize,
+            ]
+        );
+    }
+
+    #[test]
+    fn double_tap_does_not_conflict_with_press() {
+        let mut double_tap = bind("Super_L");
+        double_tap.activation = BindActivation::DoubleTap;
+        let binds = Binds(vec![bind("Super_L"), double_tap]);
+
+        let conflicts = find_bind_conflicts(&binds, ModKey::Super, false);
+        assert_eq!(conflicts, vec![]);
+    }
+
+    #[test]
+    fn format_key_round_trips() {
+        for key in [
+            "Mod+Shift+T",
+            "Ctrl+Alt+Delete",
+            "Mod+WheelScrollDown",
+            "XF86AudioMute",
+        ] {
+            let parsed: Key = key.parse().unwrap();
+            assert_eq!(format_key(parsed).parse::<Key>().unwrap(), parsed);
+        }
+    }
+}
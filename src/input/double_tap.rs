@@ -0,0 +1,155 @@
+use smithay::backend::input::Keycode;
+
+/// Maximum time between the first press and the second release of a double tap.
+const DOUBLE_TAP_TIMEOUT_MS: u32 = 300;
+
+/// Recognizes double taps of a lone modifier key.
+///
+/// A tap is a press followed by a release of the same modifier with no other key in between.
+/// Modifier events are never suppressed, so clients still see both taps as regular modifier
+/// presses.
+#[derive(Debug, Default)]
+pub struct DoubleTapTracker {
+    state: State,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum State {
+    #[default]
+    Idle,
+    FirstDown {
+        key: Keycode,
+        start: u32,
+    },
+    FirstUp {
+        key: Keycode,
+        start: u32,
+    },
+    SecondDown {
+        key: Keycode,
+        start: u32,
+    },
+}
+
+impl DoubleTapTracker {
+    /// Feeds a key event, returns `true` when it completes a double tap.
+    pub fn on_key(
+        &mut self,
+        key_code: Keycode,
+        is_modifier: bool,
+        pressed: bool,
+        time: u32,
+    ) -> bool {
+        if !is_modifier {
+            self.reset();
+            return false;
+        }
+
+        let within_timeout = |start: u32| time.wrapping_sub(start) <= DOUBLE_TAP_TIMEOUT_MS;
+
+        let (state, tapped) = match (self.state, pressed) {
+            (State::FirstUp { key, start }, true) if key == key_code && within_timeout(start) => {
+                (State::SecondDown { key, start }, false)
+            }
+            // Another modifier is already held: this is a chord, not a tap.
+            (State::FirstDown { .. } | State::SecondDown { .. }, true) => (State::Idle, false),
+            (_, true) => (
+                State::FirstDown {
+                    key: key_code,
+                    start: time,
+                },
+                false,
+            ),
+            (State::FirstDown { key, start }, false) if key == key_code => {
+                (State::FirstUp { key, start }, false)
+            }
+            (State::SecondDown { key, start }, false) if key == key_code => {
+                (State::Idle, within_timeout(start))
+            }
+            (_, false) => (State::Idle, false),
+        };
+
+        self.state = state;
+        tapped
+    }
+
+    pub fn reset(&mut self) {
+        self.state = State::Idle;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUPER: Keycode = Keycode::new(133);
+    const SHIFT: Keycode = Keycode::new(50);
+    const A: Keycode = Keycode::new(38);
+
+    fn tap(tracker: &mut DoubleTapTracker, key: Keycode, is_modifier: bool, time: u32) -> bool {
+        let pressed = tracker.on_key(key, is_modifier, true, time);
+        let released = tracker.on_key(key, is_modifier, false, time + 50);
+        assert!(!pressed);
+        released
+    }
+
+    #[test]
+    fn double_tap() {
+        let mut tracker = DoubleTapTracker::default();
+        assert!(!tap(&mut tracker, SUPER, true, 0));
+        assert!(tap(&mut tracker, SUPER, true, 150));
+
+        // The third tap starts over.
+        assert!(!tap(&mut tracker, SUPER, true, 300));
+        assert!(tap(&mut tracker, SUPER, true, 400));
+    }
+
+    #[test]
+    fn too_slow() {
+        let mut tracker = DoubleTapTracker::default();
+        assert!(!tap(&mut tracker, SUPER, true, 0));
+        assert!(!tap(&mut tracker, SUPER, true, 400));
+        // The slow tap counts as the first tap of a new double tap.
+        assert!(tap(&mut tracker, SUPER, true, 500));
+    }
+
+    #[test]
+    fn timestamp_wraparound() {
+        let mut tracker = DoubleTapTracker::default();
+        assert!(!tap(&mut tracker, SUPER, true, u32::MAX - 100));
+        assert!(tap(&mut tracker, SUPER, true, 50));
+    }
+
+    #[test]
+    fn interrupted_by_other_key() {
+        let mut tracker = DoubleTapTracker::default();
+        assert!(!tap(&mut tracker, SUPER, true, 0));
+        assert!(!tap(&mut tracker, A, false, 50));
+        assert!(!tap(&mut tracker, SUPER, true, 100));
+
+        let mut tracker = DoubleTapTracker::default();
+        assert!(!tap(&mut tracker, SUPER, true, 0));
+        assert!(!tap(&mut tracker, SHIFT, true, 50));
+        assert!(!tap(&mut tracker, SUPER, true, 100));
+    }
+
+    #[test]
+    fn chord_is_not_a_tap() {
+        let mut tracker = DoubleTapTracker::default();
+        assert!(!tap(&mut tracker, SUPER, true, 0));
+
+        // Super+Shift chord.
+        assert!(!tracker.on_key(SUPER, true, true, 50));
+        assert!(!tracker.on_key(SHIFT, true, true, 60));
+        assert!(!tracker.on_key(SHIFT, true, false, 70));
+        assert!(!tracker.on_key(SUPER, true, false, 80));
+    }
+
+    #[test]
+    fn reset() {
+        let mut tracker = DoubleTapTracker::default();
+        assert!(!tap(&mut tracker, SUPER, true, 0));
+        tracker.reset();
+        assert!(!tap(&mut tracker, SUPER, true, 100));
+    }
+}
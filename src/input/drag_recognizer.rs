@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use niri_config::DragThreshold;
+use smithay::utils::{Logical, Point};
+
+/// Tells clicks apart from drags for pointer grabs that start on a button press.
+///
+/// The press turns into a drag once the pointer has moved far enough from where the button went
+/// down, and enough time has passed since then.
+#[derive(Debug, Clone, Copy)]
+pub struct DragRecognizer {
+    start_location: Point<f64, Logical>,
+    start_time: Duration,
+    threshold: DragThreshold,
+}
+
+impl DragRecognizer {
+    pub fn new(
+        threshold: DragThreshold,
+        start_location: Point<f64, Logical>,
+        start_time: Duration,
+    ) -> Self {
+        Self {
+            start_location,
+            start_time,
+            threshold,
+        }
+    }
+
+    /// Whether the pointer being at `location` at `time` makes the press a drag.
+    pub fn is_drag(&self, location: Point<f64, Logical>, time: Duration) -> bool {
+        let c = location - self.start_location;
+        let distance = self.threshold.distance.0;
+        let delay = Duration::from_millis(u64::from(self.threshold.delay_ms));
+
+        c.x * c.x + c.y * c.y >= distance * distance
+            && time.saturating_sub(self.start_time) >= delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use niri_config::FloatOrInt;
+
+    use super::*;
+
+    fn recognizer(distance: f64, delay_ms: u16) -> DragRecognizer {
+        let threshold = DragThreshold {
+            distance: FloatOrInt(distance),
+            delay_ms,
+        };
+        DragRecognizer::new(
+            threshold,
+            Point::from((0., 0.)),
+            Duration::from_millis(1000),
+        )
+    }
+
+    #[test]
+    fn distance_threshold() {
+        let r = recognizer(8., 0);
+        let now = Duration::from_millis(1000);
+        assert!(!r.is_drag(Point::from((5., 5.)), now));
+        assert!(r.is_drag(Point::from((6., 6.)), now));
+        assert!(r.is_drag(Point::from((0., -8.)), now));
+    }
+
+    #[test]
+    fn delay_threshold() {
+        let r = recognizer(8., 100);
+        let far = Point::from((20., 0.));
+        assert!(!r.is_drag(far, Duration::from_millis(1050)));
+        assert!(r.is_drag(far, Duration::from_millis(1100)));
+        // Timestamps from before the press don't count as a drag.
+        assert!(!r.is_drag(far, Duration::from_millis(900)));
+    }
+}
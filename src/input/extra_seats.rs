@@ -0,0 +1,478 @@
+//! Experimental support for extra seats.
+//!
+//! Input devices listed in a `seat` config section drive an extra seat with its own cursor and
+//! keyboard focus instead of the main seat, so that two people can use one machine. Extra seats
+//! are deliberately simple: they have no key bindings or gestures, and their keyboard focus
+//! follows clicks. They use the keyboard layout and repeat settings of the main seat, except for
+//! the xkb `file`.
+
+use std::cell::RefCell;
+use std::iter::zip;
+
+use niri_config::{Config, Xkb};
+use smithay::backend::input::{
+    AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, Event, InputBackend, InputEvent,
+    KeyboardKeyEvent, PointerAxisEvent, PointerButtonEvent, PointerMotionEvent,
+};
+use smithay::backend::renderer::element::Kind;
+use smithay::desktop::Window;
+use smithay::input::keyboard::FilterResult;
+use smithay::input::pointer::{AxisFrame, ButtonEvent, MotionEvent};
+use smithay::input::{Seat, SeatState};
+use smithay::reexports::wayland_server::DisplayHandle;
+use smithay::utils::{IsAlive, Logical, Point, Rectangle, Serial, Size, SERIAL_COUNTER};
+
+use crate::niri::State;
+use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::utils::center;
+
+/// Width of the focus ring around the window focused by an extra seat.
+const FOCUS_RING_WIDTH: f64 = 4.;
+
+pub struct ExtraSeats {
+    seats: Vec<ExtraSeat>,
+    /// Name of the main seat, which extra seats can't take.
+    main_seat_name: String,
+}
+
+pub struct ExtraSeat {
+    seat: Seat<State>,
+    config: niri_config::Seat,
+    /// Window with the keyboard focus of this seat.
+    focus: Option<Window>,
+    /// Focus ring edges: top, bottom, left and right.
+    ring: RefCell<[SolidColorBuffer; 4]>,
+}
+
+impl ExtraSeats {
+    /// Creates the seats from the config.
+    ///
+    /// Seats can't be added or removed later, since clients don't expect seats to come and go.
+    pub fn new(
+        seat_state: &mut SeatState<State>,
+        display: &DisplayHandle,
+        config: &Config,
+        main_seat_name: String,
+    ) -> Self {
+        let keyboard = &config.input.keyboard;
+        let repeat_delay = keyboard.repeat_delay.into();
+        let repeat_rate = keyboard.repeat_rate.into();
+
+        let seats = usable_configs(&config.seats, &main_seat_name)
+            .map(|seat_config| {
+                let mut seat = seat_state.new_wl_seat(display, &seat_config.name);
+
+                let xkb = keyboard.xkb.to_xkb_config();
+                if let Err(err) = seat.add_keyboard(xkb, repeat_delay, repeat_rate) {
+                    warn!(
+                        "error adding keyboard to seat {:?}, trying default keymap: {err:?}",
+                        seat_config.name
+                    );
+                    seat.add_keyboard(Default::default(), repeat_delay, repeat_rate)
+                        .unwrap();
+                }
+                seat.add_pointer();
+
+                ExtraSeat {
+                    seat,
+                    config: seat_config.clone(),
+                    focus: None,
+                    ring: Default::default(),
+                }
+            })
+            .collect();
+
+        Self {
+            seats,
+            main_seat_name,
+        }
+    }
+
+    /// Applies the changed device lists and colors of existing seats.
+    pub fn update_config(&mut self, configs: &[niri_config::Seat]) {
+        let configs: Vec<_> = usable_configs(configs, &self.main_seat_name).collect();
+
+        for seat in &mut self.seats {
+            match configs.iter().find(|c| c.name == seat.config.name) {
+                Some(config) => seat.config = (*config).clone(),
+                // The seat stays around until restart, but it no longer gets any input.
+                None => seat.config.devices.clear(),
+            }
+        }
+
+        for config in configs {
+            if !self
+                .seats
+                .iter()
+                .any(|seat| seat.config.name == config.name)
+            {
+                warn!("seat {:?} will be added after restarting niri", config.name);
+            }
+        }
+    }
+
+    /// Applies the changed key repeat settings to all seats.
+    pub fn change_repeat_info(&self, rate: i32, delay: i32) {
+        for seat in &self.seats {
+            let keyboard = seat.seat.get_keyboard().unwrap();
+            keyboard.change_repeat_info(rate, delay);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seats.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ExtraSeat> {
+        self.seats.iter()
+    }
+
+    /// Returns the index of the seat that the input device with this name belongs to.
+    pub fn seat_for_device(&self, name: &str) -> Option<usize> {
+        self.seats.iter().position(|seat| seat.has_device(name))
+    }
+}
+
+impl ExtraSeat {
+    fn has_device(&self, name: &str) -> bool {
+        self.config.devices.iter().any(|device| device.name == name)
+    }
+
+    pub fn pointer_location(&self) -> Point<f64, Logical> {
+        self.seat.get_pointer().unwrap().current_location()
+    }
+
+    /// Returns the window that this seat focused by clicking, if it's still around.
+    pub fn focus(&self) -> Option<&Window> {
+        self.focus.as_ref().filter(|window| window.alive())
+    }
+
+    /// Renders the focus ring around `rect`, the focused tile in output coordinates.
+    pub fn render_focus_ring(&self, rect: Rectangle<f64, Logical>) -> Vec<SolidColorRenderElement> {
+        let width = FOCUS_RING_WIDTH;
+        let horizontal = Size::from((rect.size.w + width * 2., width));
+        let vertical = Size::from((width, rect.size.h));
+        let edges = [
+            Rectangle::new(rect.loc - Point::from((width, width)), horizontal),
+            Rectangle::new(rect.loc + Point::from((-width, rect.size.h)), horizontal),
+            Rectangle::new(rect.loc - Point::from((width, 0.)), vertical),
+            Rectangle::new(rect.loc + Point::from((rect.size.w, 0.)), vertical),
+        ];
+
+        let color = self.config.focus_ring_color.to_array_premul();
+        let mut ring = self.ring.borrow_mut();
+        zip(ring.iter_mut(), edges)
+            .map(|(buffer, edge)| {
+                buffer.update(edge.size, color);
+                SolidColorRenderElement::from_buffer(buffer, edge.loc, 1., Kind::Unspecified)
+            })
+            .collect()
+    }
+}
+
+/// Returns the seat configs that can be used, skipping the ones with a taken name.
+///
+/// Clients tell seats apart by name, so an extra seat can't share it with the main seat or with
+/// another extra seat.
+fn usable_configs<'a>(
+    configs: &'a [niri_config::Seat],
+    main_seat_name: &'a str,
+) -> impl Iterator<Item = &'a niri_config::Seat> + 'a {
+    configs.iter().enumerate().filter_map(move |(idx, config)| {
+        if config.name == main_seat_name {
+            warn!(
+                "ignoring seat {:?}: this is the name of the main seat",
+                config.name
+            );
+            return None;
+        }
+
+        if configs[..idx].iter().any(|c| c.name == config.name) {
+            warn!(
+                "ignoring seat {:?}: a seat with this name already exists",
+                config.name
+            );
+            return None;
+        }
+
+        Some(config)
+    })
+}
+
+/// Keeps the pointer moving from `pos` to `new_pos` within the outputs.
+///
+/// Works like the main seat: a movement ending outside the outputs is clipped to the output that
+/// the pointer was on, and a pointer that wasn't on any output goes to the center of the first
+/// one. Returns `None` when there are no outputs.
+fn clip_to_outputs(
+    outputs: &[Rectangle<i32, Logical>],
+    pos: Point<f64, Logical>,
+    mut new_pos: Point<f64, Logical>,
+) -> Option<Point<f64, Logical>> {
+    let output_under =
+        |pos: Point<f64, Logical>| outputs.iter().find(|geo| geo.to_f64().contains(pos));
+
+    if output_under(new_pos).is_some() {
+        return Some(new_pos);
+    }
+
+    if let Some(geo) = output_under(pos) {
+        let geo = geo.to_f64();
+        new_pos.x = new_pos.x.clamp(geo.loc.x, geo.loc.x + geo.size.w - 1.);
+        new_pos.y = new_pos.y.clamp(geo.loc.y, geo.loc.y + geo.size.h - 1.);
+        return Some(new_pos);
+    }
+
+    outputs.first().map(|geo| center(*geo).to_f64())
+}
+
+impl State {
+    /// Returns the index of the extra seat that this event's device belongs to.
+    pub fn extra_seat_for_event<I: InputBackend>(&self, event: &InputEvent<I>) -> Option<usize> {
+        if self.niri.extra_seats.is_empty() {
+            return None;
+        }
+
+        let device = match event {
+            InputEvent::Keyboard { event } => event.device(),
+            InputEvent::PointerMotion { event } => event.device(),
+            InputEvent::PointerMotionAbsolute { event } => event.device(),
+            InputEvent::PointerButton { event } => event.device(),
+            InputEvent::PointerAxis { event } => event.device(),
+            _ => return None,
+        };
+
+        self.niri.extra_seats.seat_for_device(&device.name())
+    }
+
+    /// Forwards an event to the clients through an extra seat.
+    pub fn on_extra_seat_event<I: InputBackend>(&mut self, idx: usize, event: InputEvent<I>) {
+        let seat = self.niri.extra_seats.seats[idx].seat.clone();
+
+        match event {
+            InputEvent::Keyboard { event } => {
+                let keyboard = seat.get_keyboard().unwrap();
+                keyboard.input::<(), _>(
+                    self,
+                    event.key_code(),
+                    event.state(),
+                    SERIAL_COUNTER.next_serial(),
+                    event.time_msec(),
+                    |_, _, _| FilterResult::Forward,
+                );
+            }
+            InputEvent::PointerMotion { event } => {
+                let pos = seat.get_pointer().unwrap().current_location() + event.delta();
+                self.on_extra_seat_pointer_motion(&seat, pos, event.time_msec());
+            }
+            InputEvent::PointerMotionAbsolute { event } => {
+                let Some(geo) = self.global_bounding_rectangle() else {
+                    return;
+                };
+                let pos = event.position_transformed(geo.size) + geo.loc.to_f64();
+                self.on_extra_seat_pointer_motion(&seat, pos, event.time_msec());
+            }
+            InputEvent::PointerButton { event } => {
+                let pointer = seat.get_pointer().unwrap();
+                let serial = SERIAL_COUNTER.next_serial();
+
+                // Keyboard focus follows clicks.
+                if event.state() == ButtonState::Pressed {
+                    let location = pointer.current_location();
+                    if let Some(mapped) = self.niri.window_under(location) {
+                        let window = mapped.window.clone();
+                        self.focus_extra_seat_window(idx, window, serial);
+                    }
+                }
+
+                pointer.button(
+                    self,
+                    &ButtonEvent {
+                        button: event.button_code(),
+                        state: event.state(),
+                        serial,
+                        time: event.time_msec(),
+                    },
+                );
+                pointer.frame(self);
+            }
+            InputEvent::PointerAxis { event } => {
+                let source = event.source();
+                let mut frame = AxisFrame::new(event.time_msec()).source(source);
+                for axis in [Axis::Horizontal, Axis::Vertical] {
+                    let amount_v120 = event.amount_v120(axis);
+                    let amount = event
+                        .amount(axis)
+                        // Winit backend, discrete scrolling.
+                        .or_else(|| amount_v120.map(|v120| v120 / 120. * 15.))
+                        .unwrap_or(0.);
+
+                    if amount != 0. {
+                        frame = frame.relative_direction(axis, event.relative_direction(axis));
+                        frame = frame.value(axis, amount);
+                        if let Some(v120) = amount_v120 {
+                            frame = frame.v120(axis, v120 as i32);
+                        }
+                    } else if source == AxisSource::Finger && event.amount(axis) == Some(0.) {
+                        frame = frame.stop(axis);
+                    }
+                }
+
+                let pointer = seat.get_pointer().unwrap();
+                pointer.axis(self, frame);
+                pointer.frame(self);
+            }
+            _ => (),
+        }
+    }
+
+    fn on_extra_seat_pointer_motion(
+        &mut self,
+        seat: &Seat<State>,
+        new_pos: Point<f64, Logical>,
+        time: u32,
+    ) {
+        let pointer = seat.get_pointer().unwrap();
+        let old_pos = pointer.current_location();
+
+        let outputs: Vec<_> = self
+            .niri
+            .global_space
+            .outputs()
+            .filter_map(|output| self.niri.global_space.output_geometry(output))
+            .collect();
+        let Some(pos) = clip_to_outputs(&outputs, old_pos, new_pos) else {
+            return;
+        };
+
+        let under = self.niri.contents_under(pos);
+
+        pointer.motion(
+            self,
+            under.surface,
+            &MotionEvent {
+                location: pos,
+                serial: SERIAL_COUNTER.next_serial(),
+                time,
+            },
+        );
+        pointer.frame(self);
+
+        // Redraw the outputs that the cursor left and entered.
+        let outputs: Vec<_> = self
+            .niri
+            .global_space
+            .output_under(old_pos)
+            .chain(self.niri.global_space.output_under(pos))
+            .cloned()
+            .collect();
+        for output in outputs {
+            self.niri.queue_redraw(&output);
+        }
+    }
+
+    fn focus_extra_seat_window(&mut self, idx: usize, window: Window, serial: Serial) {
+        let extra = &mut self.niri.extra_seats.seats[idx];
+        if extra.focus() == Some(&window) {
+            return;
+        }
+
+        let surface = window
+            .toplevel()
+            .map(|toplevel| toplevel.wl_surface().clone());
+        let old_focus = extra.focus.replace(window.clone());
+
+        let keyboard = extra.seat.get_keyboard().unwrap();
+        keyboard.set_focus(self, surface, serial);
+
+        // Redraw the outputs that the focus ring moves between.
+        for window in old_focus.iter().chain([&window]) {
+            let Some(surface) = window.toplevel().map(|toplevel| toplevel.wl_surface()) else {
+                continue;
+            };
+            if let Some((_, Some(output))) = self.niri.layout.find_window_and_output(surface) {
+                let output = output.clone();
+                self.niri.queue_redraw(&output);
+            }
+        }
+    }
+
+    /// Applies the keyboard layout from the config to the extra seats.
+    pub fn update_extra_seats_xkb(&mut self, xkb: &Xkb) {
+        let keyboards: Vec<_> = self
+            .niri
+            .extra_seats
+            .iter()
+            .map(|seat| seat.seat.get_keyboard().unwrap())
+            .collect();
+
+        for keyboard in keyboards {
+            if let Err(err) = keyboard.set_xkb_config(self, xkb.to_xkb_config()) {
+                warn!("error updating xkb config of an extra seat: {err:?}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use niri_config::DEFAULT_SEAT_FOCUS_RING_COLOR;
+
+    use super::*;
+
+    fn seat(name: &str) -> niri_config::Seat {
+        niri_config::Seat {
+            name: name.to_owned(),
+            devices: vec![],
+            focus_ring_color: DEFAULT_SEAT_FOCUS_RING_COLOR,
+        }
+    }
+
+    fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+        Rectangle::new(Point::from((x, y)), Size::from((w, h)))
+    }
+
+    #[test]
+    fn skips_taken_seat_names() {
+        let configs = [seat("seat0"), seat("guest"), seat("other"), seat("guest")];
+        let names: Vec<_> = usable_configs(&configs, "seat0")
+            .map(|config| config.name.as_str())
+            .collect();
+        assert_eq!(names, ["guest", "other"]);
+    }
+
+    #[test]
+    fn pointer_moves_freely_within_outputs() {
+        let outputs = [rect(0, 0, 1920, 1080), rect(1920, 0, 1280, 720)];
+        let pos = clip_to_outputs(
+            &outputs,
+            Point::from((1900., 500.)),
+            Point::from((2000., 600.)),
+        );
+        assert_eq!(pos, Some(Point::from((2000., 600.))));
+    }
+
+    #[test]
+    fn pointer_stays_out_of_gaps_between_outputs() {
+        // The bounding rectangle of these outputs includes the area below the smaller one.
+        let outputs = [rect(0, 0, 1920, 1080), rect(1920, 0, 1280, 720)];
+        let pos = clip_to_outputs(
+            &outputs,
+            Point::from((2000., 700.)),
+            Point::from((2100., 900.)),
+        );
+        assert_eq!(pos, Some(Point::from((2100., 719.))));
+    }
+
+    #[test]
+    fn pointer_outside_outputs_goes_to_first_output() {
+        let outputs = [rect(0, 0, 1920, 1080), rect(1920, 0, 1280, 720)];
+        let pos = clip_to_outputs(&outputs, Point::from((-10., -10.)), Point::from((-5., -5.)));
+        assert_eq!(pos, Some(Point::from((960., 540.))));
+
+        assert_eq!(
+            clip_to_outputs(&[], Point::from((0., 0.)), Point::from((0., 0.))),
+            None
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Built-in handling of the volume and brightness keys.
+//!
+//! When these keys aren't bound to anything, niri changes the volume of the default PipeWire sink
+//! with `wpctl` and the backlight brightness through logind or sysfs, then shows the new level in
+//! an OSD. This way a minimal session doesn't need to bind every key to an external script.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+use anyhow::{bail, Context};
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+
+use crate::niri::State;
+use crate::ui::level_osd::{self, Level};
+
+const SINK: &str = "@DEFAULT_AUDIO_SINK@";
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeChange {
+    Raise,
+    Lower,
+    ToggleMute,
+}
+
+impl State {
+    /// Changes the volume of the default audio output and shows the new level.
+    ///
+    /// `wpctl` runs on a separate thread, so the level shows up once it's done.
+    pub fn change_volume(&mut self, change: VolumeChange) {
+        let step = self.niri.config.borrow().hardware_keys.volume_step;
+        let step = step.clamp(1, 100);
+        let to_niri = self.niri.level_osd_sender.clone();
+
+        let res = thread::Builder::new()
+            .name("Volume Control".to_owned())
+            .spawn(move || match set_volume(change, step) {
+                Ok(level) => {
+                    let _ = to_niri.send(level);
+                }
+                Err(err) => warn!("error changing the volume: {err:?}"),
+            });
+        if let Err(err) = res {
+            warn!("error spawning the volume control thread: {err:?}");
+        }
+    }
+
+    /// Changes the backlight brightness and shows the new level.
+    pub fn change_brightness(&mut self, raise: bool) {
+        let config = self.niri.config.borrow();
+        let step = config.hardware_keys.brightness_step.clamp(1, 100);
+        let name = config.hardware_keys.backlight.clone();
+        drop(config);
+
+        match self.step_backlight(name, step, raise) {
+            Ok(percent) => self.show_level_osd(Level::Brightness { percent }),
+            Err(err) => warn!("error changing the brightness: {err:?}"),
+        }
+    }
+
+    /// Shows the level OSD, restarting its timeout.
+    pub fn show_level_osd(&mut self, level: Level) {
+        if let Some(token) = self.niri.level_osd.take_hide_timer() {
+            self.niri.event_loop.remove(token);
+        }
+
+        let timer = Timer::from_duration(level_osd::TIMEOUT);
+        let token = self
+            .niri
+            .event_loop
+            .insert_source(timer, |_, _, state| {
+                if state.niri.level_osd.hide() {
+                    state.niri.queue_redraw_all();
+                }
+                TimeoutAction::Drop
+            })
+            .unwrap();
+
+        self.niri.level_osd.show(level, token);
+        self.niri.queue_redraw_all();
+    }
+
+    /// Steps the brightness of the backlight device, returns the new brightness in percent.
+    fn step_backlight(&self, name: Option<String>, step: u8, raise: bool) -> anyhow::Result<u32> {
+        let dir = match name {
+            Some(name) => Path::new(BACKLIGHT_DIR).join(name),
+            None => first_backlight()?,
+        };
+        let name = dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .context("invalid backlight device name")?;
+
+        let max = read_u32(&dir.join("max_brightness"))?;
+        let current = read_u32(&dir.join("brightness"))?;
+        let brightness = step_brightness(current, max, step, raise);
+        if brightness != current {
+            self.set_backlight(&dir, name, brightness)?;
+        }
+
+        Ok(brightness_percent(brightness, max))
+    }
+
+    fn set_backlight(&self, dir: &Path, name: &str, brightness: u32) -> anyhow::Result<()> {
+        let path = dir.join("brightness");
+
+        // Going through logind works without write access to sysfs. The call is async and falls
+        // back to sysfs by itself.
+        #[cfg(feature = "dbus")]
+        if let Some(logind) = &self.niri.logind {
+            logind.set_backlight_brightness(name.to_owned(), brightness, path);
+            return Ok(());
+        }
+        #[cfg(not(feature = "dbus"))]
+        let _ = name;
+
+        fs::write(&path, brightness.to_string())
+            .with_context(|| format!("error writing {path:?}"))?;
+        Ok(())
+    }
+}
+
+fn set_volume(change: VolumeChange, step: u8) -> anyhow::Result<Level> {
+    match change {
+        VolumeChange::Raise => {
+            let step = format!("{step}%+");
+            wpctl(&["set-volume", "--limit", "1.0", SINK, &step])?;
+        }
+        VolumeChange::Lower => {
+            let step = format!("{step}%-");
+            wpctl(&["set-volume", SINK, &step])?;
+        }
+        VolumeChange::ToggleMute => {
+            wpctl(&["set-mute", SINK, "toggle"])?;
+        }
+    }
+
+    let output = wpctl(&["get-volume", SINK])?;
+    parse_volume(&output).with_context(|| format!("error parsing wpctl output: {output:?}"))
+}
+
+fn wpctl(args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("wpctl")
+        .args(args)
+        .output()
+        .context("error running wpctl")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("wpctl {args:?} failed: {}", stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parses `wpctl get-volume` output, like `Volume: 0.45 [MUTED]`.
+fn parse_volume(output: &str) -> Option<Level> {
+    let mut words = output.trim().strip_prefix("Volume:")?.split_whitespace();
+    let volume: f64 = words.next()?.parse().ok()?;
+    let muted = words.any(|word| word == "[MUTED]");
+    let percent = (volume.max(0.) * 100.).round() as u32;
+    Some(Level::Volume { percent, muted })
+}
+
+fn first_backlight() -> anyhow::Result<PathBuf> {
+    let mut devices = fs::read_dir(BACKLIGHT_DIR)
+        .with_context(|| format!("error reading {BACKLIGHT_DIR}"))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect::<Vec<_>>();
+    devices.sort();
+    devices.into_iter().next().context("no backlight devices")
+}
+
+fn read_u32(path: &Path) -> anyhow::Result<u32> {
+    let value = fs::read_to_string(path).with_context(|| format!("error reading {path:?}"))?;
+    value
+        .trim()
+        .parse()
+        .with_context(|| format!("error parsing {path:?}"))
+}
+
+/// Computes the brightness after one key press of `step` percent.
+///
+/// Lowering stops at the lowest non-zero level since some backlights turn off at zero.
+fn step_brightness(current: u32, max: u32, step: u8, raise: bool) -> u32 {
+    let step = (u64::from(max) * u64::from(step) / 100).max(1) as u32;
+    if raise {
+        current.saturating_add(step).min(max)
+    } else {
+        current.saturating_sub(step).max(1).min(max)
+    }
+}
+
+fn brightness_percent(brightness: u32, max: u32) -> u32 {
+    if max == 0 {
+        return 0;
+    }
+
+    ((u64::from(brightness) * 100 + u64::from(max) / 2) / u64::from(max)) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_wpctl_volume() {
+        assert_eq!(
+            parse_volume("Volume: 0.45\n"),
+            Some(Level::Volume {
+                percent: 45,
+                muted: false
+            })
+        );
+        assert_eq!(
+            parse_volume("Volume: 1.00 [MUTED]\n"),
+            Some(Level::Volume {
+                percent: 100,
+                muted: true
+            })
+        );
+        assert_eq!(parse_volume("Error: no such node"), None);
+    }
+
+    #[test]
+    fn brightness_steps() {
+        assert_eq!(step_brightness(500, 1000, 5, true), 550);
+        assert_eq!(step_brightness(980, 1000, 5, true), 1000);
+        // Lowering never turns the backlight off.
+        assert_eq!(step_brightness(30, 1000, 5, false), 1);
+        // Small ranges still change by at least one.
+        assert_eq!(step_brightness(3, 7, 5, true), 4);
+
+        assert_eq!(brightness_percent(550, 1000), 55);
+        assert_eq!(brightness_percent(0, 0), 0);
+    }
+}
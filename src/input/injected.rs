@@ -0,0 +1,309 @@
+//! Synthetic input events injected over IPC.
+//!
+//! These go through the same `process_input_event()` path as real input devices.
+
+use smithay::backend::input::{
+    AbsolutePositionEvent, Axis, AxisRelativeDirection, AxisSource, ButtonState, Device,
+    DeviceCapability, Event, InputBackend, KeyState, KeyboardKeyEvent, Keycode, PointerAxisEvent,
+    PointerButtonEvent, PointerMotionAbsoluteEvent, PointerMotionEvent, UnusedEvent,
+};
+use smithay::utils::{Logical, Point, Size};
+
+use crate::utils::get_monotonic_time;
+
+/// Offset between evdev and xkb key codes.
+const XKB_KEYCODE_OFFSET: u32 = 8;
+
+/// Scroll distance of one wheel click, matching libinput.
+const WHEEL_CLICK_DISTANCE: f64 = 15.;
+
+pub struct InjectedInputBackend;
+
+/// The device that all injected events come from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InjectedDevice;
+
+impl Device for InjectedDevice {
+    fn id(&self) -> String {
+        String::from("niri-injected-input")
+    }
+
+    fn name(&self) -> String {
+        String::from("niri injected input")
+    }
+
+    fn has_capability(&self, capability: DeviceCapability) -> bool {
+        matches!(
+            capability,
+            DeviceCapability::Keyboard | DeviceCapability::Pointer
+        )
+    }
+
+    fn usb_id(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    fn syspath(&self) -> Option<std::path::PathBuf> {
+        None
+    }
+}
+
+fn now_micros() -> u64 {
+    get_monotonic_time().as_micros() as u64
+}
+
+pub struct InjectedKeyEvent {
+    time: u64,
+    keycode: Keycode,
+    state: KeyState,
+}
+
+impl InjectedKeyEvent {
+    /// Creates a key event from an evdev key code.
+    ///
+    /// Returns `None` if the key code has no xkb equivalent.
+    pub fn new(keycode: u32, state: KeyState) -> Option<Self> {
+        let keycode = keycode.checked_add(XKB_KEYCODE_OFFSET)?;
+        Some(Self {
+            time: now_micros(),
+            keycode: keycode.into(),
+            state,
+        })
+    }
+}
+
+impl Event<InjectedInputBackend> for InjectedKeyEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> InjectedDevice {
+        InjectedDevice
+    }
+}
+
+impl KeyboardKeyEvent<InjectedInputBackend> for InjectedKeyEvent {
+    fn key_code(&self) -> Keycode {
+        self.keycode
+    }
+
+    fn state(&self) -> KeyState {
+        self.state
+    }
+
+    fn count(&self) -> u32 {
+        u32::from(self.state == KeyState::Pressed)
+    }
+}
+
+pub struct InjectedMotionEvent {
+    time: u64,
+    delta: Point<f64, Logical>,
+}
+
+impl InjectedMotionEvent {
+    pub fn new(delta: Point<f64, Logical>) -> Self {
+        Self {
+            time: now_micros(),
+            delta,
+        }
+    }
+}
+
+impl Event<InjectedInputBackend> for InjectedMotionEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> InjectedDevice {
+        InjectedDevice
+    }
+}
+
+impl PointerMotionEvent<InjectedInputBackend> for InjectedMotionEvent {
+    fn delta_x(&self) -> f64 {
+        self.delta.x
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.delta.y
+    }
+
+    fn delta_x_unaccel(&self) -> f64 {
+        self.delta.x
+    }
+
+    fn delta_y_unaccel(&self) -> f64 {
+        self.delta.y
+    }
+}
+
+pub struct InjectedMotionAbsoluteEvent {
+    time: u64,
+    /// Position relative to the area covering all outputs.
+    pos: Point<f64, Logical>,
+    /// Size of the area covering all outputs.
+    size: Size<f64, Logical>,
+}
+
+impl InjectedMotionAbsoluteEvent {
+    /// Creates an event for `pos` within an area of `size`.
+    ///
+    /// The position is converted to the 0 to 1 range that absolute devices report.
+    pub fn new(pos: Point<f64, Logical>, size: Size<f64, Logical>) -> Self {
+        Self {
+            time: now_micros(),
+            pos,
+            size,
+        }
+    }
+}
+
+impl Event<InjectedInputBackend> for InjectedMotionAbsoluteEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> InjectedDevice {
+        InjectedDevice
+    }
+}
+
+impl AbsolutePositionEvent<InjectedInputBackend> for InjectedMotionAbsoluteEvent {
+    fn x(&self) -> f64 {
+        self.pos.x / self.size.w
+    }
+
+    fn y(&self) -> f64 {
+        self.pos.y / self.size.h
+    }
+
+    fn x_transformed(&self, width: i32) -> f64 {
+        self.x() * f64::from(width)
+    }
+
+    fn y_transformed(&self, height: i32) -> f64 {
+        self.y() * f64::from(height)
+    }
+}
+
+impl PointerMotionAbsoluteEvent<InjectedInputBackend> for InjectedMotionAbsoluteEvent {}
+
+pub struct InjectedButtonEvent {
+    time: u64,
+    button: u32,
+    state: ButtonState,
+}
+
+impl InjectedButtonEvent {
+    pub fn new(button: u32, state: ButtonState) -> Self {
+        Self {
+            time: now_micros(),
+            button,
+            state,
+        }
+    }
+}
+
+impl Event<InjectedInputBackend> for InjectedButtonEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> InjectedDevice {
+        InjectedDevice
+    }
+}
+
+impl PointerButtonEvent<InjectedInputBackend> for InjectedButtonEvent {
+    fn button_code(&self) -> u32 {
+        self.button
+    }
+
+    fn state(&self) -> ButtonState {
+        self.state
+    }
+}
+
+pub struct InjectedAxisEvent {
+    time: u64,
+    /// Horizontal and vertical amounts in wheel clicks.
+    clicks: (f64, f64),
+}
+
+impl InjectedAxisEvent {
+    pub fn new(horizontal: f64, vertical: f64) -> Self {
+        Self {
+            time: now_micros(),
+            clicks: (horizontal, vertical),
+        }
+    }
+
+    fn clicks(&self, axis: Axis) -> f64 {
+        match axis {
+            Axis::Horizontal => self.clicks.0,
+            Axis::Vertical => self.clicks.1,
+        }
+    }
+}
+
+impl Event<InjectedInputBackend> for InjectedAxisEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> InjectedDevice {
+        InjectedDevice
+    }
+}
+
+impl PointerAxisEvent<InjectedInputBackend> for InjectedAxisEvent {
+    fn amount(&self, axis: Axis) -> Option<f64> {
+        Some(self.clicks(axis) * WHEEL_CLICK_DISTANCE)
+    }
+
+    fn amount_v120(&self, axis: Axis) -> Option<f64> {
+        Some(self.clicks(axis) * 120.)
+    }
+
+    fn source(&self) -> AxisSource {
+        AxisSource::Wheel
+    }
+
+    fn relative_direction(&self, _axis: Axis) -> AxisRelativeDirection {
+        AxisRelativeDirection::Identical
+    }
+}
+
+impl InputBackend for InjectedInputBackend {
+    type Device = InjectedDevice;
+
+    type KeyboardKeyEvent = InjectedKeyEvent;
+    type PointerAxisEvent = InjectedAxisEvent;
+    type PointerButtonEvent = InjectedButtonEvent;
+    type PointerMotionEvent = InjectedMotionEvent;
+    type PointerMotionAbsoluteEvent = InjectedMotionAbsoluteEvent;
+
+    type GestureSwipeBeginEvent = UnusedEvent;
+    type GestureSwipeUpdateEvent = UnusedEvent;
+    type GestureSwipeEndEvent = UnusedEvent;
+    type GesturePinchBeginEvent = UnusedEvent;
+    type GesturePinchUpdateEvent = UnusedEvent;
+    type GesturePinchEndEvent = UnusedEvent;
+    type GestureHoldBeginEvent = UnusedEvent;
+    type GestureHoldEndEvent = UnusedEvent;
+
+    type TouchDownEvent = UnusedEvent;
+    type TouchUpEvent = UnusedEvent;
+    type TouchMotionEvent = UnusedEvent;
+    type TouchCancelEvent = UnusedEvent;
+    type TouchFrameEvent = UnusedEvent;
+    type TabletToolAxisEvent = UnusedEvent;
+    type TabletToolProximityEvent = UnusedEvent;
+    type TabletToolTipEvent = UnusedEvent;
+    type TabletToolButtonEvent = UnusedEvent;
+
+    type SwitchToggleEvent = UnusedEvent;
+
+    type SpecialEvent = UnusedEvent;
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use smithay::input::keyboard::Layout;
+
+/// Lock keys and active layout of a keyboard.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceKeyboardState {
+    pub caps_lock: bool,
+    pub num_lock: bool,
+    pub layout: Layout,
+}
+
+/// Remembers the lock keys and active layout of every keyboard when they aren't shared.
+///
+/// All keyboards feed into the same seat keyboard, so the seat state always belongs to the
+/// keyboard that was used last. When another keyboard is used, the seat state is saved for the
+/// previous keyboard and the state of the new one is restored.
+#[derive(Debug, Default)]
+pub struct PerDeviceKeyboardState {
+    /// Id of the keyboard that was used last.
+    active: Option<String>,
+    /// Saved state of the keyboards that aren't active.
+    saved: HashMap<String, DeviceKeyboardState>,
+}
+
+impl PerDeviceKeyboardState {
+    /// Makes the keyboard active, saving `current` for the previously active keyboard.
+    ///
+    /// Returns the state to apply to the seat keyboard, if it differs from `current`. A keyboard
+    /// that wasn't used before keeps the current state.
+    pub fn activate(
+        &mut self,
+        id: &str,
+        current: DeviceKeyboardState,
+    ) -> Option<DeviceKeyboardState> {
+        if self.active.as_deref() == Some(id) {
+            return None;
+        }
+
+        if let Some(previous) = self.active.replace(id.to_owned()) {
+            self.saved.insert(previous, current);
+        }
+
+        self.saved
+            .remove(id)
+            .filter(|restored| *restored != current)
+    }
+
+    pub fn is_active(&self, id: &str) -> bool {
+        self.active.as_deref() == Some(id)
+    }
+
+    /// Forgets a keyboard (when it's disconnected).
+    pub fn remove(&mut self, id: &str) {
+        self.saved.remove(id);
+        if self.is_active(id) {
+            self.active = None;
+        }
+    }
+
+    /// Forgets all keyboards, for example when the keymap changes.
+    pub fn clear(&mut self) {
+        self.active = None;
+        self.saved.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(caps_lock: bool, layout: u32) -> DeviceKeyboardState {
+        DeviceKeyboardState {
+            caps_lock,
+            num_lock: false,
+            layout: Layout(layout),
+        }
+    }
+
+    #[test]
+    fn restores_state_per_keyboard() {
+        let mut states = PerDeviceKeyboardState::default();
+
+        // The first keyboard keeps the current state.
+        assert_eq!(states.activate("internal", state(false, 0)), None);
+        assert_eq!(states.activate("internal", state(true, 0)), None);
+
+        // A new keyboard inherits the state of the previous one.
+        assert_eq!(states.activate("external", state(true, 0)), None);
+        assert_eq!(
+            states.activate("internal", state(false, 1)),
+            Some(state(true, 0))
+        );
+        assert_eq!(
+            states.activate("external", state(true, 0)),
+            Some(state(false, 1))
+        );
+    }
+
+    #[test]
+    fn skips_unchanged_state() {
+        let mut states = PerDeviceKeyboardState::default();
+        states.activate("internal", state(true, 0));
+        states.activate("external", state(true, 0));
+        assert_eq!(states.activate("internal", state(true, 0)), None);
+    }
+
+    #[test]
+    fn remove_forgets_keyboard() {
+        let mut states = PerDeviceKeyboardState::default();
+        states.activate("internal", state(false, 0));
+        states.activate("external", state(true, 0));
+
+        states.remove("external");
+        assert!(!states.is_active("external"));
+        assert_eq!(
+            states.activate("internal", state(true, 0)),
+            Some(state(false, 0))
+        );
+        assert_eq!(states.activate("external", state(false, 0)), None);
+    }
+}
@@ -79,21 +79,28 @@
 
 //     实时响应：支持动画过渡和即时界面更新
 
+use std::any::Any;
 use std::cmp::min;
 use std::collections::hash_map::Entry;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
 use std::time::Duration;
 
 use calloop::timer::{TimeoutAction, Timer};
-use niri_config::{Action, Bind, Binds, Key, ModKey, Modifiers, SwitchBinds, Trigger};
-use niri_ipc::LayoutSwitchTarget;
+use niri_config::{
+    Action, Bind, BindActivation, Binds, HotCorners, Key, LidSwitchPolicy, ModKey, Modifiers,
+    SwitchBinds, TrackState, Trigger, Wallpaper,
+};
+use niri_ipc::{LayoutSwitchTarget, PositionChange, SizeChange};
 use smithay::backend::input::{
-    AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, Event,
+    AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, DeviceCapability, Event,
     InputEvent, KeyState, KeyboardKeyEvent, Keycode, MouseButton, PointerAxisEvent,
-    PointerButtonEvent, PointerMotionEvent, Switch, SwitchState, SwitchToggleEvent,
+    PointerButtonEvent, PointerMotionEvent, ProximityState, Switch, SwitchState, SwitchToggleEvent,
+    TabletToolButtonEvent, TabletToolEvent, TabletToolProximityEvent, TabletToolTipEvent,
+    TabletToolTipState,
 };
 use smithay::backend::libinput::LibinputInputBackend;
-use smithay::input::keyboard::{keysyms, FilterResult, Keysym, Layout, ModifiersState};
+use smithay::input::keyboard::{keysyms, FilterResult, Keysym, Layout, LedState, ModifiersState};
 use smithay::input::pointer::{
     AxisFrame, ButtonEvent, CursorIcon, CursorImageStatus, Focus,
     GrabStartData as PointerGrabStartData, MotionEvent, RelativeMotionEvent,
@@ -103,35 +110,68 @@ use smithay::input::touch::{
 };
 use smithay::input::SeatHandler;
 use smithay::output::Output;
-use smithay::utils::{Logical, Point, Rectangle, Size, SERIAL_COUNTER};
+use smithay::utils::{Logical, Point, Rectangle, Transform, SERIAL_COUNTER};
 use smithay::wayland::keyboard_shortcuts_inhibit::KeyboardShortcutsInhibitor;
 use smithay::wayland::pointer_constraints::{with_pointer_constraint, PointerConstraint};
 use smithay::wayland::selection::data_device::DnDGrab;
+use smithay::wayland::tablet_manager::{TabletDescriptor, TabletSeatTrait};
 
+use self::drag_recognizer::DragRecognizer;
+use self::hardware_keys::VolumeChange;
+use self::injected::{
+    InjectedAxisEvent, InjectedButtonEvent, InjectedInputBackend, InjectedKeyEvent,
+    InjectedMotionAbsoluteEvent, InjectedMotionEvent,
+};
+use self::keyboard_state::DeviceKeyboardState;
 use self::move_grab::MoveGrab;
 use self::resize_grab::ResizeGrab;
 use self::spatial_movement_grab::SpatialMovementGrab;
+use crate::backend::Backend;
 use crate::layout::scrolling::ScrollDirection;
 use crate::layout::{ActivateWindow, LayoutElement as _};
 use crate::niri::{ PointerVisibility, State};
+use crate::ui::crash_report::UiOverlay;
+use crate::ui::hot_areas::HotArea;
+use crate::ui::kill_prompt::KillAnswer;
+use crate::ui::overview_search::SearchKey;
+use crate::utils::crash::IS_SAFE_MODE;
 use crate::utils::spawning::spawn;
 use crate::utils::{center, get_monotonic_time, ResizeEdge};
 
 pub mod backend_ext;
+pub mod bind_conflicts;
+pub mod double_tap;
+pub mod drag_recognizer;
+pub mod extra_seats;
+pub mod hardware_keys;
+pub mod injected;
+pub mod keyboard_state;
 pub mod move_grab;
+pub mod relative_motion;
 pub mod resize_grab;
 pub mod scroll_swipe_gesture;
 pub mod scroll_tracker;
 pub mod spatial_movement_grab;
 pub mod swipe_tracker;
+pub mod tablet_pad;
+pub mod touch_gestures;
 pub mod touch_move_grab;
 pub mod touch_overview_grab;
 pub mod touch_resize_grab;
+pub mod virtual_input;
 
 use backend_ext::{NiriInputBackend as InputBackend, NiriInputDevice as _};
 
 pub const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
 
+// Left mouse button from linux/input-event-codes.h, used for dwell-click.
+const BTN_LEFT: u32 = 0x110;
+
+// Pen buttons from linux/input-event-codes.h.
+const BTN_STYLUS: u32 = 0x14b;
+const BTN_STYLUS2: u32 = 0x14c;
+const BTN_STYLUS3: u32 = 0x149;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct TabletData {
     pub aspect_ratio: f64,
@@ -174,6 +214,12 @@ impl State {
             }
         }
 
+        // Devices of extra seats bypass the bindings and the main seat entirely.
+        if let Some(idx) = self.extra_seat_for_event(&event) {
+            self.on_extra_seat_event(idx, event);
+            return;
+        }
+
         use InputEvent::*;
         match event {
             DeviceAdded { device } => self.on_device_added(device),
@@ -183,7 +229,15 @@ impl State {
             PointerMotionAbsolute { event } => self.on_pointer_motion_absolute::<I>(event),
             PointerButton { event } => self.on_pointer_button::<I>(event),
             PointerAxis { event } => self.on_pointer_axis::<I>(event),
+            TabletToolAxis { event } => self.on_tablet_tool_axis::<I>(event),
+            TabletToolTip { event } => self.on_tablet_tool_tip::<I>(event),
+            TabletToolProximity { event } => self.on_tablet_tool_proximity::<I>(event),
+            TabletToolButton { event } => self.on_tablet_tool_button::<I>(event),
             SwitchToggle { event } => self.on_switch_toggle::<I>(event),
+            TouchDown { event } => self.on_touch_down::<I>(event),
+            TouchMotion { event } => self.on_touch_motion::<I>(event),
+            TouchUp { event } => self.on_touch_up::<I>(event),
+            TouchCancel { event } => self.on_touch_cancel::<I>(event),
             Special(_) => (),
             _ => {},
         }
@@ -196,6 +250,7 @@ impl State {
             InputEvent::DeviceAdded { device } => {
                 self.niri.devices.insert(device.clone());
 
+                // Until the new keyboard is used, it shows the current lock keys, in both modes.
                 if device.has_capability(input::DeviceCapability::Keyboard) {
                     if let Some(led_state) = self
                         .niri
@@ -207,21 +262,168 @@ impl State {
                     }
                 }
 
+                if device.has_capability(input::DeviceCapability::TabletTool) {
+                    match device.size() {
+                        Some((w, h)) => {
+                            let aspect_ratio = w / h;
+                            let data = TabletData { aspect_ratio };
+                            self.niri.tablets.insert(device.clone(), data);
+                        }
+                        None => {
+                            warn!("tablet tool device has no size");
+                        }
+                    }
+                }
+
                 apply_libinput_settings(&self.niri.config.borrow().input, device);
             }
             InputEvent::DeviceRemoved { device } => {
+                self.niri.keyboard_states.remove(&device.id());
+                self.niri.tablets.remove(device);
                 self.niri.devices.remove(device);
             }
+            InputEvent::Special(input::event::Event::TabletPad(event)) => {
+                self.on_tablet_pad_event(event);
+            }
             _ => (),
         }
     }
 
-    fn on_device_added(&mut self, _device: impl Device) {
+    /// Processes an input event injected over IPC.
+    pub fn inject_input(&mut self, input: niri_ipc::InjectedInput) -> Result<(), String> {
+        if !self.niri.config.borrow().debug.ipc_input_injection {
+            return Err(String::from(
+                "input injection is disabled, enable it with debug { ipc-input-injection; }",
+            ));
+        }
+        if matches!(self.backend, Backend::Tty(_)) {
+            return Err(String::from(
+                "input injection is only available in the headless and winit backends",
+            ));
+        }
+
+        let key_event = |keycode, state| {
+            InjectedKeyEvent::new(keycode, state)
+                .ok_or_else(|| format!("key code {keycode} is out of range"))
+        };
+
+        use niri_ipc::InjectedInput::*;
+        let event = match input {
+            KeyPress { keycode } => InputEvent::Keyboard {
+                event: key_event(keycode, KeyState::Pressed)?,
+            },
+            KeyRelease { keycode } => InputEvent::Keyboard {
+                event: key_event(keycode, KeyState::Released)?,
+            },
+            PointerMoveTo { x, y } => {
+                let Some(geo) = self.global_bounding_rectangle() else {
+                    return Err(String::from("there are no outputs"));
+                };
+                let geo = geo.to_f64();
+                let pos = Point::from((x, y)) - geo.loc;
+                InputEvent::PointerMotionAbsolute {
+                    event: InjectedMotionAbsoluteEvent::new(pos, geo.size),
+                }
+            }
+            PointerMoveBy { dx, dy } => InputEvent::PointerMotion {
+                event: InjectedMotionEvent::new(Point::from((dx, dy))),
+            },
+            ButtonPress { button } => InputEvent::PointerButton {
+                event: InjectedButtonEvent::new(button, ButtonState::Pressed),
+            },
+            ButtonRelease { button } => InputEvent::PointerButton {
+                event: InjectedButtonEvent::new(button, ButtonState::Released),
+            },
+            Scroll {
+                horizontal,
+                vertical,
+            } => InputEvent::PointerAxis {
+                event: InjectedAxisEvent::new(horizontal, vertical),
+            },
+        };
+
+        self.process_input_event::<InjectedInputBackend>(event);
+        Ok(())
+    }
+
+    /// Updates the lock key LEDs of the keyboards.
+    ///
+    /// With per-device state, only the keyboard that was used last shows the seat state.
+    pub fn update_keyboard_leds(&mut self, led_state: LedState) {
+        let per_device =
+            self.niri.config.borrow().input.keyboard.track_state == TrackState::PerDevice;
+
+        let keyboards = self
+            .niri
+            .devices
+            .iter()
+            .filter(|device| device.has_capability(input::DeviceCapability::Keyboard))
+            .filter(|device| !per_device || self.niri.keyboard_states.is_active(&device.id()))
+            .cloned();
+
+        for mut keyboard in keyboards {
+            keyboard.led_update(led_state.into());
+        }
+    }
+
+    /// Restores the lock keys and layout of the keyboard, if they're tracked per device.
+    fn activate_keyboard_device(&mut self, id: &str) {
+        if self.niri.config.borrow().input.keyboard.track_state != TrackState::PerDevice
+            || self.niri.keyboard_states.is_active(id)
+        {
+            return;
+        }
+
+        let keyboard = self.niri.seat.get_keyboard().unwrap();
+        let modifier_state = keyboard.modifier_state();
+        let layout = keyboard.with_xkb_state(self, |context| {
+            context.xkb().lock().unwrap().active_layout()
+        });
+        let current = DeviceKeyboardState {
+            caps_lock: modifier_state.caps_lock,
+            num_lock: modifier_state.num_lock,
+            layout,
+        };
+
+        let Some(restored) = self.niri.keyboard_states.activate(id, current) else {
+            // Move the LEDs over from the previous keyboard.
+            self.update_keyboard_leds(keyboard.led_state());
+            return;
+        };
+
+        keyboard.set_modifier_state(ModifiersState {
+            caps_lock: restored.caps_lock,
+            num_lock: restored.num_lock,
+            ..modifier_state
+        });
+        if restored.layout != layout {
+            keyboard.with_xkb_state(self, |mut context| context.set_layout(restored.layout));
+        }
+
+        self.update_keyboard_leds(keyboard.led_state());
+    }
+
+    fn on_device_added(&mut self, device: impl Device) {
+        if device.has_capability(DeviceCapability::TabletTool) {
+            let tablet_seat = self.niri.seat.tablet_seat();
 
+            let desc = TabletDescriptor::from(&device);
+            tablet_seat.add_tablet::<Self>(&self.niri.display_handle, &desc);
+        }
     }
 
-    fn on_device_removed(&mut self, _device: impl Device) {
+    fn on_device_removed(&mut self, device: impl Device) {
+        if device.has_capability(DeviceCapability::TabletTool) {
+            let tablet_seat = self.niri.seat.tablet_seat();
 
+            let desc = TabletDescriptor::from(&device);
+            tablet_seat.remove_tablet(&desc);
+
+            // If there are no tablets in seat we can remove all tools.
+            if tablet_seat.count_tablets() == 0 {
+                tablet_seat.clear_tools();
+            }
+        }
     }
 
     /// Computes the rectangle that covers all outputs in global space.
@@ -274,9 +476,13 @@ impl State {
             self.hide_cursor_if_needed();
         }
 
+        self.activate_keyboard_device(&event.device().id());
+
         let is_inhibiting_shortcuts = self.is_inhibiting_shortcuts();
 
-        let Some(Some(bind)) = self.niri.seat.get_keyboard().unwrap().input(
+        let mut double_tap_bind = None;
+
+        let res = self.niri.seat.get_keyboard().unwrap().input(
             self,
             event.key_code(),
             event.state(),
@@ -287,6 +493,126 @@ impl State {
                 let modified = keysym.modified_sym();
                 let raw = keysym.raw_latin_sym_or_raw_current_sym();
 
+                let is_double_tap = this.niri.modifier_taps.on_key(
+                    key_code,
+                    modified.is_modifier_key(),
+                    pressed,
+                    time,
+                );
+
+                // The crash report offers to restart in safe mode, which takes priority over
+                // everything else in case the rest of the UI is broken.
+                if this.niri.crash_report.is_open() && pressed {
+                    this.niri.suppressed_keys.insert(key_code);
+
+                    match raw {
+                        Some(Keysym::r) if !IS_SAFE_MODE.load(Ordering::Relaxed) => {
+                            this.restart_in_safe_mode();
+                        }
+                        Some(Keysym::Escape) => {
+                            this.niri.crash_report.hide();
+                            this.niri.queue_redraw_all();
+                        }
+                        _ => (),
+                    }
+                    return FilterResult::Intercept(None);
+                }
+
+                // The virtual input prompt must be answered before anything else.
+                if this.niri.ui_overlay_is_open(UiOverlay::VirtualInputPrompt) && pressed {
+                    this.niri.suppressed_keys.insert(key_code);
+
+                    let allowed = match raw {
+                        Some(Keysym::y) => true,
+                        Some(Keysym::n | Keysym::Escape) => false,
+                        _ => return FilterResult::Intercept(None),
+                    };
+
+                    if let Some(request) = this.niri.virtual_input_prompt.pop() {
+                        this.niri.virtual_input.decide(request.executable, allowed);
+                    }
+                    this.niri.queue_redraw_all();
+                    return FilterResult::Intercept(None);
+                }
+
+                // So must the prompt to kill an unresponsive client.
+                if this.niri.ui_overlay_is_open(UiOverlay::KillPrompt) && pressed {
+                    this.niri.suppressed_keys.insert(key_code);
+
+                    match this.niri.kill_prompt.answer(raw) {
+                        KillAnswer::Kill(request) => {
+                            this.niri.kill_unresponsive_client(&request);
+                        }
+                        KillAnswer::Dismiss(_) => (),
+                        KillAnswer::Ignore => return FilterResult::Intercept(None),
+                    }
+                    this.niri.queue_redraw_all();
+                    return FilterResult::Intercept(None);
+                }
+
+                // Any key press closes the bind conflicts overlay, and is then handled as usual.
+                if pressed && this.niri.bind_conflicts_overlay.hide() {
+                    this.niri.queue_redraw_all();
+                }
+
+                // Adjust mode is modal and consumes every key press.
+                if this.niri.ui_overlay_is_open(UiOverlay::AdjustMode) && pressed {
+                    this.niri.suppressed_keys.insert(key_code);
+
+                    if matches!(raw, Some(Keysym::Escape | Keysym::Return)) {
+                        this.niri.adjust_mode.close();
+                        this.niri.queue_redraw_all();
+                        return FilterResult::Intercept(None);
+                    }
+
+                    let floating = this
+                        .niri
+                        .layout
+                        .active_workspace()
+                        .is_some_and(|ws| ws.floating_is_active());
+                    let config = this.niri.config.borrow().adjust_mode;
+                    let bind = raw
+                        .and_then(|raw| hardcoded_adjust_mode_bind(raw, *mods, floating, config));
+                    return FilterResult::Intercept(bind);
+                }
+
+                // Typing in the overview searches windows by title and app id.
+                if this.niri.keyboard_focus.is_overview() && pressed {
+                    let search_mods = modifiers_from_state(*mods);
+                    match this.niri.overview_search.on_key(raw, modified, search_mods) {
+                        Some(SearchKey::Edited) => {
+                            this.niri.suppressed_keys.insert(key_code);
+                            this.niri.update_overview_search();
+                            this.niri.queue_redraw_all();
+                            return FilterResult::Intercept(None);
+                        }
+                        Some(SearchKey::Accept) => {
+                            this.niri.suppressed_keys.insert(key_code);
+                            this.accept_overview_search();
+                            return FilterResult::Intercept(None);
+                        }
+                        None => (),
+                    }
+                }
+
+                // The tutorial is modal and consumes every key press.
+                if this.niri.ui_overlay_is_open(UiOverlay::Tutorial) && pressed {
+                    this.niri.suppressed_keys.insert(key_code);
+
+                    let changed = match raw {
+                        Some(Keysym::Return | Keysym::KP_Enter | Keysym::space) => {
+                            this.niri.tutorial.next()
+                        }
+                        Some(Keysym::BackSpace) => this.niri.tutorial.previous(),
+                        Some(Keysym::Escape) => this.niri.tutorial.close(),
+                        _ => false,
+                    };
+                    if changed {
+                        this.niri.queue_redraw_all();
+                    }
+                    return FilterResult::Intercept(None);
+                }
+
                 if pressed
                     && raw == Some(Keysym::Escape)
                 {
@@ -303,8 +629,17 @@ impl State {
 
                 let bindings = &this.niri.config.borrow().binds;
 
+                // Double taps are recognized on the release of the modifier, which we still pass
+                // through to the client like every other modifier event.
+                if is_double_tap {
+                    double_tap_bind = raw
+                        .and_then(|raw| find_double_tap_bind(bindings, raw))
+                        .filter(|bind| !(is_inhibiting_shortcuts && bind.allow_inhibiting));
+                }
+
                 let res = should_intercept_key(
                     &mut this.niri.suppressed_keys,
+                    &mut this.niri.release_binds,
                     bindings,
                     mod_key,
                     key_code,
@@ -325,15 +660,32 @@ impl State {
                             return FilterResult::Intercept(Some(bind));
                         }
                     }
+
+                    // Volume and brightness keys that aren't bound to anything.
+                    let hardware_keys_off = this.niri.config.borrow().hardware_keys.off;
+                    if !hardware_keys_off && pressed {
+                        if let Some(bind) = hardcoded_hardware_key_bind(modified) {
+                            this.niri.suppressed_keys.insert(key_code);
+                            return FilterResult::Intercept(Some(bind));
+                        }
+                    }
                 }
 
                 res
             },
-        ) else {
+        );
+
+        if let Some(bind) = double_tap_bind {
+            self.handle_bind(bind);
+        }
+
+        let Some(Some(bind)) = res else {
             return;
         };
 
+        // Release binds fire once, without key repeat.
         if !pressed {
+            self.handle_bind(bind);
             return;
         }
 
@@ -355,14 +707,15 @@ impl State {
         let config = self.niri.config.borrow();
         let config = &config.input.keyboard;
 
-        let repeat_rate = config.repeat_rate;
+        // The bind can override the keyboard repeat settings, for example to resize faster.
+        let repeat_rate = bind.repeat_rate.unwrap_or(config.repeat_rate);
         if repeat_rate == 0 {
             return;
         }
         let repeat_duration = Duration::from_secs_f64(1. / f64::from(repeat_rate));
 
-        let repeat_timer =
-            Timer::from_duration(Duration::from_millis(u64::from(config.repeat_delay)));
+        let repeat_delay = bind.repeat_delay.unwrap_or(config.repeat_delay);
+        let repeat_timer = Timer::from_duration(Duration::from_millis(u64::from(repeat_delay)));
 
         let token = self
             .niri
@@ -447,6 +800,21 @@ impl State {
             Action::PowerOnMonitors => {
                 self.niri.activate_monitors(&mut self.backend);
             }
+            Action::VolumeUp => {
+                self.change_volume(VolumeChange::Raise);
+            }
+            Action::VolumeDown => {
+                self.change_volume(VolumeChange::Lower);
+            }
+            Action::ToggleMute => {
+                self.change_volume(VolumeChange::ToggleMute);
+            }
+            Action::BrightnessUp => {
+                self.change_brightness(true);
+            }
+            Action::BrightnessDown => {
+                self.change_brightness(false);
+            }
             Action::ToggleDebugTint => {
                 self.backend.toggle_debug_tint();
                 self.niri.queue_redraw_all();
@@ -458,9 +826,25 @@ impl State {
             Action::DebugToggleDamage => {
                 self.niri.debug_toggle_damage();
             }
+            Action::DebugToggleDamageHeatmap => {
+                self.niri.debug_toggle_damage_heatmap();
+            }
             Action::Spawn(command) => {
                 let (token, _) = self.niri.activation_state.create_external_token(None);
-                spawn(command, Some(token.clone()));
+                let token = token.clone();
+                self.show_launch_placeholder(&token, &command);
+                spawn(command, Some(token));
+            }
+            Action::FocusOrSpawn(app_id, command, all_outputs) => {
+                if let Some(window) = self.niri.window_by_app_id(&app_id, all_outputs) {
+                    self.focus_window(&window);
+                    self.niri.layout.center_window(Some(&window));
+                } else {
+                    let (token, _) = self.niri.activation_state.create_external_token(None);
+                    let token = token.clone();
+                    self.show_launch_placeholder(&token, &command);
+                    spawn(command, Some(token));
+                }
             }
             Action::ToggleKeyboardShortcutsInhibit => {
                 if let Some(inhibitor) = self.niri.keyboard_focus.surface().and_then(|surface| {
@@ -486,6 +870,15 @@ impl State {
                     mapped.toplevel().send_close();
                 }
             }
+            Action::CloseWindowOrKill(timeout_ms, auto_kill) => {
+                if let Some(mapped) = self.niri.layout.focus() {
+                    let id = mapped.id().get();
+                    self.close_window_or_kill(id, timeout_ms, auto_kill);
+                }
+            }
+            Action::CloseWindowOrKillById(id, timeout_ms, auto_kill) => {
+                self.close_window_or_kill(id, timeout_ms, auto_kill);
+            }
             Action::FullscreenWindow => {
                 let focus = self.niri.layout.focus().map(|m| m.window.clone());
                 if let Some(window) = focus {
@@ -535,7 +928,20 @@ impl State {
                 self.niri.queue_redraw_all();
             }
             Action::FocusWindowPrevious => {
-                if let Some(window) = self.niri.previously_focused_window.clone() {
+                let current = self.niri.layout.focus().map(|m| m.window.clone());
+                if let Some(window) = self.niri.focus_history.previous(current.as_ref()) {
+                    self.focus_window(&window);
+                }
+            }
+            Action::CycleFocusHistoryBackward => {
+                let current = self.niri.layout.focus().map(|m| m.window.clone());
+                if let Some(window) = self.niri.focus_history.cycle(current.as_ref(), true) {
+                    self.focus_window(&window);
+                }
+            }
+            Action::CycleFocusHistoryForward => {
+                let current = self.niri.layout.focus().map(|m| m.window.clone());
+                if let Some(window) = self.niri.focus_history.cycle(current.as_ref(), false) {
                     self.focus_window(&window);
                 }
             }
@@ -955,6 +1361,13 @@ impl State {
                             self.maybe_warp_cursor_to_focus();
                         }
                     } else {
+                        if !focus {
+                            self.backend.with_primary_renderer(|renderer| {
+                                self.niri
+                                    .layout
+                                    .start_move_to_workspace_animation(renderer, None, index);
+                            });
+                        }
                         self.niri.layout.move_to_workspace(None, index, activate);
                         self.maybe_warp_cursor_to_focus();
                     }
@@ -1004,6 +1417,15 @@ impl State {
                                 }
                             }
                         } else {
+                            if !focus {
+                                self.backend.with_primary_renderer(|renderer| {
+                                    self.niri.layout.start_move_to_workspace_animation(
+                                        renderer,
+                                        Some(&window),
+                                        index,
+                                    );
+                                });
+                            }
                             self.niri
                                 .layout
                                 .move_to_workspace(Some(&window), index, activate);
@@ -1138,6 +1560,21 @@ impl State {
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
+            Action::PeekWorkspaceDown => {
+                self.niri.layout.peek_workspace_down();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::PeekWorkspaceUp => {
+                self.niri.layout.peek_workspace_up();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::EndWorkspacePeek => {
+                self.niri.layout.end_workspace_peek();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
             Action::MoveWorkspaceDown => {
                 self.niri.layout.move_workspace_down();
                 // FIXME: granular
@@ -1215,6 +1652,12 @@ impl State {
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
+            Action::ToggleColumnPin(edge) => {
+                self.niri.layout.toggle_column_pin(edge);
+                self.maybe_warp_cursor_to_focus();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
             Action::SwitchPresetColumnWidth => {
                 self.niri.layout.toggle_width();
             }
@@ -1539,6 +1982,9 @@ impl State {
             Action::ExpandColumnToAvailableWidth => {
                 self.niri.layout.expand_column_to_available_width();
             }
+            Action::FitColumnToContent => {
+                self.niri.layout.fit_column_to_content();
+            }
             Action::MoveWorkspaceToMonitorLeft => {
                 if let Some(output) = self.niri.output_left() {
                     self.niri.layout.move_workspace_to_output(&output);
@@ -1678,7 +2124,7 @@ impl State {
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
-            Action::MoveFloatingWindowById { id, x, y } => {
+            Action::MoveFloatingWindowById { id, x, y, animate } => {
                 let window = if let Some(id) = id {
                     let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
                     let window = window.map(|(_, m)| m.window.clone());
@@ -1692,7 +2138,7 @@ impl State {
 
                 self.niri
                     .layout
-                    .move_floating_window(window.as_ref(), x, y, true);
+                    .move_floating_window(window.as_ref(), x, y, animate);
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
@@ -1724,6 +2170,26 @@ impl State {
                     }
                 }
             }
+            Action::ToggleWindowKeepAspectRatio => {
+                let active_window = self
+                    .niri
+                    .layout
+                    .active_workspace_mut()
+                    .and_then(|ws| ws.active_window_mut());
+                if let Some(window) = active_window {
+                    window.toggle_keep_aspect_ratio();
+                }
+            }
+            Action::ToggleWindowKeepAspectRatioById(id) => {
+                let window = self
+                    .niri
+                    .layout
+                    .workspaces_mut()
+                    .find_map(|ws| ws.windows_mut().find(|w| w.id().get() == id));
+                if let Some(window) = window {
+                    window.toggle_keep_aspect_ratio();
+                }
+            }
             Action::ToggleOverview => {
                 self.niri.layout.toggle_overview();
                 self.niri.queue_redraw_all();
@@ -1772,13 +2238,77 @@ impl State {
                 }
                 self.niri.queue_redraw_all();
             }
+            Action::ShowBindConflicts => {
+                if self.niri.bind_conflicts_overlay.show() {
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::OpenConfigError => {
+                if self.niri.config_error_notification.open_in_editor() {
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::ShowTutorial => {
+                if self.niri.tutorial.show() {
+                    self.niri.start_tutorial_hint_timer();
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::DoScreenTransition(delay_ms, duration_ms, style) => {
+                self.backend.with_primary_renderer(|renderer| {
+                    self.niri
+                        .do_screen_transition(renderer, delay_ms, duration_ms, style);
+                });
+            }
+            Action::SetWallpaper(path, output, mode, crossfade_ms) => {
+                let output = match output {
+                    Some(name) => self.niri.output_by_name_match(&name),
+                    None => self.niri.layout.active_output(),
+                };
+                if let Some(output) = output.cloned() {
+                    let wallpaper = Wallpaper { path, mode };
+                    self.niri.set_wallpaper(&output, wallpaper, crossfade_ms);
+                }
+            }
+            Action::EnterAdjustMode => {
+                if self.niri.layout.focus().is_some() && self.niri.adjust_mode.open() {
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::ToggleWindowRule(name) => {
+                self.set_window_rule_enabled(&name, None);
+            }
+            Action::EnableWindowRule(name) => {
+                self.set_window_rule_enabled(&name, Some(true));
+            }
+            Action::DisableWindowRule(name) => {
+                self.set_window_rule_enabled(&name, Some(false));
+            }
+            Action::LoadConfigFile(path) => {
+                self.load_config_file(&path);
+            }
+            Action::WarpPointerToWindow(target) => {
+                self.warp_pointer_to_window(target);
+            }
+            Action::WarpPointerToRegion(region) => {
+                self.warp_pointer_to_region(region);
+            }
+            Action::SaveGroup(name, columns) => {
+                if let Err(err) = self.niri.save_column_group(name, columns.map(usize::from)) {
+                    warn!("error saving column group: {err}");
+                }
+            }
+            Action::RecallGroup(name) => {
+                if let Err(err) = self.niri.recall_column_group(&name) {
+                    warn!("error recalling column group: {err}");
+                }
+            }
         }
     }
 
     fn on_pointer_motion<I: InputBackend>(&mut self, event: I::PointerMotionEvent) {
-        let was_inside_hot_corner = self.niri.pointer_inside_hot_corner;
-        // Any of the early returns here mean that the pointer is not inside the hot corner.
-        self.niri.pointer_inside_hot_corner = false;
+        // Any of the early returns here mean that the pointer is not inside a hot area.
+        let prev_hot_area = self.niri.hot_areas.take_current();
 
         // We need an output to be able to move the pointer.
         if self.niri.global_space.outputs().next().is_none() {
@@ -1796,6 +2326,7 @@ impl State {
 
         // We received an event for the regular pointer, so show it now.
         self.niri.pointer_visibility = PointerVisibility::Visible;
+        self.niri.tablet_cursor_location = None;
 
         // Check if we have an active pointer constraint.
         //
@@ -1833,16 +2364,18 @@ impl State {
 
             // If the pointer is locked, only send relative motion.
             if pointer_locked {
-                pointer.relative_motion(
-                    self,
-                    Some(under.clone()),
-                    &RelativeMotionEvent {
-                        delta: event.delta(),
-                        delta_unaccel: event.delta_unaccel(),
-                        utime: event.time(),
-                    },
-                );
+                let event = RelativeMotionEvent {
+                    delta: event.delta(),
+                    delta_unaccel: event.delta_unaccel(),
+                    utime: event.time(),
+                };
+
+                if self.niri.config.borrow().input.mouse.batch_relative_motion {
+                    self.batch_relative_motion(under.clone(), event);
+                    return;
+                }
 
+                pointer.relative_motion(self, Some(under.clone()), &event);
                 pointer.frame(self);
 
                 // I guess a redraw to hide the tablet cursor could be nice? Doesn't matter too
@@ -1851,6 +2384,9 @@ impl State {
             }
         }
 
+        // The pointer isn't locked anymore, so send any batched motion before the new one.
+        self.flush_relative_motion();
+
         if self
             .niri
             .global_space
@@ -1940,20 +2476,7 @@ impl State {
 
         pointer.frame(self);
 
-        // contents_under() will return no surface when the hot corner should trigger.
-        let hot_corners = self.niri.config.borrow().gestures.hot_corners;
-        if !hot_corners.off
-            && pointer.current_focus().is_none()
-        {
-            let hot_corner = Rectangle::from_size(Size::from((1., 1.)));
-            if let Some((_, pos_within_output)) = self.niri.output_under(pos) {
-                let inside_hot_corner = hot_corner.contains(pos_within_output);
-                if inside_hot_corner && !was_inside_hot_corner {
-                    self.niri.layout.toggle_overview();
-                }
-                self.niri.pointer_inside_hot_corner = inside_hot_corner;
-            }
-        }
+        self.update_hot_area(prev_hot_area, pos);
 
         // Activate a new confinement if necessary.
         self.niri.maybe_activate_pointer_constraint();
@@ -1970,6 +2493,8 @@ impl State {
             }
         }
 
+        self.update_dwell_click();
+
         // Redraw to update the cursor position.
         // FIXME: redraw only outputs overlapping the cursor.
         self.niri.queue_redraw_all();
@@ -1979,9 +2504,8 @@ impl State {
         &mut self,
         event: I::PointerMotionAbsoluteEvent,
     ) {
-        let was_inside_hot_corner = self.niri.pointer_inside_hot_corner;
-        // Any of the early returns here mean that the pointer is not inside the hot corner.
-        self.niri.pointer_inside_hot_corner = false;
+        // Any of the early returns here mean that the pointer is not inside a hot area.
+        let prev_hot_area = self.niri.hot_areas.take_current();
 
         let Some(pos) = self.compute_absolute_location(&event, None).or_else(|| {
             self.global_bounding_rectangle().map(|output_geo| {
@@ -2013,25 +2537,13 @@ impl State {
 
         pointer.frame(self);
 
-        // contents_under() will return no surface when the hot corner should trigger.
-        let hot_corners = self.niri.config.borrow().gestures.hot_corners;
-        if !hot_corners.off
-            && pointer.current_focus().is_none()
-        {
-            let hot_corner = Rectangle::from_size(Size::from((1., 1.)));
-            if let Some((_, pos_within_output)) = self.niri.output_under(pos) {
-                let inside_hot_corner = hot_corner.contains(pos_within_output);
-                if inside_hot_corner && !was_inside_hot_corner {
-                    self.niri.layout.toggle_overview();
-                }
-                self.niri.pointer_inside_hot_corner = inside_hot_corner;
-            }
-        }
+        self.update_hot_area(prev_hot_area, pos);
 
         self.niri.maybe_activate_pointer_constraint();
 
         // We moved the pointer, show it.
         self.niri.pointer_visibility = PointerVisibility::Visible;
+        self.niri.tablet_cursor_location = None;
 
         // Inform the layout of an ongoing DnD operation.
         let mut is_dnd_grab = false;
@@ -2045,36 +2557,190 @@ impl State {
             }
         }
 
+        self.update_dwell_click();
+
         // Redraw to update the cursor position.
         // FIXME: redraw only outputs overlapping the cursor.
         self.niri.queue_redraw_all();
     }
 
-    fn on_pointer_button<I: InputBackend>(&mut self, event: I::PointerButtonEvent) {
+    /// Updates the hot area under the pointer, triggering it when the pointer enters.
+    fn update_hot_area(
+        &mut self,
+        prev: Option<(Output, HotArea, Rectangle<f64, Logical>)>,
+        pos: Point<f64, Logical>,
+    ) {
         let pointer = self.niri.seat.get_pointer().unwrap();
 
-        let serial = SERIAL_COUNTER.next_serial();
+        // contents_under() will return no surface when a hot area should trigger.
+        let mut current = None;
+        let mut config = None;
+        if pointer.current_focus().is_none() {
+            if let Some((output, pos_within_output)) = self.niri.output_under(pos) {
+                let output = output.clone();
+                if let Some((area, geo)) = self.niri.hot_area_under(&output, pos_within_output) {
+                    config = Some(self.niri.hot_corners_for(&output));
+                    current = Some((output, area, geo));
+                }
+            }
+        }
 
-        let button = event.button();
+        let changed = match (&prev, &current) {
+            (Some((prev_output, prev_area, _)), Some((output, area, _))) => {
+                prev_output != output || prev_area != area
+            }
+            (None, None) => false,
+            _ => true,
+        };
 
-        let button_code = event.button_code();
+        let hint_color = config.as_ref().and_then(|c| c.hint_color);
+        let entered = current.as_ref().filter(|_| changed).map(|(_, area, _)| *area);
+        self.niri.hot_areas.set_current(current, hint_color);
 
-        let button_state = event.state();
+        if !changed {
+            return;
+        }
 
-        let mod_key = self.backend.mod_key(&self.niri.config.borrow());
+        if let Some(timer) = self.niri.hot_areas.replace_timer(None) {
+            self.niri.event_loop.remove(timer);
+        }
 
-        // Ignore release events for mouse clicks that triggered a bind.
-        if self.niri.suppressed_buttons.remove(&button_code) {
+        let (Some(area), Some(config)) = (entered, config) else {
+            return;
+        };
+
+        if config.delay_ms == 0 {
+            self.trigger_hot_area(area, &config);
             return;
         }
 
-        if ButtonState::Pressed == button_state {
-            let mods = self.niri.seat.get_keyboard().unwrap().modifier_state();
-            let modifiers = modifiers_from_state(mods);
+        let delay = Duration::from_millis(u64::from(config.delay_ms));
+        let timer = self
+            .niri
+            .event_loop
+            .insert_source(Timer::from_duration(delay), move |_, _, state| {
+                state.niri.hot_areas.replace_timer(None);
 
-            if self.niri.mods_with_mouse_binds.contains(&modifiers) {
-                if let Some(bind) = match button {
-                    Some(MouseButton::Left) => Some(Trigger::MouseLeft),
+                // Only trigger if the pointer stayed in the area for the whole delay.
+                let config = match state.niri.hot_areas.current() {
+                    Some((output, current)) if current == area => {
+                        Some(state.niri.hot_corners_for(output))
+                    }
+                    _ => None,
+                };
+                if let Some(config) = config {
+                    state.trigger_hot_area(area, &config);
+                }
+
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.niri.hot_areas.replace_timer(Some(timer));
+    }
+
+    /// Runs the actions of a hot area.
+    fn trigger_hot_area(&mut self, area: HotArea, config: &HotCorners) {
+        for action in area.actions(config) {
+            self.do_action(action);
+        }
+    }
+
+    /// Starts the dwell-click countdown if the pointer moved far enough.
+    fn update_dwell_click(&mut self) {
+        let Some(config) = self.niri.config.borrow().input.dwell_click else {
+            return;
+        };
+
+        let pos = self.niri.seat.get_pointer().unwrap().current_location();
+        let delay = Duration::from_millis(u64::from(config.delay_ms));
+        if !self
+            .niri
+            .dwell_click
+            .on_motion(pos, config.distance.0, delay)
+        {
+            return;
+        }
+
+        let timer = self
+            .niri
+            .event_loop
+            .insert_source(Timer::from_duration(delay), |_, _, state| {
+                state.niri.dwell_click.replace_timer(None);
+                state.dwell_click();
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        if let Some(timer) = self.niri.dwell_click.replace_timer(Some(timer)) {
+            self.niri.event_loop.remove(timer);
+        }
+    }
+
+    /// Clicks at the end of the dwell-click countdown.
+    fn dwell_click(&mut self) {
+        if !self.niri.dwell_click.finish() {
+            return;
+        }
+        self.niri.queue_redraw_all();
+
+        // Don't click in the middle of a drag or another grab.
+        if self.niri.seat.get_pointer().unwrap().is_grabbed() {
+            return;
+        }
+
+        for button_state in [ButtonState::Pressed, ButtonState::Released] {
+            let event = InjectedButtonEvent::new(BTN_LEFT, button_state);
+            self.process_input_event::<InjectedInputBackend>(InputEvent::PointerButton { event });
+        }
+    }
+
+    fn on_pointer_button<I: InputBackend>(&mut self, event: I::PointerButtonEvent) {
+        let pointer = self.niri.seat.get_pointer().unwrap();
+
+        let serial = SERIAL_COUNTER.next_serial();
+
+        let button = event.button();
+
+        let button_code = event.button_code();
+
+        let button_state = event.state();
+
+        let mod_key = self.backend.mod_key(&self.niri.config.borrow());
+
+        // Ignore release events for mouse clicks that triggered a bind.
+        if self.niri.suppressed_buttons.remove(&button_code) {
+            return;
+        }
+
+        if ButtonState::Pressed == button_state {
+            // Super+click is a chord, so it shouldn't count towards a double tap of Super.
+            self.niri.modifier_taps.reset();
+
+            // Clicking by hand ends the dwell-click countdown.
+            self.niri.stop_dwell_click();
+
+            // Clicking the config error notification opens the config in an editor.
+            if button == Some(MouseButton::Left)
+                && self
+                    .niri
+                    .ui_overlay_is_open(UiOverlay::ConfigErrorNotification)
+            {
+                let pos = pointer.current_location();
+                let over_notification = self.niri.output_under(pos).is_some_and(|(output, pos)| {
+                    self.niri.config_error_notification.contains(output, pos)
+                });
+                if over_notification && self.niri.config_error_notification.open_in_editor() {
+                    self.niri.suppressed_buttons.insert(button_code);
+                    self.niri.queue_redraw_all();
+                    return;
+                }
+            }
+
+            let mods = self.niri.seat.get_keyboard().unwrap().modifier_state();
+            let modifiers = modifiers_from_state(mods);
+
+            if self.niri.mods_with_mouse_binds.contains(&modifiers) {
+                if let Some(bind) = match button {
+                    Some(MouseButton::Left) => Some(Trigger::MouseLeft),
                     Some(MouseButton::Right) => Some(Trigger::MouseRight),
                     Some(MouseButton::Middle) => Some(Trigger::MouseMiddle),
                     Some(MouseButton::Back) => Some(Trigger::MouseBack),
@@ -2113,7 +2779,13 @@ impl State {
                     self.niri
                         .layout
                         .view_offset_gesture_begin(&output, Some(ws_idx), false);
-                    let grab = SpatialMovementGrab::new(start_data, output, ws_id, true);
+                    let recognizer = DragRecognizer::new(
+                        self.niri.config.borrow().input.drag_threshold,
+                        location,
+                        Duration::from_millis(u64::from(event.time_msec())),
+                    );
+                    let grab =
+                        SpatialMovementGrab::new(start_data, output, ws_id, recognizer, true);
                     pointer.set_grab(self, grab, serial, Focus::Clear);
                     self.niri
                         .cursor_manager
@@ -2150,7 +2822,13 @@ impl State {
                             button: button_code,
                             location,
                         };
-                        let grab = SpatialMovementGrab::new(start_data, output, ws_id, false);
+                        let recognizer = DragRecognizer::new(
+                            self.niri.config.borrow().input.drag_threshold,
+                            location,
+                            Duration::from_millis(u64::from(event.time_msec())),
+                        );
+                        let grab =
+                            SpatialMovementGrab::new(start_data, output, ws_id, recognizer, false);
                         pointer.set_grab(self, grab, serial, Focus::Clear);
                         self.niri
                             .cursor_manager
@@ -2191,14 +2869,15 @@ impl State {
                                 button: button_code,
                                 location,
                             };
-                            let grab = MoveGrab::new(start_data, window.clone(), is_overview_open);
+                            // Don't start moving the window until the click turns into a drag,
+                            // so that sloppy Mod+clicks on a sensitive touchpad don't move it.
+                            let recognizer = DragRecognizer::new(
+                                self.niri.config.borrow().input.drag_threshold,
+                                location,
+                                Duration::from_millis(u64::from(event.time_msec())),
+                            );
+                            let grab = MoveGrab::new(start_data, window.clone(), Some(recognizer));
                             pointer.set_grab(self, grab, serial, Focus::Clear);
-
-                            if !is_overview_open {
-                                self.niri
-                                    .cursor_manager
-                                    .set_cursor_image(CursorImageStatus::Named(CursorIcon::Move));
-                            }
                         }
                     }
                 }
@@ -2397,7 +3076,10 @@ impl State {
                                 modifiers: Modifiers::empty(),
                             },
                             action: Action::FocusColumnLeftUnderMouse,
+                            activation: BindActivation::Press,
                             repeat: true,
+                            repeat_delay: None,
+                            repeat_rate: None,
                             cooldown: None,
                             allow_when_locked: false,
                             allow_inhibiting: false,
@@ -2409,7 +3091,10 @@ impl State {
                                 modifiers: Modifiers::empty(),
                             },
                             action: Action::FocusColumnRightUnderMouse,
+                            activation: BindActivation::Press,
                             repeat: true,
+                            repeat_delay: None,
+                            repeat_rate: None,
                             cooldown: None,
                             allow_when_locked: false,
                             allow_inhibiting: false,
@@ -2453,7 +3138,10 @@ impl State {
                                 modifiers: Modifiers::empty(),
                             },
                             action: Action::FocusWorkspaceUpUnderMouse,
+                            activation: BindActivation::Press,
                             repeat: true,
+                            repeat_delay: None,
+                            repeat_rate: None,
                             cooldown: Some(Duration::from_millis(50)),
                             allow_when_locked: false,
                             allow_inhibiting: false,
@@ -2465,7 +3153,10 @@ impl State {
                                 modifiers: Modifiers::empty(),
                             },
                             action: Action::FocusWorkspaceDownUnderMouse,
+                            activation: BindActivation::Press,
                             repeat: true,
+                            repeat_delay: None,
+                            repeat_rate: None,
                             cooldown: Some(Duration::from_millis(50)),
                             allow_when_locked: false,
                             allow_inhibiting: false,
@@ -2479,7 +3170,10 @@ impl State {
                                 modifiers: Modifiers::empty(),
                             },
                             action: Action::FocusColumnLeftUnderMouse,
+                            activation: BindActivation::Press,
                             repeat: true,
+                            repeat_delay: None,
+                            repeat_rate: None,
                             cooldown: Some(Duration::from_millis(50)),
                             allow_when_locked: false,
                             allow_inhibiting: false,
@@ -2491,7 +3185,10 @@ impl State {
                                 modifiers: Modifiers::empty(),
                             },
                             action: Action::FocusColumnRightUnderMouse,
+                            activation: BindActivation::Press,
                             repeat: true,
+                            repeat_delay: None,
+                            repeat_rate: None,
                             cooldown: Some(Duration::from_millis(50)),
                             allow_when_locked: false,
                             allow_inhibiting: false,
@@ -2606,6 +3303,259 @@ impl State {
         )
     }
 
+    /// Computes the area the tablet is mapped to.
+    ///
+    /// Returns the area in global coordinates, whether to keep the tablet aspect ratio, the size
+    /// of one physical pixel and the transform to apply.
+    fn tablet_target_area(
+        &self,
+        device_output: Option<&Output>,
+    ) -> Option<(Rectangle<f64, Logical>, bool, f64, Transform)> {
+        if self.niri.config.borrow().input.tablet.map_to_focused_window {
+            let window_area = self.niri.layout.active_output().and_then(|output| {
+                let monitor = self.niri.layout.monitor_for_output(output)?;
+                let mut rect = monitor.active_tile_visual_rectangle()?;
+                let output_geo = self.niri.global_space.output_geometry(output).unwrap();
+                rect.loc += output_geo.loc.to_f64();
+                let px = 1. / output.current_scale().fractional_scale();
+                Some((rect, true, px, output.current_transform()))
+            });
+            if window_area.is_some() {
+                return window_area;
+            }
+        }
+
+        if let Some(output) = device_output.or_else(|| self.niri.output_for_tablet()) {
+            let output_geo = self.niri.global_space.output_geometry(output).unwrap();
+            let px = 1. / output.current_scale().fractional_scale();
+            return Some((output_geo.to_f64(), true, px, output.current_transform()));
+        }
+
+        let geo = self.global_bounding_rectangle()?;
+
+        // FIXME: this 1 px size should ideally somehow be computed for the rightmost output
+        // corresponding to the position on the right when clamping.
+        let output = self.niri.global_space.outputs().next().unwrap();
+        let px = 1. / output.current_scale().fractional_scale();
+
+        // Do not keep ratio for the unified mode as this is what OpenTabletDriver expects.
+        Some((geo.to_f64(), false, px, Transform::Normal))
+    }
+
+    fn compute_tablet_position<I: InputBackend>(
+        &self,
+        event: &(impl Event<I> + TabletToolEvent<I>),
+    ) -> Option<Point<f64, Logical>>
+    where
+        I::Device: 'static,
+    {
+        let device_output = event.device().output(self);
+        let (target_geo, keep_ratio, px, transform) =
+            self.tablet_target_area(device_output.as_ref())?;
+
+        let size = transform.invert().transform_size(target_geo.size);
+        let mut pos =
+            transform.transform_point_in(event.position_transformed(size.to_i32_round()), &size);
+
+        if keep_ratio {
+            pos.x /= target_geo.size.w;
+            pos.y /= target_geo.size.h;
+
+            let device = event.device();
+            if let Some(device) = (&device as &dyn Any).downcast_ref::<input::Device>() {
+                if let Some(data) = self.niri.tablets.get(device) {
+                    // This does the same thing as mutter with "keep aspect ratio" enabled.
+                    let target_aspect_ratio = size.w / size.h;
+                    let ratio = data.aspect_ratio / target_aspect_ratio;
+
+                    if ratio > 1. {
+                        pos.x *= ratio;
+                    } else {
+                        pos.y /= ratio;
+                    }
+                }
+            };
+
+            pos.x *= target_geo.size.w;
+            pos.y *= target_geo.size.h;
+        }
+
+        pos.x = pos.x.clamp(0., target_geo.size.w - px);
+        pos.y = pos.y.clamp(0., target_geo.size.h - px);
+        Some(pos + target_geo.loc)
+    }
+
+    fn on_tablet_tool_axis<I: InputBackend>(&mut self, event: I::TabletToolAxisEvent)
+    where
+        I::Device: 'static, // Needed for downcasting.
+    {
+        let Some(pos) = self.compute_tablet_position(&event) else {
+            return;
+        };
+
+        let under = self.niri.contents_under(pos);
+
+        let tablet_seat = self.niri.seat.tablet_seat();
+        let tablet = tablet_seat.get_tablet(&TabletDescriptor::from(&event.device()));
+        let tool = tablet_seat.get_tool(&event.tool());
+        if let (Some(tablet), Some(tool)) = (tablet, tool) {
+            if event.pressure_has_changed() {
+                tool.pressure(event.pressure());
+            }
+            if event.distance_has_changed() {
+                tool.distance(event.distance());
+            }
+            if event.tilt_has_changed() {
+                tool.tilt(event.tilt());
+            }
+            if event.slider_has_changed() {
+                tool.slider_position(event.slider_position());
+            }
+            if event.rotation_has_changed() {
+                tool.rotation(event.rotation());
+            }
+            if event.wheel_has_changed() {
+                tool.wheel(event.wheel_delta(), event.wheel_delta_discrete());
+            }
+
+            tool.motion(
+                pos,
+                under.surface,
+                &tablet,
+                SERIAL_COUNTER.next_serial(),
+                event.time_msec(),
+            );
+
+            self.niri.pointer_visibility = PointerVisibility::Visible;
+            self.niri.tablet_cursor_location = Some(pos);
+        }
+
+        // Redraw to update the cursor position.
+        // FIXME: redraw only outputs overlapping the cursor.
+        self.niri.queue_redraw_all();
+    }
+
+    fn on_tablet_tool_tip<I: InputBackend>(&mut self, event: I::TabletToolTipEvent) {
+        let Some(tool) = self.niri.seat.tablet_seat().get_tool(&event.tool()) else {
+            return;
+        };
+
+        match event.tip_state() {
+            TabletToolTipState::Down => {
+                let serial = SERIAL_COUNTER.next_serial();
+                tool.tip_down(serial, event.time_msec());
+
+                if let Some(pos) = self.niri.tablet_cursor_location {
+                    let under = self.niri.contents_under(pos);
+                    if let Some((window, _)) = under.window {
+                        if !self.niri.layout.is_overview_open() {
+                            self.niri.layout.activate_window(&window);
+                        }
+
+                        // FIXME: granular.
+                        self.niri.queue_redraw_all();
+                    } else if let Some(output) = under.output {
+                        self.niri.layout.focus_output(&output);
+
+                        // FIXME: granular.
+                        self.niri.queue_redraw_all();
+                    }
+                    self.niri.focus_layer_surface_if_on_demand(under.layer);
+                }
+            }
+            TabletToolTipState::Up => {
+                tool.tip_up(event.time_msec());
+            }
+        }
+    }
+
+    fn on_tablet_tool_proximity<I: InputBackend>(&mut self, event: I::TabletToolProximityEvent)
+    where
+        I::Device: 'static, // Needed for downcasting.
+    {
+        let Some(pos) = self.compute_tablet_position(&event) else {
+            return;
+        };
+
+        let under = self.niri.contents_under(pos);
+
+        let tablet_seat = self.niri.seat.tablet_seat();
+        let display_handle = self.niri.display_handle.clone();
+        let tool = tablet_seat.add_tool::<Self>(self, &display_handle, &event.tool());
+        let tablet = tablet_seat.get_tablet(&TabletDescriptor::from(&event.device()));
+        if let Some(tablet) = tablet {
+            match event.state() {
+                ProximityState::In => {
+                    if let Some(under) = under.surface {
+                        tool.proximity_in(
+                            pos,
+                            under,
+                            &tablet,
+                            SERIAL_COUNTER.next_serial(),
+                            event.time_msec(),
+                        );
+                    }
+                    self.niri.pointer_visibility = PointerVisibility::Visible;
+                    self.niri.tablet_cursor_location = Some(pos);
+                }
+                ProximityState::Out => {
+                    tool.proximity_out(event.time_msec());
+
+                    // Move the mouse pointer here to avoid discontinuity.
+                    if let Some(pos) = self.niri.tablet_cursor_location {
+                        self.move_cursor(pos);
+                    }
+
+                    self.niri.pointer_visibility = PointerVisibility::Visible;
+                    self.niri.tablet_cursor_location = None;
+                }
+            }
+
+            // FIXME: granular.
+            self.niri.queue_redraw_all();
+        }
+    }
+
+    fn on_tablet_tool_button<I: InputBackend>(&mut self, event: I::TabletToolButtonEvent) {
+        let button = event.button();
+        let button_state = event.button_state();
+
+        // Ignore release events for pen buttons that triggered a bind.
+        if self.niri.suppressed_buttons.remove(&button) {
+            return;
+        }
+
+        if button_state == ButtonState::Pressed {
+            let trigger = match button {
+                BTN_STYLUS => Some(Trigger::PenButton1),
+                BTN_STYLUS2 => Some(Trigger::PenButton2),
+                BTN_STYLUS3 => Some(Trigger::PenButton3),
+                _ => None,
+            };
+
+            let mod_key = self.backend.mod_key(&self.niri.config.borrow());
+            let mods = self.niri.seat.get_keyboard().unwrap().modifier_state();
+            if let Some(bind) = trigger.and_then(|trigger| {
+                let config = self.niri.config.borrow();
+                find_configured_bind(&config.binds, mod_key, trigger, mods)
+            }) {
+                self.niri.suppressed_buttons.insert(button);
+                self.handle_bind(bind);
+                return;
+            }
+        }
+
+        let tool = self.niri.seat.tablet_seat().get_tool(&event.tool());
+        if let Some(tool) = tool {
+            tool.button(
+                button,
+                button_state,
+                SERIAL_COUNTER.next_serial(),
+                event.time_msec(),
+            );
+        }
+    }
+
 
     fn on_switch_toggle<I: InputBackend>(&mut self, evt: I::SwitchToggleEvent) {
         let Some(switch) = evt.switch() else {
@@ -2617,6 +3567,11 @@ impl State {
             debug!("lid switch {}", if is_closed { "closed" } else { "opened" });
             self.niri.is_lid_closed = is_closed;
             self.backend.on_output_config_changed(&mut self.niri);
+
+            let policy = self.niri.config.borrow().logind.lid_switch;
+            if is_closed && policy == Some(LidSwitchPolicy::Suspend) {
+                self.backend.suspend();
+            }
         }
 
         let action = {
@@ -2633,9 +3588,13 @@ impl State {
 /// Check whether the key should be intercepted and mark intercepted
 /// pressed keys as `suppressed`, thus preventing `releases` corresponding
 /// to them from being delivered.
+///
+/// Binds that trigger on release are remembered in `release_binds` on press and returned on the
+/// matching release, so that letting go of the modifiers first doesn't lose the bind.
 #[allow(clippy::too_many_arguments)]
 fn should_intercept_key(
     suppressed_keys: &mut HashSet<Keycode>,
+    release_binds: &mut HashMap<Keycode, Bind>,
     bindings: &Binds,
     mod_key: ModKey,
     key_code: Keycode,
@@ -2666,8 +3625,31 @@ fn should_intercept_key(
         (Some(bind), true) => {
             if is_inhibiting_shortcuts && bind.allow_inhibiting {
                 FilterResult::Forward
+            } else if bind.activation == BindActivation::Release {
+                suppressed_keys.insert(key_code);
+                release_binds.insert(key_code, bind);
+                FilterResult::Intercept(None)
             } else {
                 suppressed_keys.insert(key_code);
+
+                // Peeks last while the key is held.
+                if matches!(
+                    bind.action,
+                    Action::PeekWorkspaceDown | Action::PeekWorkspaceUp
+                ) {
+                    // The peek never ends by itself, so the release must not be dropped by the
+                    // cooldown of the peek bind, which shares its key.
+                    let end = Bind {
+                        action: Action::EndWorkspacePeek,
+                        repeat: false,
+                        cooldown: None,
+                        ..bind.clone()
+                    };
+                    release_binds.insert(key_code, end);
+                } else {
+                    release_binds.remove(&key_code);
+                }
+
                 FilterResult::Intercept(Some(bind))
             }
         }
@@ -2678,7 +3660,7 @@ fn should_intercept_key(
             // if it was inhibited on press (forwarded to the client), it wouldn't be suppressed,
             // so the release would already have been forwarded at the start of this function.
             suppressed_keys.remove(&key_code);
-            FilterResult::Intercept(None)
+            FilterResult::Intercept(release_binds.remove(&key_code))
         }
         (None, true) => FilterResult::Forward,
     }
@@ -2713,7 +3695,10 @@ fn find_bind(
                 modifiers: Modifiers::empty(),
             },
             action,
+            activation: BindActivation::Press,
             repeat: true,
+            repeat_delay: None,
+            repeat_rate: None,
             cooldown: None,
             allow_when_locked: false,
             // In a worst-case scenario, the user has no way to unlock the compositor and a
@@ -2745,7 +3730,8 @@ fn find_configured_bind(
     }
 
     for bind in &bindings.0 {
-        if bind.key.trigger != trigger {
+        // Double-tap binds are matched separately in find_double_tap_bind().
+        if bind.key.trigger != trigger || bind.activation == BindActivation::DoubleTap {
             continue;
         }
 
@@ -2764,6 +3750,16 @@ fn find_configured_bind(
     None
 }
 
+fn find_double_tap_bind(bindings: &Binds, raw: Keysym) -> Option<Bind> {
+    bindings
+        .0
+        .iter()
+        .find(|bind| {
+            bind.activation == BindActivation::DoubleTap && bind.key.trigger == Trigger::Keysym(raw)
+        })
+        .cloned()
+}
+
 fn find_configured_switch_action(
     bindings: &SwitchBinds,
     switch: Switch,
@@ -2851,7 +3847,99 @@ fn hardcoded_overview_bind(raw: Keysym, mods: ModifiersState) -> Option<Bind> {
             modifiers: Modifiers::empty(),
         },
         action,
+        activation: BindActivation::Press,
         repeat,
+        repeat_delay: None,
+        repeat_rate: None,
+        cooldown: None,
+        allow_when_locked: false,
+        allow_inhibiting: false,
+        hotkey_overlay_title: None,
+    })
+}
+
+fn hardcoded_hardware_key_bind(modified: Keysym) -> Option<Bind> {
+    let action = match modified {
+        Keysym::XF86_AudioRaiseVolume => Action::VolumeUp,
+        Keysym::XF86_AudioLowerVolume => Action::VolumeDown,
+        Keysym::XF86_AudioMute => Action::ToggleMute,
+        Keysym::XF86_MonBrightnessUp => Action::BrightnessUp,
+        Keysym::XF86_MonBrightnessDown => Action::BrightnessDown,
+        _ => return None,
+    };
+    let repeat = action != Action::ToggleMute;
+
+    Some(Bind {
+        key: Key {
+            trigger: Trigger::Keysym(modified),
+            modifiers: Modifiers::empty(),
+        },
+        action,
+        activation: BindActivation::Press,
+        repeat,
+        repeat_delay: None,
+        repeat_rate: None,
+        cooldown: None,
+        allow_when_locked: true,
+        allow_inhibiting: false,
+        hotkey_overlay_title: None,
+    })
+}
+
+fn hardcoded_adjust_mode_bind(
+    raw: Keysym,
+    mods: ModifiersState,
+    floating: bool,
+    config: niri_config::AdjustMode,
+) -> Option<Bind> {
+    let mods = modifiers_from_state(mods);
+    let resize = if mods.is_empty() {
+        false
+    } else if mods == Modifiers::SHIFT {
+        true
+    } else {
+        return None;
+    };
+
+    let (dx, dy) = match raw {
+        Keysym::Left | Keysym::h => (-1, 0),
+        Keysym::Right | Keysym::l => (1, 0),
+        Keysym::Up | Keysym::k => (0, -1),
+        Keysym::Down | Keysym::j => (0, 1),
+        _ => return None,
+    };
+
+    let move_step = config.move_step.0;
+    let resize_step = i32::from(config.resize_step);
+
+    let action = if floating && !resize {
+        Action::MoveFloatingWindowById {
+            id: None,
+            x: PositionChange::AdjustFixed(f64::from(dx) * move_step),
+            y: PositionChange::AdjustFixed(f64::from(dy) * move_step),
+            animate: true,
+        }
+    } else if dx != 0 {
+        let change = SizeChange::AdjustFixed(dx * resize_step);
+        if floating {
+            Action::SetWindowWidth(change)
+        } else {
+            Action::SetColumnWidth(change)
+        }
+    } else {
+        Action::SetWindowHeight(SizeChange::AdjustFixed(dy * resize_step))
+    };
+
+    Some(Bind {
+        key: Key {
+            trigger: Trigger::Keysym(raw),
+            modifiers: mods,
+        },
+        action,
+        activation: BindActivation::Press,
+        repeat: true,
+        repeat_delay: None,
+        repeat_rate: None,
         cooldown: None,
         allow_when_locked: false,
         allow_inhibiting: false,
@@ -3054,6 +4142,31 @@ pub fn apply_libinput_settings(config: &niri_config::Input, device: &mut input::
         }
     }
 
+    let is_tablet = device.has_capability(input::DeviceCapability::TabletTool);
+    if is_tablet {
+        let c = &config.tablet;
+        let _ = device.config_send_events_set_mode(if c.off {
+            input::SendEventsMode::DISABLED
+        } else {
+            input::SendEventsMode::ENABLED
+        });
+
+        #[rustfmt::skip]
+        const IDENTITY_MATRIX: [f32; 6] = [
+            1., 0., 0.,
+            0., 1., 0.,
+        ];
+
+        let _ = device.config_calibration_set_matrix(
+            c.calibration_matrix
+                .as_deref()
+                .and_then(|m| m.try_into().ok())
+                .or(device.config_calibration_default_matrix())
+                .unwrap_or(IDENTITY_MATRIX),
+        );
+
+        let _ = device.config_left_handed_set(c.left_handed);
+    }
 }
 
 pub fn mods_with_binds(mod_key: ModKey, binds: &Binds, triggers: &[Trigger]) -> HashSet<Modifiers> {
@@ -3119,7 +4232,10 @@ mod tests {
                 modifiers: Modifiers::COMPOSITOR | Modifiers::CTRL,
             },
             action: Action::CloseWindow,
+            activation: BindActivation::Press,
             repeat: true,
+            repeat_delay: None,
+            repeat_rate: None,
             cooldown: None,
             allow_when_locked: false,
             allow_inhibiting: true,
@@ -3139,6 +4255,7 @@ mod tests {
         let close_key_event = |suppr: &mut HashSet<Keycode>, mods: ModifiersState, pressed| {
             should_intercept_key(
                 suppr,
+                &mut HashMap::new(),
                 &bindings,
                 comp_mod,
                 close_key_code,
@@ -3155,6 +4272,7 @@ mod tests {
         let none_key_event = |suppr: &mut HashSet<Keycode>, mods: ModifiersState, pressed| {
             should_intercept_key(
                 suppr,
+                &mut HashMap::new(),
                 &bindings,
                 comp_mod,
                 Keycode::from(Keysym::l.raw() + 8),
@@ -3293,6 +4411,177 @@ mod tests {
         assert!(suppressed_keys.is_empty());
     }
 
+    #[test]
+    fn release_binds() {
+        let bindings = Binds(vec![Bind {
+            key: Key {
+                trigger: Trigger::Keysym(Keysym::q),
+                modifiers: Modifiers::COMPOSITOR,
+            },
+            action: Action::CloseWindow,
+            activation: BindActivation::Release,
+            repeat: true,
+            repeat_delay: None,
+            repeat_rate: None,
+            cooldown: None,
+            allow_when_locked: false,
+            allow_inhibiting: true,
+            hotkey_overlay_title: None,
+        }]);
+
+        let mut suppressed_keys = HashSet::new();
+        let mut release_binds = HashMap::new();
+        let key_code = Keycode::from(Keysym::q.raw() + 8);
+
+        let mut key_event = |suppr: &mut HashSet<Keycode>, mods: ModifiersState, pressed| {
+            should_intercept_key(
+                suppr,
+                &mut release_binds,
+                &bindings,
+                ModKey::Super,
+                key_code,
+                Keysym::q,
+                Some(Keysym::q),
+                pressed,
+                mods,
+                false,
+                false,
+            )
+        };
+
+        let mods = ModifiersState {
+            logo: true,
+            ..Default::default()
+        };
+
+        // The press is suppressed but doesn't trigger anything yet.
+        let filter = key_event(&mut suppressed_keys, mods, true);
+        assert!(matches!(filter, FilterResult::Intercept(None)));
+        assert!(suppressed_keys.contains(&key_code));
+
+        // The bind triggers on release, even if Mod was released first.
+        let filter = key_event(&mut suppressed_keys, ModifiersState::default(), false);
+        assert!(matches!(
+            filter,
+            FilterResult::Intercept(Some(Bind {
+                action: Action::CloseWindow,
+                ..
+            }))
+        ));
+        assert!(suppressed_keys.is_empty());
+
+        // Without Mod, the key goes to the client in both directions.
+        let filter = key_event(&mut suppressed_keys, ModifiersState::default(), true);
+        assert!(matches!(filter, FilterResult::Forward));
+        let filter = key_event(&mut suppressed_keys, ModifiersState::default(), false);
+        assert!(matches!(filter, FilterResult::Forward));
+    }
+
+    #[test]
+    fn peek_ends_on_release() {
+        check_peek_ends_on_release(None);
+    }
+
+    #[test]
+    fn peek_with_cooldown_ends_on_release() {
+        check_peek_ends_on_release(Some(Duration::from_millis(500)));
+    }
+
+    fn check_peek_ends_on_release(cooldown: Option<Duration>) {
+        let bindings = Binds(vec![Bind {
+            key: Key {
+                trigger: Trigger::Keysym(Keysym::grave),
+                modifiers: Modifiers::COMPOSITOR,
+            },
+            action: Action::PeekWorkspaceDown,
+            activation: BindActivation::Press,
+            repeat: true,
+            repeat_delay: None,
+            repeat_rate: None,
+            cooldown,
+            allow_when_locked: false,
+            allow_inhibiting: true,
+            hotkey_overlay_title: None,
+        }]);
+
+        let mut suppressed_keys = HashSet::new();
+        let mut release_binds = HashMap::new();
+        let key_code = Keycode::from(Keysym::grave.raw() + 8);
+
+        let mut key_event = |mods: ModifiersState, pressed| {
+            should_intercept_key(
+                &mut suppressed_keys,
+                &mut release_binds,
+                &bindings,
+                ModKey::Super,
+                key_code,
+                Keysym::grave,
+                Some(Keysym::grave),
+                pressed,
+                mods,
+                false,
+                false,
+            )
+        };
+
+        let mods = ModifiersState {
+            logo: true,
+            ..Default::default()
+        };
+
+        let filter = key_event(mods, true);
+        assert!(matches!(
+            filter,
+            FilterResult::Intercept(Some(Bind {
+                action: Action::PeekWorkspaceDown,
+                ..
+            }))
+        ));
+
+        // Releasing the key ends the peek, even if Mod was released first. The end bind has no
+        // cooldown, so handle_bind() never drops it as being on the peek bind's cooldown.
+        let filter = key_event(ModifiersState::default(), false);
+        assert!(matches!(
+            filter,
+            FilterResult::Intercept(Some(Bind {
+                action: Action::EndWorkspacePeek,
+                repeat: false,
+                cooldown: None,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn double_tap_binds_are_separate() {
+        let bindings = Binds(vec![Bind {
+            key: Key {
+                trigger: Trigger::Keysym(Keysym::Super_L),
+                modifiers: Modifiers::empty(),
+            },
+            action: Action::ToggleOverview,
+            activation: BindActivation::DoubleTap,
+            repeat: true,
+            repeat_delay: None,
+            repeat_rate: None,
+            cooldown: None,
+            allow_when_locked: false,
+            allow_inhibiting: true,
+            hotkey_overlay_title: None,
+        }]);
+
+        let mods = ModifiersState::default();
+        let trigger = Trigger::Keysym(Keysym::Super_L);
+        assert_eq!(
+            find_configured_bind(&bindings, ModKey::Super, trigger, mods),
+            None
+        );
+        assert_eq!(
+            find_double_tap_bind(&bindings, Keysym::Super_L).map(|bind| bind.action),
+            Some(Action::ToggleOverview)
+        );
+    }
+
     #[test]
     fn comp_mod_handling() {
         let bindings = Binds(vec![
@@ -3302,7 +4591,10 @@ mod tests {
                     modifiers: Modifiers::COMPOSITOR,
                 },
                 action: Action::CloseWindow,
+                activation: BindActivation::Press,
                 repeat: true,
+                repeat_delay: None,
+                repeat_rate: None,
                 cooldown: None,
                 allow_when_locked: false,
                 allow_inhibiting: true,
@@ -3314,7 +4606,10 @@ mod tests {
                     modifiers: Modifiers::SUPER,
                 },
                 action: Action::FocusColumnLeft,
+                activation: BindActivation::Press,
                 repeat: true,
+                repeat_delay: None,
+                repeat_rate: None,
                 cooldown: None,
                 allow_when_locked: false,
                 allow_inhibiting: true,
@@ -3326,7 +4621,10 @@ mod tests {
                     modifiers: Modifiers::empty(),
                 },
                 action: Action::FocusWindowDown,
+                activation: BindActivation::Press,
                 repeat: true,
+                repeat_delay: None,
+                repeat_rate: None,
                 cooldown: None,
                 allow_when_locked: false,
                 allow_inhibiting: true,
@@ -3338,7 +4636,10 @@ mod tests {
                     modifiers: Modifiers::COMPOSITOR | Modifiers::SUPER,
                 },
                 action: Action::FocusWindowUp,
+                activation: BindActivation::Press,
                 repeat: true,
+                repeat_delay: None,
+                repeat_rate: None,
                 cooldown: None,
                 allow_when_locked: false,
                 allow_inhibiting: true,
@@ -3350,7 +4651,10 @@ mod tests {
                     modifiers: Modifiers::SUPER | Modifiers::ALT,
                 },
                 action: Action::FocusColumnRight,
+                activation: BindActivation::Press,
                 repeat: true,
+                repeat_delay: None,
+                repeat_rate: None,
                 cooldown: None,
                 allow_when_locked: false,
                 allow_inhibiting: true,
@@ -3477,4 +4781,46 @@ mod tests {
             None,
         );
     }
+
+    #[test]
+    fn adjust_mode_binds() {
+        let config = niri_config::AdjustMode::default();
+        let shift = ModifiersState {
+            shift: true,
+            ..Default::default()
+        };
+
+        let action = |raw, mods, floating| {
+            hardcoded_adjust_mode_bind(raw, mods, floating, config).map(|bind| bind.action)
+        };
+
+        assert_eq!(
+            action(Keysym::Left, ModifiersState::default(), true),
+            Some(Action::MoveFloatingWindowById {
+                id: None,
+                x: PositionChange::AdjustFixed(-10.),
+                y: PositionChange::AdjustFixed(0.),
+                animate: true,
+            })
+        );
+        assert_eq!(
+            action(Keysym::Down, shift, true),
+            Some(Action::SetWindowHeight(SizeChange::AdjustFixed(10)))
+        );
+        assert_eq!(
+            action(Keysym::Right, ModifiersState::default(), false),
+            Some(Action::SetColumnWidth(SizeChange::AdjustFixed(10)))
+        );
+        assert_eq!(
+            action(Keysym::Up, shift, false),
+            Some(Action::SetWindowHeight(SizeChange::AdjustFixed(-10)))
+        );
+
+        let ctrl = ModifiersState {
+            ctrl: true,
+            ..Default::default()
+        };
+        assert_eq!(action(Keysym::Left, ctrl, true), None);
+        assert_eq!(action(Keysym::q, ModifiersState::default(), true), None);
+    }
 }
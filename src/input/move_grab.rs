@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use smithay::backend::input::ButtonState;
 use smithay::desktop::Window;
 use smithay::input::pointer::{
@@ -10,12 +12,14 @@ use smithay::input::pointer::{
 use smithay::input::SeatHandler;
 use smithay::utils::{IsAlive, Logical, Point};
 
+use crate::input::drag_recognizer::DragRecognizer;
 use crate::niri::State;
 
 pub struct MoveGrab {
     start_data: PointerGrabStartData<State>,
     last_location: Point<f64, Logical>,
     window: Window,
+    recognizer: Option<DragRecognizer>,
     gesture: GestureState,
 }
 
@@ -26,12 +30,16 @@ enum GestureState {
 }
 
 impl MoveGrab {
+    /// Creates a move grab.
+    ///
+    /// With a `recognizer`, the move only starts once the press turns into a drag. Otherwise, it
+    /// starts right away, which is what clients expect when they request a move themselves.
     pub fn new(
         start_data: PointerGrabStartData<State>,
         window: Window,
-        use_threshold: bool,
+        recognizer: Option<DragRecognizer>,
     ) -> Self {
-        let gesture = if use_threshold {
+        let gesture = if recognizer.is_some() {
             GestureState::Recognizing
         } else {
             GestureState::Move
@@ -41,6 +49,7 @@ impl MoveGrab {
             last_location: start_data.location,
             start_data,
             window,
+            recognizer,
             gesture,
         }
     }
@@ -74,10 +83,11 @@ impl PointerGrab<State> for MoveGrab {
                 self.last_location = event.location;
 
                 if self.gesture == GestureState::Recognizing {
-                    let c = event.location - self.start_data.location;
-
-                    // Check if the gesture moved far enough to decide.
-                    if c.x * c.x + c.y * c.y >= 8. * 8. {
+                    let time = Duration::from_millis(u64::from(event.time));
+                    if self
+                        .recognizer
+                        .is_some_and(|r| r.is_drag(event.location, time))
+                    {
                         self.gesture = GestureState::Move;
 
                         data.niri
@@ -0,0 +1,124 @@
+//! Batching the relative motion of a locked pointer.
+//!
+//! High-rate mice send motion events at up to several kHz, unevenly spaced relative to the
+//! display frames. Games that lock the pointer then see an uneven amount of motion from one frame
+//! to the next. With batching, the motion is summed up and sent once per frame of the output
+//! under the pointer, with the hardware timestamp of the last event.
+
+use std::time::Duration;
+
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::RegistrationToken;
+use smithay::input::pointer::RelativeMotionEvent;
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::utils::{IsAlive as _, Logical, Point};
+
+use crate::niri::State;
+
+/// Batch interval for outputs that don't report their refresh rate.
+const FALLBACK_INTERVAL: Duration = Duration::from_micros(16_667);
+
+/// Relative motion summed up during the current frame.
+#[derive(Debug, Default)]
+pub struct RelativeMotionBatch {
+    pending: Option<Pending>,
+}
+
+#[derive(Debug)]
+struct Pending {
+    /// Surface that the motion goes to, along with its location.
+    focus: (WlSurface, Point<f64, Logical>),
+    /// Sum of the motion, with the timestamp of the last event.
+    event: RelativeMotionEvent,
+    /// Timer that sends the batch at the end of the frame.
+    timer: RegistrationToken,
+}
+
+impl RelativeMotionBatch {
+    /// Adds motion to the batch, returns `false` if there's no batch for this surface.
+    fn add(&mut self, focus: &WlSurface, event: &RelativeMotionEvent) -> bool {
+        let Some(pending) = &mut self.pending else {
+            return false;
+        };
+        if pending.focus.0 != *focus {
+            return false;
+        }
+
+        pending.event.delta += event.delta;
+        pending.event.delta_unaccel += event.delta_unaccel;
+        pending.event.utime = event.utime;
+        true
+    }
+
+    #[cfg(test)]
+    pub fn pending_delta(&self) -> Option<Point<f64, Logical>> {
+        self.pending.as_ref().map(|pending| pending.event.delta)
+    }
+}
+
+impl State {
+    /// Batches relative motion of a locked pointer to send it at the end of the frame.
+    pub fn batch_relative_motion(
+        &mut self,
+        focus: (WlSurface, Point<f64, Logical>),
+        event: RelativeMotionEvent,
+    ) {
+        if self.niri.relative_motion_batch.add(&focus.0, &event) {
+            return;
+        }
+
+        // The motion goes to another surface, so finish the batch for the previous one first.
+        self.flush_relative_motion();
+
+        let pos = self.niri.seat.get_pointer().unwrap().current_location();
+        let interval = self
+            .niri
+            .output_under(pos)
+            .and_then(|(output, _)| self.niri.output_state.get(output))
+            .and_then(|state| state.frame_clock.refresh_interval())
+            .unwrap_or(FALLBACK_INTERVAL);
+
+        let timer = self
+            .niri
+            .event_loop
+            .insert_source(Timer::from_duration(interval), |_, _, state| {
+                // The timer is dropped right after, so it mustn't be removed again.
+                if let Some(pending) = state.niri.relative_motion_batch.pending.take() {
+                    state.send_relative_motion(pending.focus, pending.event);
+                }
+                TimeoutAction::Drop
+            })
+            .unwrap();
+
+        self.niri.relative_motion_batch.pending = Some(Pending {
+            focus,
+            event,
+            timer,
+        });
+    }
+
+    /// Sends the batched relative motion right away, if there's any.
+    pub fn flush_relative_motion(&mut self) {
+        let Some(pending) = self.niri.relative_motion_batch.pending.take() else {
+            return;
+        };
+
+        self.niri.event_loop.remove(pending.timer);
+        self.send_relative_motion(pending.focus, pending.event);
+    }
+
+    fn send_relative_motion(
+        &mut self,
+        focus: (WlSurface, Point<f64, Logical>),
+        event: RelativeMotionEvent,
+    ) {
+        // The surface could've gone away while the motion was batched.
+        if !focus.0.alive() {
+            return;
+        }
+
+        let pointer = self.niri.seat.get_pointer().unwrap();
+        pointer.relative_motion(self, Some(focus), &event);
+        pointer.frame(self);
+    }
+}
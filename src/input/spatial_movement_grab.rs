@@ -10,6 +10,7 @@ use smithay::input::SeatHandler;
 use smithay::output::Output;
 use smithay::utils::{Logical, Point};
 
+use crate::input::drag_recognizer::DragRecognizer;
 use crate::layout::workspace::WorkspaceId;
 use crate::niri::State;
 
@@ -18,6 +19,7 @@ pub struct SpatialMovementGrab {
     last_location: Point<f64, Logical>,
     output: Output,
     workspace_id: WorkspaceId,
+    recognizer: DragRecognizer,
     gesture: GestureState,
 }
 
@@ -33,6 +35,7 @@ impl SpatialMovementGrab {
         start_data: PointerGrabStartData<State>,
         output: Output,
         workspace_id: WorkspaceId,
+        recognizer: DragRecognizer,
         is_view_offset: bool,
     ) -> Self {
         let gesture = if is_view_offset {
@@ -46,6 +49,7 @@ impl SpatialMovementGrab {
             start_data,
             output,
             workspace_id,
+            recognizer,
             gesture,
         }
     }
@@ -89,8 +93,8 @@ impl PointerGrab<State> for SpatialMovementGrab {
             GestureState::Recognizing => {
                 let c = event.location - self.start_data.location;
 
-                // Check if the gesture moved far enough to decide. Threshold copied from GTK 4.
-                if c.x * c.x + c.y * c.y >= 8. * 8. {
+                // Check if the gesture moved far enough to decide.
+                if self.recognizer.is_drag(event.location, timestamp) {
                     if c.x.abs() > c.y.abs() {
                         self.gesture = GestureState::ViewOffset;
                         if let Some((ws_idx, ws)) = layout.find_workspace_by_id(self.workspace_id) {
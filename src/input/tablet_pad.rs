@@ -0,0 +1,378 @@
+//! Mappings for the buttons, rings and strips of drawing tablet pads.
+//!
+//! Pad input isn't forwarded to clients. Instead, every button and every step of a ring or a
+//! strip can either emulate a key press or run niri actions, as set up in the `tablet-pad`
+//! sections of the input config. Profiles with an `app-id` are switched based on the focused
+//! window.
+//!
+//! Dials aren't supported yet since they need a newer libinput than the one we build against.
+
+use std::collections::HashMap;
+
+use input::event::tablet_pad::{ButtonState as PadButtonState, TabletPadEvent};
+use niri_config::{Action, Key, Modifiers, PadAxisBind, PadDirection, TabletPad, Trigger};
+use smithay::backend::input::{InputEvent, KeyState};
+use smithay::input::keyboard::Keysym;
+
+use super::injected::{InjectedInputBackend, InjectedKeyEvent};
+use crate::niri::State;
+use crate::utils::with_toplevel_role;
+
+/// Degrees a ring must turn for one step.
+const RING_STEP: f64 = 15.;
+/// Fraction of its length a finger must slide along a strip for one step.
+const STRIP_STEP: f64 = 0.1;
+
+// Modifier keys from linux/input-event-codes.h, used for key emulation.
+const KEY_LEFTCTRL: u32 = 29;
+const KEY_LEFTSHIFT: u32 = 42;
+const KEY_LEFTALT: u32 = 56;
+const KEY_LEFTMETA: u32 = 125;
+const KEY_RIGHTALT: u32 = 100;
+
+// Offset between evdev and xkb key codes.
+const XKB_KEYCODE_OFFSET: u32 = 8;
+
+/// Accumulates ring and strip movement into discrete steps.
+#[derive(Debug, Default)]
+pub struct PadAxisTracker {
+    axes: HashMap<(PadAxis, u32), AxisState>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PadAxis {
+    Ring,
+    Strip,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AxisState {
+    last: f64,
+    accumulated: f64,
+}
+
+/// A mapping resolved for the focused window.
+#[derive(Debug, Clone, PartialEq)]
+enum PadBind {
+    Key(Key),
+    Actions(Vec<Action>),
+}
+
+impl PadAxisTracker {
+    /// Feeds a new ring position in degrees, returns the steps taken since the last one.
+    ///
+    /// Positive steps are clockwise. A negative position means the finger was lifted.
+    fn ring(&mut self, number: u32, position: f64) -> i32 {
+        self.update((PadAxis::Ring, number), position, RING_STEP, |last, new| {
+            // The ring wraps around, take the shortest way.
+            (new - last + 540.).rem_euclid(360.) - 180.
+        })
+    }
+
+    /// Feeds a new strip position in 0..1, returns the steps taken since the last one.
+    ///
+    /// Positive steps are up, towards 0. A negative position means the finger was lifted.
+    fn strip(&mut self, number: u32, position: f64) -> i32 {
+        self.update(
+            (PadAxis::Strip, number),
+            position,
+            STRIP_STEP,
+            |last, new| last - new,
+        )
+    }
+
+    fn update(
+        &mut self,
+        key: (PadAxis, u32),
+        position: f64,
+        step: f64,
+        delta: impl Fn(f64, f64) -> f64,
+    ) -> i32 {
+        if position < 0. {
+            self.axes.remove(&key);
+            return 0;
+        }
+
+        let Some(state) = self.axes.get_mut(&key) else {
+            let state = AxisState {
+                last: position,
+                accumulated: 0.,
+            };
+            self.axes.insert(key, state);
+            return 0;
+        };
+
+        state.accumulated += delta(state.last, position);
+        state.last = position;
+
+        let steps = (state.accumulated / step).trunc();
+        state.accumulated -= steps * step;
+        steps as i32
+    }
+}
+
+impl State {
+    /// Handles a libinput tablet pad event.
+    pub fn on_tablet_pad_event(&mut self, event: &TabletPadEvent) {
+        match event {
+            TabletPadEvent::Button(ev) => {
+                // Emulated keys are pressed and released right away, so releases do nothing.
+                if ev.button_state() != PadButtonState::Pressed {
+                    return;
+                }
+
+                let button = ev.button_number();
+                let bind = self.pad_bind(|pad| {
+                    let bind = pad.buttons.iter().find(|bind| bind.button == button)?;
+                    PadBind::new(bind.key, &bind.actions)
+                });
+                if let Some(bind) = bind {
+                    self.run_pad_bind(bind, 1);
+                }
+            }
+            TabletPadEvent::Ring(ev) => {
+                let steps = self.niri.tablet_pad_axes.ring(ev.number(), ev.position());
+                let direction = if steps > 0 {
+                    PadDirection::Clockwise
+                } else {
+                    PadDirection::CounterClockwise
+                };
+                self.on_pad_axis_steps(steps, |pad| &pad.rings, ev.number(), direction);
+            }
+            TabletPadEvent::Strip(ev) => {
+                let steps = self.niri.tablet_pad_axes.strip(ev.number(), ev.position());
+                let direction = if steps > 0 {
+                    PadDirection::Up
+                } else {
+                    PadDirection::Down
+                };
+                self.on_pad_axis_steps(steps, |pad| &pad.strips, ev.number(), direction);
+            }
+            _ => (),
+        }
+    }
+
+    fn on_pad_axis_steps(
+        &mut self,
+        steps: i32,
+        binds: impl Fn(&TabletPad) -> &[PadAxisBind],
+        number: u32,
+        direction: PadDirection,
+    ) {
+        if steps == 0 {
+            return;
+        }
+
+        let bind = self.pad_bind(|pad| {
+            let bind = binds(pad)
+                .iter()
+                .find(|bind| bind.number == number && bind.direction == direction)?;
+            PadBind::new(bind.key, &bind.actions)
+        });
+        if let Some(bind) = bind {
+            self.run_pad_bind(bind, steps.unsigned_abs());
+        }
+    }
+
+    /// Finds the mapping for the focused window.
+    fn pad_bind(&self, find: impl Fn(&TabletPad) -> Option<PadBind>) -> Option<PadBind> {
+        let app_id =
+            self.niri.layout.focus().and_then(|mapped| {
+                with_toplevel_role(mapped.toplevel(), |role| role.app_id.clone())
+            });
+
+        let config = self.niri.config.borrow();
+        find_pad_bind(&config.input.tablet_pads, app_id.as_deref(), find)
+    }
+
+    fn run_pad_bind(&mut self, bind: PadBind, times: u32) {
+        for _ in 0..times {
+            match &bind {
+                PadBind::Key(key) => self.emulate_key(*key),
+                PadBind::Actions(actions) => {
+                    for action in actions {
+                        self.do_action(action.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Presses and releases a key with its modifiers, as if it was typed on the keyboard.
+    fn emulate_key(&mut self, key: Key) {
+        let Trigger::Keysym(keysym) = key.trigger else {
+            warn!("tablet pad mappings can only emulate keyboard keys");
+            return;
+        };
+
+        let Some((keycode, needs_shift)) = self.find_keycode(keysym) else {
+            warn!("no key produces {keysym:?} in the current keyboard layout");
+            return;
+        };
+
+        let mut modifiers = key.modifiers;
+        if modifiers.contains(Modifiers::COMPOSITOR) {
+            let mod_key = self.backend.mod_key(&self.niri.config.borrow());
+            modifiers.remove(Modifiers::COMPOSITOR);
+            modifiers |= mod_key.to_modifiers();
+        }
+        if needs_shift {
+            modifiers |= Modifiers::SHIFT;
+        }
+
+        let mut keycodes: Vec<u32> = [
+            (Modifiers::CTRL, KEY_LEFTCTRL),
+            (Modifiers::SHIFT, KEY_LEFTSHIFT),
+            (Modifiers::ALT, KEY_LEFTALT),
+            (Modifiers::SUPER, KEY_LEFTMETA),
+            (Modifiers::ISO_LEVEL3_SHIFT, KEY_RIGHTALT),
+        ]
+        .into_iter()
+        .filter(|(modifier, _)| modifiers.contains(*modifier))
+        .map(|(_, keycode)| keycode)
+        .collect();
+        keycodes.push(keycode);
+
+        // Modifier and keymap key codes are small, so they always convert to xkb ones.
+        for &keycode in &keycodes {
+            let Some(event) = InjectedKeyEvent::new(keycode, KeyState::Pressed) else {
+                continue;
+            };
+            self.process_input_event::<InjectedInputBackend>(InputEvent::Keyboard { event });
+        }
+        for &keycode in keycodes.iter().rev() {
+            let Some(event) = InjectedKeyEvent::new(keycode, KeyState::Released) else {
+                continue;
+            };
+            self.process_input_event::<InjectedInputBackend>(InputEvent::Keyboard { event });
+        }
+    }
+
+    /// Finds the evdev key code that produces `keysym` in the active layout.
+    ///
+    /// Also returns whether Shift must be held for it.
+    fn find_keycode(&mut self, keysym: Keysym) -> Option<(u32, bool)> {
+        let keyboard = self.niri.seat.get_keyboard().unwrap();
+        keyboard.with_xkb_state(self, |context| {
+            let xkb = context.xkb().lock().unwrap();
+            let layout = xkb.active_layout().0;
+            // SAFETY: the keymap isn't modified while we look at it.
+            let keymap = unsafe { xkb.keymap() };
+
+            let mut found = None;
+            for level in [0, 1] {
+                keymap.key_for_each(|keymap, keycode| {
+                    if found.is_some() {
+                        return;
+                    }
+                    let syms = keymap.key_get_syms_by_level(keycode, layout, level);
+                    if syms.contains(&keysym) {
+                        found = Some((keycode.raw() - XKB_KEYCODE_OFFSET, level == 1));
+                    }
+                });
+                if found.is_some() {
+                    break;
+                }
+            }
+            found
+        })
+    }
+}
+
+impl PadBind {
+    fn new(key: Option<Key>, actions: &[Action]) -> Option<Self> {
+        match key {
+            Some(key) => Some(Self::Key(key)),
+            None if !actions.is_empty() => Some(Self::Actions(actions.to_vec())),
+            None => None,
+        }
+    }
+}
+
+/// Looks for a mapping in the profiles for `app_id` first, then in the generic ones.
+fn find_pad_bind(
+    pads: &[TabletPad],
+    app_id: Option<&str>,
+    find: impl Fn(&TabletPad) -> Option<PadBind>,
+) -> Option<PadBind> {
+    let app_specific = pads.iter().filter(|pad| {
+        pad.app_id
+            .as_ref()
+            .is_some_and(|re| app_id.is_some_and(|app_id| re.0.is_match(app_id)))
+    });
+    let generic = pads.iter().filter(|pad| pad.app_id.is_none());
+
+    app_specific.chain(generic).find_map(find)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use niri_config::{PadButtonBind, RegexEq};
+
+    use super::*;
+
+    #[test]
+    fn ring_steps_wrap_around() {
+        let mut tracker = PadAxisTracker::default();
+
+        assert_eq!(tracker.ring(0, 350.), 0);
+        assert_eq!(tracker.ring(0, 5.), 1);
+        assert_eq!(tracker.ring(0, 10.), 0);
+        assert_eq!(tracker.ring(0, 340.), -1);
+
+        // Lifting the finger drops the leftover movement.
+        assert_eq!(tracker.ring(0, -1.), 0);
+        assert_eq!(tracker.ring(0, 100.), 0);
+        assert_eq!(tracker.ring(0, 110.), 0);
+    }
+
+    #[test]
+    fn strip_steps_go_up_towards_zero() {
+        let mut tracker = PadAxisTracker::default();
+
+        assert_eq!(tracker.strip(0, 0.5), 0);
+        assert_eq!(tracker.strip(0, 0.25), 2);
+        assert_eq!(tracker.strip(0, 0.65), -3);
+        // Other strips are tracked separately.
+        assert_eq!(tracker.strip(1, 0.1), 0);
+    }
+
+    #[test]
+    fn app_profiles_take_precedence() {
+        let button = |button, action| PadButtonBind {
+            button,
+            key: None,
+            actions: vec![action],
+        };
+        let pads = [
+            TabletPad {
+                buttons: vec![
+                    button(0, Action::ToggleOverview),
+                    button(1, Action::FocusColumnLeft),
+                ],
+                ..Default::default()
+            },
+            TabletPad {
+                app_id: Some(RegexEq::from_str("^krita$").unwrap()),
+                buttons: vec![button(0, Action::FocusColumnRight)],
+                ..Default::default()
+            },
+        ];
+
+        let find = |app_id, number| {
+            find_pad_bind(&pads, app_id, |pad| {
+                let bind = pad.buttons.iter().find(|bind| bind.button == number)?;
+                PadBind::new(bind.key, &bind.actions)
+            })
+        };
+
+        let actions = |action| Some(PadBind::Actions(vec![action]));
+        assert_eq!(find(None, 0), actions(Action::ToggleOverview));
+        assert_eq!(find(Some("krita"), 0), actions(Action::FocusColumnRight));
+        // Buttons missing from the app profile fall back to the generic one.
+        assert_eq!(find(Some("krita"), 1), actions(Action::FocusColumnLeft));
+        assert_eq!(find(Some("gimp"), 2), None);
+    }
+}
@@ -0,0 +1,445 @@
+//! Compositor gestures on touchscreens.
+//!
+//! Recognizes multi-finger swipes anywhere on the screen, and single-finger swipes that start at
+//! a screen edge. The recognizer only tracks the touch points: the caller feeds it touch events
+//! and drives the matching layout gesture with the updates it returns.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use smithay::backend::input::{AbsolutePositionEvent, Event, InputBackend, TouchEvent, TouchSlot};
+use smithay::output::Output;
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::niri::State;
+
+/// Distance that the touch points have to move before the gesture is recognized.
+const RECOGNITION_THRESHOLD: f64 = 16.;
+
+/// Number of fingers for the workspace switch and view scrolling swipes.
+const SWIPE_FINGERS: usize = 3;
+
+/// Number of fingers for the overview swipe.
+const OVERVIEW_FINGERS: usize = 4;
+
+/// Screen edge that a swipe started at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenEdge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// Layout gesture driven by a touchscreen gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureKind {
+    WorkspaceSwitch,
+    ViewOffset,
+    Overview,
+}
+
+/// Update of an ongoing gesture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureUpdate {
+    pub kind: GestureKind,
+    /// Whether the gesture was just recognized and the layout gesture should begin.
+    pub begin: bool,
+    /// Movement in the convention of the layout gesture.
+    ///
+    /// For the workspace switch and view offset gestures, this is the inverse of the finger
+    /// movement, so that the content follows the fingers. For the overview gesture, positive `y`
+    /// opens the overview.
+    pub delta: Point<f64, Logical>,
+}
+
+#[derive(Debug, Default)]
+pub struct TouchGestures {
+    /// Positions of the touch points that are down.
+    points: HashMap<TouchSlot, Point<f64, Logical>>,
+    state: GestureState,
+    /// Output where the touch sequence started.
+    pub output: Option<Output>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum GestureState {
+    /// Nothing to recognize with the current touch points.
+    #[default]
+    Idle,
+    /// Waiting for the touch points to move far enough.
+    Recognizing {
+        start: Point<f64, Logical>,
+        edge: Option<ScreenEdge>,
+    },
+    /// A gesture is ongoing.
+    Active {
+        kind: GestureKind,
+        last: Point<f64, Logical>,
+        /// Sign applied to the vertical movement of the overview gesture.
+        overview_sign: f64,
+    },
+    /// The touch sequence didn't turn into a gesture, or the gesture ended. Waits for all touch
+    /// points to go up.
+    Ignored,
+}
+
+impl TouchGestures {
+    /// Handles a touch point going down.
+    ///
+    /// `edge` is the screen edge that the point is at, if any.
+    pub fn down(&mut self, slot: TouchSlot, pos: Point<f64, Logical>, edge: Option<ScreenEdge>) {
+        self.points.insert(slot, pos);
+        let centroid = self.centroid();
+
+        match &mut self.state {
+            GestureState::Idle | GestureState::Recognizing { .. } => {
+                let count = self.points.len();
+                let edge = edge.filter(|_| count == 1);
+                self.state = if edge.is_some() || count >= SWIPE_FINGERS {
+                    GestureState::Recognizing {
+                        start: centroid,
+                        edge,
+                    }
+                } else {
+                    GestureState::Idle
+                };
+            }
+            // Don't let the centroid jump when fingers join an ongoing gesture.
+            GestureState::Active { last, .. } => *last = centroid,
+            GestureState::Ignored => (),
+        }
+    }
+
+    /// Handles a touch point moving, returning the gesture update if there is one.
+    pub fn motion(&mut self, slot: TouchSlot, pos: Point<f64, Logical>) -> Option<GestureUpdate> {
+        let point = self.points.get_mut(&slot)?;
+        *point = pos;
+        let centroid = self.centroid();
+
+        match self.state {
+            GestureState::Idle | GestureState::Ignored => None,
+            GestureState::Recognizing { start, edge } => {
+                let total = centroid - start;
+                if total.x.hypot(total.y) < RECOGNITION_THRESHOLD {
+                    return None;
+                }
+
+                let Some((kind, overview_sign)) = recognize(total, edge, self.points.len()) else {
+                    self.state = GestureState::Ignored;
+                    return None;
+                };
+
+                self.state = GestureState::Active {
+                    kind,
+                    last: centroid,
+                    overview_sign,
+                };
+                Some(GestureUpdate {
+                    kind,
+                    begin: true,
+                    delta: layout_delta(kind, total, overview_sign),
+                })
+            }
+            GestureState::Active {
+                kind,
+                last,
+                overview_sign,
+            } => {
+                self.state = GestureState::Active {
+                    kind,
+                    last: centroid,
+                    overview_sign,
+                };
+                Some(GestureUpdate {
+                    kind,
+                    begin: false,
+                    delta: layout_delta(kind, centroid - last, overview_sign),
+                })
+            }
+        }
+    }
+
+    /// Handles a touch point going up, returning the gesture that ended, if any.
+    pub fn up(&mut self, slot: TouchSlot) -> Option<GestureKind> {
+        self.points.remove(&slot)?;
+
+        let ended = match self.state {
+            GestureState::Active { kind, .. } => Some(kind),
+            _ => None,
+        };
+
+        self.state = if self.points.is_empty() {
+            self.output = None;
+            GestureState::Idle
+        } else if ended.is_some() {
+            // Lifting a finger ends the gesture, the remaining fingers shouldn't start another.
+            GestureState::Ignored
+        } else {
+            match self.state {
+                GestureState::Recognizing { .. } if self.points.len() < SWIPE_FINGERS => {
+                    GestureState::Idle
+                }
+                state => state,
+            }
+        };
+
+        ended
+    }
+
+    /// Forgets all touch points, returning the gesture that was ongoing, if any.
+    pub fn cancel(&mut self) -> Option<GestureKind> {
+        self.points.clear();
+        self.output = None;
+
+        match std::mem::take(&mut self.state) {
+            GestureState::Active { kind, .. } => Some(kind),
+            _ => None,
+        }
+    }
+
+    fn centroid(&self) -> Point<f64, Logical> {
+        let sum = self
+            .points
+            .values()
+            .fold(Point::default(), |acc, pos| acc + *pos);
+        sum.downscale(self.points.len().max(1) as f64)
+    }
+}
+
+impl State {
+    pub(super) fn on_touch_down<I: InputBackend>(&mut self, evt: I::TouchDownEvent) {
+        let (off, edge_width) = {
+            let config = self.niri.config.borrow();
+            let touchscreen = &config.gestures.touchscreen;
+            (touchscreen.off, touchscreen.edge_swipe_width.0)
+        };
+        if off {
+            return;
+        }
+
+        let Some(pos) = self.touch_location::<I>(&evt) else {
+            return;
+        };
+
+        let mut edge = None;
+        if let Some((output, _)) = self.niri.output_under(pos) {
+            let output = output.clone();
+            let geo = self.niri.global_space.output_geometry(&output).unwrap();
+            edge = screen_edge(geo, pos, edge_width);
+
+            let gestures = &mut self.niri.touch_gestures;
+            if gestures.output.is_none() {
+                gestures.output = Some(output);
+            }
+        }
+
+        self.niri.touch_gestures.down(evt.slot(), pos, edge);
+    }
+
+    pub(super) fn on_touch_motion<I: InputBackend>(&mut self, evt: I::TouchMotionEvent) {
+        let Some(pos) = self.touch_location::<I>(&evt) else {
+            return;
+        };
+        let Some(update) = self.niri.touch_gestures.motion(evt.slot(), pos) else {
+            return;
+        };
+        let Some(output) = self.niri.touch_gestures.output.clone() else {
+            return;
+        };
+
+        let timestamp = Duration::from_millis(u64::from(evt.time_msec()));
+        let layout = &mut self.niri.layout;
+        let ongoing = match update.kind {
+            GestureKind::WorkspaceSwitch => {
+                if update.begin {
+                    layout.workspace_switch_gesture_begin(&output, false);
+                }
+                layout
+                    .workspace_switch_gesture_update(update.delta.y, timestamp, false)
+                    .is_some()
+            }
+            GestureKind::ViewOffset => {
+                if update.begin {
+                    layout.view_offset_gesture_begin(&output, None, false);
+                }
+                layout
+                    .view_offset_gesture_update(update.delta.x, timestamp, false)
+                    .is_some()
+            }
+            GestureKind::Overview => {
+                if update.begin {
+                    layout.overview_gesture_begin();
+                }
+                layout
+                    .overview_gesture_update(update.delta.y, timestamp)
+                    .is_some()
+            }
+        };
+
+        if ongoing {
+            self.niri.queue_redraw_all();
+        } else {
+            // Something else took over the layout gesture.
+            self.niri.touch_gestures.cancel();
+        }
+    }
+
+    pub(super) fn on_touch_up<I: InputBackend>(&mut self, evt: I::TouchUpEvent) {
+        if let Some(kind) = self.niri.touch_gestures.up(evt.slot()) {
+            self.end_touch_gesture(kind);
+        }
+    }
+
+    pub(super) fn on_touch_cancel<I: InputBackend>(&mut self, _evt: I::TouchCancelEvent) {
+        if let Some(kind) = self.niri.touch_gestures.cancel() {
+            self.end_touch_gesture(kind);
+        }
+    }
+
+    fn end_touch_gesture(&mut self, kind: GestureKind) {
+        let layout = &mut self.niri.layout;
+        match kind {
+            GestureKind::WorkspaceSwitch => {
+                layout.workspace_switch_gesture_end(Some(false));
+            }
+            GestureKind::ViewOffset => {
+                layout.view_offset_gesture_end(Some(false));
+            }
+            GestureKind::Overview => {
+                layout.overview_gesture_end();
+            }
+        }
+        self.niri.queue_redraw_all();
+    }
+
+    fn touch_location<I: InputBackend>(
+        &self,
+        evt: &(impl TouchEvent<I> + AbsolutePositionEvent<I>),
+    ) -> Option<Point<f64, Logical>> {
+        if let Some(pos) = self.compute_absolute_location(evt, None) {
+            return Some(pos);
+        }
+
+        // Touchscreens not mapped to an output span all outputs.
+        let geo = self.global_bounding_rectangle()?;
+        Some(evt.position_transformed(geo.size) + geo.loc.to_f64())
+    }
+}
+
+/// Returns the edge of the output geometry that the position is within `width` of.
+fn screen_edge(
+    geo: Rectangle<i32, Logical>,
+    pos: Point<f64, Logical>,
+    width: f64,
+) -> Option<ScreenEdge> {
+    let geo = geo.to_f64();
+    let pos = pos - geo.loc;
+
+    if pos.y < width {
+        Some(ScreenEdge::Top)
+    } else if pos.y >= geo.size.h - width {
+        Some(ScreenEdge::Bottom)
+    } else if pos.x < width {
+        Some(ScreenEdge::Left)
+    } else if pos.x >= geo.size.w - width {
+        Some(ScreenEdge::Right)
+    } else {
+        None
+    }
+}
+
+/// Picks the gesture for the movement so far, along with the overview sign.
+fn recognize(
+    total: Point<f64, Logical>,
+    edge: Option<ScreenEdge>,
+    fingers: usize,
+) -> Option<(GestureKind, f64)> {
+    let vertical = total.y.abs() > total.x.abs();
+
+    if let Some(edge) = edge {
+        // Edge swipes have to move away from the edge.
+        return match edge {
+            ScreenEdge::Top if vertical && total.y > 0. => Some((GestureKind::Overview, 1.)),
+            ScreenEdge::Bottom if vertical && total.y < 0. => Some((GestureKind::Overview, -1.)),
+            ScreenEdge::Left if !vertical && total.x > 0. => Some((GestureKind::ViewOffset, 1.)),
+            ScreenEdge::Right if !vertical && total.x < 0. => Some((GestureKind::ViewOffset, 1.)),
+            _ => None,
+        };
+    }
+
+    match fingers {
+        SWIPE_FINGERS if vertical => Some((GestureKind::WorkspaceSwitch, 1.)),
+        SWIPE_FINGERS => Some((GestureKind::ViewOffset, 1.)),
+        // Swiping up opens the overview, like on touchpads.
+        OVERVIEW_FINGERS if vertical => Some((GestureKind::Overview, -1.)),
+        _ => None,
+    }
+}
+
+fn layout_delta(
+    kind: GestureKind,
+    delta: Point<f64, Logical>,
+    overview_sign: f64,
+) -> Point<f64, Logical> {
+    match kind {
+        GestureKind::WorkspaceSwitch | GestureKind::ViewOffset => Point::from((-delta.x, -delta.y)),
+        GestureKind::Overview => Point::from((0., delta.y * overview_sign)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(id: u32) -> TouchSlot {
+        TouchSlot::from(Some(id))
+    }
+
+    #[test]
+    fn three_finger_vertical_swipe_switches_workspace() {
+        let mut gestures = TouchGestures::default();
+        for id in 0..3 {
+            gestures.down(slot(id), Point::from((100. * f64::from(id), 500.)), None);
+        }
+
+        // Small movements aren't recognized yet.
+        assert_eq!(gestures.motion(slot(0), Point::from((0., 490.))), None);
+
+        let update = gestures.motion(slot(1), Point::from((100., 400.))).unwrap();
+        assert_eq!(update.kind, GestureKind::WorkspaceSwitch);
+        assert!(update.begin);
+        assert!(update.delta.y > 0.);
+
+        let update = gestures.motion(slot(2), Point::from((200., 470.))).unwrap();
+        assert!(!update.begin);
+        assert_eq!(update.delta, Point::from((0., 10.)));
+
+        assert_eq!(gestures.up(slot(0)), Some(GestureKind::WorkspaceSwitch));
+        assert_eq!(gestures.motion(slot(1), Point::from((100., 0.))), None);
+        assert_eq!(gestures.up(slot(1)), None);
+        assert_eq!(gestures.up(slot(2)), None);
+    }
+
+    #[test]
+    fn edge_swipe_must_move_away_from_edge() {
+        let mut gestures = TouchGestures::default();
+        gestures.down(slot(0), Point::from((500., 1.)), Some(ScreenEdge::Top));
+        assert_eq!(gestures.motion(slot(0), Point::from((540., 1.))), None);
+        gestures.up(slot(0));
+
+        gestures.down(slot(0), Point::from((500., 1.)), Some(ScreenEdge::Top));
+        let update = gestures.motion(slot(0), Point::from((500., 41.))).unwrap();
+        assert_eq!(update.kind, GestureKind::Overview);
+        assert_eq!(update.delta, Point::from((0., 40.)));
+        assert_eq!(gestures.cancel(), Some(GestureKind::Overview));
+    }
+
+    #[test]
+    fn single_touch_away_from_edge_is_ignored() {
+        let mut gestures = TouchGestures::default();
+        gestures.down(slot(0), Point::from((500., 500.)), None);
+        assert_eq!(gestures.motion(slot(0), Point::from((500., 100.))), None);
+        assert_eq!(gestures.up(slot(0)), None);
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use niri_config::VirtualInputPolicy;
+use smithay::reexports::wayland_server::Client;
+
+use crate::niri::ClientState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtualInputAccess {
+    Allowed,
+    Denied,
+    /// The user hasn't answered the prompt for this client yet.
+    Undecided,
+}
+
+/// Decides which clients may inject input through virtual pointers and keyboards.
+///
+/// Shared between the compositor state and the Wayland global filters, which is why it's behind
+/// an `Arc<Mutex>`.
+#[derive(Debug, Clone)]
+pub struct VirtualInputPermissions {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    policy: VirtualInputPolicy,
+    /// User answers to the prompt, per executable.
+    decisions: HashMap<PathBuf, bool>,
+}
+
+impl VirtualInputPermissions {
+    pub fn new(policy: VirtualInputPolicy) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                policy,
+                decisions: HashMap::new(),
+            })),
+        }
+    }
+
+    pub fn set_policy(&self, policy: VirtualInputPolicy) {
+        self.inner.lock().unwrap().policy = policy;
+    }
+
+    pub fn access(&self, client: &Client) -> VirtualInputAccess {
+        let Some(data) = client.get_data::<ClientState>() else {
+            return VirtualInputAccess::Denied;
+        };
+
+        if data.restricted {
+            return VirtualInputAccess::Denied;
+        }
+
+        let inner = self.inner.lock().unwrap();
+        match inner.policy {
+            VirtualInputPolicy::Allow => VirtualInputAccess::Allowed,
+            VirtualInputPolicy::Deny => VirtualInputAccess::Denied,
+            VirtualInputPolicy::Ask => {
+                // We can't remember the answer for clients that we cannot identify, so don't
+                // bother asking.
+                let Some(executable) = data.process.get().and_then(|p| p.executable.as_ref())
+                else {
+                    return VirtualInputAccess::Denied;
+                };
+
+                match inner.decisions.get(executable) {
+                    Some(true) => VirtualInputAccess::Allowed,
+                    Some(false) => VirtualInputAccess::Denied,
+                    None => VirtualInputAccess::Undecided,
+                }
+            }
+        }
+    }
+
+    pub fn decide(&self, executable: PathBuf, allowed: bool) {
+        self.inner
+            .lock()
+            .unwrap()
+            .decisions
+            .insert(executable, allowed);
+    }
+}
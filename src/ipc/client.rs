@@ -1,37 +1,101 @@
+use std::collections::HashMap;
 use std::io::ErrorKind;
 use std::iter::Peekable;
+use std::path::Path;
 use std::slice;
 
 use anyhow::{anyhow, bail, Context};
-use niri_config::OutputName;
+use clap::Parser as _;
+use niri_config::{Config, OutputName};
 use niri_ipc::socket::Socket;
 use niri_ipc::{
-    Event, KeyboardLayouts, LogicalOutput, Mode, Output, OutputConfigChanged, Overview, Request,
-    Response, Transform, Window,
+    Action, BindConflict, BindConflictKind, Colorimetry, CursorPlane, CursorPlaneFallback, Event,
+    HdrMetadata, KeyboardLayouts, LogicalOutput, Mode, Output, OutputConfigChanged, OutputEdid,
+    Overview, Request, Response, ServiceState, Transform, VirtualDevice, VirtualDeviceKind, Window,
+    WindowRuleMatch, Workspace,
 };
 use serde_json::json;
 
-use crate::cli::Msg;
+use crate::cli::{ConfigMsg, DebugMsg, Msg, MsgFormat, SnapshotMsg};
+use crate::ipc::config_diff;
 use crate::utils::version;
 
-pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
+pub fn handle_msg(msg: Msg, format: Option<MsgFormat>) -> anyhow::Result<()> {
+    let json = format == Some(MsgFormat::Json);
+    let tabular = format.filter(|format| *format != MsgFormat::Json);
+    if tabular.is_some() && !matches!(msg, Msg::Windows { .. } | Msg::Workspaces | Msg::Outputs) {
+        bail!("--format table and csv are only supported for windows, workspaces and outputs");
+    }
+    if tabular.is_some() && matches!(msg, Msg::Windows { watch: true }) {
+        bail!("--format table and csv are not supported with --watch");
+    }
+
     let request = match &msg {
         Msg::Version => Request::Version,
         Msg::Outputs => Request::Outputs,
         Msg::FocusedWindow => Request::FocusedWindow,
+        Msg::FocusHistory => Request::FocusHistory,
+        Msg::WindowThumbnail { id, path } => {
+            // niri writes the file, so resolve relative paths against our working directory.
+            let path = std::path::absolute(path).context("error resolving thumbnail path")?;
+            let path = path
+                .into_os_string()
+                .into_string()
+                .map_err(|path| anyhow!("thumbnail path is not valid UTF-8: {path:?}"))?;
+            Request::WindowThumbnail { id: *id, path }
+        }
         Msg::FocusedOutput => Request::FocusedOutput,
-        Msg::Action { action } => Request::Action(action.clone()),
+        Msg::Action { action } => {
+            let mut action = action.clone();
+            resolve_action_paths(&mut action)?;
+            Request::Action(action)
+        }
+        Msg::Batch { actions } => Request::Actions(batch_actions(actions)?),
         Msg::Output { output, action } => Request::Output {
             output: output.clone(),
             action: action.clone(),
         },
         Msg::Workspaces => Request::Workspaces,
-        Msg::Windows => Request::Windows,
+        Msg::Windows { watch: false } => Request::Windows,
+        // Window changes come from the event stream.
+        Msg::Windows { watch: true } => Request::EventStream,
         Msg::Layers => Request::Layers,
-        Msg::KeyboardLayouts => Request::KeyboardLayouts,
+        Msg::KeyboardLayouts { .. } => Request::KeyboardLayouts,
         Msg::EventStream => Request::EventStream,
         Msg::RequestError => Request::ReturnError,
         Msg::OverviewState => Request::OverviewState,
+        Msg::BindConflicts => Request::BindConflicts,
+        Msg::VirtualDevices => Request::VirtualDevices,
+        Msg::Status { .. } => Request::Status,
+        Msg::Services => Request::Services,
+        Msg::Snapshot {
+            snapshot: SnapshotMsg::Save { path },
+        } => Request::SaveLayoutSnapshot {
+            path: path.as_deref().map(resolve_snapshot_path).transpose()?,
+        },
+        Msg::Snapshot {
+            snapshot: SnapshotMsg::Restore { path },
+        } => Request::RestoreLayoutSnapshot {
+            path: path.as_deref().map(resolve_snapshot_path).transpose()?,
+        },
+        Msg::Debug {
+            debug: DebugMsg::MatchRules { id },
+        } => Request::MatchWindowRules { id: *id },
+        Msg::Debug {
+            debug: DebugMsg::CursorPlanes,
+        } => Request::CursorPlanes,
+        Msg::Debug {
+            debug: DebugMsg::Inject { input },
+        } => Request::InjectInput(*input),
+        Msg::Config {
+            config: ConfigMsg::Set { path, value },
+        } => Request::SetConfigValue {
+            path: path.clone(),
+            value: value.clone(),
+        },
+        Msg::Config {
+            config: ConfigMsg::Get { path },
+        } => Request::GetConfigValue { path: path.clone() },
     };
 
     let mut socket = Socket::connect().context("error connecting to the niri socket")?;
@@ -142,6 +206,12 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                 .collect::<Vec<_>>();
             outputs.sort_unstable_by(|a, b| a.0.compare(&b.0));
 
+            if let Some(format) = tabular {
+                let outputs = outputs.into_iter().map(|(_name, output)| output);
+                print!("{}", outputs_table(outputs).format(format));
+                return Ok(());
+            }
+
             for (_name, output) in outputs.into_iter() {
                 print_output(output)?;
                 println!();
@@ -164,7 +234,42 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                 println!("No window is focused.");
             }
         }
-        Msg::Windows => {
+        Msg::FocusHistory => {
+            let Response::FocusHistory(windows) = response else {
+                bail!("unexpected response: expected FocusHistory, got {response:?}");
+            };
+
+            if json {
+                let windows =
+                    serde_json::to_string(&windows).context("error formatting response")?;
+                println!("{windows}");
+                return Ok(());
+            }
+
+            if windows.is_empty() {
+                println!("No windows were focused yet.");
+            }
+
+            for window in windows {
+                print_window(&window);
+                println!();
+            }
+        }
+        Msg::Windows { watch: true } => {
+            let Response::Handled = response else {
+                bail!("unexpected response: expected Handled, got {response:?}");
+            };
+
+            let mut windows = HashMap::new();
+            let mut read_event = socket.read_events();
+            loop {
+                let event = read_event().context("error reading event from niri")?;
+                if let Some(line) = window_watch_line(&mut windows, event) {
+                    println!("{line}");
+                }
+            }
+        }
+        Msg::Windows { watch: false } => {
             let Response::Windows(mut windows) = response else {
                 bail!("unexpected response: expected Windows, got {response:?}");
             };
@@ -178,6 +283,11 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
 
             windows.sort_unstable_by(|a, b| a.id.cmp(&b.id));
 
+            if let Some(format) = tabular {
+                print!("{}", windows_table(&windows).format(format));
+                return Ok(());
+            }
+
             for window in windows {
                 print_window(&window);
                 println!();
@@ -264,7 +374,7 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
             }
         }
 
-        Msg::Action { .. } => {
+        Msg::Action { .. } | Msg::Batch { .. } => {
             let Response::Handled = response else {
                 bail!("unexpected response: expected Handled, got {response:?}");
             };
@@ -298,14 +408,19 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                 return Ok(());
             }
 
+            response.sort_by_key(|ws| ws.idx);
+            response.sort_by(|a, b| a.output.cmp(&b.output));
+
+            if let Some(format) = tabular {
+                print!("{}", workspaces_table(&response).format(format));
+                return Ok(());
+            }
+
             if response.is_empty() {
                 println!("No workspaces.");
                 return Ok(());
             }
 
-            response.sort_by_key(|ws| ws.idx);
-            response.sort_by(|a, b| a.output.cmp(&b.output));
-
             let mut current_output = if let Some(output) = response[0].output.as_deref() {
                 println!("Output \"{output}\":");
                 Some(output)
@@ -324,21 +439,19 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                     println!("\nOutput \"{output}\":");
                 }
 
-                let is_active = if ws.is_active { " * " } else { "   " };
-                let idx = ws.idx;
-                let name = if let Some(name) = ws.name.as_deref() {
-                    format!(" \"{name}\"")
-                } else {
-                    String::new()
-                };
-                println!("{is_active}{idx}{name}");
+                println!("{}", workspace_line(ws));
             }
         }
-        Msg::KeyboardLayouts => {
+        Msg::KeyboardLayouts { current } => {
             let Response::KeyboardLayouts(response) = response else {
                 bail!("unexpected response: expected KeyboardLayouts, got {response:?}");
             };
 
+            if current {
+                println!("{}", current_layout_line(&response, json)?);
+                return Ok(());
+            }
+
             if json {
                 let response =
                     serde_json::to_string(&response).context("error formatting response")?;
@@ -409,15 +522,28 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                     Event::WindowUrgencyChanged { id, urgent } => {
                         println!("Window {id}: urgency changed to {urgent}");
                     }
+                    Event::WindowGeometryChanged {
+                        id,
+                        workspace_geometry,
+                        output_geometry,
+                    } => {
+                        println!(
+                            "Window {id}: geometry changed to {workspace_geometry:?} \
+                             on the workspace, {output_geometry:?} on the output"
+                        );
+                    }
                     Event::KeyboardLayoutsChanged { keyboard_layouts } => {
                         println!("Keyboard layouts changed: {keyboard_layouts:?}");
                     }
-                    Event::KeyboardLayoutSwitched { idx } => {
-                        println!("Keyboard layout switched: {idx}");
+                    Event::KeyboardLayoutSwitched { idx, name } => {
+                        println!("Keyboard layout switched: {idx} {name}");
                     }
                     Event::OverviewOpenedOrClosed { is_open: opened } => {
                         println!("Overview toggled: {opened}");
                     }
+                    Event::SessionPausedOrResumed { is_paused } => {
+                        println!("Session paused: {is_paused}");
+                    }
                 }
             }
         }
@@ -440,11 +566,326 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                 println!("Overview is closed.");
             }
         }
+        Msg::BindConflicts => {
+            let Response::BindConflicts(conflicts) = response else {
+                bail!("unexpected response: expected BindConflicts, got {response:?}");
+            };
+
+            if json {
+                let conflicts =
+                    serde_json::to_string(&conflicts).context("error formatting response")?;
+                println!("{conflicts}");
+                return Ok(());
+            }
+
+            if conflicts.is_empty() {
+                println!("No conflicting binds found.");
+                return Ok(());
+            }
+
+            for conflict in conflicts {
+                let BindConflict { key, kind } = conflict;
+                match kind {
+                    BindConflictKind::ShadowedBy { key: other } => {
+                        println!("{key}: unreachable, shadowed by {other}");
+                    }
+                    BindConflictKind::ShadowedByHardcoded => {
+                        println!("{key}: unreachable, handled by a hardcoded bind");
+                    }
+                    BindConflictKind::OverridesInteractiveMove => {
+                        println!("{key}: replaces the built-in interactive move");
+                    }
+                    BindConflictKind::OverridesInteractiveResize => {
+                        println!("{key}: replaces the built-in interactive resize");
+                    }
+                    BindConflictKind::OverridesViewScroll => {
+                        println!("{key}: replaces the built-in view scrolling");
+                    }
+                }
+            }
+        }
+        Msg::VirtualDevices => {
+            let Response::VirtualDevices(devices) = response else {
+                bail!("unexpected response: expected VirtualDevices, got {response:?}");
+            };
+
+            if json {
+                let devices =
+                    serde_json::to_string(&devices).context("error formatting response")?;
+                println!("{devices}");
+                return Ok(());
+            }
+
+            if devices.is_empty() {
+                println!("No virtual devices.");
+                return Ok(());
+            }
+
+            for device in devices {
+                let VirtualDevice {
+                    kind,
+                    pid,
+                    executable,
+                    allowed,
+                } = device;
+
+                let kind = match kind {
+                    VirtualDeviceKind::Pointer => "Pointer",
+                    VirtualDeviceKind::Keyboard => "Keyboard",
+                };
+                println!("{kind}:");
+
+                let executable = executable.as_deref().unwrap_or("(unknown)");
+                println!("  Executable: {executable}");
+                if let Some(pid) = pid {
+                    println!("  PID: {pid}");
+                }
+                let allowed = if allowed { "yes" } else { "no" };
+                println!("  Allowed: {allowed}");
+            }
+        }
+        Msg::Status { verbose } => {
+            let Response::Status(status) = response else {
+                bail!("unexpected response: expected Status, got {response:?}");
+            };
+
+            if json {
+                let status = serde_json::to_string(&status).context("error formatting response")?;
+                println!("{status}");
+                return Ok(());
+            }
+
+            let mut frame_stats = status.frame_stats.into_iter().collect::<Vec<_>>();
+            frame_stats.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+            for (name, stats) in frame_stats {
+                println!("Output \"{name}\":");
+                println!("  Presented frames: {}", stats.presented_frames);
+                println!("  Missed deadlines: {}", stats.missed_deadlines);
+                println!("  Dropped frames: {}", stats.dropped_frames);
+
+                if verbose {
+                    println!("  Recent presentation time prediction error:");
+                    println!("    Mean: {:.0} µs", stats.mean_prediction_error_us);
+                    println!("    Jitter: {:.0} µs", stats.prediction_jitter_us);
+                    println!("    Max: {:.0} µs", stats.max_prediction_error_us);
+                }
+            }
+        }
+        Msg::Services => {
+            let Response::Services(services) = response else {
+                bail!("unexpected response: expected Services, got {response:?}");
+            };
+
+            if json {
+                let services =
+                    serde_json::to_string(&services).context("error formatting response")?;
+                println!("{services}");
+                return Ok(());
+            }
+
+            if services.is_empty() {
+                println!("No supervised services.");
+                return Ok(());
+            }
+
+            for service in services {
+                let state = match service.state {
+                    ServiceState::Running => "running",
+                    ServiceState::Restarting => "restarting",
+                    ServiceState::Exited => "exited",
+                    ServiceState::Failed => "failed",
+                };
+                println!("{}: {state}", service.command.join(" "));
+
+                if let Some(pid) = service.pid {
+                    println!("  PID: {pid}");
+                }
+                println!("  Restarts: {}", service.restarts);
+            }
+        }
+        Msg::Debug {
+            debug: DebugMsg::MatchRules { .. },
+        } => {
+            let Response::WindowRuleMatches(matches) = response else {
+                bail!("unexpected response: expected WindowRuleMatches, got {response:?}");
+            };
+
+            if json {
+                let matches =
+                    serde_json::to_string(&matches).context("error formatting response")?;
+                println!("{matches}");
+                return Ok(());
+            }
+
+            if matches.rules.is_empty() {
+                println!("No window rules configured.");
+            }
+
+            for rule in matches.rules {
+                let WindowRuleMatch {
+                    index,
+                    matched,
+                    excluded,
+                    applied,
+                } = rule;
+                let status = if applied { "applied" } else { "not applied" };
+                println!("Window rule {}: {status}", index + 1);
+
+                if !matched.is_empty() {
+                    let matched = matched.iter().map(|i| (i + 1).to_string());
+                    println!("  Matched: {}", matched.collect::<Vec<_>>().join(", "));
+                }
+                if !excluded.is_empty() {
+                    let excluded = excluded.iter().map(|i| (i + 1).to_string());
+                    println!("  Excluded by: {}", excluded.collect::<Vec<_>>().join(", "));
+                }
+            }
+
+            println!();
+            println!("Resolved rules:");
+            println!("{}", matches.resolved);
+        }
+        Msg::WindowThumbnail { .. }
+        | Msg::Snapshot { .. }
+        | Msg::Debug {
+            debug: DebugMsg::Inject { .. },
+        } => {
+            let Response::Handled = response else {
+                bail!("unexpected response: expected Handled, got {response:?}");
+            };
+        }
+        Msg::Config {
+            config: ConfigMsg::Set { .. },
+        } => {
+            let Response::Handled = response else {
+                bail!("unexpected response: expected Handled, got {response:?}");
+            };
+        }
+        Msg::Config {
+            config: ConfigMsg::Get { .. },
+        } => {
+            let Response::ConfigValue(value) = response else {
+                bail!("unexpected response: expected ConfigValue, got {response:?}");
+            };
+
+            if json {
+                let value = serde_json::to_string(&value).context("error formatting response")?;
+                println!("{value}");
+                return Ok(());
+            }
+
+            println!("{value}");
+        }
+        Msg::Debug {
+            debug: DebugMsg::CursorPlanes,
+        } => {
+            let Response::CursorPlanes(planes) = response else {
+                bail!("unexpected response: expected CursorPlanes, got {response:?}");
+            };
+
+            if json {
+                let planes = serde_json::to_string(&planes).context("error formatting response")?;
+                println!("{planes}");
+                return Ok(());
+            }
+
+            let mut planes = planes.into_iter().collect::<Vec<_>>();
+            planes.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+            for (name, plane) in planes {
+                let plane = match plane {
+                    CursorPlane::Hardware => "hardware cursor plane",
+                    CursorPlane::Composited(reason) => match reason {
+                        CursorPlaneFallback::Unsupported => "composited (no cursor plane)",
+                        CursorPlaneFallback::Disabled => "composited (disabled in the config)",
+                        CursorPlaneFallback::FractionalScale => "composited (fractional scale)",
+                        CursorPlaneFallback::Oversized => "composited (cursor too large)",
+                        CursorPlaneFallback::SurfaceTransform => {
+                            "composited (cursor surface transform)"
+                        }
+                        CursorPlaneFallback::SurfaceScale => {
+                            "composited (cursor surface scale mismatch)"
+                        }
+                    },
+                };
+                println!("Output \"{name}\": {plane}");
+            }
+        }
     }
 
     Ok(())
 }
 
+/// Prints how the config of the running niri instance would change with `config`.
+pub fn print_config_diff(config: &Config) -> anyhow::Result<()> {
+    let mut socket = Socket::connect().context("error connecting to the niri socket")?;
+    let reply = socket
+        .send(Request::ConfigSummary)
+        .context("error communicating with niri")?;
+    let response = reply.map_err(|err_msg| anyhow!(err_msg).context("niri returned an error"))?;
+    let Response::ConfigSummary(running) = response else {
+        bail!("unexpected response: expected ConfigSummary, got {response:?}");
+    };
+
+    let changes = config_diff::diff(&running, &config_diff::summarize(config));
+    if changes.is_empty() {
+        println!("No changes in binds, outputs or window rules.");
+    }
+    for change in changes {
+        println!("{change}");
+    }
+
+    Ok(())
+}
+
+/// Parses the `;`-separated actions of `niri msg batch`.
+fn batch_actions(args: &[String]) -> anyhow::Result<Vec<Action>> {
+    args.split(|arg| arg == ";")
+        .map(|args| {
+            if args.is_empty() {
+                bail!("empty action in batch");
+            }
+
+            let args = std::iter::once("action").chain(args.iter().map(String::as_str));
+            let mut action = Action::try_parse_from(args).unwrap_or_else(|err| err.exit());
+            resolve_action_paths(&mut action)?;
+            Ok(action)
+        })
+        .collect()
+}
+
+/// Makes paths in the action absolute, since niri resolves them in its own working directory.
+fn resolve_action_paths(action: &mut Action) -> anyhow::Result<()> {
+    if let Action::SetWallpaper { path, .. } = action {
+        if !path.starts_with('~') {
+            let absolute = std::path::absolute(&*path).context("error resolving wallpaper path")?;
+            *path = absolute
+                .into_os_string()
+                .into_string()
+                .map_err(|path| anyhow!("wallpaper path is not valid UTF-8: {path:?}"))?;
+        }
+    }
+    if let Action::LoadConfigFile { path } = action {
+        if !path.starts_with('~') {
+            let absolute = std::path::absolute(&*path).context("error resolving config path")?;
+            *path = absolute
+                .into_os_string()
+                .into_string()
+                .map_err(|path| anyhow!("config path is not valid UTF-8: {path:?}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Makes the snapshot path absolute, since niri reads and writes it in its own working directory.
+fn resolve_snapshot_path(path: &Path) -> anyhow::Result<String> {
+    let path = std::path::absolute(path).context("error resolving snapshot path")?;
+    path.into_os_string()
+        .into_string()
+        .map_err(|path| anyhow!("snapshot path is not valid UTF-8: {path:?}"))
+}
+
 fn print_output(output: Output) -> anyhow::Result<()> {
     let Output {
         name,
@@ -457,6 +898,9 @@ fn print_output(output: Output) -> anyhow::Result<()> {
         vrr_supported,
         vrr_enabled,
         logical,
+        edid,
+        dpi,
+        suggested_scale,
     } = output;
 
     let serial = serial.as_deref().unwrap_or("Unknown");
@@ -492,6 +936,13 @@ fn print_output(output: Output) -> anyhow::Result<()> {
         println!("  Physical size: unknown");
     }
 
+    if let Some(dpi) = dpi {
+        println!("  DPI: {dpi:.0}");
+    }
+    if let Some(scale) = suggested_scale {
+        println!("  Suggested scale: {scale}");
+    }
+
     if let Some(logical) = logical {
         let LogicalOutput {
             x,
@@ -518,6 +969,10 @@ fn print_output(output: Output) -> anyhow::Result<()> {
         println!("  Transform: {transform}");
     }
 
+    if let Some(edid) = edid {
+        print_output_edid(&edid);
+    }
+
     println!("  Available modes:");
     for (idx, mode) in modes.into_iter().enumerate() {
         let Mode {
@@ -541,6 +996,63 @@ fn print_output(output: Output) -> anyhow::Result<()> {
     Ok(())
 }
 
+fn print_output_edid(edid: &OutputEdid) {
+    if let Some((width, height)) = edid.max_resolution {
+        println!("  Max resolution: {width}x{height}");
+    }
+
+    if let Some(hdr) = &edid.hdr {
+        let HdrMetadata {
+            pq,
+            hlg,
+            traditional_hdr,
+            max_luminance,
+            max_frame_avg_luminance,
+            min_luminance,
+        } = *hdr;
+
+        let mut eotfs = Vec::new();
+        if pq {
+            eotfs.push("PQ");
+        }
+        if hlg {
+            eotfs.push("HLG");
+        }
+        if traditional_hdr {
+            eotfs.push("traditional gamma");
+        }
+        println!("  HDR: supported ({})", eotfs.join(", "));
+
+        let format = |value: Option<f64>| match value {
+            Some(value) => format!("{value:.2} cd/m²"),
+            None => String::from("unknown"),
+        };
+        println!("    Max luminance: {}", format(max_luminance));
+        println!(
+            "    Max frame-average luminance: {}",
+            format(max_frame_avg_luminance)
+        );
+        println!("    Min luminance: {}", format(min_luminance));
+    } else {
+        println!("  HDR: not supported");
+    }
+
+    if !edid.colorimetry.is_empty() {
+        let colorimetry = edid
+            .colorimetry
+            .iter()
+            .map(|c| match c {
+                Colorimetry::Bt2020Rgb => "BT.2020 RGB",
+                Colorimetry::Bt2020Ycc => "BT.2020 YCC",
+                Colorimetry::Bt2020Cycc => "BT.2020 cYCC",
+                Colorimetry::St2113Rgb => "ST 2113 RGB",
+                Colorimetry::Ictcp => "ICtCp",
+            })
+            .collect::<Vec<_>>();
+        println!("  Colorimetry: {}", colorimetry.join(", "));
+    }
+}
+
 fn print_window(window: &Window) {
     let focused = if window.is_focused { " (focused)" } else { "" };
     let urgent = if window.is_urgent { " (urgent)" } else { "" };
@@ -574,4 +1086,415 @@ fn print_window(window: &Window) {
     } else {
         println!("  Workspace ID: (none)");
     }
+
+    if let Some(geo) = window.workspace_geometry {
+        println!(
+            "  Workspace geometry: {}x{} at {}, {}",
+            geo.width, geo.height, geo.x, geo.y
+        );
+    }
+
+    if let Some(geo) = window.output_geometry {
+        println!(
+            "  Output geometry: {}x{} at {}, {}",
+            geo.width, geo.height, geo.x, geo.y
+        );
+    }
+}
+
+/// Query results for `--format table` and `--format csv`.
+struct Table {
+    header: &'static [&'static str],
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn format(&self, format: MsgFormat) -> String {
+        match format {
+            MsgFormat::Table => self.to_aligned(),
+            MsgFormat::Csv => self.to_csv(),
+            MsgFormat::Json => unreachable!("JSON is formatted with serde"),
+        }
+    }
+
+    fn to_aligned(&self) -> String {
+        let mut widths = self.header.iter().map(|h| h.len()).collect::<Vec<_>>();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let mut out = String::new();
+        let header = self.header.iter().map(|h| h.to_string());
+        let rows = std::iter::once(header.collect()).chain(self.rows.iter().cloned());
+        for row in rows {
+            let mut line = String::new();
+            for (cell, width) in row.iter().zip(&widths) {
+                let padding = width - cell.chars().count();
+                line.push_str(cell);
+                line.push_str(&" ".repeat(padding + 2));
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    fn to_csv(&self) -> String {
+        let quote = |cell: &str| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_owned()
+            }
+        };
+
+        let mut out = String::new();
+        let header = self.header.iter().map(|h| quote(h));
+        out.push_str(&header.collect::<Vec<_>>().join(","));
+        out.push('\n');
+        for row in &self.rows {
+            let row = row.iter().map(|cell| quote(cell));
+            out.push_str(&row.collect::<Vec<_>>().join(","));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn opt_cell<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Formats the line printed by `niri msg keyboard-layouts --current`.
+///
+/// Prints an empty name rather than failing if the current index is out of range, so that status
+/// bars polling this don't show an error.
+fn current_layout_line(layouts: &KeyboardLayouts, json: bool) -> anyhow::Result<String> {
+    let name = layouts.current_name().unwrap_or_default();
+    if json {
+        serde_json::to_string(name).context("error formatting response")
+    } else {
+        Ok(name.to_owned())
+    }
+}
+
+/// Formats a workspace for the `niri msg workspaces` list.
+fn workspace_line(ws: &Workspace) -> String {
+    let is_active = if ws.is_active { " * " } else { "   " };
+    let idx = ws.idx;
+    let name = if let Some(name) = ws.name.as_deref() {
+        format!(" \"{name}\"")
+    } else {
+        String::new()
+    };
+    let urgent = if ws.is_urgent { " (urgent)" } else { "" };
+    format!("{is_active}{idx}{name}{urgent}")
+}
+
+/// Turns an event into the line printed by `niri msg windows --watch`, if it's about windows.
+///
+/// `windows` tracks the open windows to tell newly opened windows and title changes apart from
+/// other window changes.
+fn window_watch_line(
+    windows: &mut HashMap<u64, Window>,
+    event: Event,
+) -> Option<serde_json::Value> {
+    match event {
+        Event::WindowsChanged { windows: all } => {
+            windows.clear();
+            windows.extend(all.iter().map(|window| (window.id, window.clone())));
+            Some(json!({ "event": "windows", "windows": all }))
+        }
+        Event::WindowOpenedOrChanged { window } => {
+            let line = match windows.get(&window.id) {
+                None => Some(json!({ "event": "opened", "window": &window })),
+                Some(old) if old.title != window.title => Some(json!({
+                    "event": "title-changed",
+                    "id": window.id,
+                    "title": &window.title,
+                })),
+                Some(_) => None,
+            };
+            windows.insert(window.id, window);
+            line
+        }
+        Event::WindowClosed { id } => {
+            windows.remove(&id);
+            Some(json!({ "event": "closed", "id": id }))
+        }
+        Event::WindowFocusChanged { id } => Some(json!({ "event": "focused", "id": id })),
+        _ => None,
+    }
+}
+
+fn windows_table(windows: &[Window]) -> Table {
+    let rows = windows
+        .iter()
+        .map(|window| {
+            vec![
+                window.id.to_string(),
+                opt_cell(window.title.as_deref()),
+                opt_cell(window.app_id.as_deref()),
+                opt_cell(window.pid),
+                opt_cell(window.workspace_id),
+                window.is_floating.to_string(),
+                window.is_focused.to_string(),
+                window.is_urgent.to_string(),
+            ]
+        })
+        .collect();
+
+    Table {
+        header: &[
+            "ID",
+            "TITLE",
+            "APP ID",
+            "PID",
+            "WORKSPACE",
+            "FLOATING",
+            "FOCUSED",
+            "URGENT",
+        ],
+        rows,
+    }
+}
+
+fn workspaces_table(workspaces: &[Workspace]) -> Table {
+    let rows = workspaces
+        .iter()
+        .map(|ws| {
+            vec![
+                ws.id.to_string(),
+                ws.idx.to_string(),
+                opt_cell(ws.name.as_deref()),
+                opt_cell(ws.output.as_deref()),
+                ws.is_active.to_string(),
+                ws.is_focused.to_string(),
+                ws.is_urgent.to_string(),
+                opt_cell(ws.active_window_id),
+            ]
+        })
+        .collect();
+
+    Table {
+        header: &[
+            "ID",
+            "IDX",
+            "NAME",
+            "OUTPUT",
+            "ACTIVE",
+            "FOCUSED",
+            "URGENT",
+            "ACTIVE WINDOW",
+        ],
+        rows,
+    }
+}
+
+fn outputs_table(outputs: impl Iterator<Item = Output>) -> Table {
+    let rows = outputs
+        .map(|output| {
+            let mode = output
+                .current_mode
+                .and_then(|idx| output.modes.get(idx))
+                .map(|mode| {
+                    let refresh = mode.refresh_rate as f64 / 1000.;
+                    format!("{}x{}@{refresh:.3}", mode.width, mode.height)
+                });
+            let logical = output.logical;
+
+            vec![
+                output.name,
+                output.make,
+                output.model,
+                opt_cell(output.serial),
+                opt_cell(mode),
+                output.vrr_enabled.to_string(),
+                opt_cell(logical.map(|l| l.x)),
+                opt_cell(logical.map(|l| l.y)),
+                opt_cell(logical.map(|l| l.width)),
+                opt_cell(logical.map(|l| l.height)),
+                opt_cell(logical.map(|l| l.scale)),
+                opt_cell(logical.map(|l| transform_name(l.transform))),
+            ]
+        })
+        .collect();
+
+    Table {
+        header: &[
+            "NAME",
+            "MAKE",
+            "MODEL",
+            "SERIAL",
+            "MODE",
+            "VRR",
+            "X",
+            "Y",
+            "WIDTH",
+            "HEIGHT",
+            "SCALE",
+            "TRANSFORM",
+        ],
+        rows,
+    }
+}
+
+/// Transform name as written in the config.
+fn transform_name(transform: Transform) -> &'static str {
+    match transform {
+        Transform::Normal => "normal",
+        Transform::_90 => "90",
+        Transform::_180 => "180",
+        Transform::_270 => "270",
+        Transform::Flipped => "flipped",
+        Transform::Flipped90 => "flipped-90",
+        Transform::Flipped180 => "flipped-180",
+        Transform::Flipped270 => "flipped-270",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use niri_ipc::WorkspaceReferenceArg;
+
+    use super::*;
+
+    fn table() -> Table {
+        Table {
+            header: &["ID", "TITLE"],
+            rows: vec![
+                vec![String::from("1"), String::from("Firefox")],
+                vec![String::from("12"), String::from("a, \"b\"")],
+            ],
+        }
+    }
+
+    #[test]
+    fn aligned_table() {
+        assert_eq!(
+            table().format(MsgFormat::Table),
+            "ID  TITLE\n1   Firefox\n12  a, \"b\"\n"
+        );
+    }
+
+    #[test]
+    fn window_watch_lines() {
+        let window = Window {
+            id: 1,
+            title: Some(String::from("a")),
+            app_id: None,
+            pid: None,
+            workspace_id: None,
+            is_focused: false,
+            is_floating: false,
+            is_urgent: false,
+            workspace_geometry: None,
+            output_geometry: None,
+        };
+        let mut windows = HashMap::new();
+
+        let line = window_watch_line(&mut windows, Event::WindowsChanged { windows: vec![] });
+        assert_eq!(line.unwrap()["event"], "windows");
+
+        let opened = Event::WindowOpenedOrChanged {
+            window: window.clone(),
+        };
+        let line = window_watch_line(&mut windows, opened).unwrap();
+        assert_eq!(line["event"], "opened");
+        assert_eq!(line["window"]["id"], 1);
+
+        // Changes other than the title aren't reported.
+        let floating = Window {
+            is_floating: true,
+            ..window.clone()
+        };
+        let changed = Event::WindowOpenedOrChanged { window: floating };
+        assert_eq!(window_watch_line(&mut windows, changed), None);
+
+        let renamed = Window {
+            title: Some(String::from("b")),
+            ..window
+        };
+        let changed = Event::WindowOpenedOrChanged { window: renamed };
+        let line = window_watch_line(&mut windows, changed).unwrap();
+        assert_eq!(
+            line,
+            json!({ "event": "title-changed", "id": 1, "title": "b" })
+        );
+
+        let line = window_watch_line(&mut windows, Event::WindowClosed { id: 1 }).unwrap();
+        assert_eq!(line, json!({ "event": "closed", "id": 1 }));
+        assert!(windows.is_empty());
+
+        let overview = Event::OverviewOpenedOrClosed { is_open: true };
+        assert_eq!(window_watch_line(&mut windows, overview), None);
+    }
+
+    #[test]
+    fn current_keyboard_layout() {
+        let mut layouts = KeyboardLayouts {
+            names: vec![String::from("English (US)"), String::from("Russian")],
+            current_idx: 1,
+        };
+        assert_eq!(current_layout_line(&layouts, false).unwrap(), "Russian");
+        assert_eq!(current_layout_line(&layouts, true).unwrap(), "\"Russian\"");
+
+        layouts.current_idx = 2;
+        assert_eq!(current_layout_line(&layouts, false).unwrap(), "");
+        assert_eq!(current_layout_line(&layouts, true).unwrap(), "\"\"");
+    }
+
+    #[test]
+    fn workspace_lines() {
+        let ws = Workspace {
+            id: 1,
+            idx: 2,
+            name: None,
+            output: Some(String::from("DP-1")),
+            is_urgent: false,
+            is_active: false,
+            is_focused: false,
+            active_window_id: None,
+        };
+        assert_eq!(workspace_line(&ws), "   2");
+
+        let ws = Workspace {
+            name: Some(String::from("chat")),
+            is_urgent: true,
+            is_active: true,
+            ..ws
+        };
+        assert_eq!(workspace_line(&ws), " * 2 \"chat\" (urgent)");
+    }
+
+    #[test]
+    fn batch_splits_actions() {
+        let args = |args: &str| args.split(' ').map(String::from).collect::<Vec<_>>();
+
+        let actions = batch_actions(&args("focus-workspace 3 ; focus-column-left")).unwrap();
+        assert!(matches!(
+            actions[..],
+            [
+                Action::FocusWorkspace {
+                    reference: WorkspaceReferenceArg::Index(3)
+                },
+                Action::FocusColumnLeft {},
+            ]
+        ));
+
+        // A stray separator leaves an empty action.
+        assert!(batch_actions(&args("focus-column-left ;")).is_err());
+        assert!(batch_actions(&args("; focus-column-left")).is_err());
+        assert!(batch_actions(&args("focus-column-left ; ; focus-column-right")).is_err());
+    }
+
+    #[test]
+    fn csv_quoting() {
+        assert_eq!(
+            table().format(MsgFormat::Csv),
+            "ID,TITLE\n1,Firefox\n12,\"a, \"\"b\"\"\"\n"
+        );
+    }
 }
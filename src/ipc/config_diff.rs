@@ -0,0 +1,121 @@
+use std::collections::{BTreeSet, HashMap};
+
+use niri_config::{BindActivation, Config};
+use niri_ipc::ConfigSummary;
+
+use crate::input::bind_conflicts::format_key;
+
+/// Summarizes the parts of the config that `niri validate --diff` compares.
+pub fn summarize(config: &Config) -> ConfigSummary {
+    let binds = config
+        .binds
+        .0
+        .iter()
+        .map(|bind| {
+            let mut key = format_key(bind.key);
+            match bind.activation {
+                BindActivation::Press => (),
+                BindActivation::Release => key.push_str(" (release)"),
+                BindActivation::DoubleTap => key.push_str(" (double-tap)"),
+            }
+            (key, format!("{:?}", bind.action))
+        })
+        .collect();
+
+    let outputs = config
+        .outputs
+        .0
+        .iter()
+        .map(|output| (output.name.clone(), format!("{output:?}")))
+        .collect();
+
+    let window_rules = config
+        .window_rules
+        .iter()
+        .map(|rule| format!("{rule:?}"))
+        .collect();
+
+    ConfigSummary {
+        binds,
+        outputs,
+        window_rules,
+    }
+}
+
+/// Describes the changes from the `old` config to the `new` one, one per line.
+pub fn diff(old: &ConfigSummary, new: &ConfigSummary) -> Vec<String> {
+    let mut rv = Vec::new();
+    diff_map(&mut rv, "bind", &old.binds, &new.binds);
+    diff_map(&mut rv, "output", &old.outputs, &new.outputs);
+
+    let rule_count = old.window_rules.len().max(new.window_rules.len());
+    for idx in 0..rule_count {
+        match (old.window_rules.get(idx), new.window_rules.get(idx)) {
+            (Some(old), Some(new)) if old != new => {
+                rv.push(format!("~ window-rule #{idx}: {old} -> {new}"));
+            }
+            (Some(old), None) => rv.push(format!("- window-rule #{idx}: {old}")),
+            (None, Some(new)) => rv.push(format!("+ window-rule #{idx}: {new}")),
+            _ => (),
+        }
+    }
+
+    rv
+}
+
+fn diff_map(
+    rv: &mut Vec<String>,
+    kind: &str,
+    old: &HashMap<String, String>,
+    new: &HashMap<String, String>,
+) {
+    let names: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+    for name in names {
+        match (old.get(name), new.get(name)) {
+            (Some(old), Some(new)) if old != new => {
+                rv.push(format!("~ {kind} {name}: {old} -> {new}"));
+            }
+            (Some(old), None) => rv.push(format!("- {kind} {name}: {old}")),
+            (None, Some(new)) => rv.push(format!("+ {kind} {name}: {new}")),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(binds: &[(&str, &str)], window_rules: &[&str]) -> ConfigSummary {
+        ConfigSummary {
+            binds: binds
+                .iter()
+                .map(|(key, action)| (key.to_string(), action.to_string()))
+                .collect(),
+            outputs: HashMap::new(),
+            window_rules: window_rules.iter().map(|rule| rule.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed() {
+        let old = summary(&[("Mod+T", "Spawn"), ("Mod+Q", "CloseWindow")], &["a", "b"]);
+        let new = summary(&[("Mod+T", "SpawnSh"), ("Mod+W", "CloseWindow")], &["a"]);
+
+        assert_eq!(
+            diff(&old, &new),
+            [
+                "- bind Mod+Q: CloseWindow",
+                "~ bind Mod+T: Spawn -> SpawnSh",
+                "+ bind Mod+W: CloseWindow",
+                "- window-rule #1: b",
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_of_same_config_is_empty() {
+        let config = Config::default();
+        assert!(diff(&summarize(&config), &summarize(&config)).is_empty());
+    }
+}
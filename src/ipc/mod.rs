@@ -30,4 +30,5 @@
 
 //     src/ipc/server.rs：具体实现服务端逻辑
 pub mod client;
+pub mod config_diff;
 pub mod server;
@@ -1,11 +1,13 @@
-use std::cell::RefCell;
-use std::collections::HashSet;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::os::unix::net::{UnixListener, UnixStream};
+use std::panic::AssertUnwindSafe;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
-use std::{env, io, process};
+use std::time::Duration;
+use std::{env, io, process, thread};
 
 use anyhow::Context;
 use async_channel::{Receiver, Sender, TrySendError};
@@ -17,24 +19,41 @@ use futures_util::{select_biased, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, Fu
 use niri_config::OutputName;
 use niri_ipc::state::{EventStreamState, EventStreamStatePart as _};
 use niri_ipc::{
-    Event, KeyboardLayouts, OutputConfigChanged, Overview, Reply, Request, Response, Workspace,
+    Event, KeyboardLayouts, OutputAction, OutputConfigChanged, Overview, Reply, Request, Response,
+    Status, VirtualDevice, VirtualDeviceKind, WindowGeometry, WindowRuleMatches, Workspace,
+    WorkspaceReferenceArg,
 };
 use smithay::desktop::layer_map_for_output;
 use smithay::reexports::calloop::generic::Generic;
-use smithay::reexports::calloop::{Interest, LoopHandle, Mode, PostAction};
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use smithay::reexports::calloop::{Interest, LoopHandle, Mode, PostAction, RegistrationToken};
 use smithay::reexports::rustix::fs::unlink;
+use smithay::reexports::wayland_server::Client;
+use smithay::utils::{Logical, Rectangle};
 use smithay::wayland::shell::wlr_layer::{KeyboardInteractivity, Layer};
 
 use crate::backend::IpcOutputMap;
+use crate::input::bind_conflicts::find_bind_conflicts;
+use crate::input::virtual_input::VirtualInputAccess;
+use crate::ipc::config_diff;
 use crate::layout::workspace::WorkspaceId;
-use crate::niri::State;
-use crate::utils::{version, with_toplevel_role};
-use crate::window::Mapped;
+use crate::niri::{ClientState, State};
+use crate::utils::crash;
+use crate::utils::{get_monotonic_time, version, with_toplevel_role};
+use crate::window::thumbnails;
+use crate::window::{Mapped, ResolvedWindowRules, WindowRef};
 
 // If an event stream client fails to read events fast enough that we accumulate more than this
 // number in our buffer, we drop that event stream client.
 const EVENT_STREAM_BUFFER_SIZE: usize = 64;
 
+// Window geometry changes are sent at most this often, since they change every frame during
+// animations.
+const GEOMETRY_EVENT_THROTTLE: Duration = Duration::from_millis(50);
+
+/// Geometry of a window within its workspace view and within its output.
+type IpcWindowGeometry = (Option<WindowGeometry>, Option<WindowGeometry>);
+
 pub struct IpcServer {
     /// Path to the IPC socket.
     ///
@@ -42,6 +61,10 @@ pub struct IpcServer {
     pub socket_path: Option<PathBuf>,
     event_streams: Rc<RefCell<Vec<EventStreamSender>>>,
     event_stream_state: Rc<RefCell<EventStreamState>>,
+    /// When window geometry changes were last sent.
+    last_geometry_event: Cell<Option<Duration>>,
+    /// Timer to send window geometry changes held back by throttling.
+    geometry_timer: Cell<Option<RegistrationToken>>,
 }
 
 struct ClientCtx {
@@ -103,6 +126,8 @@ impl IpcServer {
             socket_path,
             event_streams: Rc::new(RefCell::new(Vec::new())),
             event_stream_state: Rc::new(RefCell::new(EventStreamState::default())),
+            last_geometry_event: Cell::new(None),
+            geometry_timer: Cell::new(None),
         })
     }
 
@@ -203,7 +228,7 @@ async fn handle_client(ctx: ClientCtx, stream: Async<'static, UnixStream>) -> an
         let requested_event_stream = matches!(request, Ok(Request::EventStream));
 
         let reply = match request {
-            Ok(request) => process(&ctx, request).await,
+            Ok(request) => process_catching_panics(&ctx, request).await,
             Err(err) => Err(err),
         };
 
@@ -262,6 +287,22 @@ async fn handle_client(ctx: ClientCtx, stream: Async<'static, UnixStream>) -> an
     }
 }
 
+/// Processes a request, reporting a panic in the process instead of crashing the compositor.
+///
+/// Actions themselves run on the main loop outside of this, since a panic halfway through one
+/// could leave the layout in an inconsistent state.
+async fn process_catching_panics(ctx: &ClientCtx, request: Request) -> Reply {
+    match AssertUnwindSafe(process(ctx, request)).catch_unwind().await {
+        Ok(reply) => reply,
+        Err(_) => {
+            let report = crash::report("IPC request handling");
+            ctx.event_loop
+                .insert_idle(move |state| state.show_crash_report(report));
+            Err(String::from("niri crashed while processing the request"))
+        }
+    }
+}
+
 async fn process(ctx: &ClientCtx, request: Request) -> Reply {
     let response = match request {
         Request::ReturnError => return Err(String::from("example compositor error")),
@@ -334,22 +375,93 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
             let window = windows.values().find(|win| win.is_focused).cloned();
             Response::FocusedWindow(window)
         }
+        Request::FocusHistory => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let layout = &state.niri.layout;
+                let ids: Vec<_> = state
+                    .niri
+                    .focus_history
+                    .iter()
+                    .filter_map(|window| {
+                        let (_, mapped) = layout.windows().find(|(_, m)| &m.window == window)?;
+                        Some(mapped.id().get())
+                    })
+                    .collect();
+                let _ = tx.send_blocking(ids);
+            });
+            let result = rx.recv().await;
+            let ids = result.map_err(|_| String::from("error getting focus history"))?;
+
+            let state = ctx.event_stream_state.borrow();
+            let windows = &state.windows.windows;
+            let windows = ids
+                .iter()
+                .filter_map(|id| windows.get(id).cloned())
+                .collect();
+            Response::FocusHistory(windows)
+        }
 
         Request::Action(action) => {
             let (tx, rx) = async_channel::bounded(1);
 
+            let window_id = action_window_id(&action);
+            let workspace_id = action_workspace_id(&action);
             let action = niri_config::Action::from(action);
             ctx.event_loop.insert_idle(move |state| {
+                // Validate ids right before running the action, so that a window or workspace
+                // that went away in the meantime is reported rather than silently ignored.
+                if let Err(err) = check_target_ids(state, window_id, workspace_id) {
+                    let _ = tx.send_blocking(Err(err));
+                    return;
+                }
+
                 // Make sure some logic like workspace clean-up has a chance to run before doing
                 // actions.
                 state.do_action(action);
-                let _ = tx.send_blocking(());
+                let _ = tx.send_blocking(Ok(()));
             });
 
             // Wait until the action has been processed before returning. This is important for a
             // few actions, for instance for DoScreenTransition this wait ensures that the screen
             // contents were sampled into the texture.
-            let _ = rx.recv().await;
+            if let Ok(Err(err)) = rx.recv().await {
+                return Err(err);
+            }
+            Response::Handled
+        }
+        Request::Actions(actions) => {
+            let (tx, rx) = async_channel::bounded(1);
+
+            let actions: Vec<_> = actions
+                .into_iter()
+                .map(|action| {
+                    let window_id = action_window_id(&action);
+                    let workspace_id = action_workspace_id(&action);
+                    (window_id, workspace_id, niri_config::Action::from(action))
+                })
+                .collect();
+            ctx.event_loop.insert_idle(move |state| {
+                // Check all ids up-front so that a missing target doesn't leave the batch
+                // half-done.
+                for (window_id, workspace_id, _) in &actions {
+                    if let Err(err) = check_target_ids(state, *window_id, *workspace_id) {
+                        let _ = tx.send_blocking(Err(err));
+                        return;
+                    }
+                }
+
+                // Run everything in this one idle callback, so that the intermediate states are
+                // neither rendered nor sent over the event stream.
+                for (_, _, action) in actions {
+                    state.do_action(action);
+                }
+                let _ = tx.send_blocking(Ok(()));
+            });
+
+            if let Ok(Err(err)) = rx.recv().await {
+                return Err(err);
+            }
             Response::Handled
         }
         Request::Output { output, action } => {
@@ -364,8 +476,14 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
             };
             drop(ipc_outputs);
 
-            ctx.event_loop.insert_idle(move |state| {
-                state.apply_transient_output_config(&output, action);
+            ctx.event_loop.insert_idle(move |state| match action {
+                OutputAction::PowerOff => state.set_output_powered(&output, false),
+                OutputAction::PowerOn => state.set_output_powered(&output, true),
+                OutputAction::Gamma { gamma, brightness } => {
+                    state.set_output_gamma(&output, gamma, brightness)
+                }
+                OutputAction::ToggleGrayscale => state.toggle_output_grayscale(&output),
+                action => state.apply_transient_output_config(&output, action),
             });
 
             Response::OutputConfigChanged(response)
@@ -402,11 +520,288 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
             let is_open = state.overview.is_open;
             Response::OverviewState(Overview { is_open })
         }
+        Request::BindConflicts => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let config = state.niri.config.borrow();
+                let mod_key = state.backend.mod_key(&config);
+                let conflicts = find_bind_conflicts(
+                    &config.binds,
+                    mod_key,
+                    config.input.disable_power_key_handling,
+                );
+                let conflicts = conflicts.iter().map(|c| c.to_ipc()).collect();
+                let _ = tx.send_blocking(conflicts);
+            });
+            let result = rx.recv().await;
+            let conflicts = result.map_err(|_| String::from("error analyzing binds"))?;
+            Response::BindConflicts(conflicts)
+        }
+        Request::VirtualDevices => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let niri = &state.niri;
+                let device = |kind, client: Option<Client>| {
+                    let process = client
+                        .as_ref()
+                        .and_then(|client| client.get_data::<ClientState>())
+                        .and_then(|data| data.process.get());
+                    let allowed = client.is_some_and(|client| {
+                        niri.virtual_input.access(&client) == VirtualInputAccess::Allowed
+                    });
+
+                    VirtualDevice {
+                        kind,
+                        pid: process.map(|p| p.pid),
+                        executable: process
+                            .and_then(|p| p.executable.as_ref())
+                            .map(|exe| exe.to_string_lossy().into_owned()),
+                        allowed,
+                    }
+                };
+
+                let pointers = niri
+                    .virtual_pointers
+                    .iter()
+                    .map(|pointer| device(VirtualDeviceKind::Pointer, pointer.client()));
+                let keyboards = niri.virtual_keyboards.iter().flat_map(|(id, count)| {
+                    let client = niri.display_handle.get_client(id.clone()).ok();
+                    let keyboard = device(VirtualDeviceKind::Keyboard, client);
+                    std::iter::repeat(keyboard).take(*count)
+                });
+                let devices = pointers.chain(keyboards).collect();
+                let _ = tx.send_blocking(devices);
+            });
+            let result = rx.recv().await;
+            let devices = result.map_err(|_| String::from("error getting virtual devices"))?;
+            Response::VirtualDevices(devices)
+        }
+        Request::Status => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let frame_stats = state
+                    .niri
+                    .output_state
+                    .iter()
+                    .map(|(output, output_state)| {
+                        (output.name(), output_state.frame_clock.stats().to_ipc())
+                    })
+                    .collect();
+                let _ = tx.send_blocking(Status { frame_stats });
+            });
+            let result = rx.recv().await;
+            let status = result.map_err(|_| String::from("error getting status"))?;
+            Response::Status(status)
+        }
+        Request::MatchWindowRules { id } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let window = state
+                    .niri
+                    .layout
+                    .windows()
+                    .find(|(_, m)| m.id().get() == id);
+                let matches = window.map(|(_, mapped)| {
+                    let config = state.niri.config.borrow();
+                    let (resolved, rules) = ResolvedWindowRules::compute_with_matches(
+                        &config.window_rules,
+                        WindowRef::Mapped(mapped),
+                    );
+                    WindowRuleMatches {
+                        rules,
+                        resolved: format!("{resolved:#?}"),
+                    }
+                });
+                let _ = tx.send_blocking(matches);
+            });
+            let result = rx.recv().await;
+            let matches = result.map_err(|_| String::from("error matching window rules"))?;
+            let matches = matches.ok_or_else(|| format!("window with id {id} not found"))?;
+            Response::WindowRuleMatches(matches)
+        }
+        Request::CursorPlanes => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let planes = state
+                    .niri
+                    .output_state
+                    .iter()
+                    .map(|(output, output_state)| (output.name(), output_state.cursor_plane))
+                    .collect();
+                let _ = tx.send_blocking(planes);
+            });
+            let result = rx.recv().await;
+            let planes = result.map_err(|_| String::from("error getting cursor planes"))?;
+            Response::CursorPlanes(planes)
+        }
+        Request::SetConfigValue { path, value } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.set_config_value(&path, &value));
+            });
+            let result = rx.recv().await;
+            result.map_err(|_| String::from("error setting config value"))??;
+            Response::Handled
+        }
+        Request::WindowThumbnail { id, path } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.window_thumbnail_pixels(id));
+            });
+            let result = rx.recv().await;
+            let (size, pixels) =
+                result.map_err(|_| String::from("error saving window thumbnail"))??;
+
+            // Encoding can take a while for large thumbnails, so keep it off the main thread.
+            let (tx, rx) = async_channel::bounded(1);
+            thread::Builder::new()
+                .name("Thumbnail Encoder".to_owned())
+                .spawn(move || {
+                    let path = PathBuf::from(path);
+                    let _ = tx.send_blocking(thumbnails::save_png(&path, size, &pixels));
+                })
+                .map_err(|err| format!("error spawning thread: {err}"))?;
+            let result = rx.recv().await;
+            result.map_err(|_| String::from("error saving window thumbnail"))??;
+            Response::Handled
+        }
+        Request::SaveLayoutSnapshot { path } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.niri.save_layout_snapshot(path.as_deref()));
+            });
+            let result = rx.recv().await;
+            result.map_err(|_| String::from("error saving layout snapshot"))??;
+            Response::Handled
+        }
+        Request::RestoreLayoutSnapshot { path } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.niri.restore_layout_snapshot(path.as_deref()));
+            });
+            let result = rx.recv().await;
+            result.map_err(|_| String::from("error restoring layout snapshot"))??;
+            Response::Handled
+        }
+        Request::InjectInput(input) => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.inject_input(input));
+            });
+            let result = rx.recv().await;
+            result.map_err(|_| String::from("error injecting input"))??;
+            Response::Handled
+        }
+        Request::GetConfigValue { path } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let config = state.niri.config.borrow();
+                let _ = tx.send_blocking(niri_config::overrides::get_value(&config, &path));
+            });
+            let result = rx.recv().await;
+            let value = result.map_err(|_| String::from("error getting config value"))??;
+            Response::ConfigValue(value)
+        }
+        Request::ConfigSummary => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let config = state.niri.config.borrow();
+                let _ = tx.send_blocking(config_diff::summarize(&config));
+            });
+            let result = rx.recv().await;
+            let summary = result.map_err(|_| String::from("error summarizing config"))?;
+            Response::ConfigSummary(summary)
+        }
+        Request::Services => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.niri.services.snapshot());
+            });
+            let result = rx.recv().await;
+            let services = result.map_err(|_| String::from("error getting services"))?;
+            Response::Services(services)
+        }
     };
 
     Ok(response)
 }
 
+/// Checks that the window and workspace targeted by an action still exist.
+fn check_target_ids(
+    state: &State,
+    window_id: Option<u64>,
+    workspace_id: Option<u64>,
+) -> Result<(), String> {
+    if let Some(id) = window_id {
+        let found = state.niri.layout.windows().any(|(_, m)| m.id().get() == id);
+        if !found {
+            return Err(format!("window with id {id} not found"));
+        }
+    }
+    if let Some(id) = workspace_id {
+        let id = WorkspaceId::specific(id);
+        if state.niri.layout.find_workspace_by_id(id).is_none() {
+            let id = id.get();
+            return Err(format!("workspace with id {id} not found"));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the id of the window that the action explicitly targets, if any.
+fn action_window_id(action: &niri_ipc::Action) -> Option<u64> {
+    use niri_ipc::Action::*;
+
+    match *action {
+        FocusWindow { id }
+        | ToggleWindowUrgent { id }
+        | SetWindowUrgent { id }
+        | UnsetWindowUrgent { id } => Some(id),
+        CloseWindow { id }
+        | CloseWindowOrKill { id, .. }
+        | FullscreenWindow { id }
+        | ToggleWindowedFullscreen { id }
+        | ConsumeOrExpelWindowLeft { id }
+        | ConsumeOrExpelWindowRight { id }
+        | CenterWindow { id }
+        | MoveWindowToMonitor { id, .. }
+        | SetWindowWidth { id, .. }
+        | SetWindowHeight { id, .. }
+        | ResetWindowHeight { id }
+        | SwitchPresetWindowWidth { id }
+        | SwitchPresetWindowHeight { id }
+        | ToggleWindowFloating { id }
+        | MoveWindowToFloating { id }
+        | MoveWindowToTiling { id }
+        | MoveFloatingWindow { id, .. }
+        | ToggleWindowRuleOpacity { id }
+        | ToggleWindowKeepAspectRatio { id } => id,
+        MoveWindowToWorkspace { window_id, .. } => window_id,
+        _ => None,
+    }
+}
+
+/// Returns the id of the workspace that the action explicitly targets by id, if any.
+fn action_workspace_id(action: &niri_ipc::Action) -> Option<u64> {
+    use niri_ipc::Action::*;
+
+    let reference = match action {
+        FocusWorkspace { reference }
+        | MoveWindowToWorkspace { reference, .. }
+        | MoveColumnToWorkspace { reference, .. } => Some(reference),
+        MoveWorkspaceToIndex { reference, .. }
+        | UnsetWorkspaceName { reference }
+        | MoveWorkspaceToMonitor { reference, .. } => reference.as_ref(),
+        SetWorkspaceName { workspace, .. } => workspace.as_ref(),
+        _ => None,
+    };
+
+    match reference? {
+        WorkspaceReferenceArg::Id(id) => Some(*id),
+        _ => None,
+    }
+}
+
 async fn handle_event_stream_client(client: EventStreamClient) -> anyhow::Result<()> {
     let EventStreamClient {
         events,
@@ -434,7 +829,11 @@ async fn handle_event_stream_client(client: EventStreamClient) -> anyhow::Result
     Ok(())
 }
 
-fn make_ipc_window(mapped: &Mapped, workspace_id: Option<WorkspaceId>) -> niri_ipc::Window {
+fn make_ipc_window(
+    mapped: &Mapped,
+    workspace_id: Option<WorkspaceId>,
+    (workspace_geometry, output_geometry): IpcWindowGeometry,
+) -> niri_ipc::Window {
     with_toplevel_role(mapped.toplevel(), |role| niri_ipc::Window {
         id: mapped.id().get(),
         title: role.title.clone(),
@@ -444,9 +843,20 @@ fn make_ipc_window(mapped: &Mapped, workspace_id: Option<WorkspaceId>) -> niri_i
         is_focused: mapped.is_focused(),
         is_floating: mapped.is_floating(),
         is_urgent: mapped.is_urgent(),
+        workspace_geometry,
+        output_geometry,
     })
 }
 
+fn make_ipc_geometry(geo: Rectangle<f64, Logical>) -> WindowGeometry {
+    WindowGeometry {
+        x: geo.loc.x,
+        y: geo.loc.y,
+        width: geo.size.w,
+        height: geo.size.h,
+    }
+}
+
 impl State {
     pub fn ipc_keyboard_layouts_changed(&mut self) {
         let keyboard = self.niri.seat.get_keyboard().unwrap();
@@ -475,9 +885,10 @@ impl State {
 
     pub fn ipc_refresh_keyboard_layout_index(&mut self) {
         let keyboard = self.niri.seat.get_keyboard().unwrap();
-        let idx = keyboard.with_xkb_state(self, |context| {
+        let (idx, name) = keyboard.with_xkb_state(self, |context| {
             let xkb = context.xkb().lock().unwrap();
-            xkb.active_layout().0 as u8
+            let layout = xkb.active_layout();
+            (layout.0 as u8, xkb.layout_name(layout).to_owned())
         });
 
         let Some(server) = &self.niri.ipc_server else {
@@ -491,7 +902,7 @@ impl State {
             return;
         }
 
-        let event = Event::KeyboardLayoutSwitched { idx };
+        let event = Event::KeyboardLayoutSwitched { idx, name };
         state.apply(event.clone());
         server.send_event(event);
     }
@@ -614,10 +1025,27 @@ impl State {
         let mut events = Vec::new();
         let layout = &self.niri.layout;
 
+        let mut geometries = HashMap::new();
+        layout.with_window_geometries(|mapped, geo, output_geo| {
+            let geometry = (
+                Some(make_ipc_geometry(geo)),
+                output_geo.map(make_ipc_geometry),
+            );
+            geometries.insert(mapped.id().get(), geometry);
+        });
+
+        // Geometry changes are throttled, the rest are sent right away.
+        let now = get_monotonic_time();
+        let geometry_due = server.last_geometry_event.get().map_or(true, |last| {
+            GEOMETRY_EVENT_THROTTLE <= now.saturating_sub(last)
+        });
+        let mut geometry_sent = false;
+        let mut geometry_pending = false;
+
         // Check for window changes.
         let mut seen = HashSet::new();
         let mut focused_id = None;
-        layout.with_windows(|mapped, _, ws_id| {
+        let mut check_window = |mapped: &Mapped, ws_id: Option<WorkspaceId>| {
             let id = mapped.id().get();
             seen.insert(id);
 
@@ -625,8 +1053,10 @@ impl State {
                 focused_id = Some(id);
             }
 
+            let geometry = geometries.get(&id).copied().unwrap_or_default();
+
             let Some(ipc_win) = state.windows.get(&id) else {
-                let window = make_ipc_window(mapped, ws_id);
+                let window = make_ipc_window(mapped, ws_id, geometry);
                 events.push(Event::WindowOpenedOrChanged { window });
                 return;
             };
@@ -640,7 +1070,7 @@ impl State {
             });
 
             if changed {
-                let window = make_ipc_window(mapped, ws_id);
+                let window = make_ipc_window(mapped, ws_id, geometry);
                 events.push(Event::WindowOpenedOrChanged { window });
                 return;
             }
@@ -653,7 +1083,27 @@ impl State {
             if urgent != ipc_win.is_urgent {
                 events.push(Event::WindowUrgencyChanged { id, urgent })
             }
-        });
+
+            let (workspace_geometry, output_geometry) = geometry;
+            if ipc_win.workspace_geometry != workspace_geometry
+                || ipc_win.output_geometry != output_geometry
+            {
+                if geometry_due {
+                    geometry_sent = true;
+                    events.push(Event::WindowGeometryChanged {
+                        id,
+                        workspace_geometry,
+                        output_geometry,
+                    });
+                } else {
+                    geometry_pending = true;
+                }
+            }
+        };
+        layout.with_windows(|mapped, _, ws_id| check_window(mapped, ws_id));
+        // Windows hidden by window swallowing are still open.
+        self.niri
+            .with_swallowed_windows(|mapped, _, ws_id| check_window(mapped, ws_id));
 
         // Check for closed windows.
         let mut ipc_focused_id = None;
@@ -677,6 +1127,29 @@ impl State {
             state.apply(event.clone());
             server.send_event(event);
         }
+
+        if geometry_sent {
+            server.last_geometry_event.set(Some(now));
+        }
+
+        // Make sure that throttled geometry changes are sent even if nothing else happens, for
+        // example when an animation ends right after the last geometry event.
+        if geometry_pending && server.geometry_timer.get().is_none() {
+            let last = server.last_geometry_event.get().unwrap_or(now);
+            let delay = (last + GEOMETRY_EVENT_THROTTLE).saturating_sub(now);
+            let token = self
+                .niri
+                .event_loop
+                .insert_source(Timer::from_duration(delay), |_, _, state| {
+                    if let Some(server) = &state.niri.ipc_server {
+                        server.geometry_timer.set(None);
+                    }
+                    state.ipc_refresh_windows();
+                    TimeoutAction::Drop
+                })
+                .unwrap();
+            server.geometry_timer.set(Some(token));
+        }
     }
 
     pub fn ipc_refresh_overview(&mut self) {
@@ -696,4 +1169,22 @@ impl State {
         state.apply(event.clone());
         server.send_event(event);
     }
+
+    pub fn ipc_refresh_session(&mut self) {
+        let Some(server) = &self.niri.ipc_server else {
+            return;
+        };
+
+        let mut state = server.event_stream_state.borrow_mut();
+        let state = &mut state.session;
+        let is_paused = self.niri.is_session_paused;
+
+        if state.is_paused == is_paused {
+            return;
+        }
+
+        let event = Event::SessionPausedOrResumed { is_paused };
+        state.apply(event.clone());
+        server.send_event(event);
+    }
 }
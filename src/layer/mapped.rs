@@ -3,25 +3,29 @@
 // Wayland概念: LayerSurface - 分层表面协议，允许客户端在桌面不同层级显示内容
 // Rust概念: 泛型 - <R: NiriRenderer> 表示接受任何实现NiriRenderer的类型
 
+use std::cell::{Ref, RefCell};
+
 use niri_config::layer_rule::LayerRule;
-use niri_config::Config;
-use smithay::backend::renderer::element::surface::{
-    render_elements_from_surface_tree, WaylandSurfaceRenderElement,
-};
+use niri_config::{BlockOutFrom, Config};
+use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::Kind;
 use smithay::desktop::{LayerSurface, PopupManager};
-use smithay::utils::{Logical, Point, Scale, Size};
+use smithay::utils::{Logical, Point, Rectangle, Scale, Size};
 use smithay::wayland::shell::wlr_layer::{ExclusiveZone, Layer};
 
 // 导入父模块的ResolvedLayerRules
 use super::ResolvedLayerRules;
 // 导入本地工具函数和类型
-use crate::animation::Clock;
+use crate::animation::{Animation, Clock};
+use crate::layout::focus_ring::{FocusRing, FocusRingRenderElement};
 use crate::layout::shadow::Shadow;
 use crate::niri_render_elements;
+use crate::render_helpers::offscreen::{OffscreenData, OffscreenRenderElement};
+use crate::render_helpers::opacity_group::OpacityGroup;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::shadow::ShadowRenderElement;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::render_helpers::surface::render_elements_from_surface_tree;
 use crate::render_helpers::{RenderTarget, SplitElements};
 use crate::utils::{baba_is_float_offset, round_logical_in_physical};
 
@@ -53,6 +57,16 @@ pub struct MappedLayer {
     // 作用: 表面阴影渲染器
     shadow: Shadow,
 
+    /// The focus ring around the surface, drawn while it has keyboard focus.
+    // 字段: focus_ring
+    // 作用: 键盘焦点所在的层表面（如启动器）周围的焦点环
+    focus_ring: FocusRing,
+
+    /// Whether the surface has keyboard focus.
+    // 字段: is_focused
+    // 作用: 焦点环只在层表面拥有键盘焦点时绘制
+    is_focused: bool,
+
     /// The view size for the layer surface's output.
     // 字段: view_size
     // 类型: Size<f64, Logical>
@@ -70,6 +84,31 @@ pub struct MappedLayer {
     // 类型: Clock
     // 作用: 动画时钟驱动
     clock: Clock,
+
+    /// Animation of the opacity after a layer rule changed it.
+    // 字段: opacity_animation
+    // 作用: 规则改变不透明度时的渐变动画
+    opacity_animation: Option<Animation>,
+
+    /// Animation of the block-out after a layer rule changed it, along with the previous value.
+    // 字段: block_out_animation
+    // 作用: 规则改变屏蔽状态时的淡入淡出动画（0 为旧状态，1 为新状态）
+    block_out_animation: Option<(Animation, Option<BlockOutFrom>)>,
+
+    /// Config for the rule change animations.
+    // 字段: animation_config
+    // 作用: 规则变化动画的参数
+    animation_config: niri_config::Animation,
+
+    /// Fades the surface together with its focus ring and shadow.
+    // 字段: opacity_group
+    // 作用: 在工作区之间淡入淡出时，把表面、焦点环和阴影作为整体渲染
+    opacity_group: OpacityGroup,
+
+    /// Data of the last render through the opacity group.
+    // 字段: offscreen_data
+    // 作用: 记录离屏渲染的元素状态，用于跟踪表面的主输出
+    offscreen_data: RefCell<Option<OffscreenData>>,
 }
 
 // 宏: niri_render_elements!
@@ -80,6 +119,8 @@ niri_render_elements! {
         Wayland = WaylandSurfaceRenderElement<R>,
         SolidColor = SolidColorRenderElement,
         Shadow = ShadowRenderElement,
+        FocusRing = FocusRingRenderElement,
+        Offscreen = OffscreenRenderElement,
     }
 }
 
@@ -108,6 +149,8 @@ impl MappedLayer {
         shadow_config.on = false;
         // 合并规则中的阴影覆盖
         let shadow_config = rules.shadow.resolve_against(shadow_config);
+        // 焦点环同样需要显式启用
+        let focus_ring_config = focus_ring_config(&rules, config);
 
         // 创建MappedLayer实例
         Self {
@@ -119,7 +162,14 @@ impl MappedLayer {
             scale,
             // 使用配置创建阴影渲染器
             shadow: Shadow::new(shadow_config),
+            focus_ring: FocusRing::new(focus_ring_config),
+            is_focused: false,
             clock,
+            opacity_animation: None,
+            block_out_animation: None,
+            animation_config: config.animations.window_movement.0,
+            opacity_group: OpacityGroup::default(),
+            offscreen_data: RefCell::new(None),
         }
     }
 
@@ -131,12 +181,17 @@ impl MappedLayer {
         shadow_config.on = false;
         let shadow_config = self.rules.shadow.resolve_against(shadow_config);
         self.shadow.update_config(shadow_config);
+        self.focus_ring
+            .update_config(focus_ring_config(&self.rules, config));
+
+        self.animation_config = config.animations.window_movement.0;
     }
 
     // 函数: update_shaders
     // 作用: 更新着色器（例如分辨率变化时）
     pub fn update_shaders(&mut self) {
         self.shadow.update_shaders();
+        self.focus_ring.update_shaders();
     }
 
     // 函数: update_sizes
@@ -148,11 +203,16 @@ impl MappedLayer {
 
     // 函数: update_render_elements
     // 作用: 更新渲染元素（尺寸变化时调用）
-    // 参数: size - 新的表面尺寸
-    pub fn update_render_elements(&mut self, size: Size<f64, Logical>) {
+    // 参数:
+    //   geo - 表面在输出上的几何区域
+    //   is_focused - 表面是否拥有键盘焦点
+    pub fn update_render_elements(&mut self, geo: Rectangle<f64, Logical>, is_focused: bool) {
+        self.is_focused = is_focused;
+
         // 将逻辑尺寸四舍五入到物理像素
         // Rust概念: 方法链 - 连续调用多个方法
-        let size = size
+        let size = geo
+            .size
             .to_physical_precise_round(self.scale)
             .to_logical(self.scale);
 
@@ -161,16 +221,37 @@ impl MappedLayer {
 
         // 获取圆角半径配置
         let radius = self.rules.geometry_corner_radius.unwrap_or_default();
-        // 更新阴影渲染元素
-        // FIXME: 基于键盘焦点设置is_active?
+        // 更新阴影渲染元素（与窗口一样按键盘焦点区分激活状态）
         self.shadow
-            .update_render_elements(size, true, radius, self.scale, 1.);
+            .update_render_elements(size, is_focused, radius, self.scale, 1.);
+
+        // 更新焦点环渲染元素（渐变相对于输出视图）
+        let view_rect = Rectangle::new(-geo.loc, self.view_size);
+        let radius = radius.expanded_by(self.focus_ring.width() as f32);
+        let alpha = self.opacity();
+        self.focus_ring.update_render_elements(
+            size, true, true, false, view_rect, radius, self.scale, alpha,
+        );
+
+        // 清除已完成的规则变化动画
+        if self.opacity_animation.as_ref().is_some_and(|a| a.is_done()) {
+            self.opacity_animation = None;
+        }
+        if self
+            .block_out_animation
+            .as_ref()
+            .is_some_and(|(a, _)| a.is_done())
+        {
+            self.block_out_animation = None;
+        }
     }
 
     // 函数: are_animations_ongoing
     // 作用: 检查是否有动画正在进行
     pub fn are_animations_ongoing(&self) -> bool {
         self.rules.baba_is_float // "baba is float"动画状态
+            || self.opacity_animation.is_some()
+            || self.block_out_animation.is_some()
     }
 
     // 函数: surface
@@ -179,6 +260,12 @@ impl MappedLayer {
         &self.surface
     }
 
+    // 函数: offscreen_data
+    // 作用: 返回上次通过不透明度组渲染时的离屏数据
+    pub fn offscreen_data(&self) -> Ref<Option<OffscreenData>> {
+        self.offscreen_data.borrow()
+    }
+
     // 函数: rules
     // 作用: 获取渲染规则引用
     pub fn rules(&self) -> &ResolvedLayerRules {
@@ -198,11 +285,62 @@ impl MappedLayer {
             return false;
         }
 
+        // 不透明度改变时从当前值渐变到新值
+        if new_rules.opacity != self.rules.opacity {
+            let from = self.opacity();
+            let to = new_rules.opacity.unwrap_or(1.).clamp(0., 1.);
+            self.opacity_animation = Some(Animation::new(
+                self.clock.clone(),
+                f64::from(from),
+                f64::from(to),
+                0.,
+                self.animation_config,
+            ));
+        }
+
+        // 屏蔽状态改变时在表面和纯色块之间淡入淡出（开始屏蔽时立即生效，见 block_out_amount）
+        if new_rules.block_out_from != self.rules.block_out_from {
+            self.block_out_animation = Some((
+                Animation::new(self.clock.clone(), 0., 1., 0., self.animation_config),
+                self.rules.block_out_from,
+            ));
+        }
+
         // 更新规则
         self.rules = new_rules;
         true
     }
 
+    // 函数: opacity
+    // 作用: 获取当前（可能正在动画中的）不透明度
+    fn opacity(&self) -> f32 {
+        match &self.opacity_animation {
+            Some(anim) => anim.clamped_value().clamp(0., 1.) as f32,
+            None => self.rules.opacity.unwrap_or(1.).clamp(0., 1.),
+        }
+    }
+
+    // 函数: block_out_amount
+    // 作用: 计算对该渲染目标的屏蔽程度（0 为正常显示，1 为完全屏蔽）
+    // 注意: 开始屏蔽时不做淡入，否则动画期间截屏或录屏会拍到半透明的真实内容；
+    //       只有解除屏蔽时才逐渐显示内容
+    fn block_out_amount(&self, target: RenderTarget) -> f32 {
+        let blocked = target.should_block_out(self.rules.block_out_from);
+        if blocked {
+            return 1.;
+        }
+
+        if let Some((anim, prev)) = &self.block_out_animation {
+            let was_blocked = target.should_block_out(*prev);
+            if was_blocked {
+                let progress = anim.clamped_value().clamp(0., 1.) as f32;
+                return 1. - progress;
+            }
+        }
+
+        0.
+    }
+
     // 函数: place_within_backdrop
     // 作用: 判断是否应放置在概览背景中
     pub fn place_within_backdrop(&self) -> bool {
@@ -245,20 +383,22 @@ impl MappedLayer {
     // 参数:
     //   renderer - 渲染器实例
     //   location - 渲染位置
+    //   fade - 额外的不透明度（用于背景层表面在工作区之间淡入淡出）
     //   target - 渲染目标类型
     // 返回: SplitElements - 分类的渲染元素集合
     // 流程图:
     //   [开始]
     //   -> 计算浮动偏移
-    //   -> 检查是否需要阻止渲染:
-    //        |-> 是: 渲染纯色块
-    //        |-> 否: 渲染实际表面和弹出窗口
-    //   -> 添加阴影
+    //   -> 检查是否在淡入淡出:
+    //        |-> 否: 直接渲染表面、焦点环和阴影
+    //        |-> 是: 把它们作为一个不透明度组渲染，再统一应用 fade
+    //   -> 单独渲染弹出窗口
     //   -> 返回渲染元素集合
     pub fn render<R: NiriRenderer>(
         &self,
         renderer: &mut R,
         location: Point<f64, Logical>,
+        fade: f32,
         target: RenderTarget,
     ) -> SplitElements<LayerSurfaceRenderElement<R>> {
         // 创建空的渲染元素集合
@@ -266,13 +406,59 @@ impl MappedLayer {
 
         // 创建缩放对象
         let scale = Scale::from(self.scale);
-        // 获取不透明度（限制在0-1范围内）
-        let alpha = self.rules.opacity.unwrap_or(1.).clamp(0., 1.);
         // 应用浮动偏移
         let location = location + self.bob_offset();
 
-        // 检查是否需要阻止渲染
-        if target.should_block_out(self.rules.block_out_from) {
+        // 弹出窗口不参与分组，直接乘上 fade
+        rv.popups = self.render_popups(renderer, location, self.opacity() * fade, target);
+
+        self.offscreen_data.replace(None);
+
+        if fade < 1. {
+            // 淡入淡出时，逐个元素应用 fade 会让阴影透过半透明的表面，使画面过暗。
+            // 因此先以完全不透明的方式离屏渲染，再对结果整体应用 fade。
+            let location = location.to_physical_precise_round(scale).to_logical(scale);
+            let gles_renderer = renderer.as_gles_renderer();
+            let elements = self.render_normal(gles_renderer, Point::from((0., 0.)), 1., target);
+
+            match self
+                .opacity_group
+                .render(gles_renderer, scale, location, fade, &elements)
+            {
+                Ok((elem, data)) => {
+                    self.offscreen_data.replace(Some(data));
+                    rv.normal.push(elem.into());
+                    return rv;
+                }
+                Err(err) => {
+                    warn!("error rendering layer surface to offscreen: {err:?}");
+                }
+            }
+        }
+
+        rv.normal = self.render_normal(renderer, location, fade, target);
+        rv
+    }
+
+    // 函数: render_normal
+    // 作用: 渲染层表面本身、屏蔽纯色块、焦点环和阴影（不含弹出窗口）
+    fn render_normal<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        location: Point<f64, Logical>,
+        fade: f32,
+        target: RenderTarget,
+    ) -> Vec<LayerSurfaceRenderElement<R>> {
+        let mut rv = Vec::new();
+
+        // 创建缩放对象
+        let scale = Scale::from(self.scale);
+        // 获取不透明度（限制在0-1范围内）
+        let alpha = self.opacity() * fade;
+
+        // 检查是否需要阻止渲染（规则变化时可能处于淡入淡出中）
+        let block_out = self.block_out_amount(target);
+        if block_out > 0. {
             // 四舍五入位置到物理像素
             let location = location.to_physical_precise_round(scale).to_logical(scale);
 
@@ -281,50 +467,119 @@ impl MappedLayer {
             let elem = SolidColorRenderElement::from_buffer(
                 &self.block_out_buffer,
                 location,
-                alpha,
+                alpha * block_out,
                 Kind::Unspecified,
             );
-            // 添加到普通元素列表
-            rv.normal.push(elem.into());
-        } else {
-            // 计算表面位置
-            let buf_pos = location;
-
-            // 获取主表面
-            let surface = self.surface.wl_surface();
-            // 处理所有弹出窗口
-            // Wayland概念: Popup - 临时弹出窗口
-            for (popup, popup_offset) in PopupManager::popups_for_surface(surface) {
-                // 计算弹出窗口偏移
-                let offset = popup_offset - popup.geometry().loc;
-
-                // 渲染弹出窗口表面树
-                rv.popups.extend(render_elements_from_surface_tree(
-                    renderer,
-                    popup.wl_surface(),
-                    (buf_pos + offset.to_f64()).to_physical_precise_round(scale),
-                    scale,
-                    alpha,
-                    Kind::Unspecified,
-                ));
-            }
+            rv.push(elem.into());
+        }
 
-            // 渲染主表面树
-            rv.normal = render_elements_from_surface_tree(
+        // 完全屏蔽时不渲染表面
+        if block_out < 1. {
+            // 渲染主表面树（位于屏蔽纯色块之下）
+            rv.extend(render_elements_from_surface_tree(
                 renderer,
-                surface,
-                buf_pos.to_physical_precise_round(scale),
+                self.surface.wl_surface(),
+                location.to_physical_precise_round(scale),
                 scale,
                 alpha,
                 Kind::Unspecified,
-            );
+            ));
         }
 
-        // 渲染阴影
         let location = location.to_physical_precise_round(scale).to_logical(scale);
-        rv.normal
-            .extend(self.shadow.render(renderer, location).map(Into::into));
+
+        // 渲染焦点环（位于表面之下）
+        if self.is_focused && block_out < 1. {
+            rv.extend(self.focus_ring.render(renderer, location).map(Into::into));
+        }
+
+        // 渲染阴影
+        rv.extend(
+            self.shadow
+                .render(renderer, location)
+                .map(move |elem| elem.with_alpha(fade).into()),
+        );
 
         rv
     }
-}
\ No newline at end of file
+
+    // 函数: render_popups
+    // 作用: 渲染层表面的所有弹出窗口
+    fn render_popups<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        location: Point<f64, Logical>,
+        alpha: f32,
+        target: RenderTarget,
+    ) -> Vec<LayerSurfaceRenderElement<R>> {
+        let mut rv = Vec::new();
+
+        // 弹出窗口与表面使用相同的屏蔽程度，完全屏蔽时不渲染
+        let block_out = self.block_out_amount(target);
+        if block_out >= 1. {
+            return rv;
+        }
+        let alpha = alpha * (1. - block_out);
+
+        let scale = Scale::from(self.scale);
+
+        // 获取主表面
+        let surface = self.surface.wl_surface();
+        // 处理所有弹出窗口
+        // Wayland概念: Popup - 临时弹出窗口
+        for (popup, popup_offset) in PopupManager::popups_for_surface(surface) {
+            // 计算弹出窗口偏移
+            let offset = popup_offset - popup.geometry().loc;
+
+            // 渲染弹出窗口表面树
+            rv.extend(render_elements_from_surface_tree(
+                renderer,
+                popup.wl_surface(),
+                (location + offset.to_f64()).to_physical_precise_round(scale),
+                scale,
+                alpha,
+                Kind::Unspecified,
+            ));
+        }
+
+        rv
+    }
+}
+
+// 函数: focus_ring_config
+// 作用: 计算层表面的焦点环配置（默认关闭，由层规则启用）
+fn focus_ring_config(rules: &ResolvedLayerRules, config: &Config) -> niri_config::FocusRing {
+    let mut focus_ring_config = config.layout.focus_ring;
+    focus_ring_config.off = true;
+    rules
+        .focus_ring
+        .resolve_against(focus_ring_config.into())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use niri_config::FloatOrInt;
+
+    use super::*;
+
+    #[test]
+    fn focus_ring_is_opt_in() {
+        let config = Config::default();
+        assert!(!config.layout.focus_ring.off);
+
+        // Layer surfaces don't get the layout focus ring unless a layer rule enables it.
+        let mut rules = ResolvedLayerRules::empty();
+        assert!(focus_ring_config(&rules, &config).off);
+
+        rules.focus_ring.on = true;
+        rules.focus_ring.width = Some(FloatOrInt(8.));
+        let focus_ring = focus_ring_config(&rules, &config);
+        assert!(!focus_ring.off);
+        assert_eq!(focus_ring.width, FloatOrInt(8.));
+        assert_eq!(
+            focus_ring.active_color,
+            config.layout.focus_ring.active_color
+        );
+    }
+}
@@ -4,7 +4,7 @@
 // Rust概念: 模块系统 - 通过mod声明子模块，use导入其他模块的公开项
 
 use niri_config::layer_rule::{LayerRule, Match};
-use niri_config::{BlockOutFrom, CornerRadius, ShadowRule};
+use niri_config::{BlockOutFrom, BorderRule, CornerRadius, ShadowRule};
 use smithay::desktop::LayerSurface;
 
 // 子模块声明: mapped
@@ -33,6 +33,10 @@ pub struct ResolvedLayerRules {
     // 中文翻译: 阴影覆盖设置
     pub shadow: ShadowRule,
 
+    /// Focus ring overrides.
+    // 中文翻译: 焦点环覆盖设置（层表面的焦点环需要显式启用）
+    pub focus_ring: BorderRule,
+
     /// Corner radius to assume this layer surface has.
     // 中文翻译: 假定此层表面具有的圆角半径
     pub geometry_corner_radius: Option<CornerRadius>,
@@ -41,6 +45,10 @@ pub struct ResolvedLayerRules {
     // 中文翻译: 是否将此层表面放置在概览背景中
     pub place_within_backdrop: bool,
 
+    /// Workspace that this backdrop layer surface belongs to.
+    // 中文翻译: 此背景层表面所属的工作区
+    pub backdrop_workspace: Option<String>,
+
     /// Whether to bob this window up and down.
     // 中文翻译: 是否使此窗口上下浮动
     pub baba_is_float: bool,
@@ -65,8 +73,20 @@ impl ResolvedLayerRules {
                 color: None,
                 inactive_color: None,
             },
+            focus_ring: BorderRule {
+                off: false,
+                on: false,
+                width: None,
+                active_color: None,
+                inactive_color: None,
+                urgent_color: None,
+                active_gradient: None,
+                inactive_gradient: None,
+                urgent_gradient: None,
+            },
             geometry_corner_radius: None,
             place_within_backdrop: false,
+            backdrop_workspace: None,
             baba_is_float: false,
         }
     }
@@ -133,6 +153,9 @@ impl ResolvedLayerRules {
             if let Some(x) = rule.place_within_backdrop {
                 resolved.place_within_backdrop = x;
             }
+            if let Some(x) = &rule.backdrop_workspace {
+                resolved.backdrop_workspace = Some(x.clone());
+            }
             if let Some(x) = rule.baba_is_float {
                 resolved.baba_is_float = x;
             }
@@ -140,6 +163,7 @@ impl ResolvedLayerRules {
             // 合并阴影规则
             // Wayland概念: 阴影 - 控制窗口阴影的视觉表现
             resolved.shadow.merge_with(&rule.shadow);
+            resolved.focus_ring.merge_with(&rule.focus_ring);
         }
 
         resolved
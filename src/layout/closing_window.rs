@@ -46,6 +46,11 @@ pub struct ClosingWindow {
     /// How much the blocked-out texture should be offset.
     blocked_out_buffer_offset: Point<f64, Logical>,
 
+    /// Point in the view that the window shrinks toward.
+    ///
+    /// Set when the window moves away, for example to another workspace, rather than closes.
+    shrink_to: Option<Point<f64, Logical>>,
+
     /// The closing animation.
     anim_state: AnimationState,
 
@@ -84,12 +89,14 @@ impl AnimationState {
 }
 
 impl ClosingWindow {
+    #[allow(clippy::too_many_arguments)]
     pub fn new<E: RenderElement<GlesRenderer>>(
         renderer: &mut GlesRenderer,
         snapshot: RenderSnapshot<E, E>,
         scale: Scale<f64>,
         geo_size: Size<f64, Logical>,
         pos: Point<f64, Logical>,
+        shrink_to: Option<Point<f64, Logical>>,
         blocker: TransactionBlocker,
         anim: Animation,
     ) -> anyhow::Result<Self> {
@@ -132,6 +139,7 @@ impl ClosingWindow {
             pos,
             buffer_offset,
             blocked_out_buffer_offset,
+            shrink_to,
             anim_state: AnimationState::new(blocker, anim),
             random_seed: fastrand::f32(),
         })
@@ -199,6 +207,39 @@ impl ClosingWindow {
         let progress = anim.value();
         let clamped_progress = anim.clamped_value().clamp(0., 1.);
 
+        let mut location = self.pos + offset;
+        location.x -= view_rect.loc.x;
+
+        if let Some(shrink_to) = self.shrink_to {
+            // The custom close shader is meant for closing, so moving windows always use the
+            // texture.
+            let elem = TextureRenderElement::from_texture_buffer(
+                buffer.clone(),
+                Point::from((0., 0.)),
+                1. - clamped_progress as f32,
+                None,
+                None,
+                Kind::Unspecified,
+            );
+
+            let elem = PrimaryGpuTextureRenderElement(elem);
+
+            // Scale around the target point, so that the window collapses into it.
+            let elem = RescaleRenderElement::from_element(
+                elem,
+                (shrink_to - location).to_physical_precise_round(scale),
+                (1. - clamped_progress).max(0.),
+            );
+
+            let elem = RelocateRenderElement::from_element(
+                elem,
+                location.to_physical_precise_round(scale),
+                Relocate::Relative,
+            );
+
+            return elem.into();
+        }
+
         if Shaders::get(renderer).program(ProgramType::Close).is_some() {
             let area_loc = Vec2::new(view_rect.loc.x as f32, view_rect.loc.y as f32);
             let area_size = Vec2::new(view_rect.size.w as f32, view_rect.size.h as f32);
@@ -262,8 +303,6 @@ impl ClosingWindow {
             ((1. - clamped_progress) / 5. + 0.8).max(0.),
         );
 
-        let mut location = self.pos + offset;
-        location.x -= view_rect.loc.x;
         let elem = RelocateRenderElement::from_element(
             elem,
             location.to_physical_precise_round(scale),
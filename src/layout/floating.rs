@@ -9,6 +9,7 @@ use smithay::utils::{Logical, Point, Rectangle, Scale, Serial, Size};
 
 use super::closing_window::{ClosingWindow, ClosingWindowRenderElement};
 use super::scrolling::ColumnWidth;
+use super::snapshot::WindowSnapshot;
 use super::tile::{Tile, TileRenderElement, TileRenderSnapshot};
 use super::workspace::{InteractiveResize, ResolvedSize};
 use super::{
@@ -255,6 +256,14 @@ impl<W: LayoutElement> FloatingSpace<W> {
         });
     }
 
+    /// Drops the textures rendered for animations, for example after a GPU reset.
+    pub fn drop_render_snapshots(&mut self) {
+        self.closing_windows.clear();
+        for tile in self.tiles_mut() {
+            tile.drop_render_snapshots();
+        }
+    }
+
     pub fn are_animations_ongoing(&self) -> bool {
         self.tiles.iter().any(Tile::are_animations_ongoing) || !self.closing_windows.is_empty()
     }
@@ -283,6 +292,21 @@ impl<W: LayoutElement> FloatingSpace<W> {
         self.tiles.iter_mut()
     }
 
+    pub fn snapshot_windows(&self, app_id: &impl Fn(&W) -> Option<String>) -> Vec<WindowSnapshot> {
+        zip(&self.tiles, &self.data)
+            .map(|(tile, data)| {
+                let window = tile.window();
+                let size = window.size();
+                let pos = data.logical_pos - self.working_area.loc;
+                WindowSnapshot {
+                    app_id: app_id(window),
+                    size: (size.w, size.h),
+                    pos: Some((pos.x, pos.y)),
+                }
+            })
+            .collect()
+    }
+
     pub fn tiles_with_offsets(&self) -> impl Iterator<Item = (&Tile<W>, Point<f64, Logical>)> + '_ {
         let offsets = self.data.iter().map(|d| d.logical_pos);
         zip(&self.tiles, offsets)
@@ -526,6 +550,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
         &mut self,
         renderer: &mut GlesRenderer,
         id: &W::Id,
+        shrink_to: Option<Point<f64, Logical>>,
         blocker: TransactionBlocker,
     ) {
         let (tile, tile_pos) = self
@@ -539,7 +564,9 @@ impl<W: LayoutElement> FloatingSpace<W> {
 
         let tile_size = tile.tile_size();
 
-        self.start_close_animation_for_tile(renderer, snapshot, tile_size, tile_pos, blocker);
+        self.start_close_animation_for_tile(
+            renderer, snapshot, tile_size, tile_pos, shrink_to, blocker,
+        );
     }
 
     pub fn activate_window_without_raising(&mut self, id: &W::Id) -> bool {
@@ -578,6 +605,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
         snapshot: TileRenderSnapshot,
         tile_size: Size<f64, Logical>,
         tile_pos: Point<f64, Logical>,
+        shrink_to: Option<Point<f64, Logical>>,
         blocker: TransactionBlocker,
     ) {
         let anim = Animation::new(
@@ -596,7 +624,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
 
         let scale = Scale::from(self.scale);
         let res = ClosingWindow::new(
-            renderer, snapshot, scale, tile_size, tile_pos, blocker, anim,
+            renderer, snapshot, scale, tile_size, tile_pos, shrink_to, blocker, anim,
         );
         match res {
             Ok(closing) => {
@@ -697,6 +725,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
             return;
         };
         let idx = self.idx_of(id).unwrap();
+        let aspect_ratio = self.locked_aspect_ratio(idx);
 
         let tile = &mut self.tiles[idx];
         tile.floating_preset_width_idx = None;
@@ -730,10 +759,21 @@ impl<W: LayoutElement> FloatingSpace<W> {
         let min_size = win.min_size();
         let max_size = win.max_size();
 
-        let win_width = ensure_min_max_size(win_width, min_size.w, max_size.w);
+        let (win_width, win_height) = if let Some(ratio) = aspect_ratio {
+            keep_aspect_ratio(
+                win_width,
+                ratio,
+                (min_size.w, max_size.w),
+                (min_size.h, max_size.h),
+            )
+        } else {
+            let win_width = ensure_min_max_size(win_width, min_size.w, max_size.w);
+
+            let win_height = win.expected_size().unwrap_or_default().h;
+            let win_height = ensure_min_max_size(win_height, min_size.h, max_size.h);
 
-        let win_height = win.expected_size().unwrap_or_default().h;
-        let win_height = ensure_min_max_size(win_height, min_size.h, max_size.h);
+            (win_width, win_height)
+        };
 
         let win_size = Size::from((win_width, win_height));
         win.request_size_once(win_size, animate);
@@ -744,6 +784,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
             return;
         };
         let idx = self.idx_of(id).unwrap();
+        let aspect_ratio = self.locked_aspect_ratio(idx);
 
         let tile = &mut self.tiles[idx];
         tile.floating_preset_width_idx = None;
@@ -777,15 +818,37 @@ impl<W: LayoutElement> FloatingSpace<W> {
         let min_size = win.min_size();
         let max_size = win.max_size();
 
-        let win_height = ensure_min_max_size(win_height, min_size.h, max_size.h);
+        let (win_width, win_height) = if let Some(ratio) = aspect_ratio {
+            let (win_height, win_width) = keep_aspect_ratio(
+                win_height,
+                1. / ratio,
+                (min_size.h, max_size.h),
+                (min_size.w, max_size.w),
+            );
+            (win_width, win_height)
+        } else {
+            let win_height = ensure_min_max_size(win_height, min_size.h, max_size.h);
 
-        let win_width = win.expected_size().unwrap_or_default().w;
-        let win_width = ensure_min_max_size(win_width, min_size.w, max_size.w);
+            let win_width = win.expected_size().unwrap_or_default().w;
+            let win_width = ensure_min_max_size(win_width, min_size.w, max_size.w);
+
+            (win_width, win_height)
+        };
 
         let win_size = Size::from((win_width, win_height));
         win.request_size_once(win_size, animate);
     }
 
+    /// Returns the height to width ratio to keep while resizing the window, if it's locked.
+    ///
+    /// The ratio is stored when the lock is set and stays the same for every later resize, so
+    /// rounding errors don't accumulate, and a client picking a different size doesn't change it.
+    fn locked_aspect_ratio(&mut self, idx: usize) -> Option<f64> {
+        let tile = &mut self.tiles[idx];
+        update_aspect_ratio(tile);
+        tile.floating_aspect_ratio
+    }
+
     fn focus_directional(
         &mut self,
         distance: impl Fn(Point<f64, Logical>, Point<f64, Logical>) -> f64,
@@ -1051,24 +1114,37 @@ impl<W: LayoutElement> FloatingSpace<W> {
         }
 
         let original_window_size = resize.original_window_size;
-        let edges = resize.data.edges;
+        let mut edges = resize.data.edges;
 
-        if edges.intersects(ResizeEdge::LEFT_RIGHT) {
-            let mut dx = delta.x;
-            if edges.contains(ResizeEdge::LEFT) {
-                dx = -dx;
-            };
+        let mut dx = delta.x;
+        if edges.contains(ResizeEdge::LEFT) {
+            dx = -dx;
+        };
+
+        let mut dy = delta.y;
+        if edges.contains(ResizeEdge::TOP) {
+            dy = -dy;
+        };
+
+        // With a locked aspect ratio, a corner resize follows whichever edge moved more, since
+        // the other dimension is derived from it anyway.
+        if edges.intersects(ResizeEdge::LEFT_RIGHT) && edges.intersects(ResizeEdge::TOP_BOTTOM) {
+            let idx = self.idx_of(window).unwrap();
+            if self.locked_aspect_ratio(idx).is_some() {
+                if (dx / original_window_size.w).abs() >= (dy / original_window_size.h).abs() {
+                    edges.remove(ResizeEdge::TOP_BOTTOM);
+                } else {
+                    edges.remove(ResizeEdge::LEFT_RIGHT);
+                }
+            }
+        }
 
+        if edges.intersects(ResizeEdge::LEFT_RIGHT) {
             let window_width = (original_window_size.w + dx).round() as i32;
             self.set_window_width(Some(window), SizeChange::SetFixed(window_width), false);
         }
 
         if edges.intersects(ResizeEdge::TOP_BOTTOM) {
-            let mut dy = delta.y;
-            if edges.contains(ResizeEdge::TOP) {
-                dy = -dy;
-            };
-
             let window_height = (original_window_size.h + dy).round() as i32;
             self.set_window_height(Some(window), SizeChange::SetFixed(window_height), false);
         }
@@ -1093,6 +1169,8 @@ impl<W: LayoutElement> FloatingSpace<W> {
     pub fn refresh(&mut self, is_active: bool) {
         let active = self.active_window_id.clone();
         for tile in &mut self.tiles {
+            update_aspect_ratio(tile);
+
             let win = tile.window_mut();
 
             win.set_active_in_column(true);
@@ -1162,7 +1240,9 @@ impl<W: LayoutElement> FloatingSpace<W> {
 
         let diff = prev_pos - new_pos;
         if diff.x * diff.x + diff.y * diff.y > ANIMATION_THRESHOLD_SQ {
-            tile.animate_move_from(prev_pos - new_pos);
+            let config = self.options.animations.floating_window_movement.0;
+            tile.animate_move_x_from_with_config(diff.x, config);
+            tile.animate_move_y_from_with_config(diff.y, config);
         }
     }
 
@@ -1206,7 +1286,10 @@ impl<W: LayoutElement> FloatingSpace<W> {
                 let size = tile.tile_size();
                 let area = self.working_area;
 
-                let mut pos = Point::from((pos.x.0, pos.y.0));
+                let px_per_mm = self.options.px_per_mm;
+                let x = pos.x.to_logical(px_per_mm, area.size.w);
+                let y = pos.y.to_logical(px_per_mm, area.size.h);
+                let mut pos = Point::from((x, y));
                 if relative_to == RelativeTo::TopRight
                     || relative_to == RelativeTo::BottomRight
                     || relative_to == RelativeTo::Right
@@ -1311,6 +1394,44 @@ impl<W: LayoutElement> FloatingSpace<W> {
     }
 }
 
+/// Stores the aspect ratio of a tile whose window just got its aspect ratio locked, or clears it
+/// when the lock is removed.
+fn update_aspect_ratio<W: LayoutElement>(tile: &mut Tile<W>) {
+    let win = tile.window();
+    if !win.keeps_aspect_ratio() {
+        tile.floating_aspect_ratio = None;
+        return;
+    }
+
+    if tile.floating_aspect_ratio.is_none() {
+        let size = win.expected_size().unwrap_or_else(|| win.size()).to_f64();
+        tile.floating_aspect_ratio = (size.w > 0. && size.h > 0.).then(|| size.h / size.w);
+    }
+}
+
+/// Computes the window size for a new `main` dimension, keeping `other = main * ratio`.
+///
+/// Returns `(main, other)`. The min and max sizes take priority over the ratio when they can't
+/// be satisfied together.
+fn keep_aspect_ratio(
+    main: i32,
+    ratio: f64,
+    (min_main, max_main): (i32, i32),
+    (min_other, max_other): (i32, i32),
+) -> (i32, i32) {
+    let mut main = ensure_min_max_size(main, min_main, max_main);
+    let mut other = (f64::from(main) * ratio).round().max(1.) as i32;
+
+    let clamped = ensure_min_max_size(other, min_other, max_other);
+    if clamped != other {
+        other = clamped;
+        main = (f64::from(other) / ratio).round().max(1.) as i32;
+        main = ensure_min_max_size(main, min_main, max_main);
+    }
+
+    (main, other)
+}
+
 fn compute_toplevel_bounds(
     border_config: niri_config::Border,
     working_area_size: Size<f64, Logical>,
@@ -32,17 +32,16 @@
 //! don't want an unassuming workspace to end up on it.
 
 use std::cmp::min;
-use std::collections::HashMap;
 use std::mem;
 use std::rc::Rc;
 use std::time::Duration;
 
 use monitor::{InsertHint, InsertPosition, InsertWorkspace, MonitorAddWindowTarget};
 use niri_config::{
-    CenterFocusedColumn, Config, CornerRadius, FloatOrInt, PresetSize, Struts,
-    Workspace as WorkspaceConfig, WorkspaceReference,
+    CenterFocusedColumn, Config, CornerRadius, FloatOrInt, Length, PresetSize, ReservedSlot,
+    Struts, UrgencyFlash, Workspace as WorkspaceConfig, WorkspaceReference,
 };
-use niri_ipc::{ColumnDisplay, PositionChange, SizeChange};
+use niri_ipc::{ColumnDisplay, ColumnPinEdge, PositionChange, SizeChange};
 use scrolling::{Column, ColumnWidth};
 use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::utils::RescaleRenderElement;
@@ -50,6 +49,7 @@ use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
 use smithay::output::{self, Output};
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Serial, Size, Transform};
+use snapshot::{ColumnSnapshot, LayoutSnapshot, RestoreSlot, WorkspaceSnapshot};
 use tile::{Tile, TileRenderElement};
 use workspace::{WorkspaceAddWindowTarget, WorkspaceId};
 
@@ -69,8 +69,8 @@ use crate::render_helpers::{BakedBuffer, RenderTarget, SplitElements};
 use crate::rubber_band::RubberBand;
 use crate::utils::transaction::{Transaction, TransactionBlocker};
 use crate::utils::{
-    ensure_min_max_size_maybe_zero, output_matches_name, output_size,
-    round_logical_in_physical_max1, ResizeEdge,
+    ensure_min_max_size_maybe_zero, logical_px_per_mm, output_matches_name, output_size,
+    round_logical_in_physical_max1, ResizeEdge, DEFAULT_PX_PER_MM,
 };
 use crate::window::ResolvedWindowRules;
 
@@ -82,6 +82,7 @@ pub mod monitor;
 pub mod opening_window;
 pub mod scrolling;
 pub mod shadow;
+pub mod snapshot;
 pub mod tab_indicator;
 pub mod tile;
 pub mod workspace;
@@ -210,8 +211,21 @@ pub trait LayoutElement {
     fn set_bounds(&self, bounds: Size<i32, Logical>);
     fn is_ignoring_opacity_window_rule(&self) -> bool;
 
+    /// Whether floating resizes should preserve the window's aspect ratio.
+    fn keeps_aspect_ratio(&self) -> bool;
+
     fn is_urgent(&self) -> bool;
 
+    /// Returns the urgency flash config and its current intensity in `0..=1` while flashing.
+    fn urgency_flash(&self) -> Option<(UrgencyFlash, f32)> {
+        None
+    }
+
+    /// Whether the element doesn't match the overview search and should be drawn dimmed.
+    fn is_filtered_out(&self) -> bool {
+        false
+    }
+
     fn configure_intent(&self) -> ConfigureIntent;
     fn send_pending_configure(&mut self);
 
@@ -289,14 +303,15 @@ pub struct Layout<W: LayoutElement> {
     /// This normally indicates that the layout has keyboard focus, but not always. E.g. when the
     /// screenshot UI is open, it keeps the layout drawing as active.
     is_active: bool,
-    /// Map from monitor name to id of its last active workspace.
+    /// Ids of the last active workspaces of disconnected monitors.
     ///
     /// This data is stored upon monitor removal and is used to restore the active workspace when
-    /// the monitor is reconnected.
+    /// the monitor is reconnected. Monitors are matched by make, model and serial when available,
+    /// so this works even if the monitor comes back on a different connector.
     ///
     /// The workspace id does not necessarily point to a valid workspace. If it doesn't, then it is
     /// simply ignored.
-    last_active_workspace_id: HashMap<String, WorkspaceId>,
+    last_active_workspace_id: Vec<(OutputId, WorkspaceId)>,
     /// Ongoing interactive move.
     interactive_move: Option<InteractiveMoveState<W>>,
     /// Ongoing drag-and-drop operation.
@@ -338,6 +353,8 @@ enum MonitorSet<W: LayoutElement> {
 pub struct Options {
     /// Padding around windows in logical pixels.
     pub gaps: f64,
+    /// Gaps in millimeters or percent, resolved into `gaps` for every output.
+    pub gaps_length: Option<Length<0, 65535>>,
     /// Extra padding around the working area in logical pixels.
     pub struts: Struts,
     pub focus_ring: niri_config::FocusRing,
@@ -358,15 +375,30 @@ pub struct Options {
     pub animations: niri_config::Animations,
     pub gestures: niri_config::Gestures,
     pub overview: niri_config::Overview,
+    /// Slots reserved on named workspaces, along with the workspace name.
+    pub reserved_slots: Vec<(String, ReservedSlot)>,
+    /// Logical pixels in one millimeter on the output.
+    pub px_per_mm: f64,
     // Debug flags.
     pub disable_resize_throttling: bool,
     pub disable_transactions: bool,
 }
 
+/// Output properties that options in millimeters or percent are resolved against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputUnits {
+    pub scale: f64,
+    /// Logical pixels in one millimeter on the output.
+    pub px_per_mm: f64,
+    /// Logical size of the output.
+    pub size: Size<f64, Logical>,
+}
+
 impl Default for Options {
     fn default() -> Self {
         Self {
             gaps: 16.,
+            gaps_length: None,
             struts: Default::default(),
             focus_ring: Default::default(),
             border: Default::default(),
@@ -386,6 +418,8 @@ impl Default for Options {
             animations: Default::default(),
             gestures: Default::default(),
             overview: Default::default(),
+            reserved_slots: Vec::new(),
+            px_per_mm: DEFAULT_PX_PER_MM,
             disable_resize_throttling: false,
             disable_transactions: false,
             preset_window_heights: vec![
@@ -511,6 +545,11 @@ pub enum AddWindowTarget<'a, W: LayoutElement> {
     Workspace(WorkspaceId),
     /// Next to this existing window.
     NextTo(&'a W::Id),
+    /// Into the slot reserved for this app ID on this workspace.
+    ReservedSlot {
+        workspace: WorkspaceId,
+        app_id: &'a str,
+    },
 }
 
 /// Type of the window hit from `window_under()`.
@@ -639,8 +678,25 @@ impl Options {
             .map(|w| w.0)
             .unwrap_or(Some(PresetSize::Proportion(0.5)));
 
+        let reserved_slots = config
+            .workspaces
+            .iter()
+            .flat_map(|ws| {
+                ws.reserved_slots
+                    .iter()
+                    .map(|slot| (ws.name.0.clone(), slot.clone()))
+            })
+            .collect();
+
+        // Gaps in millimeters or percent are resolved once the output is known.
+        let gaps_length = match layout.gaps {
+            Length::Logical(_) => None,
+            length => Some(length),
+        };
+
         Self {
-            gaps: layout.gaps.0,
+            gaps: layout.gaps.to_logical(DEFAULT_PX_PER_MM, 0.),
+            gaps_length,
             struts: layout.struts,
             focus_ring: layout.focus_ring,
             border: layout.border,
@@ -654,16 +710,25 @@ impl Options {
             preset_column_widths,
             default_column_width,
             animations: config.animations.clone(),
-            gestures: config.gestures,
+            gestures: config.gestures.clone(),
             overview: config.overview,
+            reserved_slots,
+            px_per_mm: DEFAULT_PX_PER_MM,
             disable_resize_throttling: config.debug.disable_resize_throttling,
             disable_transactions: config.debug.disable_transactions,
             preset_window_heights,
         }
     }
 
-    fn adjusted_for_scale(mut self, scale: f64) -> Self {
-        let round = |logical: f64| round_logical_in_physical_max1(scale, logical);
+    fn adjusted_for_output(mut self, units: OutputUnits) -> Self {
+        let round = |logical: f64| round_logical_in_physical_max1(units.scale, logical);
+
+        self.px_per_mm = units.px_per_mm;
+        if let Some(gaps) = self.gaps_length {
+            // Percentages are of the shorter side of the output.
+            let reference = f64::min(units.size.w, units.size.h);
+            self.gaps = gaps.to_logical(units.px_per_mm, reference);
+        }
 
         self.gaps = round(self.gaps);
         self.focus_ring.width = FloatOrInt(round(self.focus_ring.width.0));
@@ -673,6 +738,16 @@ impl Options {
     }
 }
 
+impl OutputUnits {
+    pub fn from_output(output: &Output) -> Self {
+        Self {
+            scale: output.current_scale().fractional_scale(),
+            px_per_mm: logical_px_per_mm(output),
+            size: output_size(output),
+        }
+    }
+}
+
 impl OverviewProgress {
     fn value(&self) -> f64 {
         match self {
@@ -695,7 +770,7 @@ impl<W: LayoutElement> Layout<W> {
         Self {
             monitor_set: MonitorSet::NoOutputs { workspaces: vec![] },
             is_active: true,
-            last_active_workspace_id: HashMap::new(),
+            last_active_workspace_id: Vec::new(),
             interactive_move: None,
             dnd: None,
             clock,
@@ -720,7 +795,7 @@ impl<W: LayoutElement> Layout<W> {
         Self {
             monitor_set: MonitorSet::NoOutputs { workspaces },
             is_active: true,
-            last_active_workspace_id: HashMap::new(),
+            last_active_workspace_id: Vec::new(),
             interactive_move: None,
             dnd: None,
             clock,
@@ -738,75 +813,21 @@ impl<W: LayoutElement> Layout<W> {
                 primary_idx,
                 active_monitor_idx,
             } => {
-                let primary = &mut monitors[primary_idx];
-
-                let ws_id_to_activate = self.last_active_workspace_id.remove(&output.name());
-                let mut active_workspace_idx = None;
-
-                let mut stopped_primary_ws_switch = false;
-
-                let mut workspaces = vec![];
-                for i in (0..primary.workspaces.len()).rev() {
-                    if primary.workspaces[i].original_output.matches(&output) {
-                        let ws = primary.workspaces.remove(i);
-
-                        // FIXME: this can be coded in a way that the workspace switch won't be
-                        // affected if the removed workspace is invisible. But this is good enough
-                        // for now.
-                        if primary.workspace_switch.is_some() {
-                            primary.workspace_switch = None;
-                            stopped_primary_ws_switch = true;
-                        }
-
-                        // The user could've closed a window while remaining on this workspace, on
-                        // another monitor. However, we will add an empty workspace in the end
-                        // instead.
-                        if ws.has_windows_or_name() {
-                            if Some(ws.id()) == ws_id_to_activate {
-                                active_workspace_idx = Some(workspaces.len());
-                            }
-
-                            workspaces.push(ws);
-                        }
+                let ws_id_to_activate = self.take_last_active_workspace_id(&output);
 
-                        if i <= primary.active_workspace_idx
-                            // Generally when moving the currently active workspace, we want to
-                            // fall back to the workspace above, so as not to end up on the last
-                            // empty workspace. However, with empty workspace above first, when
-                            // moving the workspace at index 1 (first non-empty), we want to stay
-                            // at index 1, so as once again not to end up on an empty workspace.
-                            //
-                            // This comes into play at compositor startup when having named
-                            // workspaces set up across multiple monitors. Without this check, the
-                            // first monitor to connect can end up with the first empty workspace
-                            // focused instead of the first named workspace.
-                            && !(self.options.empty_workspace_above_first
-                                && primary.active_workspace_idx == 1)
-                        {
-                            primary.active_workspace_idx =
-                                primary.active_workspace_idx.saturating_sub(1);
-                        }
+                // Workspaces of disconnected monitors generally end up on the primary monitor, so
+                // go through it first to keep their order.
+                let mut workspaces = monitors[primary_idx].take_workspaces_of(&output);
+                for (idx, mon) in monitors.iter_mut().enumerate() {
+                    if idx != primary_idx {
+                        workspaces.extend(mon.take_workspaces_of(&output));
                     }
                 }
 
-                // If we stopped a workspace switch, then we might need to clean up workspaces.
-                // Also if empty_workspace_above_first is set and there are only 2 workspaces left,
-                // both will be empty and one of them needs to be removed. clean_up_workspaces
-                // takes care of this.
-
-                if stopped_primary_ws_switch
-                    || (primary.options.empty_workspace_above_first
-                        && primary.workspaces.len() == 2)
-                {
-                    primary.clean_up_workspaces();
-                }
-
-                workspaces.reverse();
-
-                if let Some(idx) = &mut active_workspace_idx {
-                    *idx = workspaces.len() - *idx - 1;
-                }
-                let mut active_workspace_idx = active_workspace_idx.unwrap_or(0);
+                let mut active_workspace_idx = workspaces
+                    .iter()
+                    .position(|ws| Some(ws.id()) == ws_id_to_activate)
+                    .unwrap_or(0);
 
                 // Make sure there's always an empty workspace.
                 workspaces.push(Workspace::new(
@@ -857,7 +878,7 @@ impl<W: LayoutElement> Layout<W> {
                     active_workspace_idx += 1;
                 }
 
-                let ws_id_to_activate = self.last_active_workspace_id.remove(&output.name());
+                let ws_id_to_activate = self.take_last_active_workspace_id(&output);
 
                 for (i, workspace) in workspaces.iter_mut().enumerate() {
                     workspace.set_output(Some(output.clone()));
@@ -882,6 +903,14 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    fn take_last_active_workspace_id(&mut self, output: &Output) -> Option<WorkspaceId> {
+        let idx = self
+            .last_active_workspace_id
+            .iter()
+            .position(|(id, _)| id.matches(output))?;
+        Some(self.last_active_workspace_id.swap_remove(idx).1)
+    }
+
     pub fn remove_output(&mut self, output: &Output) {
         self.monitor_set = match mem::take(&mut self.monitor_set) {
             MonitorSet::Normal {
@@ -895,10 +924,12 @@ impl<W: LayoutElement> Layout<W> {
                     .expect("trying to remove non-existing output");
                 let monitor = monitors.remove(idx);
 
-                self.last_active_workspace_id.insert(
-                    monitor.output_name().clone(),
+                self.last_active_workspace_id
+                    .retain(|(id, _)| !id.matches(&monitor.output));
+                self.last_active_workspace_id.push((
+                    OutputId::new(&monitor.output),
                     monitor.workspaces[monitor.active_workspace_idx].id(),
-                );
+                ));
 
                 let mut workspaces = monitor.workspaces;
 
@@ -1079,6 +1110,20 @@ impl<W: LayoutElement> Layout<W> {
                             (mon_idx, MonitorAddWindowTarget::NextTo(next_to))
                         }
                     }
+                    AddWindowTarget::ReservedSlot { workspace, app_id } => {
+                        let mon_idx = monitors
+                            .iter()
+                            .position(|mon| mon.workspaces.iter().any(|ws| ws.id() == workspace))
+                            .unwrap();
+
+                        (
+                            mon_idx,
+                            MonitorAddWindowTarget::ReservedSlot {
+                                id: workspace,
+                                app_id,
+                            },
+                        )
+                    }
                 };
                 let mon = &mut monitors[mon_idx];
 
@@ -1150,6 +1195,13 @@ impl<W: LayoutElement> Layout<W> {
                             (ws_idx, WorkspaceAddWindowTarget::NextTo(next_to))
                         }
                     }
+                    AddWindowTarget::ReservedSlot { workspace, app_id } => {
+                        let ws_idx = workspaces
+                            .iter()
+                            .position(|ws| ws.id() == workspace)
+                            .unwrap();
+                        (ws_idx, WorkspaceAddWindowTarget::ReservedSlot(app_id))
+                    }
                 };
                 let ws = &mut workspaces[ws_idx];
 
@@ -1175,6 +1227,32 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Moves the tiled `window` into the slot of the tiled `swallower` on the same workspace,
+    /// taking `swallower` out of the layout.
+    ///
+    /// Returns the taken out window, or `None` if the windows aren't tiled on the same workspace.
+    pub fn swallow_window(&mut self, swallower: &W::Id, window: &W::Id) -> Option<W> {
+        let ws = self.workspaces_mut().find(|ws| ws.has_window(swallower))?;
+        ws.swallow_window(swallower, window)
+    }
+
+    /// Puts `window` into the slot of the tiled `slot`, taking `slot` out of the layout.
+    ///
+    /// Gives `window` back if `slot` isn't tiled on a workspace.
+    pub fn replace_window(&mut self, slot: &W::Id, window: W) -> Result<W, W> {
+        match self.workspaces_mut().find(|ws| ws.has_window(slot)) {
+            Some(ws) => ws.replace_window(slot, window),
+            None => Err(window),
+        }
+    }
+
+    /// Returns whether [`Layout::replace_window()`] can put another window into the slot of
+    /// `slot`.
+    pub fn can_replace_window(&self, slot: &W::Id) -> bool {
+        self.workspaces()
+            .any(|(_, _, ws)| ws.has_window(slot) && !ws.is_floating(slot))
+    }
+
     pub fn remove_window(
         &mut self,
         window: &W::Id,
@@ -1343,6 +1421,122 @@ impl<W: LayoutElement> Layout<W> {
         None
     }
 
+    /// Saves the layout of all workspaces with windows.
+    pub fn snapshot(&self, app_id: impl Fn(&W) -> Option<String>) -> LayoutSnapshot {
+        let workspaces = self
+            .workspaces()
+            .filter(|(_, _, ws)| ws.has_windows())
+            .map(|(mon, idx, ws)| WorkspaceSnapshot {
+                output: mon.map(|mon| mon.output_name().clone()),
+                idx,
+                name: ws.name().cloned(),
+                columns: ws.scrolling().snapshot_columns(&app_id),
+                floating: ws.floating().snapshot_windows(&app_id),
+            })
+            .collect();
+
+        LayoutSnapshot { workspaces }
+    }
+
+    /// Finds the workspace for a window restored into a snapshot slot.
+    ///
+    /// Named workspaces are found by name. Other workspaces are found by output and index, going
+    /// to the last workspace of the output if it has fewer workspaces now.
+    pub fn workspace_for_restore(
+        &self,
+        slot: &RestoreSlot,
+    ) -> Option<(&Monitor<W>, &Workspace<W>)> {
+        let MonitorSet::Normal { monitors, .. } = &self.monitor_set else {
+            return None;
+        };
+
+        if let Some(name) = &slot.workspace_name {
+            return monitors.iter().find_map(|mon| {
+                let ws = mon.workspaces.iter().find(|ws| {
+                    ws.name
+                        .as_ref()
+                        .is_some_and(|ws_name| ws_name.eq_ignore_ascii_case(name))
+                })?;
+                Some((mon, ws))
+            });
+        }
+
+        let mon = monitors
+            .iter()
+            .find(|mon| Some(mon.output_name()) == slot.output.as_ref())?;
+        let idx = min(slot.workspace_idx, mon.workspaces.len() - 1);
+        Some((mon, &mon.workspaces[idx]))
+    }
+
+    /// Saves columns of the active workspace for a column group.
+    ///
+    /// Saves `count` columns starting from the active one, or all columns if `count` is `None`.
+    pub fn snapshot_active_columns(
+        &self,
+        count: Option<usize>,
+        app_id: impl Fn(&W) -> Option<String>,
+    ) -> Vec<ColumnSnapshot> {
+        self.active_workspace()
+            .map(|ws| ws.scrolling().snapshot_active_columns(count, &app_id))
+            .unwrap_or_default()
+    }
+
+    /// Makes a snapshot that puts `columns` on the active workspace.
+    pub fn snapshot_for_active_workspace(
+        &self,
+        columns: Vec<ColumnSnapshot>,
+    ) -> Option<LayoutSnapshot> {
+        let MonitorSet::Normal {
+            monitors,
+            active_monitor_idx,
+            ..
+        } = &self.monitor_set
+        else {
+            return None;
+        };
+
+        let mon = &monitors[*active_monitor_idx];
+        let ws = &mon.workspaces[mon.active_workspace_idx];
+        let workspace = WorkspaceSnapshot {
+            output: Some(mon.output_name().clone()),
+            idx: mon.active_workspace_idx,
+            name: ws.name().cloned(),
+            columns,
+            floating: Vec::new(),
+        };
+
+        Some(LayoutSnapshot {
+            workspaces: vec![workspace],
+        })
+    }
+
+    /// Takes a window out of its place and adds it again at `target`.
+    ///
+    /// Returns `false` if the window isn't in the layout.
+    pub fn readd_window(
+        &mut self,
+        window: &W::Id,
+        target: AddWindowTarget<W>,
+        width: Option<PresetSize>,
+        is_full_width: bool,
+    ) -> bool {
+        let Some(removed) = self.remove_window(window, Transaction::new()) else {
+            return false;
+        };
+
+        let window = removed.tile.into_window();
+        self.add_window(
+            window,
+            target,
+            width,
+            None,
+            is_full_width,
+            false,
+            ActivateWindow::No,
+        );
+        true
+    }
+
     pub fn find_workspace_by_name(&self, workspace_name: &str) -> Option<(usize, &Workspace<W>)> {
         match &self.monitor_set {
             MonitorSet::Normal { ref monitors, .. } => {
@@ -1372,6 +1566,13 @@ impl<W: LayoutElement> Layout<W> {
         None
     }
 
+    /// Finds a workspace with a free slot reserved for this app ID.
+    pub fn workspace_with_free_reserved_slot(&self, app_id: &str) -> Option<&Workspace<W>> {
+        self.workspaces()
+            .map(|(_, _, ws)| ws)
+            .find(|ws| ws.has_free_reserved_slot(app_id))
+    }
+
     pub fn find_workspace_by_ref(
         &mut self,
         reference: WorkspaceReference,
@@ -1658,6 +1859,15 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Returns the active window of the column to the left or right of `window`.
+    ///
+    /// Returns `None` if `window` is floating or shares its column with other windows, since the
+    /// focus stays in that column when it closes.
+    pub fn column_neighbor_window(&self, window: &W::Id, direction: ScrollDirection) -> Option<&W> {
+        let (_, _, ws) = self.workspaces().find(|(_, _, ws)| ws.has_window(window))?;
+        ws.column_neighbor_window(window, direction)
+    }
+
     pub fn active_output(&self) -> Option<&Output> {
         let MonitorSet::Normal {
             monitors,
@@ -1765,6 +1975,48 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Calls `f` with every window on a workspace, its geometry within the workspace view, and its
+    /// geometry within the output if it's currently visible.
+    pub fn with_window_geometries(
+        &self,
+        mut f: impl FnMut(&W, Rectangle<f64, Logical>, Option<Rectangle<f64, Logical>>),
+    ) {
+        let mut visit = |ws: &Workspace<W>, ws_geo: Option<Rectangle<f64, Logical>>, zoom: f64| {
+            for (tile, tile_pos, visible) in ws.tiles_with_render_positions() {
+                let geo = Rectangle::new(tile_pos + tile.window_loc(), tile.window_size());
+                let output_geo = ws_geo.filter(|_| visible).map(|ws_geo| {
+                    Rectangle::new(ws_geo.loc + geo.loc.upscale(zoom), geo.size.upscale(zoom))
+                });
+                f(tile.window(), geo, output_geo);
+            }
+        };
+
+        match &self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => {
+                for mon in monitors {
+                    let zoom = mon.overview_zoom();
+                    let visible: Vec<_> = mon
+                        .workspaces_with_render_geo()
+                        .map(|(ws, geo)| (ws.id(), geo))
+                        .collect();
+
+                    for ws in &mon.workspaces {
+                        let ws_geo = visible
+                            .iter()
+                            .find(|(id, _)| *id == ws.id())
+                            .map(|(_, geo)| *geo);
+                        visit(ws, ws_geo, zoom);
+                    }
+                }
+            }
+            MonitorSet::NoOutputs { workspaces } => {
+                for ws in workspaces {
+                    visit(ws, None, 1.);
+                }
+            }
+        }
+    }
+
     pub fn with_windows_mut(&mut self, mut f: impl FnMut(&mut W, Option<&Output>)) {
         if let Some(InteractiveMoveState::Moving(move_)) = &mut self.interactive_move {
             f(move_.tile.window_mut(), Some(&move_.output));
@@ -2216,6 +2468,37 @@ impl<W: LayoutElement> Layout<W> {
         monitor.move_to_workspace(window, idx, activate);
     }
 
+    /// Starts the animation for a window about to move to another workspace on its monitor
+    /// without the view following it.
+    ///
+    /// Call right before [`Layout::move_to_workspace()`].
+    pub fn start_move_to_workspace_animation(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        window: Option<&W::Id>,
+        idx: usize,
+    ) {
+        if let Some(InteractiveMoveState::Moving(move_)) = &self.interactive_move {
+            if window.is_none() || window == Some(move_.tile.window().id()) {
+                return;
+            }
+        }
+
+        let monitor = if let Some(window) = window {
+            match &mut self.monitor_set {
+                MonitorSet::Normal { monitors, .. } => {
+                    monitors.iter_mut().find(|mon| mon.has_window(window))
+                }
+                MonitorSet::NoOutputs { .. } => None,
+            }
+        } else {
+            self.active_monitor()
+        };
+        if let Some(monitor) = monitor {
+            monitor.start_move_to_workspace_animation(renderer, window, idx);
+        }
+    }
+
     pub fn move_column_to_workspace_up(&mut self, activate: bool) {
         let Some(monitor) = self.active_monitor() else {
             return;
@@ -2251,6 +2534,31 @@ impl<W: LayoutElement> Layout<W> {
         monitor.switch_workspace_down();
     }
 
+    pub fn peek_workspace_up(&mut self) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.peek_workspace_up();
+    }
+
+    pub fn peek_workspace_down(&mut self) {
+        let Some(monitor) = self.active_monitor() else {
+            return;
+        };
+        monitor.peek_workspace_down();
+    }
+
+    pub fn end_workspace_peek(&mut self) {
+        let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set else {
+            return;
+        };
+
+        // The active monitor could've changed since the peek started.
+        for monitor in monitors {
+            monitor.end_workspace_peek();
+        }
+    }
+
     pub fn switch_workspace(&mut self, idx: usize) {
         let Some(monitor) = self.active_monitor() else {
             return;
@@ -2307,6 +2615,13 @@ impl<W: LayoutElement> Layout<W> {
         workspace.set_column_display(display);
     }
 
+    pub fn toggle_column_pin(&mut self, edge: ColumnPinEdge) {
+        let Some(workspace) = self.active_workspace_mut() else {
+            return;
+        };
+        workspace.toggle_column_pin(edge);
+    }
+
     pub fn center_column(&mut self) {
         let Some(workspace) = self.active_workspace_mut() else {
             return;
@@ -2477,8 +2792,8 @@ impl<W: LayoutElement> Layout<W> {
 
                     move_.tile.verify_invariants();
 
-                    let scale = move_.output.current_scale().fractional_scale();
-                    let options = Options::clone(&self.options).adjusted_for_scale(scale);
+                    let units = OutputUnits::from_output(&move_.output);
+                    let options = Options::clone(&self.options).adjusted_for_output(units);
                     assert_eq!(
                         &*move_.tile.options, &options,
                         "interactive moved tile options must be \
@@ -2547,7 +2862,7 @@ impl<W: LayoutElement> Layout<W> {
                     );
 
                     let options = Options::clone(&workspace.base_options)
-                        .adjusted_for_scale(workspace.scale().fractional_scale());
+                        .adjusted_for_output(workspace.output_units());
                     assert_eq!(
                         &*workspace.options, &options,
                         "workspace options must be base options adjusted for workspace scale"
@@ -2711,7 +3026,7 @@ impl<W: LayoutElement> Layout<W> {
                 );
 
                 let options = Options::clone(&workspace.base_options)
-                    .adjusted_for_scale(workspace.scale().fractional_scale());
+                    .adjusted_for_output(workspace.output_units());
                 assert_eq!(
                     &*workspace.options, &options,
                     "workspace options must be base options adjusted for workspace scale"
@@ -2770,6 +3085,20 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Drops the textures rendered for animations, for example after a GPU reset made them
+    /// invalid.
+    ///
+    /// Closing window animations and resize animations end right away.
+    pub fn drop_render_snapshots(&mut self) {
+        if let Some(InteractiveMoveState::Moving(move_)) = &mut self.interactive_move {
+            move_.tile.drop_render_snapshots();
+        }
+
+        for ws in self.workspaces_mut() {
+            ws.drop_render_snapshots();
+        }
+    }
+
     pub fn advance_animations(&mut self) {
         let _span = tracy_client::span!("Layout::advance_animations");
 
@@ -2802,6 +3131,11 @@ impl<W: LayoutElement> Layout<W> {
                 let zoom = mon.overview_zoom();
                 scrolled |= mon.dnd_scroll_gesture_scroll(pos_within_output, 1. / zoom);
 
+                // Outside the overview, a drag held at the edge switches workspaces.
+                if is_dnd && !is_overview_open {
+                    scrolled |= mon.dnd_edge_switch(pos_within_output);
+                }
+
                 if is_scrolling {
                     if let Some((ws, geo)) = mon.workspace_under(pos_within_output) {
                         let ws_id = ws.id();
@@ -3049,11 +3383,8 @@ impl<W: LayoutElement> Layout<W> {
                         .unwrap();
                     let pos_within_workspace =
                         (move_.pointer_pos_within_output - geo.loc).downscale(zoom);
-                    let position = if move_.is_floating {
-                        InsertPosition::Floating
-                    } else {
-                        ws.scrolling_insert_position(pos_within_workspace)
-                    };
+                    let position = ws
+                        .interactive_move_insert_position(pos_within_workspace, move_.is_floating);
 
                     let rules = move_.tile.window().rules();
                     let border_width = move_.tile.effective_border_width().unwrap_or(0.);
@@ -3147,12 +3478,11 @@ impl<W: LayoutElement> Layout<W> {
         let options = Rc::new(options);
 
         if let Some(InteractiveMoveState::Moving(move_)) = &mut self.interactive_move {
-            let view_size = output_size(&move_.output);
-            let scale = move_.output.current_scale().fractional_scale();
+            let units = OutputUnits::from_output(&move_.output);
             move_.tile.update_config(
-                view_size,
-                scale,
-                Rc::new(Options::clone(&options).adjusted_for_scale(scale)),
+                units.size,
+                units.scale,
+                Rc::new(Options::clone(&options).adjusted_for_output(units)),
             );
         }
 
@@ -3315,6 +3645,13 @@ impl<W: LayoutElement> Layout<W> {
         workspace.expand_column_to_available_width();
     }
 
+    pub fn fit_column_to_content(&mut self) {
+        let Some(workspace) = self.active_workspace_mut() else {
+            return;
+        };
+        workspace.fit_column_to_content();
+    }
+
     pub fn toggle_window_floating(&mut self, window: Option<&W::Id>) {
         if let Some(InteractiveMoveState::Moving(move_)) = &mut self.interactive_move {
             if window.is_none() || window == Some(move_.tile.window().id()) {
@@ -3628,9 +3965,12 @@ impl<W: LayoutElement> Layout<W> {
         }
 
         let mut ws = current.workspaces.remove(current.active_workspace_idx);
+        // The workspace below takes the place of the moved one, slide the view from there.
+        let below_id = current.workspaces[current.active_workspace_idx].id();
         current.active_workspace_idx = current.active_workspace_idx.saturating_sub(1);
         current.workspace_switch = None;
         current.clean_up_workspaces();
+        current.animate_workspace_switch_from(Some(below_id));
 
         ws.set_output(Some(output.clone()));
         ws.original_output = OutputId::new(output);
@@ -3654,6 +3994,7 @@ impl<W: LayoutElement> Layout<W> {
         target.active_workspace_idx = target_ws_idx;
         target.workspace_switch = None;
         target.clean_up_workspaces();
+        target.animate_workspace_switch_from(target.previous_workspace_id);
 
         *active_monitor_idx = target_idx;
 
@@ -3716,11 +4057,18 @@ impl<W: LayoutElement> Layout<W> {
             current.add_workspace_top();
         }
 
-        if old_idx < current.active_workspace_idx {
+        let mut below_id = None;
+        if old_idx == current_active_ws_idx {
+            // Same as move_workspace_to_output(): the workspace below takes the place of the
+            // moved one, focus the one above and slide the view from there.
+            below_id = Some(current.workspaces[current.active_workspace_idx].id());
+            current.active_workspace_idx = current.active_workspace_idx.saturating_sub(1);
+        } else if old_idx < current.active_workspace_idx {
             current.active_workspace_idx -= 1;
         }
         current.workspace_switch = None;
         current.clean_up_workspaces();
+        current.animate_workspace_switch_from(below_id);
 
         ws.set_output(Some(new_output.clone()));
         ws.original_output = OutputId::new(&new_output);
@@ -3753,6 +4101,9 @@ impl<W: LayoutElement> Layout<W> {
 
         target.workspace_switch = None;
         target.clean_up_workspaces();
+        if res {
+            target.animate_workspace_switch_from(target.previous_workspace_id);
+        }
 
         res
     }
@@ -4201,12 +4552,11 @@ impl<W: LayoutElement> Layout<W> {
                     output.current_transform(),
                 );
 
-                let view_size = output_size(&output);
-                let scale = output.current_scale().fractional_scale();
+                let units = OutputUnits::from_output(&output);
                 tile.update_config(
-                    view_size,
-                    scale,
-                    Rc::new(Options::clone(&self.options).adjusted_for_scale(scale)),
+                    units.size,
+                    units.scale,
+                    Rc::new(Options::clone(&self.options).adjusted_for_output(units)),
                 );
 
                 // Unfullscreen.
@@ -4283,12 +4633,11 @@ impl<W: LayoutElement> Layout<W> {
                         output.current_scale(),
                         output.current_transform(),
                     );
-                    let view_size = output_size(&output);
-                    let scale = output.current_scale().fractional_scale();
+                    let units = OutputUnits::from_output(&output);
                     move_.tile.update_config(
-                        view_size,
-                        scale,
-                        Rc::new(Options::clone(&self.options).adjusted_for_scale(scale)),
+                        units.size,
+                        units.scale,
+                        Rc::new(Options::clone(&self.options).adjusted_for_output(units)),
                     );
                     move_.output = output.clone();
                     self.focus_output(&output);
@@ -4431,14 +4780,13 @@ impl<W: LayoutElement> Layout<W> {
                                     .position(|ws| ws.id() == ws_id)
                                     .unwrap();
 
-                                let position = if move_.is_floating {
-                                    InsertPosition::Floating
-                                } else {
-                                    let pos_within_workspace =
-                                        (move_.pointer_pos_within_output - geo.loc).downscale(zoom);
-                                    let ws = &mut mon.workspaces[ws_idx];
-                                    ws.scrolling_insert_position(pos_within_workspace)
-                                };
+                                let pos_within_workspace =
+                                    (move_.pointer_pos_within_output - geo.loc).downscale(zoom);
+                                let ws = &mon.workspaces[ws_idx];
+                                let position = ws.interactive_move_insert_position(
+                                    pos_within_workspace,
+                                    move_.is_floating,
+                                );
 
                                 (position, Some(geo.loc))
                             }
@@ -4496,9 +4844,19 @@ impl<W: LayoutElement> Layout<W> {
 
                 match position {
                     InsertPosition::NewColumn(column_idx) => {
+                        let mut tile = move_.tile;
+
+                        // A floating window dropped onto a column gap gets tiled there. Keep its
+                        // floating size in case it goes back to floating later.
+                        if move_.is_floating {
+                            if let Some(size) = tile.window().expected_size() {
+                                tile.floating_window_size = Some(size);
+                            }
+                        }
+
                         let ws_id = mon.workspaces[ws_idx].id();
                         mon.add_tile(
-                            move_.tile,
+                            tile,
                             MonitorAddWindowTarget::Workspace {
                                 id: ws_id,
                                 column_idx: Some(column_idx),
@@ -4615,6 +4973,15 @@ impl<W: LayoutElement> Layout<W> {
     pub fn dnd_update(&mut self, output: Output, pointer_pos_within_output: Point<f64, Logical>) {
         let begin_gesture = self.dnd.is_none();
 
+        // The drag moved to another monitor, so it isn't at the edge of the previous one anymore.
+        if let Some(prev) = self.dnd.as_ref().map(|dnd| dnd.output.clone()) {
+            if prev != output {
+                if let Some(mon) = self.monitor_for_output_mut(&prev) {
+                    mon.dnd_edge_switch_end();
+                }
+            }
+        }
+
         self.dnd = Some(DndData {
             output,
             pointer_pos_within_output,
@@ -4644,6 +5011,7 @@ impl<W: LayoutElement> Layout<W> {
         if let MonitorSet::Normal { monitors, .. } = &mut self.monitor_set {
             for mon in monitors {
                 mon.dnd_scroll_gesture_end();
+                mon.dnd_edge_switch_end();
             }
         }
 
@@ -4860,16 +5228,22 @@ impl<W: LayoutElement> Layout<W> {
     pub fn toggle_overview(&mut self) {
         self.overview_open = !self.overview_open;
 
-        let from = self.overview_progress.take().map_or(0., |p| p.value());
         let to = if self.overview_open { 1. } else { 0. };
 
-        self.overview_progress = Some(OverviewProgress::Animation(Animation::new(
-            self.clock.clone(),
-            from,
-            to,
-            0.,
-            self.options.animations.overview_open_close.0,
-        )));
+        // Reverse an ongoing animation from its current position and velocity.
+        let anim = match self.overview_progress.take() {
+            Some(OverviewProgress::Animation(anim)) if !anim.is_done() => {
+                anim.restarted(anim.value(), to, anim.velocity())
+            }
+            progress => Animation::new(
+                self.clock.clone(),
+                progress.map_or(0., |p| p.value()),
+                to,
+                0.,
+                self.options.animations.overview_open_close.0,
+            ),
+        };
+        self.overview_progress = Some(OverviewProgress::Animation(anim));
 
         self.set_monitors_overview_state();
     }
@@ -5037,6 +5411,19 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Starts the close animation for a window that [`Layout::replace_window()`] is about to take
+    /// out.
+    pub fn start_close_animation_for_replaced_window(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        window: &W::Id,
+        blocker: TransactionBlocker,
+    ) {
+        if let Some(ws) = self.workspaces_mut().find(|ws| ws.has_window(window)) {
+            ws.start_close_animation_for_replaced_window(renderer, window, blocker);
+        }
+    }
+
     pub fn render_interactive_move_for_output<'a, R: NiriRenderer + 'a>(
         &'a self,
         renderer: &mut R,
@@ -5234,6 +5621,19 @@ impl<W: LayoutElement> Layout<W> {
         self.overview_open
     }
 
+    /// Returns the area where a new tiled window would open on this output.
+    ///
+    /// The area is relative to the output. Returns `None` in the overview, where the workspaces
+    /// are zoomed out.
+    pub fn new_window_area(&self, output: &Output) -> Option<Rectangle<f64, Logical>> {
+        if self.overview_open {
+            return None;
+        }
+
+        let mon = self.monitor_for_output(output)?;
+        mon.active_workspace_ref().new_window_area()
+    }
+
     fn resolve_scrolling_width(&self, window: &W, width: Option<PresetSize>) -> ColumnWidth {
         let width = width.unwrap_or_else(|| PresetSize::Fixed(window.size().w));
         match width {
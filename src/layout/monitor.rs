@@ -7,6 +7,7 @@ use niri_config::CornerRadius;
 use smithay::backend::renderer::element::utils::{
     CropRenderElement, Relocate, RelocateRenderElement, RescaleRenderElement,
 };
+use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::output::Output;
 use smithay::utils::{Logical, Point, Rectangle, Size};
 
@@ -43,6 +44,9 @@ const WORKSPACE_GESTURE_RUBBER_BAND: RubberBand = RubberBand {
 /// This constant is tied to the default dnd-edge-workspace-switch max-speed setting.
 const WORKSPACE_DND_EDGE_SCROLL_MOVEMENT: f64 = 1500.;
 
+/// Fraction of the adjacent workspace that slides into view while peeking.
+const WORKSPACE_PEEK_AMOUNT: f64 = 0.2;
+
 #[derive(Debug)]
 pub struct Monitor<W: LayoutElement> {
     /// Output for this monitor.
@@ -78,6 +82,8 @@ pub struct Monitor<W: LayoutElement> {
     pub(super) overview_open: bool,
     /// Progress of the overview zoom animation, 1 is fully in overview.
     overview_progress: Option<OverviewProgress>,
+    /// Drag-and-drop held at the top or bottom edge outside the overview.
+    dnd_edge_dwell: Option<DndEdgeDwell>,
     /// Clock for driving animations.
     pub(super) clock: Clock,
     /// Configurable properties of the layout.
@@ -88,6 +94,11 @@ pub struct Monitor<W: LayoutElement> {
 pub enum WorkspaceSwitch {
     Animation(Animation),
     Gesture(WorkspaceSwitchGesture),
+    /// Adjacent workspace partially slid into view while a peek bind is held.
+    ///
+    /// Unlike the other variants, this doesn't change the active workspace. The animation is
+    /// towards the peeked position and the peek stays there after the animation ends.
+    Peek(Animation),
 }
 
 #[derive(Debug)]
@@ -120,6 +131,14 @@ pub struct WorkspaceSwitchGesture {
     dnd_nonzero_start_time: Option<Duration>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DndEdgeDwell {
+    /// Whether the drag is at the top edge, rather than the bottom one.
+    is_top: bool,
+    /// Unadjusted time when the drag reached the edge or last switched the workspace.
+    start_time: Duration,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(super) enum InsertPosition {
     NewColumn(usize),
@@ -167,6 +186,12 @@ pub enum MonitorAddWindowTarget<'a, W: LayoutElement> {
     },
     /// Next to this existing window.
     NextTo(&'a W::Id),
+    /// Into the slot reserved for this app ID on this workspace.
+    ReservedSlot {
+        /// Id of the target workspace.
+        id: WorkspaceId,
+        app_id: &'a str,
+    },
 }
 
 niri_render_elements! {
@@ -188,6 +213,7 @@ impl WorkspaceSwitch {
             WorkspaceSwitch::Gesture(gesture) => {
                 gesture.current_idx + gesture.animation.as_ref().map_or(0., |anim| anim.value())
             }
+            WorkspaceSwitch::Peek(anim) => anim.value(),
         }
     }
 
@@ -195,12 +221,15 @@ impl WorkspaceSwitch {
         match self {
             WorkspaceSwitch::Animation(anim) => anim.to(),
             WorkspaceSwitch::Gesture(gesture) => gesture.current_idx,
+            WorkspaceSwitch::Peek(anim) => anim.to(),
         }
     }
 
     pub fn offset(&mut self, delta: isize) {
         match self {
-            WorkspaceSwitch::Animation(anim) => anim.offset(delta as f64),
+            WorkspaceSwitch::Animation(anim) | WorkspaceSwitch::Peek(anim) => {
+                anim.offset(delta as f64)
+            }
             WorkspaceSwitch::Gesture(gesture) => {
                 if delta >= 0 {
                     gesture.center_idx += delta as usize;
@@ -217,6 +246,7 @@ impl WorkspaceSwitch {
         match self {
             WorkspaceSwitch::Animation(_) => true,
             WorkspaceSwitch::Gesture(gesture) => gesture.animation.is_some(),
+            WorkspaceSwitch::Peek(anim) => !anim.is_done(),
         }
     }
 }
@@ -297,6 +327,7 @@ impl<W: LayoutElement> Monitor<W> {
             insert_hint_render_loc: None,
             overview_open: false,
             overview_progress: None,
+            dnd_edge_dwell: None,
             workspace_switch: None,
             clock,
             options,
@@ -374,6 +405,63 @@ impl<W: LayoutElement> Monitor<W> {
         self.add_workspace_at(self.workspaces.len());
     }
 
+    /// Removes and returns the workspaces that belong to `output`, in their order.
+    ///
+    /// Empty unnamed workspaces are dropped, since the output gets its own empty workspace anyway.
+    pub fn take_workspaces_of(&mut self, output: &Output) -> Vec<Workspace<W>> {
+        let mut stopped_ws_switch = false;
+
+        let mut workspaces = vec![];
+        for i in (0..self.workspaces.len()).rev() {
+            if !self.workspaces[i].original_output.matches(output) {
+                continue;
+            }
+
+            let ws = self.workspaces.remove(i);
+
+            // FIXME: this can be coded in a way that the workspace switch won't be affected if the
+            // removed workspace is invisible. But this is good enough for now.
+            if self.workspace_switch.is_some() {
+                self.workspace_switch = None;
+                stopped_ws_switch = true;
+            }
+
+            // The user could've closed a window while remaining on this workspace, on another
+            // monitor. However, the output will get an empty workspace in the end instead.
+            if ws.has_windows_or_name() {
+                workspaces.push(ws);
+            }
+
+            if i <= self.active_workspace_idx
+                // Generally when moving the currently active workspace, we want to fall back to
+                // the workspace above, so as not to end up on the last empty workspace. However,
+                // with empty workspace above first, when moving the workspace at index 1 (first
+                // non-empty), we want to stay at index 1, so as once again not to end up on an
+                // empty workspace.
+                //
+                // This comes into play at compositor startup when having named workspaces set up
+                // across multiple monitors. Without this check, the first monitor to connect can
+                // end up with the first empty workspace focused instead of the first named
+                // workspace.
+                && !(self.options.empty_workspace_above_first && self.active_workspace_idx == 1)
+            {
+                self.active_workspace_idx = self.active_workspace_idx.saturating_sub(1);
+            }
+        }
+
+        // If we stopped a workspace switch, then we might need to clean up workspaces. Also if
+        // empty_workspace_above_first is set and there are only 2 workspaces left, both will be
+        // empty and one of them needs to be removed. clean_up_workspaces takes care of this.
+        if stopped_ws_switch
+            || (self.options.empty_workspace_above_first && self.workspaces.len() == 2)
+        {
+            self.clean_up_workspaces();
+        }
+
+        workspaces.reverse();
+        workspaces
+    }
+
     pub fn activate_workspace(&mut self, idx: usize) {
         self.activate_workspace_with_anim_config(idx, None);
     }
@@ -426,6 +514,30 @@ impl<W: LayoutElement> Monitor<W> {
         }
     }
 
+    /// Animates the view from the workspace with `from_id` to the active workspace.
+    ///
+    /// Used when workspaces are moved between monitors, where the active workspace changes without
+    /// a regular switch.
+    pub(super) fn animate_workspace_switch_from(&mut self, from_id: Option<WorkspaceId>) {
+        let Some(from_idx) =
+            from_id.and_then(|id| self.workspaces.iter().position(|ws| ws.id() == id))
+        else {
+            return;
+        };
+
+        if from_idx == self.active_workspace_idx {
+            return;
+        }
+
+        self.workspace_switch = Some(WorkspaceSwitch::Animation(Animation::new(
+            self.clock.clone(),
+            from_idx as f64,
+            self.active_workspace_idx as f64,
+            0.,
+            self.options.animations.workspace_switch.0,
+        )));
+    }
+
     pub fn add_window(
         &mut self,
         window: W,
@@ -506,6 +618,10 @@ impl<W: LayoutElement> Monitor<W> {
                     .unwrap();
                 (idx, WorkspaceAddWindowTarget::NextTo(win_id))
             }
+            MonitorAddWindowTarget::ReservedSlot { id, app_id } => {
+                let idx = self.workspaces.iter().position(|ws| ws.id() == id).unwrap();
+                (idx, WorkspaceAddWindowTarget::ReservedSlot(app_id))
+            }
         };
 
         let workspace = &mut self.workspaces[workspace_idx];
@@ -679,6 +795,54 @@ impl<W: LayoutElement> Monitor<W> {
         );
     }
 
+    /// Starts the animation for a window about to move to another workspace without the view
+    /// following it.
+    ///
+    /// The window shrinks toward the edge of the view facing the target workspace. Does nothing
+    /// for windows on inactive workspaces since they aren't visible.
+    pub fn start_move_to_workspace_animation(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        window: Option<&W::Id>,
+        idx: usize,
+    ) {
+        let Some((window, shrink_to)) = self.move_to_workspace_shrink_target(window, idx) else {
+            return;
+        };
+
+        let ws = &mut self.workspaces[self.active_workspace_idx];
+        ws.start_move_animation_for_window(renderer, &window, shrink_to);
+    }
+
+    /// Returns the window about to move to workspace `idx` and the point in the view that it
+    /// shrinks toward.
+    ///
+    /// Returns `None` when the window isn't on the active workspace or stays on it.
+    pub(super) fn move_to_workspace_shrink_target(
+        &self,
+        window: Option<&W::Id>,
+        idx: usize,
+    ) -> Option<(W::Id, Point<f64, Logical>)> {
+        let ws_idx = self.active_workspace_idx;
+        let new_idx = min(idx, self.workspaces.len() - 1);
+        if new_idx == ws_idx {
+            return None;
+        }
+
+        let ws = &self.workspaces[ws_idx];
+        let window = match window {
+            Some(window) => window.clone(),
+            None => ws.active_window()?.id().clone(),
+        };
+        if !ws.has_window(&window) {
+            return None;
+        }
+
+        let view_size = ws.view_size();
+        let y = if new_idx < ws_idx { 0. } else { view_size.h };
+        Some((window, Point::from((view_size.w / 2., y))))
+    }
+
     pub fn move_to_workspace(
         &mut self,
         window: Option<&W::Id>,
@@ -892,6 +1056,8 @@ impl<W: LayoutElement> Monitor<W> {
                     }
                 }
             }
+            // The peek lasts until the bind is released.
+            Some(WorkspaceSwitch::Peek(_)) => (),
             None => (),
         }
 
@@ -1276,6 +1442,19 @@ impl<W: LayoutElement> Monitor<W> {
         }
     }
 
+    /// Returns how much the named workspace is in view, from 0 to 1.
+    ///
+    /// Follows the workspace switch progress, so it changes gradually while dragging between
+    /// workspaces. Returns 0 if this monitor has no such workspace.
+    pub fn workspace_visibility(&self, workspace_name: &str) -> f64 {
+        let Some(idx) = self.find_named_workspace_index(workspace_name) else {
+            return 0.;
+        };
+
+        let distance = (self.workspace_render_idx() - idx as f64).abs();
+        (1. - distance).clamp(0., 1.)
+    }
+
     pub fn workspaces_render_geo(&self) -> impl Iterator<Item = Rectangle<f64, Logical>> {
         let scale = self.scale.fractional_scale();
         let zoom = self.overview_zoom();
@@ -1373,6 +1552,18 @@ impl<W: LayoutElement> Monitor<W> {
         }
     }
 
+    /// Returns the on-screen rectangle of the tile with this window, if it's visible.
+    pub fn tile_render_rect(&self, window: &W::Id) -> Option<Rectangle<f64, Logical>> {
+        let zoom = self.overview_zoom();
+        self.workspaces_with_render_geo().find_map(|(ws, geo)| {
+            ws.tiles_with_render_positions()
+                .find(|(tile, _, visible)| *visible && tile.window().id() == window)
+                .map(|(tile, pos, _)| {
+                    Rectangle::new(geo.loc + pos.upscale(zoom), tile.tile_size().upscale(zoom))
+                })
+        })
+    }
+
     pub fn resize_edges_under(&self, pos_within_output: Point<f64, Logical>) -> Option<ResizeEdge> {
         if self.overview_progress.is_some() {
             return None;
@@ -1592,6 +1783,61 @@ impl<W: LayoutElement> Monitor<W> {
             })
     }
 
+    pub fn peek_workspace_up(&mut self) {
+        if let Some(idx) = self.active_workspace_idx.checked_sub(1) {
+            self.peek_workspace(idx);
+        }
+    }
+
+    pub fn peek_workspace_down(&mut self) {
+        let idx = self.active_workspace_idx + 1;
+        if idx < self.workspaces.len() {
+            self.peek_workspace(idx);
+        }
+    }
+
+    fn peek_workspace(&mut self, idx: usize) {
+        // Don't interfere with an ongoing gesture.
+        if let Some(WorkspaceSwitch::Gesture(_)) = self.workspace_switch {
+            return;
+        }
+
+        let active_idx = self.active_workspace_idx as f64;
+        let target_idx = active_idx + (idx as f64 - active_idx) * WORKSPACE_PEEK_AMOUNT;
+
+        // Key repeat can trigger the peek again.
+        if let Some(WorkspaceSwitch::Peek(anim)) = &self.workspace_switch {
+            if anim.to() == target_idx {
+                return;
+            }
+        }
+
+        self.workspace_switch = Some(WorkspaceSwitch::Peek(Animation::new(
+            self.clock.clone(),
+            self.workspace_render_idx(),
+            target_idx,
+            0.,
+            self.options.animations.workspace_switch.0,
+        )));
+    }
+
+    /// Springs back to the active workspace if a peek is in progress.
+    ///
+    /// Switching workspaces during a peek commits it, in which case this does nothing.
+    pub fn end_workspace_peek(&mut self) {
+        let Some(WorkspaceSwitch::Peek(_)) = &self.workspace_switch else {
+            return;
+        };
+
+        self.workspace_switch = Some(WorkspaceSwitch::Animation(Animation::new(
+            self.clock.clone(),
+            self.workspace_render_idx(),
+            self.active_workspace_idx as f64,
+            0.,
+            self.options.animations.workspace_switch.0,
+        )));
+    }
+
     pub fn workspace_switch_gesture_begin(&mut self, is_touchpad: bool) {
         let center_idx = self.active_workspace_idx;
         let current_idx = self.workspace_render_idx();
@@ -1836,6 +2082,63 @@ impl<W: LayoutElement> Monitor<W> {
         true
     }
 
+    /// Switches workspaces while a drag is held against the top or bottom edge.
+    ///
+    /// Outside the overview the other workspaces aren't visible, so rather than scrolling, this
+    /// switches one workspace at a time after the drag dwells at the edge. Returns `true` while
+    /// the drag is at an edge.
+    pub fn dnd_edge_switch(&mut self, pos: Point<f64, Logical>) -> bool {
+        let config = &self.options.gestures.dnd_edge_workspace_switch;
+        let dwell = Duration::from_millis(u64::from(config.dwell_ms));
+
+        // Consider the working area so layer-shell docks and such don't prevent switching.
+        let y = pos.y - self.working_area.loc.y;
+        let height = self.working_area.size.h;
+        let trigger_height = config.trigger_height.0.clamp(0., height / 2.);
+
+        let is_top = if trigger_height < 0.01 {
+            // Sanity check for trigger-height 0 or small window sizes.
+            None
+        } else if y < trigger_height {
+            Some(true)
+        } else if height - y < trigger_height {
+            Some(false)
+        } else {
+            None
+        };
+
+        let Some(is_top) = is_top else {
+            self.dnd_edge_dwell = None;
+            return false;
+        };
+
+        let now = self.clock.now_unadjusted();
+        let start_time = match self.dnd_edge_dwell {
+            Some(dwell) if dwell.is_top == is_top => dwell.start_time,
+            _ => now,
+        };
+
+        let start_time = if dwell <= now.saturating_sub(start_time) {
+            if is_top {
+                self.switch_workspace_up();
+            } else {
+                self.switch_workspace_down();
+            }
+
+            // Keep switching while the drag stays at the edge.
+            now
+        } else {
+            start_time
+        };
+
+        self.dnd_edge_dwell = Some(DndEdgeDwell { is_top, start_time });
+        true
+    }
+
+    pub fn dnd_edge_switch_end(&mut self) {
+        self.dnd_edge_dwell = None;
+    }
+
     pub fn dnd_scroll_gesture_end(&mut self) {
         if !matches!(
             self.workspace_switch,
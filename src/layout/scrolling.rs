@@ -1,16 +1,18 @@
 use std::cmp::{max, min};
 use std::iter::{self, zip};
+use std::mem;
 use std::rc::Rc;
 use std::time::Duration;
 
 use niri_config::{CenterFocusedColumn, PresetSize, Struts};
-use niri_ipc::{ColumnDisplay, SizeChange};
+use niri_ipc::{ColumnDisplay, ColumnPinEdge, SizeChange};
 use ordered_float::NotNan;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Serial, Size};
 
 use super::closing_window::{ClosingWindow, ClosingWindowRenderElement};
 use super::monitor::InsertPosition;
+use super::snapshot::{ColumnSnapshot, SnapshotWidth, WindowSnapshot};
 use super::tab_indicator::{TabIndicator, TabIndicatorRenderElement, TabInfo};
 use super::tile::{Tile, TileRenderElement, TileRenderSnapshot};
 use super::workspace::{InteractiveResize, ResolvedSize};
@@ -21,12 +23,15 @@ use crate::niri_render_elements;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::RenderTarget;
 use crate::utils::transaction::{Transaction, TransactionBlocker};
-use crate::utils::ResizeEdge;
+use crate::utils::{ensure_min_max_size, ResizeEdge};
 use crate::window::ResolvedWindowRules;
 
 /// Amount of touchpad movement to scroll the view for the width of one working area.
 const VIEW_GESTURE_WORKING_AREA_MOVEMENT: f64 = 1200.;
 
+/// Minimum half-width of the area around a column gap where a floating window can be dropped.
+const COLUMN_GAP_DROP_HALF_WIDTH: f64 = 24.;
+
 /// A scrollable-tiling space for windows.
 #[derive(Debug)]
 pub struct ScrollingSpace<W: LayoutElement> {
@@ -169,6 +174,12 @@ pub struct Column<W: LayoutElement> {
     /// Tab indicator for the tabbed display mode.
     tab_indicator: TabIndicator,
 
+    /// Edge of the view that this column is pinned to.
+    ///
+    /// A pinned column stays at its view edge while the other columns scroll. It is always the
+    /// first column for the left edge, and the last column for the right edge.
+    pinned: Option<ColumnPinEdge>,
+
     /// Animation of the render offset during window swapping.
     move_animation: Option<Animation>,
 
@@ -284,6 +295,30 @@ impl<W: LayoutElement> ScrollingSpace<W> {
     ) {
         let working_area = compute_working_area(working_area, scale, options.struts);
 
+        // Keep the active column at the same relative position in the view when the working area
+        // changes, e.g. when the workspace moves to an output with a different width.
+        if working_area != self.working_area && !self.view_offset.is_gesture() {
+            let old_area = self.working_area;
+            let reanchor = |offset: f64| {
+                let x_in_area = -offset - old_area.loc.x;
+                let fraction = x_in_area / old_area.size.w.max(1.);
+                -(fraction * working_area.size.w) - working_area.loc.x
+            };
+
+            // Fullscreen columns are always at offset 0 and don't need re-anchoring.
+            let is_fullscreen = self
+                .columns
+                .get(self.active_column_idx)
+                .is_some_and(|col| col.is_fullscreen);
+            if !is_fullscreen {
+                // Shift rather than replace the view offset, so that a running animation keeps
+                // going towards the re-anchored target instead of snapping to it.
+                let target = self.view_offset.target();
+                self.view_offset.offset(reanchor(target) - target);
+            }
+            self.view_offset_before_fullscreen = self.view_offset_before_fullscreen.map(reanchor);
+        }
+
         for (column, data) in zip(&mut self.columns, &mut self.data) {
             column.update_config(view_size, working_area, scale, options.clone());
             data.update(column);
@@ -348,6 +383,14 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         });
     }
 
+    /// Drops the textures rendered for animations, for example after a GPU reset.
+    pub fn drop_render_snapshots(&mut self) {
+        self.closing_windows.clear();
+        for tile in self.tiles_mut() {
+            tile.drop_render_snapshots();
+        }
+    }
+
     pub fn are_animations_ongoing(&self) -> bool {
         self.view_offset.is_animation_ongoing()
             || self.columns.iter().any(Column::are_animations_ongoing)
@@ -381,10 +424,64 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         self.columns.iter_mut().flat_map(|col| col.tiles.iter_mut())
     }
 
+    pub fn snapshot_columns(&self, app_id: &impl Fn(&W) -> Option<String>) -> Vec<ColumnSnapshot> {
+        self.columns
+            .iter()
+            .map(|col| {
+                let width = match col.width {
+                    ColumnWidth::Proportion(prop) => SnapshotWidth::Proportion(prop),
+                    // The fixed column width includes borders, so save the window width instead.
+                    ColumnWidth::Fixed(_) => {
+                        let window = col.tiles[col.active_tile_idx].window();
+                        SnapshotWidth::Fixed(window.size().w)
+                    }
+                };
+
+                let windows = col
+                    .tiles
+                    .iter()
+                    .map(|tile| {
+                        let window = tile.window();
+                        let size = window.size();
+                        WindowSnapshot {
+                            app_id: app_id(window),
+                            size: (size.w, size.h),
+                            pos: None,
+                        }
+                    })
+                    .collect();
+
+                ColumnSnapshot {
+                    width,
+                    is_full_width: col.is_full_width,
+                    windows,
+                }
+            })
+            .collect()
+    }
+
+    /// Saves `count` columns starting from the active one, or all columns if `count` is `None`.
+    pub fn snapshot_active_columns(
+        &self,
+        count: Option<usize>,
+        app_id: &impl Fn(&W) -> Option<String>,
+    ) -> Vec<ColumnSnapshot> {
+        let mut columns = self.snapshot_columns(app_id);
+        if let Some(count) = count {
+            columns.drain(..self.active_column_idx);
+            columns.truncate(count);
+        }
+        columns
+    }
+
     pub fn is_empty(&self) -> bool {
         self.columns.is_empty()
     }
 
+    pub fn column_count(&self) -> usize {
+        self.columns.len()
+    }
+
     pub fn active_window(&self) -> Option<&W> {
         if self.columns.is_empty() {
             return None;
@@ -394,6 +491,23 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         Some(col.tiles[col.active_tile_idx].window())
     }
 
+    /// Returns the active window of the column next to the column of `window`.
+    ///
+    /// Returns `None` if `window` shares its column with other windows.
+    pub fn column_neighbor_window(&self, window: &W::Id, direction: ScrollDirection) -> Option<&W> {
+        let idx = self.columns.iter().position(|col| col.contains(window))?;
+        if self.columns[idx].tiles.len() > 1 {
+            return None;
+        }
+
+        let idx = match direction {
+            ScrollDirection::Left => idx.checked_sub(1)?,
+            ScrollDirection::Right => idx + 1,
+        };
+        let col = self.columns.get(idx)?;
+        Some(col.tiles[col.active_tile_idx].window())
+    }
+
     pub fn active_window_mut(&mut self) -> Option<&mut W> {
         if self.columns.is_empty() {
             return None;
@@ -518,17 +632,18 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         }
 
         let target_x = target_x.unwrap_or_else(|| self.target_view_pos());
+        let working_area = self.strip_working_area();
 
         let new_offset = compute_new_view_offset(
-            target_x + self.working_area.loc.x,
-            self.working_area.size.w,
+            target_x + working_area.loc.x,
+            working_area.size.w,
             col_x,
             width,
             self.options.gaps,
         );
 
         // Non-fullscreen windows are always offset at least by the working area position.
-        new_offset - self.working_area.loc.x
+        new_offset - working_area.loc.x
     }
 
     fn compute_new_view_offset_centered(
@@ -543,15 +658,25 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         }
 
         // Columns wider than the view are left-aligned (the fit code can deal with that).
-        if self.working_area.size.w <= width {
+        let working_area = self.strip_working_area();
+        if working_area.size.w <= width {
             return self.compute_new_view_offset_fit(target_x, col_x, width, is_fullscreen);
         }
 
-        -(self.working_area.size.w - width) / 2. - self.working_area.loc.x
+        -(working_area.size.w - width) / 2. - working_area.loc.x
+    }
+
+    /// Computes the view offset that keeps the view in place when activating a pinned column.
+    fn compute_new_view_offset_for_pinned_column(&self, target_x: Option<f64>, idx: usize) -> f64 {
+        target_x.unwrap_or_else(|| self.target_view_pos()) - self.column_x(idx)
     }
 
     fn compute_new_view_offset_for_column_fit(&self, target_x: Option<f64>, idx: usize) -> f64 {
         let col = &self.columns[idx];
+        if col.pinned.is_some() {
+            return self.compute_new_view_offset_for_pinned_column(target_x, idx);
+        }
+
         self.compute_new_view_offset_fit(
             target_x,
             self.column_x(idx),
@@ -566,6 +691,10 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         idx: usize,
     ) -> f64 {
         let col = &self.columns[idx];
+        if col.pinned.is_some() {
+            return self.compute_new_view_offset_for_pinned_column(target_x, idx);
+        }
+
         self.compute_new_view_offset_centered(
             target_x,
             self.column_x(idx),
@@ -620,7 +749,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
                 } + self.options.gaps * 2.;
 
                 // If it fits together, do a normal animation, otherwise center the new column.
-                if total_width <= self.working_area.size.w {
+                if total_width <= self.strip_working_area().size.w {
                     self.compute_new_view_offset_for_column_fit(target_x, idx)
                 } else {
                     self.compute_new_view_offset_for_column_centered(target_x, idx)
@@ -753,6 +882,29 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         }
     }
 
+    /// Returns the index of the gap between columns at `pos`, if any.
+    ///
+    /// Gap `idx` is right before column `idx`. The space before the first and after the last
+    /// column doesn't count as a gap.
+    pub(super) fn column_gap_at(&self, pos: Point<f64, Logical>) -> Option<usize> {
+        let area = self.working_area;
+        if pos.y < area.loc.y || area.loc.y + area.size.h <= pos.y {
+            return None;
+        }
+
+        let x = pos.x + self.view_pos();
+        let gaps = self.options.gaps;
+        // Keep narrow gaps possible to hit.
+        let half_width = f64::max(gaps / 2., COLUMN_GAP_DROP_HALF_WIDTH);
+
+        self.column_xs(self.data.iter().copied())
+            .enumerate()
+            .take(self.columns.len())
+            .skip(1)
+            .find(|(_, col_x)| (col_x - gaps / 2. - x).abs() <= half_width)
+            .map(|(idx, _)| idx)
+    }
+
     pub(super) fn insert_position(&self, pos: Point<f64, Logical>) -> InsertPosition {
         if self.columns.is_empty() {
             return InsertPosition::NewColumn(0);
@@ -792,6 +944,23 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         // Find the closest gap between tiles.
         let col = &self.columns[col_idx];
 
+        if col.is_split() {
+            // In split mode, the gaps between tiles are vertical, like the gaps between columns.
+            let col_start = self.column_x(col_idx);
+            let (closest_tile_idx, tile_x) = col
+                .tile_offsets()
+                .map(|tile_off| col_start + tile_off.x)
+                .enumerate()
+                .min_by_key(|(_, tile_x)| NotNan::new((tile_x - x).abs()).unwrap())
+                .unwrap();
+
+            return if (col_x - x).abs() <= (tile_x - x).abs() {
+                InsertPosition::NewColumn(closest_col_idx)
+            } else {
+                InsertPosition::InColumn(col_idx, closest_tile_idx)
+            };
+        }
+
         let (closest_tile_idx, tile_y) = if col.display_mode == ColumnDisplay::Tabbed {
             // In tabbed mode, there's only one tile visible, and we want to check its top and
             // bottom.
@@ -939,6 +1108,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
                 self.active_column_idx + 1
             }
         });
+        let idx = self.new_column_idx(idx);
 
         column.update_config(
             self.view_size,
@@ -997,6 +1167,25 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         ))
     }
 
+    /// Puts `tile` in place of the tile with this window, returning the old tile.
+    ///
+    /// The new tile keeps the column and the height of the old one.
+    pub fn replace_tile(&mut self, window: &W::Id, tile: Tile<W>) -> Tile<W> {
+        // Stop interactive resize.
+        if let Some(resize) = &self.interactive_resize {
+            if window == &resize.window {
+                self.interactive_resize = None;
+            }
+        }
+
+        let column = self
+            .columns
+            .iter_mut()
+            .find(|col| col.contains(window))
+            .unwrap();
+        column.replace_tile(window, tile)
+    }
+
     pub fn remove_tile(&mut self, window: &W::Id, transaction: Transaction) -> RemovedTile<W> {
         let column_idx = self
             .columns
@@ -1035,9 +1224,14 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         // Animate movement of other tiles.
         // FIXME: tiles can move by X too, in a centered or resizing layout with one window smaller
         // than the others.
-        let offset_y = column.tile_offset(tile_idx + 1).y - column.tile_offset(tile_idx).y;
+        let offset = column.tile_offset(tile_idx + 1) - column.tile_offset(tile_idx);
+        let is_split = column.is_split();
         for tile in &mut column.tiles[tile_idx + 1..] {
-            tile.animate_move_y_from(offset_y);
+            if is_split {
+                tile.animate_move_x_from(offset.x);
+            } else {
+                tile.animate_move_y_from(offset.y);
+            }
         }
 
         if column.display_mode == ColumnDisplay::Tabbed && tile_idx != column.active_tile_idx {
@@ -1136,9 +1330,12 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             }
         }
 
-        let column = self.columns.remove(column_idx);
+        let mut column = self.columns.remove(column_idx);
         self.data.remove(column_idx);
 
+        // The column may end up on a different workspace, where it shouldn't stay pinned.
+        column.pinned = None;
+
         // Stop interactive resize.
         if let Some(resize) = &self.interactive_resize {
             if column
@@ -1356,6 +1553,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         &mut self,
         renderer: &mut GlesRenderer,
         window: &W::Id,
+        shrink_to: Option<Point<f64, Logical>>,
         blocker: TransactionBlocker,
     ) {
         let (tile, mut tile_pos) = self
@@ -1410,7 +1608,48 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             tile_pos.x -= offset;
         }
 
-        self.start_close_animation_for_tile(renderer, snapshot, tile_size, tile_pos, blocker);
+        self.start_close_animation_for_tile(
+            renderer, snapshot, tile_size, tile_pos, shrink_to, blocker,
+        );
+    }
+
+    /// Starts the close animation for a tile that another one is about to replace in its column.
+    ///
+    /// Unlike with a removed tile, the column stays in place, so the view doesn't move and the
+    /// animation needs no offset.
+    pub fn start_close_animation_for_replaced_window(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        window: &W::Id,
+        blocker: TransactionBlocker,
+    ) {
+        let col = self
+            .columns
+            .iter()
+            .find(|col| col.contains(window))
+            .unwrap();
+
+        // Skip closing animation for invisible tiles in a tabbed column.
+        if col.display_mode == ColumnDisplay::Tabbed
+            && col.tiles[col.active_tile_idx].window().id() != window
+        {
+            return;
+        }
+
+        let view_pos = self.view_pos();
+        let (tile, mut tile_pos) = self
+            .tiles_with_render_positions_mut(false)
+            .find(|(tile, _)| tile.window().id() == window)
+            .unwrap();
+
+        let Some(snapshot) = tile.take_unmap_snapshot() else {
+            return;
+        };
+
+        let tile_size = tile.tile_size();
+        tile_pos.x += view_pos;
+
+        self.start_close_animation_for_tile(renderer, snapshot, tile_size, tile_pos, None, blocker);
     }
 
     fn start_close_animation_for_tile(
@@ -1419,6 +1658,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         snapshot: TileRenderSnapshot,
         tile_size: Size<f64, Logical>,
         tile_pos: Point<f64, Logical>,
+        shrink_to: Option<Point<f64, Logical>>,
         blocker: TransactionBlocker,
     ) {
         let anim = Animation::new(
@@ -1437,7 +1677,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
 
         let scale = Scale::from(self.scale);
         let res = ClosingWindow::new(
-            renderer, snapshot, scale, tile_size, tile_pos, blocker, anim,
+            renderer, snapshot, scale, tile_size, tile_pos, shrink_to, blocker, anim,
         );
         match res {
             Ok(closing) => {
@@ -1456,19 +1696,55 @@ impl<W: LayoutElement> ScrollingSpace<W> {
     }
 
     pub fn focus_left(&mut self) -> bool {
+        if self.columns.is_empty() {
+            return false;
+        }
+
+        // Move within a split column first.
+        let col = &mut self.columns[self.active_column_idx];
+        if col.is_split() && col.active_tile_idx > 0 {
+            col.activate_idx(col.active_tile_idx - 1);
+            return true;
+        }
+
         if self.active_column_idx == 0 {
             return false;
         }
         self.activate_column(self.active_column_idx - 1);
+
+        // Entering a split column from the right focuses its rightmost window.
+        let col = &mut self.columns[self.active_column_idx];
+        if col.is_split() {
+            col.activate_idx(col.tiles.len() - 1);
+        }
+
         true
     }
 
     pub fn focus_right(&mut self) -> bool {
+        if self.columns.is_empty() {
+            return false;
+        }
+
+        // Move within a split column first.
+        let col = &mut self.columns[self.active_column_idx];
+        if col.is_split() && col.active_tile_idx + 1 < col.tiles.len() {
+            col.activate_idx(col.active_tile_idx + 1);
+            return true;
+        }
+
         if self.active_column_idx + 1 >= self.columns.len() {
             return false;
         }
 
         self.activate_column(self.active_column_idx + 1);
+
+        // Entering a split column from the left focuses its leftmost window.
+        let col = &mut self.columns[self.active_column_idx];
+        if col.is_split() {
+            col.activate_idx(0);
+        }
+
         true
     }
 
@@ -1584,9 +1860,21 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         self.move_column_to(index.saturating_sub(1).min(self.columns.len() - 1));
     }
 
-    fn move_column_to(&mut self, new_idx: usize) {
+    /// Moves the active column to a new index, returns whether it moved.
+    fn move_column_to(&mut self, new_idx: usize) -> bool {
         if self.active_column_idx == new_idx {
-            return;
+            return false;
+        }
+
+        // The pinned column stays at its edge, and other columns cannot move past it.
+        let new_idx = match self.pinned_column() {
+            Some((pinned_idx, _)) if pinned_idx == self.active_column_idx => return false,
+            Some((_, ColumnPinEdge::Left)) => max(new_idx, 1),
+            Some((pinned_idx, ColumnPinEdge::Right)) => min(new_idx, pinned_idx - 1),
+            None => new_idx,
+        };
+        if self.active_column_idx == new_idx {
+            return false;
         }
 
         let current_col_x = self.column_x(self.active_column_idx);
@@ -1619,6 +1907,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         }
 
         self.activate_column_with_anim_config(new_idx, self.options.animations.window_movement.0);
+        true
     }
 
     pub fn move_left(&mut self) -> bool {
@@ -1626,8 +1915,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             return false;
         }
 
-        self.move_column_to(self.active_column_idx - 1);
-        true
+        self.move_column_to(self.active_column_idx - 1)
     }
 
     pub fn move_right(&mut self) -> bool {
@@ -1636,8 +1924,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             return false;
         }
 
-        self.move_column_to(new_idx);
-        true
+        self.move_column_to(new_idx)
     }
 
     pub fn move_column_to_first(&mut self) {
@@ -1750,7 +2037,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
                 self.remove_tile_by_idx(source_col_idx, source_tile_idx, Transaction::new(), None);
 
             // We're inserting into the source column position.
-            let target_column_idx = source_col_idx;
+            let target_column_idx = self.new_column_idx(source_col_idx);
 
             self.add_tile(
                 Some(target_column_idx),
@@ -1844,7 +2131,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             let removed =
                 self.remove_tile_by_idx(source_col_idx, source_tile_idx, Transaction::new(), None);
 
-            let target_column_idx = source_col_idx + 1;
+            let target_column_idx = self.new_column_idx(source_col_idx + 1);
 
             self.add_tile(
                 Some(target_column_idx),
@@ -1904,7 +2191,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         }
 
         let source_col_idx = self.active_column_idx;
-        let target_col_idx = self.active_column_idx + 1;
+        let target_col_idx = self.new_column_idx(self.active_column_idx + 1);
         let cur_x = self.column_x(source_col_idx);
 
         let source_column = &self.columns[self.active_column_idx];
@@ -2071,7 +2358,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
 
         let col = &mut self.columns[self.active_column_idx];
         let display = match col.display_mode {
-            ColumnDisplay::Normal => ColumnDisplay::Tabbed,
+            ColumnDisplay::Normal | ColumnDisplay::Split => ColumnDisplay::Tabbed,
             ColumnDisplay::Tabbed => ColumnDisplay::Normal,
         };
 
@@ -2102,6 +2389,74 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         }
     }
 
+    pub fn toggle_column_pin(&mut self, edge: ColumnPinEdge) {
+        if self.columns.is_empty() {
+            return;
+        }
+
+        let idx = self.active_column_idx;
+        if self.columns[idx].is_fullscreen {
+            return;
+        }
+
+        // Remember the positions on screen to animate the columns that move.
+        let view_pos = self.view_pos();
+        let mut old_xs: Vec<f64> = self
+            .column_render_xs(self.data.iter().copied())
+            .map(|x| x - view_pos)
+            .collect();
+
+        if self.columns[idx].pinned == Some(edge) {
+            self.columns[idx].pinned = None;
+        } else {
+            // Only one column can be pinned at a time.
+            if let Some((pinned_idx, _)) = self.pinned_column() {
+                self.columns[pinned_idx].pinned = None;
+            }
+
+            let new_idx = match edge {
+                ColumnPinEdge::Left => 0,
+                ColumnPinEdge::Right => self.columns.len() - 1,
+            };
+
+            if new_idx != idx {
+                let current_col_x = self.column_x(idx);
+
+                let mut column = self.columns.remove(idx);
+                let data = self.data.remove(idx);
+                cancel_resize_for_column(&mut self.interactive_resize, &mut column);
+                self.columns.insert(new_idx, column);
+                self.data.insert(new_idx, data);
+
+                let x = old_xs.remove(idx);
+                old_xs.insert(new_idx, x);
+
+                // Preserve the camera position.
+                self.view_offset
+                    .offset(current_col_x - self.column_x(new_idx));
+                self.active_column_idx = new_idx;
+                self.activate_prev_column_on_removal = None;
+            }
+
+            self.columns[new_idx].pinned = Some(edge);
+        }
+
+        let view_pos = self.view_pos();
+        let new_xs = self
+            .column_render_xs(self.data.iter().copied())
+            .map(|x| x - view_pos);
+        let pixel = 1. / self.scale;
+        for ((col, old_x), new_x) in zip(zip(&mut self.columns, old_xs), new_xs) {
+            let offset = old_x - new_x;
+            if offset.abs() >= pixel {
+                col.animate_move_from(offset);
+            }
+        }
+
+        // Scroll to an unpinned column, or keep the view in place for a pinned one.
+        self.animate_view_offset_to_column(None, self.active_column_idx, None);
+    }
+
     pub fn center_column(&mut self) {
         if self.columns.is_empty() {
             return;
@@ -2205,6 +2560,62 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         self.column_x(self.active_column_idx) + self.view_offset.target()
     }
 
+    /// Returns the index and the edge of the pinned column, if any.
+    fn pinned_column(&self) -> Option<(usize, ColumnPinEdge)> {
+        self.columns
+            .iter()
+            .enumerate()
+            .find_map(|(idx, col)| col.pinned.map(|edge| (idx, edge)))
+    }
+
+    /// Returns the index at which to insert a new column so that it doesn't displace the pinned
+    /// column from its edge.
+    fn new_column_idx(&self, idx: usize) -> usize {
+        match self.pinned_column() {
+            Some((_, ColumnPinEdge::Left)) => max(idx, 1),
+            Some((pinned_idx, ColumnPinEdge::Right)) => min(idx, pinned_idx),
+            None => idx,
+        }
+    }
+
+    /// Returns the part of the working area where the unpinned columns scroll.
+    fn strip_working_area(&self) -> Rectangle<f64, Logical> {
+        let mut area = self.working_area;
+
+        if let Some((idx, edge)) = self.pinned_column() {
+            let width = self.data[idx].width + self.options.gaps;
+            area.size.w = f64::max(0., area.size.w - width);
+            if edge == ColumnPinEdge::Left {
+                area.loc.x += width;
+            }
+        }
+
+        area
+    }
+
+    /// Returns the X of the pinned column relative to the view.
+    fn pinned_column_view_x(&self, idx: usize, edge: ColumnPinEdge) -> f64 {
+        let area = self.working_area;
+        let gaps = self.options.gaps;
+
+        match edge {
+            ColumnPinEdge::Left => area.loc.x + gaps,
+            ColumnPinEdge::Right => area.loc.x + area.size.w - gaps - self.data[idx].width,
+        }
+    }
+
+    /// Returns the index of the column pinned to the right edge if it isn't active.
+    ///
+    /// The pinned column is rendered right after the active column so that other columns scroll
+    /// underneath it. A column pinned to the left is the first one, so it already is in that
+    /// position.
+    fn right_pinned_inactive_column_idx(&self) -> Option<usize> {
+        match self.pinned_column() {
+            Some((idx, ColumnPinEdge::Right)) if idx != self.active_column_idx => Some(idx),
+            _ => None,
+        }
+    }
+
     // HACK: pass a self.data iterator in manually as a workaround for the lack of method partial
     // borrowing. Note that this method's return value does not borrow the entire &Self!
     fn column_xs(&self, data: impl Iterator<Item = ColumnData>) -> impl Iterator<Item = f64> {
@@ -2228,21 +2639,41 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             .unwrap()
     }
 
+    /// Like `column_xs()`, but with the pinned column moved to its edge of the view.
+    fn column_render_xs(
+        &self,
+        data: impl Iterator<Item = ColumnData>,
+    ) -> impl Iterator<Item = f64> {
+        let view_pos = self.view_pos();
+        let pinned = self
+            .pinned_column()
+            .map(|(idx, edge)| (idx, view_pos + self.pinned_column_view_x(idx, edge)));
+
+        self.column_xs(data)
+            .enumerate()
+            .map(move |(idx, pos)| match pinned {
+                Some((pinned_idx, pinned_pos)) if pinned_idx == idx => pinned_pos,
+                _ => pos,
+            })
+    }
+
     fn column_xs_in_render_order(
         &self,
         data: impl Iterator<Item = ColumnData>,
     ) -> impl Iterator<Item = f64> {
         let active_idx = self.active_column_idx;
-        let active_pos = self.column_x(active_idx);
-        let offsets = self
-            .column_xs(data)
-            .enumerate()
-            .filter_map(move |(idx, pos)| (idx != active_idx).then_some(pos));
-        iter::once(active_pos).chain(offsets)
+        let pinned_idx = self.right_pinned_inactive_column_idx();
+        let xs: Vec<f64> = self.column_render_xs(data).collect();
+        let active_pos = xs[active_idx];
+        let pinned_pos = pinned_idx.map(|idx| xs[idx]);
+        let offsets = xs.into_iter().enumerate().filter_map(move |(idx, pos)| {
+            (idx != active_idx && Some(idx) != pinned_idx).then_some(pos)
+        });
+        iter::once(active_pos).chain(pinned_pos).chain(offsets)
     }
 
     fn columns_mut(&mut self) -> impl Iterator<Item = (&mut Column<W>, f64)> + '_ {
-        let offsets = self.column_xs(self.data.iter().copied());
+        let offsets = self.column_render_xs(self.data.iter().copied());
         zip(&mut self.columns, offsets)
     }
 
@@ -2257,12 +2688,20 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             (first, active, rest)
         };
 
-        let columns = active.iter().chain(first).chain(rest);
+        // The column pinned to the right edge is the last one.
+        let (rest, pinned) = if self.right_pinned_inactive_column_idx().is_some() {
+            rest.split_at(rest.len() - 1)
+        } else {
+            (rest, &[][..])
+        };
+
+        let columns = active.iter().chain(pinned).chain(first).chain(rest);
         zip(columns, offsets)
     }
 
     fn columns_in_render_order_mut(&mut self) -> impl Iterator<Item = (&mut Column<W>, f64)> + '_ {
         let offsets = self.column_xs_in_render_order(self.data.iter().copied());
+        let has_pinned = self.right_pinned_inactive_column_idx().is_some();
 
         let (first, active, rest) = if self.columns.is_empty() {
             (&mut [][..], &mut [][..], &mut [][..])
@@ -2272,7 +2711,15 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             (first, active, rest)
         };
 
-        let columns = active.iter_mut().chain(first).chain(rest);
+        // The column pinned to the right edge is the last one.
+        let (rest, pinned) = if has_pinned {
+            let len = rest.len();
+            rest.split_at_mut(len - 1)
+        } else {
+            (rest, &mut [][..])
+        };
+
+        let columns = active.iter_mut().chain(pinned).chain(first).chain(rest);
         zip(columns, offsets)
     }
 
@@ -2349,6 +2796,27 @@ impl<W: LayoutElement> ScrollingSpace<W> {
                     Rectangle::new(loc, size)
                 }
             }
+            InsertPosition::InColumn(column_index, tile_index)
+                if self
+                    .columns
+                    .get(column_index)
+                    .is_some_and(|col| col.is_split() && tile_index <= col.tiles.len()) =>
+            {
+                // In split mode, the hint goes between the tiles, same as between columns.
+                let col = &self.columns[column_index];
+                let x = col.tile_offset(tile_index).x;
+                let (width, x) = if tile_index == 0 {
+                    (150., x)
+                } else if tile_index == col.tiles.len() {
+                    (150., x - self.options.gaps - 150.)
+                } else {
+                    (300., x - self.options.gaps / 2. - 150.)
+                };
+
+                let size = Size::from((width, self.working_area.size.h - self.options.gaps * 2.));
+                let loc = Point::from((self.column_x(column_index) + x, col.tiles_origin().y));
+                Rectangle::new(loc, size)
+            }
             InsertPosition::InColumn(column_index, tile_index) => {
                 if column_index > self.columns.len() {
                     error!("insert hint column index is out of range");
@@ -2423,8 +2891,11 @@ impl<W: LayoutElement> ScrollingSpace<W> {
     pub fn active_tile_visual_rectangle(&self) -> Option<Rectangle<f64, Logical>> {
         let col = self.columns.get(self.active_column_idx)?;
 
-        let final_view_offset = self.view_offset.target();
-        let view_off = Point::from((-final_view_offset, 0.));
+        let view_x = match col.pinned {
+            Some(edge) => self.pinned_column_view_x(self.active_column_idx, edge),
+            None => -self.view_offset.target(),
+        };
+        let view_off = Point::from((view_x, 0.));
 
         let (tile, tile_off) = col.tiles().nth(col.active_tile_idx).unwrap();
 
@@ -2678,6 +3149,58 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         self.animate_view_offset_to_column(None, self.active_column_idx, None);
     }
 
+    pub fn fit_column_to_content(&mut self) {
+        if self.columns.is_empty() {
+            return;
+        }
+
+        let gaps = self.options.gaps;
+        let max_width = self.working_area.size.w - gaps * 2.;
+
+        let col = &mut self.columns[self.active_column_idx];
+        if col.is_fullscreen {
+            return;
+        }
+
+        // Windows that didn't take the size we gave them, like terminals that snap to their cell
+        // grid, have committed the size they actually want.
+        let tile_width = col
+            .tiles
+            .iter()
+            .filter_map(|tile| {
+                let win = tile.window();
+                let mut width = win.size().w;
+                if width == 0 {
+                    width = win.expected_size()?.w;
+                }
+
+                let width = ensure_min_max_size(width, win.min_size().w, win.max_size().w);
+                Some(tile.tile_width_for_window_width(f64::from(width)))
+            })
+            .reduce(f64::max);
+        let Some(tile_width) = tile_width else {
+            return;
+        };
+
+        // In split mode, every window gets an equal share of the column width.
+        let count = if col.is_split() {
+            col.tiles.len() as f64
+        } else {
+            1.
+        };
+        let width = tile_width * count + gaps * (count - 1.);
+        let width = f64::max(1., f64::min(width, max_width - col.extra_size().w));
+
+        cancel_resize_for_column(&mut self.interactive_resize, col);
+
+        col.width = ColumnWidth::Fixed(width);
+        col.preset_width_idx = None;
+        col.is_full_width = false;
+        col.update_tile_sizes(true);
+
+        self.animate_view_offset_to_column(None, self.active_column_idx, None);
+    }
+
     pub fn set_fullscreen(&mut self, window: &W::Id, is_fullscreen: bool) -> bool {
         let (mut col_idx, tile_idx) = self
             .columns
@@ -2693,6 +3216,11 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         let mut col = &mut self.columns[col_idx];
         let is_tabbed = col.display_mode == ColumnDisplay::Tabbed;
 
+        // Fullscreen columns take up the whole view, so they cannot stay pinned.
+        if is_fullscreen {
+            col.pinned = None;
+        }
+
         if is_fullscreen && col_idx == self.active_column_idx && (col.tiles.len() == 1 || is_tabbed)
         {
             self.view_offset_before_fullscreen = Some(self.view_offset.stationary());
@@ -3052,6 +3580,34 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         let end_pos = gesture.tracker.projected_end_pos() * norm_factor;
         let target_view_offset = end_pos + gesture.delta_from_tracker;
 
+        // The snapping points below don't account for the pinned column, so with one, activate the
+        // column closest to the middle of the area where the columns scroll.
+        if self.pinned_column().is_some() {
+            let active_col_x = self.column_x(self.active_column_idx);
+            let strip = self.strip_working_area();
+            let target_center = active_col_x + target_view_offset + strip.loc.x + strip.size.w / 2.;
+
+            let new_col_idx = (0..self.columns.len())
+                .filter(|&idx| self.columns[idx].pinned.is_none())
+                .min_by_key(|&idx| {
+                    let center = self.column_x(idx) + self.data[idx].width / 2.;
+                    NotNan::new((center - target_center).abs()).unwrap()
+                })
+                .unwrap_or(self.active_column_idx);
+
+            let delta = active_col_x - self.column_x(new_col_idx);
+
+            if self.active_column_idx != new_col_idx {
+                self.view_offset_before_fullscreen = None;
+            }
+
+            self.active_column_idx = new_col_idx;
+            self.view_offset = ViewOffset::Static(current_view_offset + delta);
+            self.animate_view_offset_to_column(None, new_col_idx, None);
+
+            return true;
+        }
+
         // Compute the snapping points. These are where the view aligns with column boundaries on
         // either side.
         struct Snap {
@@ -3565,6 +4121,29 @@ impl<W: LayoutElement> ScrollingSpace<W> {
                 assert_eq!(data, &data2, "column data must be up to date");
             }
 
+            let mut pinned = self
+                .columns
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, col)| col.pinned.map(|edge| (idx, edge)));
+            if let Some((idx, edge)) = pinned.next() {
+                assert!(pinned.next().is_none(), "only one column can be pinned");
+
+                match edge {
+                    ColumnPinEdge::Left => assert_eq!(idx, 0, "left pinned column must be first"),
+                    ColumnPinEdge::Right => assert_eq!(
+                        idx,
+                        self.columns.len() - 1,
+                        "right pinned column must be last"
+                    ),
+                }
+
+                assert!(
+                    !self.columns[idx].is_fullscreen,
+                    "pinned column cannot be fullscreen"
+                );
+            }
+
             let col = &self.columns[self.active_column_idx];
 
             // When we have an unfullscreen view offset stored, the active column should have a
@@ -3752,6 +4331,7 @@ impl<W: LayoutElement> Column<W> {
             is_fullscreen: false,
             display_mode,
             tab_indicator: TabIndicator::new(options.tab_indicator),
+            pinned: None,
             move_animation: None,
             view_size,
             working_area,
@@ -3992,6 +4572,18 @@ impl<W: LayoutElement> Column<W> {
         }
     }
 
+    fn replace_tile(&mut self, window: &W::Id, mut tile: Tile<W>) -> Tile<W> {
+        tile.update_config(self.view_size, self.scale, self.options.clone());
+
+        let idx = self.position(window).unwrap();
+        let height = self.data[idx].height;
+        self.data[idx] = TileData::new(&tile, height);
+        let old = mem::replace(&mut self.tiles[idx], tile);
+        self.update_tile_sizes(false);
+
+        old
+    }
+
     fn update_window(&mut self, window: &W::Id) {
         let (tile_idx, tile) = self
             .tiles
@@ -4010,6 +4602,7 @@ impl<W: LayoutElement> Column<W> {
         self.data[tile_idx].update(tile);
 
         let is_tabbed = self.display_mode == ColumnDisplay::Tabbed;
+        let is_split = self.is_split();
 
         // Move windows below in tandem with resizing.
         //
@@ -4017,7 +4610,7 @@ impl<W: LayoutElement> Column<W> {
         // windows in the column, so they should all be animated. How should this interact with
         // animated vs. non-animated resizes? For example, an animated +20 resize followed by two
         // non-animated -10 resizes.
-        if !is_tabbed && tile.resize_animation().is_some() && offset != 0. {
+        if !is_tabbed && !is_split && tile.resize_animation().is_some() && offset != 0. {
             for tile in &mut self.tiles[tile_idx + 1..] {
                 tile.animate_move_y_from_with_config(
                     offset,
@@ -4027,6 +4620,13 @@ impl<W: LayoutElement> Column<W> {
         }
     }
 
+    /// Whether the tiles are currently placed side by side.
+    ///
+    /// A single window in a split column is laid out as in the normal display mode.
+    fn is_split(&self) -> bool {
+        self.display_mode == ColumnDisplay::Split && self.tiles.len() > 1 && !self.is_fullscreen
+    }
+
     /// Extra size taken up by elements in the column such as the tab indicator.
     fn extra_size(&self) -> Size<f64, Logical> {
         if self.display_mode == ColumnDisplay::Tabbed {
@@ -4079,6 +4679,11 @@ impl<W: LayoutElement> Column<W> {
             return;
         }
 
+        if self.is_split() {
+            self.update_split_tile_sizes(animate, transaction);
+            return;
+        }
+
         let is_tabbed = self.display_mode == ColumnDisplay::Tabbed;
 
         let min_size: Vec<_> = self
@@ -4352,7 +4957,75 @@ impl<W: LayoutElement> Column<W> {
         }
     }
 
+    /// Sizes the tiles in the split display mode.
+    ///
+    /// The column width is shared equally between the tiles, and every tile takes the full
+    /// working area height. Window heights set in the normal mode are kept for when the column
+    /// goes back to it.
+    fn update_split_tile_sizes(&mut self, animate: bool, transaction: Transaction) {
+        let gaps = self.options.gaps;
+        let count = self.tiles.len();
+
+        let min_size: Vec<_> = self
+            .tiles
+            .iter()
+            .map(Tile::min_size_nonfullscreen)
+            .map(|mut size| {
+                size.w = size.w.max(1.);
+                size.h = size.h.max(1.);
+                size
+            })
+            .collect();
+
+        let width = if self.is_full_width {
+            ColumnWidth::Proportion(1.)
+        } else {
+            self.width
+        };
+        let width = self.resolve_column_width(width);
+
+        // The column must fit every tile at its min width.
+        let min_width = min_size.iter().map(|size| size.w).sum::<f64>() + gaps * (count - 1) as f64;
+        let width = f64::max(width, min_width);
+
+        let max_tile_height = self.working_area.size.h - gaps * 2.;
+
+        let mut width_left = width - gaps * (count - 1) as f64;
+        let mut tiles_left = count;
+        for (tile, min_size) in zip(&mut self.tiles, min_size) {
+            let max_size = tile.max_size_nonfullscreen();
+
+            // Wayland requires us to round the requested size for a window to integer logical
+            // pixels, therefore we compute the share of the remaining width dynamically.
+            let mut tile_width = width_left / tiles_left as f64;
+            if max_size.w > 0. {
+                tile_width = f64::min(tile_width, max_size.w);
+            }
+            tile_width = f64::max(tile_width, min_size.w);
+            let tile_width = tile.tile_width_for_window_width(
+                tile.window_width_for_tile_width(tile_width).round().max(1.),
+            );
+
+            width_left -= tile_width;
+            tiles_left -= 1;
+
+            let mut tile_height = max_tile_height;
+            if max_size.h > 0. {
+                tile_height = f64::min(tile_height, max_size.h);
+            }
+            let tile_height = f64::max(tile_height, min_size.h);
+
+            let size = Size::from((tile_width, tile_height));
+            tile.request_tile_size(size, animate, Some(transaction.clone()));
+        }
+    }
+
     fn width(&self) -> f64 {
+        if self.is_split() {
+            let tiles_width: f64 = self.data.iter().map(|data| data.size.w).sum();
+            return tiles_width + self.options.gaps * (self.tiles.len() - 1) as f64;
+        }
+
         let mut tiles_width = self
             .data
             .iter()
@@ -4375,10 +5048,19 @@ impl<W: LayoutElement> Column<W> {
     }
 
     fn focus_up(&mut self) -> bool {
+        // Split tiles are side by side, there's nothing above or below.
+        if self.is_split() {
+            return false;
+        }
+
         self.activate_idx(self.active_tile_idx.saturating_sub(1))
     }
 
     fn focus_down(&mut self) -> bool {
+        if self.is_split() {
+            return false;
+        }
+
         self.activate_idx(min(self.active_tile_idx + 1, self.tiles.len() - 1))
     }
 
@@ -4392,7 +5074,7 @@ impl<W: LayoutElement> Column<W> {
 
     fn move_up(&mut self) -> bool {
         let new_idx = self.active_tile_idx.saturating_sub(1);
-        if self.active_tile_idx == new_idx {
+        if self.active_tile_idx == new_idx || self.is_split() {
             return false;
         }
 
@@ -4415,7 +5097,7 @@ impl<W: LayoutElement> Column<W> {
 
     fn move_down(&mut self) -> bool {
         let new_idx = min(self.active_tile_idx + 1, self.tiles.len() - 1);
-        if self.active_tile_idx == new_idx {
+        if self.active_tile_idx == new_idx || self.is_split() {
             return false;
         }
 
@@ -4496,10 +5178,15 @@ impl<W: LayoutElement> Column<W> {
                 // intention behind the ability to set a fixed size.
                 let tile_idx = tile_idx.unwrap_or(self.active_tile_idx);
                 let tile = &self.tiles[tile_idx];
-                ColumnWidth::Fixed(
-                    tile.tile_width_for_window_width(f64::from(fixed))
-                        .clamp(1., MAX_PX),
-                )
+                let mut width = tile.tile_width_for_window_width(f64::from(fixed));
+
+                // In split mode, the window gets an equal share of the column width.
+                if self.is_split() {
+                    let count = self.tiles.len() as f64;
+                    width = width * count + self.options.gaps * (count - 1.);
+                }
+
+                ColumnWidth::Fixed(width.clamp(1., MAX_PX))
             }
             (_, SizeChange::SetProportion(proportion)) => {
                 ColumnWidth::Proportion((proportion / 100.).clamp(0., MAX_F))
@@ -4703,43 +5390,25 @@ impl<W: LayoutElement> Column<W> {
             return;
         }
 
-        // Animate the movement.
-        //
-        // We're doing some shortcuts here because we know that currently normal vs. tabbed can
-        // only cause a vertical shift + a shift to the origin.
-        //
-        // Doing it this way to avoid storing all tile positions in a vector. If more display modes
-        // are added it might be simpler to just collect everything into a smallvec.
-        let prev_origin = self.tiles_origin();
+        // Animate the movement. With the split display mode, tiles can move in both directions,
+        // so just remember where every tile was.
+        let prev_offsets: Vec<_> = self.tile_offsets().take(self.tiles.len()).collect();
+        let was_tabbed = self.display_mode == ColumnDisplay::Tabbed;
         self.display_mode = display;
-        let new_origin = self.tiles_origin();
-        let origin_delta = prev_origin - new_origin;
-
-        // When need to walk the tiles in the normal display mode to get the right offsets.
-        self.display_mode = ColumnDisplay::Normal;
-        for (tile, pos) in self.tiles_mut() {
-            let mut y_delta = pos.y - prev_origin.y;
 
-            // Invert the Y motion when transitioning *to* normal display mode.
-            if display == ColumnDisplay::Normal {
-                y_delta *= -1.;
-            }
-
-            let mut delta = origin_delta;
-            delta.y += y_delta;
-            tile.animate_move_from(delta);
+        for ((tile, pos), prev) in zip(self.tiles_mut(), prev_offsets) {
+            tile.animate_move_from(prev - pos);
         }
 
         // Animate the opacity.
-        for (idx, tile) in self.tiles.iter_mut().enumerate() {
-            let is_active = idx == self.active_tile_idx;
-            if !is_active {
-                let (from, to) = if display == ColumnDisplay::Tabbed {
-                    (1., 0.)
-                } else {
-                    (0., 1.)
-                };
-                tile.animate_alpha(from, to, self.options.animations.window_movement.0);
+        let is_tabbed = display == ColumnDisplay::Tabbed;
+        if was_tabbed != is_tabbed {
+            for (idx, tile) in self.tiles.iter_mut().enumerate() {
+                let is_active = idx == self.active_tile_idx;
+                if !is_active {
+                    let (from, to) = if is_tabbed { (1., 0.) } else { (0., 1.) };
+                    tile.animate_alpha(from, to, self.options.animations.window_movement.0);
+                }
             }
         }
 
@@ -4751,8 +5420,6 @@ impl<W: LayoutElement> Column<W> {
             );
         }
 
-        // Now switch the display mode for real.
-        self.display_mode = display;
         self.update_tile_sizes(true);
     }
 
@@ -4805,6 +5472,7 @@ impl<W: LayoutElement> Column<W> {
         let center = self.options.center_focused_column == CenterFocusedColumn::Always;
         let gaps = self.options.gaps;
         let tabbed = self.display_mode == ColumnDisplay::Tabbed;
+        let split = self.is_split();
 
         // Does not include extra size from the tab indicator.
         let tiles_width = self
@@ -4828,6 +5496,12 @@ impl<W: LayoutElement> Column<W> {
         data.map(move |data| {
             let mut pos = origin;
 
+            // Split tiles go side by side.
+            if split {
+                origin.x += data.size.w + gaps;
+                return pos;
+            }
+
             if center {
                 pos.x += (tiles_width - data.size.w) / 2.;
             } else if data.interactively_resizing_by_left_edge {
@@ -4970,6 +5644,7 @@ impl<W: LayoutElement> Column<W> {
         }
 
         let is_tabbed = self.display_mode == ColumnDisplay::Tabbed;
+        let is_split = self.is_split();
 
         let tile_count = self.tiles.len();
         if tile_count == 1 {
@@ -5037,6 +5712,7 @@ impl<W: LayoutElement> Column<W> {
         }
 
         if !is_tabbed
+            && !is_split
             && tile_count > 1
             && self.scale.round() == self.scale
             && working_size.h.round() == working_size.h
@@ -0,0 +1,325 @@
+//! Saving the layout to a file and restoring it for newly opened windows.
+//!
+//! A snapshot records, for every workspace, its columns with the app ids and sizes of their
+//! windows, and the floating windows with their positions. Restoring a snapshot doesn't move
+//! existing windows. Instead, it turns the snapshot into slots, and every window that opens while
+//! the restore is active takes the first free slot with the same app id.
+//!
+//! Column groups reuse the same slots for a few adjacent columns recalled onto one workspace.
+//! Recalling a group does move existing windows into the slots, and only the remaining slots wait
+//! for newly opened windows.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use directories::BaseDirs;
+use niri_config::PresetSize;
+use serde::{Deserialize, Serialize};
+use smithay::utils::{Logical, Point};
+
+/// How long a restore waits for windows to open before giving up on the remaining slots.
+pub const RESTORE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Saved layout of all workspaces.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayoutSnapshot {
+    pub workspaces: Vec<WorkspaceSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceSnapshot {
+    /// Name of the output that the workspace was on.
+    pub output: Option<String>,
+    /// Index of the workspace on its output.
+    pub idx: usize,
+    pub name: Option<String>,
+    pub columns: Vec<ColumnSnapshot>,
+    pub floating: Vec<WindowSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnSnapshot {
+    pub width: SnapshotWidth,
+    pub is_full_width: bool,
+    /// Windows from top to bottom.
+    pub windows: Vec<WindowSnapshot>,
+}
+
+/// Width of a saved column.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SnapshotWidth {
+    /// Proportion of the view width.
+    Proportion(f64),
+    /// Fixed window width in logical pixels.
+    Fixed(i32),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowSnapshot {
+    pub app_id: Option<String>,
+    /// Window size in logical pixels.
+    pub size: (i32, i32),
+    /// Position relative to the working area, only for floating windows.
+    pub pos: Option<(f64, f64)>,
+}
+
+/// Named group of adjacent columns.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnGroup {
+    pub columns: Vec<ColumnSnapshot>,
+}
+
+/// Place in the layout for one window from a snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestoreSlot {
+    /// Index of the workspace in the snapshot.
+    snapshot_ws_idx: usize,
+    /// Index of the column in the snapshot workspace, `None` for floating windows.
+    column_idx: Option<usize>,
+    /// Name of the output that the workspace was on.
+    pub output: Option<String>,
+    /// Index of the workspace on its output.
+    pub workspace_idx: usize,
+    pub workspace_name: Option<String>,
+    pub width: PresetSize,
+    pub height: PresetSize,
+    pub is_full_width: bool,
+    /// Position relative to the working area, only for floating windows.
+    pub floating_pos: Option<Point<f64, Logical>>,
+}
+
+/// Snapshot restore in progress.
+#[derive(Debug)]
+pub struct LayoutRestore<Id> {
+    /// Slots that no window has taken yet, in layout order, with the app id they match.
+    free: Vec<(String, RestoreSlot)>,
+    /// Windows placed so far, with the snapshot workspace and column they went to.
+    placed: Vec<(usize, usize, Id)>,
+}
+
+impl LayoutSnapshot {
+    /// Returns the path where snapshots are saved by default.
+    pub fn default_path() -> Option<PathBuf> {
+        let dirs = BaseDirs::new()?;
+        Some(dirs.state_dir()?.join("niri").join("layout-snapshot.json"))
+    }
+
+    /// Reads a snapshot from a file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json =
+            fs::read_to_string(path).map_err(|err| format!("error reading {path:?}: {err}"))?;
+        serde_json::from_str(&json).map_err(|err| format!("error parsing layout snapshot: {err}"))
+    }
+
+    /// Reads a snapshot from a file and moves the file out of the way, so that it's read once.
+    ///
+    /// The file is renamed to `<name>.restored.json` even if it fails to parse, so that a broken
+    /// snapshot isn't reported again on every startup. The renamed file can still be restored
+    /// manually.
+    pub fn take(path: &Path) -> Result<Self, String> {
+        let res = Self::load(path);
+
+        let restored = Self::restored_path(path);
+        if let Err(err) = fs::rename(path, &restored) {
+            warn!("error renaming {path:?} to {restored:?}: {err}");
+        }
+
+        res
+    }
+
+    /// Returns the path that [`LayoutSnapshot::take`] moves a snapshot to.
+    pub fn restored_path(path: &Path) -> PathBuf {
+        path.with_extension("restored.json")
+    }
+}
+
+impl RestoreSlot {
+    pub fn is_floating(&self) -> bool {
+        self.column_idx.is_none()
+    }
+}
+
+impl<Id> LayoutRestore<Id> {
+    pub fn new(snapshot: LayoutSnapshot) -> Self {
+        let mut free = Vec::new();
+
+        for (snapshot_ws_idx, ws) in snapshot.workspaces.into_iter().enumerate() {
+            let make_slot = |column_idx, window: &WindowSnapshot| {
+                let (w, h) = window.size;
+                RestoreSlot {
+                    snapshot_ws_idx,
+                    column_idx,
+                    output: ws.output.clone(),
+                    workspace_idx: ws.idx,
+                    workspace_name: ws.name.clone(),
+                    width: PresetSize::Fixed(w),
+                    height: PresetSize::Fixed(h),
+                    is_full_width: false,
+                    floating_pos: window.pos.map(Point::from),
+                }
+            };
+
+            for (column_idx, col) in ws.columns.iter().enumerate() {
+                for window in &col.windows {
+                    // Windows without an app id can't be told apart, so they aren't restored.
+                    let Some(app_id) = window.app_id.clone() else {
+                        continue;
+                    };
+
+                    let mut slot = make_slot(Some(column_idx), window);
+                    slot.is_full_width = col.is_full_width;
+                    if let SnapshotWidth::Proportion(prop) = col.width {
+                        slot.width = PresetSize::Proportion(prop);
+                    }
+                    free.push((app_id, slot));
+                }
+            }
+
+            for window in &ws.floating {
+                let Some(app_id) = window.app_id.clone() else {
+                    continue;
+                };
+
+                free.push((app_id, make_slot(None, window)));
+            }
+        }
+
+        Self {
+            free,
+            placed: Vec::new(),
+        }
+    }
+
+    /// Returns whether all slots have been taken.
+    pub fn is_done(&self) -> bool {
+        self.free.is_empty()
+    }
+
+    /// Returns app ids of the free slots in layout order.
+    pub fn free_app_ids(&self) -> impl Iterator<Item = &str> + '_ {
+        self.free.iter().map(|(app_id, _)| app_id.as_str())
+    }
+
+    /// Takes the first free slot for a window with this app id.
+    pub fn take_slot(&mut self, app_id: &str) -> Option<RestoreSlot> {
+        let idx = self.free.iter().position(|(id, _)| id == app_id)?;
+        Some(self.free.remove(idx).1)
+    }
+
+    /// Records that a window was placed into its slot.
+    pub fn window_placed(&mut self, slot: &RestoreSlot, id: Id) {
+        if let Some(column_idx) = slot.column_idx {
+            self.placed.push((slot.snapshot_ws_idx, column_idx, id));
+        }
+    }
+
+    /// Returns an already placed window to open the window for this slot next to.
+    ///
+    /// The returned flag is `true` if that window is in the same column, meaning that the new
+    /// window should be consumed into it. Otherwise, the returned window is in the closest column
+    /// to the left. Windows for which `exists` returns `false` are skipped.
+    pub fn anchor(&self, slot: &RestoreSlot, exists: impl Fn(&Id) -> bool) -> Option<(&Id, bool)> {
+        let column_idx = slot.column_idx?;
+
+        self.placed
+            .iter()
+            .filter(|(ws_idx, col_idx, id)| {
+                *ws_idx == slot.snapshot_ws_idx && *col_idx <= column_idx && exists(id)
+            })
+            .max_by_key(|(_, col_idx, _)| *col_idx)
+            .map(|(_, col_idx, id)| (id, *col_idx == column_idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xshell::Shell;
+
+    use super::*;
+
+    fn window(app_id: &str) -> WindowSnapshot {
+        WindowSnapshot {
+            app_id: Some(app_id.to_owned()),
+            size: (800, 600),
+            pos: None,
+        }
+    }
+
+    #[test]
+    fn slots_are_placed_next_to_their_columns() {
+        let snapshot = LayoutSnapshot {
+            workspaces: vec![WorkspaceSnapshot {
+                output: Some(String::from("DP-1")),
+                idx: 0,
+                name: None,
+                columns: vec![
+                    ColumnSnapshot {
+                        width: SnapshotWidth::Proportion(0.5),
+                        is_full_width: false,
+                        windows: vec![window("term"), window("term")],
+                    },
+                    ColumnSnapshot {
+                        width: SnapshotWidth::Fixed(800),
+                        is_full_width: false,
+                        windows: vec![window("browser")],
+                    },
+                ],
+                floating: vec![],
+            }],
+        };
+        let mut restore = LayoutRestore::new(snapshot);
+
+        let app_ids: Vec<_> = restore.free_app_ids().collect();
+        assert_eq!(app_ids, ["term", "term", "browser"]);
+        assert_eq!(restore.take_slot("editor"), None);
+
+        let first = restore.take_slot("term").unwrap();
+        assert_eq!(first.width, PresetSize::Proportion(0.5));
+        assert_eq!(restore.anchor(&first, |_| true), None);
+        restore.window_placed(&first, 1);
+
+        let browser = restore.take_slot("browser").unwrap();
+        assert_eq!(browser.width, PresetSize::Fixed(800));
+        assert_eq!(restore.anchor(&browser, |_| true), Some((&1, false)));
+        restore.window_placed(&browser, 2);
+
+        let second = restore.take_slot("term").unwrap();
+        assert_eq!(restore.anchor(&second, |_| true), Some((&1, true)));
+        assert_eq!(restore.anchor(&second, |id| *id != 1), None);
+
+        assert!(restore.is_done());
+    }
+
+    #[test]
+    fn take_renames_the_file() {
+        let sh = Shell::new().unwrap();
+        let temp_dir = sh.create_temp_dir().unwrap();
+        let path = temp_dir.path().join("layout-snapshot.json");
+        let restored = temp_dir.path().join("layout-snapshot.restored.json");
+        assert_eq!(LayoutSnapshot::restored_path(&path), restored);
+
+        let snapshot = LayoutSnapshot {
+            workspaces: vec![WorkspaceSnapshot {
+                output: None,
+                idx: 0,
+                name: Some(String::from("main")),
+                columns: vec![],
+                floating: vec![],
+            }],
+        };
+        fs::write(&path, serde_json::to_string(&snapshot).unwrap()).unwrap();
+
+        assert_eq!(LayoutSnapshot::take(&path).unwrap(), snapshot);
+        assert!(!path.exists());
+        assert_eq!(LayoutSnapshot::load(&restored).unwrap(), snapshot);
+
+        // Taking again fails since the file is gone.
+        assert!(LayoutSnapshot::take(&path).is_err());
+
+        // A broken snapshot is moved out of the way too.
+        fs::write(&path, "not json").unwrap();
+        assert!(LayoutSnapshot::take(&path).is_err());
+        assert!(!path.exists());
+    }
+}
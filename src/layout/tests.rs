@@ -1,8 +1,9 @@
 use std::cell::Cell;
+use std::collections::HashMap;
 
 use niri_config::{
-    FloatOrInt, OutputName, TabIndicatorLength, TabIndicatorPosition, WorkspaceName,
-    WorkspaceReference,
+    FloatOrInt, OutputName, ReservedSlotPosition, TabIndicatorLength, TabIndicatorPosition,
+    WorkspaceName, WorkspaceReference,
 };
 use proptest::prelude::*;
 use proptest_derive::Arbitrary;
@@ -31,6 +32,8 @@ struct TestWindowInner {
     is_fullscreen: Cell<bool>,
     is_windowed_fullscreen: Cell<bool>,
     is_pending_windowed_fullscreen: Cell<bool>,
+    keep_aspect_ratio: Cell<bool>,
+    rules: ResolvedWindowRules,
 }
 
 #[derive(Debug, Clone)]
@@ -47,6 +50,8 @@ struct TestWindowParams {
     bbox: Rectangle<i32, Logical>,
     #[proptest(strategy = "arbitrary_min_max_size()")]
     min_max_size: (Size<i32, Logical>, Size<i32, Logical>),
+    keep_aspect_ratio: bool,
+    skip_animations: bool,
 }
 
 impl TestWindowParams {
@@ -57,6 +62,8 @@ impl TestWindowParams {
             is_floating: false,
             bbox: Rectangle::from_size(Size::from((100, 200))),
             min_max_size: Default::default(),
+            keep_aspect_ratio: false,
+            skip_animations: false,
         }
     }
 }
@@ -76,6 +83,11 @@ impl TestWindow {
             is_fullscreen: Cell::new(false),
             is_windowed_fullscreen: Cell::new(false),
             is_pending_windowed_fullscreen: Cell::new(false),
+            keep_aspect_ratio: Cell::new(params.keep_aspect_ratio),
+            rules: ResolvedWindowRules {
+                skip_animations: params.skip_animations.then_some(true),
+                ..ResolvedWindowRules::empty()
+            },
         }))
     }
 
@@ -195,6 +207,10 @@ impl LayoutElement for TestWindow {
         false
     }
 
+    fn keeps_aspect_ratio(&self) -> bool {
+        self.0.keep_aspect_ratio.get()
+    }
+
     fn configure_intent(&self) -> ConfigureIntent {
         ConfigureIntent::CanSend
     }
@@ -240,8 +256,7 @@ impl LayoutElement for TestWindow {
     fn refresh(&self) {}
 
     fn rules(&self) -> &ResolvedWindowRules {
-        static EMPTY: ResolvedWindowRules = ResolvedWindowRules::empty();
-        &EMPTY
+        &self.0.rules
     }
 
     fn animation_snapshot(&self) -> Option<&LayoutElementRenderSnapshot> {
@@ -365,7 +380,15 @@ fn arbitrary_scroll_direction() -> impl Strategy<Value = ScrollDirection> {
 }
 
 fn arbitrary_column_display() -> impl Strategy<Value = ColumnDisplay> {
-    prop_oneof![Just(ColumnDisplay::Normal), Just(ColumnDisplay::Tabbed)]
+    prop_oneof![
+        Just(ColumnDisplay::Normal),
+        Just(ColumnDisplay::Tabbed),
+        Just(ColumnDisplay::Split)
+    ]
+}
+
+fn arbitrary_column_pin_edge() -> impl Strategy<Value = ColumnPinEdge> {
+    prop_oneof![Just(ColumnPinEdge::Left), Just(ColumnPinEdge::Right)]
 }
 
 #[derive(Debug, Clone, Copy, Arbitrary)]
@@ -459,6 +482,7 @@ enum Op {
     SwapWindowInDirection(#[proptest(strategy = "arbitrary_scroll_direction()")] ScrollDirection),
     ToggleColumnTabbedDisplay,
     SetColumnDisplay(#[proptest(strategy = "arbitrary_column_display()")] ColumnDisplay),
+    ToggleColumnPin(#[proptest(strategy = "arbitrary_column_pin_edge()")] ColumnPinEdge),
     CenterColumn,
     CenterWindow {
         #[proptest(strategy = "proptest::option::of(1..=5usize)")]
@@ -470,6 +494,9 @@ enum Op {
     FocusWorkspace(#[proptest(strategy = "0..=4usize")] usize),
     FocusWorkspaceAutoBackAndForth(#[proptest(strategy = "0..=4usize")] usize),
     FocusWorkspacePrevious,
+    PeekWorkspaceDown,
+    PeekWorkspaceUp,
+    EndWorkspacePeek,
     MoveWindowToWorkspaceDown,
     MoveWindowToWorkspaceUp,
     MoveWindowToWorkspace {
@@ -548,6 +575,7 @@ enum Op {
         id: Option<usize>,
     },
     ExpandColumnToAvailableWidth,
+    FitColumnToContent,
     ToggleWindowFloating {
         #[proptest(strategy = "proptest::option::of(1..=5usize)")]
         id: Option<usize>,
@@ -583,6 +611,7 @@ enum Op {
         #[proptest(strategy = "arbitrary_msec_delta()")]
         msec_delta: i32,
     },
+    DropRenderSnapshots,
     MoveWorkspaceToOutput(#[proptest(strategy = "1..=5usize")] usize),
     ViewOffsetGestureBegin {
         #[proptest(strategy = "1..=5usize")]
@@ -769,6 +798,8 @@ impl Op {
                 layout.ensure_named_workspace(&WorkspaceConfig {
                     name: WorkspaceName(format!("ws{ws_name}")),
                     open_on_output: output_name.map(|name| format!("output{name}")),
+                    wallpaper: None,
+                    reserved_slots: vec![],
                 });
             }
             Op::UnnameWorkspace { ws_name } => {
@@ -1064,6 +1095,7 @@ impl Op {
             Op::SwapWindowInDirection(direction) => layout.swap_window_in_direction(direction),
             Op::ToggleColumnTabbedDisplay => layout.toggle_column_tabbed_display(),
             Op::SetColumnDisplay(display) => layout.set_column_display(display),
+            Op::ToggleColumnPin(edge) => layout.toggle_column_pin(edge),
             Op::CenterColumn => layout.center_column(),
             Op::CenterWindow { id } => {
                 let id = id.filter(|id| layout.has_window(id));
@@ -1077,6 +1109,9 @@ impl Op {
                 layout.switch_workspace_auto_back_and_forth(idx)
             }
             Op::FocusWorkspacePrevious => layout.switch_workspace_previous(),
+            Op::PeekWorkspaceDown => layout.peek_workspace_down(),
+            Op::PeekWorkspaceUp => layout.peek_workspace_up(),
+            Op::EndWorkspacePeek => layout.end_workspace_peek(),
             Op::MoveWindowToWorkspaceDown => layout.move_to_workspace_down(),
             Op::MoveWindowToWorkspaceUp => layout.move_to_workspace_up(),
             Op::MoveWindowToWorkspace {
@@ -1219,6 +1254,7 @@ impl Op {
                 layout.reset_window_height(id.as_ref());
             }
             Op::ExpandColumnToAvailableWidth => layout.expand_column_to_available_width(),
+            Op::FitColumnToContent => layout.fit_column_to_content(),
             Op::ToggleWindowFloating { id } => {
                 let id = id.filter(|id| layout.has_window(id));
                 layout.toggle_window_floating(id.as_ref());
@@ -1360,6 +1396,9 @@ impl Op {
                 layout.clock.set_unadjusted(now);
                 layout.advance_animations();
             }
+            Op::DropRenderSnapshots => {
+                layout.drop_render_snapshots();
+            }
             Op::MoveWorkspaceToOutput(id) => {
                 let name = format!("output{id}");
                 let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
@@ -1975,6 +2014,71 @@ fn workspaces_update_original_output_on_moving_to_same_monitor() {
     assert_eq!(mon.output_name(), "output2");
 }
 
+#[test]
+fn reconnecting_output_on_other_connector_restores_workspaces() {
+    let make_output = |connector: &str| {
+        let output = Output::new(
+            connector.to_owned(),
+            PhysicalProperties {
+                size: Size::from((1280, 720)),
+                subpixel: Subpixel::Unknown,
+                make: String::from("Make"),
+                model: String::from("Model"),
+            },
+        );
+        output.change_current_state(
+            Some(Mode {
+                size: Size::from((1280, 720)),
+                refresh: 60000,
+            }),
+            None,
+            None,
+            None,
+        );
+        output.user_data().insert_if_missing(|| OutputName {
+            connector: connector.to_owned(),
+            make: Some(String::from("Make")),
+            model: Some(String::from("Model")),
+            serial: Some(String::from("Serial")),
+        });
+        output
+    };
+
+    let mut layout = check_ops(&[Op::AddOutput(1)]);
+
+    let output = make_output("DP-1");
+    layout.add_output(output.clone());
+    layout.focus_output(&output);
+    let ops = [
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FocusWorkspaceDown,
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+    ];
+    for op in ops {
+        op.apply(&mut layout);
+        layout.verify_invariants();
+    }
+
+    layout.remove_output(&output);
+    layout.verify_invariants();
+
+    // The same monitor comes back on a different connector.
+    let output = make_output("DP-2");
+    layout.add_output(output.clone());
+    layout.verify_invariants();
+
+    let mon = layout.monitor_for_output(&output).unwrap();
+    assert_eq!(mon.workspaces.len(), 3);
+    assert!(mon.workspaces[0].has_window(&1));
+    assert!(mon.workspaces[1].has_window(&2));
+    // The previously active workspace is active again.
+    assert_eq!(mon.active_workspace_idx, 1);
+}
+
 #[test]
 fn large_negative_height_change() {
     let ops = [
@@ -2114,76 +2218,172 @@ fn move_workspace_to_output() {
 }
 
 #[test]
-fn fullscreen() {
+fn move_workspace_to_output_animates_both_outputs() {
     let ops = [
         Op::AddOutput(1),
+        Op::AddOutput(2),
+        Op::FocusOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::FocusWorkspaceDown,
         Op::AddWindow {
             params: TestWindowParams::new(1),
         },
-        Op::FullscreenWindow(1),
+        Op::FocusWorkspaceDown,
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::FocusWorkspaceUp,
+        Op::AdvanceAnimations { msec_delta: 1000 },
+        Op::MoveWorkspaceToOutput(2),
     ];
 
-    check_ops(&ops);
+    let mut layout = check_ops(&ops);
+
+    let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+        unreachable!()
+    };
+
+    // The source output slides from the workspace below to the one above.
+    assert_eq!(monitors[0].active_workspace_idx, 0);
+    assert_eq!(monitors[0].workspace_render_idx(), 1.);
+    // The target output slides from its old workspace to the moved one.
+    assert_eq!(monitors[1].active_workspace_idx, 0);
+    assert_eq!(monitors[1].workspace_render_idx(), 1.);
+    assert!(monitors[1].workspaces[0].has_window(&1));
+
+    Op::AdvanceAnimations { msec_delta: 1000 }.apply(&mut layout);
+    assert!(!layout.are_animations_ongoing(None));
 }
 
 #[test]
-fn unfullscreen_window_in_column() {
+fn move_workspace_to_output_by_name_animates_both_outputs() {
     let ops = [
         Op::AddOutput(1),
+        Op::AddOutput(2),
+        Op::FocusOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::FocusWorkspaceDown,
         Op::AddWindow {
             params: TestWindowParams::new(1),
         },
+        Op::SetWorkspaceName {
+            new_ws_name: 1,
+            ws_name: None,
+        },
+        Op::FocusWorkspaceDown,
         Op::AddWindow {
             params: TestWindowParams::new(2),
         },
-        Op::ConsumeOrExpelWindowLeft { id: None },
-        Op::SetFullscreenWindow {
-            window: 2,
-            is_fullscreen: false,
+        Op::FocusWorkspaceUp,
+        Op::AdvanceAnimations { msec_delta: 1000 },
+        Op::MoveWorkspaceToMonitor {
+            ws_name: Some(1),
+            output_id: 2,
         },
     ];
 
-    check_ops(&ops);
+    let mut layout = check_ops(&ops);
+
+    let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+        unreachable!()
+    };
+
+    // Same as moving the active workspace without a name.
+    assert_eq!(monitors[0].active_workspace_idx, 0);
+    assert_eq!(monitors[0].workspace_render_idx(), 1.);
+    assert_eq!(monitors[1].active_workspace_idx, 0);
+    assert_eq!(monitors[1].workspace_render_idx(), 1.);
+    assert!(monitors[1].workspaces[0].has_window(&1));
+
+    Op::AdvanceAnimations { msec_delta: 1000 }.apply(&mut layout);
+    assert!(!layout.are_animations_ongoing(None));
 }
 
 #[test]
-fn open_right_of_on_different_workspace() {
+fn move_inactive_workspace_to_output_by_name_does_not_animate() {
     let ops = [
         Op::AddOutput(1),
+        Op::AddOutput(2),
+        Op::FocusOutput(1),
         Op::AddWindow {
-            params: TestWindowParams::new(1),
+            params: TestWindowParams::new(0),
         },
         Op::FocusWorkspaceDown,
         Op::AddWindow {
-            params: TestWindowParams::new(2),
+            params: TestWindowParams::new(1),
         },
-        Op::AddWindowNextTo {
-            params: TestWindowParams::new(3),
-            next_to_id: 1,
+        Op::SetWorkspaceName {
+            new_ws_name: 1,
+            ws_name: None,
+        },
+        Op::FocusWorkspaceUp,
+        Op::AdvanceAnimations { msec_delta: 1000 },
+        Op::MoveWorkspaceToMonitor {
+            ws_name: Some(1),
+            output_id: 2,
         },
     ];
 
     let layout = check_ops(&ops);
 
-    let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+    let MonitorSet::Normal {
+        monitors,
+        active_monitor_idx,
+        ..
+    } = &layout.monitor_set
+    else {
         unreachable!()
     };
 
-    let mon = monitors.into_iter().next().unwrap();
-    assert_eq!(
-        mon.active_workspace_idx, 1,
-        "the second workspace must remain active"
-    );
-    assert_eq!(
-        mon.workspaces[0].scrolling().active_column_idx(),
-        1,
-        "the new window must become active"
-    );
+    // Neither output changes its visible workspace.
+    assert_eq!(*active_monitor_idx, 0);
+    assert!(monitors[0].workspace_switch.is_none());
+    assert!(monitors[1].workspace_switch.is_none());
+    assert!(monitors[0].workspaces[0].has_window(&0));
+    // Inserted before the empty workspace, which stays active.
+    assert_eq!(monitors[1].active_workspace_idx, 1);
+    assert!(monitors[1].workspaces[0].has_window(&1));
 }
 
 #[test]
-// empty_workspace_above_first = true
-fn open_right_of_on_different_workspace_ewaf() {
+fn fullscreen() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FullscreenWindow(1),
+    ];
+
+    check_ops(&ops);
+}
+
+#[test]
+fn unfullscreen_window_in_column() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::ConsumeOrExpelWindowLeft { id: None },
+        Op::SetFullscreenWindow {
+            window: 2,
+            is_fullscreen: false,
+        },
+    ];
+
+    check_ops(&ops);
+}
+
+#[test]
+fn open_right_of_on_different_workspace() {
     let ops = [
         Op::AddOutput(1),
         Op::AddWindow {
@@ -2199,11 +2399,7 @@ fn open_right_of_on_different_workspace_ewaf() {
         },
     ];
 
-    let options = Options {
-        empty_workspace_above_first: true,
-        ..Default::default()
-    };
-    let layout = check_ops_with_options(options, &ops);
+    let layout = check_ops(&ops);
 
     let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
         unreachable!()
@@ -2211,66 +2407,84 @@ fn open_right_of_on_different_workspace_ewaf() {
 
     let mon = monitors.into_iter().next().unwrap();
     assert_eq!(
-        mon.active_workspace_idx, 2,
+        mon.active_workspace_idx, 1,
         "the second workspace must remain active"
     );
     assert_eq!(
-        mon.workspaces[1].scrolling().active_column_idx(),
+        mon.workspaces[0].scrolling().active_column_idx(),
         1,
         "the new window must become active"
     );
 }
 
 #[test]
-fn unfullscreen_view_offset_not_reset_on_removal() {
+fn window_geometries_on_visible_and_hidden_workspaces() {
     let ops = [
         Op::AddOutput(1),
         Op::AddWindow {
             params: TestWindowParams::new(0),
         },
-        Op::FullscreenWindow(0),
+        Op::FocusWorkspaceDown,
         Op::AddWindow {
             params: TestWindowParams::new(1),
         },
-        Op::ConsumeOrExpelWindowRight { id: None },
+        Op::Communicate(0),
+        Op::Communicate(1),
+        Op::AdvanceAnimations { msec_delta: 1000 },
     ];
+    let layout = check_ops(&ops);
 
-    check_ops(&ops);
+    let mut geometries = HashMap::new();
+    layout.with_window_geometries(|win, geo, output_geo| {
+        geometries.insert(*win.id(), (geo, output_geo));
+    });
+
+    // The window on the inactive workspace isn't on screen.
+    let (_, output_geo) = geometries[&0];
+    assert_eq!(output_geo, None);
+
+    // The active workspace fills the output, so both geometries match.
+    let (geo, output_geo) = geometries[&1];
+    assert_eq!(output_geo, Some(geo));
 }
 
 #[test]
-fn unfullscreen_view_offset_not_reset_on_consume() {
+fn swallowed_window_returns_to_its_place() {
     let ops = [
         Op::AddOutput(1),
         Op::AddWindow {
             params: TestWindowParams::new(0),
         },
-        Op::FullscreenWindow(0),
         Op::AddWindow {
             params: TestWindowParams::new(1),
         },
-        Op::ConsumeWindowIntoColumn,
-    ];
-
-    check_ops(&ops);
-}
-
-#[test]
-fn unfullscreen_view_offset_not_reset_on_quick_double_toggle() {
-    let ops = [
-        Op::AddOutput(1),
         Op::AddWindow {
-            params: TestWindowParams::new(0),
+            params: TestWindowParams::new(2),
         },
-        Op::FullscreenWindow(0),
-        Op::FullscreenWindow(0),
     ];
+    let mut layout = check_ops(&ops);
 
-    check_ops(&ops);
+    let order = |layout: &Layout<TestWindow>| {
+        let (_, _, ws) = layout.workspaces().next().unwrap();
+        ws.scrolling()
+            .tiles()
+            .map(|t| t.window().0.id)
+            .collect::<Vec<_>>()
+    };
+
+    let swallowed = layout.swallow_window(&0, &2).unwrap();
+    layout.verify_invariants();
+    assert_eq!(swallowed.0.id, 0);
+    assert_eq!(order(&layout), [2, 1]);
+
+    let child = layout.replace_window(&2, swallowed).unwrap();
+    layout.verify_invariants();
+    assert_eq!(child.0.id, 2);
+    assert_eq!(order(&layout), [0, 1]);
 }
 
 #[test]
-fn unfullscreen_view_offset_set_on_fullscreening_inactive_tile_in_column() {
+fn only_tiled_windows_can_be_replaced() {
     let ops = [
         Op::AddOutput(1),
         Op::AddWindow {
@@ -2279,57 +2493,437 @@ fn unfullscreen_view_offset_set_on_fullscreening_inactive_tile_in_column() {
         Op::AddWindow {
             params: TestWindowParams::new(1),
         },
-        Op::ConsumeOrExpelWindowLeft { id: None },
-        Op::FullscreenWindow(0),
+        Op::ToggleWindowFloating { id: Some(1) },
     ];
+    let mut layout = check_ops(&ops);
 
-    check_ops(&ops);
+    assert!(layout.can_replace_window(&0));
+    assert!(!layout.can_replace_window(&1));
+    assert!(!layout.can_replace_window(&2));
+
+    // A floating slot gives the window back.
+    let window = TestWindow::new(TestWindowParams::new(2));
+    let window = layout.replace_window(&1, window).unwrap_err();
+    assert_eq!(window.0.id, 2);
+    layout.verify_invariants();
 }
 
 #[test]
-fn unfullscreen_view_offset_not_reset_on_gesture() {
+fn dnd_at_edge_switches_workspace_after_dwell() {
     let ops = [
         Op::AddOutput(1),
         Op::AddWindow {
             params: TestWindowParams::new(0),
         },
+        Op::FocusWorkspaceDown,
         Op::AddWindow {
             params: TestWindowParams::new(1),
         },
-        Op::FullscreenWindow(1),
-        Op::ViewOffsetGestureBegin {
+        Op::FocusWorkspaceUp,
+        Op::DndUpdate {
             output_idx: 1,
-            workspace_idx: None,
-            is_touchpad: true,
-        },
-        Op::ViewOffsetGestureEnd {
-            is_touchpad: Some(true),
+            px: 640.,
+            py: 710.,
         },
+        Op::AdvanceAnimations { msec_delta: 0 },
+        Op::AdvanceAnimations { msec_delta: 300 },
     ];
+    let mut layout = check_ops(&ops);
 
-    check_ops(&ops);
+    let active_idx = |layout: &Layout<TestWindow>| {
+        let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+            unreachable!()
+        };
+        monitors[0].active_workspace_idx
+    };
+
+    // Still dwelling at the bottom edge.
+    assert_eq!(active_idx(&layout), 0);
+
+    Op::AdvanceAnimations { msec_delta: 300 }.apply(&mut layout);
+    layout.verify_invariants();
+    assert_eq!(active_idx(&layout), 1);
+
+    // Leaving the edge stops the switching.
+    Op::DndUpdate {
+        output_idx: 1,
+        px: 640.,
+        py: 360.,
+    }
+    .apply(&mut layout);
+    Op::AdvanceAnimations { msec_delta: 1000 }.apply(&mut layout);
+    layout.dnd_end();
+    layout.verify_invariants();
+    assert_eq!(active_idx(&layout), 1);
 }
 
 #[test]
-fn removing_all_outputs_preserves_empty_named_workspaces() {
+fn reserved_slot_window_opens_in_its_slot() {
+    let options = Options {
+        reserved_slots: vec![(
+            String::from("ws1"),
+            ReservedSlot {
+                app_id: String::from("app"),
+                position: ReservedSlotPosition::First,
+            },
+        )],
+        ..Default::default()
+    };
     let ops = [
         Op::AddOutput(1),
         Op::AddNamedWorkspace {
             ws_name: 1,
             output_name: None,
         },
-        Op::AddNamedWorkspace {
-            ws_name: 2,
-            output_name: None,
+        Op::AddWindowToNamedWorkspace {
+            params: TestWindowParams::new(1),
+            ws_name: 1,
+        },
+        Op::AddWindowToNamedWorkspace {
+            params: TestWindowParams::new(2),
+            ws_name: 1,
         },
-        Op::RemoveOutput(1),
     ];
 
-    let layout = check_ops(&ops);
+    let mut layout = check_ops_with_options(options, &ops);
+    let ws_id = layout
+        .workspace_with_free_reserved_slot("app")
+        .unwrap()
+        .id();
+
+    layout.add_window(
+        TestWindow::new(TestWindowParams::new(3)),
+        AddWindowTarget::ReservedSlot {
+            workspace: ws_id,
+            app_id: "app",
+        },
+        None,
+        None,
+        false,
+        false,
+        ActivateWindow::default(),
+    );
+    layout.verify_invariants();
 
-    let MonitorSet::NoOutputs { workspaces } = layout.monitor_set else {
-        unreachable!()
-    };
+    assert!(layout.workspace_with_free_reserved_slot("app").is_none());
+    let (_, _, ws) = layout
+        .workspaces()
+        .find(|(_, _, ws)| ws.id() == ws_id)
+        .unwrap();
+    let first = ws.scrolling().tiles().next().unwrap();
+    assert_eq!(first.window().0.id, 3);
+
+    // The slot frees up when its window closes.
+    layout.remove_window(&3, Transaction::new());
+    assert!(layout.workspace_with_free_reserved_slot("app").is_some());
+}
+
+#[test]
+fn workspace_peek_springs_back() {
+    let mut ops = vec![
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FocusWorkspaceDown,
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::FocusWorkspaceUp,
+        Op::AdvanceAnimations { msec_delta: 1000 },
+        Op::PeekWorkspaceDown,
+        Op::AdvanceAnimations { msec_delta: 1000 },
+    ];
+
+    let layout = check_ops(&ops);
+    let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+        unreachable!()
+    };
+    let mon = &monitors[0];
+    assert_eq!(
+        mon.active_workspace_idx, 0,
+        "peeking must not switch workspaces"
+    );
+    assert_eq!(mon.workspace_render_idx(), 0.2);
+
+    ops.extend([
+        Op::EndWorkspacePeek,
+        Op::AdvanceAnimations { msec_delta: 1000 },
+    ]);
+
+    let layout = check_ops(&ops);
+    let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+        unreachable!()
+    };
+    let mon = &monitors[0];
+    assert_eq!(mon.active_workspace_idx, 0);
+    assert!(mon.workspace_switch.is_none());
+}
+
+#[test]
+fn workspace_peek_commits_on_switch() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::PeekWorkspaceDown,
+        Op::FocusWorkspaceDown,
+        Op::EndWorkspacePeek,
+        Op::AdvanceAnimations { msec_delta: 1000 },
+    ];
+
+    let layout = check_ops(&ops);
+    let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+        unreachable!()
+    };
+    assert_eq!(monitors[0].active_workspace_idx, 1);
+}
+
+#[test]
+// empty_workspace_above_first = true
+fn open_right_of_on_different_workspace_ewaf() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FocusWorkspaceDown,
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::AddWindowNextTo {
+            params: TestWindowParams::new(3),
+            next_to_id: 1,
+        },
+    ];
+
+    let options = Options {
+        empty_workspace_above_first: true,
+        ..Default::default()
+    };
+    let layout = check_ops_with_options(options, &ops);
+
+    let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+        unreachable!()
+    };
+
+    let mon = monitors.into_iter().next().unwrap();
+    assert_eq!(
+        mon.active_workspace_idx, 2,
+        "the second workspace must remain active"
+    );
+    assert_eq!(
+        mon.workspaces[1].scrolling().active_column_idx(),
+        1,
+        "the new window must become active"
+    );
+}
+
+#[test]
+fn unfullscreen_view_offset_not_reset_on_removal() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::FullscreenWindow(0),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::ConsumeOrExpelWindowRight { id: None },
+    ];
+
+    check_ops(&ops);
+}
+
+#[test]
+fn unfullscreen_view_offset_not_reset_on_consume() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::FullscreenWindow(0),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::ConsumeWindowIntoColumn,
+    ];
+
+    check_ops(&ops);
+}
+
+#[test]
+fn unfullscreen_view_offset_not_reset_on_quick_double_toggle() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::FullscreenWindow(0),
+        Op::FullscreenWindow(0),
+    ];
+
+    check_ops(&ops);
+}
+
+#[test]
+fn unfullscreen_view_offset_set_on_fullscreening_inactive_tile_in_column() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::ConsumeOrExpelWindowLeft { id: None },
+        Op::FullscreenWindow(0),
+    ];
+
+    check_ops(&ops);
+}
+
+#[test]
+fn unfullscreen_view_offset_not_reset_on_gesture() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FullscreenWindow(1),
+        Op::ViewOffsetGestureBegin {
+            output_idx: 1,
+            workspace_idx: None,
+            is_touchpad: true,
+        },
+        Op::ViewOffsetGestureEnd {
+            is_touchpad: Some(true),
+        },
+    ];
+
+    check_ops(&ops);
+}
+
+#[test]
+fn view_offset_reanchored_on_move_to_narrower_output() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddScaledOutput { id: 2, scale: 2. },
+        Op::FocusOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(4),
+        },
+        Op::FocusColumnLeft,
+        Op::FocusColumnLeft,
+    ];
+
+    let options = Options {
+        gaps: 0.,
+        default_column_width: Some(PresetSize::Proportion(0.25)),
+        ..Default::default()
+    };
+    let mut layout = check_ops_with_options(options, &ops);
+
+    let relative_x = |layout: &Layout<TestWindow>| {
+        let (_, _, ws) = layout
+            .workspaces()
+            .find(|(_, _, ws)| ws.has_windows())
+            .unwrap();
+        -ws.scrolling().view_offset().target() / ws.view_size().w
+    };
+    assert_eq!(relative_x(&layout), 0.25);
+
+    Op::MoveWorkspaceToOutput(2).apply(&mut layout);
+    layout.verify_invariants();
+
+    assert_eq!(
+        relative_x(&layout),
+        0.25,
+        "the active column must stay at the same relative position"
+    );
+}
+
+#[test]
+fn view_offset_animation_retargeted_on_move_to_narrower_output() {
+    let mut ops = vec![Op::AddOutput(1), Op::AddScaledOutput { id: 2, scale: 2. }];
+    ops.push(Op::FocusOutput(1));
+    for id in 0..5 {
+        ops.push(Op::AddWindow {
+            params: TestWindowParams::new(id),
+        });
+    }
+    ops.extend([
+        Op::FocusColumnFirst,
+        Op::AdvanceAnimations { msec_delta: 1000 },
+        // Scrolls the view to the right edge.
+        Op::FocusColumnLast,
+    ]);
+
+    let options = Options {
+        gaps: 0.,
+        default_column_width: Some(PresetSize::Proportion(0.25)),
+        ..Default::default()
+    };
+    let mut layout = check_ops_with_options(options, &ops);
+
+    let view_offset = |layout: &Layout<TestWindow>| {
+        let (_, _, ws) = layout
+            .workspaces()
+            .find(|(_, _, ws)| ws.has_windows())
+            .unwrap();
+        let view_offset = ws.scrolling().view_offset();
+        let relative_x = -view_offset.target() / ws.view_size().w;
+        (view_offset.is_animation_ongoing(), relative_x)
+    };
+    assert_eq!(view_offset(&layout), (true, 0.75));
+
+    Op::MoveWorkspaceToOutput(2).apply(&mut layout);
+    layout.verify_invariants();
+
+    assert_eq!(
+        view_offset(&layout),
+        (true, 0.75),
+        "the animation must keep going towards the re-anchored target"
+    );
+}
+
+#[test]
+fn removing_all_outputs_preserves_empty_named_workspaces() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddNamedWorkspace {
+            ws_name: 1,
+            output_name: None,
+        },
+        Op::AddNamedWorkspace {
+            ws_name: 2,
+            output_name: None,
+        },
+        Op::RemoveOutput(1),
+    ];
+
+    let layout = check_ops(&ops);
+
+    let MonitorSet::NoOutputs { workspaces } = layout.monitor_set else {
+        unreachable!()
+    };
 
     assert_eq!(workspaces.len(), 2);
 }
@@ -2530,6 +3124,65 @@ fn start_interactive_move_then_remove_window() {
     check_ops(&ops);
 }
 
+#[test]
+fn interactive_move_floating_onto_column_gap() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams {
+                is_floating: true,
+                ..TestWindowParams::new(2)
+            },
+        },
+        Op::Communicate(0),
+        Op::Communicate(1),
+        Op::Communicate(2),
+        Op::AdvanceAnimations { msec_delta: 1000 },
+    ];
+    let mut layout = check_ops(&ops);
+
+    // Find the middle of the gap between the two columns.
+    let ws = layout.active_workspace().unwrap();
+    let gap_x = ws
+        .tiles_with_render_positions()
+        .find(|(tile, _, _)| *tile.window().id() == 1)
+        .map(|(_, pos, _)| pos.x - ws.options.gaps / 2.)
+        .unwrap();
+
+    let ops = [
+        Op::InteractiveMoveBegin {
+            window: 2,
+            output_idx: 1,
+            px: 0.,
+            py: 0.,
+        },
+        Op::InteractiveMoveUpdate {
+            window: 2,
+            dx: gap_x,
+            dy: 100.,
+            output_idx: 1,
+            px: gap_x,
+            py: 100.,
+        },
+        Op::InteractiveMoveEnd { window: 2 },
+    ];
+    for op in ops {
+        op.apply(&mut layout);
+        layout.verify_invariants();
+    }
+
+    let ws = layout.active_workspace().unwrap();
+    assert!(!ws.is_floating(&2));
+    let ids: Vec<_> = ws.tiles().map(|tile| *tile.window().id()).collect();
+    assert_eq!(ids, [0, 2, 1]);
+}
+
 #[test]
 fn interactive_move_onto_empty_output() {
     let ops = [
@@ -3206,6 +3859,268 @@ fn preset_column_width_fixed_correct_with_border() {
     assert_eq!(win.requested_size().unwrap().w, 500);
 }
 
+#[test]
+fn floating_aspect_ratio_does_not_drift() {
+    let mut params = TestWindowParams::new(1);
+    params.is_floating = true;
+    params.keep_aspect_ratio = true;
+    params.bbox = Rectangle::from_size(Size::from((300, 200)));
+
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow { params },
+        Op::SetWindowWidth {
+            id: None,
+            change: SizeChange::SetFixed(301),
+        },
+        Op::Communicate(1),
+        Op::SetWindowWidth {
+            id: None,
+            change: SizeChange::SetFixed(600),
+        },
+    ];
+    let layout = check_ops(&ops);
+
+    // Re-deriving the ratio from the rounded 301x201 size would give a height of 401.
+    let win = layout.windows().next().unwrap().1;
+    assert_eq!(win.requested_size().unwrap(), Size::from((600, 400)));
+}
+
+#[test]
+fn floating_aspect_ratio_is_taken_when_locked() {
+    let mut params = TestWindowParams::new(1);
+    params.is_floating = true;
+    params.bbox = Rectangle::from_size(Size::from((300, 200)));
+
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow { params },
+        Op::SetWindowHeight {
+            id: None,
+            change: SizeChange::SetFixed(300),
+        },
+        Op::Communicate(1),
+    ];
+    let mut layout = check_ops(&ops);
+
+    // Lock the ratio at the current 300x300 size.
+    let win = layout.windows().next().unwrap().1.clone();
+    win.0.keep_aspect_ratio.set(true);
+    layout.refresh(true);
+
+    layout.set_window_width(None, SizeChange::SetFixed(500));
+    assert_eq!(win.requested_size().unwrap(), Size::from((500, 500)));
+
+    // Unlocking resizes the dimensions independently again.
+    win.0.keep_aspect_ratio.set(false);
+    layout.refresh(true);
+
+    layout.set_window_height(None, SizeChange::SetFixed(100));
+    assert_eq!(win.requested_size().unwrap(), Size::from((500, 100)));
+}
+
+#[test]
+fn move_floating_window_uses_floating_movement_animation() {
+    let options = Options {
+        animations: niri_config::Animations {
+            floating_window_movement: niri_config::FloatingWindowMovementAnim(
+                niri_config::Animation {
+                    off: false,
+                    kind: niri_config::AnimationKind::Easing(niri_config::EasingParams {
+                        duration_ms: 500,
+                        curve: niri_config::AnimationCurve::Linear,
+                    }),
+                },
+            ),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let mut params = TestWindowParams::new(1);
+    params.is_floating = true;
+
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow { params },
+        Op::AdvanceAnimations { msec_delta: 1000 },
+        Op::MoveFloatingWindow {
+            id: None,
+            x: PositionChange::AdjustFixed(100.),
+            y: PositionChange::AdjustFixed(0.),
+            animate: true,
+        },
+        Op::AdvanceAnimations { msec_delta: 250 },
+    ];
+    let mut layout = check_ops_with_options(options, &ops);
+
+    // Halfway through the linear animation, the tile is still half of the way behind.
+    let offset = |layout: &Layout<TestWindow>| {
+        let tile = layout.active_workspace().unwrap().tiles().next().unwrap();
+        tile.render_offset()
+    };
+    assert_eq!(offset(&layout), Point::from((-50., 0.)));
+
+    Op::AdvanceAnimations { msec_delta: 250 }.apply(&mut layout);
+    assert_eq!(offset(&layout), Point::from((0., 0.)));
+    assert!(!layout.are_animations_ongoing(None));
+
+    // Without animate, the tile jumps to the new position right away.
+    Op::MoveFloatingWindow {
+        id: None,
+        x: PositionChange::AdjustFixed(100.),
+        y: PositionChange::AdjustFixed(0.),
+        animate: false,
+    }
+    .apply(&mut layout);
+    assert_eq!(offset(&layout), Point::from((0., 0.)));
+    assert!(!layout.are_animations_ongoing(None));
+}
+
+#[test]
+fn skip_animations_rule_turns_off_tile_animations() {
+    let ops = |skip_animations| {
+        let mut params = TestWindowParams::new(1);
+        params.is_floating = true;
+        params.skip_animations = skip_animations;
+        [Op::AddOutput(1), Op::AddWindow { params }]
+    };
+
+    // A regular window animates opening.
+    let mut layout = check_ops(&ops(false));
+    layout.start_open_animation_for_window(&1);
+    assert!(layout.are_animations_ongoing(None));
+
+    // With the rule, the window shows up right away.
+    let mut layout = check_ops(&ops(true));
+    layout.start_open_animation_for_window(&1);
+    assert!(!layout.are_animations_ongoing(None));
+
+    // And it moves without an animation even when asked to animate.
+    Op::MoveFloatingWindow {
+        id: None,
+        x: PositionChange::AdjustFixed(100.),
+        y: PositionChange::AdjustFixed(0.),
+        animate: true,
+    }
+    .apply(&mut layout);
+    let tile = layout.active_workspace().unwrap().tiles().next().unwrap();
+    assert_eq!(tile.render_offset(), Point::from((0., 0.)));
+    assert!(!layout.are_animations_ongoing(None));
+}
+
+#[test]
+fn column_neighbor_window_only_for_lone_tiles() {
+    let mut floating = TestWindowParams::new(5);
+    floating.is_floating = true;
+
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(4),
+        },
+        Op::ConsumeOrExpelWindowLeft { id: None },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+        Op::AddWindow { params: floating },
+    ];
+    let layout = check_ops(&ops);
+
+    // Columns: [1, 4], [2], [3].
+    let neighbor = |id, direction| {
+        layout
+            .column_neighbor_window(&id, direction)
+            .map(|win| *win.id())
+    };
+    assert_eq!(neighbor(3, ScrollDirection::Left), Some(2));
+    assert_eq!(neighbor(2, ScrollDirection::Right), Some(3));
+    assert_eq!(neighbor(3, ScrollDirection::Right), None);
+
+    // Closing a window that shares its column keeps the focus in that column.
+    assert_eq!(neighbor(1, ScrollDirection::Right), None);
+    assert_eq!(neighbor(4, ScrollDirection::Right), None);
+
+    // Floating windows have no columns.
+    assert_eq!(neighbor(5, ScrollDirection::Left), None);
+}
+
+#[test]
+fn move_to_workspace_shrinks_toward_target_edge() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FocusWorkspaceDown,
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+    ];
+    let layout = check_ops(&ops);
+
+    // Workspaces: [1], [2], empty; the second one is active.
+    let mon = layout.active_monitor_ref().unwrap();
+    let view_size = layout.active_workspace().unwrap().view_size();
+    let top = Point::from((view_size.w / 2., 0.));
+    let bottom = Point::from((view_size.w / 2., view_size.h));
+
+    assert_eq!(mon.move_to_workspace_shrink_target(None, 0), Some((2, top)));
+    assert_eq!(
+        mon.move_to_workspace_shrink_target(Some(&2), 2),
+        Some((2, bottom))
+    );
+    // Indices past the end go to the last workspace.
+    assert_eq!(
+        mon.move_to_workspace_shrink_target(None, 10),
+        Some((2, bottom))
+    );
+
+    // Nothing to animate when staying on the workspace or moving a window that isn't visible.
+    assert_eq!(mon.move_to_workspace_shrink_target(None, 1), None);
+    assert_eq!(mon.move_to_workspace_shrink_target(Some(&1), 2), None);
+}
+
+#[test]
+fn fit_column_to_content_uses_window_size() {
+    let ops = [
+        Op::AddOutput(0),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::FitColumnToContent,
+    ];
+    let layout = check_ops(&ops);
+
+    // The window hasn't resized yet, so the column fits its initial width.
+    let win = layout.windows().next().unwrap().1;
+    assert_eq!(win.requested_size().unwrap().w, 100);
+
+    // The column fits its widest window, taking min size into account.
+    let mut params = TestWindowParams::new(1);
+    params.min_max_size.0 = Size::from((150, 0));
+    let ops = [
+        Op::AddOutput(0),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::AddWindow { params },
+        Op::ConsumeOrExpelWindowLeft { id: None },
+        Op::FitColumnToContent,
+    ];
+    let layout = check_ops(&ops);
+
+    for (_, win) in layout.windows() {
+        assert_eq!(win.requested_size().unwrap().w, 150);
+    }
+}
+
 #[test]
 fn preset_column_width_reset_after_set_width() {
     let ops = [
@@ -3230,6 +4145,46 @@ fn preset_column_width_reset_after_set_width() {
     assert_eq!(win.requested_size().unwrap().w, 500);
 }
 
+#[test]
+fn split_column_places_windows_side_by_side() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::ConsumeOrExpelWindowLeft { id: None },
+        Op::SetColumnDisplay(ColumnDisplay::Split),
+    ];
+
+    let options = Options {
+        gaps: 0.,
+        default_column_width: Some(PresetSize::Proportion(0.5)),
+        ..Default::default()
+    };
+    let mut layout = check_ops_with_options(options, &ops);
+
+    for (_, win) in layout.windows() {
+        assert_eq!(win.requested_size(), Some(Size::from((320, 720))));
+    }
+
+    let focus = |layout: &Layout<TestWindow>| *layout.focus().unwrap().id();
+    assert_eq!(focus(&layout), 1);
+
+    // Vertical navigation doesn't apply to side-by-side windows.
+    Op::FocusWindowUp.apply(&mut layout);
+    assert_eq!(focus(&layout), 1);
+
+    // Horizontal navigation goes through the windows in the column.
+    Op::FocusColumnLeft.apply(&mut layout);
+    assert_eq!(focus(&layout), 0);
+    Op::FocusColumnRight.apply(&mut layout);
+    assert_eq!(focus(&layout), 1);
+    layout.verify_invariants();
+}
+
 #[test]
 fn disable_tabbed_mode_in_fullscreen() {
     let ops = [
@@ -3249,6 +4204,65 @@ fn disable_tabbed_mode_in_fullscreen() {
     check_ops(&ops);
 }
 
+#[test]
+fn pinned_column_stays_at_edge() {
+    let ops = [
+        Op::AddOutput(0),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::FocusColumnLeft,
+        Op::ToggleColumnPin(ColumnPinEdge::Left),
+        // The pinned column doesn't move, and other columns cannot move past it.
+        Op::MoveColumnRight,
+        Op::FocusColumnRight,
+        Op::MoveColumnToFirst,
+        // New columns go after the pinned one.
+        Op::FocusColumnFirst,
+        Op::ExpelWindowFromColumn,
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+    ];
+
+    let layout = check_ops(&ops);
+    let ws = layout.active_workspace().unwrap();
+    let ids: Vec<_> = ws.tiles().map(|tile| *tile.window().id()).collect();
+    assert_eq!(ids[0], 1, "the pinned window must stay first");
+    assert_eq!(ws.scrolling().active_column_idx(), 1);
+
+    let ops = [
+        Op::AddOutput(0),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FocusColumnFirst,
+        Op::ToggleColumnPin(ColumnPinEdge::Right),
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::FullscreenWindow(0),
+    ];
+
+    let layout = check_ops(&ops);
+    let ws = layout.active_workspace().unwrap();
+    let ids: Vec<_> = ws.tiles().map(|tile| *tile.window().id()).collect();
+    assert_eq!(
+        ids,
+        [1, 2, 0],
+        "the new window must go before the pinned one"
+    );
+}
+
 #[test]
 fn unfullscreen_with_large_border() {
     let ops = [
@@ -1,7 +1,7 @@
 use core::f64;
 use std::rc::Rc;
 
-use niri_config::{Color, CornerRadius, GradientInterpolation};
+use niri_config::{Color, CornerRadius, FloatOrInt, GradientInterpolation, ShadowOffset};
 use smithay::backend::renderer::element::{Element, Kind};
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Size};
@@ -18,15 +18,22 @@ use crate::niri_render_elements;
 use crate::render_helpers::border::BorderRenderElement;
 use crate::render_helpers::clipped_surface::{ClippedSurfaceRenderElement, RoundedCornerDamage};
 use crate::render_helpers::damage::ExtraDamage;
+use crate::render_helpers::desaturate::Desaturation;
 use crate::render_helpers::offscreen::{OffscreenBuffer, OffscreenRenderElement};
+use crate::render_helpers::opacity_group::OpacityGroup;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::resize::ResizeRenderElement;
 use crate::render_helpers::shadow::ShadowRenderElement;
 use crate::render_helpers::snapshot::RenderSnapshot;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::render_helpers::urgency_flash::UrgencyFlashRenderElement;
 use crate::render_helpers::RenderTarget;
 use crate::utils::transaction::Transaction;
-use crate::utils::{baba_is_float_offset, round_logical_in_physical};
+use crate::utils::{baba_is_float_offset, round_logical_in_physical, ResizeEdge};
+use crate::window::ResolvedWindowRules;
+
+/// Opacity of windows that don't match the overview search.
+const FILTERED_OUT_ALPHA: f32 = 0.3;
 
 /// Toplevel window with decorations.
 #[derive(Debug)]
@@ -43,6 +50,12 @@ pub struct Tile<W: LayoutElement> {
     /// The shadow around the window.
     shadow: Shadow,
 
+    /// The glow around the focus ring.
+    focus_ring_glow: Shadow,
+
+    /// The flash around the window when it becomes urgent.
+    urgency_flash: UrgencyFlashRenderElement,
+
     /// Whether this tile is fullscreen.
     ///
     /// This will update only when the `window` actually goes fullscreen, rather than right away,
@@ -74,6 +87,11 @@ pub struct Tile<W: LayoutElement> {
     /// Currently selected preset height index when this tile is floating.
     pub(super) floating_preset_height_idx: Option<usize>,
 
+    /// Height to width ratio kept while resizing this tile when floating.
+    ///
+    /// Taken from the window size when its aspect ratio gets locked, and cleared when unlocked.
+    pub(super) floating_aspect_ratio: Option<f64>,
+
     /// The animation upon opening a window.
     open_animation: Option<OpenAnimation>,
 
@@ -89,6 +107,15 @@ pub struct Tile<W: LayoutElement> {
     /// The animation of the tile's opacity.
     pub(super) alpha_animation: Option<AlphaAnimation>,
 
+    /// Saturation requested by the window rules.
+    saturation: f64,
+
+    /// The animation of the tile's saturation towards the requested one.
+    saturation_animation: Option<Animation>,
+
+    /// Draws the tile with reduced saturation.
+    desaturation: Desaturation,
+
     /// Offset during the initial interactive move rubberband.
     pub(super) interactive_move_offset: Point<f64, Logical>,
 
@@ -122,6 +149,7 @@ niri_render_elements! {
         Resize = ResizeRenderElement,
         Border = BorderRenderElement,
         Shadow = ShadowRenderElement,
+        UrgencyFlash = UrgencyFlashRenderElement,
         ClippedSurface = ClippedSurfaceRenderElement<R>,
         Offscreen = OffscreenRenderElement,
         ExtraDamage = ExtraDamage,
@@ -135,6 +163,10 @@ pub type TileRenderSnapshot =
 struct ResizeAnimation {
     anim: Animation,
     size_from: Size<f64, Logical>,
+    /// Edges that move during the resize; the opposite edges stay put.
+    ///
+    /// Cropped resize contents are anchored to the edges that stay put.
+    edges: ResizeEdge,
     snapshot: LayoutElementRenderSnapshot,
     offscreen: OffscreenBuffer,
 }
@@ -154,7 +186,8 @@ pub(super) struct AlphaAnimation {
     /// semitransparent, then hold it at semitransparent for a while, until the operation
     /// completes.
     pub(super) hold_after_done: bool,
-    offscreen: OffscreenBuffer,
+    /// Fades the window together with its border and shadow.
+    group: OpacityGroup,
 }
 
 impl<W: LayoutElement> Tile<W> {
@@ -169,6 +202,7 @@ impl<W: LayoutElement> Tile<W> {
         let border_config = rules.border.resolve_against(options.border);
         let focus_ring_config = rules.focus_ring.resolve_against(options.focus_ring.into());
         let shadow_config = rules.shadow.resolve_against(options.shadow);
+        let saturation = rule_saturation(rules);
         let is_fullscreen = window.is_fullscreen();
 
         Self {
@@ -176,6 +210,8 @@ impl<W: LayoutElement> Tile<W> {
             border: FocusRing::new(border_config.into()),
             focus_ring: FocusRing::new(focus_ring_config.into()),
             shadow: Shadow::new(shadow_config),
+            focus_ring_glow: Shadow::new(niri_config::Shadow::default()),
+            urgency_flash: UrgencyFlashRenderElement::empty(),
             is_fullscreen,
             fullscreen_backdrop: SolidColorBuffer::new(view_size, [0., 0., 0., 1.]),
             unfullscreen_to_floating: false,
@@ -183,11 +219,15 @@ impl<W: LayoutElement> Tile<W> {
             floating_pos: None,
             floating_preset_width_idx: None,
             floating_preset_height_idx: None,
+            floating_aspect_ratio: None,
             open_animation: None,
             resize_animation: None,
             move_x_animation: None,
             move_y_animation: None,
             alpha_animation: None,
+            saturation,
+            saturation_animation: None,
+            desaturation: Desaturation::default(),
             interactive_move_offset: Point::from((0., 0.)),
             unmap_snapshot: None,
             rounded_corner_damage: Default::default(),
@@ -236,12 +276,17 @@ impl<W: LayoutElement> Tile<W> {
         self.border.update_shaders();
         self.focus_ring.update_shaders();
         self.shadow.update_shaders();
+        self.focus_ring_glow.update_shaders();
     }
 
     pub fn update_window(&mut self) {
         self.is_fullscreen = self.window.is_fullscreen();
 
         if let Some(animate_from) = self.window.take_animation_snapshot() {
+            // The last commit of an interactive resize may have already cleared the resize data,
+            // so keep the edges of the resize animation that it continues.
+            let mut edges = self.window.interactive_resize_data().map(|data| data.edges);
+
             let (size_from, offscreen) = if let Some(resize) = self.resize_animation.take() {
                 // Compute like in animated_window_size(), but using the snapshot geometry (since
                 // the current one is already overwritten).
@@ -253,6 +298,8 @@ impl<W: LayoutElement> Tile<W> {
                 size.w = size_from.w + (size.w - size_from.w) * val;
                 size.h = size_from.h + (size.h - size_from.h) * val;
 
+                edges = edges.or(Some(resize.edges));
+
                 // Also try to reuse the existing offscreen buffer if we have one.
                 (size, resize.offscreen)
             } else {
@@ -261,7 +308,7 @@ impl<W: LayoutElement> Tile<W> {
 
             let change = self.window.size().to_f64().to_point() - size_from.to_point();
             let change = f64::max(change.x.abs(), change.y.abs());
-            if change > RESIZE_ANIMATION_THRESHOLD {
+            if change > RESIZE_ANIMATION_THRESHOLD && !self.skips_animations() {
                 let anim = Animation::new(
                     self.clock.clone(),
                     0.,
@@ -272,6 +319,7 @@ impl<W: LayoutElement> Tile<W> {
                 self.resize_animation = Some(ResizeAnimation {
                     anim,
                     size_from,
+                    edges: edges.unwrap_or(ResizeEdge::BOTTOM_RIGHT),
                     snapshot: animate_from,
                     offscreen,
                 });
@@ -291,6 +339,19 @@ impl<W: LayoutElement> Tile<W> {
         let shadow_config = rules.shadow.resolve_against(self.options.shadow);
         self.shadow.update_config(shadow_config);
 
+        let saturation = rule_saturation(rules);
+        if saturation != self.saturation {
+            let from = self.saturation();
+            self.saturation = saturation;
+            self.saturation_animation = Some(Animation::new(
+                self.clock.clone(),
+                from,
+                saturation,
+                0.,
+                self.options.animations.saturation_change.0,
+            ));
+        }
+
         let window_size = self.window_size();
         let radius = rules
             .geometry_corner_radius
@@ -329,10 +390,18 @@ impl<W: LayoutElement> Tile<W> {
                 self.alpha_animation = None;
             }
         }
+
+        if let Some(anim) = &self.saturation_animation {
+            if anim.is_done() {
+                self.saturation_animation = None;
+            }
+        }
     }
 
     pub fn are_animations_ongoing(&self) -> bool {
-        self.are_transitions_ongoing() || self.window.rules().baba_is_float == Some(true)
+        self.are_transitions_ongoing()
+            || self.window.rules().baba_is_float == Some(true)
+            || self.window.urgency_flash().is_some()
     }
 
     pub fn are_transitions_ongoing(&self) -> bool {
@@ -340,12 +409,20 @@ impl<W: LayoutElement> Tile<W> {
             || self.resize_animation.is_some()
             || self.move_x_animation.is_some()
             || self.move_y_animation.is_some()
+            || self.saturation_animation.is_some()
             || self
                 .alpha_animation
                 .as_ref()
                 .is_some_and(|alpha| !alpha.anim.is_done())
     }
 
+    /// Returns the current saturation of the tile, taking the animation into account.
+    fn saturation(&self) -> f64 {
+        self.saturation_animation
+            .as_ref()
+            .map_or(self.saturation, |anim| anim.clamped_value().clamp(0., 1.))
+    }
+
     pub fn update_render_elements(&mut self, is_active: bool, view_rect: Rectangle<f64, Logical>) {
         let rules = self.window.rules();
 
@@ -391,11 +468,41 @@ impl<W: LayoutElement> Tile<W> {
             1.,
         );
 
+        if let Some((flash, intensity)) = self.window.urgency_flash() {
+            let width = flash.width.0;
+            let tile_size = self.animated_tile_size();
+            self.urgency_flash.update(
+                Size::from((tile_size.w + width * 2., tile_size.h + width * 2.)),
+                Rectangle::new(Point::from((width, width)), tile_size),
+                flash.color,
+                width as f32,
+                radius,
+                self.scale as f32,
+                intensity,
+            );
+        }
+
         let draw_focus_ring_with_background = if self.effective_border_width().is_some() {
             false
         } else {
             draw_border_with_background
         };
+        // The glow is a shadow of the focus ring outer edge, so it can use the same radius as the
+        // shadow above and be expanded by the ring width through the spread.
+        let glow_config = focus_ring_glow_config(
+            self.focus_ring.config(),
+            self.options.focus_ring.glow,
+            self.window.is_urgent(),
+        );
+        self.focus_ring_glow.update_config(glow_config);
+        self.focus_ring_glow.update_render_elements(
+            self.animated_tile_size(),
+            is_active,
+            radius,
+            self.scale,
+            1.,
+        );
+
         let radius = radius.expanded_by(self.focus_ring.width() as f32);
         self.focus_ring.update_render_elements(
             self.animated_tile_size(),
@@ -428,7 +535,16 @@ impl<W: LayoutElement> Tile<W> {
         offset
     }
 
+    /// Whether window rules turn off the open, close, resize and move animations of this window.
+    fn skips_animations(&self) -> bool {
+        self.window.rules().skip_animations == Some(true)
+    }
+
     pub fn start_open_animation(&mut self) {
+        if self.skips_animations() {
+            return;
+        }
+
         self.open_animation = Some(OpenAnimation::new(Animation::new(
             self.clock.clone(),
             0.,
@@ -452,18 +568,22 @@ impl<W: LayoutElement> Tile<W> {
     }
 
     pub fn animate_move_x_from_with_config(&mut self, from: f64, config: niri_config::Animation) {
-        let current_offset = self.render_offset().x;
+        if self.skips_animations() {
+            self.move_x_animation = None;
+            return;
+        }
 
-        // Preserve the previous config if ongoing.
-        let anim = self.move_x_animation.take().map(|move_| move_.anim);
-        let anim = anim
-            .map(|anim| anim.restarted(1., 0., 0.))
-            .unwrap_or_else(|| Animation::new(self.clock.clone(), 1., 0., 0., config));
+        let current_offset = self.render_offset().x;
+        let from = from + current_offset;
 
-        self.move_x_animation = Some(MoveAnimation {
-            anim,
-            from: from + current_offset,
+        // Preserve the previous config and velocity if ongoing.
+        let anim = self.move_x_animation.take().map(|move_| {
+            let velocity = move_velocity(&move_, from);
+            move_.anim.restarted(1., 0., velocity)
         });
+        let anim = anim.unwrap_or_else(|| Animation::new(self.clock.clone(), 1., 0., 0., config));
+
+        self.move_x_animation = Some(MoveAnimation { anim, from });
     }
 
     pub fn animate_move_y_from(&mut self, from: f64) {
@@ -471,18 +591,22 @@ impl<W: LayoutElement> Tile<W> {
     }
 
     pub fn animate_move_y_from_with_config(&mut self, from: f64, config: niri_config::Animation) {
-        let current_offset = self.render_offset().y;
+        if self.skips_animations() {
+            self.move_y_animation = None;
+            return;
+        }
 
-        // Preserve the previous config if ongoing.
-        let anim = self.move_y_animation.take().map(|move_| move_.anim);
-        let anim = anim
-            .map(|anim| anim.restarted(1., 0., 0.))
-            .unwrap_or_else(|| Animation::new(self.clock.clone(), 1., 0., 0., config));
+        let current_offset = self.render_offset().y;
+        let from = from + current_offset;
 
-        self.move_y_animation = Some(MoveAnimation {
-            anim,
-            from: from + current_offset,
+        // Preserve the previous config and velocity if ongoing.
+        let anim = self.move_y_animation.take().map(|move_| {
+            let velocity = move_velocity(&move_, from);
+            move_.anim.restarted(1., 0., velocity)
         });
+        let anim = anim.unwrap_or_else(|| Animation::new(self.clock.clone(), 1., 0., 0., config));
+
+        self.move_y_animation = Some(MoveAnimation { anim, from });
     }
 
     pub fn stop_move_animations(&mut self) {
@@ -494,16 +618,16 @@ impl<W: LayoutElement> Tile<W> {
         let from = from.clamp(0., 1.);
         let to = to.clamp(0., 1.);
 
-        let (current, offscreen) = if let Some(alpha) = self.alpha_animation.take() {
-            (alpha.anim.clamped_value(), alpha.offscreen)
+        let (current, group) = if let Some(alpha) = self.alpha_animation.take() {
+            (alpha.anim.clamped_value(), alpha.group)
         } else {
-            (from, OffscreenBuffer::default())
+            (from, OpacityGroup::default())
         };
 
         self.alpha_animation = Some(AlphaAnimation {
             anim: Animation::new(self.clock.clone(), current, to, 0., config),
             hold_after_done: false,
-            offscreen,
+            group,
         });
     }
 
@@ -531,6 +655,10 @@ impl<W: LayoutElement> Tile<W> {
         &mut self.window
     }
 
+    pub fn into_window(self) -> W {
+        self.window
+    }
+
     pub fn is_fullscreen(&self) -> bool {
         self.is_fullscreen
     }
@@ -827,11 +955,14 @@ impl<W: LayoutElement> Tile<W> {
 
         let scale = Scale::from(self.scale);
 
-        let win_alpha = if self.is_fullscreen || self.window.is_ignoring_opacity_window_rule() {
+        let mut win_alpha = if self.is_fullscreen || self.window.is_ignoring_opacity_window_rule() {
             1.
         } else {
             self.window.rules().opacity.unwrap_or(1.).clamp(0., 1.)
         };
+        if self.window.is_filtered_out() {
+            win_alpha *= FILTERED_OUT_ALPHA;
+        }
 
         // This is here rather than in render_offset() because render_offset() is currently assumed
         // by the code to be temporary. So, for example, interactive move will try to "grab" the
@@ -911,6 +1042,8 @@ impl<W: LayoutElement> Tile<W> {
                             window_size,
                             resize.anim.value() as f32,
                             resize.anim.clamped_value().clamp(0., 1.) as f32,
+                            rules.resize_content.unwrap_or_default(),
+                            resize.edges,
                             radius,
                             clip_to_geometry,
                             win_alpha,
@@ -1045,6 +1178,24 @@ impl<W: LayoutElement> Tile<W> {
         let elem = focus_ring.then(|| self.focus_ring.render(renderer, location).map(Into::into));
         let rv = rv.chain(elem.into_iter().flatten());
 
+        let elem = focus_ring.then(|| {
+            self.focus_ring_glow
+                .render(renderer, location)
+                .map(Into::into)
+        });
+        let rv = rv.chain(elem.into_iter().flatten());
+
+        let elem = self
+            .window
+            .urgency_flash()
+            .filter(|_| UrgencyFlashRenderElement::has_shader(renderer))
+            .map(|(flash, _)| {
+                let width = flash.width.0;
+                let loc = location - Point::from((width, width));
+                self.urgency_flash.clone().with_location(loc).into()
+            });
+        let rv = rv.chain(elem);
+
         rv.chain(self.shadow.render(renderer, location).map(Into::into))
     }
 
@@ -1064,7 +1215,10 @@ impl<W: LayoutElement> Tile<W> {
             .as_ref()
             .map_or(1., |alpha| alpha.anim.clamped_value()) as f32;
 
+        let saturation = self.saturation() as f32;
+
         let mut open_anim_elem = None;
+        let mut desaturated_elem = None;
         let mut alpha_anim_elem = None;
         let mut window_elems = None;
 
@@ -1090,15 +1244,31 @@ impl<W: LayoutElement> Tile<W> {
                     warn!("error rendering window opening animation: {err:?}");
                 }
             }
+        } else if saturation < 1. && Desaturation::has_shader(renderer) {
+            let renderer = renderer.as_gles_renderer();
+            let elements = self.render_inner(renderer, Point::from((0., 0.)), focus_ring, target);
+            let elements = elements.collect::<Vec<TileRenderElement<_>>>();
+            match self
+                .desaturation
+                .render(renderer, scale, location, saturation, tile_alpha, &elements)
+            {
+                Ok((elem, data)) => {
+                    self.window().set_offscreen_data(Some(data));
+                    desaturated_elem = Some(elem.into());
+                }
+                Err(err) => {
+                    warn!("error rendering tile to offscreen for desaturation: {err:?}");
+                }
+            }
         } else if let Some(alpha) = &self.alpha_animation {
             let renderer = renderer.as_gles_renderer();
             let elements = self.render_inner(renderer, Point::from((0., 0.)), focus_ring, target);
             let elements = elements.collect::<Vec<TileRenderElement<_>>>();
-            match alpha.offscreen.render(renderer, scale, &elements) {
-                Ok((elem, _sync, data)) => {
-                    let offset = elem.offset();
-                    let elem = elem.with_alpha(tile_alpha).with_offset(location + offset);
-
+            match alpha
+                .group
+                .render(renderer, scale, location, tile_alpha, &elements)
+            {
+                Ok((elem, data)) => {
                     self.window().set_offscreen_data(Some(data));
                     alpha_anim_elem = Some(elem.into());
                 }
@@ -1108,12 +1278,13 @@ impl<W: LayoutElement> Tile<W> {
             }
         }
 
-        if open_anim_elem.is_none() && alpha_anim_elem.is_none() {
+        if open_anim_elem.is_none() && desaturated_elem.is_none() && alpha_anim_elem.is_none() {
             window_elems = Some(self.render_inner(renderer, location, focus_ring, target));
         }
 
         open_anim_elem
             .into_iter()
+            .chain(desaturated_elem)
             .chain(alpha_anim_elem)
             .chain(window_elems.into_iter().flatten())
     }
@@ -1149,8 +1320,20 @@ impl<W: LayoutElement> Tile<W> {
         }
     }
 
+    /// Drops the textures rendered for animations, for example after a GPU reset.
+    ///
+    /// The running resize animation ends, since it draws from such a texture.
+    pub fn drop_render_snapshots(&mut self) {
+        self.resize_animation = None;
+        self.unmap_snapshot = None;
+        self.window.take_animation_snapshot();
+    }
+
     pub fn take_unmap_snapshot(&mut self) -> Option<TileRenderSnapshot> {
-        self.unmap_snapshot.take()
+        let snapshot = self.unmap_snapshot.take();
+
+        // Without a snapshot, the window closes without an animation.
+        snapshot.filter(|_| !self.skips_animations())
     }
 
     pub fn border(&self) -> &FocusRing {
@@ -1184,3 +1367,51 @@ impl<W: LayoutElement> Tile<W> {
         assert_abs_diff_eq!(size.h, rounded.h, epsilon = 1e-5);
     }
 }
+
+/// Returns the saturation that the window rules ask for, from 0 (grayscale) to 1 (unchanged).
+fn rule_saturation(rules: &ResolvedWindowRules) -> f64 {
+    rules.saturation.map_or(1., |x| f64::from(x).clamp(0., 1.))
+}
+
+/// Returns the shadow config that draws the glow around the focus ring.
+fn focus_ring_glow_config(
+    focus_ring: &niri_config::FocusRing,
+    glow: niri_config::FocusRingGlow,
+    is_urgent: bool,
+) -> niri_config::Shadow {
+    // For gradients, glow in the starting color.
+    let active = (focus_ring.active_gradient).map_or(focus_ring.active_color, |g| g.from);
+    let inactive = (focus_ring.inactive_gradient).map_or(focus_ring.inactive_color, |g| g.from);
+    let (active, inactive) = if is_urgent {
+        let urgent = (focus_ring.urgent_gradient).map_or(focus_ring.urgent_color, |g| g.from);
+        (urgent, urgent)
+    } else {
+        (active, inactive)
+    };
+    let intensity = glow.intensity.0 as f32;
+
+    niri_config::Shadow {
+        on: glow.on && !focus_ring.off,
+        offset: ShadowOffset {
+            x: FloatOrInt(0.),
+            y: FloatOrInt(0.),
+        },
+        softness: glow.radius,
+        spread: FloatOrInt(focus_ring.width.0),
+        draw_behind_window: false,
+        color: active * intensity,
+        inactive_color: Some(inactive * intensity),
+    }
+}
+
+/// Converts the velocity of an ongoing move animation for a restart with a new `from` offset.
+///
+/// The move animation goes from 1 to 0 and scales the offset, so the velocity in logical pixels
+/// has to be divided by the new offset.
+fn move_velocity(move_: &MoveAnimation, from: f64) -> f64 {
+    if from == 0. {
+        return 0.;
+    }
+
+    move_.from * move_.anim.velocity() / from
+}
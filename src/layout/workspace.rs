@@ -3,9 +3,11 @@ use std::rc::Rc;
 use std::time::Duration;
 
 use niri_config::{
-    CenterFocusedColumn, CornerRadius, OutputName, PresetSize, Workspace as WorkspaceConfig,
+    CenterFocusedColumn, CornerRadius, OutputName, PresetSize, ReservedSlot, ReservedSlotPosition,
+    Workspace as WorkspaceConfig,
 };
-use niri_ipc::{ColumnDisplay, PositionChange, SizeChange};
+use niri_ipc::{ColumnDisplay, ColumnPinEdge, PositionChange, SizeChange};
+use smithay::backend::renderer::element::Kind;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::desktop::{layer_map_for_output, Window};
 use smithay::output::Output;
@@ -23,18 +25,19 @@ use super::shadow::Shadow;
 use super::tile::{Tile, TileRenderSnapshot};
 use super::{
     ActivateWindow, HitType, InsertPosition, InteractiveResizeData, LayoutElement, Options,
-    RemovedTile, SizeFrac,
+    OutputUnits, RemovedTile, SizeFrac,
 };
 use crate::animation::Clock;
 use crate::niri_render_elements;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::shadow::ShadowRenderElement;
+use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
 use crate::render_helpers::RenderTarget;
 use crate::utils::id::IdCounter;
 use crate::utils::transaction::{Transaction, TransactionBlocker};
 use crate::utils::{
-    ensure_min_max_size, ensure_min_max_size_maybe_zero, output_size, send_scale_transform,
-    ResizeEdge,
+    ensure_min_max_size, ensure_min_max_size_maybe_zero, logical_px_per_mm, output_size,
+    send_scale_transform, ResizeEdge, DEFAULT_PX_PER_MM,
 };
 use crate::window::ResolvedWindowRules;
 
@@ -99,6 +102,12 @@ pub struct Workspace<W: LayoutElement> {
     /// Optional name of this workspace.
     pub(super) name: Option<String>,
 
+    /// Windows that opened into the reserved slots, along with their app ID.
+    reserved_slot_windows: Vec<(String, W::Id)>,
+
+    /// Placeholders drawn in the empty reserved slots, along with their location.
+    reserved_slot_placeholders: Vec<(SolidColorBuffer, Point<f64, Logical>)>,
+
     /// Unique ID of this workspace.
     id: WorkspaceId,
 }
@@ -136,6 +145,7 @@ niri_render_elements! {
     WorkspaceRenderElement<R> => {
         Scrolling = ScrollingSpaceRenderElement<R>,
         Floating = FloatingSpaceRenderElement<R>,
+        ReservedSlot = SolidColorRenderElement,
     }
 }
 
@@ -180,6 +190,8 @@ pub enum WorkspaceAddWindowTarget<'a, W: LayoutElement> {
     NewColumnAt(usize),
     /// Next to this existing window.
     NextTo(&'a W::Id),
+    /// Into the slot reserved for this app ID.
+    ReservedSlot(&'a str),
 }
 
 impl OutputId {
@@ -213,8 +225,8 @@ impl<W: LayoutElement> Workspace<W> {
             .unwrap_or(OutputId::new(&output));
 
         let scale = output.current_scale();
-        let options =
-            Rc::new(Options::clone(&base_options).adjusted_for_scale(scale.fractional_scale()));
+        let units = OutputUnits::from_output(&output);
+        let options = Rc::new(Options::clone(&base_options).adjusted_for_output(units));
 
         let view_size = output_size(&output);
         let working_area = compute_working_area(&output);
@@ -253,6 +265,8 @@ impl<W: LayoutElement> Workspace<W> {
             base_options,
             options,
             name: config.map(|c| c.name.0),
+            reserved_slot_windows: Vec::new(),
+            reserved_slot_placeholders: Vec::new(),
             id: WorkspaceId::next(),
         }
     }
@@ -270,10 +284,14 @@ impl<W: LayoutElement> Workspace<W> {
         );
 
         let scale = smithay::output::Scale::Integer(1);
-        let options =
-            Rc::new(Options::clone(&base_options).adjusted_for_scale(scale.fractional_scale()));
-
         let view_size = Size::from((1280., 720.));
+        let units = OutputUnits {
+            scale: scale.fractional_scale(),
+            px_per_mm: DEFAULT_PX_PER_MM,
+            size: view_size,
+        };
+        let options = Rc::new(Options::clone(&base_options).adjusted_for_output(units));
+
         let working_area = Rectangle::from_size(Size::from((1280., 720.)));
 
         let scrolling = ScrollingSpace::new(
@@ -310,6 +328,8 @@ impl<W: LayoutElement> Workspace<W> {
             base_options,
             options,
             name: config.map(|c| c.name.0),
+            reserved_slot_windows: Vec::new(),
+            reserved_slot_placeholders: Vec::new(),
             id: WorkspaceId::next(),
         }
     }
@@ -347,6 +367,12 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.are_animations_ongoing() || self.floating.are_animations_ongoing()
     }
 
+    /// Drops the textures rendered for animations, for example after a GPU reset.
+    pub fn drop_render_snapshots(&mut self) {
+        self.scrolling.drop_render_snapshots();
+        self.floating.drop_render_snapshots();
+    }
+
     pub fn are_transitions_ongoing(&self) -> bool {
         self.scrolling.are_transitions_ongoing() || self.floating.are_transitions_ongoing()
     }
@@ -366,11 +392,43 @@ impl<W: LayoutElement> Workspace<W> {
             self.scale.fractional_scale(),
             1.,
         );
+
+        self.update_reserved_slot_placeholders();
     }
 
-    pub fn update_config(&mut self, base_options: Rc<Options>) {
+    fn update_reserved_slot_placeholders(&mut self) {
         let scale = self.scale.fractional_scale();
-        let options = Rc::new(Options::clone(&base_options).adjusted_for_scale(scale));
+        let areas: Vec<_> = self
+            .reserved_slots()
+            .filter(|slot| !self.is_reserved_slot_taken(&slot.app_id))
+            .filter_map(|slot| {
+                let column_idx = self.reserved_slot_column_idx(slot);
+                self.insert_hint_area(InsertPosition::NewColumn(column_idx))
+            })
+            .map(|area| area.to_physical_precise_round(scale).to_logical(scale))
+            .collect();
+
+        // Dim version of the insert hint.
+        let mut color = self.options.insert_hint.color;
+        color.a *= 0.3;
+        let color = color.to_array_premul();
+
+        // Keep the existing buffers so that the placeholders aren't damaged every frame.
+        self.reserved_slot_placeholders.truncate(areas.len());
+        for (idx, area) in areas.into_iter().enumerate() {
+            if let Some((buffer, loc)) = self.reserved_slot_placeholders.get_mut(idx) {
+                buffer.update(area.size, color);
+                *loc = area.loc;
+            } else {
+                let buffer = SolidColorBuffer::new(area.size, color);
+                self.reserved_slot_placeholders.push((buffer, area.loc));
+            }
+        }
+    }
+
+    pub fn update_config(&mut self, base_options: Rc<Options>) {
+        let options =
+            Rc::new(Options::clone(&base_options).adjusted_for_output(self.output_units()));
 
         self.scrolling.update_config(
             self.view_size,
@@ -394,6 +452,18 @@ impl<W: LayoutElement> Workspace<W> {
         self.options = options;
     }
 
+    /// Returns the output properties that options are resolved against.
+    pub fn output_units(&self) -> OutputUnits {
+        OutputUnits {
+            scale: self.scale.fractional_scale(),
+            px_per_mm: self
+                .output
+                .as_ref()
+                .map_or(DEFAULT_PX_PER_MM, logical_px_per_mm),
+            size: self.view_size,
+        }
+    }
+
     pub fn update_shaders(&mut self) {
         self.scrolling.update_shaders();
         self.floating.update_shaders();
@@ -505,15 +575,15 @@ impl<W: LayoutElement> Workspace<W> {
             return;
         }
 
-        let fractional_scale_changed = self.scale.fractional_scale() != scale.fractional_scale();
+        let old_units = self.output_units();
 
         self.scale = scale;
         self.transform = transform;
         self.view_size = size;
         self.working_area = working_area;
 
-        if fractional_scale_changed {
-            // Options need to be recomputed for the new scale.
+        if self.output_units() != old_units {
+            // Options need to be recomputed for the new scale and size.
             self.update_config(self.base_options.clone());
         } else {
             // Pass our existing options as is.
@@ -599,6 +669,27 @@ impl<W: LayoutElement> Workspace<W> {
                     self.floating_is_active = FloatingActive::No;
                 }
             }
+            WorkspaceAddWindowTarget::ReservedSlot(app_id) => {
+                // Don't steal focus from an active fullscreen window.
+                let activate = activate.map_smart(|| !self.is_active_fullscreen());
+
+                let col_idx = self
+                    .reserved_slots()
+                    .find(|slot| slot.app_id == app_id)
+                    .map(|slot| self.reserved_slot_column_idx(slot));
+
+                let id = tile.window().id().clone();
+                self.scrolling
+                    .add_tile(col_idx, tile, activate, width, is_full_width, None);
+
+                if activate {
+                    self.floating_is_active = FloatingActive::No;
+                }
+
+                self.reserved_slot_windows
+                    .retain(|(slot_app_id, _)| slot_app_id != app_id);
+                self.reserved_slot_windows.push((app_id.to_owned(), id));
+            }
             WorkspaceAddWindowTarget::NextTo(next_to) => {
                 let activate = activate.map_smart(|| self.active_window().unwrap().id() == next_to);
 
@@ -709,6 +800,45 @@ impl<W: LayoutElement> Workspace<W> {
         removed
     }
 
+    /// Moves the tiled `window` into the slot of the tiled `swallower`, taking `swallower` out of
+    /// the workspace.
+    ///
+    /// Returns the taken out window, or `None` if either window isn't tiled on this workspace.
+    pub fn swallow_window(&mut self, swallower: &W::Id, window: &W::Id) -> Option<W> {
+        let is_tiled = |id: &W::Id| self.has_window(id) && !self.floating.has_window(id);
+        if !is_tiled(swallower) || !is_tiled(window) {
+            return None;
+        }
+
+        let removed = self.scrolling.remove_tile(window, Transaction::new());
+        let old = self.scrolling.replace_tile(swallower, removed.tile);
+
+        if let Some(output) = &self.output {
+            old.window().output_leave(output);
+        }
+
+        Some(old.into_window())
+    }
+
+    /// Puts `window` into the slot of the tiled `slot`, taking `slot` out of the workspace.
+    ///
+    /// Gives `window` back if `slot` isn't tiled on this workspace.
+    pub fn replace_window(&mut self, slot: &W::Id, window: W) -> Result<W, W> {
+        if !self.has_window(slot) || self.floating.has_window(slot) {
+            return Err(window);
+        }
+
+        self.enter_output_for_window(&window);
+        let tile = self.make_tile(window);
+        let old = self.scrolling.replace_tile(slot, tile);
+
+        if let Some(output) = &self.output {
+            old.window().output_leave(output);
+        }
+
+        Ok(old.into_window())
+    }
+
     pub fn remove_active_tile(&mut self, transaction: Transaction) -> Option<RemovedTile<W>> {
         let from_floating = self.floating_is_active.get();
         let removed = if from_floating {
@@ -1061,6 +1191,13 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.expel_from_column();
     }
 
+    pub fn column_neighbor_window(&self, window: &W::Id, direction: ScrollDirection) -> Option<&W> {
+        if self.floating.has_window(window) {
+            return None;
+        }
+        self.scrolling.column_neighbor_window(window, direction)
+    }
+
     pub fn swap_window_in_direction(&mut self, direction: ScrollDirection) {
         if self.floating_is_active.get() {
             return;
@@ -1082,6 +1219,13 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.set_column_display(display);
     }
 
+    pub fn toggle_column_pin(&mut self, edge: ColumnPinEdge) {
+        if self.floating_is_active.get() {
+            return;
+        }
+        self.scrolling.toggle_column_pin(edge);
+    }
+
     pub fn center_column(&mut self) {
         if self.floating_is_active.get() {
             self.floating.center_window(None);
@@ -1188,6 +1332,13 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.expand_column_to_available_width();
     }
 
+    pub fn fit_column_to_content(&mut self) {
+        if self.floating_is_active.get() {
+            return;
+        }
+        self.scrolling.fit_column_to_content();
+    }
+
     pub fn set_fullscreen(&mut self, window: &W::Id, is_fullscreen: bool) {
         let mut unfullscreen_to_floating = false;
         if self.floating.has_window(window) {
@@ -1266,8 +1417,9 @@ impl<W: LayoutElement> Workspace<W> {
                 self.floating_is_active = FloatingActive::No;
             }
         } else {
+            // Keep the ongoing move animations: the animation below continues them, so that toggling
+            // again mid-animation reverses smoothly.
             let mut removed = self.scrolling.remove_tile(&id, Transaction::new());
-            removed.tile.stop_move_animations();
 
             // Come up with a default floating position close to the tile position.
             let stored_or_default = self.floating.stored_or_default_tile_pos(&removed.tile);
@@ -1458,6 +1610,12 @@ impl<W: LayoutElement> Workspace<W> {
             .render_elements(renderer, target, scrolling_focus_ring);
         let scrolling = scrolling.into_iter().map(WorkspaceRenderElement::from);
 
+        let placeholders = self.reserved_slot_placeholders.iter().map(|(buffer, loc)| {
+            let elem = SolidColorRenderElement::from_buffer(buffer, *loc, 1., Kind::Unspecified);
+            WorkspaceRenderElement::from(elem)
+        });
+        let scrolling = scrolling.chain(placeholders);
+
         let floating_focus_ring = focus_ring && self.floating_is_active();
         let floating = self.is_floating_visible().then(|| {
             let view_rect = Rectangle::from_size(self.view_size);
@@ -1520,10 +1678,44 @@ impl<W: LayoutElement> Workspace<W> {
     ) {
         if self.floating.has_window(window) {
             self.floating
-                .start_close_animation_for_window(renderer, window, blocker);
+                .start_close_animation_for_window(renderer, window, None, blocker);
+        } else {
+            self.scrolling
+                .start_close_animation_for_window(renderer, window, None, blocker);
+        }
+    }
+
+    /// Starts the close animation for a tiled window that [`Workspace::replace_window()`] is about
+    /// to take out.
+    pub fn start_close_animation_for_replaced_window(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        window: &W::Id,
+        blocker: TransactionBlocker,
+    ) {
+        self.scrolling
+            .start_close_animation_for_replaced_window(renderer, window, blocker);
+    }
+
+    /// Starts the animation for a window that is about to move away from this workspace.
+    ///
+    /// The window shrinks toward `shrink_to`, a point in the view.
+    pub fn start_move_animation_for_window(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        window: &W::Id,
+        shrink_to: Point<f64, Logical>,
+    ) {
+        self.store_unmap_snapshot_if_empty(renderer, window);
+
+        let shrink_to = Some(shrink_to);
+        let blocker = TransactionBlocker::completed();
+        if self.floating.has_window(window) {
+            self.floating
+                .start_close_animation_for_window(renderer, window, shrink_to, blocker);
         } else {
             self.scrolling
-                .start_close_animation_for_window(renderer, window, blocker);
+                .start_close_animation_for_window(renderer, window, shrink_to, blocker);
         }
     }
 
@@ -1536,7 +1728,7 @@ impl<W: LayoutElement> Workspace<W> {
         blocker: TransactionBlocker,
     ) {
         self.floating
-            .start_close_animation_for_tile(renderer, snapshot, tile_size, tile_pos, blocker);
+            .start_close_animation_for_tile(renderer, snapshot, tile_size, tile_pos, None, blocker);
     }
 
     pub fn start_open_animation(&mut self, id: &W::Id) -> bool {
@@ -1651,6 +1843,54 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.insert_position(pos)
     }
 
+    /// Returns where an interactively moved window would go when dropped at `pos`.
+    ///
+    /// Floating windows stay floating, unless dropped onto a gap between columns, which tiles them
+    /// into a new column there.
+    pub(super) fn interactive_move_insert_position(
+        &self,
+        pos: Point<f64, Logical>,
+        is_floating: bool,
+    ) -> InsertPosition {
+        if !is_floating {
+            return self.scrolling.insert_position(pos);
+        }
+
+        match self.scrolling.column_gap_at(pos) {
+            Some(column_idx) => InsertPosition::NewColumn(column_idx),
+            None => InsertPosition::Floating,
+        }
+    }
+
+    /// Returns the slots reserved on this workspace in the config.
+    pub fn reserved_slots(&self) -> impl Iterator<Item = &ReservedSlot> + '_ {
+        let name = self.name.as_deref();
+        self.options
+            .reserved_slots
+            .iter()
+            .filter(move |(ws_name, _)| name.is_some_and(|name| name.eq_ignore_ascii_case(ws_name)))
+            .map(|(_, slot)| slot)
+    }
+
+    /// Returns whether this workspace has a slot for this app ID with no window in it.
+    pub fn has_free_reserved_slot(&self, app_id: &str) -> bool {
+        self.reserved_slots().any(|slot| slot.app_id == app_id)
+            && !self.is_reserved_slot_taken(app_id)
+    }
+
+    fn is_reserved_slot_taken(&self, app_id: &str) -> bool {
+        self.reserved_slot_windows
+            .iter()
+            .any(|(slot_app_id, id)| slot_app_id == app_id && self.has_window(id))
+    }
+
+    fn reserved_slot_column_idx(&self, slot: &ReservedSlot) -> usize {
+        match slot.position {
+            ReservedSlotPosition::First => 0,
+            ReservedSlotPosition::Last => self.scrolling.column_count(),
+        }
+    }
+
     pub(super) fn insert_hint_area(
         &self,
         position: InsertPosition,
@@ -1658,6 +1898,16 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.insert_hint_area(position)
     }
 
+    /// Returns the area where a new tiled window would open, relative to the view.
+    pub fn new_window_area(&self) -> Option<Rectangle<f64, Logical>> {
+        let column_idx = if self.scrolling.is_empty() {
+            0
+        } else {
+            self.scrolling.active_column_idx() + 1
+        };
+        self.insert_hint_area(InsertPosition::NewColumn(column_idx))
+    }
+
     pub fn view_offset_gesture_begin(&mut self, is_touchpad: bool) {
         self.scrolling.view_offset_gesture_begin(is_touchpad);
     }
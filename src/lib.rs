@@ -23,6 +23,13 @@ pub mod cli;
 /// 职责：跟踪光标位置、形状变化和主题设置
 pub mod cursor;
 
+/// 声明公共模块 dbus - D-Bus 集成（需启用 dbus 特性）
+/// 与 systemd-logind 对接：会话空闲提示、锁定信号、盖子开关处理
+/// 以及提供 freedesktop ScreenSaver 接口（空闲抑制、模拟用户活动、查询空闲时长）
+/// 和 Mutter IdleMonitor 接口（空闲与用户活动监视）
+#[cfg(feature = "dbus")]
+pub mod dbus;
+
 /// 声明公共模块 frame_clock - 帧同步时钟
 /// 合成器核心：管理VSync信号，协调渲染循环
 pub mod frame_clock;
@@ -59,10 +66,18 @@ pub mod protocols;
 /// 提供共享的渲染函数如纹理处理、着色器管理
 pub mod render_helpers;
 
+/// 声明公共模块 render_scenario - 脚本化场景渲染
+/// 将场景渲染为 PNG，用于渲染回归（golden image）测试
+pub mod render_scenario;
+
 /// 声明公共模块 rubber_band - 弹性滚动效果
 /// 模拟物理滚动效果（如惯性滚动、边界回弹）
 pub mod rubber_band;
 
+/// 声明公共模块 ui - 界面覆盖层
+/// 在输出上绘制的提示面板，如按键绑定冲突
+pub mod ui;
+
 /// 声明公共模块 utils - 工具函数集
 /// 提供跨模块使用的辅助函数(如几何计算)
 pub mod utils;
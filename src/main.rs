@@ -10,7 +10,7 @@ use std::io::{self, Write};
 // 用于从原始文件描述符创建文件
 use std::os::fd::FromRawFd;
 // 路径操作相关模块
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 // 子进程管理
 use std::process::Command;
 // 环境变量和内存操作
@@ -21,22 +21,26 @@ use clap::{CommandFactory, Parser};
 // 获取项目目录路径
 use directories::ProjectDirs;
 // 引入命令行接口定义
-use niri::cli::{Cli, Sub};
+use niri::cli::{Cli, MsgFormat, Sub};
 // IPC客户端消息处理
-use niri::ipc::client::handle_msg;
+use niri::ipc::client::{handle_msg, print_config_diff};
 // niri主状态机
 use niri::niri::State;
+// 脚本化场景渲染（golden image 测试）
+use niri::render_scenario;
 // 子进程生成与环境管理工具
 use niri::utils::spawning::{
-    spawn, store_and_increase_nofile_rlimit, CHILD_ENV, REMOVE_ENV_RUST_BACKTRACE,
-    REMOVE_ENV_RUST_LIB_BACKTRACE,
+    set_child_env, spawn, store_and_increase_nofile_rlimit, xwayland_display,
+    REMOVE_ENV_RUST_BACKTRACE, REMOVE_ENV_RUST_LIB_BACKTRACE,
 };
-// 配置文件监视器
-use niri::utils::watcher::Watcher;
+// 自启动服务监管
+use niri::utils::supervisor::spawn_supervised;
+// 崩溃恢复（panic 钩子、安全模式）
+use niri::utils::crash::{self, IS_SAFE_MODE};
 // 工具函数（版本信息、panic触发等）
 use niri::utils::{cause_panic, version, IS_SYSTEMD_SERVICE};
 // 配置加载模块
-use niri_config::Config;
+use niri_config::{Config, ConfigErrorDetail};
 // IPC套接字路径环境变量名
 use niri_ipc::socket::SOCKET_PATH_ENV;
 // 原子操作
@@ -128,21 +132,28 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Rust概念：模式匹配（match）用于处理枚举变体
         match subcommand {
             // 配置验证子命令
-            Sub::Validate { config } => {
+            Sub::Validate { config, diff } => {
                 // 启动性能分析器
                 tracy_client::Client::start();
 
                 // 获取配置路径
                 let (path, _, _) = config_path(config);
                 // 加载并验证配置
-                Config::load(&path)?;
+                let config = Config::load(&path)?;
                 info!("config is valid");
+
+                // 通过 IPC 与正在运行的实例比较
+                if diff {
+                    print_config_diff(&config)?;
+                }
                 return Ok(());
             }
             // IPC消息处理子命令
-            Sub::Msg { msg, json } => {
+            Sub::Msg { msg, json, format } => {
+                // --json 是 --format json 的简写
+                let format = if json { Some(MsgFormat::Json) } else { format };
                 // 处理消息并返回
-                handle_msg(msg, json)?;
+                handle_msg(msg, format)?;
                 return Ok(());
             }
             // 触发panic子命令（用于测试）
@@ -156,12 +167,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // 渲染测试场景并退出（不启动合成器）
+    if let Some(path) = cli.test_render_scenario {
+        render_scenario::run(&path)?;
+        return Ok(());
+    }
+
     // 启动性能分析器（Tracy）
     tracy_client::Client::start();
 
     // 打印启动日志（含版本号）
     info!("starting version {}", &version());
 
+    // 记录 panic 信息，用于非关键子系统的崩溃报告
+    crash::install_panic_hook();
+
+    // 安全模式标志（供崩溃提示判断是否还能提供重启）
+    if cli.safe_mode {
+        warn!("running in safe mode with the default config and animations off");
+        IS_SAFE_MODE.store(true, Ordering::Relaxed);
+    }
+
     // 获取配置路径、监视路径和是否创建默认配置标志
     let (path, watch_path, create_default) = config_path(cli.config);
     // 清除环境变量避免影响子进程
@@ -205,17 +231,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    // 加载配置文件
-    let config_load_result = Config::load(&path);
-    // 处理配置加载结果：出错时使用默认配置并记录警告
-    let mut config = config_load_result
-        .map_err(|err| warn!("{err:?}"))
-        .unwrap_or_default();
+    // 加载配置文件（安全模式下跳过，直接使用默认配置）
+    let (mut config, config_error) = if cli.safe_mode {
+        (crash::safe_mode_config(), None)
+    } else {
+        let config_load_result = Config::load(&path);
+        // 处理配置加载结果：出错时使用默认配置并记录警告，稍后显示错误通知
+        let config_error = config_load_result
+            .as_ref()
+            .err()
+            .map(|err| ConfigErrorDetail::from_report(&path, err));
+        let config = config_load_result
+            .map_err(|err| warn!("{err:?}"))
+            .unwrap_or_default();
+        (config, config_error)
+    };
 
     // 提取启动时需要执行的命令
     let spawn_at_startup = mem::take(&mut config.spawn_at_startup);
     // 存储环境变量配置（用于子进程）
-    *CHILD_ENV.write().unwrap() = mem::take(&mut config.environment);
+    set_child_env(mem::take(&mut config.environment));
 
     // 增加文件描述符限制
     store_and_increase_nofile_rlimit();
@@ -236,6 +271,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     )
     .unwrap();
 
+    // 在 TTY 上接入 logind（空闲提示、锁屏信号和合盖策略）
+    #[cfg(feature = "dbus")]
+    if state.backend.tty_checked().is_some() {
+        state.niri.start_logind();
+    }
+
+    // 显示启动时的配置错误
+    if let Some(detail) = config_error {
+        state.niri.config_error_notification.show(detail);
+    }
+
+    // 首次运行时显示新手教程（仅显示一次）
+    if state.niri.tutorial.show_at_startup() {
+        state.niri.start_tutorial_hint_timer();
+    }
+
     // 设置WAYLAND_DISPLAY环境变量（供客户端连接）
     let socket_name = state.niri.socket_name.as_deref().unwrap();
     env::set_var("WAYLAND_DISPLAY", socket_name);
@@ -251,11 +302,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         info!("IPC listening on: {}", socket_path.to_string_lossy());
     }
 
+    // 启动 xwayland-satellite（需要 WAYLAND_DISPLAY，且要早于自启动程序以便它们获得 DISPLAY）
+    state.niri.start_xwayland_satellite();
+
     // 会话模式特殊处理
     if cli.session {
         // 导入环境变量到会话管理器
         import_environment();
 
+        // 提供 freedesktop ScreenSaver 接口（嵌套运行时由宿主会话提供）
+        #[cfg(feature = "dbus")]
+        state.niri.start_screen_saver();
+
+        // 提供 Mutter IdleMonitor 接口，供 GNOME 应用检测用户是否在场
+        #[cfg(feature = "dbus")]
+        state.niri.start_idle_monitor();
+
     }
 
     // 系统通知处理
@@ -271,37 +333,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    // 配置文件监视器初始化
-    let _watcher = {
-        // 配置文件加载处理闭包
-        let process = |path: &Path| {
-            Config::load(path).map_err(|err| {
-                warn!("{:?}", err.context("error loading config"));
-            })
-        };
-
-        // 创建通道用于监视事件
-        let (tx, rx) = calloop::channel::sync_channel(1);
-        // 初始化文件监视器
-        let watcher = Watcher::new(watch_path.clone(), process, tx);
-        // 将通道加入事件循环
-        event_loop
-            .handle()
-            .insert_source(rx, |event, _, state| match event {
-                // 收到新配置时重载
-                calloop::channel::Event::Msg(config) => state.reload_config(config),
-                calloop::channel::Event::Closed => (),
-            })
-            .unwrap();
-        watcher
-    };
+    // 配置文件监视器初始化（安全模式下不加载配置文件，也就不需要监视）
+    if !cli.safe_mode {
+        state.watch_config(watch_path);
+    }
 
     // 启动命令行指定的程序
     spawn(cli.command, None);
 
     // 启动配置中指定的自启动程序
     for elem in spawn_at_startup {
-        spawn(elem.command, None);
+        if elem.restart_on_failure {
+            // 受监管的服务异常退出时自动重启
+            spawn_supervised(elem.command, elem.max_restarts, state.niri.services.clone());
+        } else {
+            spawn(elem.command, None);
+        }
     }
     
     // 使用 spawn 函数启动 Alacritty 终端
@@ -323,13 +370,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 // 导入环境变量到会话系统
 fn import_environment() {
     // 需要导入的环境变量列表
-    let variables = [
+    let mut variables = vec![
         "WAYLAND_DISPLAY",
         "XDG_CURRENT_DESKTOP",
         "XDG_SESSION_TYPE",
         SOCKET_PATH_ENV,
-    ]
-    .join(" ");
+    ];
+
+    // xwayland-satellite 的 DISPLAY 只在子进程环境中，需要显式传给导入命令
+    let display = xwayland_display();
+    if display.is_some() {
+        variables.push("DISPLAY");
+    }
+    let variables = variables.join(" ");
 
     // 构建初始化系统导入命令
     let mut init_system_import = String::new();
@@ -347,16 +400,19 @@ fn import_environment() {
     }
 
     // 执行环境导入命令
-    let rv = Command::new("/bin/sh")
-        .args([
-            "-c",
-            &format!(
-                "{init_system_import}\
-                 hash dbus-update-activation-environment 2>/dev/null && \
-                 dbus-update-activation-environment {variables}"
-            ),
-        ])
-        .spawn();
+    let mut command = Command::new("/bin/sh");
+    command.args([
+        "-c",
+        &format!(
+            "{init_system_import}\
+             hash dbus-update-activation-environment 2>/dev/null && \
+             dbus-update-activation-environment {variables}"
+        ),
+    ]);
+    if let Some(display) = display {
+        command.env("DISPLAY", display);
+    }
+    let rv = command.spawn();
     // 等待命令完成
     match rv {
         Ok(mut child) => match child.wait() {
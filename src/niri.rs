@@ -6,16 +6,18 @@ use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 // Unix域套接字
 use std::os::unix::net::UnixStream;
+// 文件系统操作
+use std::fs;
 // 路径处理
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 // 引用计数智能指针
 use std::rc::Rc;
 // 原子布尔类型
 use std::sync::atomic::{AtomicBool, Ordering};
 // 多生产者单消费者通道
 use std::sync::mpsc::{self, Receiver, Sender};
-// 线程同步原语（互斥锁和原子引用计数）
-use std::sync::{Arc, Mutex};
+// 线程同步原语（互斥锁、原子引用计数和一次性初始化单元）
+use std::sync::{Arc, Mutex, OnceLock};
 // 时间处理
 use std::time::{Duration, Instant};
 // 环境变量和内存操作
@@ -26,10 +28,14 @@ use anyhow::{Context};
 // 异步任务调度器
 use calloop::futures::Scheduler;
 // 配置相关结构体
+use niri_config::overrides::ConfigOverrides;
 use niri_config::{
-    Config, FloatOrInt, Key, Modifiers, OutputName, PreviewRender, TrackLayout,
-    WarpMouseToFocusMode, WorkspaceReference,
+    Bind, Config, ConfigErrorDetail, FloatOrInt, FocusAfterClose, HotCorners, Key, Modifiers,
+    OutputName, PreviewRender, TrackLayout, Wallpaper, WarpMouseToFocusMode, WorkspaceReference,
 };
+use niri_ipc::{CursorPlane, CursorPlaneFallback, ScreenTransitionStyle, WindowWarpTarget};
+// 像素格式
+use smithay::backend::allocator::Fourcc;
 // 输入键码
 use smithay::backend::input::Keycode;
 // 渲染器相关组件
@@ -46,6 +52,7 @@ use smithay::backend::renderer::element::{
     default_primary_scanout_output_compare, Element, Id, Kind, PrimaryScanoutOutput,
     RenderElementStates,
 };
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
 // 颜色类型
 use smithay::backend::renderer::Color32F;
 // 桌面工具函数
@@ -64,7 +71,7 @@ use smithay::desktop::{
 use smithay::input::keyboard::Layout as KeyboardLayout;
 // 指针事件
 use smithay::input::pointer::{
- CursorImageStatus, CursorImageSurfaceData,
+ CursorIcon, CursorImageStatus, CursorImageSurfaceData,
      MotionEvent,
 };
 // 输入设备管理
@@ -80,6 +87,7 @@ use smithay::reexports::calloop::{
 // Wayland协议扩展
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::WmCapabilities;
 // Wayland服务器底层
+use smithay::reexports::wayland_server::backend::protocol::ProtocolError;
 use smithay::reexports::wayland_server::backend::{
     ClientData, ClientId, DisconnectReason, GlobalId,
 };
@@ -88,13 +96,13 @@ use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::{Client, Display, DisplayHandle, Resource};
 // 实用工具
 use smithay::utils::{
-    ClockSource, IsAlive as _, Logical, Monotonic, Point, Rectangle, Scale, Size,
+    Buffer, ClockSource, IsAlive as _, Logical, Monotonic, Physical, Point, Rectangle, Scale, Size,
     Transform, SERIAL_COUNTER,
 };
 // Wayland合成器
 use smithay::wayland::compositor::{
     with_states, with_surface_tree_downward, CompositorClientState, CompositorHandler,
-    CompositorState, HookId, SurfaceData, TraversalAction,
+    CompositorState, HookId, SurfaceAttributes, SurfaceData, TraversalAction,
 };
 // 光标形状管理
 use smithay::wayland::cursor_shape::CursorShapeManagerState;
@@ -137,53 +145,119 @@ use smithay::wayland::shell::xdg::XdgShellState;
 use smithay::wayland::shm::ShmState;
 // Wayland套接字监听
 use smithay::wayland::socket::ListeningSocketSource;
+// 数位板（绘图笔）
+use smithay::wayland::tablet_manager::TabletManagerState;
 // 文本输入
 use smithay::wayland::text_input::TextInputManagerState;
 // 视口管理
 use smithay::wayland::viewporter::ViewporterState;
+// 虚拟键盘
+use smithay::wayland::virtual_keyboard::VirtualKeyboardManagerState;
 // XDG激活协议
-use smithay::wayland::xdg_activation::XdgActivationState;
+use smithay::wayland::xdg_activation::{XdgActivationState, XdgActivationToken};
 
 // 内部模块
-use crate::animation::Clock;
+use crate::animation::{Animation, Clock};
 use crate::backend::tty::SurfaceDmabufFeedback;
 use crate::backend::{Backend, Headless, RenderResult, Tty, Winit};
 use crate::cursor::{CursorManager, CursorTextureCache, RenderCursor, XCursor};
+#[cfg(feature = "dbus")]
+use crate::dbus::freedesktop_screensaver::{ScreenSaver, ScreenSaverToNiri};
+#[cfg(feature = "dbus")]
+use crate::dbus::logind::{self, Logind, LogindToNiri, SessionSignal};
+#[cfg(feature = "dbus")]
+use crate::dbus::mutter_idle_monitor::{IdleMonitor, IdleMonitorToNiri};
 use crate::frame_clock::FrameClock;
 use crate::handlers::{XDG_ACTIVATION_TOKEN_TIMEOUT};
+use crate::input::bind_conflicts::find_bind_conflicts;
+use crate::input::double_tap::DoubleTapTracker;
+use crate::input::extra_seats::ExtraSeats;
+use crate::input::keyboard_state::PerDeviceKeyboardState;
+use crate::input::relative_motion::RelativeMotionBatch;
 use crate::input::scroll_tracker::ScrollTracker;
+use crate::input::tablet_pad::PadAxisTracker;
+use crate::input::touch_gestures::TouchGestures;
+use crate::input::virtual_input::{VirtualInputAccess, VirtualInputPermissions};
 use crate::input::{
     apply_libinput_settings, mods_with_mouse_binds,
-    mods_with_wheel_binds,
+    mods_with_wheel_binds, TabletData,
 };
 use crate::ipc::server::IpcServer;
 use crate::layer::mapped::LayerSurfaceRenderElement;
 use crate::layer::MappedLayer;
+use crate::layout::scrolling::ScrollDirection;
+use crate::layout::snapshot::{
+    ColumnGroup, LayoutRestore, LayoutSnapshot, RestoreSlot, RESTORE_TIMEOUT,
+};
 use crate::layout::tile::TileRenderElement;
 use crate::layout::workspace::{Workspace, WorkspaceId};
-use crate::layout::{HitType, Layout, LayoutElement as _, MonitorRenderElement};
+use crate::layout::{
+    ActivateWindow, AddWindowTarget, HitType, Layout, LayoutElement as _, MonitorRenderElement,
+};
 use crate::niri_render_elements;
+use crate::protocols::alpha_modifier::AlphaModifierManagerState;
+use crate::protocols::commit_timing::{CommitTimer, CommitTimingManagerState};
+use crate::protocols::fifo::{clear_fifo_barrier, FifoManagerState};
 use crate::protocols::foreign_toplevel::{self, ForeignToplevelManagerState};
 use crate::protocols::output_management::OutputManagementManagerState;
-use crate::render_helpers::debug::draw_opaque_regions;
+use crate::protocols::virtual_pointer::{VirtualPointer, VirtualPointerManagerState};
+use crate::render_helpers::debug::{draw_opaque_regions, DamageHeatmap};
+use crate::render_helpers::offscreen::OffscreenRenderElement;
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::render_helpers::texture::TextureBuffer;
 use crate::render_helpers::{
-    shaders, RenderTarget, SplitElements,
+    download_texture, render_to_texture, shaders, RenderTarget, SplitElements,
 };
+use crate::ui::adjust_mode::AdjustMode;
+use crate::ui::bind_conflicts::BindConflictsOverlay;
+use crate::ui::config_error_notification::ConfigErrorNotification;
+use crate::ui::crash_report::{CrashReportOverlay, CrashedOverlays, UiOverlay};
+use crate::ui::dwell_click::DwellClick;
+use crate::ui::hot_areas::{self, HotArea, HotAreas};
+use crate::ui::idle_fade::IdleFade;
+use crate::ui::kill_prompt::{KillPrompt, KillRequest};
+use crate::ui::launch_placeholder::{self, LaunchPlaceholder, LaunchPlaceholders};
+use crate::ui::level_osd::{Level, LevelOsd};
+use crate::ui::overview_search::{fuzzy_score, OverviewSearch};
+use crate::ui::screen_transition::{self, ScreenTransition};
+use crate::ui::texture_cache;
+use crate::ui::tutorial::{Tutorial, HINT_INTERVAL};
+use crate::ui::virtual_input_prompt::VirtualInputPrompt;
+use crate::ui::wallpaper::{self, Wallpapers};
+use crate::utils::crash::{self, CrashReport};
+use crate::utils::desktop_entry;
 use crate::utils::scale::{closest_representable_scale, guess_monitor_scale};
-use crate::utils::spawning::CHILD_ENV;
+use crate::utils::spawning::{
+    clear_xwayland_display, find_executable, set_child_env, set_xwayland_display, TerminableChild,
+};
+use crate::utils::supervisor::{spawn_supervised_with_hooks, ServiceHooks, Services};
+use crate::utils::watcher::Watcher;
+use crate::utils::xwayland::{set_satellite_pid, X11Sockets};
 use crate::utils::{
-    center, center_f64, expand_home, get_monotonic_time, ipc_transform_to_smithay,
-    logical_output, output_matches_name, output_size, send_scale_transform,
+    center, center_f64, expand_home, gamma_ramp, get_monotonic_time, ipc_transform_to_smithay,
+    logical_output, output_matches_name, output_size, read_flatpak_app_id, send_scale_transform,
+    with_toplevel_role,
 
 };
-use crate::window::{InitialConfigureState, Mapped, ResolvedWindowRules, Unmapped, WindowRef};  
+use crate::window::focus_history::FocusHistory;
+use crate::window::pending_close::{self, PendingClose, PendingCloses};
+use crate::window::thumbnails::{self, render_thumbnail, Thumbnail, ThumbnailCache};
+use crate::window::mapped::MappedId;
+use crate::window::{
+    is_descendant_process, InitialConfigureState, Mapped, ResolvedWindowRules, Unmapped, WindowRef,
+};  
 // 我们将尝试每秒至少发送一次帧回调。我们将创建一个每秒触发一次的计时器，因此，在最差的情况下，同一表面两次帧回调之间的最大间隔
 // 应该约为 1.995 秒。
 const FRAME_CALLBACK_THROTTLE: Option<Duration> = Some(Duration::from_millis(995));  
 
+// 指针跳转到窗口边缘时向内缩进的距离，避免落在边框或调整大小区域上
+const WARP_EDGE_INSET: f64 = 16.;
+
+// 每个客户端最多同时等待目标呈现时间的提交数，超出的提交直接应用，避免客户端无限堆积
+const MAX_COMMIT_TIMERS_PER_CLIENT: usize = 16;
+
 pub struct Niri {
     /// 动态配置
     pub config: Rc<RefCell<Config>>,  // 动态配置
@@ -195,6 +269,16 @@ pub struct Niri {
     /// （以及是否删除瞬时更改）。
     pub config_file_output_config: niri_config::Outputs,   // 配置文件中的输出配置
 
+    /// 通过 IPC 在运行时修改的配置值
+    ///
+    /// 每次从磁盘重新加载配置时都会重新应用在配置文件的值之上。
+    pub config_overrides: ConfigOverrides,
+
+    /// 配置文件监视器及其通道事件源（安全模式下为 None）
+    ///
+    /// 通过 load-config-file 切换配置文件时会被替换。
+    pub config_watcher: Option<(Watcher, RegistrationToken)>,
+
     /// 事件循环句柄，用于管理事件源和回调
     pub event_loop: LoopHandle<'static, State>,
     /// 异步任务调度器，用于调度未来执行的任务
@@ -233,6 +317,9 @@ pub struct Niri {
     /// 尚未连接缓冲区的窗口（未映射窗口）
     pub unmapped_windows: HashMap<WlSurface, Unmapped>,  
 
+    /// 被吞噬的窗口（通常是终端），按吞噬它的窗口 ID 索引；吞噬者关闭后放回原位
+    pub swallowed_windows: Vec<(MappedId, Mapped)>,
+
     /// 尚未附加缓冲区的层表面（未映射层表面）
     pub unmapped_layer_surfaces: HashSet<WlSurface>,  
 
@@ -252,6 +339,11 @@ pub struct Niri {
     /// 通知客户端他们的阻塞器已被清除的接收通道
     pub blocker_cleared_rx: Receiver<Client>,  
 
+    /// 等待目标呈现时间的提交
+    pub commit_timers: Vec<CommitTimer>,
+    /// 唤醒事件循环以放行下一个定时提交的计时器，及其唤醒时刻
+    pub commit_timer_wakeup: Option<(RegistrationToken, Duration)>,
+
     /// 每个输出的状态管理
     pub output_state: HashMap<Output, OutputState>,  
 
@@ -260,6 +352,11 @@ pub struct Niri {
     /// 当该值为假时，我们将处于空闲状态并且显示器已关闭
     pub monitors_active: bool,  
 
+    /// 会话是否已暂停（切换到其他 VT）
+    ///
+    /// 暂停期间不向客户端发送帧回调，让它们停止渲染
+    pub is_session_paused: bool,
+
     /// 笔记本电脑盖子是否关闭
     ///
     /// Libinput 保证盖子开关启动时处于打开状态，如果在启动过程中盖子开关处于关闭状态，则 libinput 将立即发送一个关闭事件
@@ -267,6 +364,18 @@ pub struct Niri {
 
     /// 已连接的输入设备集合
     pub devices: HashSet<input::Device>, 
+    /// 按设备分别保存的锁定键和键盘布局状态（仅在 track-state "per-device" 时使用）
+    pub keyboard_states: PerDeviceKeyboardState,
+    /// 已连接的数位板及其宽高比，用于把笔的绝对坐标映射到输出上
+    pub tablets: HashMap<input::Device, TabletData>,
+    /// 数位板笔在全局坐标中的位置（笔在感应范围内时为 Some）
+    ///
+    /// 此时绘制的光标跟随笔而不是鼠标指针
+    pub tablet_cursor_location: Option<Point<f64, Logical>>,
+    /// 数位板 pad 上环和触摸条的累计移动，用于换算成映射的步数
+    pub tablet_pad_axes: PadAxisTracker,
+    /// 触摸屏上正在识别或进行中的合成器手势（多指滑动和边缘滑动）
+    pub touch_gestures: TouchGestures,
 
     // Smithay 状态管理（以下是一组 Smithay 相关的状态对象）
     /// 管理 Wayland 合成器协议的状态（创建/管理表面）
@@ -283,6 +392,12 @@ pub struct Niri {
     pub output_management_state: OutputManagementManagerState,
     /// 管理视口协议的状态（表面缩放和裁剪）
     pub viewporter_state: ViewporterState,
+    /// 管理 alpha 修饰协议的状态（客户端请求的表面透明度）
+    pub alpha_modifier_state: AlphaModifierManagerState,
+    /// 管理 FIFO 协议的状态（提交排队到上一帧显示之后）
+    pub fifo_state: FifoManagerState,
+    /// 管理提交定时协议的状态（提交等待目标呈现时间）
+    pub commit_timing_state: CommitTimingManagerState,
     /// 管理共享内存协议的状态（SHM 缓冲区支持）
     pub shm_state: ShmState,
     /// 管理输出全局对象的状态（显示器枚举）
@@ -311,6 +426,10 @@ pub struct Niri {
     pub data_device_state: DataDeviceState,
     /// 管理主选择协议的状态（Linux 主选择剪贴板）
     pub primary_selection_state: PrimarySelectionState,
+    /// 上次在主选择和剪贴板之间同步的时间（用于限制同步频率）
+    pub selection_sync_last: Option<Duration>,
+    /// 被限流推迟的选择同步计时器
+    pub selection_sync_timer: Option<RegistrationToken>,
     /// 管理 WLR 数据控制协议的状态（剪贴板管理器扩展）
     pub wlr_data_control_state: WlrDataControlState,
     /// 管理扩展数据控制协议的状态（额外剪贴板功能）
@@ -325,12 +444,28 @@ pub struct Niri {
     pub presentation_state: PresentationState,
     /// 安全上下文状态
     pub security_context_state: SecurityContextState,
+    /// 虚拟键盘管理状态
+    pub virtual_keyboard_state: VirtualKeyboardManagerState,
+    /// 虚拟指针管理状态
+    pub virtual_pointer_state: VirtualPointerManagerState,
+    /// 当前存在的虚拟指针
+    pub virtual_pointers: HashSet<VirtualPointer>,
+    /// 每个客户端当前存在的虚拟键盘数量
+    pub virtual_keyboards: HashMap<ClientId, usize>,
+    /// 虚拟输入设备的权限
+    pub virtual_input: VirtualInputPermissions,
     /// XDG 激活状态
     pub activation_state: XdgActivationState,  
     /// 输入座位（包含键盘、指针等）
     pub seat: Seat<State>,
+    /// 额外的输入座位（实验性多座位支持），各自拥有光标和键盘焦点
+    pub extra_seats: ExtraSeats,
     /// 要抑制的键的扫描码集合
     pub suppressed_keys: HashSet<Keycode>,
+    /// 按下时记住的松开触发的绑定，按扫描码索引
+    pub release_binds: HashMap<Keycode, Bind>,
+    /// 修饰键双击识别器
+    pub modifier_taps: DoubleTapTracker,
     /// 要抑制的鼠标按钮的按钮代码集合
     pub suppressed_buttons: HashSet<u32>,
     /// 按键绑定冷却计时器
@@ -341,12 +476,20 @@ pub struct Niri {
     pub keyboard_focus: KeyboardFocus,
     /// 按需聚焦的层表面
     pub layer_shell_on_demand_focus: Option<LayerSurface>,
-    /// 先前聚焦的窗口（用于恢复焦点）
-    pub previously_focused_window: Option<Window>,
+    /// 窗口焦点历史（用于恢复焦点和循环浏览）
+    pub focus_history: FocusHistory<Window>,
+    /// 窗口缩略图缓存（概览搜索预览和 IPC 共用）
+    pub thumbnails: ThumbnailCache<TextureBuffer<GlesTexture>>,
+    /// 已请求关闭、正在等待超时的窗口
+    pub pending_closes: PendingCloses<RegistrationToken>,
+    /// 各输出和工作区的壁纸
+    pub wallpapers: Wallpapers,
     /// 空闲抑制的表面集合
     pub idle_inhibiting_surfaces: HashSet<WlSurface>,
     /// 标记是否被 FDO 空闲抑制
     pub is_fdo_idle_inhibited: Arc<AtomicBool>,
+    /// 上次用户活动的时间（供 ScreenSaver 接口计算会话空闲时长）
+    pub last_activity: Arc<Mutex<Instant>>,
     /// 键盘快捷键抑制的表面映射
     pub keyboard_shortcuts_inhibiting_surfaces: HashMap<WlSurface, KeyboardShortcutsInhibitor>,  
 
@@ -356,6 +499,8 @@ pub struct Niri {
     pub cursor_texture_cache: CursorTextureCache,
     /// 光标形状管理器状态
     pub cursor_shape_manager_state: CursorShapeManagerState,
+    /// 数位板协议（tablet-v2）状态
+    pub tablet_state: TabletManagerState,
     /// 拖放操作中的图标
     pub dnd_icon: Option<DndIcon>,
     /// 指针下的内容（定期更新）
@@ -364,6 +509,8 @@ pub struct Niri {
     /// 进行抓取的客户端上，但此字段将继续更新为最新内容，就像没有
     /// 抓取处于活动状态一样
     pub pointer_contents: PointContents,
+    /// 锁定指针时按帧批量发送的相对运动
+    pub relative_motion_batch: RelativeMotionBatch,
     /// 指针可见性状态
     pub pointer_visibility: PointerVisibility,
     /// 指针不活动计时器
@@ -376,8 +523,8 @@ pub struct Niri {
     ///
     /// 用于将通知限制为每次迭代一次，以免被高分辨率鼠标所淹没
     pub notified_activity_this_iteration: bool,
-    /// 标记指针是否在热角区域内
-    pub pointer_inside_hot_corner: bool,
+    /// 热角与热边：指针所在的区域、延迟计时器和提示
+    pub hot_areas: HotAreas,
     /// 垂直滚轮跟踪器
     pub vertical_wheel_tracker: ScrollTracker,
     /// 水平滚轮跟踪器
@@ -386,15 +533,72 @@ pub struct Niri {
     pub mods_with_mouse_binds: HashSet<Modifiers>,
     /// 包含滚轮绑定的修饰键集合
     pub mods_with_wheel_binds: HashSet<Modifiers>,
+    /// 按键绑定冲突覆盖层
+    pub bind_conflicts_overlay: BindConflictsOverlay,
+    /// 键盘调整窗口的模式及其屏幕提示
+    pub adjust_mode: AdjustMode,
+    /// 音量和亮度按键调整后显示的屏幕提示
+    pub level_osd: LevelOsd,
+    /// 音量控制线程把新音量发回事件循环的通道
+    pub level_osd_sender: calloop::channel::Sender<Level>,
+    /// 概览中按标题和应用 ID 过滤窗口的搜索框
+    pub overview_search: OverviewSearch,
+    /// 空闲一段时间后把所有输出渐变为黑色
+    pub idle_fade: IdleFade,
+    /// 指针静止一段时间后自动点击（辅助功能）
+    pub dwell_click: DwellClick,
+    /// 等待空闲超时后开始渐黑的计时器
+    pub idle_fade_timer: Option<RegistrationToken>,
+    /// 首次运行的新手教程
+    pub tutorial: Tutorial,
+    /// 教程显示期间推进列导航提示动画的计时器
+    pub tutorial_hint_timer: Option<RegistrationToken>,
+    /// 询问是否允许程序使用虚拟输入设备的提示
+    pub virtual_input_prompt: VirtualInputPrompt,
+    /// 询问是否结束未响应窗口的客户端进程的提示
+    pub kill_prompt: KillPrompt,
+    /// 正在启动的程序在新窗口位置显示的占位提示
+    pub launch_placeholders: LaunchPlaceholders,
+    /// 正在进行的布局快照恢复，新打开的窗口按 app id 放回快照中的位置
+    pub layout_restore: Option<LayoutRestore<MappedId>>,
+    /// 布局快照恢复的超时计时器
+    pub layout_restore_timer: Option<RegistrationToken>,
+    /// 按名字保存的列组，niri 退出前一直保留
+    pub column_groups: HashMap<String, ColumnGroup>,
+    /// 配置加载失败时的通知
+    pub config_error_notification: ConfigErrorNotification,
+    /// 从非关键子系统的崩溃中恢复后显示的提示
+    pub crash_report: CrashReportOverlay,
+    /// 渲染时发生过 panic 的界面叠加层（之后不再渲染，避免每帧重复崩溃）
+    pub crashed_ui_overlays: CrashedOverlays,
     /// 调试标记：是否绘制不透明区域
     pub debug_draw_opaque_regions: bool,
     /// 调试标记：是否绘制损坏区域
     pub debug_draw_damage: bool,  
+    /// 调试标记：是否绘制损坏热力图（经常重绘的区域颜色更热，随时间冷却）
+    pub debug_damage_heatmap: bool,
 
     /// IPC 服务器实例
     pub ipc_server: Option<IpcServer>,
     /// 标记输出状态是否发生变化（需要 IPC 更新）
     pub ipc_outputs_changed: bool,  
+
+    /// 受监管的自启动服务状态
+    pub services: Services,
+
+    /// systemd-logind 集成（仅 TTY 后端启动）
+    #[cfg(feature = "dbus")]
+    pub logind: Option<Logind>,
+    /// niri 启动的锁屏命令（logind 的 Unlock 信号会结束它）
+    pub lock_command: Option<TerminableChild>,
+
+    /// freedesktop ScreenSaver 接口（仅以会话方式运行时启动）
+    #[cfg(feature = "dbus")]
+    pub screen_saver: Option<ScreenSaver>,
+
+    /// Mutter IdleMonitor 接口（仅以会话方式运行时启动）
+    #[cfg(feature = "dbus")]
+    pub idle_monitor: Option<IdleMonitor>,
 }  
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -460,6 +664,20 @@ pub struct OutputState {
     pub backdrop_buffer: SolidColorBuffer,
     /// Damage tracker used for the debug damage visualization.
     pub debug_damage_tracker: OutputDamageTracker,
+    /// Damage accumulated for the debug damage heatmap.
+    pub debug_damage_heatmap: DamageHeatmap,
+    /// Whether the last frame allowed the cursor on the hardware cursor plane.
+    pub cursor_plane: CursorPlane,
+    /// Frozen screen contents transitioning to the live contents.
+    pub screen_transition: Option<ScreenTransition>,
+    /// Timer to redraw when a window throttled by max-render-fps may get its next frame callback.
+    pub throttled_frame_timer: Option<RegistrationToken>,
+    /// Whether the monitor was powered off through IPC while the output stays enabled.
+    pub powered_off: bool,
+    /// Whether the output was switched to grayscale through IPC.
+    pub grayscale: bool,
+    /// Animation of the output saturation after toggling grayscale.
+    pub saturation_anim: Option<Animation>,
 }  
 
 #[derive(Debug, Default)]
@@ -634,6 +852,12 @@ impl State {
         state.ipc_keyboard_layouts_changed();
         // Focus the default monitor if set by the user.
         state.focus_default_monitor();  
+        // Start waiting for the idle fade if it's enabled.
+        state.niri.reschedule_idle_fade();
+        // Put the windows of the new session back where they were when the layout was saved.
+        if !headless {
+            state.niri.restore_saved_layout();
+        }
 
         Ok(state)
     }  
@@ -659,8 +883,10 @@ impl State {
     fn refresh(&mut self) {
         let _span = tracy_client::span!("State::refresh");  
 
-        // Handle commits for surfaces whose blockers cleared this cycle. This should happen before
+        // Let through the timed commits that will make it to their target frame, then handle
+        // commits for surfaces whose blockers cleared this cycle. This should happen before
         // layout.refresh() since this is where these surfaces handle commits.
+        self.niri.release_commit_timers();
         self.notify_blocker_cleared();  
 
         // These should be called periodically, before flushing the clients.
@@ -683,11 +909,203 @@ impl State {
         foreign_toplevel::refresh(self);  
 
         self.niri.refresh_window_rules();
+        self.niri.refresh_overview_search();
+        self.refresh_thumbnails();
+        self.refresh_wallpapers();
         self.refresh_ipc_outputs();
         self.ipc_refresh_layout();
         self.ipc_refresh_keyboard_layout_index();
+        self.ipc_refresh_session();
     }  
 
+    /// Updates the thumbnails shown in the overview search.
+    ///
+    /// The render path only has `&self`, so they are rendered ahead of time here. A thumbnail is
+    /// only re-rendered when it isn't cached yet or when the window committed new contents.
+    fn refresh_thumbnails(&mut self) {
+        let Some(id) = self.niri.overview_search.best_match() else {
+            return;
+        };
+        if !self.niri.thumbnails.needs_render(id) {
+            return;
+        }
+
+        let _span = tracy_client::span!("State::refresh_thumbnails");
+
+        self.backend.with_primary_renderer(|renderer| {
+            self.niri.window_thumbnail(renderer, id);
+        });
+
+        // The search box only shows on the active output.
+        if let Some(output) = self.niri.layout.active_output().cloned() {
+            self.niri.queue_redraw(&output);
+        }
+    }
+
+    /// Loads new wallpapers set by the config or IPC and uploads images decoded in the background.
+    fn refresh_wallpapers(&mut self) {
+        if !self.niri.wallpapers.needs_load() {
+            return;
+        }
+
+        let config = self.niri.config.borrow();
+        let wallpapers = &mut self.niri.wallpapers;
+        let res = self.backend.with_primary_renderer(|renderer| {
+            wallpapers.load(renderer, &config);
+        });
+        drop(config);
+
+        // Keep the flag if the renderer isn't available yet and try again later.
+        if res.is_some() {
+            self.niri.queue_redraw_all();
+        }
+    }
+
+    /// Shows a placeholder for a program that is starting, if enabled in the config.
+    ///
+    /// It goes away once the program activates with its startup token, opens a matching window, or
+    /// times out.
+    pub fn show_launch_placeholder(&mut self, token: &XdgActivationToken, command: &[String]) {
+        let config = self.niri.config.borrow().launch_placeholder;
+        if !config.on {
+            return;
+        }
+
+        let Some(output) = self.niri.layout.active_output() else {
+            return;
+        };
+        let output = output.downgrade();
+        let Some(name) = launch_placeholder::app_name(command) else {
+            return;
+        };
+
+        let token = token.as_str().to_owned();
+        self.niri
+            .event_loop
+            .insert_source(
+                Timer::from_duration(Duration::from_millis(u64::from(config.timeout_ms))),
+                {
+                    let token = token.clone();
+                    move |_, _, state| {
+                        if state.niri.launch_placeholders.remove_token(&token) {
+                            state.niri.queue_redraw_all();
+                        }
+                        TimeoutAction::Drop
+                    }
+                },
+            )
+            .unwrap();
+
+        self.niri.launch_placeholders.add(LaunchPlaceholder {
+            token,
+            name,
+            output,
+        });
+        self.niri.queue_redraw_all();
+    }
+
+    /// Shows the crash recovery prompt.
+    pub fn show_crash_report(&mut self, report: CrashReport) {
+        self.niri.crash_report.show(report);
+        self.niri.queue_redraw_all();
+    }
+
+    /// Restarts niri in safe mode, replacing this process with one using the default config and no
+    /// animations.
+    pub fn restart_in_safe_mode(&mut self) {
+        // The new process keeps the PID, so remove the IPC socket first, or it can't create it
+        // again.
+        self.niri.ipc_server = None;
+
+        let err = crash::exec_safe_mode();
+        warn!("error restarting in safe mode: {err:?}");
+    }
+
+    /// Asks the window to close, then kills (or offers to kill) its client if it's still there
+    /// after a timeout.
+    ///
+    /// Calling this again while the window is already waiting to close times out right away.
+    pub fn close_window_or_kill(&mut self, id: u64, timeout_ms: Option<u32>, auto_kill: bool) {
+        if let Some(pending) = self.niri.pending_closes.remove(id) {
+            self.niri.event_loop.remove(pending.timer);
+            self.escalate_close(id, auto_kill || pending.auto_kill);
+            return;
+        }
+
+        let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
+        let Some((_, mapped)) = window else {
+            return;
+        };
+
+        mapped.toplevel().send_close();
+
+        let timer = self
+            .niri
+            .event_loop
+            .insert_source(
+                Timer::from_duration(pending_close::timeout(timeout_ms)),
+                move |_, _, state| {
+                    if let Some(pending) = state.niri.pending_closes.remove(id) {
+                        state.escalate_close(id, pending.auto_kill);
+                    }
+                    TimeoutAction::Drop
+                },
+            )
+            .unwrap();
+        self.niri
+            .pending_closes
+            .insert(id, PendingClose { auto_kill, timer });
+    }
+
+    /// The window didn't close in time: disconnect its client or show the confirmation prompt.
+    fn escalate_close(&mut self, id: u64, auto_kill: bool) {
+        let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
+        let Some((_, mapped)) = window else {
+            return;
+        };
+
+        if auto_kill {
+            kill_client(&self.niri.display_handle, mapped.toplevel().wl_surface());
+            return;
+        }
+
+        let name = with_toplevel_role(mapped.toplevel(), |role| {
+            role.title.clone().or_else(|| role.app_id.clone())
+        });
+        let request = KillRequest {
+            window_id: id,
+            name: name.unwrap_or_else(|| format!("Window {id}")),
+        };
+        if self.niri.kill_prompt.request(request) {
+            self.niri.queue_redraw_all();
+        }
+    }
+
+    /// Downloads the RGBA pixels of a window thumbnail.
+    ///
+    /// PNG encoding is slow, so callers do it on a background thread (see
+    /// [`thumbnails::save_png`]).
+    pub fn window_thumbnail_pixels(
+        &mut self,
+        id: u64,
+    ) -> Result<(Size<i32, Physical>, Vec<u8>), String> {
+        let _span = tracy_client::span!("State::window_thumbnail_pixels");
+
+        let res = self.backend.with_primary_renderer(|renderer| {
+            let thumbnail = self.niri.window_thumbnail(renderer, id)?;
+            let size = thumbnail.size;
+            let texture = thumbnail.texture.texture();
+            let pixels = download_texture(renderer, texture, size, Fourcc::Abgr8888);
+            Some((size, pixels))
+        });
+        let Some(Some((size, pixels))) = res else {
+            return Err(format!("window with id {id} not found"));
+        };
+        let pixels = pixels.map_err(|err| format!("error downloading thumbnail: {err:?}"))?;
+
+        Ok((size, pixels))
+    }
+
     fn notify_blocker_cleared(&mut self) {
         let dh = self.niri.display_handle.clone();
         while let Ok(client) = self.niri.blocker_cleared_rx.try_recv() {
@@ -795,6 +1213,66 @@ impl State {
         rv
     }  
 
+    /// Warps the pointer to the center or an edge of the focused window.
+    pub fn warp_pointer_to_window(&mut self, target: WindowWarpTarget) -> bool {
+        if !self.niri.keyboard_focus.is_layout() {
+            return false;
+        }
+
+        let Some(output) = self.niri.layout.active_output() else {
+            return false;
+        };
+        let monitor = self.niri.layout.monitor_for_output(output).unwrap();
+        let Some(mut rect) = monitor.active_tile_visual_rectangle() else {
+            return false;
+        };
+
+        let output_geo = self.niri.global_space.output_geometry(output).unwrap();
+        rect.loc += output_geo.loc.to_f64();
+
+        let inset_x = WARP_EDGE_INSET.min(rect.size.w / 2.);
+        let inset_y = WARP_EDGE_INSET.min(rect.size.h / 2.);
+        let center = center_f64(rect);
+        let location = match target {
+            WindowWarpTarget::Center => center,
+            WindowWarpTarget::Left => Point::from((rect.loc.x + inset_x, center.y)),
+            WindowWarpTarget::Right => Point::from((rect.loc.x + rect.size.w - inset_x, center.y)),
+            WindowWarpTarget::Top => Point::from((center.x, rect.loc.y + inset_y)),
+            WindowWarpTarget::Bottom => Point::from((center.x, rect.loc.y + rect.size.h - inset_y)),
+        };
+
+        self.move_cursor(location);
+        true
+    }
+
+    /// Warps the pointer to the center of a region of the focused output.
+    ///
+    /// The output is split into a 3×3 grid, with regions numbered from 1 at the top left to 9 at
+    /// the bottom right. Focus follows the pointer like it would for a real pointer motion.
+    pub fn warp_pointer_to_region(&mut self, region: u8) {
+        if !(1..=9).contains(&region) {
+            warn!("pointer warp region must be between 1 and 9, got {region}");
+            return;
+        }
+
+        let Some(output) = self.niri.layout.active_output() else {
+            return;
+        };
+        let geo = self.niri.global_space.output_geometry(output).unwrap();
+        let geo = geo.to_f64();
+
+        let idx = region - 1;
+        let col = f64::from(idx % 3);
+        let row = f64::from(idx / 3);
+        let cell_w = geo.size.w / 3.;
+        let cell_h = geo.size.h / 3.;
+        let location = geo.loc + Point::from(((col + 0.5) * cell_w, (row + 0.5) * cell_h));
+
+        let under = self.niri.contents_under(location);
+        self.niri.handle_focus_follows_mouse(&under);
+        self.move_cursor(location);
+    }
+
     pub fn focus_default_monitor(&mut self) {
         // Our default target is the first output in sorted order.
         let Some(mut target) = self.niri.sorted_outputs.first().cloned() else {
@@ -839,6 +1317,19 @@ impl State {
         self.niri.queue_redraw_all();
     }  
 
+    /// Focuses the window that best matches the overview search, then closes the overview.
+    pub fn accept_overview_search(&mut self) {
+        let best = self.niri.update_overview_search();
+        self.niri.overview_search.clear();
+        self.niri.update_overview_search();
+
+        if let Some(window) = best {
+            self.focus_window(&window);
+        }
+        self.niri.layout.close_overview();
+        self.niri.queue_redraw_all();
+    }
+
     pub fn maybe_warp_cursor_to_focus(&mut self) -> bool {
         let focused = match self.niri.config.borrow().input.warp_mouse_to_focus {
             None => return false,
@@ -1087,14 +1578,12 @@ impl State {
             );
 
             // Tell the windows their new focus state for window rule purposes.
-            let mut previous_focus = None;
             if let KeyboardFocus::Layout {
                 surface: Some(surface),
             } = &self.niri.keyboard_focus
             {
                 if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(surface) {
                     mapped.set_is_focused(false);
-                    previous_focus = Some(mapped.window.clone());
                 }
             }
             if let KeyboardFocus::Layout {
@@ -1103,32 +1592,27 @@ impl State {
             {
                 if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(surface) {
                     mapped.set_is_focused(true);
+
+                    // Record the window in the focus history. Focus going to layer-shell or to the
+                    // Overview and coming back to the same window leaves the history unchanged,
+                    // while switching to an empty workspace keeps the last focused window in
+                    // front, so that FocusWindowPrevious goes back to it.
+                    self.niri.focus_history.record(&mapped.window);
                 }
             }
 
-            // Update the previous focus but only when staying focused on the layout.
-            //
-            // Case 1: opening and closing exclusive-keyboard layer-shell (e.g. app launcher). This
-            // involves going from Layout to LayerShell, then from LayerShell to Layout. The
-            // previously focused window should stay unchanged.
-            //
-            //     Case 1.5: opening layer-shell, in the background switching layout focus, closing
-            //     layer-shell. With the current logic, this won't update the previously focused
-            //     window, which is incorrect. But this case should be rare.
-            //
-            // Case 2: switching to an empty workspace, then hitting FocusWindowPrevious. The focus
-            // should go to the window that was just focused. The keyboard focus goes from Layout
-            // (with Some surface) to Layout (with None surface), so we update the previously
-            // focused window.
-            //
-            // FIXME: Ideally this should happen inside Layout itself, then there wouldn't be any
-            // problems with layer-shell, etc. Or a similar problem now with the Overview where we
-            // don't update the previously focused window because the keyboard focus is on the
-            // Overview rather than on the Layout.
-            if matches!(self.niri.keyboard_focus, KeyboardFocus::Layout { .. })
-                && matches!(focus, KeyboardFocus::Layout { .. })
-            {
-                self.niri.previously_focused_window = previous_focus;
+            // Layer surfaces draw their focus ring and shadow based on keyboard focus.
+            let is_layer =
+                |focus: &KeyboardFocus| matches!(focus, KeyboardFocus::LayerShell { .. });
+            if is_layer(&self.niri.keyboard_focus) || is_layer(&focus) {
+                self.niri.queue_redraw_all();
+            }
+
+            // Adjust mode acts on the window that had focus when it was opened. Close it when
+            // focus moves away, including when that window is unmapped, so that arrow keys don't
+            // silently start resizing a different window.
+            if self.niri.adjust_mode.close() {
+                self.niri.queue_redraw_all();
             }
 
             if let Some(grab) = self.niri.popup_grab.as_mut() {
@@ -1218,17 +1702,70 @@ impl State {
         }
     }
 
-    pub fn reload_config(&mut self, config: Result<Config, ()>) {
+    /// Starts watching the config file and reloading the config when it changes.
+    ///
+    /// Replaces the previous watcher, so only one config file is watched at a time.
+    pub fn watch_config(&mut self, path: PathBuf) {
+        if let Some((_watcher, token)) = self.niri.config_watcher.take() {
+            self.niri.event_loop.remove(token);
+        }
+
+        let (tx, rx) = calloop::channel::sync_channel(1);
+        let watcher = Watcher::new(path, load_config, tx);
+        let token = self
+            .niri
+            .event_loop
+            .insert_source(rx, |event, _, state| match event {
+                // Reload when a new config arrives.
+                calloop::channel::Event::Msg(config) => state.reload_config(config),
+                calloop::channel::Event::Closed => (),
+            })
+            .unwrap();
+        self.niri.config_watcher = Some((watcher, token));
+    }
+
+    /// Switches to another config file: loads it right away and watches it for changes instead.
+    ///
+    /// If loading fails, keeps the current config and shows the error notification; fixing the file
+    /// reloads it automatically.
+    pub fn load_config_file(&mut self, path: &str) {
+        let path = PathBuf::from(path);
+        let path = match expand_home(&path) {
+            Ok(expanded) => expanded.unwrap_or(path),
+            Err(err) => {
+                warn!("error expanding ~ in config path: {err:?}");
+                return;
+            }
+        };
+
+        info!("loading config from {path:?}");
+        let config = load_config(&path);
+        self.reload_config(config);
+        self.watch_config(path);
+    }
+
+    pub fn reload_config(&mut self, config: Result<Config, ConfigErrorDetail>) {
         let _span = tracy_client::span!("State::reload_config");
 
         let mut config = match config {
             Ok(config) => config,
-            Err(()) => {
+            Err(detail) => {
+                self.niri.config_error_notification.show(detail);
                 self.niri.queue_redraw_all();
                 return;
             }
         };
 
+        if self.niri.config_error_notification.hide() {
+            self.niri.queue_redraw_all();
+        }
+
+        // Keep the values changed at runtime over the ones from the file.
+        self.niri.config_overrides.apply(&mut config);
+
+        // UI overlay contents can depend on the config, e.g. on binds.
+        texture_cache::invalidate_all();
+
         // Find & orphan removed named workspaces.
         let mut removed_workspaces: Vec<String> = vec![];
         for ws in &self.niri.config.borrow().workspaces {
@@ -1256,14 +1793,17 @@ impl State {
             .clock
             .set_complete_instantly(config.animations.off);
 
-        *CHILD_ENV.write().unwrap() = mem::take(&mut config.environment);
+        set_child_env(mem::take(&mut config.environment));
 
         let mut reload_xkb = None;
+        let mut reset_keyboard_leds = false;
         let mut libinput_config_changed = false;
+        let mut idle_fade_changed = false;
         let mut output_config_changed = false;
         let mut preserved_output_config = None;
         let mut window_rules_changed = false;
         let mut layer_rules_changed = false;
+        let mut binds_changed = false;
         let mut shaders_changed = false;
         let mut old_config = self.niri.config.borrow_mut();
 
@@ -1289,6 +1829,28 @@ impl State {
                 config.input.keyboard.repeat_rate.into(),
                 config.input.keyboard.repeat_delay.into(),
             );
+            self.niri.extra_seats.change_repeat_info(
+                config.input.keyboard.repeat_rate.into(),
+                config.input.keyboard.repeat_delay.into(),
+            );
+        }
+
+        // Switching the lock key tracking or the keymap invalidates the saved per-device state.
+        if config.input.keyboard.track_state != old_config.input.keyboard.track_state
+            || config.input.keyboard.xkb != old_config.input.keyboard.xkb
+        {
+            self.niri.keyboard_states.clear();
+            reset_keyboard_leds = true;
+        }
+
+        if config.seats != old_config.seats {
+            self.niri.extra_seats.update_config(&config.seats);
+        }
+
+        if config.input.virtual_input != old_config.input.virtual_input {
+            self.niri
+                .virtual_input
+                .set_policy(config.input.virtual_input);
         }
 
         if config.input.touchpad != old_config.input.touchpad
@@ -1317,6 +1879,14 @@ impl State {
             layer_rules_changed = true;
         }
 
+        // The mod key changes what the binds resolve to.
+        if config.binds != old_config.binds
+            || config.input.mod_key != old_config.input.mod_key
+            || config.input.mod_key_nested != old_config.input.mod_key_nested
+        {
+            binds_changed = true;
+        }
+
         if config.animations.window_resize.custom_shader
             != old_config.animations.window_resize.custom_shader
         {
@@ -1343,6 +1913,23 @@ impl State {
             output_config_changed = true;
         }
 
+        if config.logind.lid_switch != old_config.logind.lid_switch {
+            output_config_changed = true;
+
+            #[cfg(feature = "dbus")]
+            if let Some(logind) = &mut self.niri.logind {
+                logind.set_handle_lid_switch(config.logind.lid_switch.is_some());
+            }
+        }
+
+        if config.idle_fade != old_config.idle_fade {
+            idle_fade_changed = true;
+        }
+
+        if config.input.dwell_click.is_none() {
+            self.niri.dwell_click.reset();
+        }
+
         // FIXME: move backdrop rendering into layout::Monitor, then this will become unnecessary.
         if config.overview.backdrop_color != old_config.overview.backdrop_color {
             output_config_changed = true;
@@ -1351,6 +1938,9 @@ impl State {
             output_config_changed = true;
         }
 
+        // The wallpaper config may have changed, reload on the next refresh.
+        self.niri.wallpapers.mark_needs_load();
+
         *old_config = config;
 
         if let Some(outputs) = preserved_output_config {
@@ -1362,6 +1952,8 @@ impl State {
 
         // Now with a &mut self we can reload the xkb config.
         if let Some(mut xkb) = reload_xkb {
+            self.update_extra_seats_xkb(&xkb);
+
             let mut set_xkb_config = true;
 
             // It's fine to .take() the xkb file, as this is a
@@ -1385,6 +1977,11 @@ impl State {
             self.ipc_keyboard_layouts_changed();
         }
 
+        if reset_keyboard_leds {
+            let keyboard = self.niri.seat.get_keyboard().unwrap();
+            self.update_keyboard_leds(keyboard.led_state());
+        }
+
         if libinput_config_changed {
             let config = self.niri.config.borrow();
             for mut device in self.niri.devices.iter().cloned() {
@@ -1396,6 +1993,13 @@ impl State {
             self.reload_output_config();
         }
 
+        if idle_fade_changed {
+            if self.niri.idle_fade.cancel() {
+                self.niri.queue_redraw_all();
+            }
+            self.niri.reschedule_idle_fade();
+        }
+
         if window_rules_changed {
             self.niri.recompute_window_rules();
         }
@@ -1408,6 +2012,25 @@ impl State {
             self.niri.update_shaders();
         }
 
+        if binds_changed {
+            let config = self.niri.config.borrow();
+            let mod_key = self.backend.mod_key(&config);
+            self.niri.mods_with_mouse_binds = mods_with_mouse_binds(mod_key, &config.binds);
+            self.niri.mods_with_wheel_binds = mods_with_wheel_binds(mod_key, &config.binds);
+
+            let conflicts = find_bind_conflicts(
+                &config.binds,
+                mod_key,
+                config.input.disable_power_key_handling,
+            );
+            for conflict in conflicts {
+                warn!("{}", conflict.describe());
+            }
+
+            self.niri.bind_conflicts_overlay.on_config_updated(mod_key);
+            self.niri.tutorial.on_config_updated(mod_key);
+        }
+
         // Can't really update xdg-decoration settings since we have to hide the globals for CSD
         // due to the SDL2 bug... I don't imagine clients are prepared for the xdg-decoration
         // global suddenly appearing? Either way, right now it's live-reloaded in a sense that new
@@ -1540,6 +2163,58 @@ impl State {
         fun(config);
     }
 
+    /// Changes a config value at runtime, keeping it over the config file until niri exits.
+    pub fn set_config_value(&mut self, path: &str, value: &str) -> Result<(), String> {
+        self.niri.config_overrides.set(path, value)?;
+
+        let mut config = self.niri.config.borrow_mut();
+        let old_xcursor_size = config.cursor.xcursor_size;
+        self.niri.config_overrides.apply(&mut config);
+
+        self.niri.layout.update_config(&config);
+
+        let rate = 1.0 / config.animations.slowdown.max(0.001);
+        self.niri.clock.set_rate(rate);
+        self.niri
+            .clock
+            .set_complete_instantly(config.animations.off);
+
+        if config.cursor.xcursor_size != old_xcursor_size {
+            self.niri
+                .cursor_manager
+                .reload(&config.cursor.xcursor_theme, config.cursor.xcursor_size);
+            self.niri.cursor_texture_cache.clear();
+        }
+
+        drop(config);
+        self.niri.queue_redraw_all();
+
+        Ok(())
+    }
+
+    /// Enables or disables the window rules with this name, or toggles them if `enabled` is
+    /// `None`.
+    pub fn set_window_rule_enabled(&mut self, name: &str, enabled: Option<bool>) {
+        let mut config = self.niri.config.borrow_mut();
+        let Some(rule) = config
+            .window_rules
+            .iter()
+            .find(|rule| rule.name.as_deref() == Some(name))
+        else {
+            warn!("no window rule named {name:?}");
+            return;
+        };
+
+        let enabled = enabled.unwrap_or(rule.off);
+        self.niri
+            .config_overrides
+            .set_window_rule_enabled(name, enabled);
+        self.niri.config_overrides.apply(&mut config);
+        drop(config);
+
+        self.niri.recompute_window_rules();
+    }
+
     pub fn apply_transient_output_config(&mut self, name: &str, action: niri_ipc::OutputAction) {
         self.modify_output_config(name, move |config| match action {
             niri_ipc::OutputAction::Off => config.off = true,
@@ -1575,33 +2250,169 @@ impl State {
                     None
                 }
             }
+            // These don't change the output config and are handled in set_output_powered(),
+            // set_output_gamma() and toggle_output_grayscale().
+            niri_ipc::OutputAction::PowerOff
+            | niri_ipc::OutputAction::PowerOn
+            | niri_ipc::OutputAction::Gamma { .. }
+            | niri_ipc::OutputAction::ToggleGrayscale => (),
         });
 
         self.reload_output_config();
     }
 
-    pub fn refresh_ipc_outputs(&mut self) {
-        if !self.niri.ipc_outputs_changed {
+    /// Powers the monitor of an output off or on, without disabling the output.
+    pub fn set_output_powered(&mut self, name: &str, powered: bool) {
+        let Some(output) = self.niri.output_by_name_match(name).cloned() else {
+            warn!("no output matching {name:?}");
             return;
-        }
-        self.niri.ipc_outputs_changed = false;
-
-        let _span = tracy_client::span!("State::refresh_ipc_outputs");
+        };
 
-        for ipc_output in self.backend.ipc_outputs().lock().unwrap().values_mut() {
-            let logical = self
-                .niri
-                .global_space
-                .outputs()
-                .find(|output| output.name() == ipc_output.name)
-                .map(logical_output);
-            ipc_output.logical = logical;
+        let state = self.niri.output_state.get_mut(&output).unwrap();
+        if state.powered_off != powered {
+            return;
+        }
+        state.powered_off = !powered;
+
+        self.backend.set_output_active(&output, powered);
+        if powered {
+            self.niri.queue_redraw(&output);
+        }
+    }
+
+    /// Sets the software gamma and brightness of an output.
+    ///
+    /// Gamma and brightness of 1 reset the gamma ramp.
+    pub fn set_output_gamma(&mut self, name: &str, gamma: f64, brightness: f64) {
+        let Some(output) = self.niri.output_by_name_match(name).cloned() else {
+            warn!("no output matching {name:?}");
+            return;
+        };
+
+        if !gamma.is_finite() || gamma <= 0. || !(0. ..=1.).contains(&brightness) {
+            warn!("invalid gamma {gamma} or brightness {brightness}");
+            return;
+        }
+
+        let ramp = if gamma == 1. && brightness == 1. {
+            None
+        } else {
+            let size = match self.backend.get_gamma_size(&output) {
+                Ok(size) => size,
+                Err(err) => {
+                    warn!("error getting gamma size for {name}: {err:?}");
+                    return;
+                }
+            };
+            Some(gamma_ramp(size, gamma, brightness))
+        };
+
+        if let Err(err) = self.backend.set_gamma(&output, ramp) {
+            warn!("error setting gamma for {name}: {err:?}");
+        }
+    }
+
+    /// Toggles the grayscale mode of an output, fading its saturation.
+    pub fn toggle_output_grayscale(&mut self, name: &str) {
+        let Some(output) = self.niri.output_by_name_match(name).cloned() else {
+            warn!("no output matching {name:?}");
+            return;
+        };
+
+        let config = self.niri.config.borrow().animations.saturation_change.0;
+        let from = self.niri.output_saturation(&output);
+        let state = self.niri.output_state.get_mut(&output).unwrap();
+        state.grayscale = !state.grayscale;
+        let to = if state.grayscale { 0. } else { 1. };
+        state.saturation_anim = Some(Animation::new(
+            self.niri.clock.clone(),
+            from,
+            to,
+            0.,
+            config,
+        ));
+
+        self.niri.queue_redraw(&output);
+    }
+
+    pub fn refresh_ipc_outputs(&mut self) {
+        if !self.niri.ipc_outputs_changed {
+            return;
+        }
+        self.niri.ipc_outputs_changed = false;
+
+        let _span = tracy_client::span!("State::refresh_ipc_outputs");
+
+        for ipc_output in self.backend.ipc_outputs().lock().unwrap().values_mut() {
+            let logical = self
+                .niri
+                .global_space
+                .outputs()
+                .find(|output| output.name() == ipc_output.name)
+                .map(logical_output);
+            ipc_output.logical = logical;
         }
 
         let new_config = self.backend.ipc_outputs().lock().unwrap().clone();
         self.niri.output_management_state.notify_changes(new_config);
     }
 
+    #[cfg(feature = "dbus")]
+    pub fn on_logind_msg(&mut self, msg: LogindToNiri) {
+        match msg {
+            LogindToNiri::Connected(mut logind) => {
+                let handle_lid_switch = self.niri.config.borrow().logind.lid_switch.is_some();
+                logind.set_handle_lid_switch(handle_lid_switch);
+                // The monitors could have powered off while connecting.
+                logind.set_idle_hint(!self.niri.monitors_active);
+                self.niri.logind = Some(logind);
+            }
+            LogindToNiri::Signal(signal) => self.on_logind_session_signal(signal),
+        }
+    }
+
+    #[cfg(feature = "dbus")]
+    fn on_logind_session_signal(&mut self, signal: SessionSignal) {
+        match signal {
+            SessionSignal::Lock => {
+                // niri has no built-in locker, so run the configured one.
+                if !self.niri.spawn_lock_command() {
+                    warn!("logind asked to lock the session, but logind.lock-command is not set");
+                }
+            }
+            SessionSignal::Unlock => {
+                // Only the locker that niri started is known to be a locker, so that's the one to
+                // stop. A locker started some other way has to unlock itself.
+                if !self.niri.stop_lock_command() {
+                    debug!("logind asked to unlock the session, but no lock command is running");
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "dbus")]
+    pub fn on_screen_saver_msg(&mut self, msg: ScreenSaverToNiri) {
+        match msg {
+            ScreenSaverToNiri::SimulateUserActivity => {
+                // Same as real input: reset the idle timer and wake up powered off monitors.
+                self.niri.activate_monitors(&mut self.backend);
+                self.niri.notify_activity();
+            }
+            ScreenSaverToNiri::InhibitedChanged => self.niri.refresh_idle_inhibit(),
+        }
+    }
+
+    #[cfg(feature = "dbus")]
+    pub fn on_idle_monitor_msg(&mut self, msg: IdleMonitorToNiri) {
+        match msg {
+            IdleMonitorToNiri::ResetIdletime => {
+                // Same as real input: reset the idle timer and wake up powered off monitors.
+                self.niri.activate_monitors(&mut self.backend);
+                self.niri.notify_activity();
+            }
+        }
+    }
+
 
 }
 
@@ -1690,6 +2501,22 @@ impl Niri {
         let security_context_state =
             SecurityContextState::new::<State, _>(&display_handle, client_is_unrestricted);
 
+        let virtual_input = VirtualInputPermissions::new(config_.input.virtual_input);
+        let virtual_keyboard_state = {
+            let virtual_input = virtual_input.clone();
+            // Undecided clients still see the global so that creating a keyboard can raise the
+            // prompt. The creation itself is refused until they're allowed, see handlers.
+            VirtualKeyboardManagerState::new::<State, _>(&display_handle, move |client| {
+                virtual_input.access(client) != VirtualInputAccess::Denied
+            })
+        };
+        let virtual_pointer_state = {
+            let virtual_input = virtual_input.clone();
+            VirtualPointerManagerState::new::<State, _>(&display_handle, move |client| {
+                virtual_input.access(client) != VirtualInputAccess::Denied
+            })
+        };
+
         let text_input_state = TextInputManagerState::new::<State>(&display_handle);
         let input_method_state =
             InputMethodManagerState::new::<State, _>(&display_handle, client_is_unrestricted);
@@ -1701,6 +2528,9 @@ impl Niri {
             OutputManagementManagerState::new::<State, _>(&display_handle, client_is_unrestricted);
         output_management_state.on_config_changed(config_.outputs.clone());
         let viewporter_state = ViewporterState::new::<State>(&display_handle);
+        let alpha_modifier_state = AlphaModifierManagerState::new::<State>(&display_handle);
+        let fifo_state = FifoManagerState::new::<State>(&display_handle);
+        let commit_timing_state = CommitTimingManagerState::new::<State>(&display_handle);
 
         let _is_tty = matches!(backend, Backend::Tty(_));
         let activation_state = XdgActivationState::new::<State>(&display_handle);
@@ -1744,13 +2574,45 @@ impl Niri {
         }
         seat.add_pointer();
 
+        let extra_seats = ExtraSeats::new(
+            &mut seat_state,
+            &display_handle,
+            &config_,
+            backend.seat_name(),
+        );
+
         let cursor_shape_manager_state = CursorShapeManagerState::new::<State>(&display_handle);
+        let tablet_state = TabletManagerState::new::<State>(&display_handle);
         let cursor_manager =
             CursorManager::new(&config_.cursor.xcursor_theme, config_.cursor.xcursor_size);
 
         let mod_key = backend.mod_key(&config.borrow());
         let mods_with_mouse_binds = mods_with_mouse_binds(mod_key, &config_.binds);
         let mods_with_wheel_binds = mods_with_wheel_binds(mod_key, &config_.binds);
+        let bind_conflicts_overlay = BindConflictsOverlay::new(config.clone(), mod_key);
+        let tutorial = Tutorial::new(config.clone(), mod_key);
+
+        // Wallpapers are decoded on a background thread and sent back to the event loop.
+        let (wallpaper_sender, rx) = calloop::channel::channel();
+        event_loop
+            .insert_source(rx, |event, _, state| match event {
+                calloop::channel::Event::Msg(decoded) => state.niri.wallpapers.on_decoded(decoded),
+                calloop::channel::Event::Closed => (),
+            })
+            .unwrap();
+        let wallpapers = Wallpapers::new(animation_clock.clone(), wallpaper_sender);
+
+        let launch_placeholders = LaunchPlaceholders::new(animation_clock.clone());
+        let idle_fade = IdleFade::new(animation_clock.clone());
+        let dwell_click = DwellClick::new(animation_clock.clone());
+
+        let (level_osd_sender, rx) = calloop::channel::channel();
+        event_loop
+            .insert_source(rx, |event, _, state| match event {
+                calloop::channel::Event::Msg(level) => state.show_level_osd(level),
+                calloop::channel::Event::Closed => (),
+            })
+            .unwrap();
 
         event_loop
             .insert_source(
@@ -1771,6 +2633,7 @@ impl Niri {
                         client,
                         restricted: false,
                         credentials_unknown: false,
+                        sandbox: None,
                     });
                 })
                 .unwrap();
@@ -1801,8 +2664,9 @@ impl Niri {
                 Timer::from_duration(Duration::from_secs(60)),
                 |_, _, state| {
                     let _span = tracy_client::span!("startup timeout");
+                    // Windows match at-startup by their state when they opened, so only layer rules
+                    // need recomputing here.
                     state.niri.is_at_startup = false;
-                    state.niri.recompute_window_rules();
                     state.niri.recompute_layer_rules();
                     TimeoutAction::Drop
                 },
@@ -1813,6 +2677,8 @@ impl Niri {
         let niri = Self {
             config,
             config_file_output_config,
+            config_overrides: ConfigOverrides::default(),
+            config_watcher: None,
 
             event_loop,
             scheduler,
@@ -1828,16 +2694,25 @@ impl Niri {
             sorted_outputs: Vec::default(),
             output_state: HashMap::new(),
             unmapped_windows: HashMap::new(),
+            swallowed_windows: Vec::new(),
             unmapped_layer_surfaces: HashSet::new(),
             mapped_layer_surfaces: HashMap::new(),
             root_surface: HashMap::new(),
             dmabuf_pre_commit_hook: HashMap::new(),
             blocker_cleared_tx,
             blocker_cleared_rx,
+            commit_timers: Vec::new(),
+            commit_timer_wakeup: None,
             monitors_active: true,
+            is_session_paused: false,
             is_lid_closed: false,
 
             devices: HashSet::new(),
+            keyboard_states: PerDeviceKeyboardState::default(),
+            tablets: HashMap::new(),
+            tablet_cursor_location: None,
+            tablet_pad_axes: PadAxisTracker::default(),
+            touch_gestures: TouchGestures::default(),
 
             compositor_state,
             xdg_shell_state,
@@ -1846,6 +2721,9 @@ impl Niri {
             foreign_toplevel_state,
             output_management_state,
             viewporter_state,
+            alpha_modifier_state,
+            fifo_state,
+            commit_timing_state,
             text_input_state,
             input_method_state,
             keyboard_shortcuts_inhibit_state,
@@ -1860,45 +2738,88 @@ impl Niri {
             idle_inhibit_manager_state,
             data_device_state,
             primary_selection_state,
+            selection_sync_last: None,
+            selection_sync_timer: None,
             wlr_data_control_state,
             ext_data_control_state,
             popups: PopupManager::default(),
             popup_grab: None,
             suppressed_keys: HashSet::new(),
+            release_binds: HashMap::new(),
+            modifier_taps: DoubleTapTracker::default(),
             suppressed_buttons: HashSet::new(),
             bind_cooldown_timers: HashMap::new(),
             bind_repeat_timer: Option::default(),
             presentation_state,
             security_context_state,
+            virtual_keyboard_state,
+            virtual_pointer_state,
+            virtual_pointers: HashSet::new(),
+            virtual_keyboards: HashMap::new(),
+            virtual_input,
             activation_state,
             seat,
+            extra_seats,
             keyboard_focus: KeyboardFocus::Layout { surface: None },
             layer_shell_on_demand_focus: None,
-            previously_focused_window: None,
+            focus_history: FocusHistory::new(),
+            thumbnails: ThumbnailCache::new(thumbnails::DEFAULT_MEMORY_BUDGET),
+            pending_closes: PendingCloses::new(),
+            wallpapers,
             idle_inhibiting_surfaces: HashSet::new(),
             is_fdo_idle_inhibited: Arc::new(AtomicBool::new(false)),
+            last_activity: Arc::new(Mutex::new(Instant::now())),
             keyboard_shortcuts_inhibiting_surfaces: HashMap::new(),
             cursor_manager,
             cursor_texture_cache: Default::default(),
             cursor_shape_manager_state,
+            tablet_state,
             dnd_icon: None,
             pointer_contents: PointContents::default(),
+            relative_motion_batch: RelativeMotionBatch::default(),
             pointer_visibility: PointerVisibility::Visible,
             pointer_inactivity_timer: None,
             pointer_inactivity_timer_got_reset: false,
             notified_activity_this_iteration: false,
-            pointer_inside_hot_corner: false,
+            hot_areas: HotAreas::new(),
             vertical_wheel_tracker: ScrollTracker::new(120),
             horizontal_wheel_tracker: ScrollTracker::new(120),
             mods_with_mouse_binds,
             mods_with_wheel_binds,
-
+            bind_conflicts_overlay,
+            adjust_mode: AdjustMode::new(),
+            level_osd: LevelOsd::new(),
+            level_osd_sender,
+            overview_search: OverviewSearch::new(),
+            idle_fade,
+            idle_fade_timer: None,
+            dwell_click,
+            tutorial,
+            tutorial_hint_timer: None,
+            virtual_input_prompt: VirtualInputPrompt::new(),
+            kill_prompt: KillPrompt::new(),
+            launch_placeholders,
+            layout_restore: None,
+            layout_restore_timer: None,
+            column_groups: HashMap::new(),
+            config_error_notification: ConfigErrorNotification::new(),
+            crash_report: CrashReportOverlay::new(),
+            crashed_ui_overlays: CrashedOverlays::default(),
 
             debug_draw_opaque_regions: false,
             debug_draw_damage: false,
+            debug_damage_heatmap: false,
 
             ipc_server,
+            #[cfg(feature = "dbus")]
+            logind: None,
+            lock_command: None,
+            #[cfg(feature = "dbus")]
+            screen_saver: None,
+            #[cfg(feature = "dbus")]
+            idle_monitor: None,
             ipc_outputs_changed: false,
+            services: Services::default(),
 
         };
 
@@ -1910,6 +2831,7 @@ impl Niri {
             client,
             restricted,
             credentials_unknown,
+            sandbox,
         } = client;
 
         let config = self.config.borrow();
@@ -1919,10 +2841,24 @@ impl Niri {
             primary_selection_disabled: config.clipboard.disable_primary,
             restricted,
             credentials_unknown,
+            process: OnceLock::new(),
+            sandbox,
         });
 
-        if let Err(err) = self.display_handle.insert_client(client, data) {
-            warn!("error inserting client: {err}");
+        match self.display_handle.insert_client(client, data.clone()) {
+            Ok(client) => {
+                if let Ok(credentials) = client.get_credentials(&self.display_handle) {
+                    let pid = credentials.pid;
+                    let executable = fs::read_link(format!("/proc/{pid}/exe")).ok();
+                    let flatpak_app_id = read_flatpak_app_id(pid);
+                    let _ = data.process.set(ClientProcess {
+                        pid,
+                        executable,
+                        flatpak_app_id,
+                    });
+                }
+            }
+            Err(err) => warn!("error inserting client: {err}"),
         }
     }
 
@@ -2107,6 +3043,13 @@ impl Niri {
             background_buffer: SolidColorBuffer::new(size, background_color),
             backdrop_buffer: SolidColorBuffer::new(size, backdrop_color),
             debug_damage_tracker: OutputDamageTracker::from_output(&output),
+            debug_damage_heatmap: DamageHeatmap::new(&output),
+            cursor_plane: CursorPlane::Composited(CursorPlaneFallback::Unsupported),
+            screen_transition: None,
+            throttled_frame_timer: None,
+            powered_off: false,
+            grayscale: false,
+            saturation_anim: None,
         };
         let rv = self.output_state.insert(output.clone(), state);
         assert!(rv.is_none(), "output was already tracked");
@@ -2134,6 +3077,10 @@ impl Niri {
             RedrawState::WaitingForEstimatedVBlankAndQueued(token) => self.event_loop.remove(token),
         }
 
+        if let Some(token) = state.throttled_frame_timer {
+            self.event_loop.remove(token);
+        }
+
         // Disable the output global and remove some time later to give the clients some time to
         // process it.
         let global = state.global;
@@ -2190,6 +3137,12 @@ impl Niri {
 
         self.monitors_active = false;
         backend.set_monitors_active(false);
+
+        // Powered off monitors are what niri considers an idle session.
+        #[cfg(feature = "dbus")]
+        if let Some(logind) = &mut self.logind {
+            logind.set_idle_hint(true);
+        }
     }
 
     pub fn activate_monitors(&mut self, backend: &mut Backend) {
@@ -2200,9 +3153,122 @@ impl Niri {
         self.monitors_active = true;
         backend.set_monitors_active(true);
 
+        #[cfg(feature = "dbus")]
+        if let Some(logind) = &mut self.logind {
+            logind.set_idle_hint(false);
+        }
+
         self.queue_redraw_all();
     }
 
+    /// Connects to logind to report the idle hint, receive session signals and take over the lid
+    /// switch if configured.
+    ///
+    /// The connection is made in the background; `logind` is set once it's ready.
+    #[cfg(feature = "dbus")]
+    pub fn start_logind(&mut self) {
+        let (tx, rx) = calloop::channel::channel();
+        self.event_loop
+            .insert_source(rx, |event, _, state| match event {
+                calloop::channel::Event::Msg(msg) => state.on_logind_msg(msg),
+                calloop::channel::Event::Closed => (),
+            })
+            .unwrap();
+
+        logind::start(&self.scheduler, tx);
+    }
+
+    /// Serves the freedesktop ScreenSaver interface on the session bus (idle inhibiting, simulating
+    /// user activity and querying the idle time).
+    #[cfg(feature = "dbus")]
+    pub fn start_screen_saver(&mut self) {
+        let (tx, rx) = calloop::channel::channel();
+        self.event_loop
+            .insert_source(rx, |event, _, state| match event {
+                calloop::channel::Event::Msg(msg) => state.on_screen_saver_msg(msg),
+                calloop::channel::Event::Closed => (),
+            })
+            .unwrap();
+
+        match ScreenSaver::start(
+            self.is_fdo_idle_inhibited.clone(),
+            self.last_activity.clone(),
+            tx,
+        ) {
+            Ok(screen_saver) => self.screen_saver = Some(screen_saver),
+            Err(err) => warn!("error starting the ScreenSaver interface: {err:?}"),
+        }
+    }
+
+    /// Serves the Mutter IdleMonitor interface on the session bus (idle and activity watches, used
+    /// by GNOME apps to detect presence).
+    #[cfg(feature = "dbus")]
+    pub fn start_idle_monitor(&mut self) {
+        let (tx, rx) = calloop::channel::channel();
+        self.event_loop
+            .insert_source(rx, |event, _, state| match event {
+                calloop::channel::Event::Msg(msg) => state.on_idle_monitor_msg(msg),
+                calloop::channel::Event::Closed => (),
+            })
+            .unwrap();
+
+        match IdleMonitor::start(self.last_activity.clone(), tx) {
+            Ok(idle_monitor) => self.idle_monitor = Some(idle_monitor),
+            Err(err) => warn!("error starting the IdleMonitor interface: {err:?}"),
+        }
+    }
+
+    /// Starts xwayland-satellite for X11 apps and restarts it when it crashes.
+    ///
+    /// Must be called after WAYLAND_DISPLAY is set; processes spawned afterwards get a DISPLAY
+    /// pointing at it. niri creates the X11 sockets and passes them to satellite, so DISPLAY works
+    /// as soon as it is exported.
+    /// Once satellite stops being restarted, the sockets are closed and DISPLAY is removed from the
+    /// environment of spawned processes.
+    pub fn start_xwayland_satellite(&mut self) {
+        let config = self.config.borrow().xwayland_satellite.clone();
+        if config.off {
+            return;
+        }
+
+        if find_executable(&config.path).is_none() {
+            debug!("{} not found, X11 apps won't be supported", config.path);
+            return;
+        }
+
+        let Some(sockets) = X11Sockets::bind() else {
+            warn!("error starting xwayland-satellite: no free X11 display");
+            return;
+        };
+        let display = sockets.display_name();
+
+        let mut command = vec![config.path, display.clone()];
+        for fd in sockets.listen_fds() {
+            command.push(String::from("-listenfd"));
+            command.push(fd.to_string());
+        }
+
+        let hooks = ServiceHooks {
+            inherit_fds: sockets.listen_fds().to_vec(),
+            on_spawn: Some(Box::new(|pid| set_satellite_pid(Some(pid)))),
+            // The supervisor thread owns the sockets and closes them when it finishes.
+            on_stop: Some(Box::new(move || {
+                warn!("xwayland-satellite stopped, X11 apps won't be supported");
+                set_satellite_pid(None);
+                clear_xwayland_display();
+                drop(sockets);
+            })),
+        };
+
+        info!("starting xwayland-satellite on X11 display {display}");
+        // Set DISPLAY first: if satellite stops right away, on_stop removes it again.
+        set_xwayland_display(display);
+        let services = self.services.clone();
+        if !spawn_supervised_with_hooks(command, config.max_restarts, services, hooks) {
+            clear_xwayland_display();
+        }
+    }
+
     pub fn output_under(&self, pos: Point<f64, Logical>) -> Option<(&Output, Point<f64, Logical>)> {
         let output = self.global_space.output_under(pos).next()?;
         let pos_within_output = pos
@@ -2216,6 +3282,28 @@ impl Niri {
         Some((output, pos_within_output))
     }
 
+    /// Returns the hot corner config of the output, its own settings take precedence over the
+    /// global ones.
+    pub fn hot_corners_for(&self, output: &Output) -> HotCorners {
+        let config = self.config.borrow();
+        let name = output.user_data().get::<OutputName>().unwrap();
+        config
+            .outputs
+            .find(name)
+            .and_then(|c| c.hot_corners.clone())
+            .unwrap_or_else(|| config.gestures.hot_corners.clone())
+    }
+
+    /// Returns the hot area under the position within the output and its geometry in the output.
+    pub fn hot_area_under(
+        &self,
+        output: &Output,
+        pos_within_output: Point<f64, Logical>,
+    ) -> Option<(HotArea, Rectangle<f64, Logical>)> {
+        let config = self.hot_corners_for(output);
+        hot_areas::hot_area_under(&config, output_size(output), pos_within_output)
+    }
+
     pub fn is_sticky_obscured_under(
         &self,
         output: &Output,
@@ -2259,12 +3347,8 @@ impl Niri {
             return false;
         }
 
-        let hot_corners = self.config.borrow().gestures.hot_corners;
-        if !hot_corners.off {
-            let hot_corner = Rectangle::from_size(Size::from((1., 1.)));
-            if hot_corner.contains(pos_within_output) {
-                return true;
-            }
+        if self.hot_area_under(output, pos_within_output).is_some() {
+            return true;
         }
 
         if layer_popup_under(Layer::Top) || layer_toplevel_under(Layer::Top) {
@@ -2499,12 +3583,8 @@ impl Niri {
                 .or_else(|| layer_toplevel_under(Layer::Bottom))
                 .or_else(|| layer_toplevel_under(Layer::Background));
         } else {
-            let hot_corners = self.config.borrow().gestures.hot_corners;
-            if !hot_corners.off {
-                let hot_corner = Rectangle::from_size(Size::from((1., 1.)));
-                if hot_corner.contains(pos_within_output) {
-                    return rv;
-                }
+            if self.hot_area_under(output, pos_within_output).is_some() {
+                return rv;
             }
 
             under = under
@@ -2659,107 +3739,510 @@ impl Niri {
             .cloned()
     }
 
-    pub fn output_by_name_match(&self, target: &str) -> Option<&Output> {
-        self.global_space
-            .outputs()
-            .find(|output| output_matches_name(output, target))
+    /// Finds a window with the given app ID, preferring the most recently focused one.
+    ///
+    /// If `all_outputs` is false, only looks on the active output.
+    pub fn window_by_app_id(&self, app_id: &str, all_outputs: bool) -> Option<Window> {
+        let active_output = self.layout.active_output();
+        let windows: Vec<_> = self
+            .layout
+            .windows()
+            .filter(|(mon, _)| all_outputs || mon.map(|mon| mon.output()) == active_output)
+            .map(|(_, mapped)| mapped)
+            .filter(|mapped| {
+                with_toplevel_role(mapped.toplevel(), |role| {
+                    role.app_id.as_deref() == Some(app_id)
+                })
+            })
+            .map(|mapped| mapped.window.clone())
+            .collect();
+
+        let recent = self
+            .focus_history
+            .iter()
+            .find(|window| windows.contains(window))
+            .cloned();
+        recent.or_else(|| windows.into_iter().next())
     }
 
-    pub fn output_for_root(&self, root: &WlSurface) -> Option<&Output> {
-        // Check the main layout.
-        let win_out = self.layout.find_window_and_output(root);
-        let layout_output = win_out.map(|(_, output)| output);
-        if let Some(output) = layout_output {
-            return output;
+    /// Picks the window to focus after the focused window closes.
+    ///
+    /// Must be called before removing the window; `None` keeps the layout's default choice.
+    pub fn window_to_focus_after_close(&self, window: &Window) -> Option<MappedId> {
+        let (_, mapped) = self.layout.windows().find(|(_, m)| &m.window == window)?;
+        let focus = mapped.rules().focus_after_close;
+        let focus = focus.unwrap_or(self.config.borrow().layout.focus_after_close);
+
+        match focus {
+            FocusAfterClose::Auto => None,
+            FocusAfterClose::Left => self
+                .layout
+                .column_neighbor_window(&mapped.id(), ScrollDirection::Left)
+                .map(|m| m.id()),
+            FocusAfterClose::Right => self
+                .layout
+                .column_neighbor_window(&mapped.id(), ScrollDirection::Right)
+                .map(|m| m.id()),
+            FocusAfterClose::Previous => {
+                // Only look on the same workspace to avoid jumping away when closing a window.
+                let (_, _, ws) = self
+                    .layout
+                    .workspaces()
+                    .find(|(_, _, ws)| ws.has_window(&mapped.id()))?;
+                self.focus_history
+                    .iter()
+                    .filter(|w| *w != window)
+                    .find_map(|w| ws.windows().find(|m| &m.window == w))
+                    .map(|m| m.id())
+            }
+            FocusAfterClose::Parent => {
+                let parent = mapped.toplevel().parent()?;
+                let (parent, _) = self.layout.find_window_and_output(&parent)?;
+                Some(parent.id())
+            }
         }
-
-        // Check layer-shell.
-        let has_layer_surface = |o: &&Output| {
-            layer_map_for_output(o)
-                .layer_for_surface(root, WindowSurfaceType::TOPLEVEL)
-                .is_some()
-        };
-        self.layout.outputs().find(has_layer_surface)
     }
 
-    /// Schedules an immediate redraw on all outputs if one is not already scheduled.
-    pub fn queue_redraw_all(&mut self) {
-        for state in self.output_state.values_mut() {
-            state.redraw_state = mem::take(&mut state.redraw_state).queue_redraw();
+    /// Saves the current layout as a snapshot file, at the default location if no path is given.
+    pub fn save_layout_snapshot(&self, path: Option<&str>) -> Result<(), String> {
+        let path = layout_snapshot_path(path)?;
+        let app_id = |mapped: &Mapped| with_toplevel_role(mapped.toplevel(), |r| r.app_id.clone());
+        let snapshot = self.layout.snapshot(app_id);
+        let json = serde_json::to_string_pretty(&snapshot)
+            .map_err(|err| format!("error serializing layout snapshot: {err}"))?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| format!("error creating {parent:?}: {err}"))?;
         }
+        fs::write(&path, json).map_err(|err| format!("error writing {path:?}: {err}"))
     }
 
-    /// Schedules an immediate redraw if one is not already scheduled.
-    pub fn queue_redraw(&mut self, output: &Output) {
-        let state = self.output_state.get_mut(output).unwrap();
-        state.redraw_state = mem::take(&mut state.redraw_state).queue_redraw();
+    /// Loads a layout snapshot, windows opening for a while afterwards go back to their snapshot
+    /// places by app id.
+    pub fn restore_layout_snapshot(&mut self, path: Option<&str>) -> Result<(), String> {
+        let path = layout_snapshot_path(path)?;
+        let snapshot = LayoutSnapshot::load(&path)?;
+        self.start_layout_restore(LayoutRestore::new(snapshot));
+        Ok(())
     }
 
-    pub fn redraw_queued_outputs(&mut self, backend: &mut Backend) {
-        let _span = tracy_client::span!("Niri::redraw_queued_outputs");
-
-        while let Some((output, _)) = self.output_state.iter().find(|(_, state)| {
-            matches!(
-                state.redraw_state,
-                RedrawState::Queued | RedrawState::WaitingForEstimatedVBlankAndQueued(_)
-            )
-        }) {
-            trace!("redrawing output");
-            let output = output.clone();
-            self.redraw(backend, &output);
+    /// Starts restoring a layout, windows opening for a while afterwards take the remaining places
+    /// by app id.
+    ///
+    /// Replaces a restore in progress.
+    fn start_layout_restore(&mut self, restore: LayoutRestore<MappedId>) {
+        if let Some(token) = self.layout_restore_timer.take() {
+            self.event_loop.remove(token);
         }
+
+        self.layout_restore = Some(restore);
+
+        // After the timeout, the remaining places stop waiting for windows.
+        let token = self
+            .event_loop
+            .insert_source(Timer::from_duration(RESTORE_TIMEOUT), |_, _, state| {
+                state.niri.layout_restore = None;
+                state.niri.layout_restore_timer = None;
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.layout_restore_timer = Some(token);
     }
 
-    pub fn pointer_element<R: NiriRenderer>(
-        &self,
-        renderer: &mut R,
-        output: &Output,
-    ) -> Vec<OutputRenderElements<R>> {
-        if !self.pointer_visibility.is_visible() {
-            return vec![];
+    /// Saves columns of the current workspace as a column group: `count` columns starting from the
+    /// active one, or all columns if not given.
+    pub fn save_column_group(&mut self, name: String, count: Option<usize>) -> Result<(), String> {
+        let app_id = |mapped: &Mapped| with_toplevel_role(mapped.toplevel(), |r| r.app_id.clone());
+        let columns = self.layout.snapshot_active_columns(count, app_id);
+        if columns.is_empty() {
+            return Err(String::from("no columns to save"));
         }
 
-        let _span = tracy_client::span!("Niri::pointer_element");
-        let output_scale = output.current_scale();
-        let output_pos = self.global_space.output_geometry(output).unwrap().loc;
+        self.column_groups.insert(name, ColumnGroup { columns });
+        Ok(())
+    }
 
-        // Check whether we need to draw the tablet cursor or the regular cursor.
-        let pointer_pos = self.seat.get_pointer().unwrap().current_location();
-        let pointer_pos = pointer_pos - output_pos.to_f64();
+    /// Recreates a column group on the current workspace.
+    ///
+    /// Open windows with the same app id move to their places in the group. Missing programs are
+    /// started from their desktop file by app id, and their windows take the remaining places like
+    /// when restoring a layout snapshot.
+    pub fn recall_column_group(&mut self, name: &str) -> Result<(), String> {
+        let group = self
+            .column_groups
+            .get(name)
+            .ok_or_else(|| format!("no column group named {name:?}"))?
+            .clone();
+        let workspace_id = self
+            .layout
+            .active_workspace()
+            .map(|ws| ws.id())
+            .ok_or("no active workspace")?;
+        let snapshot = self
+            .layout
+            .snapshot_for_active_workspace(group.columns)
+            .ok_or("no active workspace")?;
+        let mut restore = LayoutRestore::new(snapshot);
+
+        // Move windows in the group order, each one next to the previously placed one.
+        let app_ids: Vec<String> = restore.free_app_ids().map(String::from).collect();
+        let mut moved = Vec::new();
+        for app_id in app_ids {
+            let window = self.layout.windows().find_map(|(_, mapped)| {
+                let id = mapped.id();
+                let matches = with_toplevel_role(mapped.toplevel(), |role| {
+                    role.app_id.as_deref() == Some(app_id.as_str())
+                });
+                (matches && !moved.contains(&id)).then_some(id)
+            });
+            let Some(id) = window else {
+                continue;
+            };
+            let Some(slot) = restore.take_slot(&app_id) else {
+                continue;
+            };
 
-        // Get the render cursor to draw.
-        let cursor_scale = output_scale.integer_scale();
-        let render_cursor = self.cursor_manager.get_render_cursor(cursor_scale);
+            let anchor = restore
+                .anchor(&slot, |id| self.layout.has_window(id))
+                .map(|(anchor, consume)| (*anchor, consume));
+            let target = match &anchor {
+                Some((anchor, _)) => AddWindowTarget::NextTo(anchor),
+                None => AddWindowTarget::Workspace(workspace_id),
+            };
+            self.layout
+                .readd_window(&id, target, Some(slot.width), slot.is_full_width);
+            if anchor.is_some_and(|(_, consume)| consume) {
+                self.layout.consume_or_expel_window_left(Some(&id));
+            }
 
-        let output_scale = Scale::from(output.current_scale().fractional_scale());
+            restore.window_placed(&slot, id);
+            moved.push(id);
+        }
 
-        let mut pointer_elements = match render_cursor {
-            RenderCursor::Hidden => vec![],
-            RenderCursor::Surface { surface, hotspot } => {
-                let pointer_pos =
-                    (pointer_pos - hotspot.to_f64()).to_physical_precise_round(output_scale);
+        if let Some(first) = moved.first() {
+            self.layout.activate_window(first);
+        }
 
-                render_elements_from_surface_tree(
-                    renderer,
-                    &surface,
-                    pointer_pos,
-                    output_scale,
-                    1.,
-                    Kind::Cursor,
-                )
+        if !restore.is_done() {
+            for app_id in restore.free_app_ids() {
+                let (token, _) = self.activation_state.create_external_token(None);
+                spawn_desktop_entry(app_id.to_owned(), token.clone());
             }
-            RenderCursor::Named {
-                icon,
-                scale,
-                cursor,
-            } => {
-                let (idx, frame) = cursor.frame(self.start_time.elapsed().as_millis() as u32);
-                let hotspot = XCursor::hotspot(frame).to_logical(scale);
-                let pointer_pos =
-                    (pointer_pos - hotspot.to_f64()).to_physical_precise_round(output_scale);
 
-                let texture = self.cursor_texture_cache.get(icon, scale, &cursor, idx);
-                let mut pointer_elements = vec![];
+            self.start_layout_restore(restore);
+        }
+
+        self.queue_redraw_all();
+        Ok(())
+    }
+
+    /// Records that a window went back to its snapshot place, ends the restore once all places are
+    /// used.
+    pub fn layout_restore_window_placed(&mut self, slot: &RestoreSlot, id: MappedId) {
+        let Some(restore) = &mut self.layout_restore else {
+            return;
+        };
+
+        restore.window_placed(slot, id);
+        if restore.is_done() {
+            self.layout_restore = None;
+            if let Some(token) = self.layout_restore_timer.take() {
+                self.event_loop.remove(token);
+            }
+        }
+    }
+
+    /// Finds a window that can swallow the new window: it has the swallow-children rule and its
+    /// process is an ancestor of the new window's process.
+    pub fn find_swallower(&self, pid: i32) -> Option<MappedId> {
+        let mut rv = None;
+        self.layout.with_windows(|mapped, _, _| {
+            if rv.is_some() || mapped.rules().swallow_children != Some(true) {
+                return;
+            }
+
+            let Some(creds) = mapped.credentials() else {
+                return;
+            };
+            // New windows from the same process (e.g. a new terminal window) are not children.
+            if creds.pid != pid && is_descendant_process(pid, creds.pid) {
+                rv = Some(mapped.id());
+            }
+        });
+        rv
+    }
+
+    /// Puts the window in place of the swallower and takes the swallower out of the layout.
+    pub fn swallow_window(&mut self, swallower: MappedId, window: MappedId) {
+        let was_active = self.layout.focus().is_some_and(|m| m.id() == window);
+        if let Some(mapped) = self.layout.swallow_window(&swallower, &window) {
+            self.swallowed_windows.push((window, mapped));
+
+            // Focus may move to another column after leaving the original column.
+            if was_active {
+                self.layout.activate_window(&window);
+            }
+        }
+    }
+
+    /// Whether closing the window puts its swallowed window back in its place, rather than removing
+    /// the place.
+    pub fn will_restore_swallowed_window(&self, window: MappedId) -> bool {
+        self.swallowed_windows.iter().any(|(id, _)| *id == window)
+            && self.layout.can_replace_window(&window)
+    }
+
+    /// Iterates over swallowed windows (taken out of the layout) with the output and workspace of
+    /// their swallower.
+    ///
+    /// Swallowed windows still exist, so IPC and the foreign toplevel list need to keep them.
+    pub fn with_swallowed_windows(
+        &self,
+        mut f: impl FnMut(&Mapped, Option<&Output>, Option<WorkspaceId>),
+    ) {
+        for (swallower, mapped) in &self.swallowed_windows {
+            let location = self
+                .layout
+                .workspaces()
+                .find(|(_, _, ws)| ws.has_window(swallower));
+            match location {
+                Some((mon, _, ws)) => f(mapped, mon.map(|mon| mon.output()), Some(ws.id())),
+                None => f(mapped, None, None),
+            }
+        }
+    }
+
+    /// Puts the window swallowed by a closing window back in its place.
+    ///
+    /// Returns `true` if the window was replaced out of the layout and the caller needn't remove
+    /// it.
+    pub fn restore_swallowed_window(&mut self, window: MappedId) -> bool {
+        let Some(idx) = self
+            .swallowed_windows
+            .iter()
+            .position(|(id, _)| *id == window)
+        else {
+            return false;
+        };
+
+        let (_, swallowed) = self.swallowed_windows.remove(idx);
+        match self.layout.replace_window(&window, swallowed) {
+            Ok(_) => true,
+            Err(swallowed) => {
+                // The swallower is no longer in the tiling layout (e.g. it became floating), put
+                // the swallowed window back as a new window.
+                self.layout.add_window(
+                    swallowed,
+                    AddWindowTarget::Auto,
+                    None,
+                    None,
+                    false,
+                    false,
+                    ActivateWindow::No,
+                );
+                false
+            }
+        }
+    }
+
+    /// Starts the timer that advances the hint animation once the tutorial opens.
+    ///
+    /// The hint animation jumps to the next frame at a fixed interval, so only redraw on frame
+    /// changes rather than continuously. The timer removes itself once the tutorial closes.
+    pub fn start_tutorial_hint_timer(&mut self) {
+        if self.tutorial_hint_timer.is_some() {
+            return;
+        }
+
+        let token = self
+            .event_loop
+            .insert_source(Timer::from_duration(HINT_INTERVAL), |_, _, state| {
+                if !state.niri.tutorial.is_open() {
+                    state.niri.tutorial_hint_timer = None;
+                    return TimeoutAction::Drop;
+                }
+
+                if state.niri.tutorial.advance_hint() {
+                    // The tutorial only shows on the active output.
+                    if let Some(output) = state.niri.layout.active_output().cloned() {
+                        state.niri.queue_redraw(&output);
+                    }
+                }
+
+                TimeoutAction::ToDuration(HINT_INTERVAL)
+            })
+            .unwrap();
+        self.tutorial_hint_timer = Some(token);
+    }
+
+    /// Restores the layout snapshot saved at the default location on startup, if there is one.
+    ///
+    /// The snapshot is only restored once: the file is renamed after reading, so later startups
+    /// don't restore it again.
+    pub fn restore_saved_layout(&mut self) {
+        let Some(path) = LayoutSnapshot::default_path() else {
+            return;
+        };
+        if !path.exists() {
+            return;
+        }
+
+        match LayoutSnapshot::take(&path) {
+            Ok(snapshot) => {
+                info!("restoring layout snapshot from {path:?}");
+                self.start_layout_restore(LayoutRestore::new(snapshot));
+            }
+            Err(err) => warn!("error restoring layout snapshot: {err}"),
+        }
+    }
+
+    /// Stops waiting for a window to close and drops its kill prompt once it closed.
+    pub fn cancel_pending_close(&mut self, id: u64) {
+        if let Some(pending) = self.pending_closes.remove(id) {
+            self.event_loop.remove(pending.timer);
+        }
+        if self.kill_prompt.remove(id) {
+            self.queue_redraw_all();
+        }
+    }
+
+    /// Disconnects the client of an unresponsive window once confirmed by the user (does nothing if
+    /// the window has already closed).
+    pub fn kill_unresponsive_client(&self, request: &KillRequest) {
+        let window = self
+            .layout
+            .windows()
+            .find(|(_, m)| m.id().get() == request.window_id);
+        if let Some((_, mapped)) = window {
+            kill_client(&self.display_handle, mapped.toplevel().wl_surface());
+        }
+    }
+
+    /// Output that tablets map to with `map-to-output` in the config.
+    pub fn output_for_tablet(&self) -> Option<&Output> {
+        let config = self.config.borrow();
+        let map_to_output = config.input.tablet.map_to_output.as_ref();
+        map_to_output.and_then(|name| self.output_by_name_match(name))
+    }
+
+    pub fn output_by_name_match(&self, target: &str) -> Option<&Output> {
+        self.global_space
+            .outputs()
+            .find(|output| output_matches_name(output, target))
+    }
+
+    /// Current saturation of the output: 0 is grayscale, 1 is full color, animated when toggling
+    /// grayscale.
+    pub fn output_saturation(&self, output: &Output) -> f64 {
+        let Some(state) = self.output_state.get(output) else {
+            return 1.;
+        };
+
+        match &state.saturation_anim {
+            Some(anim) => anim.clamped_value().clamp(0., 1.),
+            None if state.grayscale => 0.,
+            None => 1.,
+        }
+    }
+
+    pub fn output_for_root(&self, root: &WlSurface) -> Option<&Output> {
+        // Check the main layout.
+        let win_out = self.layout.find_window_and_output(root);
+        let layout_output = win_out.map(|(_, output)| output);
+        if let Some(output) = layout_output {
+            return output;
+        }
+
+        // Check layer-shell.
+        let has_layer_surface = |o: &&Output| {
+            layer_map_for_output(o)
+                .layer_for_surface(root, WindowSurfaceType::TOPLEVEL)
+                .is_some()
+        };
+        self.layout.outputs().find(has_layer_surface)
+    }
+
+    /// Schedules an immediate redraw on all outputs if one is not already scheduled.
+    pub fn queue_redraw_all(&mut self) {
+        for state in self.output_state.values_mut() {
+            state.redraw_state = mem::take(&mut state.redraw_state).queue_redraw();
+        }
+    }
+
+    /// Schedules an immediate redraw if one is not already scheduled.
+    pub fn queue_redraw(&mut self, output: &Output) {
+        let state = self.output_state.get_mut(output).unwrap();
+        state.redraw_state = mem::take(&mut state.redraw_state).queue_redraw();
+    }
+
+    pub fn redraw_queued_outputs(&mut self, backend: &mut Backend) {
+        let _span = tracy_client::span!("Niri::redraw_queued_outputs");
+
+        while let Some((output, _)) = self.output_state.iter().find(|(_, state)| {
+            matches!(
+                state.redraw_state,
+                RedrawState::Queued | RedrawState::WaitingForEstimatedVBlankAndQueued(_)
+            )
+        }) {
+            trace!("redrawing output");
+            let output = output.clone();
+            self.redraw(backend, &output);
+        }
+    }
+
+    pub fn pointer_element<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Vec<OutputRenderElements<R>> {
+        if !self.pointer_visibility.is_visible() {
+            return vec![];
+        }
+
+        let _span = tracy_client::span!("Niri::pointer_element");
+        let output_scale = output.current_scale();
+        let output_pos = self.global_space.output_geometry(output).unwrap().loc;
+
+        // Check whether we need to draw the tablet cursor or the regular cursor.
+        let pointer_pos = self
+            .tablet_cursor_location
+            .unwrap_or_else(|| self.seat.get_pointer().unwrap().current_location());
+        let pointer_pos = pointer_pos - output_pos.to_f64();
+
+        // Get the render cursor to draw.
+        let cursor_scale = output_scale.integer_scale();
+        let render_cursor = self.cursor_manager.get_render_cursor(cursor_scale);
+
+        let output_scale = Scale::from(output.current_scale().fractional_scale());
+
+        let mut pointer_elements = match render_cursor {
+            RenderCursor::Hidden => vec![],
+            RenderCursor::Surface { surface, hotspot } => {
+                let pointer_pos =
+                    (pointer_pos - hotspot.to_f64()).to_physical_precise_round(output_scale);
+
+                render_elements_from_surface_tree(
+                    renderer,
+                    &surface,
+                    pointer_pos,
+                    output_scale,
+                    1.,
+                    Kind::Cursor,
+                )
+            }
+            RenderCursor::Named {
+                icon,
+                scale,
+                cursor,
+            } => {
+                let (idx, frame) = cursor.frame(self.start_time.elapsed().as_millis() as u32);
+                let hotspot = XCursor::hotspot(frame).to_logical(scale);
+                let pointer_pos =
+                    (pointer_pos - hotspot.to_f64()).to_physical_precise_round(output_scale);
+
+                let texture = self.cursor_texture_cache.get(icon, scale, &cursor, idx);
+                let mut pointer_elements = vec![];
                 let pointer_element = match MemoryRenderBufferRenderElement::from_buffer(
                     renderer,
                     pointer_pos,
@@ -2799,6 +4282,125 @@ impl Niri {
         pointer_elements
     }
 
+    /// Renders the cursors of extra seats (always the default cursor, never on a hardware plane).
+    pub fn extra_seat_pointer_elements<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Vec<OutputRenderElements<R>> {
+        if self.extra_seats.is_empty() {
+            return vec![];
+        }
+
+        let output_geo = self.global_space.output_geometry(output).unwrap();
+        let cursor_scale = output.current_scale().integer_scale();
+        let output_scale = Scale::from(output.current_scale().fractional_scale());
+
+        let cursor = self.cursor_manager.get_default_cursor(cursor_scale);
+        let (idx, frame) = cursor.frame(self.start_time.elapsed().as_millis() as u32);
+        let hotspot = XCursor::hotspot(frame).to_logical(cursor_scale);
+        let texture =
+            self.cursor_texture_cache
+                .get(CursorIcon::Default, cursor_scale, &cursor, idx);
+
+        let mut elements = vec![];
+        for seat in self.extra_seats.iter() {
+            let pointer_pos = seat.pointer_location();
+            if !output_geo.to_f64().contains(pointer_pos) {
+                continue;
+            }
+
+            let pointer_pos = pointer_pos - output_geo.loc.to_f64() - hotspot.to_f64();
+            let pointer_pos = pointer_pos.to_physical_precise_round(output_scale);
+            match MemoryRenderBufferRenderElement::from_buffer(
+                renderer,
+                pointer_pos,
+                &texture,
+                None,
+                None,
+                None,
+                Kind::Unspecified,
+            ) {
+                Ok(element) => elements.push(OutputRenderElements::NamedPointer(element)),
+                Err(err) => warn!("error importing a cursor texture: {err:?}"),
+            }
+        }
+
+        elements
+    }
+
+    /// Renders the focus rings around the focused windows of extra seats.
+    pub fn extra_seat_focus_ring_elements<R: NiriRenderer>(
+        &self,
+        output: &Output,
+    ) -> Vec<OutputRenderElements<R>> {
+        let Some(mon) = self.layout.monitor_for_output(output) else {
+            return vec![];
+        };
+
+        let mut elements = vec![];
+        for seat in self.extra_seats.iter() {
+            let Some(window) = seat.focus() else {
+                continue;
+            };
+            let Some(rect) = mon.tile_render_rect(window) else {
+                continue;
+            };
+            elements.extend(seat.render_focus_ring(rect).into_iter().map(Into::into));
+        }
+
+        elements
+    }
+
+    /// Checks whether the cursor can be shown on the hardware cursor plane of this output.
+    ///
+    /// The cursor plane shows the buffer as is, without scaling or transforming it, so any cursor
+    /// that needs either must be composited instead, otherwise it shows up at the wrong size.
+    pub fn cursor_plane_fallback(
+        &self,
+        output: &Output,
+        plane_size: Size<u32, Buffer>,
+    ) -> Option<CursorPlaneFallback> {
+        let output_scale = output.current_scale();
+        if output_scale.fractional_scale().fract() != 0. {
+            return Some(CursorPlaneFallback::FractionalScale);
+        }
+
+        let scale = output_scale.integer_scale();
+        let size = match self.cursor_manager.get_render_cursor(scale) {
+            RenderCursor::Hidden => return None,
+            RenderCursor::Surface { surface, .. } => {
+                let (transform, buffer_scale) = with_states(&surface, |states| {
+                    let mut guard = states.cached_state.get::<SurfaceAttributes>();
+                    let attrs = guard.current();
+                    (attrs.buffer_transform, attrs.buffer_scale)
+                });
+                if Transform::from(transform) != Transform::Normal {
+                    return Some(CursorPlaneFallback::SurfaceTransform);
+                }
+                if buffer_scale != scale {
+                    return Some(CursorPlaneFallback::SurfaceScale);
+                }
+
+                bbox_from_surface_tree(&surface, (0, 0))
+                    .size
+                    .to_physical(scale)
+            }
+            RenderCursor::Named { cursor, .. } => {
+                let frames = cursor.frames();
+                let w = frames.iter().map(|frame| frame.width).max().unwrap_or(0);
+                let h = frames.iter().map(|frame| frame.height).max().unwrap_or(0);
+                Size::from((w as i32, h as i32))
+            }
+        };
+
+        if size.w > plane_size.w as i32 || size.h > plane_size.h as i32 {
+            return Some(CursorPlaneFallback::Oversized);
+        }
+
+        None
+    }
+
     pub fn refresh_pointer_outputs(&mut self) {
         if !self.pointer_visibility.is_visible() {
             return;
@@ -2807,7 +4409,9 @@ impl Niri {
         let _span = tracy_client::span!("Niri::refresh_pointer_outputs");
 
         // Check whether we need to draw the tablet cursor or the regular cursor.
-        let pointer_pos = self.seat.get_pointer().unwrap().current_location();
+        let pointer_pos = self
+            .tablet_cursor_location
+            .unwrap_or_else(|| self.seat.get_pointer().unwrap().current_location());
 
         match self.cursor_manager.cursor_image() {
             CursorImageStatus::Surface(ref surface) => {
@@ -2963,91 +4567,554 @@ impl Niri {
     pub fn refresh_idle_inhibit(&mut self) {
         let _span = tracy_client::span!("Niri::refresh_idle_inhibit");
 
-        self.idle_inhibiting_surfaces.retain(|s| s.is_alive());
+        self.idle_inhibiting_surfaces.retain(|s| s.is_alive());
+
+        let is_inhibited = self.is_idle_inhibited();
+        self.idle_notifier_state.set_is_inhibited(is_inhibited);
+    }
+
+    /// Whether a client (a visible idle-inhibit surface or D-Bus ScreenSaver) inhibits going idle.
+    pub fn is_idle_inhibited(&self) -> bool {
+        self.is_fdo_idle_inhibited.load(Ordering::SeqCst)
+            || self.idle_inhibiting_surfaces.iter().any(|surface| {
+                with_states(surface, |states| {
+                    surface_primary_scanout_output(surface, states).is_some()
+                })
+            })
+    }
+
+    /// Restarts the idle fade timer with the configured timeout.
+    pub fn reschedule_idle_fade(&mut self) {
+        if let Some(token) = self.idle_fade_timer.take() {
+            self.event_loop.remove(token);
+        }
+
+        let Some(timeout_ms) = self.config.borrow().idle_fade.timeout_ms else {
+            return;
+        };
+        let timeout = Duration::from_millis(u64::from(timeout_ms));
+        let elapsed = self.last_activity.lock().unwrap().elapsed();
+
+        let token = self
+            .event_loop
+            .insert_source(
+                Timer::from_duration(timeout.saturating_sub(elapsed)),
+                move |_, _, state| {
+                    // When inhibited (e.g. playing a fullscreen video), check again later.
+                    if state.niri.is_idle_inhibited() {
+                        return TimeoutAction::ToDuration(timeout);
+                    }
+
+                    // There may have been activity while waiting.
+                    let elapsed = state.niri.last_activity.lock().unwrap().elapsed();
+                    if elapsed < timeout {
+                        return TimeoutAction::ToDuration(timeout - elapsed);
+                    }
+
+                    state.niri.idle_fade_timer = None;
+                    let duration_ms = state.niri.config.borrow().idle_fade.fade_duration_ms;
+                    state.niri.idle_fade.start(duration_ms);
+                    state.niri.queue_redraw_all();
+                    TimeoutAction::Drop
+                },
+            )
+            .unwrap();
+        self.idle_fade_timer = Some(token);
+    }
+
+    /// Stops the dwell click countdown without clicking.
+    pub fn stop_dwell_click(&mut self) {
+        if let Some(timer) = self.dwell_click.replace_timer(None) {
+            self.event_loop.remove(timer);
+        }
+        if self.dwell_click.finish() {
+            self.queue_redraw_all();
+        }
+    }
+
+    /// Runs the configured lock command; returns `false` if no command is set.
+    ///
+    /// Doesn't start a second one while the lock command is already running.
+    pub fn spawn_lock_command(&mut self) -> bool {
+        let command = self.config.borrow().logind.lock_command.clone();
+        if command.is_empty() {
+            return false;
+        }
+
+        let running = self.lock_command.as_ref().is_some_and(|c| c.is_running());
+        if running {
+            debug!("lock command is already running");
+            return true;
+        }
+
+        let (token, _) = self.activation_state.create_external_token(None);
+        self.lock_command = Some(TerminableChild::spawn(command, Some(token.clone())));
+        true
+    }
+
+    /// Kills the lock command started by niri; returns `false` if none is running.
+    pub fn stop_lock_command(&mut self) -> bool {
+        self.lock_command
+            .take()
+            .is_some_and(|child| child.terminate())
+    }
+
+    /// Clears the search when the overview closes; keeps up with windows opening, closing and
+    /// changing titles while searching.
+    pub fn refresh_overview_search(&mut self) {
+        let closed = !self.layout.is_overview_open() && self.overview_search.clear();
+        if closed || self.overview_search.is_active() {
+            self.update_overview_search();
+        }
+    }
+
+    /// Filters windows by the overview search query, dimming the ones that don't match; returns the
+    /// best match.
+    pub fn update_overview_search(&mut self) -> Option<Window> {
+        let _span = tracy_client::span!("Niri::update_overview_search");
+
+        let query = self.overview_search.query();
+        let mut best: Option<(i32, Window, u64)> = None;
+        let mut match_count = 0;
+        let mut changed = false;
+        self.layout.with_windows_mut(|mapped, _output| {
+            let score = if query.is_empty() {
+                Some(0)
+            } else {
+                // Take the higher of the title and app ID scores.
+                let (title, app_id) = with_toplevel_role(mapped.toplevel(), |role| {
+                    (role.title.clone(), role.app_id.clone())
+                });
+                [title, app_id]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|text| fuzzy_score(query, &text))
+                    .max()
+            };
+
+            changed |= mapped.set_filtered_out(score.is_none());
+
+            if let Some(score) = score {
+                match_count += 1;
+                // On ties, keep the window that came first.
+                if best.as_ref().map_or(true, |(best, _, _)| score > *best) {
+                    best = Some((score, mapped.window.clone(), mapped.id().get()));
+                }
+            }
+        });
+
+        // The thumbnail under the search box changes with the best match.
+        let best_id = best.as_ref().map(|(_, _, id)| *id);
+        changed |= self.overview_search.best_match() != best_id.filter(|_| !query.is_empty());
+        self.overview_search.set_matches(match_count, best_id);
+
+        if changed {
+            self.queue_redraw_all();
+        }
+
+        best.map(|(_, window, _)| window)
+    }
+
+    pub fn refresh_window_states(&mut self) {
+        let _span = tracy_client::span!("Niri::refresh_window_states");
+
+        let config = self.config.borrow();
+        self.layout.with_windows_mut(|mapped, _output| {
+            mapped.update_tiled_state(config.prefer_no_csd);
+        });
+        drop(config);
+    }
+
+    pub fn refresh_window_rules(&mut self) {
+        let _span = tracy_client::span!("Niri::refresh_window_rules");
+
+        let config = self.config.borrow();
+        let window_rules = &config.window_rules;
+
+        let mut windows = vec![];
+        let mut outputs = HashSet::new();
+        self.layout.with_windows_mut(|mapped, output| {
+            if mapped.recompute_window_rules_if_needed(window_rules) {
+                windows.push(mapped.window.clone());
+
+                if let Some(output) = output {
+                    outputs.insert(output.clone());
+                }
+
+                // Since refresh_window_rules() is called after refresh_layout(), we need to update
+                // the tiled state right here, so that it's picked up by the following
+                // send_pending_configure().
+                mapped.update_tiled_state(config.prefer_no_csd);
+            }
+        });
+        drop(config);
+
+        for win in windows {
+            self.layout.update_window(&win, None);
+            win.toplevel()
+                .expect("no X11 support")
+                .send_pending_configure();
+        }
+        for output in outputs {
+            self.queue_redraw(&output);
+        }
+    }
+
+    pub fn update_render_elements(&mut self, output: Option<&Output>) {
+        self.layout.update_render_elements(output);
+        self.wallpapers.advance_animations();
+
+        if self.idle_fade.advance_animations() {
+            let lock = self.config.borrow().idle_fade.lock;
+            if lock && !self.spawn_lock_command() {
+                warn!("idle-fade asked to lock the session, but logind.lock-command is not set");
+            }
+        }
+
+        for (out, state) in self.output_state.iter_mut() {
+            if output.map_or(true, |output| out == output) {
+                let scale = Scale::from(out.current_scale().fractional_scale());
+                let transform = out.current_transform();
+
+                if state.saturation_anim.as_ref().is_some_and(|a| a.is_done()) {
+                    state.saturation_anim = None;
+                }
+
+                if let Some(transition) = &mut state.screen_transition {
+                    transition.advance_animations();
+                    if transition.is_done() {
+                        state.screen_transition = None;
+                    } else {
+                        transition.update_render_elements(scale, transform);
+                    }
+                }
+
+                let layer_map = layer_map_for_output(out);
+                for surface in layer_map.layers() {
+                    let Some(mapped) = self.mapped_layer_surfaces.get_mut(surface) else {
+                        continue;
+                    };
+                    let Some(geo) = layer_map.layer_geometry(surface) else {
+                        continue;
+                    };
+
+                    // A layer surface with keyboard focus (like a launcher) can draw a focus ring.
+                    let is_focused = matches!(
+                        &self.keyboard_focus,
+                        KeyboardFocus::LayerShell { surface: focused }
+                            if focused == surface.wl_surface()
+                    );
+                    mapped.update_render_elements(geo.to_f64(), is_focused);
+                }
+            }
+        }
+    }
+
+    pub fn update_shaders(&mut self) {
+        self.layout.update_shaders();
+
+        for mapped in self.mapped_layer_surfaces.values_mut() {
+            mapped.update_shaders();
+        }
+    }
+
+    /// After a GPU reset, the renderer has a new context and textures from the old one are
+    /// unusable.
+    ///
+    /// Drops all cached textures so they get re-rendered on next use, and fully redraws all
+    /// outputs. The backend recompiles the shaders on the new renderer.
+    pub fn on_renderer_reset(&mut self) {
+        texture_cache::invalidate_all();
+        self.cursor_texture_cache.clear();
+        self.wallpapers.clear_textures();
+        self.dwell_click.clear_buffers();
+        self.thumbnails.clear();
+
+        // Snapshots of the transition, closing and resizing animations come from the old context,
+        // end them right away.
+        for state in self.output_state.values_mut() {
+            state.screen_transition = None;
+        }
+        self.layout.drop_render_snapshots();
+
+        self.queue_redraw_all();
+    }
+
+    /// Returns the window thumbnail, re-rendering it if needed.
+    pub fn window_thumbnail(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        id: u64,
+    ) -> Option<&Thumbnail<TextureBuffer<GlesTexture>>> {
+        let mapped = self
+            .layout
+            .windows()
+            .map(|(_, mapped)| mapped)
+            .find(|mapped| mapped.id().get() == id);
+        let Some(mapped) = mapped else {
+            self.thumbnails.remove(id);
+            return None;
+        };
+
+        let quality = self.config.borrow().overview.thumbnail_quality;
+        let now = get_monotonic_time();
+        self.thumbnails
+            .get(id, now, || render_thumbnail(renderer, mapped, quality))
+    }
+
+    /// Resolves the wallpaper shown on an output for a workspace.
+    ///
+    /// Priority: wallpaper set via IPC > workspace config > output config.
+    pub fn wallpaper_for(&self, output: &Output, ws_name: Option<&String>) -> Option<Wallpaper> {
+        if let Some(wallpaper) = self.wallpapers.override_for(&output.name()) {
+            return Some(wallpaper.clone());
+        }
+
+        let config = self.config.borrow();
+        let ws_wallpaper = ws_name.and_then(|name| {
+            config
+                .workspaces
+                .iter()
+                .find(|ws| ws.name.0.eq_ignore_ascii_case(name))
+                .and_then(|ws| ws.wallpaper.clone())
+        });
+        ws_wallpaper.or_else(|| {
+            let name = output.user_data().get::<OutputName>().unwrap();
+            config.outputs.find(name)?.wallpaper.clone()
+        })
+    }
+
+    /// Sets the wallpaper of an output via IPC, with an optional fade-in transition.
+    pub fn set_wallpaper(
+        &mut self,
+        output: &Output,
+        wallpaper: Wallpaper,
+        crossfade_ms: Option<u16>,
+    ) {
+        let mon = self.layout.monitor_for_output(output);
+        let ws_name = mon.and_then(|mon| mon.active_workspace_ref().name().cloned());
+        let from = self.wallpaper_for(output, ws_name.as_ref());
+
+        self.wallpapers
+            .set_override(output.name(), wallpaper, from.as_ref(), crossfade_ms);
+        self.queue_redraw(output);
+    }
+
+    pub fn do_screen_transition(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        delay_ms: Option<u16>,
+        duration_ms: Option<u16>,
+        style: ScreenTransitionStyle,
+    ) {
+        let _span = tracy_client::span!("Niri::do_screen_transition");
+
+        self.update_render_elements(None);
+
+        let textures: Vec<_> = self
+            .global_space
+            .outputs()
+            .cloned()
+            .filter_map(|output| {
+                let size = output.current_mode().unwrap().size;
+                let transform = output.current_transform();
+                let size = transform.transform_size(size);
+
+                let scale = Scale::from(output.current_scale().fractional_scale());
+                let targets = [
+                    RenderTarget::Output,
+                    RenderTarget::Screencast,
+                    RenderTarget::ScreenCapture,
+                ];
+                let textures = targets.map(|target| {
+                    let elements = self.render::<GlesRenderer>(renderer, &output, false, target);
+                    let elements = elements.iter().rev();
+
+                    let res = render_to_texture(
+                        renderer,
+                        size,
+                        scale,
+                        Transform::Normal,
+                        Fourcc::Abgr8888,
+                        elements,
+                    );
+
+                    if let Err(err) = &res {
+                        warn!("error rendering output {}: {err:?}", output.name());
+                    }
+
+                    res
+                });
+
+                if textures.iter().any(|res| res.is_err()) {
+                    return None;
+                }
+
+                let textures = textures.map(|res| {
+                    let texture = res.unwrap().0;
+                    TextureBuffer::from_texture(
+                        renderer,
+                        texture,
+                        scale,
+                        transform,
+                        Vec::new(), // We want windows below to get frame callbacks.
+                    )
+                });
+
+                Some((output, textures))
+            })
+            .collect();
+
+        let delay = delay_ms.map_or(screen_transition::DEFAULT_DELAY, |d| {
+            Duration::from_millis(u64::from(d))
+        });
+        let duration = duration_ms.map_or(screen_transition::DEFAULT_DURATION, |d| {
+            Duration::from_millis(u64::from(d))
+        });
+
+        for (output, from_texture) in textures {
+            let state = self.output_state.get_mut(&output).unwrap();
+            state.screen_transition = Some(ScreenTransition::new(
+                from_texture,
+                delay,
+                duration,
+                style,
+                self.clock.clone(),
+            ));
+        }
+
+        // Keep redrawing so that the transition runs even if nothing else changes on screen.
+        self.queue_redraw_all();
+    }
+
+    /// Renders a UI overlay, catching any panic in it.
+    ///
+    /// A crashed overlay is no longer rendered, the other overlays are unaffected.
+    fn render_ui_overlay<I: IntoIterator<Item = PrimaryGpuTextureRenderElement>>(
+        &self,
+        overlay: UiOverlay,
+        elements: &mut Vec<PrimaryGpuTextureRenderElement>,
+        render: impl FnOnce() -> I,
+    ) {
+        if self.crashed_ui_overlays.contains(overlay) {
+            return;
+        }
+
+        match crash::catch(&format!("UI rendering ({overlay:?})"), render) {
+            Ok(element) => elements.extend(element),
+            Err(report) => {
+                self.crashed_ui_overlays.mark(overlay);
+                self.crash_report.show(report);
+                // Rendering only has `&self`, so closing the overlay and redrawing other outputs
+                // happen in an idle callback.
+                self.event_loop.insert_idle(move |state| {
+                    state.niri.close_crashed_ui_overlay(overlay);
+                    state.niri.queue_redraw_all();
+                });
+            }
+        }
+    }
+
+    /// Closes a crashed modal overlay so that it stops intercepting input.
+    fn close_crashed_ui_overlay(&mut self, overlay: UiOverlay) {
+        if !overlay.is_modal() {
+            return;
+        }
 
-        let is_inhibited = self.is_fdo_idle_inhibited.load(Ordering::SeqCst)
-            || self.idle_inhibiting_surfaces.iter().any(|surface| {
-                with_states(surface, |states| {
-                    surface_primary_scanout_output(surface, states).is_some()
-                })
-            });
-        self.idle_notifier_state.set_is_inhibited(is_inhibited);
+        match overlay {
+            UiOverlay::VirtualInputPrompt => self.virtual_input_prompt.clear(),
+            UiOverlay::KillPrompt => self.kill_prompt.clear(),
+            UiOverlay::ConfigErrorNotification => {
+                self.config_error_notification.hide();
+            }
+            UiOverlay::AdjustMode => {
+                self.adjust_mode.close();
+            }
+            UiOverlay::Tutorial => {
+                self.tutorial.close();
+            }
+            _ => (),
+        }
     }
 
-    pub fn refresh_window_states(&mut self) {
-        let _span = tracy_client::span!("Niri::refresh_window_states");
+    /// Whether the overlay is open and accepts input (a crashed overlay counts as closed).
+    pub fn ui_overlay_is_open(&self, overlay: UiOverlay) -> bool {
+        if self.crashed_ui_overlays.contains(overlay) {
+            return false;
+        }
 
-        let config = self.config.borrow();
-        self.layout.with_windows_mut(|mapped, _output| {
-            mapped.update_tiled_state(config.prefer_no_csd);
-        });
-        drop(config);
+        match overlay {
+            UiOverlay::VirtualInputPrompt => self.virtual_input_prompt.is_open(),
+            UiOverlay::KillPrompt => self.kill_prompt.is_open(),
+            UiOverlay::ConfigErrorNotification => self.config_error_notification.is_open(),
+            UiOverlay::AdjustMode => self.adjust_mode.is_open(),
+            UiOverlay::Tutorial => self.tutorial.is_open(),
+            UiOverlay::BindConflicts
+            | UiOverlay::OverviewSearch
+            | UiOverlay::LevelOsd
+            | UiOverlay::LaunchPlaceholders => false,
+        }
     }
 
-    pub fn refresh_window_rules(&mut self) {
-        let _span = tracy_client::span!("Niri::refresh_window_rules");
-
-        let config = self.config.borrow();
-        let window_rules = &config.window_rules;
+    fn render_ui_overlays<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Vec<PrimaryGpuTextureRenderElement> {
+        let mut elements = Vec::new();
 
-        let mut windows = vec![];
-        let mut outputs = HashSet::new();
-        self.layout.with_windows_mut(|mapped, output| {
-            if mapped.recompute_window_rules_if_needed(window_rules, self.is_at_startup) {
-                windows.push(mapped.window.clone());
+        // The virtual input prompt, which must not be obscured.
+        self.render_ui_overlay(UiOverlay::VirtualInputPrompt, &mut elements, || {
+            self.virtual_input_prompt.render_output(renderer, output)
+        });
 
-                if let Some(output) = output {
-                    outputs.insert(output.clone());
-                }
+        // Then, the prompt to kill an unresponsive client.
+        self.render_ui_overlay(UiOverlay::KillPrompt, &mut elements, || {
+            self.kill_prompt.render_output(renderer, output)
+        });
 
-                // Since refresh_window_rules() is called after refresh_layout(), we need to update
-                // the tiled state right here, so that it's picked up by the following
-                // send_pending_configure().
-                mapped.update_tiled_state(config.prefer_no_csd);
-            }
+        // Then, the config error notification.
+        self.render_ui_overlay(UiOverlay::ConfigErrorNotification, &mut elements, || {
+            self.config_error_notification
+                .render_output(renderer, output)
         });
-        drop(config);
 
-        for win in windows {
-            self.layout.update_window(&win, None);
-            win.toplevel()
-                .expect("no X11 support")
-                .send_pending_configure();
-        }
-        for output in outputs {
-            self.queue_redraw(&output);
-        }
-    }
+        // Then, the bind conflicts overlay.
+        self.render_ui_overlay(UiOverlay::BindConflicts, &mut elements, || {
+            self.bind_conflicts_overlay.render_output(renderer, output)
+        });
 
-    pub fn update_render_elements(&mut self, output: Option<&Output>) {
-        self.layout.update_render_elements(output);
+        // Then, the adjust mode OSD.
+        self.render_ui_overlay(UiOverlay::AdjustMode, &mut elements, || {
+            self.adjust_mode
+                .render_output(renderer, output, &self.layout)
+        });
 
-        for (out, _state) in self.output_state.iter_mut() {
-            if output.map_or(true, |output| out == output) {
-                let _scale = Scale::from(out.current_scale().fractional_scale());
-                let _transform = out.current_transform();
+        // Then, the overview search box.
+        self.render_ui_overlay(UiOverlay::OverviewSearch, &mut elements, || {
+            let preview = self.overview_search.best_match();
+            let preview = preview.and_then(|id| self.thumbnails.peek(id));
+            self.overview_search
+                .render_output(renderer, output, &self.layout, preview)
+        });
 
-                let layer_map = layer_map_for_output(out);
-                for surface in layer_map.layers() {
-                    let Some(mapped) = self.mapped_layer_surfaces.get_mut(surface) else {
-                        continue;
-                    };
-                    let Some(geo) = layer_map.layer_geometry(surface) else {
-                        continue;
-                    };
+        // Then, the volume and brightness OSD.
+        self.render_ui_overlay(UiOverlay::LevelOsd, &mut elements, || {
+            self.level_osd.render_output(renderer, output, &self.layout)
+        });
 
-                    mapped.update_render_elements(geo.size.to_f64());
-                }
-            }
-        }
-    }
+        // Then, the tutorial.
+        self.render_ui_overlay(UiOverlay::Tutorial, &mut elements, || {
+            self.tutorial.render_output(renderer, output, &self.layout)
+        });
 
-    pub fn update_shaders(&mut self) {
-        self.layout.update_shaders();
+        // Then, the placeholder for a launching app.
+        self.render_ui_overlay(UiOverlay::LaunchPlaceholders, &mut elements, || {
+            self.launch_placeholders
+                .render_output(renderer, output, &self.layout)
+        });
 
-        for mapped in self.mapped_layer_surfaces.values_mut() {
-            mapped.update_shaders();
-        }
+        elements
     }
 
     pub fn render<R: NiriRenderer>(
@@ -3070,17 +5137,57 @@ impl Niri {
 
         let output_scale = Scale::from(output.current_scale().fractional_scale());
 
-        // The pointer goes on the top.
+        // The idle fade covers everything, including the pointer.
         let mut elements = vec![];
+        if target == RenderTarget::Output {
+            if let Some(fade) = self.idle_fade.render_output(output) {
+                elements.push(fade.into());
+            }
+        }
+
+        // Then the pointer.
         if include_pointer {
-            elements = self.pointer_element(renderer, output);
+            elements.extend(self.pointer_element(renderer, output));
+            elements.extend(self.extra_seat_pointer_elements(renderer, output));
+
+            // The dwell-click countdown goes right below the pointer.
+            if self.pointer_visibility.is_visible() {
+                let output_pos = self.global_space.output_geometry(output).unwrap().loc;
+                let pointer_pos = self.seat.get_pointer().unwrap().current_location();
+                let pointer_pos = pointer_pos - output_pos.to_f64();
+                let scale = output.current_scale().fractional_scale();
+                if let Some(ring) = self.dwell_click.render(renderer, pointer_pos, scale) {
+                    elements.push(ring.into());
+                }
+            }
+        }
+
+        // The hot corner hint goes below the pointer, above everything else.
+        if let Some(hint) = self.hot_areas.render(output) {
+            elements.push(hint.into());
+        }
+
+        // Then, the UI overlays. A panic while rendering one of them is reported instead of
+        // taking down the whole compositor, and only that overlay stays off afterwards.
+        let ui_elements = self.render_ui_overlays(renderer, output);
+
+        // The crash report goes on top of the other overlays.
+        if let Some(element) = self.crash_report.render_output(renderer, output) {
+            elements.push(element.into());
         }
+        elements.extend(ui_elements.into_iter().map(Into::into));
 
         // Next, the screen transition texture.
         {
-            let _state = self.output_state.get(output).unwrap();
+            let state = self.output_state.get(output).unwrap();
+            if let Some(transition) = &state.screen_transition {
+                elements.extend(transition.render(target).map(Into::into));
+            }
         }
 
+        // The focus rings of extra seats go above the windows they surround.
+        elements.extend(self.extra_seat_focus_ring_elements(output));
+
         // Prepare the background elements.
         let state = self.output_state.get(output).unwrap();
         let background_buffer = state.background_buffer.clone();
@@ -3105,6 +5212,9 @@ impl Niri {
         // Get monitor elements.
         let mon = self.layout.monitor_for_output(output).unwrap();
         let zoom = mon.overview_zoom();
+
+        let output_name = output.name();
+        let output_size = output_size(output);
         let monitor_elements = Vec::from_iter(
             mon.render_elements(renderer, target, focus_ring)
                 .map(|(geo, iter)| (geo, Vec::from_iter(iter))),
@@ -3120,7 +5230,15 @@ impl Niri {
         let layer_map = layer_map_for_output(output);
         let mut extend_from_layer =
             |elements: &mut SplitElements<LayerSurfaceRenderElement<R>>, layer, for_backdrop| {
-                self.render_layer(renderer, target, &layer_map, layer, elements, for_backdrop);
+                self.render_layer(
+                    renderer,
+                    target,
+                    output,
+                    &layer_map,
+                    layer,
+                    elements,
+                    for_backdrop,
+                );
             };
 
         // The overlay layer elements go next.
@@ -3161,6 +5279,16 @@ impl Niri {
             elements.extend(top_layer.into_iter().map(OutputRenderElements::from));
             elements.extend(layer_elems.into_iter().map(OutputRenderElements::from));
 
+            let active_ws_name = mon.active_workspace_ref().name();
+            let wallpaper = self.wallpaper_for(output, active_ws_name);
+            let wallpaper_elements = self.wallpapers.render(
+                &output_name,
+                wallpaper.as_ref(),
+                output_size,
+                output_scale.x,
+            );
+            elements.extend(wallpaper_elements.map(OutputRenderElements::from));
+
             elements.push(OutputRenderElements::from(background));
 
             elements.extend(
@@ -3183,7 +5311,57 @@ impl Niri {
                     .map(OutputRenderElements::from),
             );
 
-            for (ws_geo, ws_elements) in monitor_elements {
+            // Outside the overview, different workspace wallpapers crossfade during a workspace
+            // switch, rather than slide along with their workspaces.
+            let render_idx = mon.workspace_render_idx();
+            let switch_position = wallpaper::switch_position(render_idx).filter(|_| zoom == 1.);
+            let switch_wallpapers = if let Some((above_idx, progress)) = switch_position {
+                let mut above = None;
+                let mut below = None;
+                for ((idx, ws), _) in mon.workspaces_with_render_geo_idx() {
+                    if idx == above_idx {
+                        above = Some(self.wallpaper_for(output, ws.name()));
+                    } else if idx == above_idx + 1 {
+                        below = Some(self.wallpaper_for(output, ws.name()));
+                    }
+                }
+                above
+                    .zip(below)
+                    .filter(|(above, below)| above != below)
+                    .map(|(above, below)| (above, below, progress))
+            } else {
+                None
+            };
+
+            // Get the wallpaper elements for every workspace, in the same order as the monitor
+            // elements.
+            let wallpaper_elements = mon.workspaces_with_render_geo().map(|(ws, geo)| {
+                if let Some((above, below, progress)) = &switch_wallpapers {
+                    // Keep the wallpaper in place on the output while the workspace moves.
+                    let offset = geo.loc.to_physical_precise_round(output_scale);
+                    let offset = offset.to_logical(output_scale).upscale(-1.);
+                    return Vec::from_iter(self.wallpapers.render_switch(
+                        above.as_ref(),
+                        below.as_ref(),
+                        *progress,
+                        offset,
+                        output_size,
+                        output_scale.x,
+                    ));
+                }
+
+                let wallpaper = self.wallpaper_for(output, ws.name());
+                Vec::from_iter(self.wallpapers.render(
+                    &output_name,
+                    wallpaper.as_ref(),
+                    output_size,
+                    output_scale.x,
+                ))
+            });
+
+            for ((ws_geo, ws_elements), wallpaper_elements) in
+                monitor_elements.into_iter().zip(wallpaper_elements)
+            {
                 // Collect all other layer-shell elements.
                 let mut layer_elems = SplitElements::default();
                 extend_from_layer(&mut layer_elems, Layer::Bottom, false);
@@ -3207,6 +5385,13 @@ impl Niri {
                         .map(OutputRenderElements::from),
                 );
 
+                elements.extend(
+                    wallpaper_elements
+                        .into_iter()
+                        .filter_map(|elem| scale_relocate_crop(elem, output_scale, zoom, ws_geo))
+                        .map(OutputRenderElements::from),
+                );
+
                 if let Some(elem) =
                     scale_relocate_crop(background.clone(), output_scale, zoom, ws_geo)
                 {
@@ -3240,11 +5425,14 @@ impl Niri {
         &self,
         renderer: &mut R,
         target: RenderTarget,
+        output: &Output,
         layer_map: &LayerMap,
         layer: Layer,
         elements: &mut SplitElements<LayerSurfaceRenderElement<R>>,
         for_backdrop: bool,
     ) {
+        let mon = self.layout.monitor_for_output(output);
+
         // LayerMap returns layers in reverse stacking order.
         let iter = layer_map.layers_on(layer).rev().filter_map(|surface| {
             let mapped = self.mapped_layer_surfaces.get(surface)?;
@@ -3253,11 +5441,23 @@ impl Niri {
                 return None;
             }
 
+            // Backdrop surfaces of a workspace crossfade with the workspace switch progress, so
+            // dragging in the overview blends between the backdrops of neighboring workspaces.
+            let fade = match &mapped.rules().backdrop_workspace {
+                Some(name) if for_backdrop => {
+                    mon.map_or(0., |mon| mon.workspace_visibility(name)) as f32
+                }
+                _ => 1.,
+            };
+            if fade == 0. {
+                return None;
+            }
+
             let geo = layer_map.layer_geometry(surface)?;
-            Some((mapped, geo))
+            Some((mapped, geo, fade))
         });
-        for (mapped, geo) in iter {
-            elements.extend(mapped.render(renderer, geo.loc.to_f64(), target));
+        for (mapped, geo, fade) in iter {
+            elements.extend(mapped.render(renderer, geo.loc.to_f64(), fade, target));
         }
     }
 
@@ -3279,10 +5479,33 @@ impl Niri {
         self.update_render_elements(Some(output));
 
         let mut res = RenderResult::Skipped;
-        if self.monitors_active {
+        if self.monitors_active && !self.output_state[output].powered_off {
             let state = self.output_state.get_mut(output).unwrap();
             state.unfinished_animations_remain = self.layout.are_animations_ongoing(Some(output));
 
+            // Also keep redrawing during a screen transition.
+            state.unfinished_animations_remain |= state.screen_transition.is_some();
+
+            // Also keep redrawing while the output fades to or from grayscale.
+            state.unfinished_animations_remain |= state.saturation_anim.is_some();
+
+            // Also keep redrawing during a wallpaper crossfade.
+            state.unfinished_animations_remain |=
+                self.wallpapers.are_animations_ongoing(&output.name());
+
+            // Also keep redrawing while a launch placeholder spinner is on screen.
+            state.unfinished_animations_remain |= self.launch_placeholders.are_animations_ongoing();
+
+            // Also keep redrawing while the outputs fade to black.
+            state.unfinished_animations_remain |= self.idle_fade.is_animation_ongoing();
+
+            // Also keep redrawing during the dwell-click countdown.
+            state.unfinished_animations_remain |= self.dwell_click.is_counting();
+
+            // Also keep redrawing while the debug damage heatmap cools down.
+            state.unfinished_animations_remain |=
+                self.debug_damage_heatmap && !state.debug_damage_heatmap.is_cold();
+
             // Also keep redrawing if the current cursor is animated.
             state.unfinished_animations_remain |= self
                 .cursor_manager
@@ -3463,11 +5686,29 @@ impl Niri {
         }
 
         for surface in layer_map_for_output(output).layers() {
+            let mapped = self.mapped_layer_surfaces.get(surface);
+            let offscreen_data = mapped.map(|mapped| mapped.offscreen_data());
+            let offscreen_data = offscreen_data.as_deref().and_then(Option::as_ref);
+
             surface.with_surfaces(|surface, states| {
-                update_surface_primary_scanout_output(
-                    surface,
+                let primary_scanout_output = states
+                    .data_map
+                    .get_or_insert_threadsafe(Mutex::<PrimaryScanoutOutput>::default);
+                let mut primary_scanout_output = primary_scanout_output.lock().unwrap();
+
+                let mut id = Id::from_wayland_resource(surface);
+
+                // Layer surfaces fading between workspaces are drawn through an offscreen, same
+                // as windows above.
+                if let Some(data) = offscreen_data {
+                    if data.states.element_was_presented(id.clone()) {
+                        id = data.id.clone();
+                    }
+                }
+
+                primary_scanout_output.update_from_render_element_states(
+                    id,
                     output,
-                    states,
                     render_element_states,
                     // Layer surfaces are shown only on one output at a time.
                     |_, _, output, _| output,
@@ -3556,6 +5797,7 @@ impl Niri {
 
         let state = self.output_state.get(output).unwrap();
         let sequence = state.frame_callback_sequence;
+        let blocker_cleared_tx = &self.blocker_cleared_tx;
 
         let should_send = |surface: &WlSurface, states: &SurfaceData| {
             // Do the standard primary scanout output check. For pointer surfaces it deduplicates
@@ -3583,6 +5825,9 @@ impl Niri {
             }
 
             if send {
+                // The surface is shown for this refresh cycle, which completes its FIFO barrier.
+                clear_surface_fifo_barrier(surface, states, blocker_cleared_tx);
+
                 *last_sent_at = Some((output.clone(), sequence));
                 Some(output.clone())
             } else {
@@ -3592,13 +5837,37 @@ impl Niri {
 
         let frame_callback_time = get_monotonic_time();
 
+        let mut throttled_delay: Option<Duration> = None;
         for mapped in self.layout.windows_for_output_mut(output) {
-            mapped.send_frame(
+            let delay = mapped.send_frame(
                 output,
                 frame_callback_time,
                 FRAME_CALLBACK_THROTTLE,
                 should_send,
             );
+            if let Some(delay) = delay {
+                throttled_delay = Some(throttled_delay.map_or(delay, |d| d.min(delay)));
+            }
+        }
+
+        // Throttled windows didn't get a frame callback, so make sure we come back for them even
+        // if nothing else causes a redraw.
+        if let Some(delay) = throttled_delay {
+            let state = self.output_state.get_mut(output).unwrap();
+            if state.throttled_frame_timer.is_none() {
+                let output = output.clone();
+                let token = self
+                    .event_loop
+                    .insert_source(Timer::from_duration(delay), move |_, _, state| {
+                        if let Some(output_state) = state.niri.output_state.get_mut(&output) {
+                            output_state.throttled_frame_timer = None;
+                            state.niri.queue_redraw(&output);
+                        }
+                        TimeoutAction::Drop
+                    })
+                    .unwrap();
+                state.throttled_frame_timer = Some(token);
+            }
         }
 
         for surface in layer_map_for_output(output).layers() {
@@ -3634,6 +5903,11 @@ impl Niri {
     pub fn send_frame_callbacks_on_fallback_timer(&mut self) {
         let _span = tracy_client::span!("Niri::send_frame_callbacks_on_fallback_timer");
 
+        // Withhold frame callbacks while the session is paused, the redraw on resume sends them.
+        if self.is_session_paused {
+            return;
+        }
+
         // Make up a bogus output; we don't care about it here anyway, just the throttling timer.
         let output = Output::new(
             String::new(),
@@ -3648,12 +5922,19 @@ impl Niri {
 
         let frame_callback_time = get_monotonic_time();
 
+        // Invisible surfaces don't get refresh cycles, so release their FIFO barriers here.
+        let blocker_cleared_tx = &self.blocker_cleared_tx;
+        let clear_fifo = |surface: &WlSurface, states: &SurfaceData| {
+            clear_surface_fifo_barrier(surface, states, blocker_cleared_tx);
+            None
+        };
+
         self.layout.with_windows_mut(|mapped, _| {
             mapped.send_frame(
                 output,
                 frame_callback_time,
                 FRAME_CALLBACK_THROTTLE,
-                |_, _| None,
+                clear_fifo,
             );
         });
 
@@ -3663,7 +5944,7 @@ impl Niri {
                     output,
                     frame_callback_time,
                     FRAME_CALLBACK_THROTTLE,
-                    |_, _| None,
+                    clear_fifo,
                 );
             }
 
@@ -3675,7 +5956,7 @@ impl Niri {
                 output,
                 frame_callback_time,
                 FRAME_CALLBACK_THROTTLE,
-                |_, _| None,
+                clear_fifo,
             );
         }
 
@@ -3685,11 +5966,122 @@ impl Niri {
                 output,
                 frame_callback_time,
                 FRAME_CALLBACK_THROTTLE,
-                |_, _| None,
+                clear_fifo,
             );
         }
     }
 
+    /// Registers a commit waiting for its target presentation time, released by
+    /// `release_commit_timers()` during refresh.
+    pub fn queue_commit_timer(&mut self, timer: CommitTimer) {
+        let Some(surface) = timer.surface() else {
+            return;
+        };
+
+        let client = surface.client();
+        let queued = self
+            .commit_timers
+            .iter()
+            .filter(|timer| timer.surface().and_then(|s| s.client()) == client)
+            .count();
+        if queued >= MAX_COMMIT_TIMERS_PER_CLIENT {
+            trace!("too many timed commits queued, applying right away");
+            timer.release();
+            if let Some(client) = client {
+                let _ = self.blocker_cleared_tx.send(client);
+            }
+            return;
+        }
+
+        self.commit_timers.push(timer);
+    }
+
+    /// Releases commits that can make their target presentation time and schedules a wakeup for the
+    /// rest.
+    ///
+    /// The release time comes from the frame clock of the surface's output: after the VBlank before
+    /// the target frame. Clients are notified of cleared blockers afterwards, all at once.
+    pub fn release_commit_timers(&mut self) {
+        if self.commit_timers.is_empty() {
+            self.schedule_commit_timer_wakeup(None);
+            return;
+        }
+
+        let now = get_monotonic_time();
+        let output_state = &self.output_state;
+        let blocker_cleared_tx = &self.blocker_cleared_tx;
+        let mut next_wakeup: Option<Duration> = None;
+        let mut outputs_to_redraw = Vec::new();
+        self.commit_timers.retain(|timer| {
+            // The surface is gone, its commit doesn't need releasing anymore.
+            let Some(surface) = timer.surface() else {
+                return false;
+            };
+
+            // Invisible surfaces have no frame clock, release by the current time.
+            let output = with_states(&surface, |states| {
+                surface_primary_scanout_output(&surface, states)
+            });
+            let timestamp = timer.timestamp();
+            let (due, release_time) =
+                match output.as_ref().and_then(|output| output_state.get(output)) {
+                    Some(state) => (
+                        state.frame_clock.is_commit_due(timestamp),
+                        state.frame_clock.commit_release_time(timestamp),
+                    ),
+                    None => (timestamp <= now, timestamp),
+                };
+
+            if due {
+                timer.release();
+                if let Some(client) = surface.client() {
+                    let _ = blocker_cleared_tx.send(client);
+                }
+                return false;
+            }
+
+            if release_time <= now {
+                // The release time has passed, but an idle output has no VBlank to advance its
+                // frame clock, so redraw once.
+                outputs_to_redraw.extend(output);
+            } else {
+                next_wakeup = Some(next_wakeup.map_or(release_time, |t| t.min(release_time)));
+            }
+            true
+        });
+
+        for output in outputs_to_redraw {
+            self.queue_redraw(&output);
+        }
+        self.schedule_commit_timer_wakeup(next_wakeup);
+    }
+
+    /// Schedules waking up the event loop at `at` (the following refresh releases due commits),
+    /// `None` cancels the wakeup.
+    fn schedule_commit_timer_wakeup(&mut self, at: Option<Duration>) {
+        if self.commit_timer_wakeup.as_ref().map(|(_, t)| *t) == at {
+            return;
+        }
+
+        if let Some((token, _)) = self.commit_timer_wakeup.take() {
+            self.event_loop.remove(token);
+        }
+
+        let Some(at) = at else {
+            return;
+        };
+
+        let delay = at.saturating_sub(get_monotonic_time());
+        let token = self
+            .event_loop
+            .insert_source(Timer::from_duration(delay), |_, _, state| {
+                state.niri.commit_timer_wakeup = None;
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.commit_timer_wakeup = Some((token, at));
+    }
+
     pub fn take_presentation_feedbacks(
         &mut self,
         output: &Output,
@@ -3754,6 +6146,18 @@ impl Niri {
         self.queue_redraw_all();
     }
 
+    pub fn debug_toggle_damage_heatmap(&mut self) {
+        self.debug_damage_heatmap = !self.debug_damage_heatmap;
+
+        if self.debug_damage_heatmap {
+            for (output, state) in &mut self.output_state {
+                state.debug_damage_heatmap = DamageHeatmap::new(output);
+            }
+        }
+
+        self.queue_redraw_all();
+    }
+
     /// Activates the pointer constraint if necessary according to the current pointer contents.
     ///
     /// Make sure the pointer location and contents are up to date before calling this.
@@ -3891,11 +6295,8 @@ impl Niri {
             let window_rules = &self.config.borrow().window_rules;
 
             for unmapped in self.unmapped_windows.values_mut() {
-                let new_rules = ResolvedWindowRules::compute(
-                    window_rules,
-                    WindowRef::Unmapped(unmapped),
-                    self.is_at_startup,
-                );
+                let new_rules =
+                    ResolvedWindowRules::compute(window_rules, WindowRef::Unmapped(unmapped));
                 if let InitialConfigureState::Configured { rules, .. } = &mut unmapped.state {
                     *rules = new_rules;
                 }
@@ -3903,7 +6304,7 @@ impl Niri {
 
             let mut windows = vec![];
             self.layout.with_windows_mut(|mapped, _| {
-                if mapped.recompute_window_rules(window_rules, self.is_at_startup) {
+                if mapped.recompute_window_rules(window_rules) {
                     windows.push(mapped.window.clone());
                 }
             });
@@ -3951,6 +6352,17 @@ impl Niri {
         let _span = tracy_client::span!("Niri::notify_activity");
 
         self.idle_notifier_state.notify_activity(&self.seat);
+        *self.last_activity.lock().unwrap() = Instant::now();
+        #[cfg(feature = "dbus")]
+        if let Some(idle_monitor) = &self.idle_monitor {
+            idle_monitor.notify_activity();
+        }
+
+        // Any activity brings faded outputs back right away.
+        if self.idle_fade.cancel() {
+            self.queue_redraw_all();
+            self.reschedule_idle_fade();
+        }
 
         self.notified_activity_this_iteration = true;
     }
@@ -3960,6 +6372,8 @@ pub struct NewClient {
     pub client: UnixStream,
     pub restricted: bool,
     pub credentials_unknown: bool,
+    /// Sandbox that the client connected from, known for security-context clients.
+    pub sandbox: Option<ClientSandbox>,
 }
 
 pub struct ClientState {
@@ -3970,6 +6384,49 @@ pub struct ClientState {
     pub restricted: bool,
     /// We cannot retrieve this client's socket credentials.
     pub credentials_unknown: bool,
+    /// The process behind this client, set right after the client is inserted.
+    pub process: OnceLock<ClientProcess>,
+    /// Sandbox that the client connected from through security-context.
+    pub sandbox: Option<ClientSandbox>,
+}
+
+#[derive(Debug)]
+pub struct ClientProcess {
+    pub pid: i32,
+    pub executable: Option<PathBuf>,
+    /// App id if the process runs in Flatpak.
+    pub flatpak_app_id: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientSandbox {
+    /// Name of the sandbox engine, for example `org.flatpak`.
+    pub engine: Option<String>,
+    /// App id within the sandbox engine.
+    pub app_id: Option<String>,
+}
+
+impl ClientState {
+    /// Returns the sandbox engine of the client.
+    ///
+    /// Falls back to detecting Flatpak for clients that didn't connect through security-context.
+    pub fn sandbox_engine(&self) -> Option<&str> {
+        if let Some(sandbox) = &self.sandbox {
+            return sandbox.engine.as_deref();
+        }
+
+        let process = self.process.get()?;
+        process.flatpak_app_id.as_ref().map(|_| "org.flatpak")
+    }
+
+    /// Returns the app id of the client within its sandbox engine.
+    pub fn sandbox_app_id(&self) -> Option<&str> {
+        if let Some(sandbox) = &self.sandbox {
+            return sandbox.app_id.as_deref();
+        }
+
+        self.process.get()?.flatpak_app_id.as_deref()
+    }
 }
 
 impl ClientData for ClientState {
@@ -3977,6 +6434,75 @@ impl ClientData for ClientState {
     fn disconnected(&self, _client_id: ClientId, _reason: DisconnectReason) {}
 }
 
+/// Disconnects the Wayland client that owns the window.
+///
+/// Doesn't signal the PID from the client credentials: it may be a proxy like
+/// xwayland-satellite, waypipe or a flatpak sandbox, and killing it would take down every client
+/// behind it. Disconnecting only affects this one Wayland client.
+fn kill_client(display_handle: &DisplayHandle, surface: &WlSurface) {
+    let Some(client) = surface.client() else {
+        return;
+    };
+
+    info!("killing unresponsive client {:?}", client.id());
+    let error = ProtocolError {
+        code: 0,
+        object_id: 0,
+        object_interface: String::from("wl_display"),
+        message: String::from("killed by the user for not responding"),
+    };
+    client.kill(display_handle, error);
+}
+
+/// The surface contents were shown for one refresh cycle: clears its FIFO barrier and notifies
+/// the client about its blocked commits.
+fn clear_surface_fifo_barrier(
+    surface: &WlSurface,
+    states: &SurfaceData,
+    blocker_cleared_tx: &Sender<Client>,
+) {
+    if clear_fifo_barrier(states) {
+        if let Some(client) = surface.client() {
+            let _ = blocker_cleared_tx.send(client);
+        }
+    }
+}
+
+/// Loads the config from a file, logging a warning and returning the details for the error
+/// notification on failure.
+pub fn load_config(path: &Path) -> Result<Config, ConfigErrorDetail> {
+    Config::load(path).map_err(|err| {
+        let detail = ConfigErrorDetail::from_report(path, &err);
+        warn!("{:?}", err.context("error loading config"));
+        detail
+    })
+}
+
+/// Path of the layout snapshot file, the default location if not given.
+fn layout_snapshot_path(path: Option<&str>) -> Result<PathBuf, String> {
+    match path {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => LayoutSnapshot::default_path()
+            .ok_or_else(|| String::from("error getting the default layout snapshot path")),
+    }
+}
+
+/// Finds the desktop file of a program by app id and starts it.
+///
+/// Looking it up scans the desktop file directories, so it happens on a separate thread.
+fn spawn_desktop_entry(app_id: String, token: XdgActivationToken) {
+    let res = std::thread::Builder::new()
+        .name(String::from("Desktop Entry Lookup"))
+        .spawn(move || match desktop_entry::find_command(&app_id) {
+            Some(command) => crate::utils::spawning::spawn(command, Some(token)),
+            None => warn!("no desktop entry found to start {app_id}"),
+        });
+
+    if let Err(err) = res {
+        warn!("error spawning a thread to look up a desktop entry: {err:?}");
+    }
+}
+
 fn scale_relocate_crop<E: Element>(
     elem: E,
     output_scale: Scale<f64>,
@@ -4004,7 +6530,12 @@ niri_render_elements! {
             SolidColorRenderElement
         >>>,
         Texture = PrimaryGpuTextureRenderElement,
+        RelocatedTexture = CropRenderElement<RelocateRenderElement<RescaleRenderElement<
+            PrimaryGpuTextureRenderElement
+        >>>,
         // Used for the CPU-rendered panels.
         RelocatedMemoryBuffer = RelocateRenderElement<MemoryRenderBufferRenderElement<R>>,
+        // Used for rendering below the native resolution.
+        Offscreen = OffscreenRenderElement,
     }
 }
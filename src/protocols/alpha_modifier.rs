@@ -0,0 +1,219 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use smithay::reexports::wayland_protocols::wp::alpha_modifier::v1::server::{
+    wp_alpha_modifier_surface_v1, wp_alpha_modifier_v1,
+};
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, Weak,
+};
+use smithay::wayland::compositor::{with_states, Cacheable, SurfaceData};
+use wp_alpha_modifier_surface_v1::WpAlphaModifierSurfaceV1;
+use wp_alpha_modifier_v1::WpAlphaModifierV1;
+
+const VERSION: u32 = 1;
+
+pub struct AlphaModifierManagerState {}
+
+pub trait AlphaModifierHandler {}
+
+pub struct AlphaModifierSurfaceData {
+    surface: Weak<WlSurface>,
+}
+
+/// Double-buffered alpha multiplier of a surface.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AlphaModifierCachedState {
+    /// Multiplier where `u32::MAX` is fully opaque, `None` if there's no alpha modifier.
+    multiplier: Option<u32>,
+}
+
+/// Whether the surface already has an alpha modifier object.
+#[derive(Default)]
+struct AlphaModifierMarker(AtomicBool);
+
+impl AlphaModifierManagerState {
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<WpAlphaModifierV1, ()>,
+        D: Dispatch<WpAlphaModifierV1, ()>,
+        D: Dispatch<WpAlphaModifierSurfaceV1, AlphaModifierSurfaceData>,
+        D: AlphaModifierHandler,
+        D: 'static,
+    {
+        display.create_global::<D, WpAlphaModifierV1, _>(VERSION, ());
+
+        Self {}
+    }
+}
+
+impl AlphaModifierCachedState {
+    fn alpha(&self) -> f32 {
+        self.multiplier.map_or(1., |multiplier| {
+            (f64::from(multiplier) / f64::from(u32::MAX)) as f32
+        })
+    }
+}
+
+impl Cacheable for AlphaModifierCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        *self
+    }
+
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        *into = self;
+    }
+}
+
+/// Returns the alpha that the client requested for the surface, in `0.0..=1.0`.
+pub fn surface_alpha(states: &SurfaceData) -> f32 {
+    states
+        .cached_state
+        .get::<AlphaModifierCachedState>()
+        .current()
+        .alpha()
+}
+
+impl<D> GlobalDispatch<WpAlphaModifierV1, (), D> for AlphaModifierManagerState
+where
+    D: GlobalDispatch<WpAlphaModifierV1, ()>,
+    D: Dispatch<WpAlphaModifierV1, ()>,
+    D: Dispatch<WpAlphaModifierSurfaceV1, AlphaModifierSurfaceData>,
+    D: AlphaModifierHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        manager: New<WpAlphaModifierV1>,
+        _manager_state: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+}
+
+impl<D> Dispatch<WpAlphaModifierV1, (), D> for AlphaModifierManagerState
+where
+    D: Dispatch<WpAlphaModifierV1, ()>,
+    D: Dispatch<WpAlphaModifierSurfaceV1, AlphaModifierSurfaceData>,
+    D: AlphaModifierHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpAlphaModifierV1,
+        request: <WpAlphaModifierV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_alpha_modifier_v1::Request::GetSurface { id, surface } => {
+                let already_constructed = with_states(&surface, |states| {
+                    let marker = states
+                        .data_map
+                        .get_or_insert_threadsafe(AlphaModifierMarker::default);
+                    marker.0.swap(true, Ordering::Relaxed)
+                });
+
+                if already_constructed {
+                    resource.post_error(
+                        wp_alpha_modifier_v1::Error::AlreadyConstructed,
+                        "the surface already has an alpha modifier",
+                    );
+                    return;
+                }
+
+                let data = AlphaModifierSurfaceData {
+                    surface: surface.downgrade(),
+                };
+                data_init.init(id, data);
+            }
+            wp_alpha_modifier_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpAlphaModifierSurfaceV1, AlphaModifierSurfaceData, D>
+    for AlphaModifierManagerState
+where
+    D: Dispatch<WpAlphaModifierSurfaceV1, AlphaModifierSurfaceData>,
+    D: AlphaModifierHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpAlphaModifierSurfaceV1,
+        request: <WpAlphaModifierSurfaceV1 as Resource>::Request,
+        data: &AlphaModifierSurfaceData,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_alpha_modifier_surface_v1::Request::SetMultiplier { factor } => {
+                let Ok(surface) = data.surface.upgrade() else {
+                    resource.post_error(
+                        wp_alpha_modifier_surface_v1::Error::NoSurface,
+                        "the surface was destroyed",
+                    );
+                    return;
+                };
+
+                with_states(&surface, |states| {
+                    let mut cached = states.cached_state.get::<AlphaModifierCachedState>();
+                    cached.pending().multiplier = Some(factor);
+                });
+            }
+            wp_alpha_modifier_surface_v1::Request::Destroy => {
+                // The surface goes back to fully opaque on the next commit.
+                if let Ok(surface) = data.surface.upgrade() {
+                    with_states(&surface, |states| {
+                        let mut cached = states.cached_state.get::<AlphaModifierCachedState>();
+                        cached.pending().multiplier = None;
+
+                        if let Some(marker) = states.data_map.get::<AlphaModifierMarker>() {
+                            marker.0.store(false, Ordering::Relaxed);
+                        }
+                    });
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! delegate_alpha_modifier {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::alpha_modifier::v1::server::wp_alpha_modifier_v1::WpAlphaModifierV1: ()
+        ] => $crate::protocols::alpha_modifier::AlphaModifierManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::alpha_modifier::v1::server::wp_alpha_modifier_v1::WpAlphaModifierV1: ()
+        ] => $crate::protocols::alpha_modifier::AlphaModifierManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::alpha_modifier::v1::server::wp_alpha_modifier_surface_v1::WpAlphaModifierSurfaceV1: $crate::protocols::alpha_modifier::AlphaModifierSurfaceData
+        ] => $crate::protocols::alpha_modifier::AlphaModifierManagerState);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiplier_maps_to_alpha() {
+        let alpha = |multiplier| AlphaModifierCachedState { multiplier }.alpha();
+        assert_eq!(alpha(None), 1.);
+        assert_eq!(alpha(Some(u32::MAX)), 1.);
+        assert_eq!(alpha(Some(0)), 0.);
+        assert!((alpha(Some(u32::MAX / 2)) - 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,310 @@
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use smithay::reexports::wayland_protocols::wp::commit_timing::v1::server::{
+    wp_commit_timer_v1, wp_commit_timing_manager_v1,
+};
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, Weak,
+};
+use smithay::wayland::compositor::{
+    add_blocker, add_pre_commit_hook, remove_pre_commit_hook, with_states, Blocker, BlockerState,
+    Cacheable, HookId,
+};
+use wp_commit_timer_v1::WpCommitTimerV1;
+use wp_commit_timing_manager_v1::WpCommitTimingManagerV1;
+
+use crate::utils::get_monotonic_time;
+
+const VERSION: u32 = 1;
+
+/// How far into the future a commit may be timed.
+///
+/// Later timestamps are clamped, so that a client can't stall its surface for minutes with a bogus
+/// or far-off target.
+const MAX_DELAY: Duration = Duration::from_secs(1);
+
+pub struct CommitTimingManagerState {}
+
+pub trait CommitTimingHandler {
+    /// A commit was blocked until its target presentation time.
+    ///
+    /// The handler should release the timer once the commit, applied right away, would be
+    /// presented no earlier than the timestamp.
+    fn commit_timer_queued(&mut self, timer: CommitTimer);
+}
+
+pub struct CommitTimerData {
+    surface: Weak<WlSurface>,
+    hook: HookId,
+}
+
+/// Commit of a surface that waits for its target presentation time.
+#[derive(Debug)]
+pub struct CommitTimer {
+    surface: Weak<WlSurface>,
+    timestamp: Duration,
+    released: Arc<AtomicBool>,
+}
+
+struct CommitTimerBlocker(Arc<AtomicBool>);
+
+/// Double-buffered target presentation time of a surface.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CommitTimingCachedState {
+    /// Presentation time in the monotonic clock, `None` if the commit isn't timed.
+    timestamp: Option<Duration>,
+}
+
+/// Whether the surface already has a commit timer object.
+#[derive(Default)]
+struct CommitTimerMarker(AtomicBool);
+
+impl CommitTimingManagerState {
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<WpCommitTimingManagerV1, ()>,
+        D: Dispatch<WpCommitTimingManagerV1, ()>,
+        D: Dispatch<WpCommitTimerV1, CommitTimerData>,
+        D: CommitTimingHandler,
+        D: 'static,
+    {
+        display.create_global::<D, WpCommitTimingManagerV1, _>(VERSION, ());
+
+        Self {}
+    }
+}
+
+impl CommitTimer {
+    pub fn surface(&self) -> Option<WlSurface> {
+        self.surface.upgrade().ok()
+    }
+
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    /// Lets the commit through.
+    ///
+    /// The client's blocked commits should be processed afterwards.
+    pub fn release(&self) {
+        self.released.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Blocker for CommitTimerBlocker {
+    fn state(&self) -> BlockerState {
+        if self.0.load(Ordering::Relaxed) {
+            BlockerState::Released
+        } else {
+            BlockerState::Pending
+        }
+    }
+}
+
+impl Cacheable for CommitTimingCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        // The timestamp applies only to the commit that it was set for.
+        mem::take(self)
+    }
+
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        *into = self;
+    }
+}
+
+impl<D> GlobalDispatch<WpCommitTimingManagerV1, (), D> for CommitTimingManagerState
+where
+    D: GlobalDispatch<WpCommitTimingManagerV1, ()>,
+    D: Dispatch<WpCommitTimingManagerV1, ()>,
+    D: Dispatch<WpCommitTimerV1, CommitTimerData>,
+    D: CommitTimingHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        manager: New<WpCommitTimingManagerV1>,
+        _manager_state: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+}
+
+impl<D> Dispatch<WpCommitTimingManagerV1, (), D> for CommitTimingManagerState
+where
+    D: Dispatch<WpCommitTimingManagerV1, ()>,
+    D: Dispatch<WpCommitTimerV1, CommitTimerData>,
+    D: CommitTimingHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpCommitTimingManagerV1,
+        request: <WpCommitTimingManagerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_commit_timing_manager_v1::Request::GetTimer { id, surface } => {
+                let already_exists = with_states(&surface, |states| {
+                    let marker = states
+                        .data_map
+                        .get_or_insert_threadsafe(CommitTimerMarker::default);
+                    marker.0.swap(true, Ordering::Relaxed)
+                });
+
+                if already_exists {
+                    resource.post_error(
+                        wp_commit_timing_manager_v1::Error::CommitTimerExists,
+                        "the surface already has a commit timer",
+                    );
+                    return;
+                }
+
+                let hook = add_pre_commit_hook::<D, _>(&surface, |state, _dh, surface| {
+                    let timestamp = with_states(surface, |states| {
+                        let mut cached = states.cached_state.get::<CommitTimingCachedState>();
+                        cached.pending().timestamp
+                    });
+                    let Some(timestamp) = timestamp else {
+                        return;
+                    };
+                    let timestamp = clamp_timestamp(timestamp, get_monotonic_time());
+
+                    let released = Arc::new(AtomicBool::new(false));
+                    add_blocker(surface, CommitTimerBlocker(released.clone()));
+                    state.commit_timer_queued(CommitTimer {
+                        surface: surface.downgrade(),
+                        timestamp,
+                        released,
+                    });
+                });
+
+                let data = CommitTimerData {
+                    surface: surface.downgrade(),
+                    hook,
+                };
+                data_init.init(id, data);
+            }
+            wp_commit_timing_manager_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpCommitTimerV1, CommitTimerData, D> for CommitTimingManagerState
+where
+    D: Dispatch<WpCommitTimerV1, CommitTimerData>,
+    D: CommitTimingHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpCommitTimerV1,
+        request: <WpCommitTimerV1 as Resource>::Request,
+        data: &CommitTimerData,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_commit_timer_v1::Request::SetTimestamp {
+                tv_sec_hi,
+                tv_sec_lo,
+                tv_nsec,
+            } => {
+                let Ok(surface) = data.surface.upgrade() else {
+                    resource.post_error(
+                        wp_commit_timer_v1::Error::SurfaceDestroyed,
+                        "the surface was destroyed",
+                    );
+                    return;
+                };
+
+                if tv_nsec >= 1_000_000_000 {
+                    resource.post_error(
+                        wp_commit_timer_v1::Error::InvalidTimestamp,
+                        "tv_nsec must be less than one second",
+                    );
+                    return;
+                }
+
+                let secs = (u64::from(tv_sec_hi) << 32) | u64::from(tv_sec_lo);
+                let timestamp = Duration::new(secs, tv_nsec);
+
+                let exists = with_states(&surface, |states| {
+                    let mut cached = states.cached_state.get::<CommitTimingCachedState>();
+                    let pending = cached.pending();
+                    if pending.timestamp.is_some() {
+                        return true;
+                    }
+
+                    pending.timestamp = Some(timestamp);
+                    false
+                });
+
+                if exists {
+                    resource.post_error(
+                        wp_commit_timer_v1::Error::TimestampExists,
+                        "the commit already has a timestamp",
+                    );
+                }
+            }
+            wp_commit_timer_v1::Request::Destroy => {
+                if let Ok(surface) = data.surface.upgrade() {
+                    remove_pre_commit_hook(&surface, data.hook.clone());
+
+                    with_states(&surface, |states| {
+                        if let Some(marker) = states.data_map.get::<CommitTimerMarker>() {
+                            marker.0.store(false, Ordering::Relaxed);
+                        }
+                    });
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn clamp_timestamp(timestamp: Duration, now: Duration) -> Duration {
+    timestamp.min(now + MAX_DELAY)
+}
+
+#[macro_export]
+macro_rules! delegate_commit_timing {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::commit_timing::v1::server::wp_commit_timing_manager_v1::WpCommitTimingManagerV1: ()
+        ] => $crate::protocols::commit_timing::CommitTimingManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::commit_timing::v1::server::wp_commit_timing_manager_v1::WpCommitTimingManagerV1: ()
+        ] => $crate::protocols::commit_timing::CommitTimingManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::commit_timing::v1::server::wp_commit_timer_v1::WpCommitTimerV1: $crate::protocols::commit_timing::CommitTimerData
+        ] => $crate::protocols::commit_timing::CommitTimingManagerState);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn far_future_timestamps_are_clamped() {
+        let now = Duration::from_secs(100);
+        let soon = now + Duration::from_millis(16);
+        assert_eq!(clamp_timestamp(soon, now), soon);
+        assert_eq!(clamp_timestamp(Duration::ZERO, now), Duration::ZERO);
+        assert_eq!(clamp_timestamp(Duration::MAX, now), now + MAX_DELAY);
+    }
+}
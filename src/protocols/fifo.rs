@@ -0,0 +1,288 @@
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use smithay::reexports::wayland_protocols::wp::fifo::v1::server::{wp_fifo_manager_v1, wp_fifo_v1};
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::{
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, Weak,
+};
+use smithay::wayland::compositor::{
+    add_blocker, add_pre_commit_hook, remove_pre_commit_hook, with_states, Blocker, BlockerState,
+    Cacheable, HookId, SurfaceData,
+};
+use wp_fifo_manager_v1::WpFifoManagerV1;
+use wp_fifo_v1::WpFifoV1;
+
+const VERSION: u32 = 1;
+
+pub struct FifoManagerState {}
+
+pub trait FifoHandler {}
+
+pub struct FifoData {
+    surface: Weak<WlSurface>,
+    hook: HookId,
+}
+
+/// Barrier set by a commit, cleared once that commit reaches the screen.
+#[derive(Debug, Default)]
+struct FifoBarrier(AtomicBool);
+
+/// Blocks a commit until the barrier that it waits on is cleared.
+struct FifoBlocker(Arc<FifoBarrier>);
+
+/// Double-buffered FIFO state of a surface.
+#[derive(Debug, Default)]
+pub struct FifoCachedState {
+    set_barrier: bool,
+    wait_barrier: bool,
+    /// Barrier set by this commit.
+    barrier: Option<Arc<FifoBarrier>>,
+}
+
+/// FIFO state of a surface that lives outside of its commits.
+#[derive(Default)]
+struct FifoSurfaceData {
+    /// Whether the surface already has a FIFO object.
+    exists: AtomicBool,
+    /// Barrier of the latest commit that set one, which later commits wait on.
+    latest_barrier: Mutex<Option<Arc<FifoBarrier>>>,
+}
+
+impl FifoManagerState {
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<WpFifoManagerV1, ()>,
+        D: Dispatch<WpFifoManagerV1, ()>,
+        D: Dispatch<WpFifoV1, FifoData>,
+        D: FifoHandler,
+        D: 'static,
+    {
+        display.create_global::<D, WpFifoManagerV1, _>(VERSION, ());
+
+        Self {}
+    }
+}
+
+impl FifoBarrier {
+    fn is_cleared(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Clears the barrier, returning whether it was set.
+    fn clear(&self) -> bool {
+        !self.0.swap(true, Ordering::Relaxed)
+    }
+}
+
+impl Blocker for FifoBlocker {
+    fn state(&self) -> BlockerState {
+        if self.0.is_cleared() {
+            BlockerState::Released
+        } else {
+            BlockerState::Pending
+        }
+    }
+}
+
+impl Cacheable for FifoCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        // Barrier requests apply only to the commit that they were made for.
+        mem::take(self)
+    }
+
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        if let Some(barrier) = self.barrier {
+            // The older commit is never shown on its own, so nothing should wait for it.
+            if let Some(old) = into.barrier.replace(barrier) {
+                old.clear();
+            }
+        }
+    }
+}
+
+/// Clears the barrier set by the current commit of the surface.
+///
+/// Should be called once the surface contents were shown for a refresh cycle. Returns `true` if
+/// this released a barrier, in which case the client's blocked commits should be processed.
+pub fn clear_fifo_barrier(states: &SurfaceData) -> bool {
+    let mut cached = states.cached_state.get::<FifoCachedState>();
+    cached
+        .current()
+        .barrier
+        .as_ref()
+        .is_some_and(|barrier| barrier.clear())
+}
+
+fn pre_commit(surface: &WlSurface) {
+    let blocker = with_states(surface, |states| {
+        let data = states
+            .data_map
+            .get_or_insert_threadsafe(FifoSurfaceData::default);
+        let mut latest_barrier = data.latest_barrier.lock().unwrap();
+
+        let mut cached = states.cached_state.get::<FifoCachedState>();
+        let pending = cached.pending();
+
+        let blocker = if pending.wait_barrier {
+            latest_barrier
+                .as_ref()
+                .filter(|barrier| !barrier.is_cleared())
+                .map(|barrier| FifoBlocker(barrier.clone()))
+        } else {
+            None
+        };
+
+        // The barrier is set in commit order, so a later commit waits on this one even if this
+        // one is still blocked itself.
+        if pending.set_barrier {
+            let barrier = Arc::new(FifoBarrier::default());
+            pending.barrier = Some(barrier.clone());
+            *latest_barrier = Some(barrier);
+        }
+
+        blocker
+    });
+
+    if let Some(blocker) = blocker {
+        add_blocker(surface, blocker);
+    }
+}
+
+impl<D> GlobalDispatch<WpFifoManagerV1, (), D> for FifoManagerState
+where
+    D: GlobalDispatch<WpFifoManagerV1, ()>,
+    D: Dispatch<WpFifoManagerV1, ()>,
+    D: Dispatch<WpFifoV1, FifoData>,
+    D: FifoHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        manager: New<WpFifoManagerV1>,
+        _manager_state: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+}
+
+impl<D> Dispatch<WpFifoManagerV1, (), D> for FifoManagerState
+where
+    D: Dispatch<WpFifoManagerV1, ()>,
+    D: Dispatch<WpFifoV1, FifoData>,
+    D: FifoHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpFifoManagerV1,
+        request: <WpFifoManagerV1 as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_fifo_manager_v1::Request::GetFifo { id, surface } => {
+                let already_exists = with_states(&surface, |states| {
+                    let data = states
+                        .data_map
+                        .get_or_insert_threadsafe(FifoSurfaceData::default);
+                    data.exists.swap(true, Ordering::Relaxed)
+                });
+
+                if already_exists {
+                    resource.post_error(
+                        wp_fifo_manager_v1::Error::AlreadyExists,
+                        "the surface already has a fifo object",
+                    );
+                    return;
+                }
+
+                let hook = add_pre_commit_hook::<D, _>(&surface, |_state, _dh, surface| {
+                    pre_commit(surface);
+                });
+
+                let data = FifoData {
+                    surface: surface.downgrade(),
+                    hook,
+                };
+                data_init.init(id, data);
+            }
+            wp_fifo_manager_v1::Request::Destroy => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpFifoV1, FifoData, D> for FifoManagerState
+where
+    D: Dispatch<WpFifoV1, FifoData>,
+    D: FifoHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpFifoV1,
+        request: <WpFifoV1 as Resource>::Request,
+        data: &FifoData,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_fifo_v1::Request::SetBarrier | wp_fifo_v1::Request::WaitBarrier => {
+                let Ok(surface) = data.surface.upgrade() else {
+                    resource.post_error(
+                        wp_fifo_v1::Error::SurfaceDestroyed,
+                        "the surface was destroyed",
+                    );
+                    return;
+                };
+
+                with_states(&surface, |states| {
+                    let mut cached = states.cached_state.get::<FifoCachedState>();
+                    let pending = cached.pending();
+                    if matches!(request, wp_fifo_v1::Request::SetBarrier) {
+                        pending.set_barrier = true;
+                    } else {
+                        pending.wait_barrier = true;
+                    }
+                });
+            }
+            wp_fifo_v1::Request::Destroy => {
+                if let Ok(surface) = data.surface.upgrade() {
+                    remove_pre_commit_hook(&surface, data.hook.clone());
+
+                    with_states(&surface, |states| {
+                        if let Some(data) = states.data_map.get::<FifoSurfaceData>() {
+                            data.exists.store(false, Ordering::Relaxed);
+                        }
+                    });
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! delegate_fifo {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::fifo::v1::server::wp_fifo_manager_v1::WpFifoManagerV1: ()
+        ] => $crate::protocols::fifo::FifoManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::fifo::v1::server::wp_fifo_manager_v1::WpFifoManagerV1: ()
+        ] => $crate::protocols::fifo::FifoManagerState);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols::wp::fifo::v1::server::wp_fifo_v1::WpFifoV1: $crate::protocols::fifo::FifoData
+        ] => $crate::protocols::fifo::FifoManagerState);
+    };
+}
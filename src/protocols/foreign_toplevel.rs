@@ -73,6 +73,12 @@ impl ForeignToplevelManagerState {
 pub fn refresh(state: &mut State) {
     let _span = tracy_client::span!("foreign_toplevel::refresh");
 
+    // Windows hidden by window swallowing are still open.
+    let mut swallowed = Vec::new();
+    state.niri.with_swallowed_windows(|mapped, output, _| {
+        swallowed.push((mapped.toplevel().clone(), output.cloned()));
+    });
+
     let protocol_state = &mut state.niri.foreign_toplevel_state;
 
     // Handle closed windows.
@@ -81,6 +87,13 @@ pub fn refresh(state: &mut State) {
             return true;
         }
 
+        if swallowed
+            .iter()
+            .any(|(toplevel, _)| toplevel.wl_surface() == surface)
+        {
+            return true;
+        }
+
         for instance in data.instances.keys() {
             instance.closed();
         }
@@ -104,6 +117,12 @@ pub fn refresh(state: &mut State) {
             }
         });
     });
+    for (toplevel, output) in &swallowed {
+        with_toplevel_role(toplevel, |role| {
+            let wl_surface = toplevel.wl_surface();
+            refresh_toplevel(protocol_state, wl_surface, role, output.as_ref(), false);
+        });
+    }
 
     // Finally, refresh the focused window.
     if let Some((window, output)) = focused {
@@ -1,3 +1,6 @@
+pub mod alpha_modifier;
+pub mod commit_timing;
+pub mod fifo;
 pub mod foreign_toplevel;
 pub mod gamma_control;
 pub mod mutter_x11_interop;
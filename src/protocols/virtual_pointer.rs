@@ -59,6 +59,10 @@ impl VirtualPointer {
         self.data().output.as_ref()
     }
 
+    pub fn client(&self) -> Option<Client> {
+        self.pointer.client()
+    }
+
     fn finish_axis_frame(&self) -> Option<AxisFrame> {
         self.data().axis_frame.lock().unwrap().take()
     }
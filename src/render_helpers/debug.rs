@@ -1,8 +1,11 @@
+use std::time::Duration;
+
 use smithay::backend::renderer::damage::OutputDamageTracker;
 use smithay::backend::renderer::element::{Element, Id, Kind};
 use smithay::backend::renderer::utils::CommitCounter;
 use smithay::backend::renderer::Color32F;
-use smithay::utils::Scale;
+use smithay::output::Output;
+use smithay::utils::{Physical, Rectangle, Scale, Size};
 
 use super::renderer::NiriRenderer;
 use super::solid_color::SolidColorRenderElement;
@@ -84,3 +87,203 @@ pub fn draw_damage<R: NiriRenderer>(
         elements.insert(0, OutputRenderElements::SolidColor(color));
     }
 }
+
+/// Size of a heatmap cell in physical pixels.
+const HEATMAP_CELL_SIZE: i32 = 32;
+/// Time for the heat of a cell to halve.
+const HEATMAP_HALF_LIFE: Duration = Duration::from_secs(1);
+/// Heat added to a cell for every frame where it's damaged.
+const HEATMAP_HEAT_PER_FRAME: f32 = 0.1;
+/// Cells with less heat are not drawn.
+const HEATMAP_MIN_HEAT: f32 = 0.02;
+/// Number of distinct colors, adjacent cells of the same color are drawn as one rectangle.
+const HEATMAP_LEVELS: f32 = 16.;
+
+/// Damage accumulated over time, to find regions that are repainted all the time.
+///
+/// Every frame adds heat to the damaged cells, and the heat decays exponentially over time. Cells
+/// damaged on every frame saturate to the hottest color.
+#[derive(Debug)]
+pub struct DamageHeatmap {
+    damage_tracker: OutputDamageTracker,
+    /// Heat of every cell in `0.0..=1.0`, row by row.
+    heat: Vec<f32>,
+    /// Size of the grid in cells.
+    grid_size: Size<i32, Physical>,
+    /// Time of the last update.
+    last_update: Option<Duration>,
+}
+
+impl DamageHeatmap {
+    pub fn new(output: &Output) -> Self {
+        Self {
+            damage_tracker: OutputDamageTracker::from_output(output),
+            heat: Vec::new(),
+            grid_size: Size::default(),
+            last_update: None,
+        }
+    }
+
+    /// Returns whether all heat has decayed, and there's nothing left to draw.
+    pub fn is_cold(&self) -> bool {
+        self.heat.iter().all(|heat| *heat < HEATMAP_MIN_HEAT)
+    }
+
+    fn update(
+        &mut self,
+        output_size: Size<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        now: Duration,
+    ) {
+        let grid_size = Size::from((
+            (output_size.w + HEATMAP_CELL_SIZE - 1) / HEATMAP_CELL_SIZE,
+            (output_size.h + HEATMAP_CELL_SIZE - 1) / HEATMAP_CELL_SIZE,
+        ));
+        if self.grid_size != grid_size {
+            self.grid_size = grid_size;
+            self.heat = vec![0.; (grid_size.w * grid_size.h) as usize];
+        }
+
+        if let Some(last_update) = self.last_update {
+            let elapsed = now.saturating_sub(last_update);
+            let decay = 0.5f32.powf(elapsed.as_secs_f32() / HEATMAP_HALF_LIFE.as_secs_f32());
+            for heat in &mut self.heat {
+                *heat *= decay;
+            }
+        }
+        self.last_update = Some(now);
+
+        // Damage rectangles can overlap, so heat up every cell at most once per frame.
+        let mut damaged = vec![false; self.heat.len()];
+        for rect in damage {
+            let x_start = (rect.loc.x / HEATMAP_CELL_SIZE).max(0);
+            let y_start = (rect.loc.y / HEATMAP_CELL_SIZE).max(0);
+            let x_end = ((rect.loc.x + rect.size.w + HEATMAP_CELL_SIZE - 1) / HEATMAP_CELL_SIZE)
+                .min(grid_size.w);
+            let y_end = ((rect.loc.y + rect.size.h + HEATMAP_CELL_SIZE - 1) / HEATMAP_CELL_SIZE)
+                .min(grid_size.h);
+
+            for y in y_start..y_end {
+                for x in x_start..x_end {
+                    damaged[(y * grid_size.w + x) as usize] = true;
+                }
+            }
+        }
+
+        for (heat, damaged) in self.heat.iter_mut().zip(damaged) {
+            if damaged {
+                *heat = (*heat + HEATMAP_HEAT_PER_FRAME).min(1.);
+            }
+        }
+    }
+
+    /// Returns the rectangles to draw, merging runs of cells with the same color in every row.
+    fn rects(&self) -> Vec<(Rectangle<i32, Physical>, f32)> {
+        let mut rects = Vec::new();
+
+        for y in 0..self.grid_size.h {
+            let row = &self.heat[(y * self.grid_size.w) as usize..][..self.grid_size.w as usize];
+
+            let mut x = 0;
+            while x < self.grid_size.w {
+                let level = heat_level(row[x as usize]);
+                let start = x;
+                while x < self.grid_size.w && heat_level(row[x as usize]) == level {
+                    x += 1;
+                }
+
+                if level > 0. {
+                    let rect = Rectangle::new(
+                        (start * HEATMAP_CELL_SIZE, y * HEATMAP_CELL_SIZE).into(),
+                        ((x - start) * HEATMAP_CELL_SIZE, HEATMAP_CELL_SIZE).into(),
+                    );
+                    rects.push((rect, level));
+                }
+            }
+        }
+
+        rects
+    }
+}
+
+/// Quantizes the heat so that adjacent cells can be drawn together.
+fn heat_level(heat: f32) -> f32 {
+    if heat < HEATMAP_MIN_HEAT {
+        return 0.;
+    }
+
+    (heat * HEATMAP_LEVELS).ceil() / HEATMAP_LEVELS
+}
+
+/// Color of a heat level, going from translucent blue through yellow to red.
+fn heat_color(level: f32) -> Color32F {
+    let (r, g, b) = if level < 0.5 {
+        let t = level * 2.;
+        (t, t, 1. - t)
+    } else {
+        let t = (level - 0.5) * 2.;
+        (1., 1. - t, 0.)
+    };
+
+    let a = 0.15 + 0.35 * level;
+    Color32F::from([r * a, g * a, b * a, a])
+}
+
+pub fn draw_damage_heatmap<R: NiriRenderer>(
+    heatmap: &mut DamageHeatmap,
+    now: Duration,
+    elements: &mut Vec<OutputRenderElements<R>>,
+) {
+    let _span = tracy_client::span!("draw_damage_heatmap");
+
+    let Ok((size, scale, _)) = heatmap.damage_tracker.mode().try_into() else {
+        return;
+    };
+
+    let damage = match heatmap.damage_tracker.damage_output(1, elements) {
+        Ok((Some(damage), _)) => damage.clone(),
+        _ => Vec::new(),
+    };
+    heatmap.update(size, &damage, now);
+
+    for (rect, level) in heatmap.rects() {
+        let color = SolidColorRenderElement::new(
+            Id::new(),
+            rect.to_f64().to_logical(scale),
+            CommitCounter::default(),
+            heat_color(level),
+            Kind::Unspecified,
+        );
+        elements.insert(0, OutputRenderElements::SolidColor(color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smithay::utils::Transform;
+
+    use super::*;
+
+    #[test]
+    fn heatmap_heats_up_and_decays() {
+        let mut heatmap = DamageHeatmap {
+            damage_tracker: OutputDamageTracker::new((64, 64), 1., Transform::Normal),
+            heat: Vec::new(),
+            grid_size: Size::default(),
+            last_update: None,
+        };
+        let size = Size::from((64, 64));
+        let damage = [Rectangle::new((0, 0).into(), (10, 10).into())];
+
+        for frame in 0..10 {
+            heatmap.update(size, &damage, Duration::from_millis(frame));
+        }
+        assert_eq!(heatmap.grid_size, Size::from((2, 2)));
+        assert!(heatmap.heat[0] > 0.9);
+        assert_eq!(heatmap.heat[1..], [0.; 3]);
+        assert!(!heatmap.is_cold());
+
+        heatmap.update(size, &[], Duration::from_secs(10));
+        assert!(heatmap.is_cold());
+    }
+}
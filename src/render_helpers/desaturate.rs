@@ -0,0 +1,63 @@
+//! Drawing render elements with reduced saturation, down to grayscale.
+//!
+//! The elements go into an offscreen texture first, which is then drawn with a shader that mixes
+//! every color with its luminance. This is used for the saturation window rule and for the
+//! grayscale mode of outputs.
+
+use std::cell::Cell;
+
+use smithay::backend::renderer::element::RenderElement;
+use smithay::backend::renderer::gles::{GlesRenderer, Uniform};
+use smithay::utils::{Logical, Point, Scale};
+
+use super::offscreen::{OffscreenBuffer, OffscreenData, OffscreenRenderElement};
+use super::renderer::NiriRenderer;
+use super::shaders::Shaders;
+
+/// Elements drawn together with reduced saturation.
+#[derive(Debug, Default)]
+pub struct Desaturation {
+    buffer: OffscreenBuffer,
+    /// Saturation of the last render.
+    ///
+    /// The damage tracking doesn't know about shader uniforms, so a change in saturation has to
+    /// damage the whole buffer.
+    last_saturation: Cell<Option<f32>>,
+}
+
+impl Desaturation {
+    pub fn has_shader(renderer: &mut impl NiriRenderer) -> bool {
+        Shaders::get(renderer).desaturate.is_some()
+    }
+
+    /// Renders `elements` as a single element with `saturation` and `alpha` applied.
+    ///
+    /// Saturation of 0 is grayscale, and 1 leaves the colors as is. `elements` should be
+    /// positioned relative to the origin, which ends up at `location`.
+    pub fn render(
+        &self,
+        renderer: &mut GlesRenderer,
+        scale: Scale<f64>,
+        location: Point<f64, Logical>,
+        saturation: f32,
+        alpha: f32,
+        elements: &[impl RenderElement<GlesRenderer>],
+    ) -> anyhow::Result<(OffscreenRenderElement, OffscreenData)> {
+        let _span = tracy_client::span!("Desaturation::render");
+
+        if self.last_saturation.replace(Some(saturation)) != Some(saturation) {
+            self.buffer.damage_all();
+        }
+
+        let (elem, _sync, data) = self.buffer.render(renderer, scale, elements)?;
+        let offset = elem.offset();
+        let mut elem = elem.with_alpha(alpha).with_offset(location + offset);
+
+        if let Some(program) = Shaders::get(renderer).desaturate.clone() {
+            let uniforms = vec![Uniform::new("saturation", saturation)];
+            elem = elem.with_program(program, uniforms);
+        }
+
+        Ok((elem, data))
+    }
+}
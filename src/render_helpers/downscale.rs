@@ -0,0 +1,89 @@
+//! CPU downscaling for textures shown at a fraction of their size.
+//!
+//! Sampling a texture with plain bilinear filtering at a small scale skips most of its pixels,
+//! which makes text and thin lines alias badly. Averaging every source pixel that covers a
+//! destination pixel (a box filter) keeps them readable.
+
+use smithay::utils::{Physical, Size};
+
+/// Downscales 4-byte-per-pixel image data with a box filter.
+///
+/// Works on any channel order. Alpha is expected to be premultiplied, in which case averaging
+/// every channel separately is correct.
+pub fn downscale_box(
+    src: &[u8],
+    src_size: Size<i32, Physical>,
+    dst_size: Size<i32, Physical>,
+) -> Vec<u8> {
+    let _span = tracy_client::span!("downscale_box");
+
+    let (src_w, src_h) = (src_size.w.max(0) as usize, src_size.h.max(0) as usize);
+    let (dst_w, dst_h) = (dst_size.w.max(0) as usize, dst_size.h.max(0) as usize);
+    assert!(src.len() >= src_w * src_h * 4);
+
+    let mut dst = vec![0; dst_w * dst_h * 4];
+    if src_w == 0 || src_h == 0 {
+        return dst;
+    }
+
+    // Source pixel span covered by destination pixel `i` out of `dst`, never empty.
+    let span = |i: usize, src: usize, dst: usize| {
+        let start = i * src / dst;
+        let end = ((i + 1) * src / dst).clamp(start + 1, src);
+        start..end
+    };
+
+    for dst_y in 0..dst_h {
+        let ys = span(dst_y, src_h, dst_h);
+        for dst_x in 0..dst_w {
+            let xs = span(dst_x, src_w, dst_w);
+
+            let mut sum = [0u32; 4];
+            for y in ys.clone() {
+                let row = &src[(y * src_w + xs.start) * 4..(y * src_w + xs.end) * 4];
+                for pixel in row.chunks_exact(4) {
+                    for (sum, value) in sum.iter_mut().zip(pixel) {
+                        *sum += u32::from(*value);
+                    }
+                }
+            }
+
+            let count = (ys.len() * xs.len()) as u32;
+            let offset = (dst_y * dst_w + dst_x) * 4;
+            for (dst, sum) in dst[offset..offset + 4].iter_mut().zip(sum) {
+                // Round to nearest.
+                *dst = ((sum + count / 2) / count) as u8;
+            }
+        }
+    }
+
+    dst
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_covered_pixels() {
+        // 4x2 image: a white and a black 2x2 block.
+        let white = [255, 255, 255, 255];
+        let black = [0, 0, 0, 255];
+        let row = [white, white, black, black].concat();
+        let src = [row.clone(), row].concat();
+
+        let dst = downscale_box(&src, Size::from((4, 2)), Size::from((2, 1)));
+        assert_eq!(dst, [white, black].concat());
+
+        // Thin lines blend into gray rather than disappearing.
+        let dst = downscale_box(&src, Size::from((4, 2)), Size::from((1, 1)));
+        assert_eq!(dst, [128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn same_size_is_identity() {
+        let src = (0..36).collect::<Vec<u8>>();
+        let dst = downscale_box(&src, Size::from((3, 3)), Size::from((3, 3)));
+        assert_eq!(dst, src);
+    }
+}
@@ -40,9 +40,11 @@ use anyhow::{ensure, Context};
 use niri_config::BlockOutFrom;
 use smithay::backend::allocator::dmabuf::Dmabuf;
 use smithay::backend::allocator::{Buffer, Fourcc};
+use smithay::backend::egl::ffi::egl;
+use smithay::backend::egl::{EGLContext, EGLDisplay};
 use smithay::backend::renderer::element::utils::{Relocate, RelocateRenderElement};
 use smithay::backend::renderer::element::{Kind, RenderElement};
-use smithay::backend::renderer::gles::{GlesMapping, GlesRenderer, GlesTarget, GlesTexture};
+use smithay::backend::renderer::gles::{ffi, GlesMapping, GlesRenderer, GlesTarget, GlesTexture};
 use smithay::backend::renderer::sync::SyncPoint;
 use smithay::backend::renderer::{Bind, Color32F, ExportMem, Frame, Offscreen, Renderer};
 use smithay::reexports::wayland_server::protocol::wl_buffer::WlBuffer;
@@ -58,8 +60,11 @@ pub mod border;
 pub mod clipped_surface;
 pub mod damage;
 pub mod debug;
+pub mod desaturate;
+pub mod downscale;
 pub mod memory;
 pub mod offscreen;
+pub mod opacity_group;
 pub mod primary_gpu_texture;
 pub mod render_elements;
 pub mod renderer;
@@ -72,6 +77,8 @@ pub mod snapshot;
 pub mod solid_color;
 pub mod surface;
 pub mod texture;
+pub mod upscale;
+pub mod urgency_flash;
 
 /// What we're rendering for.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -290,6 +297,30 @@ pub fn render_to_vec(
     Ok(copy.to_vec())
 }
 
+pub fn download_texture(
+    renderer: &mut GlesRenderer,
+    texture: &GlesTexture,
+    size: Size<i32, Physical>,
+    fourcc: Fourcc,
+) -> anyhow::Result<Vec<u8>> {
+    let _span = tracy_client::span!();
+
+    let mut texture = texture.clone();
+    let buffer_size = size.to_logical(1).to_buffer(1, Transform::Normal);
+    let mapping = {
+        let target = renderer
+            .bind(&mut texture)
+            .context("error binding texture")?;
+        renderer
+            .copy_framebuffer(&target, Rectangle::from_size(buffer_size), fourcc)
+            .context("error copying framebuffer")?
+    };
+    let copy = renderer
+        .map_texture(&mapping)
+        .context("error mapping texture")?;
+    Ok(copy.to_vec())
+}
+
 pub fn render_to_dmabuf(
     renderer: &mut GlesRenderer,
     mut dmabuf: Dmabuf,
@@ -358,6 +389,73 @@ pub fn clear_dmabuf(renderer: &mut GlesRenderer, mut dmabuf: Dmabuf) -> anyhow::
     frame.finish().context("error finishing frame")
 }
 
+// Tokens from EGL_EXT_create_context_robustness and EGL_IMG_context_priority.
+const EGL_CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY_EXT: i32 = 0x3138;
+const EGL_LOSE_CONTEXT_ON_RESET_EXT: i32 = 0x31BF;
+const EGL_CONTEXT_PRIORITY_LEVEL_IMG: i32 = 0x3100;
+const EGL_CONTEXT_PRIORITY_HIGH_IMG: i32 = 0x3101;
+
+/// Creates a high-priority EGL context that reports GPU resets.
+///
+/// The context uses the lose-context-on-reset notification strategy, so after a GPU reset
+/// [`is_context_lost`] returns `true` instead of rendering silently failing forever.
+pub fn create_robust_context(display: &EGLDisplay) -> anyhow::Result<EGLContext> {
+    let has_extension = |name: &str| display.extensions().iter().any(|ext| ext == name);
+    ensure!(
+        has_extension("EGL_EXT_create_context_robustness"),
+        "EGL_EXT_create_context_robustness is not supported"
+    );
+
+    let handle = display.get_display_handle();
+    unsafe {
+        egl::BindAPI(egl::OPENGL_ES_API);
+    }
+
+    // Prefer GLES 3, which the renderer uses when available, and fall back to GLES 2.
+    for version in [3, 2] {
+        let mut attributes = vec![
+            egl::CONTEXT_CLIENT_VERSION as i32,
+            version,
+            EGL_CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY_EXT,
+            EGL_LOSE_CONTEXT_ON_RESET_EXT,
+        ];
+        if has_extension("EGL_IMG_context_priority") {
+            attributes.push(EGL_CONTEXT_PRIORITY_LEVEL_IMG);
+            attributes.push(EGL_CONTEXT_PRIORITY_HIGH_IMG);
+        }
+        attributes.push(egl::NONE as i32);
+
+        let context =
+            unsafe { egl::CreateContext(**handle, ptr::null(), ptr::null(), attributes.as_ptr()) };
+        if context.is_null() {
+            let error = unsafe { egl::GetError() };
+            debug!("error creating robust GLES {version} context: {error:#x}");
+            continue;
+        }
+
+        let context = unsafe { EGLContext::from_raw(**handle, ptr::null(), context) }
+            .context("error wrapping the robust EGL context")?;
+        return Ok(context);
+    }
+
+    anyhow::bail!("error creating a robust EGL context");
+}
+
+/// Returns whether the GPU was reset, losing the renderer's context.
+///
+/// Only contexts created with robustness report resets, for other contexts this is always false.
+/// See [`create_robust_context`].
+pub fn is_context_lost(renderer: &mut GlesRenderer) -> bool {
+    let status = renderer.with_context(|gl| unsafe { gl.GetGraphicsResetStatus() });
+    match status {
+        Ok(status) => status != ffi::NO_ERROR,
+        Err(err) => {
+            warn!("error checking the GPU reset status: {err:?}");
+            false
+        }
+    }
+}
+
 fn render_elements(
     renderer: &mut GlesRenderer,
     target: &mut GlesTarget,
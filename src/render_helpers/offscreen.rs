@@ -7,7 +7,9 @@ use smithay::backend::renderer::element::utils::{Relocate, RelocateRenderElement
 use smithay::backend::renderer::element::{
     Element, Id, Kind, RenderElement, RenderElementStates, UnderlyingStorage,
 };
-use smithay::backend::renderer::gles::{GlesError, GlesFrame, GlesRenderer, GlesTexture};
+use smithay::backend::renderer::gles::{
+    GlesError, GlesFrame, GlesRenderer, GlesTexProgram, GlesTexture, Uniform,
+};
 use smithay::backend::renderer::sync::SyncPoint;
 use smithay::backend::renderer::utils::{
     CommitCounter, DamageBag, DamageSet, DamageSnapshot, OpaqueRegions,
@@ -57,6 +59,8 @@ pub struct OffscreenRenderElement {
     src_size: Size<i32, Buffer>,
     alpha: f32,
     kind: Kind,
+    /// Custom texture shader to draw with, and its uniforms.
+    program: Option<(GlesTexProgram, Vec<Uniform<'static>>)>,
 }
 
 #[derive(Debug)]
@@ -187,6 +191,7 @@ impl OffscreenBuffer {
             src_size,
             alpha: 1.,
             kind: Kind::Unspecified,
+            program: None,
         };
 
         let data = OffscreenData {
@@ -196,6 +201,14 @@ impl OffscreenBuffer {
 
         Ok((elem, res.sync, data))
     }
+
+    /// Damages the whole buffer for the outside, for example when its shader uniforms change.
+    pub fn damage_all(&self) {
+        if let Some(inner) = self.inner.borrow_mut().as_mut() {
+            let size = inner.texture.size();
+            inner.outer_damage.add([Rectangle::from_size(size)]);
+        }
+    }
 }
 
 impl Default for OffscreenBuffer {
@@ -226,6 +239,15 @@ impl OffscreenRenderElement {
         self
     }
 
+    pub fn with_program(
+        mut self,
+        program: GlesTexProgram,
+        uniforms: Vec<Uniform<'static>>,
+    ) -> Self {
+        self.program = Some((program, uniforms));
+        self
+    }
+
     pub fn logical_size(&self) -> Size<f64, Logical> {
         self.src_size
             .to_f64()
@@ -310,6 +332,11 @@ impl RenderElement<GlesRenderer> for OffscreenRenderElement {
             return Ok(());
         }
 
+        let (program, uniforms) = match &self.program {
+            Some((program, uniforms)) => (Some(program), &uniforms[..]),
+            None => (None, &[][..]),
+        };
+
         frame.render_texture_from_to(
             &self.texture,
             src,
@@ -318,8 +345,8 @@ impl RenderElement<GlesRenderer> for OffscreenRenderElement {
             opaque_regions,
             Transform::Normal,
             self.alpha,
-            None,
-            &[],
+            program,
+            uniforms,
         )
     }
 
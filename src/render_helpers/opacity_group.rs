@@ -0,0 +1,40 @@
+//! Fading several render elements as a single unit.
+//!
+//! Giving every element its own alpha makes overlapping parts show through each other during a
+//! fade: a half-transparent surface lets its own shadow and focus ring show through, so the frames
+//! in the middle of the fade look too dark. An opacity group draws the elements offscreen at full
+//! opacity first, then applies the alpha once to the result.
+
+use smithay::backend::renderer::element::RenderElement;
+use smithay::backend::renderer::gles::GlesRenderer;
+use smithay::utils::{Logical, Point, Scale};
+
+use super::offscreen::{OffscreenBuffer, OffscreenData, OffscreenRenderElement};
+
+/// Elements that fade in and out together.
+#[derive(Debug, Default)]
+pub struct OpacityGroup {
+    buffer: OffscreenBuffer,
+}
+
+impl OpacityGroup {
+    /// Renders `elements` as a single element with `alpha` applied.
+    ///
+    /// `elements` should be positioned relative to the group origin, which ends up at `location`.
+    pub fn render(
+        &self,
+        renderer: &mut GlesRenderer,
+        scale: Scale<f64>,
+        location: Point<f64, Logical>,
+        alpha: f32,
+        elements: &[impl RenderElement<GlesRenderer>],
+    ) -> anyhow::Result<(OffscreenRenderElement, OffscreenData)> {
+        let _span = tracy_client::span!("OpacityGroup::render");
+
+        let (elem, _sync, data) = self.buffer.render(renderer, scale, elements)?;
+        let offset = elem.offset();
+        let elem = elem.with_alpha(alpha).with_offset(location + offset);
+
+        Ok((elem, data))
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use glam::{Mat3, Vec2};
-use niri_config::CornerRadius;
+use niri_config::{CornerRadius, ResizeContent};
 use smithay::backend::renderer::element::{Element, Id, Kind, RenderElement, UnderlyingStorage};
 use smithay::backend::renderer::gles::{GlesError, GlesFrame, GlesRenderer, GlesTexture, Uniform};
 use smithay::backend::renderer::utils::{CommitCounter, DamageSet, OpaqueRegions};
@@ -12,6 +12,7 @@ use super::renderer::{AsGlesFrame, NiriRenderer};
 use super::shader_element::ShaderRenderElement;
 use super::shaders::{mat3_uniform, ProgramType, Shaders};
 use crate::backend::tty::{TtyFrame, TtyRenderer, TtyRendererError};
+use crate::utils::ResizeEdge;
 
 #[derive(Debug)]
 pub struct ResizeRenderElement(ShaderRenderElement);
@@ -27,6 +28,8 @@ impl ResizeRenderElement {
         size_next: Size<f64, Logical>,
         progress: f32,
         clamped_progress: f32,
+        content: ResizeContent,
+        edges: ResizeEdge,
         corner_radius: CornerRadius,
         clip_to_geometry: bool,
         result_alpha: f32,
@@ -81,6 +84,12 @@ impl ResizeRenderElement {
 
         let corner_radius = corner_radius.fit_to(curr_geo_size.x, curr_geo_size.y);
         let clip_to_geometry = if clip_to_geometry { 1. } else { 0. };
+        let content_mode: f32 = match content {
+            ResizeContent::Stretch => 0.,
+            ResizeContent::Crop => 1.,
+            ResizeContent::Reveal => 2.,
+        };
+        let crop_anchor = crop_anchor(edges);
 
         // Create the shader.
         Self(
@@ -99,6 +108,8 @@ impl ResizeRenderElement {
                     mat3_uniform("niri_geo_to_tex_next", geo_to_tex_next),
                     Uniform::new("niri_progress", progress),
                     Uniform::new("niri_clamped_progress", clamped_progress),
+                    Uniform::new("niri_content_mode", content_mode),
+                    Uniform::new("niri_crop_anchor", crop_anchor.to_array()),
                     Uniform::new("niri_corner_radius", <[f32; 4]>::from(corner_radius)),
                     Uniform::new("niri_clip_to_geometry", clip_to_geometry),
                 ],
@@ -197,3 +208,38 @@ impl<'render> RenderElement<TtyRenderer<'render>> for ResizeRenderElement {
         self.0.underlying_storage(renderer)
     }
 }
+
+/// Returns the point of the geometry, from (0, 0) to (1, 1), that stays in place when resizing
+/// the given edges.
+///
+/// Cropped contents are anchored there, so resizing from the left or the top edge doesn't make
+/// them jump.
+fn crop_anchor(edges: ResizeEdge) -> Vec2 {
+    let x = if edges.contains(ResizeEdge::LEFT) && !edges.contains(ResizeEdge::RIGHT) {
+        1.
+    } else {
+        0.
+    };
+    let y = if edges.contains(ResizeEdge::TOP) && !edges.contains(ResizeEdge::BOTTOM) {
+        1.
+    } else {
+        0.
+    };
+    Vec2::new(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crop_anchor_is_opposite_to_the_resized_edges() {
+        assert_eq!(crop_anchor(ResizeEdge::BOTTOM_RIGHT), Vec2::new(0., 0.));
+        assert_eq!(crop_anchor(ResizeEdge::RIGHT), Vec2::new(0., 0.));
+        assert_eq!(crop_anchor(ResizeEdge::LEFT), Vec2::new(1., 0.));
+        assert_eq!(crop_anchor(ResizeEdge::TOP), Vec2::new(0., 1.));
+        assert_eq!(crop_anchor(ResizeEdge::TOP_LEFT), Vec2::new(1., 1.));
+        assert_eq!(crop_anchor(ResizeEdge::BOTTOM_LEFT), Vec2::new(1., 0.));
+        assert_eq!(crop_anchor(ResizeEdge::empty()), Vec2::new(0., 0.));
+    }
+}
@@ -12,7 +12,10 @@ use super::shader_element::ShaderProgram;
 pub struct Shaders {
     pub border: Option<ShaderProgram>,
     pub shadow: Option<ShaderProgram>,
+    pub urgency_flash: Option<ShaderProgram>,
     pub clipped_surface: Option<GlesTexProgram>,
+    pub sharpen: Option<GlesTexProgram>,
+    pub desaturate: Option<GlesTexProgram>,
     pub resize: Option<ShaderProgram>,
     pub custom_resize: RefCell<Option<ShaderProgram>>,
     pub custom_close: RefCell<Option<ShaderProgram>>,
@@ -23,6 +26,7 @@ pub struct Shaders {
 pub enum ProgramType {
     Border,
     Shadow,
+    UrgencyFlash,
     Resize,
     Close,
     Open,
@@ -75,6 +79,23 @@ impl Shaders {
         })
         .ok();
 
+        let urgency_flash = ShaderProgram::compile(
+            renderer,
+            include_str!("urgency_flash.frag"),
+            &[
+                UniformName::new("flash_color", UniformType::_4f),
+                UniformName::new("flash_width", UniformType::_1f),
+                UniformName::new("input_to_geo", UniformType::Matrix3x3),
+                UniformName::new("geo_size", UniformType::_2f),
+                UniformName::new("corner_radius", UniformType::_4f),
+            ],
+            &[],
+        )
+        .map_err(|err| {
+            warn!("error compiling urgency flash shader: {err:?}");
+        })
+        .ok();
+
         let clipped_surface = renderer
             .compile_custom_texture_shader(
                 include_str!("clipped_surface.frag"),
@@ -90,6 +111,29 @@ impl Shaders {
             })
             .ok();
 
+        let sharpen = renderer
+            .compile_custom_texture_shader(
+                include_str!("sharpen.frag"),
+                &[
+                    UniformName::new("texel_size", UniformType::_2f),
+                    UniformName::new("sharpness", UniformType::_1f),
+                ],
+            )
+            .map_err(|err| {
+                warn!("error compiling sharpen shader: {err:?}");
+            })
+            .ok();
+
+        let desaturate = renderer
+            .compile_custom_texture_shader(
+                include_str!("desaturate.frag"),
+                &[UniformName::new("saturation", UniformType::_1f)],
+            )
+            .map_err(|err| {
+                warn!("error compiling desaturate shader: {err:?}");
+            })
+            .ok();
+
         let resize = compile_resize_program(renderer, include_str!("resize.frag"))
             .map_err(|err| {
                 warn!("error compiling resize shader: {err:?}");
@@ -99,7 +143,10 @@ impl Shaders {
         Self {
             border,
             shadow,
+            urgency_flash,
             clipped_surface,
+            sharpen,
+            desaturate,
             resize,
             custom_resize: RefCell::new(None),
             custom_close: RefCell::new(None),
@@ -145,6 +192,7 @@ impl Shaders {
         match program {
             ProgramType::Border => self.border.clone(),
             ProgramType::Shadow => self.shadow.clone(),
+            ProgramType::UrgencyFlash => self.urgency_flash.clone(),
             ProgramType::Resize => self
                 .custom_resize
                 .borrow()
@@ -184,6 +232,8 @@ fn compile_resize_program(
             UniformName::new("niri_geo_to_tex_next", UniformType::Matrix3x3),
             UniformName::new("niri_progress", UniformType::_1f),
             UniformName::new("niri_clamped_progress", UniformType::_1f),
+            UniformName::new("niri_content_mode", UniformType::_1f),
+            UniformName::new("niri_crop_anchor", UniformType::_2f),
             UniformName::new("niri_corner_radius", UniformType::_4f),
             UniformName::new("niri_clip_to_geometry", UniformType::_1f),
         ],
@@ -1,10 +1,42 @@
+bool in_unit_square(vec2 coords) {
+    return 0.0 <= coords.x && coords.x <= 1.0 && 0.0 <= coords.y && coords.y <= 1.0;
+}
+
 vec4 resize_color(vec3 coords_curr_geo, vec3 size_curr_geo) {
-    vec3 coords_tex_prev = niri_geo_to_tex_prev * coords_curr_geo;
+    // Stretch both textures to the current geometry.
+    vec3 coords_prev_geo = coords_curr_geo;
+    vec3 coords_next_geo = coords_curr_geo;
+
+    // Crop and reveal keep both textures at their own size, anchored at the edges that don't
+    // move, and cut them off at the current geometry.
+    if (niri_content_mode != 0.0) {
+        if (!in_unit_square(coords_curr_geo.xy))
+            return vec4(0.0);
+
+        vec3 anchor = vec3(niri_crop_anchor, 0.0);
+        coords_prev_geo = niri_curr_geo_to_prev_geo * (coords_curr_geo - anchor) + anchor;
+        coords_next_geo = niri_curr_geo_to_next_geo * (coords_curr_geo - anchor) + anchor;
+    }
+
+    vec3 coords_tex_prev = niri_geo_to_tex_prev * coords_prev_geo;
     vec4 color_prev = texture2D(niri_tex_prev, coords_tex_prev.st);
 
-    vec3 coords_tex_next = niri_geo_to_tex_next * coords_curr_geo;
+    vec3 coords_tex_next = niri_geo_to_tex_next * coords_next_geo;
     vec4 color_next = texture2D(niri_tex_next, coords_tex_next.st);
 
+    if (niri_content_mode != 0.0) {
+        // Don't smear the texture edges over the uncovered area.
+        if (!in_unit_square(coords_tex_prev.st))
+            color_prev = vec4(0.0);
+        if (!in_unit_square(coords_tex_next.st))
+            color_next = vec4(0.0);
+    }
+
+    // Reveal shows the new contents right away, and the old ones only where the new ones don't
+    // reach yet.
+    if (niri_content_mode == 2.0)
+        return in_unit_square(coords_tex_next.st) ? color_next : color_prev;
+
     vec4 color = mix(color_prev, color_next, niri_clamped_progress);
     return color;
 }
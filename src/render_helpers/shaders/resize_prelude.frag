@@ -21,6 +21,11 @@ uniform mat3 niri_geo_to_tex_next;
 uniform float niri_progress;
 uniform float niri_clamped_progress;
 
+// 0 for stretch, 1 for crop, 2 for reveal.
+uniform float niri_content_mode;
+// Point of the geometry, from (0, 0) to (1, 1), that cropped contents are anchored to.
+uniform vec2 niri_crop_anchor;
+
 uniform vec4 niri_corner_radius;
 uniform float niri_clip_to_geometry;
 
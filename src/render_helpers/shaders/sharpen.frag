@@ -0,0 +1,53 @@
+#version 100
+
+//_DEFINES_
+
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+precision highp float;
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+varying vec2 v_coords;
+
+#if defined(DEBUG_FLAGS)
+uniform float tint;
+#endif
+
+uniform vec2 texel_size;
+uniform float sharpness;
+
+void main() {
+    vec4 color = texture2D(tex, v_coords);
+
+    // Unsharp mask: push the color away from the average of its neighbors to counter the blur
+    // from upscaling.
+    vec4 neighbors = texture2D(tex, v_coords + vec2(texel_size.x, 0.0))
+        + texture2D(tex, v_coords - vec2(texel_size.x, 0.0))
+        + texture2D(tex, v_coords + vec2(0.0, texel_size.y))
+        + texture2D(tex, v_coords - vec2(0.0, texel_size.y));
+    color = clamp(color + (color - neighbors * 0.25) * sharpness, 0.0, 1.0);
+
+    // Keep the color premultiplied.
+    color.rgb = min(color.rgb, vec3(color.a));
+
+#if defined(NO_ALPHA)
+    color = vec4(color.rgb, 1.0);
+#endif
+
+    // Apply final alpha and tint.
+    color = color * alpha;
+
+#if defined(DEBUG_FLAGS)
+    if (tint == 1.0)
+        color = vec4(0.0, 0.2, 0.0, 0.2) + color * 0.8;
+#endif
+
+    gl_FragColor = color;
+}
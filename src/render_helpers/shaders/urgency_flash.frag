@@ -0,0 +1,60 @@
+precision highp float;
+
+#if defined(DEBUG_FLAGS)
+uniform float niri_tint;
+#endif
+
+uniform float niri_alpha;
+uniform float niri_scale;
+
+uniform vec2 niri_size;
+varying vec2 niri_v_coords;
+
+uniform vec4 flash_color;
+uniform float flash_width;
+
+uniform mat3 input_to_geo;
+uniform vec2 geo_size;
+uniform vec4 corner_radius;
+
+// Signed distance from the edge of the rounded window geometry, negative inside.
+float rounded_box_distance(vec2 coords, vec2 size, vec4 corner_radius) {
+    vec2 half_size = size * 0.5;
+    vec2 p = coords - half_size;
+
+    float radius;
+    if (p.x < 0.0 && p.y < 0.0) {
+        radius = corner_radius.x;
+    } else if (p.y < 0.0) {
+        radius = corner_radius.y;
+    } else if (p.x >= 0.0) {
+        radius = corner_radius.z;
+    } else {
+        radius = corner_radius.w;
+    }
+
+    vec2 q = abs(p) - half_size + radius;
+    return length(max(q, 0.0)) + min(max(q.x, q.y), 0.0) - radius;
+}
+
+void main() {
+    vec3 coords_geo = input_to_geo * vec3(niri_v_coords, 1.0);
+    float dist = rounded_box_distance(coords_geo.xy, geo_size, corner_radius);
+
+    // Only draw outside the window, anti-aliasing the inner edge.
+    float half_px = 0.5 / niri_scale;
+    float outside = smoothstep(-half_px, half_px, dist);
+
+    // Fade out away from the window edge.
+    float falloff = 1.0 - smoothstep(0.0, flash_width, dist);
+
+    vec4 color = flash_color * (outside * falloff * falloff);
+    color = color * niri_alpha;
+
+#if defined(DEBUG_FLAGS)
+    if (niri_tint == 1.0)
+        color = vec4(0.0, 0.2, 0.0, 0.2) + color * 0.8;
+#endif
+
+    gl_FragColor = color;
+}
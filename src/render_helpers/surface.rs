@@ -1,12 +1,85 @@
+use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
+use smithay::backend::renderer::element::Kind;
 use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
 use smithay::backend::renderer::utils::{import_surface, RendererSurfaceStateUserData};
-use smithay::backend::renderer::Renderer as _;
+use smithay::backend::renderer::{ImportAll, Renderer};
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
-use smithay::utils::{Logical, Point};
+use smithay::utils::{Logical, Physical, Point, Scale};
 use smithay::wayland::compositor::{with_surface_tree_downward, TraversalAction};
 
 use super::texture::TextureBuffer;
 use super::BakedBuffer;
+use crate::protocols::alpha_modifier::surface_alpha;
+
+/// Renders elements from a surface tree, like the Smithay function of the same name.
+///
+/// Additionally applies the alpha that clients request for each surface through the
+/// alpha-modifier protocol, on top of `alpha`.
+pub fn render_elements_from_surface_tree<R, E>(
+    renderer: &mut R,
+    surface: &WlSurface,
+    location: impl Into<Point<i32, Physical>>,
+    scale: impl Into<Scale<f64>>,
+    alpha: f32,
+    kind: Kind,
+) -> Vec<E>
+where
+    R: Renderer + ImportAll,
+    R::TextureId: Clone + 'static,
+    E: From<WaylandSurfaceRenderElement<R>>,
+{
+    let _span = tracy_client::span!("render_elements_from_surface_tree");
+
+    let location = location.into().to_f64();
+    let scale = scale.into();
+    let mut elements: Vec<E> = Vec::new();
+
+    with_surface_tree_downward(
+        surface,
+        location,
+        |_, states, location| {
+            let mut location = *location;
+            let data = states.data_map.get::<RendererSurfaceStateUserData>();
+
+            if let Some(data) = data {
+                let data = &*data.lock().unwrap();
+
+                if let Some(view) = data.view() {
+                    location += view.offset.to_f64().to_physical(scale);
+                    TraversalAction::DoChildren(location)
+                } else {
+                    TraversalAction::SkipChildren
+                }
+            } else {
+                TraversalAction::SkipChildren
+            }
+        },
+        |surface, states, location| {
+            let mut location = *location;
+            let data = states.data_map.get::<RendererSurfaceStateUserData>();
+
+            if let Some(data) = data {
+                let Some(view) = data.lock().unwrap().view() else {
+                    return;
+                };
+                location += view.offset.to_f64().to_physical(scale);
+
+                let alpha = alpha * surface_alpha(states);
+                match WaylandSurfaceRenderElement::from_surface(
+                    renderer, surface, states, location, alpha, kind,
+                ) {
+                    Ok(Some(elem)) => elements.push(elem.into()),
+                    // The surface has no buffer.
+                    Ok(None) => (),
+                    Err(err) => warn!("failed to import surface: {err:?}"),
+                }
+            }
+        },
+        |_, _, _| true,
+    );
+
+    elements
+}
 
 /// Renders elements from a surface tree as textures into `storage`.
 pub fn render_snapshot_from_surface_tree(
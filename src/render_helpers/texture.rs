@@ -114,6 +114,14 @@ impl<T: Texture> TextureBuffer<T> {
             .to_f64()
             .to_logical(self.scale, self.transform)
     }
+
+    /// Whether the opaque regions cover the whole texture.
+    pub fn is_opaque(&self) -> bool {
+        let rect = Rectangle::from_size(self.texture.size());
+        self.opaque_regions
+            .iter()
+            .any(|region| region.contains_rect(rect))
+    }
 }
 
 impl TextureBuffer<GlesTexture> {
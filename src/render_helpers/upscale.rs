@@ -0,0 +1,54 @@
+//! Rendering outputs below their native resolution.
+//!
+//! When an output keeps missing its frame deadline, its frame clock lowers the render scale. The
+//! output contents then go into a smaller offscreen texture, which is stretched back over the
+//! output with a sharpening filter to offset the blur. This keeps old integrated GPUs at the full
+//! refresh rate at the cost of some sharpness.
+
+use smithay::backend::renderer::element::RenderElement;
+use smithay::backend::renderer::gles::{GlesRenderer, Uniform};
+use smithay::backend::renderer::Texture as _;
+use smithay::utils::Scale;
+
+use super::offscreen::{OffscreenBuffer, OffscreenData, OffscreenRenderElement};
+use super::shaders::Shaders;
+
+/// Buffer for rendering an output at a reduced resolution.
+#[derive(Debug, Default)]
+pub struct Upscaler {
+    buffer: OffscreenBuffer,
+}
+
+impl Upscaler {
+    /// Renders `elements` at `render_scale` of the output resolution as a single element that
+    /// covers the whole output.
+    pub fn render(
+        &self,
+        renderer: &mut GlesRenderer,
+        output_scale: f64,
+        render_scale: f64,
+        sharpness: f32,
+        elements: &[impl RenderElement<GlesRenderer>],
+    ) -> anyhow::Result<(OffscreenRenderElement, OffscreenData)> {
+        let _span = tracy_client::span!("Upscaler::render");
+
+        let scale = Scale::from(output_scale * render_scale);
+        let (elem, _sync, data) = self.buffer.render(renderer, scale, elements)?;
+
+        let program = Shaders::get(renderer).sharpen.clone();
+        let elem = match program {
+            Some(program) if sharpness > 0. => {
+                let size = elem.texture().size();
+                let texel_size = [1. / size.w as f32, 1. / size.h as f32];
+                let uniforms = vec![
+                    Uniform::new("texel_size", texel_size),
+                    Uniform::new("sharpness", sharpness),
+                ];
+                elem.with_program(program, uniforms)
+            }
+            _ => elem,
+        };
+
+        Ok((elem, data))
+    }
+}
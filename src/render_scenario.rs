@@ -0,0 +1,460 @@
+//! Renders scripted scenes to PNG for golden-image tests.
+//!
+//! A scenario is a RON file describing an output and what's on it: windows as solid-color
+//! rectangles with optional borders, shadows and rounded corners, layer surfaces, and the cursor.
+//! It goes through the same render elements as the real compositor, so the resulting image can
+//! be compared against a golden one to catch rendering regressions.
+//!
+//! ```ron
+//! (
+//!     output: (width: 640, height: 360, scale: 1.5),
+//!     windows: [
+//!         (
+//!             x: 32, y: 32, width: 300, height: 200,
+//!             color: (0.8, 0.3, 0.3, 1.0),
+//!             corner_radius: 12,
+//!             border: Some((width: 4)),
+//!             shadow: Some(()),
+//!         ),
+//!     ],
+//!     cursor: Some((200, 150)),
+//! )
+//! ```
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use anyhow::{ensure, Context};
+use niri_config::{Color, CornerRadius, FloatOrInt, GradientInterpolation, ShadowOffset};
+use serde::Deserialize;
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::egl::{EGLContext, EGLDevice, EGLDisplay};
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::GlesRenderer;
+use smithay::utils::{Logical, Physical, Point, Rectangle, Scale, Size, Transform};
+
+use crate::cursor::CursorManager;
+use crate::layout::focus_ring::{FocusRing, FocusRingRenderElement};
+use crate::layout::shadow::Shadow;
+use crate::niri_render_elements;
+use crate::render_helpers::border::BorderRenderElement;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::shadow::ShadowRenderElement;
+use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::render_helpers::{render_to_vec, resources, shaders};
+use crate::utils::write_png_rgba8;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub output: ScenarioOutput,
+    #[serde(default = "default_background")]
+    pub background: [f32; 4],
+    /// Windows from bottom to top.
+    #[serde(default)]
+    pub windows: Vec<ScenarioWindow>,
+    /// Layer surfaces from bottom to top.
+    #[serde(default)]
+    pub layers: Vec<ScenarioLayer>,
+    /// Cursor hotspot position.
+    #[serde(default)]
+    pub cursor: Option<(f64, f64)>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioOutput {
+    pub width: u16,
+    pub height: u16,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioWindow {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub color: [f32; 4],
+    #[serde(default)]
+    pub active: bool,
+    #[serde(default)]
+    pub corner_radius: f32,
+    #[serde(default)]
+    pub border: Option<ScenarioBorder>,
+    #[serde(default)]
+    pub shadow: Option<ScenarioShadow>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioBorder {
+    pub width: f64,
+    #[serde(default = "default_active_border")]
+    pub active_color: [f32; 4],
+    #[serde(default = "default_inactive_border")]
+    pub inactive_color: [f32; 4],
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScenarioShadow {
+    pub softness: f64,
+    pub spread: f64,
+    pub offset: (f64, f64),
+    pub color: [f32; 4],
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioLayer {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub color: [f32; 4],
+    /// Whether the layer is on the top or overlay layer, rather than background or bottom.
+    #[serde(default)]
+    pub above_windows: bool,
+}
+
+niri_render_elements! {
+    ScenarioRenderElement => {
+        SolidColor = SolidColorRenderElement,
+        Border = BorderRenderElement,
+        FocusRing = FocusRingRenderElement,
+        Shadow = ShadowRenderElement,
+        Texture = PrimaryGpuTextureRenderElement,
+    }
+}
+
+impl Default for ScenarioShadow {
+    fn default() -> Self {
+        let config = niri_config::Shadow::default();
+        Self {
+            softness: config.softness.0,
+            spread: config.spread.0,
+            offset: (config.offset.x.0, config.offset.y.0),
+            color: config.color.to_array_unpremul(),
+        }
+    }
+}
+
+fn default_scale() -> f64 {
+    1.
+}
+
+fn default_background() -> [f32; 4] {
+    niri_config::DEFAULT_BACKGROUND_COLOR.to_array_unpremul()
+}
+
+fn default_active_border() -> [f32; 4] {
+    niri_config::FocusRing::default()
+        .active_color
+        .to_array_unpremul()
+}
+
+fn default_inactive_border() -> [f32; 4] {
+    niri_config::FocusRing::default()
+        .inactive_color
+        .to_array_unpremul()
+}
+
+/// Renders the scenario at `path` and writes the image next to it with a `.png` extension.
+pub fn run(path: &Path) -> anyhow::Result<()> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("error reading {path:?}"))?;
+    let scenario: Scenario = ron::from_str(&text).context("error parsing scenario")?;
+
+    let mut renderer = create_renderer().context("error creating renderer")?;
+    let (size, pixels) = render(&mut renderer, &scenario)?;
+
+    let out = path.with_extension("png");
+    let file = File::create(&out).with_context(|| format!("error creating {out:?}"))?;
+    write_png_rgba8(BufWriter::new(file), size.w as u32, size.h as u32, &pixels)
+        .context("error encoding PNG")?;
+
+    info!("wrote {out:?}");
+    Ok(())
+}
+
+/// Creates a renderer without any outputs, preferring software rendering for stable results.
+fn create_renderer() -> anyhow::Result<GlesRenderer> {
+    let mut devices: Vec<_> = EGLDevice::enumerate()
+        .context("error enumerating EGL devices")?
+        .collect();
+    devices.sort_by_key(|device| !device.is_software());
+    let device = devices.into_iter().next().context("no EGL devices")?;
+
+    let display = unsafe { EGLDisplay::new(device)? };
+    let context = EGLContext::new(&display)?;
+    let mut renderer = unsafe { GlesRenderer::new(context)? };
+
+    resources::init(&mut renderer);
+    shaders::init(&mut renderer);
+
+    Ok(renderer)
+}
+
+pub fn render(
+    renderer: &mut GlesRenderer,
+    scenario: &Scenario,
+) -> anyhow::Result<(Size<i32, Physical>, Vec<u8>)> {
+    let _span = tracy_client::span!("render_scenario::render");
+
+    let ScenarioOutput {
+        width,
+        height,
+        scale,
+    } = scenario.output;
+    ensure!(scale > 0., "output scale must be positive");
+
+    let output_size = Size::<_, Logical>::from((f64::from(width), f64::from(height)));
+    let size = output_size.to_physical_precise_round(scale);
+
+    let color = |color: [f32; 4]| Color::from_array_unpremul(color).to_array_premul();
+
+    // Elements are collected front to back, like in the compositor.
+    let mut elements: Vec<ScenarioRenderElement> = Vec::new();
+
+    if let Some(pos) = scenario.cursor {
+        elements.extend(render_cursor(renderer, pos.into(), scale)?);
+    }
+
+    let push_layers = |elements: &mut Vec<ScenarioRenderElement>, above_windows: bool| {
+        for layer in scenario.layers.iter().rev() {
+            if layer.above_windows != above_windows {
+                continue;
+            }
+
+            let buffer = SolidColorBuffer::new((layer.width, layer.height), color(layer.color));
+            let elem = SolidColorRenderElement::from_buffer(
+                &buffer,
+                (layer.x, layer.y),
+                1.,
+                Kind::Unspecified,
+            );
+            elements.push(elem.into());
+        }
+    };
+
+    push_layers(&mut elements, true);
+    for window in scenario.windows.iter().rev() {
+        render_window(renderer, window, output_size, scale, &mut elements);
+    }
+    push_layers(&mut elements, false);
+
+    let background = SolidColorBuffer::new(output_size, color(scenario.background));
+    elements.push(
+        SolidColorRenderElement::from_buffer(&background, (0., 0.), 1., Kind::Unspecified).into(),
+    );
+
+    let pixels = render_to_vec(
+        renderer,
+        size,
+        Scale::from(scale),
+        Transform::Normal,
+        Fourcc::Abgr8888,
+        elements.into_iter().rev(),
+    )?;
+
+    Ok((size, pixels))
+}
+
+fn render_window(
+    renderer: &mut GlesRenderer,
+    window: &ScenarioWindow,
+    output_size: Size<f64, Logical>,
+    scale: f64,
+    elements: &mut Vec<ScenarioRenderElement>,
+) {
+    let loc = Point::from((window.x, window.y));
+    let size = Size::from((window.width, window.height));
+
+    let border_width = window.border.as_ref().map_or(0., |border| border.width);
+    let radius = CornerRadius::from(window.corner_radius);
+    let tile_radius = radius.expanded_by(border_width as f32);
+    let tile_loc = loc - Point::from((border_width, border_width));
+    let tile_size = size + Size::from((border_width, border_width)).upscale(2.);
+
+    let color = Color::from_array_unpremul(window.color);
+    if radius != CornerRadius::default() && BorderRenderElement::has_shader(renderer) {
+        let elem = BorderRenderElement::new(
+            size,
+            Rectangle::from_size(size),
+            GradientInterpolation::default(),
+            color,
+            color,
+            0.,
+            Rectangle::from_size(size),
+            0.,
+            radius,
+            scale as f32,
+            1.,
+        )
+        .with_location(loc);
+        elements.push(elem.into());
+    } else {
+        let buffer = SolidColorBuffer::new(size, color.to_array_premul());
+        let elem = SolidColorRenderElement::from_buffer(&buffer, loc, 1., Kind::Unspecified);
+        elements.push(elem.into());
+    }
+
+    if let Some(border) = &window.border {
+        let mut ring = FocusRing::new(niri_config::FocusRing {
+            off: false,
+            width: FloatOrInt(border.width),
+            active_color: Color::from_array_unpremul(border.active_color),
+            inactive_color: Color::from_array_unpremul(border.inactive_color),
+            ..Default::default()
+        });
+        ring.update_render_elements(
+            size,
+            window.active,
+            true,
+            false,
+            Rectangle::new(tile_loc.upscale(-1.), output_size),
+            tile_radius,
+            scale,
+            1.,
+        );
+        elements.extend(ring.render(renderer, loc).map(Into::into));
+    }
+
+    if let Some(shadow) = &window.shadow {
+        let mut elem = Shadow::new(niri_config::Shadow {
+            on: true,
+            offset: ShadowOffset {
+                x: FloatOrInt(shadow.offset.0),
+                y: FloatOrInt(shadow.offset.1),
+            },
+            softness: FloatOrInt(shadow.softness),
+            spread: FloatOrInt(shadow.spread),
+            color: Color::from_array_unpremul(shadow.color),
+            ..Default::default()
+        });
+        elem.update_render_elements(tile_size, window.active, tile_radius, scale, 1.);
+        elements.extend(elem.render(renderer, tile_loc).map(Into::into));
+    }
+}
+
+fn render_cursor(
+    renderer: &mut GlesRenderer,
+    pos: Point<f64, Logical>,
+    scale: f64,
+) -> anyhow::Result<Option<ScenarioRenderElement>> {
+    // Always use the built-in cursor so that the result doesn't depend on the installed theme.
+    let cursor = CursorManager::fallback_cursor();
+    let Some(image) = cursor.frames().first() else {
+        return Ok(None);
+    };
+
+    let buffer_scale = f64::from(image.width) / f64::from(image.size);
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &image.pixels_rgba,
+        Fourcc::Argb8888,
+        (image.width as i32, image.height as i32),
+        false,
+        buffer_scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    let hotspot =
+        Point::from((f64::from(image.xhot), f64::from(image.yhot))).downscale(buffer_scale);
+    let loc = (pos - hotspot)
+        .to_physical_precise_round(scale)
+        .to_logical(scale);
+
+    let elem = TextureRenderElement::from_texture_buffer(buffer, loc, 1., None, None, Kind::Cursor);
+    Ok(Some(PrimaryGpuTextureRenderElement(elem).into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use xshell::Shell;
+
+    use super::*;
+
+    #[test]
+    fn parse_scenario() {
+        let scenario: Scenario = ron::from_str(
+            r#"(
+                output: (width: 640, height: 360, scale: 1.5),
+                windows: [
+                    (
+                        x: 32, y: 32, width: 300, height: 200,
+                        color: (0.8, 0.3, 0.3, 1.0),
+                        corner_radius: 12,
+                        border: Some((width: 4)),
+                        shadow: Some(()),
+                    ),
+                ],
+                layers: [
+                    (x: 0, y: 0, width: 640, height: 24, color: (0, 0, 0, 1), above_windows: true),
+                ],
+                cursor: Some((200, 150)),
+            )"#,
+        )
+        .unwrap();
+
+        assert_eq!(scenario.output.scale, 1.5);
+        assert_eq!(scenario.windows.len(), 1);
+        assert_eq!(scenario.windows[0].border.as_ref().unwrap().width, 4.);
+        assert!(scenario.windows[0].shadow.is_some());
+        assert!(scenario.layers[0].above_windows);
+        assert_eq!(scenario.cursor, Some((200., 150.)));
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let res = ron::from_str::<Scenario>("(output: (width: 10, height: 10), wallpaper: 1)");
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn run_writes_png() {
+        // Rendering needs an EGL device; Mesa provides a software one where there's no GPU.
+        if let Err(err) = create_renderer() {
+            eprintln!("skipping, no renderer: {err:?}");
+            return;
+        }
+
+        let sh = Shell::new().unwrap();
+        let temp_dir = sh.create_temp_dir().unwrap();
+        let path = temp_dir.path().join("scene.ron");
+        fs::write(
+            &path,
+            "(
+                output: (width: 32, height: 32),
+                background: (0, 0, 1, 1),
+                windows: [(x: 0, y: 0, width: 16, height: 8, color: (1, 0, 0, 1))],
+            )",
+        )
+        .unwrap();
+
+        run(&path).unwrap();
+
+        let decoder = png::Decoder::new(File::open(path.with_extension("png")).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (32, 32));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+
+        let pixel = |x: usize, y: usize| {
+            let offset = (y * 32 + x) * 4;
+            &pixels[offset..offset + 4]
+        };
+        // The window is in the top-left corner, which also checks that the image isn't flipped.
+        assert_eq!(pixel(4, 4), [255, 0, 0, 255]);
+        assert_eq!(pixel(4, 28), [0, 0, 255, 255]);
+        assert_eq!(pixel(28, 4), [0, 0, 255, 255]);
+    }
+}
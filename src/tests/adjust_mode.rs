@@ -0,0 +1,59 @@
+use client::ClientId;
+use niri_config::Action;
+use wayland_client::protocol::wl_surface::WlSurface;
+
+use super::*;
+
+// Sets up a fixture with one output and two tiled windows, the second one focused.
+fn set_up() -> (Fixture, ClientId, WlSurface, WlSurface) {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+
+    let id = f.add_client();
+    let mut surfaces = Vec::new();
+    for _ in 0..2 {
+        let window = f.client(id).create_window();
+        let surface = window.surface.clone();
+        window.commit();
+        f.roundtrip(id);
+
+        let window = f.client(id).window(&surface);
+        window.attach_new_buffer();
+        window.ack_last_and_commit();
+        f.double_roundtrip(id);
+
+        surfaces.push(surface);
+    }
+
+    let second = surfaces.pop().unwrap();
+    let first = surfaces.pop().unwrap();
+    (f, id, first, second)
+}
+
+#[test]
+fn focus_change_closes_adjust_mode() {
+    let (mut f, id, _first, _second) = set_up();
+
+    f.niri_state().do_action(Action::EnterAdjustMode);
+    f.double_roundtrip(id);
+    assert!(f.niri().adjust_mode.is_open());
+
+    f.niri_state().do_action(Action::FocusColumnLeft);
+    f.double_roundtrip(id);
+    assert!(!f.niri().adjust_mode.is_open());
+}
+
+#[test]
+fn unmap_closes_adjust_mode() {
+    let (mut f, id, _first, second) = set_up();
+
+    f.niri_state().do_action(Action::EnterAdjustMode);
+    f.double_roundtrip(id);
+    assert!(f.niri().adjust_mode.is_open());
+
+    let window = f.client(id).window(&second);
+    window.surface.attach(None, 0, 0);
+    window.commit();
+    f.double_roundtrip(id);
+    assert!(!f.niri().adjust_mode.is_open());
+}
@@ -251,6 +251,12 @@ impl Window {
         self.xdg_toplevel.set_title(title.to_owned());
     }
 
+    pub fn request_frame(&self) -> Arc<SyncData> {
+        let data = Arc::new(SyncData::default());
+        self.surface.frame(&self.qh, data.clone());
+        data
+    }
+
     pub fn recent_configures(&mut self) -> impl Iterator<Item = &Configure> {
         let start = self.configures_looked_at;
         self.configures_looked_at = self.configures_received.len();
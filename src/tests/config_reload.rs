@@ -0,0 +1,67 @@
+use niri_config::{Config, Modifiers};
+use xshell::Shell;
+
+use super::*;
+
+fn parse(text: &str) -> Config {
+    Config::parse("test.kdl", text).unwrap()
+}
+
+#[test]
+fn reload_updates_mods_with_mouse_and_wheel_binds() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+
+    let binds = r#"
+        binds {
+            Mod+Ctrl+MouseMiddle { close-window; }
+            Mod+Shift+WheelScrollDown { focus-column-right; }
+        }
+    "#;
+    f.niri_state().reload_config(Ok(parse(binds)));
+
+    let niri = f.niri();
+    assert!(niri
+        .mods_with_mouse_binds
+        .contains(&(Modifiers::SUPER | Modifiers::CTRL)));
+    assert!(niri
+        .mods_with_wheel_binds
+        .contains(&(Modifiers::SUPER | Modifiers::SHIFT)));
+
+    // Changing only the mod key changes the modifiers that the binds need.
+    let config = parse(&format!("input {{ mod-key \"Alt\"; }}\n{binds}"));
+    f.niri_state().reload_config(Ok(config));
+
+    let niri = f.niri();
+    assert!(niri
+        .mods_with_mouse_binds
+        .contains(&(Modifiers::ALT | Modifiers::CTRL)));
+    assert!(!niri
+        .mods_with_mouse_binds
+        .contains(&(Modifiers::SUPER | Modifiers::CTRL)));
+    assert!(niri
+        .mods_with_wheel_binds
+        .contains(&(Modifiers::ALT | Modifiers::SHIFT)));
+}
+
+#[test]
+fn load_config_file_switches_config() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+
+    let sh = Shell::new().unwrap();
+    let temp_dir = sh.create_temp_dir().unwrap();
+    let good = temp_dir.path().join("good.kdl");
+    let bad = temp_dir.path().join("bad.kdl");
+    sh.write_file(&good, "prefer-no-csd").unwrap();
+    sh.write_file(&bad, "prefer-no-csd {").unwrap();
+
+    f.niri_state().load_config_file(good.to_str().unwrap());
+    assert!(f.niri().config.borrow().prefer_no_csd);
+    assert!(f.niri().config_watcher.is_some());
+
+    // A broken file keeps the current config, but is watched so that fixing it loads it.
+    f.niri_state().load_config_file(bad.to_str().unwrap());
+    assert!(f.niri().config.borrow().prefer_no_csd);
+    assert!(f.niri().config_watcher.is_some());
+}
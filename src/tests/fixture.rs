@@ -94,6 +94,7 @@ impl Fixture {
             client: sock1,
             restricted: false,
             credentials_unknown: false,
+            sandbox: None,
         });
 
         let client = Client::new(sock2);
@@ -0,0 +1,48 @@
+use std::sync::atomic::Ordering;
+
+use niri_config::Config;
+
+use super::*;
+
+#[test]
+fn max_render_fps_withholds_frame_callbacks() {
+    let config = r##"
+window-rule {
+    max-render-fps 1
+}
+"##;
+    let config = Config::parse("test.kdl", config).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+    let output = f.niri_output(1);
+
+    let id = f.add_client();
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    // Commit to the Activated state configure.
+    f.client(id).window(&surface).ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    // Mapping sent the first frame callback, so within a second the window doesn't get another
+    // one.
+    let window = f.client(id).window(&surface);
+    let frame = window.request_frame();
+    window.commit();
+    f.double_roundtrip(id);
+
+    f.niri().send_frame_callbacks(&output);
+    f.double_roundtrip(id);
+    assert!(!frame.done.load(Ordering::Relaxed));
+
+    // A redraw is scheduled for when the window may get its frame callback.
+    let state = &f.niri().output_state[&output];
+    assert!(state.throttled_frame_timer.is_some());
+}
@@ -0,0 +1,32 @@
+use niri_config::Config;
+use niri_ipc::InjectedInput;
+
+use super::*;
+
+#[test]
+fn inject_input_requires_config_flag() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+
+    let res = f
+        .niri_state()
+        .inject_input(InjectedInput::KeyPress { keycode: 30 });
+    assert!(res.is_err());
+}
+
+#[test]
+fn inject_key_rejects_out_of_range_keycode() {
+    let config = Config::parse("test.kdl", "debug { ipc-input-injection; }").unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+    let mut inject = |input| f.niri_state().inject_input(input);
+
+    assert_eq!(inject(InjectedInput::KeyPress { keycode: 30 }), Ok(()));
+    assert_eq!(inject(InjectedInput::KeyRelease { keycode: 30 }), Ok(()));
+
+    // Adding the xkb offset would overflow, so this must fail instead of panicking.
+    let keycode = u32::MAX;
+    let err = inject(InjectedInput::KeyPress { keycode }).unwrap_err();
+    assert_eq!(err, format!("key code {keycode} is out of range"));
+    assert!(inject(InjectedInput::KeyRelease { keycode }).is_err());
+}
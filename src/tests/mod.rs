@@ -4,7 +4,14 @@ mod client;
 mod fixture;
 mod server;
 
+mod adjust_mode;
+mod config_reload;
 mod floating;
+mod frame_callbacks;
 mod fullscreen;
+mod input_injection;
+mod output;
+mod pointer;
+mod session;
 mod transactions;
 mod window_opening;
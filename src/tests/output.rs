@@ -0,0 +1,24 @@
+use niri_config::Config;
+
+use super::*;
+
+#[test]
+fn toggle_output_grayscale() {
+    let config = Config::parse("test.kdl", "animations { off; }").unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+    f.add_output(2, (1280, 720));
+    let output = f.niri_output(1);
+    let other = f.niri_output(2);
+
+    f.niri_state().toggle_output_grayscale("headless-1");
+    assert_eq!(f.niri().output_saturation(&output), 0.);
+    // Only the named output turns gray.
+    assert_eq!(f.niri().output_saturation(&other), 1.);
+
+    f.niri_state().toggle_output_grayscale("headless-1");
+    assert_eq!(f.niri().output_saturation(&output), 1.);
+
+    // Unknown outputs are ignored.
+    f.niri_state().toggle_output_grayscale("headless-3");
+}
@@ -0,0 +1,118 @@
+use niri_config::Config;
+use niri_ipc::WindowWarpTarget;
+use smithay::input::pointer::RelativeMotionEvent;
+use smithay::utils::{Logical, Point};
+
+use super::*;
+use crate::utils::center_f64;
+
+fn pointer_location(f: &mut Fixture) -> Point<f64, Logical> {
+    f.niri().seat.get_pointer().unwrap().current_location()
+}
+
+#[test]
+fn warp_pointer_to_region() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+
+    f.niri_state().warp_pointer_to_region(1);
+    assert_eq!(pointer_location(&mut f), Point::from((320., 180.)));
+
+    f.niri_state().warp_pointer_to_region(5);
+    assert_eq!(pointer_location(&mut f), Point::from((960., 540.)));
+
+    f.niri_state().warp_pointer_to_region(9);
+    assert_eq!(pointer_location(&mut f), Point::from((1600., 900.)));
+
+    // Out-of-range regions leave the pointer alone.
+    f.niri_state().warp_pointer_to_region(10);
+    assert_eq!(pointer_location(&mut f), Point::from((1600., 900.)));
+}
+
+#[test]
+fn warp_pointer_to_window() {
+    // Keep the tile still while comparing positions.
+    let config = Config::parse("test.kdl", "animations { off; }").unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+    let warp = |f: &mut Fixture, target| f.niri_state().warp_pointer_to_window(target);
+
+    // Nothing to warp to without a window.
+    assert!(!warp(&mut f, WindowWarpTarget::Center));
+
+    let id = f.add_client();
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.set_size(100, 100);
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    let output = f.niri_output(1);
+    let niri = f.niri();
+    let rect = niri
+        .layout
+        .monitor_for_output(&output)
+        .unwrap()
+        .active_tile_visual_rectangle()
+        .unwrap();
+    let center = center_f64(rect);
+
+    assert!(warp(&mut f, WindowWarpTarget::Center));
+    assert_eq!(pointer_location(&mut f), center);
+
+    // Edges are inset so that the pointer lands inside the window rather than on its border.
+    assert!(warp(&mut f, WindowWarpTarget::Left));
+    assert_eq!(
+        pointer_location(&mut f),
+        Point::from((rect.loc.x + 16., center.y))
+    );
+
+    assert!(warp(&mut f, WindowWarpTarget::Bottom));
+    assert_eq!(
+        pointer_location(&mut f),
+        Point::from((center.x, rect.loc.y + rect.size.h - 16.))
+    );
+}
+
+#[test]
+fn batch_relative_motion() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+
+    let id = f.add_client();
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    let (_, mapped) = f.niri().layout.windows().next().unwrap();
+    let wl_surface = mapped.toplevel().wl_surface().clone();
+    let focus = (wl_surface, Point::from((0., 0.)));
+    let motion = |x, y, utime| RelativeMotionEvent {
+        delta: Point::from((x, y)),
+        delta_unaccel: Point::from((x, y)),
+        utime,
+    };
+
+    // Motion within a frame adds up.
+    let state = f.niri_state();
+    state.batch_relative_motion(focus.clone(), motion(1., 2., 10));
+    state.batch_relative_motion(focus, motion(3., 4., 20));
+    let batch = &f.niri().relative_motion_batch;
+    assert_eq!(batch.pending_delta(), Some(Point::from((4., 6.))));
+
+    // Flushing sends it right away and cancels the frame timer.
+    f.niri_state().flush_relative_motion();
+    assert_eq!(f.niri().relative_motion_batch.pending_delta(), None);
+    f.double_roundtrip(id);
+}
@@ -0,0 +1,36 @@
+use std::sync::atomic::Ordering;
+
+use super::*;
+
+#[test]
+fn paused_session_withholds_frame_callbacks() {
+    // Without outputs, only the fallback timer sends frame callbacks.
+    let mut f = Fixture::new();
+
+    let id = f.add_client();
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    f.niri().is_session_paused = true;
+    let window = f.client(id).window(&surface);
+    let frame = window.request_frame();
+    window.commit();
+    f.double_roundtrip(id);
+
+    f.niri().send_frame_callbacks_on_fallback_timer();
+    f.double_roundtrip(id);
+    assert!(!frame.done.load(Ordering::Relaxed));
+
+    // Resuming lets the callback through again.
+    f.niri().is_session_paused = false;
+    f.niri().send_frame_callbacks_on_fallback_timer();
+    f.double_roundtrip(id);
+    assert!(frame.done.load(Ordering::Relaxed));
+}
@@ -3,6 +3,7 @@ use std::fmt::{self, Write as _};
 use insta::assert_snapshot;
 use niri_config::Config;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use wayland_client::protocol::wl_surface::WlSurface;
 
 use super::*;
 use crate::layout::LayoutElement;
@@ -95,6 +96,64 @@ fn dont_ack_initial_configure() {
     );
 }
 
+#[test]
+fn at_startup_matches_by_when_the_window_opened() {
+    let config = r##"
+window-rule {
+    match at-startup=true
+    opacity 0.5
+}
+"##;
+    let config = Config::parse("test.kdl", config).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+
+    let id = f.add_client();
+    let create_window = |f: &mut Fixture, title: &str| {
+        let window = f.client(id).create_window();
+        let surface = window.surface.clone();
+        window.set_title(title);
+        window.commit();
+        f.roundtrip(id);
+        surface
+    };
+    let map_window = |f: &mut Fixture, surface: &WlSurface| {
+        let window = f.client(id).window(surface);
+        window.attach_new_buffer();
+        window.ack_last_and_commit();
+        f.double_roundtrip(id);
+    };
+    let opacity = |f: &mut Fixture, title: &str| {
+        let (_, mapped) = f
+            .niri()
+            .layout
+            .windows()
+            .find(|(_, win)| {
+                with_toplevel_role(win.toplevel(), |role| role.title.as_deref() == Some(title))
+            })
+            .unwrap();
+        mapped.rules().opacity
+    };
+
+    let surface = create_window(&mut f, "during");
+    map_window(&mut f, &surface);
+    assert_eq!(opacity(&mut f, "during"), Some(0.5));
+
+    // Opened during startup, but mapped after it ends.
+    let late = create_window(&mut f, "late");
+
+    f.niri().is_at_startup = false;
+    f.niri().recompute_window_rules();
+
+    let surface = create_window(&mut f, "after");
+    map_window(&mut f, &surface);
+    map_window(&mut f, &late);
+
+    assert_eq!(opacity(&mut f, "during"), Some(0.5));
+    assert_eq!(opacity(&mut f, "late"), Some(0.5));
+    assert_eq!(opacity(&mut f, "after"), None);
+}
+
 #[derive(Clone, Copy)]
 enum WantFullscreen {
     No,
@@ -0,0 +1,152 @@
+use pango::Alignment;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+use smithay::utils::Point;
+
+use crate::layout::{Layout, LayoutElement as _};
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::ui::panel;
+use crate::ui::texture_cache::TextureCache;
+use crate::utils::output_size;
+use crate::window::Mapped;
+
+const PADDING: i32 = 8;
+const MARGIN: i32 = 32;
+
+/// Modal state for adjusting the focused window with the keyboard.
+///
+/// While open, the OSD at the bottom of the active output shows the current window dimensions.
+pub struct AdjustMode {
+    is_open: bool,
+    /// OSD textures, keyed by their text.
+    buffers: TextureCache<String>,
+}
+
+impl AdjustMode {
+    pub fn new() -> Self {
+        Self {
+            is_open: false,
+            buffers: TextureCache::new(),
+        }
+    }
+
+    pub fn open(&mut self) -> bool {
+        if !self.is_open {
+            self.is_open = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn close(&mut self) -> bool {
+        if self.is_open {
+            self.is_open = false;
+            self.buffers.clear();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    pub fn render_output<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+        layout: &Layout<Mapped>,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        if !self.is_open {
+            return None;
+        }
+
+        if layout.active_output() != Some(output) {
+            return None;
+        }
+
+        let text = osd_text(layout)?;
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let buffer = self.buffers.get_or_render(output, text.clone(), |scale| {
+            render(renderer.as_gles_renderer(), &text, scale)
+                .map_err(|err| warn!("error rendering adjust mode OSD: {err:?}"))
+                .ok()
+        })?;
+
+        let size = buffer.logical_size();
+        let x = (output_size.w - size.w) / 2.;
+        let y = output_size.h - size.h - f64::from(MARGIN);
+        let location = Point::from((x, y))
+            .to_physical_precise_round(scale)
+            .to_logical(scale);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+impl Default for AdjustMode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn osd_text(layout: &Layout<Mapped>) -> Option<String> {
+    let ws = layout.active_workspace()?;
+    let window = ws.active_window()?;
+    let size = window.size();
+
+    let text = if ws.floating_is_active() {
+        let rect = ws.active_tile_visual_rectangle()?;
+        let pos = rect.loc - ws.working_area().loc;
+        format!(
+            "<b>{} × {}</b> at {}, {}\n\
+             <span alpha=\"60%\">Arrows: move · Shift+Arrows: resize · Enter: done</span>",
+            size.w,
+            size.h,
+            pos.x.round(),
+            pos.y.round(),
+        )
+    } else {
+        format!(
+            "<b>{} × {}</b>\n\
+             <span alpha=\"60%\">Left/Right: width · Up/Down: height · Enter: done</span>",
+            size.w, size.h,
+        )
+    };
+
+    Some(text)
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    text: &str,
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("adjust_mode::render");
+
+    panel::render_text(
+        renderer,
+        text,
+        Alignment::Center,
+        PADDING,
+        panel::INFO,
+        scale,
+    )
+}
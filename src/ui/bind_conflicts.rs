@@ -0,0 +1,189 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use niri_config::{Config, ModKey};
+use pango::Alignment;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+
+use crate::input::bind_conflicts::{find_bind_conflicts, BindConflict};
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::ui::panel;
+use crate::ui::texture_cache::TextureCache;
+use crate::utils::{output_size, to_physical_precise_round};
+
+const PADDING: i32 = 8;
+const LINE_INTERVAL: i32 = 2;
+
+pub struct BindConflictsOverlay {
+    is_open: bool,
+    config: Rc<RefCell<Config>>,
+    mod_key: ModKey,
+    buffers: TextureCache,
+}
+
+impl BindConflictsOverlay {
+    pub fn new(config: Rc<RefCell<Config>>, mod_key: ModKey) -> Self {
+        Self {
+            is_open: false,
+            config,
+            mod_key,
+            buffers: TextureCache::new(),
+        }
+    }
+
+    pub fn show(&mut self) -> bool {
+        if !self.is_open {
+            self.is_open = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn hide(&mut self) -> bool {
+        if self.is_open {
+            self.is_open = false;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    /// Drops the rendered contents, to be called when the binds change.
+    pub fn on_config_updated(&mut self, mod_key: ModKey) {
+        self.mod_key = mod_key;
+        self.buffers.clear();
+    }
+
+    pub fn render_output<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        if !self.is_open {
+            return None;
+        }
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let buffer = self.buffers.get_or_render(output, (), |scale| {
+            let config = self.config.borrow();
+            let conflicts = find_bind_conflicts(
+                &config.binds,
+                self.mod_key,
+                config.input.disable_power_key_handling,
+            );
+            render(renderer.as_gles_renderer(), &conflicts, scale)
+                .map_err(|err| warn!("error rendering bind conflicts overlay: {err:?}"))
+                .ok()
+        })?;
+
+        let size = buffer.logical_size();
+        let location = (output_size.to_point() - size.to_point()).downscale(2.);
+        let mut location = location.to_physical_precise_round(scale).to_logical(scale);
+        location.x = f64::max(0., location.x);
+        location.y = f64::max(0., location.y);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    conflicts: &[BindConflict],
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("bind_conflicts::render");
+
+    let padding: i32 = to_physical_precise_round(scale, PADDING);
+    let line_interval: i32 = to_physical_precise_round(scale, LINE_INTERVAL);
+
+    let font = panel::font(scale);
+
+    let title = "<b>Bind Conflicts</b>";
+    let mut lines = Vec::new();
+    if conflicts.is_empty() {
+        lines.push(String::from("No conflicting binds found."));
+    } else {
+        for conflict in conflicts {
+            lines.push(markup_escape(&conflict.describe()));
+        }
+    }
+    lines.push(String::from(
+        "<span alpha=\"60%\">Press any key to close.</span>",
+    ));
+
+    let layout = panel::measuring_layout(&font, Alignment::Left)?;
+    let layout_size = |markup: &str| {
+        layout.set_markup(markup);
+        layout.pixel_size()
+    };
+
+    let title_size = layout_size(title);
+    let line_sizes: Vec<_> = lines.iter().map(|line| layout_size(line)).collect();
+
+    let width = line_sizes
+        .iter()
+        .chain([&title_size])
+        .map(|(w, _)| *w)
+        .max()
+        .unwrap_or(0);
+    let height = line_sizes
+        .iter()
+        .map(|(_, h)| *h + line_interval)
+        .sum::<i32>()
+        + title_size.1
+        + padding;
+
+    let border_color = if conflicts.is_empty() {
+        panel::INFO
+    } else {
+        panel::ERROR
+    };
+
+    panel::render(
+        renderer,
+        width + padding * 2,
+        height + padding * 2,
+        border_color,
+        scale,
+        |cr| {
+            let layout = panel::create_layout(cr, &font, Alignment::Left);
+
+            let mut y = padding;
+            layout.set_markup(title);
+            panel::show_layout(cr, &layout, padding, y);
+            y += title_size.1 + padding;
+
+            for (line, (_, h)) in lines.iter().zip(&line_sizes) {
+                layout.set_markup(line);
+                panel::show_layout(cr, &layout, padding, y);
+                y += h + line_interval;
+            }
+
+            Ok(())
+        },
+    )
+}
+
+fn markup_escape(text: &str) -> String {
+    pango::glib::markup_escape_text(text).to_string()
+}
@@ -0,0 +1,275 @@
+use std::env;
+use std::ffi::OsString;
+
+use niri_config::ConfigErrorDetail;
+use pango::Alignment;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::ui::panel;
+use crate::ui::texture_cache::TextureCache;
+use crate::utils::output_size;
+use crate::utils::spawning::{find_executable, spawn};
+
+const PADDING: i32 = 8;
+const MARGIN: i32 = 8;
+
+/// Notification shown at the top of every output while the config fails to load.
+///
+/// Stays until the config loads successfully, or until the user clicks it, which opens the config
+/// in an editor at the offending line.
+///
+/// `$VISUAL` and `$EDITOR` are usually terminal editors, and niri has no terminal of its own, so
+/// they run inside `$TERMINAL` or `xdg-terminal-exec`. Without an editor or a terminal, the config
+/// opens with `xdg-open` instead.
+pub struct ConfigErrorNotification {
+    detail: Option<ConfigErrorDetail>,
+    buffers: TextureCache,
+}
+
+impl ConfigErrorNotification {
+    pub fn new() -> Self {
+        Self {
+            detail: None,
+            buffers: TextureCache::new(),
+        }
+    }
+
+    pub fn show(&mut self, detail: ConfigErrorDetail) {
+        self.detail = Some(detail);
+        self.buffers.clear();
+    }
+
+    pub fn hide(&mut self) -> bool {
+        self.buffers.clear();
+        self.detail.take().is_some()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.detail.is_some()
+    }
+
+    /// Opens the erroneous config in an editor and hides the notification.
+    ///
+    /// Returns `false` if the notification wasn't shown.
+    pub fn open_in_editor(&mut self) -> bool {
+        let Some(detail) = &self.detail else {
+            return false;
+        };
+
+        let editor = env::var_os("VISUAL").or_else(|| env::var_os("EDITOR"));
+        spawn(editor_command(detail, editor, terminal_command()), None);
+
+        self.hide()
+    }
+
+    /// Returns whether the point, relative to the output, is over the notification.
+    pub fn contains(&self, output: &Output, pos: Point<f64, Logical>) -> bool {
+        let Some(buffer) = self.buffers.get(output) else {
+            return false;
+        };
+
+        let rect = Rectangle::new(location(output, &buffer), buffer.logical_size());
+        rect.contains(pos)
+    }
+
+    pub fn render_output<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let detail = self.detail.as_ref()?;
+
+        let buffer = self.buffers.get_or_render(output, (), |scale| {
+            render(renderer.as_gles_renderer(), detail, scale)
+                .map_err(|err| warn!("error rendering config error notification: {err:?}"))
+                .ok()
+        })?;
+
+        let location = location(output, &buffer);
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+impl Default for ConfigErrorNotification {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn location(output: &Output, buffer: &TextureBuffer<GlesTexture>) -> Point<f64, Logical> {
+    let scale = output.current_scale().fractional_scale();
+    let output_size = output_size(output);
+    let size = buffer.logical_size();
+
+    let x = (output_size.w - size.w) / 2.;
+    let y = f64::from(MARGIN);
+    Point::from((x, y))
+        .to_physical_precise_round(scale)
+        .to_logical(scale)
+}
+
+/// Returns the command prefix that runs a command in a new terminal window, if there's a terminal.
+fn terminal_command() -> Option<Vec<OsString>> {
+    // Most terminals take the command to run after -e.
+    if let Some(terminal) = env::var_os("TERMINAL").and_then(split_command) {
+        let mut command = terminal;
+        command.push(OsString::from("-e"));
+        return Some(command);
+    }
+
+    find_executable("xdg-terminal-exec").map(|_| vec![OsString::from("xdg-terminal-exec")])
+}
+
+/// Builds the command that opens the config at the error location.
+///
+/// Most editors understand `+LINE` before the file name. The editor runs in `terminal`. Without an
+/// editor or a terminal we fall back to `xdg-open`, which can't jump to the line.
+fn editor_command(
+    detail: &ConfigErrorDetail,
+    editor: Option<OsString>,
+    terminal: Option<Vec<OsString>>,
+) -> Vec<OsString> {
+    let (Some(editor), Some(mut command)) = (editor.and_then(split_command), terminal) else {
+        return vec![OsString::from("xdg-open"), detail.path.clone().into()];
+    };
+
+    command.extend(editor);
+    if let Some((line, _)) = detail.position {
+        command.push(format!("+{line}").into());
+    }
+    command.push(detail.path.clone().into());
+    command
+}
+
+/// Splits a command from an environment variable into arguments.
+fn split_command(command: OsString) -> Option<Vec<OsString>> {
+    let command = command.into_string().ok()?;
+    let command: Vec<OsString> = command.split_whitespace().map(OsString::from).collect();
+    (!command.is_empty()).then_some(command)
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    detail: &ConfigErrorDetail,
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("config_error_notification::render");
+
+    let path = detail.path.to_string_lossy();
+    let location = match detail.position {
+        Some((line, column)) => format!("{path}:{line}:{column}"),
+        None => path.into_owned(),
+    };
+    let location = pango::glib::markup_escape_text(&location);
+    let message = pango::glib::markup_escape_text(&detail.message);
+
+    let mut text = format!(
+        "<b>Failed to load the config file</b>\n\
+         {location}: {message}"
+    );
+    if let Some(excerpt) = &detail.excerpt {
+        let excerpt = pango::glib::markup_escape_text(excerpt);
+        text.push_str(&format!("\n<tt>{excerpt}</tt>"));
+    }
+    text.push_str("\n<span alpha=\"60%\">Click to open it in the editor</span>");
+
+    panel::render_text(
+        renderer,
+        &text,
+        Alignment::Left,
+        PADDING,
+        panel::ERROR,
+        scale,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn detail(position: Option<(usize, usize)>) -> ConfigErrorDetail {
+        ConfigErrorDetail {
+            path: PathBuf::from("/home/user/.config/niri/config.kdl"),
+            message: String::from("unexpected node"),
+            position,
+            excerpt: None,
+        }
+    }
+
+    fn terminal(command: &[&str]) -> Option<Vec<OsString>> {
+        Some(command.iter().map(OsString::from).collect())
+    }
+
+    #[test]
+    fn editor_command_with_editor() {
+        let command = editor_command(
+            &detail(Some((12, 5))),
+            Some("nvim -p".into()),
+            terminal(&["foot", "-e"]),
+        );
+        assert_eq!(
+            command,
+            [
+                "foot",
+                "-e",
+                "nvim",
+                "-p",
+                "+12",
+                "/home/user/.config/niri/config.kdl"
+            ]
+        );
+
+        let command = editor_command(
+            &detail(None),
+            Some("nano".into()),
+            terminal(&["xdg-terminal-exec"]),
+        );
+        assert_eq!(
+            command,
+            [
+                "xdg-terminal-exec",
+                "nano",
+                "/home/user/.config/niri/config.kdl"
+            ]
+        );
+    }
+
+    #[test]
+    fn editor_command_without_editor() {
+        let expected = ["xdg-open", "/home/user/.config/niri/config.kdl"];
+        let foot = terminal(&["foot", "-e"]);
+        assert_eq!(
+            editor_command(&detail(Some((12, 5))), None, foot.clone()),
+            expected
+        );
+        assert_eq!(
+            editor_command(&detail(None), Some("  ".into()), foot),
+            expected
+        );
+    }
+
+    #[test]
+    fn editor_command_without_terminal() {
+        // A terminal editor can't run without a terminal, so use the default application.
+        let expected = ["xdg-open", "/home/user/.config/niri/config.kdl"];
+        let command = editor_command(&detail(Some((12, 5))), Some("vim".into()), None);
+        assert_eq!(command, expected);
+    }
+}
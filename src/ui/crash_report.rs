@@ -0,0 +1,201 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+
+use pango::Alignment;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::ui::panel;
+use crate::ui::texture_cache::TextureCache;
+use crate::utils::crash::{CrashReport, IS_SAFE_MODE};
+use crate::utils::output_size;
+
+const PADDING: i32 = 16;
+
+/// A UI overlay, rendered on its own so that a panic in one doesn't disable the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UiOverlay {
+    VirtualInputPrompt,
+    KillPrompt,
+    ConfigErrorNotification,
+    BindConflicts,
+    AdjustMode,
+    OverviewSearch,
+    LevelOsd,
+    Tutorial,
+    LaunchPlaceholders,
+}
+
+impl UiOverlay {
+    /// Whether the overlay intercepts input while open.
+    ///
+    /// Such overlays are closed after a crash, otherwise they'd keep eating input while invisible.
+    pub fn is_modal(self) -> bool {
+        matches!(
+            self,
+            Self::VirtualInputPrompt
+                | Self::KillPrompt
+                | Self::ConfigErrorNotification
+                | Self::AdjustMode
+                | Self::Tutorial
+        )
+    }
+}
+
+/// UI overlays that panicked while rendering.
+///
+/// They stay disabled afterwards so that the panic doesn't repeat on every frame. This is behind a
+/// `RefCell` because crashes are caught during rendering.
+#[derive(Debug, Default)]
+pub struct CrashedOverlays(RefCell<HashSet<UiOverlay>>);
+
+impl CrashedOverlays {
+    /// Marks the overlay as crashed, returns `true` if it wasn't marked yet.
+    pub fn mark(&self, overlay: UiOverlay) -> bool {
+        self.0.borrow_mut().insert(overlay)
+    }
+
+    pub fn contains(&self, overlay: UiOverlay) -> bool {
+        self.0.borrow().contains(&overlay)
+    }
+}
+
+/// Tells the user that niri recovered from a crash and offers to restart in safe mode.
+///
+/// The report is behind a `RefCell` so that crashes caught during rendering can show it.
+pub struct CrashReportOverlay {
+    report: RefCell<Option<CrashReport>>,
+    buffers: TextureCache,
+}
+
+impl CrashReportOverlay {
+    pub fn new() -> Self {
+        Self {
+            report: RefCell::new(None),
+            buffers: TextureCache::new(),
+        }
+    }
+
+    /// Shows the overlay for this crash, replacing any previous one.
+    pub fn show(&self, report: CrashReport) {
+        *self.report.borrow_mut() = Some(report);
+        self.buffers.clear();
+    }
+
+    /// Hides the overlay, returns `true` if it was open.
+    pub fn hide(&mut self) -> bool {
+        self.buffers.clear();
+        self.report.get_mut().take().is_some()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.report.borrow().is_some()
+    }
+
+    pub fn render_output<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let report = self.report.borrow();
+        let report = report.as_ref()?;
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let buffer = self.buffers.get_or_render(output, (), |scale| {
+            render(renderer.as_gles_renderer(), report, scale)
+                .map_err(|err| warn!("error rendering crash report overlay: {err:?}"))
+                .ok()
+        })?;
+
+        let size = buffer.logical_size();
+        let location = (output_size.to_point() - size.to_point()).downscale(2.);
+        let location = location.to_physical_precise_round(scale).to_logical(scale);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+impl Default for CrashReportOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    report: &CrashReport,
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("crash_report::render");
+
+    let subsystem = pango::glib::markup_escape_text(&report.subsystem);
+    let message = pango::glib::markup_escape_text(&report.message);
+    let mut text = format!(
+        "<b>niri recovered from a crash in {subsystem}</b>\n\
+         <span alpha=\"60%\">{message}</span>"
+    );
+    if let Some(path) = &report.path {
+        let path = pango::glib::markup_escape_text(&path.to_string_lossy());
+        text.push_str(&format!("\nThe crash report was saved to {path}"));
+    }
+    // There's nothing to offer when already running in safe mode.
+    if IS_SAFE_MODE.load(Ordering::Relaxed) {
+        text.push_str("\n\nPress <b>Escape</b> to dismiss.");
+    } else {
+        text.push_str(
+            "\n\nPress <b>R</b> to restart niri in safe mode \
+             or <b>Escape</b> to keep going.",
+        );
+    }
+
+    panel::render_text(
+        renderer,
+        &text,
+        Alignment::Center,
+        PADDING,
+        [1., 0.6, 0.2],
+        scale,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crashes_are_tracked_per_overlay() {
+        let crashed = CrashedOverlays::default();
+        assert!(crashed.mark(UiOverlay::LevelOsd));
+        assert!(!crashed.mark(UiOverlay::LevelOsd));
+
+        assert!(crashed.contains(UiOverlay::LevelOsd));
+        assert!(!crashed.contains(UiOverlay::KillPrompt));
+        assert!(!crashed.contains(UiOverlay::Tutorial));
+    }
+
+    #[test]
+    fn input_intercepting_overlays_are_modal() {
+        assert!(UiOverlay::KillPrompt.is_modal());
+        assert!(UiOverlay::VirtualInputPrompt.is_modal());
+        assert!(UiOverlay::AdjustMode.is_modal());
+        assert!(UiOverlay::ConfigErrorNotification.is_modal());
+        assert!(!UiOverlay::LevelOsd.is_modal());
+        assert!(!UiOverlay::LaunchPlaceholders.is_modal());
+    }
+}
@@ -0,0 +1,248 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::mem;
+use std::time::Duration;
+
+use calloop::RegistrationToken;
+use pangocairo::cairo::{self, ImageSurface};
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::utils::{Logical, Point, Transform};
+
+use crate::animation::Clock;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::to_physical_precise_round;
+
+/// Radius of the countdown ring.
+const RADIUS: i32 = 10;
+/// Width of the countdown ring line.
+const LINE_WIDTH: i32 = 3;
+/// Offset of the ring center from the pointer hotspot.
+const OFFSET: i32 = 24;
+/// Number of distinct ring states; the ring is re-rendered only when the step changes.
+const STEPS: u32 = 32;
+
+/// Dwell-click: clicks when the pointer stays still for a while.
+///
+/// Moving the pointer by more than the configured distance starts a countdown, shown as a ring
+/// next to the pointer. If the pointer stays within that distance until the countdown ends, a
+/// left click happens. After that, the pointer has to move away again before the next countdown.
+pub struct DwellClick {
+    state: DwellState,
+    /// Fires the click at the end of the countdown.
+    timer: Option<RegistrationToken>,
+    clock: Clock,
+    /// Rendered rings by step and scale.
+    buffers: RefCell<HashMap<(u32, u64), Option<TextureBuffer<GlesTexture>>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DwellState {
+    /// The pointer hasn't moved since the last click, or dwell-click is off.
+    Idle,
+    /// Counting down to a click at `anchor`.
+    Counting {
+        anchor: Point<f64, Logical>,
+        start: Duration,
+        delay: Duration,
+    },
+    /// The countdown ended or was interrupted by a click at `anchor`.
+    Done { anchor: Point<f64, Logical> },
+}
+
+impl DwellClick {
+    pub fn new(clock: Clock) -> Self {
+        Self {
+            state: DwellState::Idle,
+            timer: None,
+            clock,
+            buffers: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Handles pointer motion to `pos`.
+    ///
+    /// Returns `true` if a new countdown started, in which case the caller must arm a timer for
+    /// `delay` that calls [`Self::finish()`].
+    pub fn on_motion(&mut self, pos: Point<f64, Logical>, distance: f64, delay: Duration) -> bool {
+        let anchor = match self.state {
+            DwellState::Idle => None,
+            DwellState::Counting { anchor, .. } | DwellState::Done { anchor } => Some(anchor),
+        };
+
+        // Small movements don't restart the countdown, and don't start a new one after a click.
+        if let Some(anchor) = anchor {
+            let delta = pos - anchor;
+            if delta.x.hypot(delta.y) <= distance {
+                return false;
+            }
+        }
+
+        self.state = DwellState::Counting {
+            anchor: pos,
+            start: self.clock.now_unadjusted(),
+            delay,
+        };
+        true
+    }
+
+    /// Ends the countdown, returns `true` if it was running.
+    ///
+    /// Called both when the countdown runs out and when the user clicks by themselves.
+    pub fn finish(&mut self) -> bool {
+        let DwellState::Counting { anchor, .. } = self.state else {
+            return false;
+        };
+
+        self.state = DwellState::Done { anchor };
+        true
+    }
+
+    /// Stops tracking the pointer, for example when dwell-click is turned off.
+    pub fn reset(&mut self) {
+        self.state = DwellState::Idle;
+    }
+
+    /// Replaces the countdown timer, returning the previous one to be removed.
+    pub fn replace_timer(&mut self, timer: Option<RegistrationToken>) -> Option<RegistrationToken> {
+        mem::replace(&mut self.timer, timer)
+    }
+
+    /// Drops the rendered rings so that they are rendered again, for example after a GPU reset.
+    pub fn clear_buffers(&self) {
+        self.buffers.borrow_mut().clear();
+    }
+
+    pub fn is_counting(&self) -> bool {
+        matches!(self.state, DwellState::Counting { .. })
+    }
+
+    /// Fraction of the countdown that has passed.
+    fn progress(&self) -> Option<f64> {
+        let DwellState::Counting { start, delay, .. } = self.state else {
+            return None;
+        };
+
+        let elapsed = self.clock.now_unadjusted().saturating_sub(start);
+        Some((elapsed.as_secs_f64() / delay.as_secs_f64().max(0.001)).min(1.))
+    }
+
+    /// Renders the countdown ring next to `pointer_pos`, given in output-local coordinates.
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        pointer_pos: Point<f64, Logical>,
+        scale: f64,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let progress = self.progress()?;
+        let step = (progress * f64::from(STEPS)).floor() as u32;
+
+        let mut buffers = self.buffers.borrow_mut();
+        let buffer = buffers
+            .entry((step, scale.to_bits()))
+            .or_insert_with(|| {
+                render(renderer.as_gles_renderer(), step, scale)
+                    .map_err(|err| warn!("error rendering dwell-click ring: {err:?}"))
+                    .ok()
+            })
+            .as_ref()?;
+
+        let size = buffer.logical_size();
+        let center = pointer_pos + Point::from((f64::from(OFFSET), f64::from(OFFSET)));
+        let location = Point::from((center.x - size.w / 2., center.y - size.h / 2.))
+            .to_physical_precise_round(scale)
+            .to_logical(scale);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer.clone(),
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    step: u32,
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("dwell_click::render");
+
+    let radius: i32 = to_physical_precise_round(scale, RADIUS);
+    let line_width: i32 = to_physical_precise_round(scale, LINE_WIDTH);
+    let size = (radius + line_width) * 2;
+    let center = f64::from(size) / 2.;
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, size, size)?;
+    let cr = cairo::Context::new(&surface)?;
+    cr.set_line_width(line_width.into());
+
+    // The track of the ring.
+    cr.arc(center, center, radius.into(), 0., 2. * PI);
+    cr.set_source_rgba(0.1, 0.1, 0.1, 0.6);
+    cr.stroke()?;
+
+    // The elapsed part, clockwise from the top.
+    let fraction = f64::from(step) / f64::from(STEPS);
+    if fraction > 0. {
+        let start = -PI / 2.;
+        cr.arc(
+            center,
+            center,
+            radius.into(),
+            start,
+            start + fraction * 2. * PI,
+        );
+        cr.set_source_rgb(0.5, 0.8, 1.0);
+        cr.stroke()?;
+    }
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (size, size),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn countdown_restarts_only_after_moving_away() {
+        let mut dwell = DwellClick::new(Clock::with_time(Duration::ZERO));
+        let delay = Duration::from_millis(1000);
+
+        assert!(dwell.on_motion(Point::from((10., 10.)), 4., delay));
+        // Jitter within the distance keeps the countdown going.
+        assert!(!dwell.on_motion(Point::from((12., 11.)), 4., delay));
+        assert!(dwell.is_counting());
+
+        assert!(dwell.finish());
+        assert!(!dwell.finish());
+
+        // No new countdown until the pointer moves away from the click.
+        assert!(!dwell.on_motion(Point::from((13., 10.)), 4., delay));
+        assert!(!dwell.is_counting());
+        assert!(dwell.on_motion(Point::from((20., 10.)), 4., delay));
+        assert!(dwell.is_counting());
+    }
+}
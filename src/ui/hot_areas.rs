@@ -0,0 +1,250 @@
+use std::mem;
+
+use calloop::RegistrationToken;
+use niri_config::{Action, Color, HotCorners};
+use smithay::backend::renderer::element::Kind;
+use smithay::output::Output;
+use smithay::utils::{Logical, Point, Rectangle, Size};
+
+use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+
+/// Hot corner or edge of an output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotArea {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// Hot corners and edges: running actions when the pointer enters an area at the output edges.
+///
+/// With a delay, the pointer has to stay inside the area until the timer fires. An area triggers
+/// once per entry, and the pointer has to leave it before it can trigger again.
+pub struct HotAreas {
+    /// Area that the pointer is in, along with its output and geometry within that output.
+    current: Option<(Output, HotArea, Rectangle<f64, Logical>)>,
+    /// Fires the area under the pointer at the end of the delay.
+    timer: Option<RegistrationToken>,
+    /// Hint drawn over the area while the pointer is inside, if enabled.
+    hint: Option<SolidColorBuffer>,
+}
+
+impl HotArea {
+    /// Returns the config of this area, if it is set up.
+    fn config(self, config: &HotCorners) -> Option<&niri_config::HotArea> {
+        match self {
+            HotArea::TopLeft => config.top_left.as_ref(),
+            HotArea::TopRight => config.top_right.as_ref(),
+            HotArea::BottomLeft => config.bottom_left.as_ref(),
+            HotArea::BottomRight => config.bottom_right.as_ref(),
+            HotArea::Top => config.top.as_ref(),
+            HotArea::Bottom => config.bottom.as_ref(),
+            HotArea::Left => config.left.as_ref(),
+            HotArea::Right => config.right.as_ref(),
+        }
+    }
+
+    /// Returns the actions to run for this area.
+    ///
+    /// An area without actions toggles the overview.
+    pub fn actions(self, config: &HotCorners) -> Vec<Action> {
+        match self.config(config) {
+            Some(area) if !area.actions.is_empty() => area.actions.clone(),
+            _ => vec![Action::ToggleOverview],
+        }
+    }
+}
+
+impl HotAreas {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            timer: None,
+            hint: None,
+        }
+    }
+
+    /// Returns the area that the pointer is in.
+    pub fn current(&self) -> Option<(&Output, HotArea)> {
+        self.current
+            .as_ref()
+            .map(|(output, area, _)| (output, *area))
+    }
+
+    /// Takes the area that the pointer is in, leaving none.
+    pub fn take_current(&mut self) -> Option<(Output, HotArea, Rectangle<f64, Logical>)> {
+        self.hint = None;
+        self.current.take()
+    }
+
+    /// Sets the area that the pointer is in, with the color of its hint.
+    pub fn set_current(
+        &mut self,
+        current: Option<(Output, HotArea, Rectangle<f64, Logical>)>,
+        hint_color: Option<Color>,
+    ) {
+        self.hint = match (&current, hint_color) {
+            (Some((_, _, geo)), Some(color)) => {
+                Some(SolidColorBuffer::new(geo.size, color.to_array_premul()))
+            }
+            _ => None,
+        };
+        self.current = current;
+    }
+
+    /// Replaces the delay timer, returning the previous one to be removed.
+    pub fn replace_timer(&mut self, timer: Option<RegistrationToken>) -> Option<RegistrationToken> {
+        mem::replace(&mut self.timer, timer)
+    }
+
+    /// Renders the hint over the area under the pointer, if it's on this output.
+    pub fn render(&self, output: &Output) -> Option<SolidColorRenderElement> {
+        let hint = self.hint.as_ref()?;
+        let (current_output, _, geo) = self.current.as_ref()?;
+        if current_output != output {
+            return None;
+        }
+
+        Some(SolidColorRenderElement::from_buffer(
+            hint,
+            geo.loc,
+            1.,
+            Kind::Unspecified,
+        ))
+    }
+}
+
+impl Default for HotAreas {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Finds the hot area at `pos` within an output of `output_size`.
+///
+/// Corners take precedence over edges. Without any area set up explicitly, only the top-left
+/// corner is hot.
+pub fn hot_area_under(
+    config: &HotCorners,
+    output_size: Size<f64, Logical>,
+    pos: Point<f64, Logical>,
+) -> Option<(HotArea, Rectangle<f64, Logical>)> {
+    if config.off {
+        return None;
+    }
+
+    let explicit = config.has_explicit_areas();
+    let is_set_up = |area: HotArea| {
+        if explicit {
+            area.config(config).is_some()
+        } else {
+            area == HotArea::TopLeft
+        }
+    };
+
+    let size = config.size.0;
+    let (w, h) = (output_size.w, output_size.h);
+    let left = pos.x < size;
+    let right = pos.x >= w - size;
+    let top = pos.y < size;
+    let bottom = pos.y >= h - size;
+
+    let corner = Size::from((size, size));
+    let candidates = [
+        (top && left, HotArea::TopLeft, Point::from((0., 0.)), corner),
+        (
+            top && right,
+            HotArea::TopRight,
+            Point::from((w - size, 0.)),
+            corner,
+        ),
+        (
+            bottom && left,
+            HotArea::BottomLeft,
+            Point::from((0., h - size)),
+            corner,
+        ),
+        (
+            bottom && right,
+            HotArea::BottomRight,
+            Point::from((w - size, h - size)),
+            corner,
+        ),
+        (
+            top,
+            HotArea::Top,
+            Point::from((0., 0.)),
+            Size::from((w, size)),
+        ),
+        (
+            bottom,
+            HotArea::Bottom,
+            Point::from((0., h - size)),
+            Size::from((w, size)),
+        ),
+        (
+            left,
+            HotArea::Left,
+            Point::from((0., 0.)),
+            Size::from((size, h)),
+        ),
+        (
+            right,
+            HotArea::Right,
+            Point::from((w - size, 0.)),
+            Size::from((size, h)),
+        ),
+    ];
+
+    candidates
+        .into_iter()
+        .find(|(inside, area, _, _)| *inside && is_set_up(*area))
+        .map(|(_, area, loc, size)| (area, Rectangle::new(loc, size)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output_size() -> Size<f64, Logical> {
+        Size::from((1920., 1080.))
+    }
+
+    fn area_at(config: &HotCorners, x: f64, y: f64) -> Option<HotArea> {
+        hot_area_under(config, output_size(), Point::from((x, y))).map(|(area, _)| area)
+    }
+
+    #[test]
+    fn default_is_top_left_corner() {
+        let config = HotCorners::default();
+        assert_eq!(area_at(&config, 0., 0.), Some(HotArea::TopLeft));
+        assert_eq!(area_at(&config, 1., 0.), None);
+        assert_eq!(area_at(&config, 1919., 0.), None);
+    }
+
+    #[test]
+    fn corners_take_precedence_over_edges() {
+        let config = HotCorners {
+            size: niri_config::FloatOrInt(10.),
+            top_right: Some(niri_config::HotArea::default()),
+            top: Some(niri_config::HotArea::default()),
+            ..Default::default()
+        };
+
+        assert_eq!(area_at(&config, 1915., 5.), Some(HotArea::TopRight));
+        assert_eq!(area_at(&config, 5., 5.), Some(HotArea::Top));
+        assert_eq!(area_at(&config, 960., 9.), Some(HotArea::Top));
+        assert_eq!(area_at(&config, 960., 10.), None);
+
+        let (_, geo) = hot_area_under(&config, output_size(), Point::from((1915., 5.))).unwrap();
+        assert_eq!(
+            geo,
+            Rectangle::new(Point::from((1910., 0.)), Size::from((10., 10.)))
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use smithay::backend::renderer::element::Kind;
+use smithay::output::{Output, WeakOutput};
+
+use crate::animation::{Animation, Clock, Curve};
+use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::utils::output_size;
+
+/// Fades all outputs to black after the session has been idle for a while.
+///
+/// The outputs stay black until the next user activity, which brings the contents back right
+/// away.
+pub struct IdleFade {
+    state: FadeState,
+    buffers: RefCell<HashMap<WeakOutput, SolidColorBuffer>>,
+    clock: Clock,
+}
+
+enum FadeState {
+    Off,
+    Fading(Animation),
+    Black,
+}
+
+impl IdleFade {
+    pub fn new(clock: Clock) -> Self {
+        Self {
+            state: FadeState::Off,
+            buffers: RefCell::new(HashMap::new()),
+            clock,
+        }
+    }
+
+    /// Starts fading to black over `duration_ms`.
+    pub fn start(&mut self, duration_ms: u32) {
+        if !matches!(self.state, FadeState::Off) {
+            return;
+        }
+
+        let anim = Animation::ease(
+            self.clock.clone(),
+            0.,
+            1.,
+            0.,
+            u64::from(duration_ms),
+            Curve::Linear,
+        );
+        self.state = FadeState::Fading(anim);
+    }
+
+    /// Brings the contents back, returns `true` if the outputs were fading or black.
+    pub fn cancel(&mut self) -> bool {
+        if matches!(self.state, FadeState::Off) {
+            return false;
+        }
+
+        self.state = FadeState::Off;
+        self.buffers.get_mut().clear();
+        true
+    }
+
+    /// Advances the fade, returns `true` when the outputs have just turned fully black.
+    pub fn advance_animations(&mut self) -> bool {
+        let FadeState::Fading(anim) = &self.state else {
+            return false;
+        };
+
+        if anim.is_done() {
+            self.state = FadeState::Black;
+            return true;
+        }
+
+        false
+    }
+
+    pub fn is_animation_ongoing(&self) -> bool {
+        matches!(self.state, FadeState::Fading(_))
+    }
+
+    pub fn render_output(&self, output: &Output) -> Option<SolidColorRenderElement> {
+        let alpha = match &self.state {
+            FadeState::Off => return None,
+            FadeState::Fading(anim) => anim.clamped_value() as f32,
+            FadeState::Black => 1.,
+        };
+
+        let mut buffers = self.buffers.borrow_mut();
+        buffers.retain(|output, _| output.upgrade().is_some());
+
+        let size = output_size(output);
+        let buffer = buffers
+            .entry(output.downgrade())
+            .or_insert_with(|| SolidColorBuffer::new(size, [0., 0., 0., 1.]));
+        buffer.resize(size);
+
+        Some(SolidColorRenderElement::from_buffer(
+            buffer,
+            (0., 0.),
+            alpha,
+            Kind::Unspecified,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn fades_to_black_once_until_cancelled() {
+        let mut clock = Clock::with_time(Duration::ZERO);
+        let mut fade = IdleFade::new(clock.clone());
+        assert!(!fade.cancel());
+
+        fade.start(1000);
+        clock.set_unadjusted(Duration::from_millis(500));
+        assert!(!fade.advance_animations());
+        assert!(fade.is_animation_ongoing());
+
+        // Starting again while fading doesn't restart the fade.
+        fade.start(1000);
+        clock.set_unadjusted(Duration::from_millis(1000));
+        assert!(fade.advance_animations());
+        assert!(!fade.is_animation_ongoing());
+
+        // The outputs stay black without reporting it again.
+        assert!(!fade.advance_animations());
+
+        assert!(fade.cancel());
+        assert!(!fade.cancel());
+        assert!(!fade.advance_animations());
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::VecDeque;
+
+use pango::Alignment;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::input::keyboard::Keysym;
+use smithay::output::Output;
+
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::ui::panel;
+use crate::ui::texture_cache::TextureCache;
+use crate::utils::output_size;
+
+const PADDING: i32 = 16;
+
+/// Asks the user whether to kill a client whose window didn't close in time.
+pub struct KillPrompt {
+    /// Windows waiting for an answer, the first one is on screen.
+    queue: VecDeque<KillRequest>,
+    /// Prompt textures, keyed by the number of queued prompts that they show.
+    buffers: TextureCache<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KillRequest {
+    pub window_id: u64,
+    /// Title or app ID to show in the prompt.
+    pub name: String,
+}
+
+/// What a key press did to the prompt on screen.
+#[derive(Debug, PartialEq, Eq)]
+pub enum KillAnswer {
+    /// The user chose to kill the client of this window.
+    Kill(KillRequest),
+    /// The user chose to keep waiting for this window.
+    Dismiss(KillRequest),
+    /// The key doesn't answer the prompt.
+    Ignore,
+}
+
+impl KillPrompt {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            buffers: TextureCache::new(),
+        }
+    }
+
+    /// Queues a prompt for this window, returns `true` if it wasn't queued yet.
+    pub fn request(&mut self, request: KillRequest) -> bool {
+        if self.queue.iter().any(|r| r.window_id == request.window_id) {
+            return false;
+        }
+
+        self.queue.push_back(request);
+        self.buffers.clear();
+        true
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    /// Answers the prompt currently on screen with a key press.
+    ///
+    /// K kills the client and Escape keeps waiting, both move on to the next queued prompt.
+    pub fn answer(&mut self, key: Option<Keysym>) -> KillAnswer {
+        let kill = match key {
+            Some(Keysym::k) => true,
+            Some(Keysym::Escape) => false,
+            _ => return KillAnswer::Ignore,
+        };
+
+        let Some(request) = self.queue.pop_front() else {
+            return KillAnswer::Ignore;
+        };
+        self.buffers.clear();
+
+        if kill {
+            KillAnswer::Kill(request)
+        } else {
+            KillAnswer::Dismiss(request)
+        }
+    }
+
+    /// Drops all queued prompts without answering them.
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.buffers.clear();
+    }
+
+    /// Drops the prompt for a window that has closed in the meantime.
+    ///
+    /// Returns `true` if there was such a prompt.
+    pub fn remove(&mut self, window_id: u64) -> bool {
+        let len = self.queue.len();
+        self.queue.retain(|r| r.window_id != window_id);
+        if self.queue.len() == len {
+            return false;
+        }
+
+        self.buffers.clear();
+        true
+    }
+
+    pub fn render_output<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let request = self.queue.front()?;
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let count = self.queue.len();
+        let buffer = self.buffers.get_or_render(output, count, |scale| {
+            render(renderer.as_gles_renderer(), request, count, scale)
+                .map_err(|err| warn!("error rendering kill prompt: {err:?}"))
+                .ok()
+        })?;
+
+        let size = buffer.logical_size();
+        let location = (output_size.to_point() - size.to_point()).downscale(2.);
+        let location = location.to_physical_precise_round(scale).to_logical(scale);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+impl Default for KillPrompt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    request: &KillRequest,
+    queued: usize,
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("kill_prompt::render");
+
+    let name = pango::glib::markup_escape_text(&request.name);
+    let mut text = format!(
+        "<b>{name}</b> is not responding.\n\n\
+         Press <b>K</b> to kill it or <b>Escape</b> to keep waiting."
+    );
+    if queued > 1 {
+        text.push_str(&format!(
+            "\n<span alpha=\"60%\">{} more windows not responding</span>",
+            queued - 1
+        ));
+    }
+
+    panel::render_text(
+        renderer,
+        &text,
+        Alignment::Center,
+        PADDING,
+        panel::ERROR,
+        scale,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(window_id: u64) -> KillRequest {
+        KillRequest {
+            window_id,
+            name: format!("Window {window_id}"),
+        }
+    }
+
+    #[test]
+    fn answers_prompts_in_order() {
+        let mut prompt = KillPrompt::new();
+        assert!(!prompt.is_open());
+
+        assert!(prompt.request(request(1)));
+        assert!(prompt.request(request(2)));
+        // Closing the same window again doesn't queue a second prompt.
+        assert!(!prompt.request(request(1)));
+        assert!(prompt.is_open());
+
+        assert_eq!(prompt.answer(Some(Keysym::a)), KillAnswer::Ignore);
+        assert_eq!(prompt.answer(None), KillAnswer::Ignore);
+        assert_eq!(prompt.answer(Some(Keysym::k)), KillAnswer::Kill(request(1)));
+        assert_eq!(
+            prompt.answer(Some(Keysym::Escape)),
+            KillAnswer::Dismiss(request(2))
+        );
+
+        assert!(!prompt.is_open());
+        assert_eq!(prompt.answer(Some(Keysym::k)), KillAnswer::Ignore);
+    }
+
+    #[test]
+    fn closed_window_drops_its_prompt() {
+        let mut prompt = KillPrompt::new();
+        prompt.request(request(1));
+        prompt.request(request(2));
+
+        assert!(prompt.remove(1));
+        assert!(!prompt.remove(1));
+        assert_eq!(prompt.answer(Some(Keysym::k)), KillAnswer::Kill(request(2)));
+        assert!(!prompt.is_open());
+    }
+
+    #[test]
+    fn clear_closes_the_prompt() {
+        let mut prompt = KillPrompt::new();
+        prompt.request(request(1));
+        prompt.request(request(2));
+
+        prompt.clear();
+        assert!(!prompt.is_open());
+        assert_eq!(prompt.answer(Some(Keysym::k)), KillAnswer::Ignore);
+    }
+}
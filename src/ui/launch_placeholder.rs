@@ -0,0 +1,231 @@
+use std::cell::RefCell;
+use std::f64::consts::TAU;
+use std::path::Path;
+use std::time::Duration;
+
+use pango::Alignment;
+use pangocairo::cairo;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::{Output, WeakOutput};
+use smithay::utils::Rectangle;
+
+use crate::animation::Clock;
+use crate::layout::Layout;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::ui::panel;
+use crate::ui::texture_cache::TextureCache;
+use crate::utils::{output_size, to_physical_precise_round};
+use crate::window::Mapped;
+
+const PADDING: i32 = 16;
+
+/// Size of the spinner next to the app name.
+const SPINNER_SIZE: i32 = 20;
+
+/// Number of spokes in the spinner, which is also the number of animation frames.
+const SPINNER_SPOKES: usize = 12;
+
+/// How long the spinner stays on each frame.
+const SPINNER_INTERVAL: Duration = Duration::from_millis(80);
+
+/// Placeholders shown where the windows of launching apps will appear.
+///
+/// A placeholder goes away once its app activates with the launch token or opens a window with a
+/// matching app ID, or after a timeout for apps that do neither.
+pub struct LaunchPlaceholders {
+    /// Placeholders from oldest to newest.
+    placeholders: Vec<LaunchPlaceholder>,
+    clock: Clock,
+    /// Placeholder textures, keyed by the activation token and the spinner frame.
+    buffers: TextureCache<(String, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchPlaceholder {
+    /// Activation token that the app was launched with.
+    pub token: String,
+    /// App name to show, derived from the command.
+    pub name: String,
+    /// Output that was active when the app was launched.
+    pub output: WeakOutput,
+}
+
+impl LaunchPlaceholders {
+    pub fn new(clock: Clock) -> Self {
+        Self {
+            placeholders: Vec::new(),
+            clock,
+            buffers: TextureCache::new(),
+        }
+    }
+
+    pub fn add(&mut self, placeholder: LaunchPlaceholder) {
+        self.placeholders.push(placeholder);
+    }
+
+    /// Removes the placeholder for this activation token, returns `true` if there was one.
+    pub fn remove_token(&mut self, token: &str) -> bool {
+        let len = self.placeholders.len();
+        self.placeholders.retain(|p| p.token != token);
+        len != self.placeholders.len()
+    }
+
+    /// Removes the oldest placeholder matching a newly opened window.
+    ///
+    /// Returns `true` if there was such a placeholder.
+    pub fn remove_app_id(&mut self, app_id: &str) -> bool {
+        let idx = self
+            .placeholders
+            .iter()
+            .position(|p| matches_app_id(&p.name, app_id));
+        if let Some(idx) = idx {
+            self.placeholders.remove(idx);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether a spinner is on screen and needs continuous redrawing.
+    pub fn are_animations_ongoing(&self) -> bool {
+        !self.placeholders.is_empty()
+    }
+
+    pub fn render_output<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+        layout: &Layout<Mapped>,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let weak = output.downgrade();
+        let placeholder = self.placeholders.iter().rev().find(|p| p.output == weak)?;
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let frame =
+            (self.clock.now().as_millis() / SPINNER_INTERVAL.as_millis()) as usize % SPINNER_SPOKES;
+
+        let key = (placeholder.token.clone(), frame);
+        let buffer = self.buffers.get_or_render(output, key, |scale| {
+            render(renderer.as_gles_renderer(), &placeholder.name, frame, scale)
+                .map_err(|err| warn!("error rendering launch placeholder: {err:?}"))
+                .ok()
+        })?;
+
+        // Center the placeholder where the new window will open, or on the output if that's
+        // unknown.
+        let size = buffer.logical_size();
+        let area = layout
+            .new_window_area(output)
+            .unwrap_or_else(|| Rectangle::from_size(output_size));
+        let center = area.loc + area.size.downscale(2.).to_point();
+        let mut location = center - size.downscale(2.).to_point();
+
+        // Keep the placeholder on the output.
+        location.x = location.x.clamp(0., f64::max(0., output_size.w - size.w));
+        location.y = location.y.clamp(0., f64::max(0., output_size.h - size.h));
+        let location = location.to_physical_precise_round(scale).to_logical(scale);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+/// Returns the name to show for a launched command.
+pub fn app_name(command: &[String]) -> Option<String> {
+    let program = command.first()?;
+    let name = Path::new(program).file_name()?.to_string_lossy();
+    Some(name.into_owned())
+}
+
+/// Whether a window app ID belongs to the app launched under this name.
+///
+/// App IDs are often reverse-DNS, like `org.gnome.Nautilus` for `nautilus`, so the last component
+/// is compared as well.
+fn matches_app_id(name: &str, app_id: &str) -> bool {
+    let last = app_id.rsplit('.').next().unwrap_or(app_id);
+    name.eq_ignore_ascii_case(app_id) || name.eq_ignore_ascii_case(last)
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    name: &str,
+    frame: usize,
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("launch_placeholder::render");
+
+    let padding: i32 = to_physical_precise_round(scale, PADDING);
+    let spinner_size: i32 = to_physical_precise_round(scale, SPINNER_SIZE);
+
+    let font = panel::font(scale);
+    let layout = panel::measuring_layout(&font, Alignment::Left)?;
+    layout.set_text(name);
+
+    let (text_width, text_height) = layout.pixel_size();
+    let content_height = i32::max(text_height, spinner_size);
+    let width = padding * 3 + spinner_size + text_width;
+    let height = padding * 2 + content_height;
+
+    panel::render(renderer, width, height, [0.5, 0.5, 0.5], scale, |cr| {
+        // The spinner spokes fade out behind the current one.
+        let center_x = f64::from(padding) + f64::from(spinner_size) / 2.;
+        let center_y = f64::from(height) / 2.;
+        let radius = f64::from(spinner_size) / 2.;
+        cr.set_line_width((radius / 4.).max(1.));
+        cr.set_line_cap(cairo::LineCap::Round);
+        for spoke in 0..SPINNER_SPOKES {
+            let age = (frame + SPINNER_SPOKES - spoke) % SPINNER_SPOKES;
+            let alpha = 1. - age as f64 / SPINNER_SPOKES as f64;
+            let angle = TAU * spoke as f64 / SPINNER_SPOKES as f64;
+            let (sin, cos) = angle.sin_cos();
+            cr.move_to(center_x + cos * radius / 2., center_y + sin * radius / 2.);
+            cr.line_to(center_x + cos * radius, center_y + sin * radius);
+            cr.set_source_rgba(1., 1., 1., alpha);
+            cr.stroke()?;
+        }
+
+        let layout = panel::create_layout(cr, &font, Alignment::Left);
+        layout.set_text(name);
+        let text_x = padding * 2 + spinner_size;
+        let text_y = (height - text_height) / 2;
+        panel::show_layout(cr, &layout, text_x, text_y);
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn app_name_from_command() {
+        let command = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(app_name(&command(&["alacritty"])).unwrap(), "alacritty");
+        assert_eq!(
+            app_name(&command(&["/usr/bin/firefox", "--new-window"])).unwrap(),
+            "firefox"
+        );
+        assert_eq!(app_name(&[]), None);
+    }
+
+    #[test]
+    fn app_id_matching() {
+        assert!(matches_app_id("alacritty", "Alacritty"));
+        assert!(matches_app_id("nautilus", "org.gnome.Nautilus"));
+        assert!(!matches_app_id("nautilus", "org.gnome.Console"));
+    }
+}
@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use calloop::RegistrationToken;
+use pango::Alignment;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+use smithay::utils::Point;
+
+use crate::layout::Layout;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::ui::panel;
+use crate::ui::texture_cache::TextureCache;
+use crate::utils::{output_size, to_physical_precise_round};
+use crate::window::Mapped;
+
+const PADDING: i32 = 12;
+const MARGIN: i32 = 96;
+const BAR_WIDTH: i32 = 200;
+const BAR_HEIGHT: i32 = 6;
+
+/// How long the OSD stays on screen after the last change.
+pub const TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// A level changed with the hardware keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Volume { percent: u32, muted: bool },
+    Brightness { percent: u32 },
+}
+
+/// OSD at the bottom of the active output that shows the volume or brightness after a change.
+pub struct LevelOsd {
+    level: Option<Level>,
+    hide_timer: Option<RegistrationToken>,
+    /// OSD textures, keyed by the level that they show.
+    buffers: TextureCache<Level>,
+}
+
+impl LevelOsd {
+    pub fn new() -> Self {
+        Self {
+            level: None,
+            hide_timer: None,
+            buffers: TextureCache::new(),
+        }
+    }
+
+    /// Shows the level until `hide_timer` fires.
+    pub fn show(&mut self, level: Level, hide_timer: RegistrationToken) {
+        self.level = Some(level);
+        self.hide_timer = Some(hide_timer);
+    }
+
+    /// Hides the OSD, returns `true` if it was open.
+    pub fn hide(&mut self) -> bool {
+        self.hide_timer = None;
+        self.buffers.clear();
+        self.level.take().is_some()
+    }
+
+    /// Takes the timer of the current OSD, so that it can be cancelled.
+    pub fn take_hide_timer(&mut self) -> Option<RegistrationToken> {
+        self.hide_timer.take()
+    }
+
+    pub fn render_output<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+        layout: &Layout<Mapped>,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let level = self.level?;
+
+        if layout.active_output() != Some(output) {
+            return None;
+        }
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let buffer = self.buffers.get_or_render(output, level, |scale| {
+            render(renderer.as_gles_renderer(), level, scale)
+                .map_err(|err| warn!("error rendering level OSD: {err:?}"))
+                .ok()
+        })?;
+
+        let size = buffer.logical_size();
+        let x = (output_size.w - size.w) / 2.;
+        let y = output_size.h - size.h - f64::from(MARGIN);
+        let location = Point::from((x, y))
+            .to_physical_precise_round(scale)
+            .to_logical(scale);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+impl Default for LevelOsd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn osd_text(level: Level) -> String {
+    match level {
+        Level::Volume { muted: true, .. } => String::from("<b>Volume</b> muted"),
+        Level::Volume { percent, .. } => format!("<b>Volume</b> {percent}%"),
+        Level::Brightness { percent } => format!("<b>Brightness</b> {percent}%"),
+    }
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    level: Level,
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("level_osd::render");
+
+    let padding: i32 = to_physical_precise_round(scale, PADDING);
+    let bar_width: i32 = to_physical_precise_round(scale, BAR_WIDTH);
+    let bar_height: i32 = to_physical_precise_round(scale, BAR_HEIGHT);
+
+    let font = panel::font(scale);
+
+    let text = osd_text(level);
+    let (percent, muted) = match level {
+        Level::Volume { percent, muted } => (percent, muted),
+        Level::Brightness { percent } => (percent, false),
+    };
+
+    let layout = panel::measuring_layout(&font, Alignment::Center)?;
+    layout.set_markup(&text);
+
+    let (text_width, text_height) = layout.pixel_size();
+    let content_width = text_width.max(bar_width);
+    let width = content_width + padding * 2;
+    let height = text_height + padding + bar_height + padding * 2;
+
+    panel::render(renderer, width, height, panel::INFO, scale, |cr| {
+        let layout = panel::create_layout(cr, &font, Alignment::Center);
+        layout.set_markup(&text);
+        panel::show_layout(cr, &layout, (width - text_width) / 2, padding);
+
+        // The level bar, dimmed while muted.
+        let bar_x = f64::from((width - bar_width) / 2);
+        let bar_y = f64::from(padding + text_height + padding);
+        cr.rectangle(bar_x, bar_y, bar_width.into(), bar_height.into());
+        cr.set_source_rgb(0.3, 0.3, 0.3);
+        cr.fill()?;
+
+        let filled = f64::from(bar_width) * f64::from(percent.min(100)) / 100.;
+        cr.rectangle(bar_x, bar_y, filled, bar_height.into());
+        if muted {
+            cr.set_source_rgb(0.5, 0.5, 0.5);
+        } else {
+            let [r, g, b] = panel::INFO;
+            cr.set_source_rgb(r, g, b);
+        }
+        cr.fill()?;
+
+        Ok(())
+    })
+}
@@ -0,0 +1,17 @@
+pub mod adjust_mode;
+pub mod bind_conflicts;
+pub mod config_error_notification;
+pub mod crash_report;
+pub mod dwell_click;
+pub mod hot_areas;
+pub mod idle_fade;
+pub mod kill_prompt;
+pub mod launch_placeholder;
+pub mod level_osd;
+pub mod overview_search;
+pub mod panel;
+pub mod screen_transition;
+pub mod texture_cache;
+pub mod tutorial;
+pub mod virtual_input_prompt;
+pub mod wallpaper;
@@ -0,0 +1,351 @@
+use niri_config::Modifiers;
+use pango::Alignment;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::input::keyboard::Keysym;
+use smithay::output::Output;
+use smithay::utils::Point;
+
+use crate::layout::Layout;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::ui::panel;
+use crate::ui::texture_cache::TextureCache;
+use crate::utils::output_size;
+use crate::window::thumbnails::Thumbnail;
+use crate::window::Mapped;
+
+const PADDING: i32 = 8;
+const MARGIN: i32 = 32;
+
+/// Type-to-search in the overview.
+///
+/// Typing while the overview has keyboard focus filters windows by title and app id. Windows that
+/// don't match are dimmed, and Enter focuses the best match. The search box shows at the top of
+/// the active output, with a thumbnail of the best match under it.
+pub struct OverviewSearch {
+    query: String,
+    /// Number of windows matching the query, shown next to it.
+    match_count: usize,
+    /// Id of the best matching window, whose thumbnail is shown.
+    best_match: Option<u64>,
+    /// Search box textures, keyed by their text.
+    buffers: TextureCache<String>,
+}
+
+/// What a key press did to the search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchKey {
+    /// The query changed.
+    Edited,
+    /// The user asked to focus the best match.
+    Accept,
+}
+
+impl OverviewSearch {
+    pub fn new() -> Self {
+        Self {
+            query: String::new(),
+            match_count: 0,
+            best_match: None,
+            buffers: TextureCache::new(),
+        }
+    }
+
+    /// Whether the user typed something to search for.
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty()
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn set_matches(&mut self, count: usize, best_match: Option<u64>) {
+        self.match_count = count;
+        self.best_match = best_match;
+    }
+
+    /// Id of the best matching window while searching.
+    pub fn best_match(&self) -> Option<u64> {
+        self.best_match.filter(|_| self.is_active())
+    }
+
+    /// Clears the query, returns `true` if there was one.
+    pub fn clear(&mut self) -> bool {
+        self.buffers.clear();
+        if self.query.is_empty() {
+            return false;
+        }
+
+        self.query.clear();
+        true
+    }
+
+    /// Handles a key press in the overview.
+    ///
+    /// `raw` is the keysym without modifiers applied and `modified` is the one with them. Returns
+    /// `None` for keys that the search doesn't use, so they can do their usual thing.
+    pub fn on_key(
+        &mut self,
+        raw: Option<Keysym>,
+        modified: Keysym,
+        mods: Modifiers,
+    ) -> Option<SearchKey> {
+        // Shift is fine since it types capital letters.
+        if !mods.difference(Modifiers::SHIFT).is_empty() {
+            return None;
+        }
+
+        if self.is_active() {
+            match raw {
+                Some(Keysym::Escape) => {
+                    self.clear();
+                    return Some(SearchKey::Edited);
+                }
+                Some(Keysym::Return | Keysym::KP_Enter) => return Some(SearchKey::Accept),
+                Some(Keysym::BackSpace) => {
+                    self.query.pop();
+                    return Some(SearchKey::Edited);
+                }
+                _ => (),
+            }
+        }
+
+        let c = modified.key_char().filter(|c| !c.is_control())?;
+        // Don't start searching with a space.
+        if self.query.is_empty() && c.is_whitespace() {
+            return None;
+        }
+
+        self.query.push(c);
+        Some(SearchKey::Edited)
+    }
+
+    /// Renders the search box and the thumbnail of the best match, if it was rendered already.
+    pub fn render_output<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+        layout: &Layout<Mapped>,
+        preview: Option<&Thumbnail<TextureBuffer<GlesTexture>>>,
+    ) -> Vec<PrimaryGpuTextureRenderElement> {
+        let mut elements = Vec::new();
+
+        if !self.is_active() || !layout.is_overview_open() {
+            return elements;
+        }
+
+        if layout.active_output() != Some(output) {
+            return elements;
+        }
+
+        let text = box_text(&self.query, self.match_count);
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let buffer = self.buffers.get_or_render(output, text.clone(), |scale| {
+            render(renderer.as_gles_renderer(), &text, scale)
+                .map_err(|err| warn!("error rendering overview search box: {err:?}"))
+                .ok()
+        });
+        let Some(buffer) = buffer else {
+            return elements;
+        };
+
+        let size = buffer.logical_size();
+        let x = (output_size.w - size.w) / 2.;
+        let y = f64::from(MARGIN);
+        let location = Point::from((x, y))
+            .to_physical_precise_round(scale)
+            .to_logical(scale);
+        let preview_y = y + size.h + f64::from(PADDING);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+        elements.push(PrimaryGpuTextureRenderElement(elem));
+
+        if let Some(preview) = preview {
+            let size = preview.texture.logical_size();
+            let x = (output_size.w - size.w) / 2.;
+            let location = Point::from((x, preview_y))
+                .to_physical_precise_round(scale)
+                .to_logical(scale);
+
+            let elem = TextureRenderElement::from_texture_buffer(
+                preview.texture.clone(),
+                location,
+                1.,
+                None,
+                None,
+                Kind::Unspecified,
+            );
+            elements.push(PrimaryGpuTextureRenderElement(elem));
+        }
+
+        elements
+    }
+}
+
+impl Default for OverviewSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Scores how well `query` fuzzy-matches `text`, higher is better.
+///
+/// The characters of every whitespace-separated word of the query must appear in the text in
+/// order, though not necessarily contiguously. Matches at word starts and runs of consecutive characters score
+/// higher. Returns `None` if the text doesn't match.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+
+    let mut score = 0;
+    for word in query.split_whitespace() {
+        score += word_score(word, &text)?;
+    }
+    Some(score)
+}
+
+/// Scores the best match of a single query word, trying every place where it could start.
+fn word_score(word: &str, text: &[char]) -> Option<i32> {
+    let word: Vec<char> = word.chars().flat_map(char::to_lowercase).collect();
+    let first = *word.first()?;
+
+    (0..text.len())
+        .filter(|&start| text[start] == first)
+        .filter_map(|start| match_from(&word, text, start))
+        .max()
+}
+
+/// Matches `word` greedily with its first character at `start`.
+fn match_from(word: &[char], text: &[char], start: usize) -> Option<i32> {
+    let mut score = 0;
+    let mut pos = start;
+    let mut prev_match = None;
+
+    for c in word {
+        let idx = pos + text[pos..].iter().position(|t| t == c)?;
+
+        score += 1;
+        if prev_match.is_some_and(|prev| prev + 1 == idx) {
+            score += 4;
+        }
+        if idx == 0 || !text[idx - 1].is_alphanumeric() {
+            score += 6;
+        }
+
+        prev_match = Some(idx);
+        pos = idx + 1;
+    }
+
+    Some(score)
+}
+
+fn box_text(query: &str, match_count: usize) -> String {
+    let query = pango::glib::markup_escape_text(query);
+    let matches = match match_count {
+        0 => String::from("no matches"),
+        1 => String::from("1 match"),
+        n => format!("{n} matches"),
+    };
+    format!("<b>Search:</b> {query}  <span alpha=\"60%\">{matches}</span>")
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    text: &str,
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("overview_search::render");
+
+    panel::render_text(renderer, text, Alignment::Left, PADDING, panel::INFO, scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matching() {
+        assert_eq!(fuzzy_score("ff", "Mozilla Firefox"), Some(8));
+        assert_eq!(fuzzy_score("fox", "firefox"), Some(13));
+        assert_eq!(fuzzy_score("xof", "firefox"), None);
+
+        // Every word must match.
+        assert!(fuzzy_score("fire term", "firefox").is_none());
+        assert!(fuzzy_score("fire term", "Alacritty terminal: firefox").is_some());
+
+        // Word starts and consecutive characters rank higher.
+        let start = fuzzy_score("term", "foot terminal").unwrap();
+        let scattered = fuzzy_score("term", "the extra room").unwrap();
+        assert!(start > scattered);
+    }
+
+    #[test]
+    fn typing_edits_query() {
+        let mut search = OverviewSearch::new();
+        let none = Modifiers::empty();
+
+        // Keys that don't type anything are left alone.
+        assert_eq!(search.on_key(Some(Keysym::Left), Keysym::Left, none), None);
+        assert_eq!(
+            search.on_key(Some(Keysym::space), Keysym::space, none),
+            None
+        );
+        assert_eq!(
+            search.on_key(Some(Keysym::Escape), Keysym::Escape, none),
+            None
+        );
+
+        let edited = Some(SearchKey::Edited);
+        assert_eq!(
+            search.on_key(Some(Keysym::f), Keysym::F, Modifiers::SHIFT),
+            edited
+        );
+        assert_eq!(search.on_key(Some(Keysym::o), Keysym::o, none), edited);
+        assert_eq!(
+            search.on_key(Some(Keysym::o), Keysym::o, Modifiers::CTRL),
+            None
+        );
+        assert_eq!(search.query(), "Fo");
+
+        assert_eq!(
+            search.on_key(Some(Keysym::BackSpace), Keysym::BackSpace, none),
+            edited
+        );
+        assert_eq!(search.query(), "F");
+
+        let accept = search.on_key(Some(Keysym::Return), Keysym::Return, none);
+        assert_eq!(accept, Some(SearchKey::Accept));
+
+        assert_eq!(
+            search.on_key(Some(Keysym::Escape), Keysym::Escape, none),
+            edited
+        );
+        assert!(!search.is_active());
+    }
+
+    #[test]
+    fn best_match_only_while_searching() {
+        let mut search = OverviewSearch::new();
+        search.set_matches(3, Some(7));
+        assert_eq!(search.best_match(), None);
+
+        let none = Modifiers::empty();
+        search.on_key(Some(Keysym::a), Keysym::a, none);
+        assert_eq!(search.best_match(), Some(7));
+
+        search.clear();
+        assert_eq!(search.best_match(), None);
+    }
+}
@@ -0,0 +1,151 @@
+use pango::{Alignment, FontDescription};
+use pangocairo::cairo::{self, ImageSurface};
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::utils::Transform;
+
+use crate::render_helpers::texture::TextureBuffer;
+use crate::utils::to_physical_precise_round;
+
+const FONT: &str = "sans 14px";
+const BORDER: i32 = 4;
+const BACKGROUND: [f64; 3] = [0.1, 0.1, 0.1];
+const TEXT: [f64; 3] = [1., 1., 1.];
+
+/// Border color of informational panels.
+pub const INFO: [f64; 3] = [0.5, 0.8, 1.0];
+/// Border color of panels about errors.
+pub const ERROR: [f64; 3] = [1., 0.3, 0.3];
+
+/// Returns the panel font at the output scale.
+pub fn font(scale: f64) -> FontDescription {
+    let mut font = FontDescription::from_string(FONT);
+    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
+    font
+}
+
+/// Creates a text layout for drawing on `cr` with the panel font.
+pub fn create_layout(
+    cr: &cairo::Context,
+    font: &FontDescription,
+    alignment: Alignment,
+) -> pango::Layout {
+    let layout = pangocairo::functions::create_layout(cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(font));
+    layout.set_alignment(alignment);
+    layout
+}
+
+/// Creates a text layout for measuring text before the panel size is known.
+pub fn measuring_layout(
+    font: &FontDescription,
+    alignment: Alignment,
+) -> anyhow::Result<pango::Layout> {
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
+    let cr = cairo::Context::new(&surface)?;
+    Ok(create_layout(&cr, font, alignment))
+}
+
+/// Draws `layout` at `(x, y)` in the panel text color.
+pub fn show_layout(cr: &cairo::Context, layout: &pango::Layout, x: i32, y: i32) {
+    let [r, g, b] = TEXT;
+    cr.set_source_rgb(r, g, b);
+    cr.move_to(x.into(), y.into());
+    pangocairo::functions::show_layout(cr, layout);
+}
+
+/// Renders a panel of `width` × `height` physical pixels.
+///
+/// Paints the background, lets `draw` draw the contents, then strokes the border.
+pub fn render(
+    renderer: &mut GlesRenderer,
+    width: i32,
+    height: i32,
+    border_color: [f64; 3],
+    scale: f64,
+    draw: impl FnOnce(&cairo::Context) -> anyhow::Result<()>,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    let [r, g, b] = BACKGROUND;
+    cr.set_source_rgb(r, g, b);
+    cr.paint()?;
+
+    draw(&cr)?;
+
+    cr.move_to(0., 0.);
+    cr.line_to(width.into(), 0.);
+    cr.line_to(width.into(), height.into());
+    cr.line_to(0., height.into());
+    cr.line_to(0., 0.);
+    let [r, g, b] = border_color;
+    cr.set_source_rgb(r, g, b);
+    // Keep the border width even to avoid blurry edges.
+    cr.set_line_width(border_width(scale));
+    cr.stroke()?;
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok(buffer)
+}
+
+/// Renders a panel showing `markup` with `padding` logical pixels around it.
+pub fn render_text(
+    renderer: &mut GlesRenderer,
+    markup: &str,
+    alignment: Alignment,
+    padding: i32,
+    border_color: [f64; 3],
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let padding: i32 = to_physical_precise_round(scale, padding);
+    let font = font(scale);
+
+    let layout = measuring_layout(&font, alignment)?;
+    layout.set_markup(markup);
+    let (width, height) = layout.pixel_size();
+
+    render(
+        renderer,
+        width + padding * 2,
+        height + padding * 2,
+        border_color,
+        scale,
+        |cr| {
+            let layout = create_layout(cr, &font, alignment);
+            layout.set_markup(markup);
+            show_layout(cr, &layout, padding, padding);
+            Ok(())
+        },
+    )
+}
+
+/// Returns the border line width in physical pixels.
+fn border_width(scale: f64) -> f64 {
+    (f64::from(BORDER) / 2. * scale).round() * 2.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn border_width_is_even() {
+        assert_eq!(border_width(1.), 4.);
+        assert_eq!(border_width(1.25), 6.);
+        assert_eq!(border_width(1.5), 6.);
+        assert_eq!(border_width(2.), 8.);
+    }
+}
@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+use niri_ipc::ScreenTransitionStyle;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::GlesTexture;
+use smithay::utils::{Logical, Point, Rectangle, Scale, Size, Transform};
+
+use crate::animation::Clock;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::render_helpers::RenderTarget;
+
+pub const DEFAULT_DELAY: Duration = Duration::from_millis(250);
+pub const DEFAULT_DURATION: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+pub struct ScreenTransition {
+    /// Texture to transition from for each render target.
+    from_texture: [TextureBuffer<GlesTexture>; 3],
+    /// Monotonic time when to start the transition.
+    start_at: Duration,
+    /// Duration of the transition once started.
+    duration: Duration,
+    /// How the old contents give way to the new ones.
+    style: ScreenTransitionStyle,
+    /// Current transition progress, from 0 (old contents) to 1 (new contents).
+    progress: f64,
+    /// Clock to drive animations.
+    clock: Clock,
+}
+
+impl ScreenTransition {
+    pub fn new(
+        from_texture: [TextureBuffer<GlesTexture>; 3],
+        delay: Duration,
+        duration: Duration,
+        style: ScreenTransitionStyle,
+        clock: Clock,
+    ) -> Self {
+        Self {
+            from_texture,
+            start_at: clock.now_unadjusted() + delay,
+            duration,
+            style,
+            progress: 0.,
+            clock,
+        }
+    }
+
+    pub fn advance_animations(&mut self) {
+        let now = self.clock.now_unadjusted();
+        self.progress = progress(now, self.start_at, self.duration);
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.progress >= 1.
+    }
+
+    pub fn update_render_elements(&mut self, scale: Scale<f64>, transform: Transform) {
+        // These textures should remain full-screen, even if scale or transform changes.
+        for buffer in &mut self.from_texture {
+            buffer.set_texture_scale(scale);
+            buffer.set_texture_transform(transform);
+        }
+    }
+
+    pub fn render(&self, target: RenderTarget) -> Option<PrimaryGpuTextureRenderElement> {
+        if self.is_done() {
+            return None;
+        }
+
+        let idx = match target {
+            RenderTarget::Output => 0,
+            RenderTarget::Screencast => 1,
+            RenderTarget::ScreenCapture => 2,
+        };
+        let buffer = self.from_texture[idx].clone();
+
+        let (alpha, src) = match self.style {
+            ScreenTransitionStyle::Crossfade => (1. - self.progress as f32, None),
+            style => {
+                let src = wipe_remaining(style, buffer.logical_size(), self.progress);
+                if src.is_empty() {
+                    return None;
+                }
+                (1., Some(src))
+            }
+        };
+
+        let location = src.map_or(Point::from((0., 0.)), |src| src.loc);
+        Some(PrimaryGpuTextureRenderElement(
+            TextureRenderElement::from_texture_buffer(
+                buffer,
+                location,
+                alpha,
+                src,
+                src.map(|src| src.size),
+                Kind::Unspecified,
+            ),
+        ))
+    }
+}
+
+fn progress(now: Duration, start_at: Duration, duration: Duration) -> f64 {
+    if now < start_at {
+        0.
+    } else if duration.is_zero() {
+        1.
+    } else {
+        ((now - start_at).as_secs_f64() / duration.as_secs_f64()).min(1.)
+    }
+}
+
+/// Returns the part of the old contents still visible during a wipe.
+fn wipe_remaining(
+    style: ScreenTransitionStyle,
+    size: Size<f64, Logical>,
+    progress: f64,
+) -> Rectangle<f64, Logical> {
+    let keep = 1. - progress.clamp(0., 1.);
+    let (w, h) = (size.w, size.h);
+
+    let (loc, size) = match style {
+        ScreenTransitionStyle::Crossfade => ((0., 0.), (w, h)),
+        // The new contents come in from the right edge.
+        ScreenTransitionStyle::WipeLeft => ((0., 0.), (w * keep, h)),
+        // The new contents come in from the left edge.
+        ScreenTransitionStyle::WipeRight => ((w * (1. - keep), 0.), (w * keep, h)),
+        // The new contents come in from the bottom edge.
+        ScreenTransitionStyle::WipeUp => ((0., 0.), (w, h * keep)),
+        // The new contents come in from the top edge.
+        ScreenTransitionStyle::WipeDown => ((0., h * (1. - keep)), (w, h * keep)),
+    };
+
+    Rectangle::new(Point::from(loc), Size::from(size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_waits_for_delay() {
+        let ms = Duration::from_millis;
+        assert_eq!(progress(ms(100), ms(200), ms(500)), 0.);
+        assert_eq!(progress(ms(450), ms(200), ms(500)), 0.5);
+        assert_eq!(progress(ms(900), ms(200), ms(500)), 1.);
+        assert_eq!(progress(ms(200), ms(200), Duration::ZERO), 1.);
+    }
+
+    #[test]
+    fn wipe_shrinks_towards_edge() {
+        let size = Size::from((100., 50.));
+
+        let rect = wipe_remaining(ScreenTransitionStyle::WipeLeft, size, 0.25);
+        assert_eq!(
+            rect,
+            Rectangle::new(Point::from((0., 0.)), Size::from((75., 50.)))
+        );
+
+        let rect = wipe_remaining(ScreenTransitionStyle::WipeRight, size, 0.25);
+        assert_eq!(
+            rect,
+            Rectangle::new(Point::from((25., 0.)), Size::from((75., 50.)))
+        );
+
+        let rect = wipe_remaining(ScreenTransitionStyle::WipeDown, size, 1.);
+        assert!(rect.is_empty());
+    }
+}
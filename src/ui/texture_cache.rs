@@ -0,0 +1,101 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use smithay::backend::renderer::gles::GlesTexture;
+use smithay::output::{Output, WeakOutput};
+use smithay::utils::Transform;
+
+use crate::render_helpers::texture::TextureBuffer;
+
+/// Bumped to invalidate every cache at once, for example when the config changes.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Per-output cache of a texture rendered by a UI overlay.
+///
+/// A texture is re-rendered when its key changes, or when the output scale or transform changes.
+/// The key describes the contents, for example the text that the texture shows.
+pub struct TextureCache<K = ()> {
+    entries: RefCell<HashMap<WeakOutput, Entry<K>>>,
+}
+
+struct Entry<K> {
+    key: K,
+    scale: f64,
+    transform: Transform,
+    generation: u64,
+    /// Rendered texture, or `None` if rendering failed.
+    buffer: Option<TextureBuffer<GlesTexture>>,
+}
+
+impl<K: PartialEq> TextureCache<K> {
+    pub fn new() -> Self {
+        Self {
+            entries: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the texture for this output, rendering it if the cached one is out of date.
+    ///
+    /// `render` receives the output scale. If it fails, it should log the error and return
+    /// `None`; the failure is cached like a texture so that it isn't retried every frame.
+    pub fn get_or_render(
+        &self,
+        output: &Output,
+        key: K,
+        render: impl FnOnce(f64) -> Option<TextureBuffer<GlesTexture>>,
+    ) -> Option<TextureBuffer<GlesTexture>> {
+        let scale = output.current_scale().fractional_scale();
+        let transform = output.current_transform();
+        let generation = GENERATION.load(Ordering::Relaxed);
+
+        let mut entries = self.entries.borrow_mut();
+        entries.retain(|output, _| output.upgrade().is_some());
+
+        let weak = output.downgrade();
+        if let Some(entry) = entries.get(&weak) {
+            let up_to_date = entry.key == key
+                && entry.scale == scale
+                && entry.transform == transform
+                && entry.generation == generation;
+            if up_to_date {
+                return entry.buffer.clone();
+            }
+        }
+
+        let buffer = render(scale);
+        entries.insert(
+            weak,
+            Entry {
+                key,
+                scale,
+                transform,
+                generation,
+                buffer: buffer.clone(),
+            },
+        );
+        buffer
+    }
+
+    /// Returns the last texture rendered for this output, if any.
+    pub fn get(&self, output: &Output) -> Option<TextureBuffer<GlesTexture>> {
+        let entries = self.entries.borrow();
+        entries.get(&output.downgrade())?.buffer.clone()
+    }
+
+    /// Drops all textures, so that they are re-rendered on next use.
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+}
+
+impl<K: PartialEq> Default for TextureCache<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Invalidates the textures in every cache, so that they are re-rendered on next use.
+pub fn invalidate_all() {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+}
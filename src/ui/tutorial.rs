@@ -0,0 +1,410 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use directories::BaseDirs;
+use niri_config::{Action, Config, ModKey};
+use pango::Alignment;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+
+use crate::input::bind_conflicts::format_key;
+use crate::layout::Layout;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::ui::panel;
+use crate::ui::texture_cache::TextureCache;
+use crate::utils::output_size;
+use crate::window::Mapped;
+
+const PADDING: i32 = 16;
+
+/// Number of columns in the animated column navigation hint.
+const HINT_COLUMNS: usize = 4;
+
+/// Number of frames in the animated hint: right to the last column, then back.
+const HINT_FRAMES: usize = HINT_COLUMNS * 2 - 2;
+
+/// How long the animated hint stays on each column.
+pub const HINT_INTERVAL: Duration = Duration::from_millis(600);
+
+const HIGHLIGHT: &str = "#7fc8ff";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    ModKey,
+    Columns,
+    Binds,
+    Done,
+}
+
+const STEPS: [Step; 4] = [Step::ModKey, Step::Columns, Step::Binds, Step::Done];
+
+/// Guided overlay that introduces new users to the basic binds.
+///
+/// Shown once at startup, unless a previous run already showed it. It can be brought back with
+/// the `show-tutorial` action.
+pub struct Tutorial {
+    step: Option<usize>,
+    config: Rc<RefCell<Config>>,
+    mod_key: ModKey,
+    /// Frame of the animated column navigation hint.
+    ///
+    /// The hint moves in discrete steps, so it's advanced by a timer every [`HINT_INTERVAL`]
+    /// rather than redrawn continuously.
+    hint_frame: usize,
+    /// Step textures, keyed by the step and the hint animation frame.
+    buffers: TextureCache<(usize, usize)>,
+}
+
+impl Tutorial {
+    pub fn new(config: Rc<RefCell<Config>>, mod_key: ModKey) -> Self {
+        Self {
+            step: None,
+            config,
+            mod_key,
+            hint_frame: 0,
+            buffers: TextureCache::new(),
+        }
+    }
+
+    /// Opens the tutorial if it was never shown before and isn't disabled in the config.
+    pub fn show_at_startup(&mut self) -> bool {
+        if self.config.borrow().tutorial.skip_at_startup {
+            return false;
+        }
+
+        let Some(path) = state_file_path() else {
+            return false;
+        };
+        if path.exists() {
+            return false;
+        }
+
+        // Record that the tutorial was shown right away, so that it doesn't come back even if
+        // the session ends before it is dismissed.
+        if let Some(parent) = path.parent() {
+            if let Err(err) = fs::create_dir_all(parent) {
+                warn!("error creating {parent:?}: {err:?}");
+                return false;
+            }
+        }
+        if let Err(err) = fs::write(&path, "") {
+            warn!("error writing {path:?}: {err:?}");
+            return false;
+        }
+
+        self.show()
+    }
+
+    pub fn show(&mut self) -> bool {
+        if self.step.is_none() {
+            self.step = Some(0);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn close(&mut self) -> bool {
+        if self.step.is_some() {
+            self.step = None;
+            self.buffers.clear();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.step.is_some()
+    }
+
+    /// Advances to the next step, closing the tutorial after the last one.
+    pub fn next(&mut self) -> bool {
+        let Some(step) = self.step else {
+            return false;
+        };
+
+        if step + 1 < STEPS.len() {
+            self.step = Some(step + 1);
+            true
+        } else {
+            self.close()
+        }
+    }
+
+    pub fn previous(&mut self) -> bool {
+        match self.step {
+            Some(step) if step > 0 => {
+                self.step = Some(step - 1);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Moves the animated hint to its next frame.
+    ///
+    /// Returns `true` if the current step shows the hint and needs a redraw.
+    pub fn advance_hint(&mut self) -> bool {
+        if self.step.map(|step| STEPS[step]) != Some(Step::Columns) {
+            return false;
+        }
+
+        self.hint_frame = (self.hint_frame + 1) % HINT_FRAMES;
+        true
+    }
+
+    /// Drops the rendered contents, to be called when the binds change.
+    pub fn on_config_updated(&mut self, mod_key: ModKey) {
+        self.mod_key = mod_key;
+        self.buffers.clear();
+    }
+
+    pub fn render_output<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+        layout: &Layout<Mapped>,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let step = self.step?;
+
+        if layout.active_output() != Some(output) {
+            return None;
+        }
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let hint_frame = if STEPS[step] == Step::Columns {
+            self.hint_frame
+        } else {
+            0
+        };
+
+        let key = (step, hint_frame);
+        let buffer = self.buffers.get_or_render(output, key, |scale| {
+            let text = step_text(step, self.mod_key, &self.config.borrow(), hint_frame);
+            render(renderer.as_gles_renderer(), &text, scale)
+                .map_err(|err| warn!("error rendering tutorial: {err:?}"))
+                .ok()
+        })?;
+
+        let size = buffer.logical_size();
+        let location = (output_size.to_point() - size.to_point()).downscale(2.);
+        let mut location = location.to_physical_precise_round(scale).to_logical(scale);
+        location.x = f64::max(0., location.x);
+        location.y = f64::max(0., location.y);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+fn state_file_path() -> Option<PathBuf> {
+    let dirs = BaseDirs::new()?;
+    Some(dirs.state_dir()?.join("niri").join("tutorial-shown"))
+}
+
+fn mod_key_name(mod_key: ModKey) -> &'static str {
+    match mod_key {
+        ModKey::Ctrl => "Ctrl",
+        ModKey::Shift => "Shift",
+        ModKey::Alt => "Alt",
+        ModKey::Super => "Super",
+        ModKey::IsoLevel3Shift => "ISO_Level3_Shift",
+        ModKey::IsoLevel5Shift => "ISO_Level5_Shift",
+    }
+}
+
+/// Returns the markup for the first key bound to the action.
+fn key_for(config: &Config, pred: impl Fn(&Action) -> bool) -> Option<String> {
+    let bind = config.binds.0.iter().find(|bind| pred(&bind.action))?;
+    let key = markup_escape(&format_key(bind.key));
+    Some(format!(
+        "<span foreground=\"{HIGHLIGHT}\"><b>{key}</b></span>"
+    ))
+}
+
+fn step_text(step: usize, mod_key: ModKey, config: &Config, hint_frame: usize) -> String {
+    let mut lines = Vec::new();
+
+    match STEPS[step] {
+        Step::ModKey => {
+            let name = mod_key_name(mod_key);
+            lines.push(String::from("<b>Welcome to niri!</b>"));
+            lines.push(String::new());
+            lines.push(String::from(
+                "Most shortcuts start with the <b>Mod</b> key.",
+            ));
+            lines.push(format!(
+                "Right now, Mod is <span background=\"{HIGHLIGHT}\" foreground=\"#1a1a1a\"> \
+                 <b>{name}</b> </span>."
+            ));
+        }
+        Step::Columns => {
+            lines.push(String::from("<b>Columns</b>"));
+            lines.push(String::new());
+            lines.push(String::from(
+                "Windows are arranged in columns on an infinite strip.",
+            ));
+            lines.push(String::from("Opening a window never resizes the others."));
+            lines.push(String::new());
+            lines.push(column_hint(hint_frame));
+            lines.push(String::new());
+
+            let left = key_for(config, |a| matches!(a, Action::FocusColumnLeft));
+            let right = key_for(config, |a| matches!(a, Action::FocusColumnRight));
+            match (left, right) {
+                (Some(left), Some(right)) => {
+                    lines.push(format!("Press {left} and {right} to move between columns."));
+                }
+                _ => lines.push(String::from(
+                    "Bind focus-column-left and focus-column-right to move between columns.",
+                )),
+            }
+        }
+        Step::Binds => {
+            lines.push(String::from("<b>A few useful binds</b>"));
+            lines.push(String::new());
+
+            let binds: [(&str, fn(&Action) -> bool); 4] = [
+                ("Open a program", |a| matches!(a, Action::Spawn(_))),
+                ("Close the focused window", |a| {
+                    matches!(a, Action::CloseWindow)
+                }),
+                ("Switch to the workspace below", |a| {
+                    matches!(a, Action::FocusWorkspaceDown)
+                }),
+                ("Toggle the overview", |a| {
+                    matches!(a, Action::ToggleOverview)
+                }),
+            ];
+            for (title, pred) in binds {
+                if let Some(key) = key_for(config, pred) {
+                    lines.push(format!("{key}  {title}"));
+                }
+            }
+        }
+        Step::Done => {
+            lines.push(String::from("<b>You're all set!</b>"));
+            lines.push(String::new());
+            lines.push(String::from(
+                "All binds live in the config file, which niri reloads on save.",
+            ));
+            lines.push(String::from(
+                "You can bring this tutorial back with the show-tutorial action.",
+            ));
+        }
+    }
+
+    lines.push(String::new());
+    lines.push(format!(
+        "<span alpha=\"60%\">{} of {} · Enter: next · Backspace: back · Escape: close</span>",
+        step + 1,
+        STEPS.len(),
+    ));
+
+    lines.join("\n")
+}
+
+/// Returns the index of the focused column in the given frame of the hint animation.
+///
+/// The focus moves right to the last column, then back left to the first one.
+fn hint_focused_column(frame: usize) -> usize {
+    if frame < HINT_COLUMNS {
+        frame
+    } else {
+        HINT_FRAMES - frame
+    }
+}
+
+/// Draws a row of columns with the focus bouncing between them.
+fn column_hint(frame: usize) -> String {
+    let focused = hint_focused_column(frame);
+
+    let columns: Vec<_> = (0..HINT_COLUMNS)
+        .map(|idx| {
+            if idx == focused {
+                format!("<span foreground=\"{HIGHLIGHT}\">█</span>")
+            } else {
+                String::from("<span alpha=\"40%\">█</span>")
+            }
+        })
+        .collect();
+
+    format!(
+        "<span size=\"200%\" font_family=\"monospace\">{}</span>",
+        columns.join(" ")
+    )
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    text: &str,
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("tutorial::render");
+
+    panel::render_text(
+        renderer,
+        text,
+        Alignment::Center,
+        PADDING,
+        panel::INFO,
+        scale,
+    )
+}
+
+fn markup_escape(text: &str) -> String {
+    pango::glib::markup_escape_text(text).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hint_focus_bounces() {
+        let focused: Vec<_> = (0..HINT_FRAMES).map(hint_focused_column).collect();
+        assert_eq!(focused, [0, 1, 2, 3, 2, 1]);
+    }
+
+    #[test]
+    fn hint_advances_only_on_columns_step() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let mut tutorial = Tutorial::new(config, ModKey::Super);
+
+        // Closed.
+        assert!(!tutorial.advance_hint());
+
+        // Mod key step has no hint.
+        tutorial.show();
+        assert!(!tutorial.advance_hint());
+
+        tutorial.next();
+        assert_eq!(STEPS[tutorial.step.unwrap()], Step::Columns);
+        for frame in 1..HINT_FRAMES {
+            assert!(tutorial.advance_hint());
+            assert_eq!(tutorial.hint_frame, frame);
+        }
+        assert!(tutorial.advance_hint());
+        assert_eq!(tutorial.hint_frame, 0);
+
+        tutorial.next();
+        assert!(!tutorial.advance_hint());
+    }
+}
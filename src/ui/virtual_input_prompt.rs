@@ -0,0 +1,140 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use pango::Alignment;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::ui::panel;
+use crate::ui::texture_cache::TextureCache;
+use crate::utils::output_size;
+
+const PADDING: i32 = 16;
+
+/// Asks the user whether a program may inject input through virtual devices.
+pub struct VirtualInputPrompt {
+    /// Programs waiting for an answer, the first one is on screen.
+    queue: VecDeque<PromptRequest>,
+    /// Prompt textures, keyed by the number of queued prompts that they show.
+    buffers: TextureCache<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptRequest {
+    pub pid: i32,
+    pub executable: PathBuf,
+}
+
+impl VirtualInputPrompt {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            buffers: TextureCache::new(),
+        }
+    }
+
+    /// Queues a prompt for this program, returns `true` if it wasn't queued yet.
+    pub fn request(&mut self, request: PromptRequest) -> bool {
+        if self
+            .queue
+            .iter()
+            .any(|r| r.executable == request.executable)
+        {
+            return false;
+        }
+
+        self.queue.push_back(request);
+        true
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    /// Removes the prompt currently on screen, to be called once the user has answered it.
+    pub fn pop(&mut self) -> Option<PromptRequest> {
+        self.buffers.clear();
+        self.queue.pop_front()
+    }
+
+    /// Drops all queued prompts without answering them, leaving those programs undecided.
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.buffers.clear();
+    }
+
+    pub fn render_output<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let request = self.queue.front()?;
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let count = self.queue.len();
+        let buffer = self.buffers.get_or_render(output, count, |scale| {
+            render(renderer.as_gles_renderer(), request, count, scale)
+                .map_err(|err| warn!("error rendering virtual input prompt: {err:?}"))
+                .ok()
+        })?;
+
+        let size = buffer.logical_size();
+        let location = (output_size.to_point() - size.to_point()).downscale(2.);
+        let location = location.to_physical_precise_round(scale).to_logical(scale);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+impl Default for VirtualInputPrompt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    request: &PromptRequest,
+    queued: usize,
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("virtual_input_prompt::render");
+
+    let executable = pango::glib::markup_escape_text(&request.executable.to_string_lossy());
+    let mut text = format!(
+        "<b>{executable}</b> (PID {}) wants to control\n\
+         your pointer and keyboard.\n\n\
+         Press <b>Y</b> to allow or <b>N</b> to deny.",
+        request.pid
+    );
+    if queued > 1 {
+        text.push_str(&format!(
+            "\n<span alpha=\"60%\">{} more requests waiting</span>",
+            queued - 1
+        ));
+    }
+
+    panel::render_text(
+        renderer,
+        &text,
+        Alignment::Center,
+        PADDING,
+        [1., 0.8, 0.3],
+        scale,
+    )
+}
@@ -0,0 +1,631 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::SystemTime;
+
+use anyhow::{ensure, Context};
+use calloop::channel::Sender;
+use gdk_pixbuf::Pixbuf;
+use niri_config::{Config, Wallpaper};
+use niri_ipc::WallpaperMode;
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::utils::{Buffer, Logical, Point, Rectangle, Size, Transform};
+
+use crate::animation::{Animation, Clock, Curve};
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::expand_home;
+
+/// Wallpaper images shared between all outputs and workspaces.
+///
+/// Every image is decoded once per path on a worker thread, then uploaded at its own pixel size.
+/// Outputs with different scales and sizes then crop and scale the same texture when rendering.
+/// Any format supported by gdk-pixbuf works.
+#[derive(Debug)]
+pub struct Wallpapers {
+    /// Images by configured path.
+    images: HashMap<String, Image>,
+    /// Decoded images waiting for upload.
+    decoded: Vec<Decoded>,
+    /// Sends decoded images from the worker threads back to the event loop.
+    decoded_sender: Sender<Decoded>,
+    /// Id for the next decode, to tell stale results apart.
+    next_decode_id: u64,
+    /// Wallpapers set at runtime, by output connector name.
+    overrides: HashMap<String, Wallpaper>,
+    /// Ongoing crossfades to a new wallpaper, by output connector name.
+    crossfades: HashMap<String, Crossfade>,
+    /// Whether some wallpapers may need loading.
+    needs_load: bool,
+    clock: Clock,
+}
+
+#[derive(Debug, Default)]
+struct Image {
+    /// Uploaded texture, `None` until the image is loaded or if loading failed.
+    texture: Option<TextureBuffer<GlesTexture>>,
+    /// Whether the image was loaded at least once, successfully or not.
+    loaded: bool,
+    /// Modification time of the file at the last load.
+    ///
+    /// The image is loaded again when this changes, so editing the file and reloading the config
+    /// shows the new image.
+    modified: Option<SystemTime>,
+    /// Id of the ongoing decode, if any.
+    loading: Option<u64>,
+}
+
+/// An image decoded on a worker thread.
+#[derive(Debug)]
+pub struct Decoded {
+    path: String,
+    id: u64,
+    modified: Option<SystemTime>,
+    image: anyhow::Result<(Vec<u8>, Size<i32, Buffer>)>,
+}
+
+#[derive(Debug)]
+struct Crossfade {
+    /// Wallpaper shown before the change, if any.
+    from: Option<(TextureBuffer<GlesTexture>, WallpaperMode)>,
+    anim: Animation,
+}
+
+impl Wallpapers {
+    pub fn new(clock: Clock, decoded_sender: Sender<Decoded>) -> Self {
+        Self {
+            images: HashMap::new(),
+            decoded: Vec::new(),
+            decoded_sender,
+            next_decode_id: 0,
+            overrides: HashMap::new(),
+            crossfades: HashMap::new(),
+            needs_load: true,
+            clock,
+        }
+    }
+
+    /// Returns the wallpaper set at runtime for the output, if any.
+    pub fn override_for(&self, output_name: &str) -> Option<&Wallpaper> {
+        self.overrides.get(output_name)
+    }
+
+    /// Sets the wallpaper for the output at runtime.
+    ///
+    /// `from` is the wallpaper currently shown on the output. When `crossfade_ms` is set, the
+    /// new wallpaper fades in over it.
+    pub fn set_override(
+        &mut self,
+        output_name: String,
+        wallpaper: Wallpaper,
+        from: Option<&Wallpaper>,
+        crossfade_ms: Option<u16>,
+    ) {
+        if let Some(ms) = crossfade_ms.filter(|ms| *ms > 0) {
+            let from = from.and_then(|from| Some((self.texture(from)?.clone(), from.mode)));
+            let anim = Animation::ease(
+                self.clock.clone(),
+                0.,
+                1.,
+                0.,
+                u64::from(ms),
+                Curve::EaseOutCubic,
+            );
+            self.crossfades
+                .insert(output_name.clone(), Crossfade { from, anim });
+        } else {
+            self.crossfades.remove(&output_name);
+        }
+
+        // Setting the same path again reloads the image if it changed on disk.
+        self.overrides.insert(output_name, wallpaper);
+        self.needs_load = true;
+    }
+
+    /// Marks that the configured wallpapers may have changed.
+    pub fn mark_needs_load(&mut self) {
+        self.needs_load = true;
+    }
+
+    /// Drops the loaded images so that they are loaded again, for example after a GPU reset.
+    pub fn clear_textures(&mut self) {
+        self.images.clear();
+        self.decoded.clear();
+        self.needs_load = true;
+    }
+
+    pub fn needs_load(&self) -> bool {
+        self.needs_load
+    }
+
+    /// Queues an image decoded on a worker thread for upload.
+    pub fn on_decoded(&mut self, decoded: Decoded) {
+        self.decoded.push(decoded);
+        self.needs_load = true;
+    }
+
+    /// Starts loading the images used by the config and the runtime overrides that are missing
+    /// or changed on disk, uploads the decoded ones, and drops unused ones.
+    pub fn load(&mut self, renderer: &mut GlesRenderer, config: &Config) {
+        let _span = tracy_client::span!("Wallpapers::load");
+
+        let configured = config
+            .outputs
+            .0
+            .iter()
+            .filter_map(|output| output.wallpaper.as_ref())
+            .chain(
+                config
+                    .workspaces
+                    .iter()
+                    .filter_map(|ws| ws.wallpaper.as_ref()),
+            )
+            .chain(self.overrides.values());
+        let paths: Vec<_> = configured.map(|wallpaper| wallpaper.path.clone()).collect();
+
+        self.images.retain(|path, _| paths.contains(path));
+
+        for decoded in self.decoded.drain(..) {
+            let Some(image) = self.images.get_mut(&decoded.path) else {
+                continue;
+            };
+            // A newer decode of the same path has started since.
+            if image.loading != Some(decoded.id) {
+                continue;
+            }
+
+            image.loading = None;
+            image.loaded = true;
+            image.modified = decoded.modified;
+            let res = decoded
+                .image
+                .and_then(|(pixels, size)| upload(renderer, &pixels, size));
+            match res {
+                Ok(texture) => image.texture = Some(texture),
+                Err(err) => {
+                    warn!("error loading wallpaper {:?}: {err:?}", decoded.path);
+                    image.texture = None;
+                }
+            }
+        }
+
+        for path in paths {
+            let image = self.images.entry(path.clone()).or_default();
+            if image.loading.is_some() {
+                continue;
+            }
+
+            let full_path = full_path(&path);
+            let modified = full_path.as_ref().ok().and_then(|path| modified_time(path));
+            // This also keeps failed images from being retried until the file changes.
+            if image.loaded && image.modified == modified {
+                continue;
+            }
+
+            let id = self.next_decode_id;
+            self.next_decode_id += 1;
+            image.loading = Some(id);
+
+            let sender = self.decoded_sender.clone();
+            let res = thread::Builder::new()
+                .name("Wallpaper Loader".to_owned())
+                .spawn(move || {
+                    let _span = tracy_client::span!("wallpaper::decode");
+                    let image = full_path.and_then(|path| decode(&path));
+                    let decoded = Decoded {
+                        path,
+                        id,
+                        modified,
+                        image,
+                    };
+                    let _ = sender.send(decoded);
+                });
+            if let Err(err) = res {
+                warn!("error spawning a thread to load the wallpaper: {err:?}");
+                image.loading = None;
+            }
+        }
+
+        self.needs_load = false;
+    }
+
+    pub fn advance_animations(&mut self) {
+        self.crossfades
+            .retain(|_, crossfade| !crossfade.anim.is_done());
+    }
+
+    pub fn are_animations_ongoing(&self, output_name: &str) -> bool {
+        self.crossfades.contains_key(output_name)
+    }
+
+    /// Renders the wallpaper of an output or workspace, front to back.
+    pub fn render(
+        &self,
+        output_name: &str,
+        wallpaper: Option<&Wallpaper>,
+        output_size: Size<f64, Logical>,
+        scale: f64,
+    ) -> impl Iterator<Item = PrimaryGpuTextureRenderElement> + '_ {
+        let to = wallpaper.and_then(|wallpaper| {
+            let texture = self.texture(wallpaper)?;
+            Some((texture, wallpaper.mode))
+        });
+
+        let crossfade = self.crossfades.get(output_name);
+        let progress = crossfade.map_or(1., |crossfade| crossfade.anim.clamped_value());
+        let from = crossfade.and_then(|crossfade| {
+            let (texture, mode) = crossfade.from.as_ref()?;
+            Some((texture, *mode))
+        });
+
+        let offset = Point::from((0., 0.));
+        render_crossfade(from, to, progress, offset, output_size, scale)
+    }
+
+    /// Renders the crossfade between the wallpapers of two workspaces, front to back.
+    ///
+    /// Used while switching between workspaces with different wallpapers. `offset` is added to
+    /// the wallpaper location, so that it can stay in place on the output while the workspace
+    /// moves.
+    pub fn render_switch(
+        &self,
+        from: Option<&Wallpaper>,
+        to: Option<&Wallpaper>,
+        progress: f64,
+        offset: Point<f64, Logical>,
+        output_size: Size<f64, Logical>,
+        scale: f64,
+    ) -> impl Iterator<Item = PrimaryGpuTextureRenderElement> + '_ {
+        let texture = |wallpaper: Option<&Wallpaper>| {
+            let wallpaper = wallpaper?;
+            Some((self.texture(wallpaper)?, wallpaper.mode))
+        };
+
+        render_crossfade(
+            texture(from),
+            texture(to),
+            progress,
+            offset,
+            output_size,
+            scale,
+        )
+    }
+
+    fn texture(&self, wallpaper: &Wallpaper) -> Option<&TextureBuffer<GlesTexture>> {
+        self.images.get(&wallpaper.path)?.texture.as_ref()
+    }
+}
+
+/// Returns the index of the workspace above the view and the progress towards the one below.
+///
+/// Returns `None` when the view rests on a workspace, or when the rubber band pulls it above the
+/// first workspace, where there is nothing to crossfade from.
+pub fn switch_position(render_idx: f64) -> Option<(usize, f64)> {
+    if render_idx < 0. || render_idx.fract() == 0. {
+        return None;
+    }
+
+    Some((render_idx.floor() as usize, render_idx.fract()))
+}
+
+/// Renders `to` fading in over `from`, front to back.
+///
+/// If `to` failed to load, `from` fades out instead.
+fn render_crossfade<'a>(
+    from: Option<(&'a TextureBuffer<GlesTexture>, WallpaperMode)>,
+    to: Option<(&'a TextureBuffer<GlesTexture>, WallpaperMode)>,
+    progress: f64,
+    offset: Point<f64, Logical>,
+    output_size: Size<f64, Logical>,
+    scale: f64,
+) -> impl Iterator<Item = PrimaryGpuTextureRenderElement> + 'a {
+    let to_covers_output = to.map(|(texture, mode)| {
+        texture.is_opaque() && covers_output(mode, texture.logical_size(), output_size, scale)
+    });
+    let (to_alpha, from_alpha) = crossfade_alphas(progress, to_covers_output);
+
+    let to = to.map(|(texture, mode)| (texture, mode, to_alpha));
+    let from = from.map(|(texture, mode)| (texture, mode, from_alpha));
+
+    to.into_iter()
+        .chain(from)
+        .map(move |(texture, mode, alpha)| {
+            render_texture(texture, mode, alpha as f32, offset, output_size, scale)
+        })
+}
+
+/// Returns the alphas of `to` and `from` in a crossfade, in this order.
+///
+/// `to_covers_output` is `None` without a `to` wallpaper, otherwise whether `to` is opaque and
+/// covers the whole output.
+fn crossfade_alphas(progress: f64, to_covers_output: Option<bool>) -> (f64, f64) {
+    let progress = progress.clamp(0., 1.);
+    match to_covers_output {
+        // `from` shows through `to` by exactly the part that `to` is transparent, so keeping it
+        // opaque blends the two linearly and hides it completely at the end.
+        Some(true) => (progress, 1.),
+        // `from` would keep showing around or through `to` at the end, so fade it out.
+        Some(false) => (progress, 1. - progress),
+        None => (1., 1. - progress),
+    }
+}
+
+/// Whether the image drawn in this mode covers the whole output.
+fn covers_output(
+    mode: WallpaperMode,
+    image_size: Size<f64, Logical>,
+    output_size: Size<f64, Logical>,
+    scale: f64,
+) -> bool {
+    let (_, dst) = geometry(mode, image_size, output_size, scale);
+    dst.contains_rect(Rectangle::from_size(output_size))
+}
+
+fn render_texture(
+    texture: &TextureBuffer<GlesTexture>,
+    mode: WallpaperMode,
+    alpha: f32,
+    offset: Point<f64, Logical>,
+    output_size: Size<f64, Logical>,
+    scale: f64,
+) -> PrimaryGpuTextureRenderElement {
+    let (src, dst) = geometry(mode, texture.logical_size(), output_size, scale);
+    PrimaryGpuTextureRenderElement(TextureRenderElement::from_texture_buffer(
+        texture.clone(),
+        dst.loc + offset,
+        alpha,
+        Some(src),
+        Some(dst.size),
+        Kind::Unspecified,
+    ))
+}
+
+/// Computes the visible part of the image and where it goes on the output.
+///
+/// The image size is in pixels, the returned source rectangle is in image pixels, and the
+/// destination rectangle is in output logical coordinates.
+fn geometry(
+    mode: WallpaperMode,
+    image_size: Size<f64, Logical>,
+    output_size: Size<f64, Logical>,
+    scale: f64,
+) -> (Rectangle<f64, Logical>, Rectangle<f64, Logical>) {
+    let (image_w, image_h) = (image_size.w, image_size.h);
+    let (output_w, output_h) = (output_size.w, output_size.h);
+
+    let (src, dst_size) = match mode {
+        WallpaperMode::Fill => {
+            // Crop the image to the output aspect ratio, then scale it to the output size.
+            let factor = f64::max(output_w / image_w, output_h / image_h);
+            let src_size = Size::from((output_w / factor, output_h / factor));
+            let src_loc = Point::from(((image_w - src_size.w) / 2., (image_h - src_size.h) / 2.));
+            (Rectangle::new(src_loc, src_size), output_size)
+        }
+        WallpaperMode::Fit => {
+            let factor = f64::min(output_w / image_w, output_h / image_h);
+            let dst_size = Size::from((image_w * factor, image_h * factor));
+            (Rectangle::from_size(image_size), dst_size)
+        }
+        WallpaperMode::Center => {
+            // One image pixel per physical pixel, cropping what doesn't fit on the output.
+            let dst_size = Size::from((
+                f64::min(image_w / scale, output_w),
+                f64::min(image_h / scale, output_h),
+            ));
+            let src_size = Size::from((dst_size.w * scale, dst_size.h * scale));
+            let src_loc = Point::from(((image_w - src_size.w) / 2., (image_h - src_size.h) / 2.));
+            (Rectangle::new(src_loc, src_size), dst_size)
+        }
+    };
+
+    // Keep the image aligned to physical pixels to avoid blurring it.
+    let dst_loc = Point::from(((output_w - dst_size.w) / 2., (output_h - dst_size.h) / 2.));
+    let dst_loc = dst_loc.to_physical_precise_round(scale).to_logical(scale);
+
+    (src, Rectangle::new(dst_loc, dst_size))
+}
+
+fn full_path(path: &str) -> anyhow::Result<PathBuf> {
+    let path = PathBuf::from(path);
+    let path = expand_home(&path)
+        .context("error expanding ~")?
+        .unwrap_or(path);
+    Ok(path)
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn upload(
+    renderer: &mut GlesRenderer,
+    pixels: &[u8],
+    size: Size<i32, Buffer>,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    // Skip blending with the background color when the image has no transparency.
+    let opaque_regions = if pixels.chunks_exact(4).all(|pixel| pixel[3] == u8::MAX) {
+        vec![Rectangle::from_size(size)]
+    } else {
+        vec![]
+    };
+
+    let texture = TextureBuffer::from_memory(
+        renderer,
+        pixels,
+        Fourcc::Abgr8888,
+        size,
+        false,
+        1.,
+        Transform::Normal,
+        opaque_regions,
+    )
+    .context("error uploading the image")?;
+    Ok(texture)
+}
+
+/// Decodes an image into premultiplied RGBA8 pixels.
+fn decode(path: &Path) -> anyhow::Result<(Vec<u8>, Size<i32, Buffer>)> {
+    let pixbuf = Pixbuf::from_file(path).context("error decoding the image")?;
+    // Photos are often stored sideways with an EXIF orientation tag.
+    let pixbuf = pixbuf.apply_embedded_orientation().unwrap_or(pixbuf);
+    let pixbuf = if pixbuf.has_alpha() {
+        pixbuf
+    } else {
+        pixbuf
+            .add_alpha(false, 0, 0, 0)
+            .context("error adding the alpha channel")?
+    };
+    ensure!(
+        pixbuf.bits_per_sample() == 8 && pixbuf.n_channels() == 4,
+        "unsupported pixel format"
+    );
+
+    let width = pixbuf.width() as usize;
+    let height = pixbuf.height() as usize;
+    let stride = pixbuf.rowstride() as usize;
+    let bytes = pixbuf.read_pixel_bytes();
+
+    // The last row may be shorter than the stride.
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for row in 0..height {
+        let start = row * stride;
+        pixels.extend_from_slice(&bytes[start..start + width * 4]);
+    }
+    premultiply(&mut pixels);
+
+    let size = Size::from((width as i32, height as i32));
+    Ok((pixels, size))
+}
+
+fn premultiply(pixels: &mut [u8]) {
+    for pixel in pixels.chunks_exact_mut(4) {
+        let alpha = u16::from(pixel[3]);
+        for channel in &mut pixel[..3] {
+            *channel = (u16::from(*channel) * alpha / 255) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use xshell::Shell;
+
+    use super::*;
+    use crate::utils::write_png_rgba8;
+
+    fn rect(x: f64, y: f64, w: f64, h: f64) -> Rectangle<f64, Logical> {
+        Rectangle::new(Point::from((x, y)), Size::from((w, h)))
+    }
+
+    #[test]
+    fn fill_crops_to_output_aspect_ratio() {
+        let image = Size::from((4000., 2000.));
+        let output = Size::from((1000., 1000.));
+
+        let (src, dst) = geometry(WallpaperMode::Fill, image, output, 1.);
+        assert_eq!(src, rect(1000., 0., 2000., 2000.));
+        assert_eq!(dst, rect(0., 0., 1000., 1000.));
+    }
+
+    #[test]
+    fn fit_letterboxes() {
+        let image = Size::from((4000., 2000.));
+        let output = Size::from((1000., 1000.));
+
+        let (src, dst) = geometry(WallpaperMode::Fit, image, output, 1.);
+        assert_eq!(src, rect(0., 0., 4000., 2000.));
+        assert_eq!(dst, rect(0., 250., 1000., 500.));
+    }
+
+    #[test]
+    fn center_is_unscaled_in_physical_pixels() {
+        let output = Size::from((1000., 500.));
+
+        // Smaller than the output at scale 2.
+        let (src, dst) = geometry(WallpaperMode::Center, Size::from((800., 600.)), output, 2.);
+        assert_eq!(src, rect(0., 0., 800., 600.));
+        assert_eq!(dst, rect(300., 100., 400., 300.));
+
+        // Larger than the output gets cropped.
+        let (src, dst) = geometry(WallpaperMode::Center, Size::from((3000., 600.)), output, 1.);
+        assert_eq!(src, rect(1000., 50., 1000., 500.));
+        assert_eq!(dst, rect(0., 0., 1000., 500.));
+    }
+
+    #[test]
+    fn crossfade_hides_the_old_wallpaper_at_the_end() {
+        // An opaque wallpaper covering the output hides the old one by itself.
+        assert_eq!(crossfade_alphas(1., Some(true)), (1., 1.));
+        // Otherwise the old one would show around or through the new one.
+        assert_eq!(crossfade_alphas(1., Some(false)), (1., 0.));
+        assert_eq!(crossfade_alphas(1., None), (1., 0.));
+    }
+
+    #[test]
+    fn crossfade_blends_linearly_over_a_covering_wallpaper() {
+        let (to, from) = crossfade_alphas(0.25, Some(true));
+        // With the "over" operator, `from` shows through by what `to` leaves.
+        assert_eq!((to, from * (1. - to)), (0.25, 0.75));
+    }
+
+    #[test]
+    fn covers_output_depends_on_mode() {
+        let image = Size::from((4000., 2000.));
+        let output = Size::from((1000., 1000.));
+
+        assert!(covers_output(WallpaperMode::Fill, image, output, 1.));
+        assert!(!covers_output(WallpaperMode::Fit, image, output, 1.));
+        assert!(covers_output(WallpaperMode::Center, image, output, 1.));
+        assert!(!covers_output(
+            WallpaperMode::Center,
+            Size::from((800., 600.)),
+            output,
+            1.
+        ));
+    }
+
+    #[test]
+    fn switch_position_between_workspaces() {
+        assert_eq!(switch_position(0.), None);
+        assert_eq!(switch_position(2.), None);
+        assert_eq!(switch_position(1.25), Some((1, 0.25)));
+
+        // Rubber band overscroll above the first workspace.
+        assert_eq!(switch_position(-0.25), None);
+    }
+
+    #[test]
+    fn premultiplies_alpha() {
+        let mut pixels = [255, 128, 0, 128, 10, 20, 30, 255, 200, 200, 200, 0];
+        premultiply(&mut pixels);
+        assert_eq!(pixels, [128, 64, 0, 128, 10, 20, 30, 255, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn decodes_png() {
+        let sh = Shell::new().unwrap();
+        let temp_dir = sh.create_temp_dir().unwrap();
+        let path = temp_dir.path().join("wallpaper.png");
+
+        let pixels = [
+            255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, //
+            255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 0, 255, //
+        ];
+        let file = File::create(&path).unwrap();
+        write_png_rgba8(file, 3, 2, &pixels).unwrap();
+
+        let (decoded, size) = decode(&path).unwrap();
+        assert_eq!(size, Size::from((3, 2)));
+        assert_eq!(decoded, pixels);
+    }
+
+    #[test]
+    fn missing_file_fails_to_decode() {
+        assert!(decode(Path::new("/nonexistent/wallpaper.png")).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! 崩溃恢复模块
+//!
+//! 对非关键子系统（界面叠加层渲染、IPC 请求处理）中的 panic 进行捕获：
+//! 1. panic 钩子记录最近一次 panic 的信息和回溯
+//! 2. `catch` 捕获 panic，将崩溃报告写入状态目录
+//! 3. 调用方随后显示叠加层，提供以安全模式重启的选项
+//!
+//! 安全模式使用默认配置并关闭动画，通过 `--safe-mode` 参数启用。
+
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::AtomicBool;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use directories::BaseDirs;
+use niri_config::Config;
+
+use crate::utils::version;
+
+/// 启用安全模式的命令行参数
+pub const SAFE_MODE_ARG: &str = "--safe-mode";
+
+/// 是否以安全模式运行（启动时由 `--safe-mode` 设置）
+pub static IS_SAFE_MODE: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// 当前线程最近一次 panic 的描述（由 panic 钩子写入，`catch` 取出）
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// 一次被捕获的崩溃
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashReport {
+    /// 发生 panic 的子系统名称
+    pub subsystem: String,
+    /// panic 信息（含源码位置）
+    pub message: String,
+    /// 崩溃报告文件路径（写入失败时为 None）
+    pub path: Option<PathBuf>,
+}
+
+/// 安装 panic 钩子，在调用原有钩子（输出到 stderr）之前记录 panic 信息
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let backtrace = Backtrace::force_capture();
+        let text = format!("{info}\n\nBacktrace:\n{backtrace}");
+        LAST_PANIC.with(|last| *last.borrow_mut() = Some(text));
+
+        default_hook(info);
+    }));
+}
+
+/// 运行可能 panic 的非关键代码
+///
+/// 捕获到 panic 时写入崩溃报告并返回它，由调用方负责提示用户。
+pub fn catch<T>(subsystem: &str, f: impl FnOnce() -> T) -> Result<T, CrashReport> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|_| report(subsystem))
+}
+
+/// 为刚刚捕获的 panic 生成崩溃报告并写入文件
+pub fn report(subsystem: &str) -> CrashReport {
+    let details = LAST_PANIC
+        .with(|last| last.borrow_mut().take())
+        .unwrap_or_else(|| String::from("panic details are unavailable"));
+    let message = details.lines().next().unwrap_or_default().to_owned();
+
+    error!("recovered from a panic in {subsystem}: {message}");
+
+    let contents = format_report(subsystem, &details);
+    let path = write_report(&contents)
+        .map_err(|err| warn!("error writing crash report: {err:?}"))
+        .ok();
+
+    CrashReport {
+        subsystem: subsystem.to_owned(),
+        message,
+        path,
+    }
+}
+
+/// 格式化崩溃报告文件内容
+fn format_report(subsystem: &str, details: &str) -> String {
+    format!(
+        "niri {}\n\
+         Subsystem: {subsystem}\n\n\
+         {details}\n",
+        version()
+    )
+}
+
+/// 将崩溃报告写入 `$XDG_STATE_HOME/niri/crash-reports/`
+fn write_report(contents: &str) -> anyhow::Result<PathBuf> {
+    let dirs = BaseDirs::new().context("error getting home directory")?;
+    let dir = dirs
+        .state_dir()
+        .unwrap_or_else(|| dirs.cache_dir())
+        .join("niri")
+        .join("crash-reports");
+    fs::create_dir_all(&dir).context("error creating crash report directory")?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = dir.join(format!("crash-{timestamp}.txt"));
+    fs::write(&path, contents).context("error writing crash report file")?;
+
+    info!("wrote crash report to {path:?}");
+    Ok(path)
+}
+
+/// 安全模式下使用的配置：默认配置并关闭动画
+pub fn safe_mode_config() -> Config {
+    let mut config = Config::default();
+    config.animations.off = true;
+    config
+}
+
+/// 计算以安全模式重启时的命令行参数
+///
+/// 去掉 `--` 之后的启动命令，并在最前面加上 `--safe-mode`（已有时不重复添加）。
+pub fn safe_mode_args(args: impl IntoIterator<Item = OsString>) -> Vec<OsString> {
+    let mut args: Vec<_> = args
+        .into_iter()
+        .take_while(|arg| arg != OsStr::new("--"))
+        .collect();
+    if !args.iter().any(|arg| arg == OsStr::new(SAFE_MODE_ARG)) {
+        args.insert(0, OsString::from(SAFE_MODE_ARG));
+    }
+    args
+}
+
+/// 用安全模式的 niri 替换当前进程
+///
+/// 仅在失败时返回。
+pub fn exec_safe_mode() -> io::Error {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(err) => return err,
+    };
+
+    let args = safe_mode_args(std::env::args_os().skip(1));
+    info!("restarting in safe mode: {exe:?} {args:?}");
+    Command::new(exe).args(args).exec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn safe_mode_args_drop_startup_command() {
+        assert_eq!(
+            safe_mode_args(os_args(&["--session", "--", "alacritty"])),
+            os_args(&["--safe-mode", "--session"])
+        );
+        assert_eq!(
+            safe_mode_args(os_args(&["--safe-mode", "-c", "config.kdl"])),
+            os_args(&["--safe-mode", "-c", "config.kdl"])
+        );
+    }
+
+    #[test]
+    fn catch_returns_value_without_panic() {
+        assert_eq!(catch("test", || 5), Ok(5));
+    }
+
+    #[test]
+    fn report_contains_subsystem() {
+        let report = format_report("IPC", "panicked at src/ipc/server.rs:1:1");
+        assert!(report.contains("Subsystem: IPC"));
+        assert!(report.contains("panicked at src/ipc/server.rs:1:1"));
+    }
+}
@@ -0,0 +1,279 @@
+//! 桌面文件（.desktop）查找模块
+//!
+//! 在合成器中的作用：
+//! 召回列组时按 app id 找到程序的桌面文件，用其中的 Exec 命令启动缺少的程序。
+//! 与进程的命令行不同，Exec 对 flatpak 等包装器和单实例程序也是正确的启动方式。
+
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use directories::BaseDirs;
+
+/// 按 app id 查找程序的启动命令
+///
+/// 先找文件名为 `<app_id>.desktop` 的桌面文件，再找 `StartupWMClass` 等于 app id 的。
+/// 找不到、已隐藏或需要在终端中运行的程序返回 None
+pub fn find_command(app_id: &str) -> Option<Vec<String>> {
+    let dirs: Vec<_> = data_dirs()
+        .into_iter()
+        .map(|dir| dir.join("applications"))
+        .collect();
+    find_command_in(&dirs, app_id)
+}
+
+/// 桌面文件所在的数据目录，按优先级排列（XDG Base Directory 规范）
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(base) = BaseDirs::new() {
+        dirs.push(base.data_dir().to_owned());
+    }
+
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty());
+    let data_dirs = data_dirs
+        .as_deref()
+        .unwrap_or("/usr/local/share:/usr/share");
+    dirs.extend(
+        data_dirs
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from),
+    );
+
+    dirs
+}
+
+fn find_command_in(dirs: &[PathBuf], app_id: &str) -> Option<Vec<String>> {
+    // 同名文件中优先级高的目录覆盖优先级低的
+    let file_name = format!("{app_id}.desktop");
+    let entry = dirs
+        .iter()
+        .find_map(|dir| read_entry(&dir.join(&file_name)));
+    if let Some(entry) = entry {
+        return entry.command();
+    }
+
+    // 很多程序的 app id 和桌面文件名不同，但会在 StartupWMClass 中写明
+    for dir in dirs {
+        let Ok(read_dir) = fs::read_dir(dir) else {
+            continue;
+        };
+        for dir_entry in read_dir.flatten() {
+            let path = dir_entry.path();
+            if path.extension() != Some(OsStr::new("desktop")) {
+                continue;
+            }
+            let Some(entry) = read_entry(&path) else {
+                continue;
+            };
+            if entry.startup_wm_class.as_deref() == Some(app_id) {
+                return entry.command();
+            }
+        }
+    }
+
+    None
+}
+
+/// 桌面文件 `[Desktop Entry]` 组中需要的键
+#[derive(Debug, Default, PartialEq)]
+struct Entry {
+    exec: Option<String>,
+    startup_wm_class: Option<String>,
+    hidden: bool,
+    terminal: bool,
+}
+
+impl Entry {
+    fn command(self) -> Option<Vec<String>> {
+        if self.hidden {
+            return None;
+        }
+        if self.terminal {
+            warn!(
+                "not starting {:?}: it needs to run in a terminal",
+                self.exec
+            );
+            return None;
+        }
+
+        parse_exec(&self.exec?)
+    }
+}
+
+fn read_entry(path: &Path) -> Option<Entry> {
+    let contents = fs::read_to_string(path).ok()?;
+    Some(parse_entry(&contents))
+}
+
+/// 解析桌面文件，只读取 `[Desktop Entry]` 组
+fn parse_entry(contents: &str) -> Entry {
+    let mut entry = Entry::default();
+    let mut in_group = false;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_group = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_group || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "Exec" => entry.exec = Some(value.to_owned()),
+            "StartupWMClass" => entry.startup_wm_class = Some(value.to_owned()),
+            "Hidden" => entry.hidden = value == "true",
+            "Terminal" => entry.terminal = value == "true",
+            _ => (),
+        }
+    }
+
+    entry
+}
+
+/// 将 Exec 值拆分为参数
+///
+/// 参数可以用双引号括起，引号内 `\"`、`` \` ``、`\$`、`\\` 为转义。
+/// 没有文件或 URL 要打开，因此去掉所有字段代码（`%f`、`%U` 等），`%%` 变为 `%`
+fn parse_exec(exec: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    // 当前参数是否存在（用于保留空的引号参数）
+    let mut has_arg = false;
+    let mut in_quotes = false;
+
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            '\\' if in_quotes => arg.push(chars.next()?),
+            '%' => match chars.next()? {
+                '%' => {
+                    arg.push('%');
+                    has_arg = true;
+                }
+                // 字段代码
+                _ => (),
+            },
+            c if c.is_ascii_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut arg));
+                    has_arg = false;
+                }
+            }
+            c => {
+                arg.push(c);
+                has_arg = true;
+            }
+        }
+    }
+
+    if in_quotes {
+        return None;
+    }
+    if has_arg {
+        args.push(arg);
+    }
+
+    (!args.is_empty()).then_some(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use xshell::Shell;
+
+    use super::*;
+
+    #[test]
+    fn parses_exec() {
+        assert_eq!(parse_exec("foot").unwrap(), ["foot"]);
+        assert_eq!(parse_exec("firefox %u").unwrap(), ["firefox"]);
+        assert_eq!(
+            parse_exec("/usr/bin/flatpak run --branch=stable org.gnome.Nautilus @@ %U @@").unwrap(),
+            [
+                "/usr/bin/flatpak",
+                "run",
+                "--branch=stable",
+                "org.gnome.Nautilus",
+                "@@",
+                "@@"
+            ]
+        );
+        assert_eq!(
+            parse_exec(r#"sh -c "echo \"100%%\" \$HOME""#).unwrap(),
+            ["sh", "-c", r#"echo "100%" $HOME"#]
+        );
+        assert_eq!(parse_exec(r#"app """#).unwrap(), ["app", ""]);
+
+        assert_eq!(parse_exec("%F"), None);
+        assert_eq!(parse_exec(r#"app "unterminated"#), None);
+    }
+
+    #[test]
+    fn parses_only_desktop_entry_group() {
+        let entry = parse_entry(
+            "[Desktop Entry]\n\
+             # Exec=commented\n\
+             Exec=app --new-window\n\
+             StartupWMClass=App\n\
+             Terminal=false\n\
+             \n\
+             [Desktop Action new-private-window]\n\
+             Exec=app --private-window\n",
+        );
+        assert_eq!(
+            entry,
+            Entry {
+                exec: Some(String::from("app --new-window")),
+                startup_wm_class: Some(String::from("App")),
+                hidden: false,
+                terminal: false,
+            }
+        );
+    }
+
+    #[test]
+    fn finds_command_by_file_name_and_wm_class() {
+        let sh = Shell::new().unwrap();
+        let temp_dir = sh.create_temp_dir().unwrap();
+        let user = temp_dir.path().join("user");
+        let system = temp_dir.path().join("system");
+        fs::create_dir_all(&user).unwrap();
+        fs::create_dir_all(&system).unwrap();
+
+        let write = |dir: &Path, name: &str, contents: &str| {
+            fs::write(dir.join(name), format!("[Desktop Entry]\n{contents}")).unwrap();
+        };
+        write(&system, "org.app.Editor.desktop", "Exec=editor %F");
+        write(&user, "org.app.Editor.desktop", "Exec=editor --user %F");
+        write(
+            &system,
+            "browser.desktop",
+            "Exec=browser\nStartupWMClass=Browser",
+        );
+        write(&system, "hidden.desktop", "Exec=hidden\nHidden=true");
+        write(&system, "tui.desktop", "Exec=tui\nTerminal=true");
+
+        let dirs = [user, system];
+        assert_eq!(
+            find_command_in(&dirs, "org.app.Editor").unwrap(),
+            ["editor", "--user"]
+        );
+        assert_eq!(find_command_in(&dirs, "Browser").unwrap(), ["browser"]);
+        assert_eq!(find_command_in(&dirs, "hidden"), None);
+        assert_eq!(find_command_in(&dirs, "tui"), None);
+        assert_eq!(find_command_in(&dirs, "missing"), None);
+    }
+}
@@ -11,6 +11,9 @@ use std::sync::atomic::{AtomicU64, Ordering};
 //   Ordering - 内存顺序保证，控制原子操作的内存可见性
 
 /// 计数器，返回唯一ID。
+///
+/// ID 在合成器运行期间从不复用，因此 IPC 客户端持有的过期 ID 不会指向新的对象
+/// （64 位计数器实际上不会回绕），无需额外的代数（generation）计数。
 // 中文翻译: 该结构提供原子自增计数器，用于生成全局唯一ID
 pub struct IdCounter {
     // 原子计数器值
@@ -29,11 +29,13 @@ use smithay::output::{self, Output}; // 输出设备管理
 use smithay::reexports::rustix::time::{clock_gettime, ClockId}; // 系统时间获取
 use smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1; // XDG装饰协议
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel; // XDG顶层协议
+use smithay::reexports::wayland_server::protocol::wl_shm; // SHM 像素格式
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface; // Wayland表面
 use smithay::reexports::wayland_server::{DisplayHandle, Resource as _}; // Wayland服务器核心
 use smithay::utils::{Coordinate, Logical, Point, Rectangle, Size, Transform}; // 几何工具
 use smithay::wayland::compositor::{send_surface_state, with_states, SurfaceData}; // 合成器表面状态
 use smithay::wayland::fractional_scale::with_fractional_scale; // 分数缩放支持
+use smithay::wayland::shm::with_buffer_contents; // SHM 缓冲区内容访问
 use smithay::wayland::shell::xdg::{
     ToplevelSurface, XdgToplevelSurfaceData, XdgToplevelSurfaceRoleAttributes, // XDG顶层表面
 };
@@ -42,11 +44,15 @@ use wayland_backend::server::Credentials; // Wayland凭证
 use crate::niri::ClientState; // 客户端状态
 
 // 子模块声明
+pub mod crash; // 崩溃恢复
+pub mod desktop_entry; // 桌面文件查找
 pub mod id; // ID管理
 pub mod scale; // 缩放处理
 pub mod spawning; // 进程生成
+pub mod supervisor; // 服务监管
 pub mod transaction; // 事务处理
 pub mod watcher; // 文件监视
+pub mod xwayland; // X11 显示套接字
 
 // 原子布尔值，标识当前是否作为systemd服务运行
 pub static IS_SYSTEMD_SERVICE: AtomicBool = AtomicBool::new(false);
@@ -170,6 +176,26 @@ pub fn floor_logical_in_physical_max1(scale: f64, logical: f64) -> f64 {
     (logical * scale).max(1.).floor() / scale
 }
 
+/// 输出设备未报告物理尺寸时假定的每毫米逻辑像素数（96 DPI）
+pub const DEFAULT_PX_PER_MM: f64 = 96. / 25.4;
+
+/// 计算输出设备上每毫米对应的逻辑像素数
+///
+/// 物理尺寸来自 EDID，投影仪等设备常报告为零，此时回退到 96 DPI
+pub fn logical_px_per_mm(output: &Output) -> f64 {
+    let width_mm = output.physical_properties().size.w; // 物理宽度（毫米）
+    let Some(mode) = output.current_mode() else {
+        return DEFAULT_PX_PER_MM;
+    };
+    if width_mm <= 0 || mode.size.w <= 0 {
+        return DEFAULT_PX_PER_MM;
+    }
+
+    // 物理尺寸和模式都未经旋转，可以直接相除
+    let scale = output.current_scale().fractional_scale();
+    f64::from(mode.size.w) / f64::from(width_mm) / scale
+}
+
 /// 获取输出设备的逻辑尺寸
 ///
 /// 在合成器中的作用：
@@ -237,6 +263,61 @@ pub fn is_mapped(surface: &WlSurface) -> bool {
     with_renderer_surface_state(surface, |state| state.buffer().is_some()).unwrap_or(false)
 }
 
+/// 检查表面当前的缓冲区是否几乎完全透明
+///
+/// 在合成器中的作用：
+/// 自己实现淡入效果的客户端会以透明的第一帧映射窗口，
+/// 这时再播放合成器的打开动画会造成两次淡入
+///
+/// 只检查带 alpha 通道的 SHM 缓冲区，按网格采样像素；无法读取内容时返回 false。
+/// DMA-BUF 缓冲区（大多数用 GPU 渲染的客户端）总是返回 false：读取其内容需要在主循环中
+/// 等待 GPU 回读，为每个新窗口付出这个代价不值得。这类客户端可以用 `skip-open-animation`
+/// 窗口规则关闭打开动画。
+pub fn is_surface_transparent(surface: &WlSurface) -> bool {
+    // 每个方向上的采样点数
+    const SAMPLES: i32 = 16;
+    // 低于此值的 alpha 视为透明
+    const MAX_ALPHA: u8 = 8;
+
+    with_renderer_surface_state(surface, |state| {
+        let Some(buffer) = state.buffer() else {
+            return false;
+        };
+
+        with_buffer_contents(buffer, |ptr, len, data| {
+            // 这两种格式的 alpha 都位于每个像素的最后一个字节
+            if !matches!(data.format, wl_shm::Format::Argb8888 | wl_shm::Format::Abgr8888) {
+                return false;
+            }
+
+            for sy in 0..SAMPLES {
+                for sx in 0..SAMPLES {
+                    let x = data.width * sx / SAMPLES;
+                    let y = data.height * sy / SAMPLES;
+                    let offset = data.offset + y * data.stride + x * 4 + 3;
+
+                    let Ok(offset) = usize::try_from(offset) else {
+                        return false;
+                    };
+                    if offset >= len {
+                        return false;
+                    }
+
+                    // SAFETY: offset is within the buffer contents of size len.
+                    let alpha = unsafe { *ptr.add(offset) };
+                    if alpha > MAX_ALPHA {
+                        return false;
+                    }
+                }
+            }
+
+            true
+        })
+        .unwrap_or(false)
+    })
+    .unwrap_or(false)
+}
+
 /// 向表面发送缩放和变换信息
 ///
 /// 在合成器中的作用：
@@ -400,6 +481,30 @@ pub fn get_credentials_for_surface(surface: &WlSurface) -> Option<Credentials> {
     client.get_credentials(&dh).ok()
 }
 
+/// 读取 Flatpak 沙盒中进程的应用 ID
+///
+/// Flatpak 会在沙盒根目录放置 `.flatpak-info`，其中 `[Application]` 段的 `name` 即应用 ID。
+/// 进程不在 Flatpak 中或无法读取时返回 None
+pub fn read_flatpak_app_id(pid: i32) -> Option<String> {
+    let info = std::fs::read_to_string(format!("/proc/{pid}/root/.flatpak-info")).ok()?;
+    parse_flatpak_app_id(&info)
+}
+
+fn parse_flatpak_app_id(info: &str) -> Option<String> {
+    let mut in_application = false;
+    for line in info.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_application = line == "[Application]";
+        } else if in_application {
+            if let Some(name) = line.strip_prefix("name=") {
+                return Some(name.trim().to_owned());
+            }
+        }
+    }
+    None
+}
+
 /// 确保数值在[min_size, max_size]范围内
 ///
 /// 处理规则：
@@ -484,6 +589,24 @@ pub fn baba_is_float_offset(now: Duration, view_height: f64) -> f64 {
     amplitude * ((f64::consts::TAU * now / 3.6).sin() - 1.) // 正弦波动
 }
 
+/// 生成软件伽马曲线
+///
+/// 返回红、绿、蓝三个通道依次拼接的查找表，每个通道 `size` 项：
+///   value = brightness * (i / (size - 1))^(1 / gamma) * 65535
+pub fn gamma_ramp(size: u32, gamma: f64, brightness: f64) -> Vec<u16> {
+    let size = size as usize;
+    let denom = size.saturating_sub(1).max(1) as f64;
+    let channel: Vec<u16> = (0..size)
+        .map(|i| {
+            let value = brightness * (i as f64 / denom).powf(1. / gamma);
+            (value.clamp(0., 1.) * f64::from(u16::MAX)).round() as u16
+        })
+        .collect();
+
+    // 三个通道使用相同的曲线
+    channel.repeat(3)
+}
+
 // 条件编译：仅当启用dbus特性时包含
 #[cfg(feature = "dbus")]
 /// 显示截图完成通知（通过DBus）
@@ -555,6 +678,18 @@ pub fn cause_panic() {
 mod tests {
     use super::*; // 导入父模块所有内容
 
+    // 测试伽马曲线的端点和亮度缩放
+    #[test]
+    fn test_gamma_ramp() {
+        let ramp = gamma_ramp(4, 1., 1.);
+        assert_eq!(ramp.len(), 12);
+        assert_eq!(&ramp[..4], &[0, 21845, 43690, 65535]);
+        assert_eq!(&ramp[..4], &ramp[4..8]);
+
+        let ramp = gamma_ramp(3, 2., 0.5);
+        assert_eq!(&ramp[..3], &[0, 23170, 32768]);
+    }
+
     // 测试clamp_preferring_top_left函数
     #[test]
     fn test_clamp_preferring_top_left() {
@@ -601,4 +736,14 @@ mod tests {
         check((0, 0, 10, 20), (20, 30, 4, 50), (6, 0));  // 高度过大
         check((0, 0, 10, 20), (20, 30, 40, 50), (0, 0)); // 宽高均过大
     }
+
+    // 测试 .flatpak-info 解析
+    #[test]
+    fn test_parse_flatpak_app_id() {
+        let info = "[Instance]\nname=wrong\n\n[Application]\nname=org.gnome.Maps\n";
+        let app_id = parse_flatpak_app_id(info);
+        assert_eq!(app_id.as_deref(), Some("org.gnome.Maps"));
+        // 缺少应用段
+        assert_eq!(parse_flatpak_app_id("[Instance]\nname=wrong\n"), None);
+    }
 }
\ No newline at end of file
@@ -33,26 +33,16 @@ pub fn guess_monitor_scale(
     resolution: Size<i32, Physical> // 物理分辨率（像素）
 ) -> f64 {
     // 无效尺寸检查（避免除零错误）
-    if size_mm.w == 0 || size_mm.h == 0 {
+    let Some(physical_dpi) = monitor_dpi(size_mm, resolution) else {
         return 1.; // 默认缩放
-    }
-
-    // 计算对角线尺寸（英寸）：
-    // 1. 毫米转英寸：/25.4
-    // 2. 勾股定理：sqrt(w² + h²)
-    let diag_inches = f64::from(size_mm.w * size_mm.w + size_mm.h * size_mm.h).sqrt() / 25.4;
+    };
 
     // 根据尺寸选择目标DPI
-    let target_dpi = if diag_inches < LARGE_MIN_SIZE_INCHES {
+    let target_dpi = if diagonal_inches(size_mm) < LARGE_MIN_SIZE_INCHES {
         MOBILE_TARGET_DPI  // 小尺寸设备使用更高DPI
     } else {
         LARGE_TARGET_DPI   // 大尺寸设备使用稍低DPI
     };
-
-    // 计算物理DPI：
-    // 公式：sqrt(水平像素² + 垂直像素²) / 对角线英寸
-    let physical_dpi =
-        f64::from(resolution.w * resolution.w + resolution.h * resolution.h).sqrt() / diag_inches;
     
     // 计算完美缩放比例（物理DPI / 目标DPI）
     let perfect_scale = physical_dpi / target_dpi;
@@ -67,6 +57,27 @@ pub fn guess_monitor_scale(
         .map_or(1., |(scale, _)| scale)
 }
 
+/// 计算显示器的物理DPI
+///
+/// 公式：sqrt(水平像素² + 垂直像素²) / 对角线英寸
+///
+/// 物理尺寸未知时返回 None
+pub fn monitor_dpi(size_mm: Size<i32, Raw>, resolution: Size<i32, Physical>) -> Option<f64> {
+    if size_mm.w <= 0 || size_mm.h <= 0 {
+        return None;
+    }
+
+    let diag_pixels = f64::from(resolution.w * resolution.w + resolution.h * resolution.h).sqrt();
+    Some(diag_pixels / diagonal_inches(size_mm))
+}
+
+/// 计算对角线尺寸（英寸）：
+/// 1. 勾股定理：sqrt(w² + h²)
+/// 2. 毫米转英寸：/25.4
+fn diagonal_inches(size_mm: Size<i32, Raw>) -> f64 {
+    f64::from(size_mm.w * size_mm.w + size_mm.h * size_mm.h).sqrt() / 25.4
+}
+
 /// 生成给定分辨率支持的缩放比例迭代器
 ///
 /// 支持条件：
@@ -161,6 +172,16 @@ mod tests {
         assert_eq!(check((0, 0), (1920, 1080)), 1.); // 应返回默认值1.0
     }
 
+    // 测试DPI计算
+    #[test]
+    fn test_monitor_dpi() {
+        // 27英寸4K显示器
+        let dpi = monitor_dpi(Size::from((598, 336)), Size::from((3840, 2160)));
+        assert_snapshot!(format!("{:.1}", dpi.unwrap()), @"163.1");
+        // 未知物理尺寸
+        assert_eq!(monitor_dpi(Size::from((0, 0)), Size::from((1920, 1080))), None);
+    }
+
     // 测试缩放比例舍入功能
     #[test]
     fn test_round_scale() {
@@ -7,16 +7,17 @@
 //! 4. 集成systemd进程管理（可选）
 
 use std::ffi::OsStr;
+use std::os::fd::RawFd;
 use std::os::unix::process::CommandExt; // Unix命令扩展
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::RwLock; // 读写锁
+use std::sync::{Arc, Mutex, RwLock}; // 读写锁
 use std::{io, thread};
 
 use atomic::Atomic;
 use libc::{getrlimit, rlim_t, rlimit, setrlimit, RLIMIT_NOFILE}; // 系统资源限制
-use niri_config::Environment; // 环境变量配置
+use niri_config::{Environment, EnvironmentVariable}; // 环境变量配置
 use smithay::wayland::xdg_activation::XdgActivationToken; // XDG激活令牌
 
 use crate::utils::expand_home; // 主目录路径扩展
@@ -37,6 +38,11 @@ pub static REMOVE_ENV_RUST_LIB_BACKTRACE: AtomicBool = AtomicBool::new(false);
 /// 适用于配置热更新场景
 pub static CHILD_ENV: RwLock<Environment> = RwLock::new(Environment(Vec::new()));
 
+/// xwayland-satellite 使用的 X11 显示（如 ":0"）
+///
+/// 设置后，子进程的环境变量中会加入指向它的 DISPLAY
+static XWAYLAND_DISPLAY: RwLock<Option<String>> = RwLock::new(None);
+
 /// 原始文件描述符限制（当前值）
 static ORIGINAL_NOFILE_RLIMIT_CUR: Atomic<rlim_t> = Atomic::new(0);
 
@@ -80,6 +86,75 @@ pub fn store_and_increase_nofile_rlimit() {
     }
 }
 
+/// 设置子进程的环境变量
+///
+/// 配置中没有设置 DISPLAY 时，补上 xwayland-satellite 的显示
+pub fn set_child_env(mut env: Environment) {
+    if let Some(display) = &*XWAYLAND_DISPLAY.read().unwrap() {
+        add_display(&mut env, display);
+    }
+    *CHILD_ENV.write().unwrap() = env;
+}
+
+/// 记录 xwayland-satellite 的 X11 显示，之后生成的子进程都会得到对应的 DISPLAY
+pub fn set_xwayland_display(display: String) {
+    add_display(&mut CHILD_ENV.write().unwrap(), &display);
+    *XWAYLAND_DISPLAY.write().unwrap() = Some(display);
+}
+
+/// xwayland-satellite 停止后，从子进程环境中去掉它的 DISPLAY
+///
+/// 用户在配置中自行设置的 DISPLAY 保持不变
+pub fn clear_xwayland_display() {
+    let Some(display) = XWAYLAND_DISPLAY.write().unwrap().take() else {
+        return;
+    };
+
+    CHILD_ENV
+        .write()
+        .unwrap()
+        .0
+        .retain(|var| var.name != "DISPLAY" || var.value.as_deref() != Some(&*display));
+}
+
+/// xwayland-satellite 的 X11 显示（未运行时为 `None`）
+pub fn xwayland_display() -> Option<String> {
+    XWAYLAND_DISPLAY.read().unwrap().clone()
+}
+
+/// 在用户没有自行设置 DISPLAY 时加入 DISPLAY 环境变量
+fn add_display(env: &mut Environment, display: &str) {
+    if env.0.iter().any(|var| var.name == "DISPLAY") {
+        return;
+    }
+
+    env.0.push(EnvironmentVariable {
+        name: String::from("DISPLAY"),
+        value: Some(display.to_owned()),
+    });
+}
+
+/// 查找可执行文件：含 `/` 时按路径（支持 `~`）检查，否则在 PATH 中搜索
+pub fn find_executable(command: &str) -> Option<PathBuf> {
+    if command.contains('/') {
+        let path = Path::new(command);
+        let path = match expand_home(path) {
+            Ok(Some(expanded)) => expanded,
+            Ok(None) => path.to_owned(),
+            Err(err) => {
+                warn!("主目录扩展错误: {err:?}");
+                path.to_owned()
+            }
+        };
+        return path.is_file().then_some(path);
+    }
+
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(command))
+        .find(|path| path.is_file())
+}
+
 /// 恢复原始文件描述符限制
 pub fn restore_nofile_rlimit() {
     // 获取存储值
@@ -143,8 +218,178 @@ fn spawn_sync(
         }
     }
 
+    let process = build_command(command_ref, args, token.as_ref());
+
+    // 执行生成
+    let Some(mut child) = do_spawn(command_ref, process) else {
+        return;
+    };
+
+    // 等待子进程退出
+    match child.wait() {
+        Ok(status) => {
+            if !status.success() {
+                warn!("子进程异常退出: {status:?}");
+            }
+        }
+        Err(err) => {
+            warn!("等待子进程错误: {err:?}");
+        }
+    }
+}
+
+/// 直接生成子进程（不使用双重fork）
+///
+/// 调用方负责等待子进程退出，用于需要监控进程状态的场景（如服务监管）。
+/// `inherit_fds` 中的文件描述符会保留给子进程（清除 CLOEXEC 标志）
+pub fn spawn_child(
+    command: &[String],
+    inherit_fds: &[RawFd],
+    token: Option<&XdgActivationToken>,
+) -> Option<Child> {
+    let _span = tracy_client::span!();
+
+    let (command, args) = command.split_first()?;
+    let mut command_ref = OsStr::new(command);
+
+    // 扩展主目录路径（~）
+    let expanded = expand_home(Path::new(command_ref));
+    match &expanded {
+        Ok(Some(expanded)) => command_ref = expanded.as_ref(),
+        Ok(None) => (),
+        Err(err) => {
+            warn!("主目录扩展错误: {err:?}");
+        }
+    }
+
+    let mut process = build_command(command_ref, args, token);
+
+    // 恢复文件描述符限制，并让子进程继承指定的文件描述符
+    let inherit_fds = inherit_fds.to_vec();
+    unsafe {
+        process.pre_exec(move || {
+            restore_nofile_rlimit();
+            for &fd in &inherit_fds {
+                if libc::fcntl(fd, libc::F_SETFD, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+
+    match process.spawn() {
+        Ok(child) => Some(child),
+        Err(err) => {
+            warn!("生成命令失败 {command_ref:?}: {err:?}");
+            None
+        }
+    }
+}
+
+/// 可以随时结束的子进程（例如锁屏命令）
+///
+/// 子进程在单独的线程中生成，并由该线程等待它退出。子进程被回收之前就不再向它发送信号，
+/// 因此信号不会发给复用了同一 PID 的其他进程
+#[derive(Debug)]
+pub struct TerminableChild {
+    state: Arc<Mutex<ChildState>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChildState {
+    /// 子进程正在生成；`terminate` 表示生成后立即结束它
+    Starting {
+        terminate: bool,
+    },
+    Running(i32),
+    Exited,
+}
+
+impl TerminableChild {
+    /// 在后台生成子进程并等待它退出
+    pub fn spawn(command: Vec<String>, token: Option<XdgActivationToken>) -> Self {
+        let this = Self {
+            state: Arc::new(Mutex::new(ChildState::Starting { terminate: false })),
+        };
+
+        let state = this.state.clone();
+        let res = thread::Builder::new()
+            .name("子进程等待".to_owned())
+            .spawn(move || {
+                let child = spawn_child(&command, &[], token.as_ref());
+
+                let mut child = {
+                    let mut state = state.lock().unwrap();
+                    let Some(child) = child else {
+                        *state = ChildState::Exited;
+                        return;
+                    };
+
+                    let pid = child.id() as i32;
+                    if *state == (ChildState::Starting { terminate: true }) {
+                        unsafe { libc::kill(pid, libc::SIGTERM) };
+                    }
+                    *state = ChildState::Running(pid);
+                    child
+                };
+
+                // 先等待退出但不回收，清除 PID 之前它不会被复用
+                loop {
+                    let res = unsafe {
+                        let mut info: libc::siginfo_t = std::mem::zeroed();
+                        libc::waitid(
+                            libc::P_PID,
+                            child.id() as libc::id_t,
+                            &mut info,
+                            libc::WEXITED | libc::WNOWAIT,
+                        )
+                    };
+                    let err = (res != 0).then(io::Error::last_os_error);
+                    if err.map(|err| err.kind()) != Some(io::ErrorKind::Interrupted) {
+                        break;
+                    }
+                }
+
+                *state.lock().unwrap() = ChildState::Exited;
+                let _ = child.wait();
+            });
+        if let Err(err) = res {
+            warn!("生成子进程等待线程错误: {err:?}");
+            *this.state.lock().unwrap() = ChildState::Exited;
+        }
+
+        this
+    }
+
+    /// 子进程是否正在生成或仍在运行
+    pub fn is_running(&self) -> bool {
+        *self.state.lock().unwrap() != ChildState::Exited
+    }
+
+    /// 用 SIGTERM 结束子进程；子进程已经退出时返回 `false`
+    pub fn terminate(&self) -> bool {
+        // 发送信号期间持有锁，等待线程无法在此期间回收子进程
+        let mut state = self.state.lock().unwrap();
+        match &mut *state {
+            ChildState::Starting { terminate } => {
+                *terminate = true;
+                true
+            }
+            ChildState::Running(pid) => unsafe { libc::kill(*pid, libc::SIGTERM) == 0 },
+            ChildState::Exited => false,
+        }
+    }
+}
+
+/// 构建子进程命令（参数、标准流、环境变量和激活令牌）
+fn build_command(
+    command: &OsStr,                                   // 命令路径
+    args: impl IntoIterator<Item = impl AsRef<OsStr>>, // 命令参数
+    token: Option<&XdgActivationToken>,                // 激活令牌
+) -> Command {
     // 配置命令
-    let mut process = Command::new(command_ref);
+    let mut process = Command::new(command);
     process
         .args(args) // 添加参数
         .stdin(Stdio::null()) // 关闭标准输入
@@ -172,27 +417,12 @@ fn spawn_sync(
     }
 
     // 传递激活令牌
-    if let Some(token) = token.as_ref() {
+    if let Some(token) = token {
         process.env("XDG_ACTIVATION_TOKEN", token.as_str());
         process.env("DESKTOP_STARTUP_ID", token.as_str());
     }
 
-    // 执行生成
-    let Some(mut child) = do_spawn(command_ref, process) else {
-        return;
-    };
-
-    // 等待子进程退出
-    match child.wait() {
-        Ok(status) => {
-            if !status.success() {
-                warn!("子进程异常退出: {status:?}");
-            }
-        }
-        Err(err) => {
-            warn!("等待子进程错误: {err:?}");
-        }
-    }
+    process
 }
 
 // 非systemd环境的生成实现
@@ -480,4 +710,41 @@ mod systemd {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn wait_for_exit(child: &TerminableChild) {
+        let start = Instant::now();
+        while child.is_running() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn terminates_child() {
+        let command = vec![String::from("sleep"), String::from("10")];
+        let child = TerminableChild::spawn(command, None);
+        assert!(child.is_running());
+
+        // This works both while the child is starting and once it runs.
+        assert!(child.terminate());
+        wait_for_exit(&child);
+
+        // The child is gone, so there's nothing to signal.
+        assert!(!child.terminate());
+    }
+
+    #[test]
+    fn child_that_fails_to_spawn_is_not_running() {
+        let command = vec![String::from("/nonexistent/niri-test-command")];
+        let child = TerminableChild::spawn(command, None);
+        wait_for_exit(&child);
+        assert!(!child.terminate());
+    }
+}
@@ -0,0 +1,182 @@
+//! 服务监管模块
+//!
+//! 在合成器中的作用：
+//! 为未使用 systemd 的会话提供最小化的用户服务管理，
+//! 当 `spawn-at-startup` 启动的服务（如状态栏、壁纸）异常退出时自动重启
+
+use std::os::fd::RawFd;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use niri_ipc::{Service, ServiceState};
+
+use crate::utils::spawning::spawn_child;
+
+/// 首次重启前的等待时间，之后每次失败翻倍
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// 重启等待时间上限
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// 运行超过此时间后退出的服务视为曾经正常运行，连续失败计数清零
+const HEALTHY_UPTIME: Duration = Duration::from_secs(60);
+
+/// 受监管服务的状态列表（在监管线程与 IPC 之间共享）
+#[derive(Debug, Clone, Default)]
+pub struct Services(Arc<Mutex<Vec<Service>>>);
+
+impl Services {
+    /// 获取所有服务状态的快照
+    pub fn snapshot(&self) -> Vec<Service> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn add(&self, command: Vec<String>) -> usize {
+        let mut services = self.0.lock().unwrap();
+        services.push(Service {
+            command,
+            state: ServiceState::Running,
+            pid: None,
+            restarts: 0,
+        });
+        services.len() - 1
+    }
+
+    fn update(&self, idx: usize, f: impl FnOnce(&mut Service)) {
+        f(&mut self.0.lock().unwrap()[idx]);
+    }
+}
+
+/// 受监管服务的附加行为
+#[derive(Default)]
+pub struct ServiceHooks {
+    /// 服务进程需要继承的文件描述符（调用方保证它们在监管期间保持打开）
+    pub inherit_fds: Vec<RawFd>,
+    /// 每次启动服务进程后调用，参数为进程 PID
+    pub on_spawn: Option<Box<dyn Fn(i32) + Send>>,
+    /// 监管结束后调用（服务正常退出，或连续失败后停止重启）
+    pub on_stop: Option<Box<dyn FnOnce() + Send>>,
+}
+
+/// 启动受监管的服务
+///
+/// 服务以非零状态退出（或被信号终止）时按指数退避重启，
+/// 连续失败超过 `max_restarts` 次后停止重启
+pub fn spawn_supervised(command: Vec<String>, max_restarts: u32, services: Services) {
+    spawn_supervised_with_hooks(command, max_restarts, services, ServiceHooks::default());
+}
+
+/// 启动受监管的服务，并在服务启动和停止时调用 `hooks`
+///
+/// 监管线程创建失败时返回 `false`，此时 `on_stop` 不会被调用
+pub fn spawn_supervised_with_hooks(
+    command: Vec<String>,
+    max_restarts: u32,
+    services: Services,
+    hooks: ServiceHooks,
+) -> bool {
+    if command.is_empty() {
+        return false; // 空命令检查
+    }
+
+    let idx = services.add(command.clone());
+
+    // 后台线程等待服务进程（避免阻塞主线程）
+    let services_ = services.clone();
+    let res = thread::Builder::new()
+        .name("服务监管".to_owned())
+        .spawn(move || {
+            supervise(&command, max_restarts, &services_, idx, &hooks);
+            if let Some(on_stop) = hooks.on_stop {
+                on_stop();
+            }
+        });
+
+    if let Err(err) = res {
+        warn!("生成服务监管线程错误: {err:?}");
+        services.update(idx, |service| service.state = ServiceState::Failed);
+        return false;
+    }
+
+    true
+}
+
+fn supervise(
+    command: &[String],
+    max_restarts: u32,
+    services: &Services,
+    idx: usize,
+    hooks: &ServiceHooks,
+) {
+    let mut failures = 0;
+
+    loop {
+        let start = Instant::now();
+
+        // 生成失败同样视为一次失败
+        let status = spawn_child(command, &hooks.inherit_fds, None).and_then(|mut child| {
+            let pid = child.id() as i32;
+            services.update(idx, |service| {
+                service.state = ServiceState::Running;
+                service.pid = Some(pid);
+            });
+            if let Some(on_spawn) = &hooks.on_spawn {
+                on_spawn(pid);
+            }
+
+            child
+                .wait()
+                .map_err(|err| warn!("等待服务进程错误: {err:?}"))
+                .ok()
+        });
+
+        services.update(idx, |service| service.pid = None);
+
+        if status.is_some_and(|status| status.success()) {
+            debug!("服务 {command:?} 正常退出");
+            services.update(idx, |service| service.state = ServiceState::Exited);
+            return;
+        }
+
+        warn!("服务 {command:?} 异常退出: {status:?}");
+
+        if start.elapsed() >= HEALTHY_UPTIME {
+            failures = 0;
+        }
+        failures += 1;
+
+        if failures > max_restarts {
+            warn!("服务 {command:?} 连续失败 {failures} 次，停止重启");
+            services.update(idx, |service| service.state = ServiceState::Failed);
+            return;
+        }
+
+        services.update(idx, |service| {
+            service.state = ServiceState::Restarting;
+            service.restarts += 1;
+        });
+        thread::sleep(backoff(failures));
+    }
+}
+
+/// 计算第 `failures` 次连续失败后的重启等待时间
+fn backoff(failures: u32) -> Duration {
+    let exp = failures.saturating_sub(1).min(16);
+    (INITIAL_BACKOFF * 2u32.pow(exp)).min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        assert_eq!(backoff(1), Duration::from_secs(1));
+        assert_eq!(backoff(2), Duration::from_secs(2));
+        assert_eq!(backoff(3), Duration::from_secs(4));
+        assert_eq!(backoff(5), Duration::from_secs(16));
+        assert_eq!(backoff(6), MAX_BACKOFF);
+        assert_eq!(backoff(u32::MAX), MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,206 @@
+//! X11 显示套接字模块
+//!
+//! 在合成器中的作用：
+//! niri 预先创建 X11 显示的锁文件和监听套接字，再通过 `-listenfd` 交给 xwayland-satellite。
+//! 这样导出 DISPLAY 时套接字已经存在：satellite 就绪之前连接的 X11 客户端会在监听队列中等待，
+//! 而不是连接失败。satellite 崩溃重启时继续使用同一组套接字。
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::{SocketAddr, UnixListener};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// 查找空闲 X11 显示编号时的上限
+const MAX_X11_DISPLAY: u32 = 32;
+
+/// 当前 xwayland-satellite 进程的 PID（未运行时为 0）
+static SATELLITE_PID: AtomicI32 = AtomicI32::new(0);
+
+/// 一个 X11 显示的锁文件和监听套接字
+///
+/// 释放时删除锁文件和套接字文件。
+#[derive(Debug)]
+pub struct X11Sockets {
+    /// 显示编号（DISPLAY 为 `:N`）
+    display: u32,
+    lock_path: PathBuf,
+    socket_path: PathBuf,
+    /// `/tmp/.X11-unix/XN` 上的套接字
+    unix: UnixListener,
+    /// 抽象命名空间中同名的套接字（Xlib 在 Linux 上优先连接它）
+    abstract_: UnixListener,
+}
+
+impl X11Sockets {
+    /// 占用第一个空闲的 X11 显示
+    pub fn bind() -> Option<Self> {
+        Self::bind_in(Path::new("/tmp"))
+    }
+
+    /// 在 `tmp` 目录下占用第一个空闲的 X11 显示（测试时使用临时目录）
+    fn bind_in(tmp: &Path) -> Option<Self> {
+        let socket_dir = tmp.join(".X11-unix");
+        if let Err(err) = fs::create_dir_all(&socket_dir) {
+            warn!("error creating {socket_dir:?}: {err:?}");
+            return None;
+        }
+
+        (0..=MAX_X11_DISPLAY).find_map(|display| {
+            let lock_path = tmp.join(format!(".X{display}-lock"));
+            let socket_path = socket_dir.join(format!("X{display}"));
+            remove_stale_lock(&lock_path, &socket_path);
+
+            match Self::bind_display(display, lock_path, socket_path) {
+                Ok(sockets) => Some(sockets),
+                Err(err) => {
+                    trace!("X11 display :{display} is unavailable: {err:?}");
+                    None
+                }
+            }
+        })
+    }
+
+    fn bind_display(display: u32, lock_path: PathBuf, socket_path: PathBuf) -> io::Result<Self> {
+        // create_new 保证只有一个进程能拿到这个显示
+        let mut lock = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)?;
+
+        let res = (|| {
+            // X 服务器锁文件的格式：右对齐到 10 位的 PID 加换行
+            writeln!(lock, "{:>10}", std::process::id())?;
+
+            let unix = UnixListener::bind(&socket_path)?;
+            let addr = SocketAddr::from_abstract_name(socket_path.as_os_str().as_bytes());
+            let abstract_ = match addr.and_then(|addr| UnixListener::bind_addr(&addr)) {
+                Ok(listener) => listener,
+                Err(err) => {
+                    let _ = fs::remove_file(&socket_path);
+                    return Err(err);
+                }
+            };
+
+            Ok((unix, abstract_))
+        })();
+
+        match res {
+            Ok((unix, abstract_)) => Ok(Self {
+                display,
+                lock_path,
+                socket_path,
+                unix,
+                abstract_,
+            }),
+            Err(err) => {
+                let _ = fs::remove_file(&lock_path);
+                Err(err)
+            }
+        }
+    }
+
+    /// DISPLAY 环境变量的值（如 ":1"）
+    pub fn display_name(&self) -> String {
+        format!(":{}", self.display)
+    }
+
+    /// 需要传给 xwayland-satellite 的监听套接字
+    pub fn listen_fds(&self) -> [RawFd; 2] {
+        [self.unix.as_raw_fd(), self.abstract_.as_raw_fd()]
+    }
+}
+
+impl Drop for X11Sockets {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.socket_path);
+        let _ = fs::remove_file(&self.lock_path);
+    }
+}
+
+/// 删除进程已经不存在的锁文件及其套接字（例如 niri 上次没有正常退出）
+fn remove_stale_lock(lock_path: &Path, socket_path: &Path) {
+    let Ok(contents) = fs::read_to_string(lock_path) else {
+        return;
+    };
+    let Ok(pid) = contents.trim().parse::<u32>() else {
+        return;
+    };
+    if Path::new(&format!("/proc/{pid}")).exists() {
+        return;
+    }
+
+    debug!("removing stale X11 lock {lock_path:?} of pid {pid}");
+    let _ = fs::remove_file(socket_path);
+    let _ = fs::remove_file(lock_path);
+}
+
+/// 记录当前 xwayland-satellite 进程的 PID，`None` 表示已停止
+pub fn set_satellite_pid(pid: Option<i32>) {
+    SATELLITE_PID.store(pid.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// 该 PID 是否是 xwayland-satellite，即该客户端的窗口都是 X11 窗口
+pub fn is_satellite_pid(pid: i32) -> bool {
+    pid != 0 && SATELLITE_PID.load(Ordering::Relaxed) == pid
+}
+
+#[cfg(test)]
+mod tests {
+    use xshell::Shell;
+
+    use super::*;
+
+    #[test]
+    fn binds_first_free_display() {
+        let sh = Shell::new().unwrap();
+        let temp_dir = sh.create_temp_dir().unwrap();
+        let tmp = temp_dir.path();
+
+        let first = X11Sockets::bind_in(tmp).unwrap();
+        assert_eq!(first.display_name(), ":0");
+        assert!(tmp.join(".X11-unix/X0").exists());
+
+        let lock = fs::read_to_string(tmp.join(".X0-lock")).unwrap();
+        assert_eq!(lock.len(), 11);
+        assert_eq!(lock.trim(), std::process::id().to_string());
+
+        // The first display is taken, so the next one is used.
+        let second = X11Sockets::bind_in(tmp).unwrap();
+        assert_eq!(second.display_name(), ":1");
+
+        // Dropping frees the display again.
+        drop(first);
+        assert!(!tmp.join(".X0-lock").exists());
+        assert!(!tmp.join(".X11-unix/X0").exists());
+        let third = X11Sockets::bind_in(tmp).unwrap();
+        assert_eq!(third.display_name(), ":0");
+    }
+
+    #[test]
+    fn replaces_stale_lock() {
+        let sh = Shell::new().unwrap();
+        let temp_dir = sh.create_temp_dir().unwrap();
+        let tmp = temp_dir.path();
+
+        // PIDs never go this high on Linux, so this lock is always stale.
+        fs::write(tmp.join(".X0-lock"), format!("{:>10}\n", i32::MAX)).unwrap();
+
+        let sockets = X11Sockets::bind_in(tmp).unwrap();
+        assert_eq!(sockets.display_name(), ":0");
+    }
+
+    #[test]
+    fn satellite_pid() {
+        set_satellite_pid(Some(1234));
+        assert!(is_satellite_pid(1234));
+        assert!(!is_satellite_pid(1235));
+
+        set_satellite_pid(None);
+        assert!(!is_satellite_pid(1234));
+        assert!(!is_satellite_pid(0));
+    }
+}
@@ -0,0 +1,175 @@
+// window/focus_history.rs
+// 此文件实现全局的窗口焦点历史（MRU，最近使用顺序），与空间方向的导航无关
+// 用于“聚焦上一个窗口”和“循环浏览焦点历史”等操作
+
+/// 窗口焦点历史（最近聚焦的窗口排在最前）
+///
+/// 循环浏览历史时列表顺序保持不变，直到循环结束（焦点因其他原因改变，
+/// 或执行“聚焦上一个窗口”）时才把循环到的窗口移到最前
+#[derive(Debug)]
+pub struct FocusHistory<W> {
+    /// 按最近聚焦顺序排列的窗口
+    windows: Vec<W>,
+    /// 正在循环浏览时，当前所在的历史位置
+    cycle_pos: Option<usize>,
+}
+
+impl<W: Clone + PartialEq> FocusHistory<W> {
+    pub fn new() -> Self {
+        Self {
+            windows: Vec::new(),
+            cycle_pos: None,
+        }
+    }
+
+    /// 按最近聚焦顺序遍历窗口
+    pub fn iter(&self) -> impl Iterator<Item = &W> + '_ {
+        self.windows.iter()
+    }
+
+    /// 记录窗口获得焦点
+    pub fn record(&mut self, window: &W) {
+        // 由循环浏览自身引起的焦点变化不改变顺序
+        if let Some(pos) = self.cycle_pos {
+            if &self.windows[pos] == window {
+                return;
+            }
+        }
+
+        self.commit_cycle();
+        self.move_to_front(window.clone());
+    }
+
+    /// 从历史中移除窗口（窗口关闭或取消映射时调用）
+    pub fn remove(&mut self, window: &W) {
+        let Some(idx) = self.windows.iter().position(|w| w == window) else {
+            return;
+        };
+        self.windows.remove(idx);
+
+        self.cycle_pos = match self.cycle_pos {
+            Some(pos) if pos == idx => None,
+            Some(pos) if idx < pos => Some(pos - 1),
+            pos => pos,
+        };
+    }
+
+    /// 返回当前焦点之前最近聚焦的窗口
+    pub fn previous(&mut self, current: Option<&W>) -> Option<W> {
+        self.commit_cycle();
+        self.windows.iter().find(|w| Some(*w) != current).cloned()
+    }
+
+    /// 在历史中移动一步并返回要聚焦的窗口
+    ///
+    /// `older` 为 true 时移向更早聚焦的窗口，否则移向更近聚焦的窗口，两端循环
+    pub fn cycle(&mut self, current: Option<&W>, older: bool) -> Option<W> {
+        let len = self.windows.len();
+        if len == 0 {
+            return None;
+        }
+
+        let pos = self
+            .cycle_pos
+            .or_else(|| current.and_then(|c| self.windows.iter().position(|w| w == c)));
+
+        let next = match (pos, older) {
+            (Some(pos), true) => (pos + 1) % len,
+            (Some(pos), false) => (pos + len - 1) % len,
+            // 没有聚焦的窗口时，从最近聚焦的窗口开始
+            (None, true) => 0,
+            (None, false) => len - 1,
+        };
+
+        self.cycle_pos = Some(next);
+        Some(self.windows[next].clone())
+    }
+
+    /// 结束循环浏览，把循环到的窗口移到最前
+    fn commit_cycle(&mut self) {
+        if let Some(pos) = self.cycle_pos.take() {
+            let window = self.windows.remove(pos);
+            self.windows.insert(0, window);
+        }
+    }
+
+    fn move_to_front(&mut self, window: W) {
+        self.windows.retain(|w| *w != window);
+        self.windows.insert(0, window);
+    }
+}
+
+impl<W: Clone + PartialEq> Default for FocusHistory<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(windows: &[u32]) -> FocusHistory<u32> {
+        let mut history = FocusHistory::new();
+        for window in windows {
+            history.record(window);
+        }
+        history
+    }
+
+    fn order(history: &FocusHistory<u32>) -> Vec<u32> {
+        history.iter().copied().collect()
+    }
+
+    #[test]
+    fn record_moves_to_front() {
+        let mut history = history(&[1, 2, 3]);
+        assert_eq!(order(&history), [3, 2, 1]);
+
+        history.record(&1);
+        assert_eq!(order(&history), [1, 3, 2]);
+        assert_eq!(history.previous(Some(&1)), Some(3));
+
+        // 聚焦空工作区时回到刚才聚焦的窗口
+        assert_eq!(history.previous(None), Some(1));
+    }
+
+    #[test]
+    fn cycle_keeps_order_until_committed() {
+        let mut history = history(&[1, 2, 3, 4]);
+
+        assert_eq!(history.cycle(Some(&4), true), Some(3));
+        history.record(&3);
+        assert_eq!(history.cycle(Some(&3), true), Some(2));
+        history.record(&2);
+        assert_eq!(order(&history), [4, 3, 2, 1]);
+
+        // 焦点因其他原因改变时结束循环
+        history.record(&1);
+        assert_eq!(order(&history), [1, 2, 4, 3]);
+    }
+
+    #[test]
+    fn cycle_wraps_around() {
+        let mut history = history(&[1, 2, 3]);
+
+        assert_eq!(history.cycle(Some(&3), false), Some(1));
+        assert_eq!(history.cycle(Some(&1), true), Some(3));
+        assert_eq!(history.cycle(Some(&3), true), Some(2));
+        assert_eq!(history.previous(Some(&2)), Some(3));
+        assert_eq!(order(&history), [2, 3, 1]);
+    }
+
+    #[test]
+    fn remove_prunes_cycle() {
+        let mut history = history(&[1, 2, 3]);
+
+        assert_eq!(history.cycle(Some(&3), true), Some(2));
+        history.remove(&3);
+        assert_eq!(history.cycle(Some(&2), true), Some(1));
+
+        history.remove(&1);
+        assert_eq!(order(&history), [2]);
+        assert_eq!(history.previous(Some(&2)), None);
+    }
+}
@@ -3,10 +3,10 @@
 // 在合成器中，已映射窗口代表用户可见并可交互的窗口实体
 
 use std::cell::{Cell, Ref, RefCell};  // 内部可变性容器
+use std::mem;  // 内存工具
 use std::time::Duration;  // 时间间隔
 
-use niri_config::{Color, CornerRadius, GradientInterpolation, WindowRule};  // 配置结构
-use smithay::backend::renderer::element::surface::render_elements_from_surface_tree;  // 表面渲染
+use niri_config::{Color, CornerRadius, GradientInterpolation, UrgencyFlash, WindowRule};  // 配置结构
 use smithay::backend::renderer::element::Kind;  // 渲染元素类型
 use smithay::backend::renderer::gles::GlesRenderer;  // OpenGL渲染器
 use smithay::desktop::space::SpaceElement as _;  // 空间元素特性
@@ -23,7 +23,8 @@ use smithay::wayland::shell::xdg::{SurfaceCachedState, ToplevelSurface};  // XDG
 use wayland_backend::server::Credentials;  // 进程凭证
 
 // 本地模块
-use super::{ResolvedWindowRules, WindowRef};  // 窗口规则和引用
+use super::{MapSize, ResolvedWindowRules, WindowRef};  // 窗口规则、映射尺寸和引用
+use crate::animation::{Animation, Clock, Curve};  // 动画
 use crate::layout::{  // 布局相关
     ConfigureIntent, InteractiveResizeData, LayoutElement, LayoutElementRenderElement,
     LayoutElementRenderSnapshot,
@@ -34,7 +35,10 @@ use crate::render_helpers::offscreen::OffscreenData;  // 离屏渲染数据
 use crate::render_helpers::renderer::NiriRenderer;  // 自定义渲染器
 use crate::render_helpers::snapshot::RenderSnapshot;  // 渲染快照
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};  // 纯色渲染
-use crate::render_helpers::surface::render_snapshot_from_surface_tree;  // 表面快照
+use crate::render_helpers::surface::{
+    render_elements_from_surface_tree, render_snapshot_from_surface_tree,
+};  // 表面渲染和快照
+use crate::render_helpers::urgency_flash::pulse_intensity;  // 紧急闪烁强度
 use crate::render_helpers::{BakedBuffer, RenderTarget, SplitElements};  // 渲染辅助
 use crate::utils::id::IdCounter;  // ID生成器
 use crate::utils::transaction::Transaction;  // 事务处理
@@ -62,6 +66,12 @@ pub struct Mapped {
     /// 当前应用的窗口规则
     rules: ResolvedWindowRules,
 
+    /// 映射时的尺寸信息（重新计算规则时保持按尺寸匹配的结果不变）
+    map_size: Option<MapSize>,
+
+    /// 窗口打开时 niri 是否处于启动阶段（重新计算规则时保持 at-startup 的匹配结果不变）
+    is_at_startup: bool,
+
     /// 标记是否需要重新计算规则
     need_to_recompute_rules: bool,
 
@@ -71,12 +81,27 @@ pub struct Mapped {
     /// 标记是否需要帧回调
     needs_frame_callback: bool,
 
+    /// 上次发送帧回调的时间（用于按 max-render-fps 限制帧率）
+    last_frame_callback_at: Option<Duration>,
+
     /// 离屏渲染数据（当窗口被移出屏幕时使用）
     offscreen_data: RefCell<Option<OffscreenData>>,
 
     /// 窗口是否处于紧急状态（需要用户注意）
     is_urgent: bool,
 
+    /// 紧急闪烁动画，值从 0 增长到脉冲次数
+    urgency_flash: Option<Animation>,
+
+    /// 刚变为紧急状态，等规则重新计算后开始闪烁
+    start_urgency_flash: bool,
+
+    /// 驱动紧急闪烁动画的时钟
+    clock: Clock,
+
+    /// 是否不匹配概览中的搜索（此时变暗绘制）
+    is_filtered_out: bool,
+
     /// 窗口是否拥有键盘焦点
     is_focused: bool,
 
@@ -92,6 +117,9 @@ pub struct Mapped {
     /// 是否忽略不透明度规则
     ignore_opacity_window_rule: bool,
 
+    /// 通过操作切换的宽高比锁定（覆盖 keep-aspect-ratio 窗口规则）
+    keep_aspect_ratio_override: Option<bool>,
+
     /// 屏蔽渲染时的纯色缓冲区
     block_out_buffer: RefCell<SolidColorBuffer>,
 
@@ -194,7 +222,14 @@ enum RequestSizeOnce {
 
 impl Mapped {
     // 创建新的已映射窗口
-    pub fn new(window: Window, rules: ResolvedWindowRules, hook: HookId) -> Self {
+    pub fn new(
+        window: Window,
+        rules: ResolvedWindowRules,
+        map_size: Option<MapSize>,
+        is_at_startup: bool,
+        hook: HookId,
+        clock: Clock,
+    ) -> Self {
         // 获取窗口的Wayland表面
         let surface = window.wl_surface().expect("no X11 support");
         // 获取创建此表面的进程凭证
@@ -207,16 +242,24 @@ impl Mapped {
             credentials,
             pre_commit_hook: hook,  // 保存预提交钩子
             rules,  // 初始规则
+            map_size,
+            is_at_startup,
             need_to_recompute_rules: false,
             needs_configure: false,
             needs_frame_callback: false,
+            last_frame_callback_at: None,
             offscreen_data: RefCell::new(None),  // 无离屏数据
             is_urgent: false,
+            urgency_flash: None,
+            start_urgency_flash: false,
+            clock,
+            is_filtered_out: false,
             is_focused: false,
             is_active_in_column: true,  // 默认在列中激活
             is_floating: false,
             is_window_cast_target: false,
             ignore_opacity_window_rule: false,
+            keep_aspect_ratio_override: None,
             // 创建黑色屏蔽缓冲区
             block_out_buffer: RefCell::new(SolidColorBuffer::new((0., 0.), [0., 0., 0., 1.])),
             animate_next_configure: false,
@@ -239,35 +282,44 @@ impl Mapped {
     }
 
     /// 重新计算窗口规则并返回是否更改
-    pub fn recompute_window_rules(&mut self, rules: &[WindowRule], is_at_startup: bool) -> bool {
+    pub fn recompute_window_rules(&mut self, rules: &[WindowRule]) -> bool {
         self.need_to_recompute_rules = false;  // 重置标志
 
         // 计算新规则
-        let new_rules = ResolvedWindowRules::compute(rules, WindowRef::Mapped(self), is_at_startup);
-        if new_rules == self.rules {
-            return false;  // 无变化
+        let new_rules = ResolvedWindowRules::compute(rules, WindowRef::Mapped(self));
+        let changed = new_rules != self.rules;
+        if changed {
+            // 如果新规则不再设置半透明，重置忽略标志
+            if !new_rules.opacity.is_some_and(|o| o < 1.) {
+                self.ignore_opacity_window_rule = false;
+            }
+
+            self.rules = new_rules;  // 更新规则
         }
 
-        // 如果新规则不再设置半透明，重置忽略标志
-        if !new_rules.opacity.is_some_and(|o| o < 1.) {
-            self.ignore_opacity_window_rule = false;
+        // 规则已考虑紧急状态（例如 match is-urgent=true），此时再开始闪烁
+        if mem::take(&mut self.start_urgency_flash) {
+            self.urgency_flash = self
+                .rules
+                .urgency_flash
+                .filter(|flash| !flash.off)
+                .map(|flash| {
+                    let pulses = f64::from(flash.pulses);
+                    let duration = u64::from(flash.pulses) * u64::from(flash.pulse_duration_ms);
+                    Animation::ease(self.clock.clone(), 0., pulses, 0., duration, Curve::Linear)
+                });
         }
 
-        self.rules = new_rules;  // 更新规则
-        true  // 规则已更改
+        changed  // 规则是否已更改
     }
 
     // 如果需要则重新计算规则
-    pub fn recompute_window_rules_if_needed(
-        &mut self,
-        rules: &[WindowRule],
-        is_at_startup: bool,
-    ) -> bool {
+    pub fn recompute_window_rules_if_needed(&mut self, rules: &[WindowRule]) -> bool {
         if !self.need_to_recompute_rules {
             return false;
         }
 
-        self.recompute_window_rules(rules, is_at_startup)
+        self.recompute_window_rules(rules)
     }
 
     // 标记需要配置事件
@@ -310,11 +362,26 @@ impl Mapped {
         self.is_window_cast_target
     }
 
+    // 获取映射时的尺寸信息
+    pub fn map_size(&self) -> Option<MapSize> {
+        self.map_size
+    }
+
+    // 窗口打开时 niri 是否处于启动阶段
+    pub fn is_at_startup(&self) -> bool {
+        self.is_at_startup
+    }
+
     // 切换忽略不透明度规则
     pub fn toggle_ignore_opacity_window_rule(&mut self) {
         self.ignore_opacity_window_rule = !self.ignore_opacity_window_rule;
     }
 
+    // 切换宽高比锁定（之后不再跟随窗口规则）
+    pub fn toggle_keep_aspect_ratio(&mut self) {
+        self.keep_aspect_ratio_override = Some(!self.keeps_aspect_ratio());
+    }
+
     // 设置聚焦状态
     pub fn set_is_focused(&mut self, is_focused: bool) {
         if self.is_focused == is_focused {
@@ -323,6 +390,8 @@ impl Mapped {
 
         self.is_focused = is_focused;
         self.is_urgent = false;  // 聚焦时清除紧急状态
+        self.urgency_flash = None;
+        self.start_urgency_flash = false;
         self.need_to_recompute_rules = true;  // 标记需要重新计算规则
     }
 
@@ -471,17 +540,36 @@ impl Mapped {
     }
 
     /// 发送帧回调
+    ///
+    /// 若窗口规则限制了帧率且距上次发送太近，则不发送，并返回还需等待的时间。
     pub fn send_frame<T, F>(
         &mut self,
         output: &Output,
         time: T,
         throttle: Option<Duration>,
         mut primary_scan_out_output: F,
-    ) where
+    ) -> Option<Duration>
+    where
         T: Into<Duration>,
         F: FnMut(&WlSurface, &SurfaceData) -> Option<Output> + Copy,
     {
+        let time = time.into();
         let needs_frame_callback = self.needs_frame_callback;
+
+        // 按规则限制帧率，但尺寸变化等需要立即响应的情况除外
+        if let Some(fps) = self.rules.max_render_fps {
+            if !needs_frame_callback {
+                if let Some(last) = self.last_frame_callback_at {
+                    let interval = Duration::from_secs_f64(1. / fps);
+                    let elapsed = time.saturating_sub(last);
+                    if elapsed < interval {
+                        return Some(interval - elapsed);
+                    }
+                }
+            }
+            self.last_frame_callback_at = Some(time);
+        }
+
         self.needs_frame_callback = false;
 
         // 决定是否发送帧回调
@@ -495,6 +583,7 @@ impl Mapped {
             needs_frame_callback.then(|| output.clone())
         };
         self.window.send_frame(output, time, throttle, should_send);
+        None
     }
 
     // 更新平铺状态
@@ -517,12 +606,27 @@ impl Mapped {
         let changed = self.is_urgent != urgent;
         self.is_urgent = urgent;
         self.need_to_recompute_rules |= changed;
+
+        // 变为紧急时闪烁，解除紧急时停止
+        if changed {
+            self.start_urgency_flash = urgent;
+            if !urgent {
+                self.urgency_flash = None;
+            }
+        }
     }
 
     // 检查是否紧急
     pub fn is_urgent(&self) -> bool {
         self.is_urgent
     }
+
+    // 设置是否被概览搜索过滤掉，返回是否有变化
+    pub fn set_filtered_out(&mut self, filtered_out: bool) -> bool {
+        let changed = self.is_filtered_out != filtered_out;
+        self.is_filtered_out = filtered_out;
+        changed
+    }
 }
 
 // 析构函数实现
@@ -542,6 +646,7 @@ impl Drop for Mapped {
 2. 规则系统:
    - recompute_window_rules(): 动态更新规则
    - toggle_ignore_opacity_window_rule(): 用户覆盖不透明度规则
+   - toggle_keep_aspect_ratio(): 用户覆盖宽高比锁定规则
 
 3. 渲染系统:
    - render_snapshot(): 创建不含弹出菜单的快照
@@ -915,6 +1020,17 @@ impl LayoutElement for Mapped {
         self.is_urgent
     }
 
+    fn urgency_flash(&self) -> Option<(UrgencyFlash, f32)> {
+        let anim = self.urgency_flash.as_ref().filter(|anim| !anim.is_done())?;
+        let config = self.rules.urgency_flash.filter(|flash| !flash.off)?;
+
+        Some((config, pulse_intensity(anim.value())))
+    }
+
+    fn is_filtered_out(&self) -> bool {
+        self.is_filtered_out
+    }
+
     fn set_activated(&mut self, active: bool) {
         let changed = self.toplevel().with_pending_state(|state| {
             if active {
@@ -1112,6 +1228,11 @@ impl LayoutElement for Mapped {
         self.ignore_opacity_window_rule
     }
 
+    fn keeps_aspect_ratio(&self) -> bool {
+        self.keep_aspect_ratio_override
+            .unwrap_or(self.rules.keep_aspect_ratio == Some(true))
+    }
+
     fn requested_size(&self) -> Option<Size<i32, Logical>> {
         self.toplevel().with_pending_state(|state| state.size)
     }
@@ -5,21 +5,33 @@
 use std::cmp::{max, min};  // 比较函数
 
 use niri_config::{  // 配置结构体
-    BlockOutFrom, BorderRule, CornerRadius, FloatingPosition, Match, PresetSize, ShadowRule,
-    TabIndicatorRule, WindowRule,
+    BlockOutFrom, BorderRule, CornerRadius, FloatingPosition, FocusAfterClose, Match, PresetSize,
+    ResizeContent, ShadowRule, TabIndicatorRule, UrgencyFlash, WindowRule, WindowSize,
 };
-use niri_ipc::ColumnDisplay;  // IPC通信定义
+use niri_ipc::{ColumnDisplay, WindowRuleMatch};  // IPC通信定义
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;  // Wayland顶层协议
+use smithay::reexports::wayland_server::Resource as _;  // 获取表面所属客户端
 use smithay::utils::{Logical, Size};  // 逻辑坐标和尺寸
 use smithay::wayland::compositor::with_states;  // Wayland状态访问
 use smithay::wayland::shell::xdg::{  // XDG shell实现
     SurfaceCachedState, ToplevelSurface, XdgToplevelSurfaceRoleAttributes,
 };
 
+use crate::niri::ClientState;  // 客户端状态（进程和沙盒信息）
 use crate::utils::with_toplevel_role;  // 辅助函数
+use crate::utils::xwayland::is_satellite_pid;  // 判断 X11 窗口
+
+// 子模块：窗口焦点历史
+pub mod focus_history;
 
 // 子模块：已映射窗口管理
 pub mod mapped;
+
+// 子模块：等待关闭的窗口（关闭或强制结束）
+pub mod pending_close;
+
+// 子模块：窗口缩略图缓存
+pub mod thumbnails;
 pub use mapped::Mapped;  // 导出已映射窗口结构体
 
 // 子模块：未映射窗口管理
@@ -33,6 +45,15 @@ pub enum WindowRef<'a> {
     Mapped(&'a Mapped),      // 已映射窗口引用
 }
 
+/// 窗口映射时的尺寸信息，供按尺寸匹配的规则使用
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapSize {
+    /// 窗口映射时的尺寸（已限制在窗口的最小/最大尺寸提示内）
+    pub size: Size<i32, Logical>,
+    /// 目标输出的工作区域尺寸
+    pub working_area: Size<f64, Logical>,
+}
+
 /// 已解析的窗口规则集合
 /// 包含所有应用到窗口的规则计算结果
 #[derive(Debug, PartialEq)]
@@ -66,12 +87,18 @@ pub struct ResolvedWindowRules {
     
     /// 是否聚焦打开
     pub open_focused: Option<bool>,
+
+    /// 是否跳过打开动画（None 表示自动检测客户端自己的淡入）
+    pub skip_open_animation: Option<bool>,
     
     // 尺寸约束
     pub min_width: Option<u16>,
     pub min_height: Option<u16>,
     pub max_width: Option<u16>,
     pub max_height: Option<u16>,
+
+    /// 浮动窗口调整大小时是否保持宽高比（例如画中画视频窗口）
+    pub keep_aspect_ratio: Option<bool>,
     
     // 外观规则
     pub focus_ring: BorderRule,           // 聚焦边框规则
@@ -91,6 +118,9 @@ pub struct ResolvedWindowRules {
     /// 是否裁剪到几何形状（含圆角）
     pub clip_to_geometry: Option<bool>,
     
+    /// 调整大小动画中窗口内容的显示方式
+    pub resize_content: Option<ResizeContent>,
+    
     /// 是否启用浮动动画（上下浮动）
     pub baba_is_float: Option<bool>,
     
@@ -105,6 +135,24 @@ pub struct ResolvedWindowRules {
     
     /// 是否设置平铺状态
     pub tiled_state: Option<bool>,
+
+    /// 是否关闭该窗口的打开、关闭、尺寸变化和移动动画
+    pub skip_animations: Option<bool>,
+
+    /// 窗口变为紧急状态时的闪烁效果（None 表示不闪烁）
+    pub urgency_flash: Option<UrgencyFlash>,
+
+    /// 向该窗口发送帧回调的最高频率
+    pub max_render_fps: Option<f64>,
+
+    /// 该窗口关闭后聚焦哪个窗口（覆盖布局设置）
+    pub focus_after_close: Option<FocusAfterClose>,
+
+    /// 由该窗口的子进程打开的平铺窗口是否吞噬该窗口（替换它的位置，关闭后恢复）
+    pub swallow_children: Option<bool>,
+
+    /// 窗口的饱和度，0 为灰度，1 为原色（例如用于让未聚焦的窗口变灰）
+    pub saturation: Option<f32>,
 }
 
 // 窗口引用方法实现
@@ -158,6 +206,72 @@ impl<'a> WindowRef<'a> {
             WindowRef::Mapped(mapped) => mapped.is_window_cast_target(),
         }
     }
+
+    /// 获取窗口映射时的尺寸信息（映射前未知）
+    pub fn map_size(self) -> Option<MapSize> {
+        match self {
+            WindowRef::Unmapped(unmapped) => unmapped.map_size,
+            WindowRef::Mapped(mapped) => mapped.map_size(),
+        }
+    }
+
+    /// 窗口打开时 niri 是否处于启动阶段（用于匹配 at-startup）
+    pub fn is_at_startup(self) -> bool {
+        match self {
+            WindowRef::Unmapped(unmapped) => unmapped.is_at_startup,
+            WindowRef::Mapped(mapped) => mapped.is_at_startup(),
+        }
+    }
+}
+
+impl MapSize {
+    /// 根据窗口映射时的几何尺寸和工作区域计算
+    pub fn new(
+        toplevel: &ToplevelSurface,
+        size: Size<i32, Logical>,
+        working_area: Size<f64, Logical>,
+    ) -> Self {
+        let (min_size, max_size) = with_states(toplevel.wl_surface(), |state| {
+            let mut guard = state.cached_state.get::<SurfaceCachedState>();
+            let current = guard.current();
+            (current.min_size, current.max_size)
+        });
+
+        // 尺寸提示为 0 表示不限制
+        let clamp = |value: i32, min_value: i32, max_value: i32| {
+            let value = if max_value > 0 { min(value, max_value) } else { value };
+            max(value, min_value)
+        };
+        let size = Size::from((
+            clamp(size.w, min_size.w, max_size.w),
+            clamp(size.h, min_size.h, max_size.h),
+        ));
+
+        Self { size, working_area }
+    }
+
+    /// 尺寸类别：宽高都不超过工作区域一半时为小窗口
+    pub fn category(&self) -> WindowSize {
+        let size = self.size.to_f64();
+        if size.w <= self.working_area.w / 2. && size.h <= self.working_area.h / 2. {
+            WindowSize::Small
+        } else {
+            WindowSize::Large
+        }
+    }
+
+    /// 窗口覆盖工作区域的百分比（超出工作区域的部分不计）
+    pub fn coverage(&self) -> f64 {
+        let area = self.working_area.w * self.working_area.h;
+        if area <= 0. {
+            return 0.;
+        }
+
+        let size = self.size.to_f64();
+        let w = size.w.clamp(0., self.working_area.w);
+        let h = size.h.clamp(0., self.working_area.h);
+        w * h / area * 100.
+    }
 }
 
 // 已解析规则方法实现
@@ -176,10 +290,12 @@ impl ResolvedWindowRules {
             open_fullscreen: None,
             open_floating: None,
             open_focused: None,
+            skip_open_animation: None,
             min_width: None,
             min_height: None,
             max_width: None,
             max_height: None,
+            keep_aspect_ratio: None,
             // 边框规则默认值
             focus_ring: BorderRule {
                 off: false,
@@ -225,11 +341,18 @@ impl ResolvedWindowRules {
             opacity: None,
             geometry_corner_radius: None,
             clip_to_geometry: None,
+            resize_content: None,
             baba_is_float: None,
             block_out_from: None,
             variable_refresh_rate: None,
             scroll_factor: None,
             tiled_state: None,
+            skip_animations: None,
+            urgency_flash: None,
+            max_render_fps: None,
+            focus_after_close: None,
+            swallow_children: None,
+            saturation: None,
         }
     }
     
@@ -237,9 +360,31 @@ impl ResolvedWindowRules {
     /// 参数:
     ///   rules - 所有可用规则列表
     ///   window - 目标窗口引用
-    ///   is_at_startup - 是否在启动阶段
-    pub fn compute(rules: &[WindowRule], window: WindowRef, is_at_startup: bool) -> Self {
+    ///
+    /// at-startup 按窗口打开时的状态匹配，而不是当前状态，
+    /// 这样窗口的规则在启动阶段结束前后保持一致
+    pub fn compute(rules: &[WindowRule], window: WindowRef) -> Self {
+        Self::compute_inner(rules, window, None)
+    }
+    
+    /// 计算窗口应用的规则，并记录每条规则的匹配情况（用于调试规则）
+    pub fn compute_with_matches(
+        rules: &[WindowRule],
+        window: WindowRef,
+    ) -> (Self, Vec<WindowRuleMatch>) {
+        let mut matches = Vec::with_capacity(rules.len());
+        let resolved = Self::compute_inner(rules, window, Some(&mut matches));
+        (resolved, matches)
+    }
+    
+    fn compute_inner(
+        rules: &[WindowRule],
+        window: WindowRef,
+        mut trace: Option<&mut Vec<WindowRuleMatch>>,
+    ) -> Self {
         let _span = tracy_client::span!("ResolvedWindowRules::compute");  // 性能分析
+
+        let is_at_startup = window.is_at_startup();
         
         // 创建空规则集合
         let mut resolved = ResolvedWindowRules::empty();
@@ -256,7 +401,7 @@ impl ResolvedWindowRules {
             let mut open_on_workspace = None;
             
             // 遍历所有规则
-            for rule in rules {
+            for (index, rule) in rules.iter().enumerate() {
                 // 定义匹配函数（闭包）
                 let matches = |m: &Match| {
                     // 检查启动条件
@@ -270,13 +415,43 @@ impl ResolvedWindowRules {
                     window_matches(window, role, m)
                 };
                 
-                // 检查规则是否适用（匹配任意条件且不排除）
-                if !(rule.matches.is_empty() || rule.matches.iter().any(matches)) {
-                    continue;  // 跳过不匹配规则
-                }
-                
-                if rule.excludes.iter().any(matches) {
-                    continue;  // 排除条件匹配，跳过
+                // 调试时逐条检查所有条件，记录匹配结果
+                if let Some(trace) = trace.as_deref_mut() {
+                    let matching = |directives: &[Match]| -> Vec<usize> {
+                        (directives.iter().enumerate())
+                            .filter(|(_, m)| matches(*m))
+                            .map(|(i, _)| i)
+                            .collect()
+                    };
+                    let matched = matching(&rule.matches);
+                    let excluded = matching(&rule.excludes);
+                    let applied = !rule.off
+                        && (rule.matches.is_empty() || !matched.is_empty())
+                        && excluded.is_empty();
+                    trace.push(WindowRuleMatch {
+                        index,
+                        matched,
+                        excluded,
+                        applied,
+                    });
+                    
+                    if !applied {
+                        continue;
+                    }
+                } else {
+                    // 跳过运行时被禁用的规则
+                    if rule.off {
+                        continue;
+                    }
+
+                    // 检查规则是否适用（匹配任意条件且不排除）
+                    if !(rule.matches.is_empty() || rule.matches.iter().any(matches)) {
+                        continue;  // 跳过不匹配规则
+                    }
+                    
+                    if rule.excludes.iter().any(matches) {
+                        continue;  // 排除条件匹配，跳过
+                    }
                 }
                 
                 // 应用规则属性（条件覆盖）
@@ -317,6 +492,9 @@ impl ResolvedWindowRules {
                 if let Some(x) = rule.open_focused {
                     resolved.open_focused = Some(x);
                 }
+                if let Some(x) = rule.skip_open_animation {
+                    resolved.skip_open_animation = Some(x);
+                }
                 
                 // 尺寸约束规则
                 if let Some(x) = rule.min_width {
@@ -331,6 +509,9 @@ impl ResolvedWindowRules {
                 if let Some(x) = rule.max_height {
                     resolved.max_height = Some(x);
                 }
+                if let Some(x) = rule.keep_aspect_ratio {
+                    resolved.keep_aspect_ratio = Some(x);
+                }
                 
                 // 外观规则（合并方式）
                 resolved.focus_ring.merge_with(&rule.focus_ring);
@@ -351,6 +532,9 @@ impl ResolvedWindowRules {
                 if let Some(x) = rule.clip_to_geometry {
                     resolved.clip_to_geometry = Some(x);
                 }
+                if let Some(x) = rule.resize_content {
+                    resolved.resize_content = Some(x);
+                }
                 if let Some(x) = rule.baba_is_float {
                     resolved.baba_is_float = Some(x);
                 }
@@ -366,6 +550,24 @@ impl ResolvedWindowRules {
                 if let Some(x) = rule.tiled_state {
                     resolved.tiled_state = Some(x);
                 }
+                if let Some(x) = rule.skip_animations {
+                    resolved.skip_animations = Some(x);
+                }
+                if let Some(x) = rule.urgency_flash {
+                    resolved.urgency_flash = Some(x);
+                }
+                if let Some(x) = rule.max_render_fps {
+                    resolved.max_render_fps = Some(x.0);
+                }
+                if let Some(x) = rule.focus_after_close {
+                    resolved.focus_after_close = Some(x);
+                }
+                if let Some(x) = rule.swallow_children {
+                    resolved.swallow_children = Some(x);
+                }
+                if let Some(x) = rule.saturation {
+                    resolved.saturation = Some(x);
+                }
             }
             
             // 设置最终打开位置
@@ -526,10 +728,96 @@ fn window_matches(window: WindowRef, role: &XdgToplevelSurfaceRoleAttributes, m:
         }
     }
     
+    // 检查尺寸类别和覆盖率（窗口映射前尺寸未知，不匹配）
+    if m.size.is_some() || m.min_coverage.is_some() {
+        let Some(map_size) = window.map_size() else {
+            return false;
+        };
+        if m.size.is_some_and(|size| map_size.category() != size) {
+            return false;
+        }
+        if m.min_coverage.is_some_and(|min| map_size.coverage() < min.0) {
+            return false;
+        }
+    }
+
+    // 检查客户端的沙盒、可执行文件，以及是否为 X11 窗口
+    if m.sandbox_engine.is_some()
+        || m.sandbox_app_id.is_some()
+        || m.executable.is_some()
+        || m.is_x11.is_some()
+    {
+        let client = window.toplevel().wl_surface().client();
+        let Some(data) = client.as_ref().and_then(|c| c.get_data::<ClientState>()) else {
+            return false;
+        };
+
+        if let Some(engine_re) = &m.sandbox_engine {
+            let Some(engine) = data.sandbox_engine() else {
+                return false;
+            };
+            if !engine_re.0.is_match(engine) {
+                return false;
+            }
+        }
+        if let Some(app_id_re) = &m.sandbox_app_id {
+            let Some(app_id) = data.sandbox_app_id() else {
+                return false;
+            };
+            if !app_id_re.0.is_match(app_id) {
+                return false;
+            }
+        }
+        if let Some(executable_re) = &m.executable {
+            let executable = data.process.get().and_then(|p| p.executable.as_ref());
+            let Some(executable) = executable.and_then(|exe| exe.to_str()) else {
+                return false;
+            };
+            if !executable_re.0.is_match(executable) {
+                return false;
+            }
+        }
+        // xwayland-satellite 以自己的身份为所有 X11 窗口连接 niri
+        if let Some(is_x11) = m.is_x11 {
+            let pid = data.process.get().map(|p| p.pid);
+            if pid.is_some_and(is_satellite_pid) != is_x11 {
+                return false;
+            }
+        }
+    }
+    
     // 所有条件通过
     true
 }
 
+/// 从 /proc/<pid>/stat 的内容中解析父进程 PID
+///
+/// 进程名可能包含空格和括号，所以从最后一个右括号之后开始解析：状态字段之后就是父进程 PID
+fn parse_parent_pid(stat: &str) -> Option<i32> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// 判断进程 `pid` 是否是进程 `ancestor` 的后代
+///
+/// 用于窗口吞噬：终端中启动的图形程序是终端进程的后代
+pub fn is_descendant_process(pid: i32, ancestor: i32) -> bool {
+    let mut pid = pid;
+    // 限制深度，防止进程树在读取过程中变化导致死循环
+    for _ in 0..64 {
+        let Ok(stat) = std::fs::read_to_string(format!("/proc/{pid}/stat")) else {
+            return false;
+        };
+        match parse_parent_pid(&stat) {
+            Some(parent) if parent == ancestor => return true,
+            // PID 1 是 init，再往上没有意义
+            Some(parent) if parent > 1 => pid = parent,
+            _ => return false,
+        }
+    }
+    false
+}
+
 /* 窗口规则系统详解
 
 1. 规则匹配流程
@@ -566,4 +854,39 @@ fn window_matches(window: WindowRef, role: &XdgToplevelSurfaceRoleAttributes, m:
    - 窗口打开时应用初始规则
    - 运行时动态更新规则
    - 用户配置自定义窗口行为
-*/
\ No newline at end of file
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map_size(w: i32, h: i32) -> MapSize {
+        MapSize {
+            size: Size::from((w, h)),
+            working_area: Size::from((1000., 800.)),
+        }
+    }
+
+    #[test]
+    fn map_size_category() {
+        assert_eq!(map_size(500, 400).category(), WindowSize::Small);
+        assert_eq!(map_size(501, 100).category(), WindowSize::Large);
+        assert_eq!(map_size(100, 401).category(), WindowSize::Large);
+    }
+
+    #[test]
+    fn parent_pid_from_stat() {
+        assert_eq!(parse_parent_pid("42 (bash) S 7 42 42 0"), Some(7));
+        // 进程名中的空格和括号不影响解析
+        assert_eq!(parse_parent_pid("43 (a) b (c) R 12 43 43 0"), Some(12));
+        assert_eq!(parse_parent_pid("garbage"), None);
+    }
+
+    #[test]
+    fn map_size_coverage() {
+        assert_eq!(map_size(500, 400).coverage(), 25.);
+        // 超出工作区域的部分不计
+        assert_eq!(map_size(2000, 400).coverage(), 50.);
+        assert_eq!(map_size(0, 0).coverage(), 0.);
+    }
+}
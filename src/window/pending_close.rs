@@ -0,0 +1,88 @@
+// window/pending_close.rs
+// 此文件实现“关闭或强制结束”操作的待关闭窗口跟踪
+// 先礼貌地请求窗口关闭，超时后窗口仍在时再询问（或直接）断开其客户端
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 等待窗口关闭的默认超时
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 一个已请求关闭、正在等待的窗口
+#[derive(Debug)]
+pub struct PendingClose<T> {
+    /// 超时后是否直接断开客户端而不询问
+    pub auto_kill: bool,
+    /// 超时计时器
+    pub timer: T,
+}
+
+/// 待关闭窗口跟踪器，按窗口 id 索引
+#[derive(Debug)]
+pub struct PendingCloses<T> {
+    entries: HashMap<u64, PendingClose<T>>,
+}
+
+impl<T> PendingCloses<T> {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        self.entries.contains_key(&id)
+    }
+
+    /// 记录等待关闭的窗口，返回被替换的旧记录（调用方需要取消其计时器）
+    pub fn insert(&mut self, id: u64, pending: PendingClose<T>) -> Option<PendingClose<T>> {
+        self.entries.insert(id, pending)
+    }
+
+    /// 移除窗口的记录（超时触发、窗口关闭或用户作出选择时调用）
+    pub fn remove(&mut self, id: u64) -> Option<PendingClose<T>> {
+        self.entries.remove(&id)
+    }
+}
+
+impl<T> Default for PendingCloses<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 计算等待超时，未指定时使用默认值
+pub fn timeout(timeout_ms: Option<u32>) -> Duration {
+    timeout_ms.map_or(DEFAULT_TIMEOUT, |ms| Duration::from_millis(u64::from(ms)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(timer: u32) -> PendingClose<u32> {
+        PendingClose {
+            auto_kill: false,
+            timer,
+        }
+    }
+
+    #[test]
+    fn insert_returns_replaced_timer() {
+        let mut closes = PendingCloses::new();
+        assert!(closes.insert(1, pending(10)).is_none());
+        assert!(closes.contains(1));
+
+        // 重复请求关闭时旧的计时器需要取消
+        assert_eq!(closes.insert(1, pending(11)).unwrap().timer, 10);
+        assert_eq!(closes.remove(1).unwrap().timer, 11);
+        assert!(!closes.contains(1));
+        assert!(closes.remove(1).is_none());
+    }
+
+    #[test]
+    fn timeout_defaults() {
+        assert_eq!(timeout(None), DEFAULT_TIMEOUT);
+        assert_eq!(timeout(Some(1500)), Duration::from_millis(1500));
+    }
+}
@@ -0,0 +1,334 @@
+// window/thumbnails.rs
+// 此文件实现所有已映射窗口共用的缩略图缓存
+// 概览搜索的预览和 IPC 缩略图接口复用同一份缓存，避免每次都重新渲染整个窗口
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use niri_config::ThumbnailQuality;
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::backend::renderer::ImportMem as _;
+use smithay::utils::{Logical, Physical, Point, Scale, Size, Transform};
+
+use super::Mapped;
+use crate::layout::LayoutElement as _;
+use crate::render_helpers::downscale::downscale_box;
+use crate::render_helpers::texture::TextureBuffer;
+use crate::render_helpers::{download_texture, render_to_texture, RenderTarget};
+use crate::utils::write_png_rgba8;
+
+/// 缩略图最长边的尺寸（逻辑像素）
+pub const MAX_THUMBNAIL_SIZE: f64 = 384.;
+
+/// 缩略图缓存默认的显存预算（字节）
+pub const DEFAULT_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+
+/// 缓存的单个窗口缩略图
+#[derive(Debug)]
+pub struct Thumbnail<T> {
+    /// 渲染好的缩略图纹理
+    pub texture: T,
+    /// 纹理尺寸（物理像素）
+    pub size: Size<i32, Physical>,
+    /// 窗口内容自上次渲染后是否有变化
+    damaged: bool,
+    /// 上次被使用的时间（用于淘汰最久未使用的缩略图）
+    last_used: Duration,
+}
+
+/// 窗口缩略图缓存，按窗口 id 索引
+///
+/// 窗口提交带损伤的内容时只做标记，下次使用缩略图时才重新渲染，
+/// 没有人查看的缩略图不会被更新。总占用超过预算时淘汰最久未使用的缩略图
+#[derive(Debug)]
+pub struct ThumbnailCache<T> {
+    entries: HashMap<u64, Thumbnail<T>>,
+    /// 显存预算（字节）
+    memory_budget: usize,
+}
+
+impl<T> ThumbnailCache<T> {
+    pub fn new(memory_budget: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            memory_budget,
+        }
+    }
+
+    /// 标记窗口内容已变化（在窗口提交带损伤的内容时调用）
+    pub fn mark_damaged(&mut self, id: u64) {
+        if let Some(thumbnail) = self.entries.get_mut(&id) {
+            thumbnail.damaged = true;
+        }
+    }
+
+    /// 移除窗口的缩略图（窗口关闭或取消映射时调用）
+    pub fn remove(&mut self, id: u64) {
+        self.entries.remove(&id);
+    }
+
+    /// 丢弃所有缩略图（GPU 重置后旧纹理不再可用）
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// 当前所有缩略图占用的显存（字节）
+    pub fn memory_usage(&self) -> usize {
+        self.entries.values().map(|t| texture_bytes(t.size)).sum()
+    }
+
+    /// 窗口缩略图是否需要重新渲染：还没有缓存，或者内容有变化
+    pub fn needs_render(&self, id: u64) -> bool {
+        self.entries.get(&id).map_or(true, |t| t.damaged)
+    }
+
+    /// 获取缓存的缩略图，不重新渲染（用于只能拿到 &self 的渲染路径）
+    pub fn peek(&self, id: u64) -> Option<&Thumbnail<T>> {
+        self.entries.get(&id)
+    }
+
+    /// 获取窗口缩略图，必要时调用 `render` 重新渲染
+    ///
+    /// 没有缓存或内容有变化时重新渲染，渲染失败时保留旧的缩略图
+    pub fn get(
+        &mut self,
+        id: u64,
+        now: Duration,
+        render: impl FnOnce() -> Option<(T, Size<i32, Physical>)>,
+    ) -> Option<&Thumbnail<T>> {
+        if self.needs_render(id) {
+            if let Some((texture, size)) = render() {
+                self.entries.insert(
+                    id,
+                    Thumbnail {
+                        texture,
+                        size,
+                        damaged: false,
+                        last_used: now,
+                    },
+                );
+                self.evict(id);
+            }
+        }
+
+        let thumbnail = self.entries.get_mut(&id)?;
+        thumbnail.last_used = now;
+        Some(thumbnail)
+    }
+
+    /// 淘汰最久未使用的缩略图，直到总占用不超过预算（不淘汰刚渲染的 `keep`）
+    fn evict(&mut self, keep: u64) {
+        let mut usage = self.memory_usage();
+        while usage > self.memory_budget {
+            let oldest = self
+                .entries
+                .iter()
+                .filter(|(id, _)| **id != keep)
+                .min_by_key(|(_, t)| t.last_used)
+                .map(|(id, _)| *id);
+            let Some(id) = oldest else {
+                break;
+            };
+
+            let thumbnail = self.entries.remove(&id).unwrap();
+            usage -= texture_bytes(thumbnail.size);
+        }
+    }
+}
+
+/// 计算渲染缩略图时使用的缩放比例（只缩小，不放大）
+pub fn thumbnail_scale(window_size: Size<f64, Logical>) -> f64 {
+    let longest = f64::max(window_size.w, window_size.h);
+    if longest <= MAX_THUMBNAIL_SIZE {
+        1.
+    } else {
+        MAX_THUMBNAIL_SIZE / longest
+    }
+}
+
+/// 将窗口渲染为缩略图纹理
+///
+/// 按屏幕截图目标渲染，这样设置了 `block-out-from` 的窗口在缩略图中同样被遮挡
+///
+/// `Smooth` 质量下先按原尺寸渲染，再在 CPU 上用盒式滤波缩小，
+/// 避免大幅缩小时文字和细线出现锯齿
+pub fn render_thumbnail(
+    renderer: &mut GlesRenderer,
+    mapped: &Mapped,
+    quality: ThumbnailQuality,
+) -> Option<(TextureBuffer<GlesTexture>, Size<i32, Physical>)> {
+    let _span = tracy_client::span!("render_thumbnail");
+
+    let window_size = mapped.size().to_f64();
+    let thumbnail_scale = thumbnail_scale(window_size);
+    let size = window_size.to_physical_precise_round(thumbnail_scale);
+    if size.w <= 0 || size.h <= 0 {
+        return None;
+    }
+
+    // 不需要缩小时两种质量没有区别
+    let smooth = quality == ThumbnailQuality::Smooth && thumbnail_scale < 1.;
+    let (scale, render_size) = if smooth {
+        (1., window_size.to_physical_precise_round(1.))
+    } else {
+        (thumbnail_scale, size)
+    };
+    let scale = Scale::from(scale);
+
+    let elements = mapped.render_normal(
+        renderer,
+        Point::from((0., 0.)),
+        scale,
+        1.,
+        RenderTarget::ScreenCapture,
+    );
+    let res = render_to_texture(
+        renderer,
+        render_size,
+        scale,
+        Transform::Normal,
+        Fourcc::Abgr8888,
+        elements.iter().rev(),
+    );
+    let res = res.and_then(|(texture, _sync_point)| {
+        if smooth {
+            downscale_texture(renderer, &texture, render_size, size)
+        } else {
+            Ok(texture)
+        }
+    });
+
+    match res {
+        Ok(texture) => {
+            // 纹理缩放在显示时按输出的缩放重新设置
+            let buffer =
+                TextureBuffer::from_texture(renderer, texture, 1., Transform::Normal, Vec::new());
+            Some((buffer, size))
+        }
+        Err(err) => {
+            warn!("error rendering window thumbnail: {err:?}");
+            None
+        }
+    }
+}
+
+/// 下载纹理，在 CPU 上缩小后重新上传
+fn downscale_texture(
+    renderer: &mut GlesRenderer,
+    texture: &GlesTexture,
+    src_size: Size<i32, Physical>,
+    dst_size: Size<i32, Physical>,
+) -> anyhow::Result<GlesTexture> {
+    let pixels = download_texture(renderer, texture, src_size, Fourcc::Abgr8888)?;
+    let pixels = downscale_box(&pixels, src_size, dst_size);
+
+    let buffer_size = dst_size.to_logical(1).to_buffer(1, Transform::Normal);
+    renderer
+        .import_memory(&pixels, Fourcc::Abgr8888, buffer_size, false)
+        .context("error importing downscaled thumbnail")
+}
+
+/// 将下载好的缩略图像素编码为 PNG 并保存
+///
+/// 编码大图可能需要一段时间，应在后台线程中调用
+pub fn save_png(path: &Path, size: Size<i32, Physical>, pixels: &[u8]) -> Result<(), String> {
+    let file = File::create(path).map_err(|err| format!("error creating {path:?}: {err}"))?;
+    write_png_rgba8(BufWriter::new(file), size.w as u32, size.h as u32, pixels)
+        .map_err(|err| format!("error encoding PNG: {err}"))
+}
+
+fn texture_bytes(size: Size<i32, Physical>) -> usize {
+    // RGBA8，每像素 4 字节
+    size.w.max(0) as usize * size.h.max(0) as usize * 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn render(tag: u32, w: i32) -> impl FnOnce() -> Option<(u32, Size<i32, Physical>)> {
+        move || Some((tag, Size::from((w, 10))))
+    }
+
+    #[test]
+    fn rerenders_only_after_damage() {
+        let mut cache = ThumbnailCache::new(usize::MAX);
+        assert!(cache.needs_render(1));
+        assert_eq!(cache.get(1, ms(0), render(1, 10)).unwrap().texture, 1);
+
+        // 内容没有变化时一直复用缓存，不论过了多久
+        assert!(!cache.needs_render(1));
+        assert_eq!(cache.get(1, ms(5000), render(2, 10)).unwrap().texture, 1);
+
+        // 标记损伤后在下次使用时立即重新渲染
+        cache.mark_damaged(1);
+        assert!(cache.needs_render(1));
+        assert_eq!(cache.peek(1).unwrap().texture, 1);
+        assert_eq!(cache.get(1, ms(5001), render(3, 10)).unwrap().texture, 3);
+        assert!(!cache.needs_render(1));
+
+        // 还没有缓存的窗口不受标记影响
+        cache.mark_damaged(2);
+        assert!(cache.peek(2).is_none());
+    }
+
+    #[test]
+    fn failed_render_keeps_old_thumbnail() {
+        let mut cache = ThumbnailCache::new(usize::MAX);
+        cache.get(1, ms(0), render(1, 10));
+        cache.mark_damaged(1);
+
+        let thumbnail = cache.get(1, ms(1), || None).unwrap();
+        assert_eq!(thumbnail.texture, 1);
+        assert!(cache.needs_render(1));
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        // 每个缩略图 10x10x4 = 400 字节，预算只够两个
+        let mut cache = ThumbnailCache::new(800);
+        cache.get(1, ms(0), render(1, 10));
+        cache.get(2, ms(1), render(2, 10));
+        cache.get(1, ms(2), render(0, 10));
+
+        cache.get(3, ms(3), render(3, 10));
+        assert_eq!(cache.memory_usage(), 800);
+        assert!(cache.entries.contains_key(&1));
+        assert!(!cache.entries.contains_key(&2));
+
+        // 单个超出预算的缩略图仍然保留
+        cache.get(4, ms(4), render(4, 100));
+        assert_eq!(cache.entries.len(), 1);
+        assert!(cache.entries.contains_key(&4));
+    }
+
+    #[test]
+    fn clear_drops_all_thumbnails() {
+        let mut cache = ThumbnailCache::new(usize::MAX);
+        cache.get(1, ms(0), render(1, 10));
+        cache.get(2, ms(1), render(2, 10));
+
+        // GPU 重置后所有窗口在下次使用时都重新渲染
+        cache.clear();
+        assert_eq!(cache.memory_usage(), 0);
+        assert!(cache.needs_render(1));
+        assert!(cache.needs_render(2));
+        assert!(cache.peek(1).is_none());
+        assert_eq!(cache.get(1, ms(2), render(3, 10)).unwrap().texture, 3);
+    }
+
+    #[test]
+    fn scale_only_shrinks() {
+        assert_eq!(thumbnail_scale(Size::from((200., 100.))), 1.);
+        assert_eq!(thumbnail_scale(Size::from((768., 400.))), 0.5);
+    }
+}
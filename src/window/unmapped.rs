@@ -8,7 +8,8 @@ use smithay::output::Output;  // 显示输出
 use smithay::wayland::shell::xdg::ToplevelSurface;  // Wayland toplevel 表面
 use smithay::wayland::xdg_activation::XdgActivationTokenData;  // XDG 激活令牌数据
 
-use super::ResolvedWindowRules;  // 已解析的窗口规则
+use super::{MapSize, ResolvedWindowRules};  // 已解析的窗口规则和映射尺寸
+use crate::layout::snapshot::RestoreSlot;  // 布局快照中的窗口位置
 
 /// 未映射窗口结构
 /// 设计：封装窗口在映射前的所有状态
@@ -23,6 +24,15 @@ pub struct Unmapped {
     /// 激活令牌数据（如果有）
     /// 作用：用于窗口首次显示时的焦点管理
     pub activation_token_data: Option<XdgActivationTokenData>,
+    
+    /// 映射时的尺寸信息（在窗口映射的那次提交中设置，用于按尺寸匹配规则）
+    pub map_size: Option<MapSize>,
+
+    /// 恢复布局快照时分配给该窗口的位置（在初始配置时选定）
+    pub restore_slot: Option<RestoreSlot>,
+
+    /// 窗口打开时 niri 是否处于启动阶段（该窗口的规则始终按此值匹配 at-startup）
+    pub is_at_startup: bool,
 }
 
 /// 初始配置状态枚举
@@ -77,15 +87,18 @@ pub enum InitialConfigureState {
 
 impl Unmapped {
     /// 创建新的未映射窗口
-    /// 参数：window - 基础窗口对象
+    /// 参数：window - 基础窗口对象，is_at_startup - niri 当前是否处于启动阶段
     /// 返回：初始状态为 NotConfigured 的 Unmapped 实例
-    pub fn new(window: Window) -> Self {
+    pub fn new(window: Window, is_at_startup: bool) -> Self {
         Self {
             window,
             state: InitialConfigureState::NotConfigured {
                 wants_fullscreen: None,  // 初始无全屏请求
             },
             activation_token_data: None,  // 无激活令牌
+            map_size: None,
+            restore_slot: None,  // 初始配置时才匹配快照
+            is_at_startup,
         }
     }
     